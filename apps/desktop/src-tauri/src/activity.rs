@@ -0,0 +1,225 @@
+//! Frontmost application sampling for focus analytics
+//!
+//! Off until the user opts in (`activityTracking` in the config). While enabled, the
+//! frontmost app and window title are sampled every few seconds and merged into spans, so
+//! the frontend can line up "time in editor/terminal" with the task being worked on.
+//! Nothing leaves the machine; spans live in memory only.
+//!
+//! Events:
+//! - `activity`: the current span, once per sample
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{applescript, config, power, process, store};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+const HISTORY_CAPACITY: usize = 500;
+
+/// Window title needs Accessibility access; without it the title is left empty
+const FRONTMOST_SCRIPT: &str = r#"
+tell application "System Events"
+    set p to first application process whose frontmost is true
+    set appName to name of p
+    set winTitle to ""
+    try
+        set winTitle to name of front window of p
+    end try
+end tell
+return appName & linefeed & winTitle
+"#;
+
+const EDITORS: &[&str] = &[
+    "code", "cursor", "zed", "idea", "webstorm", "pycharm", "sublime", "xcode", "nvim", "vim",
+];
+const TERMINALS: &[&str] = &[
+    "terminal",
+    "iterm",
+    "warp",
+    "kitty",
+    "wezterm",
+    "alacritty",
+    "ghostty",
+];
+const BROWSERS: &[&str] = &["safari", "chrome", "firefox", "arc", "brave", "edge"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Category {
+    Editor,
+    Terminal,
+    Browser,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivitySpan {
+    pub app: String,
+    pub title: String,
+    pub category: Category,
+    /// Unix millis
+    pub started_at: i64,
+    pub ended_at: i64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityStatus {
+    pub enabled: bool,
+    pub current: Option<ActivitySpan>,
+}
+
+struct Tracker {
+    current: Option<ActivitySpan>,
+    history: VecDeque<ActivitySpan>,
+}
+
+static TRACKER: Mutex<Tracker> = Mutex::new(Tracker {
+    current: None,
+    history: VecDeque::new(),
+});
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+fn categorize(app: &str) -> Category {
+    let app = app.to_lowercase();
+    let matches = |names: &[&str]| names.iter().any(|name| app.contains(name));
+    if matches(EDITORS) {
+        Category::Editor
+    } else if matches(TERMINALS) {
+        Category::Terminal
+    } else if matches(BROWSERS) {
+        Category::Browser
+    } else {
+        Category::Other
+    }
+}
+
+fn xdotool(args: &[&str]) -> Option<String> {
+    let output = process::output(Command::new("xdotool").args(args)).ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Frontmost app name and window title
+pub fn frontmost() -> Result<(String, String), Error> {
+    if cfg!(target_os = "macos") {
+        let output = applescript::run(FRONTMOST_SCRIPT)?;
+        let (app, title) = output.split_once('\n').unwrap_or((output.as_str(), ""));
+        Ok((app.trim().to_string(), title.trim().to_string()))
+    } else if cfg!(target_os = "linux") {
+        let title = xdotool(&["getactivewindow", "getwindowname"])
+            .ok_or_else(|| Error::NotFound("xdotool is required on Linux".to_string()))?;
+        let app = xdotool(&["getactivewindow", "getwindowpid"])
+            .and_then(|pid| std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok())
+            .map(|comm| comm.trim().to_string())
+            .unwrap_or_default();
+        Ok((app, title))
+    } else {
+        Err(Error::Unsupported(
+            "Activity tracking is not supported on this platform".to_string(),
+        ))
+    }
+}
+
+fn close_current(tracker: &mut Tracker) {
+    if let Some(done) = tracker.current.take() {
+        if tracker.history.len() == HISTORY_CAPACITY {
+            tracker.history.pop_front();
+        }
+        tracker.history.push_back(done);
+    }
+}
+
+/// Extend the current span or close it and start a new one
+fn record(app: String, title: String) -> Option<ActivitySpan> {
+    let now = store::now_ms();
+    let mut tracker = TRACKER.lock().ok()?;
+    match tracker.current.as_mut() {
+        Some(span) if span.app == app && span.title == title => span.ended_at = now,
+        _ => {
+            close_current(&mut tracker);
+            tracker.current = Some(ActivitySpan {
+                category: categorize(&app),
+                app,
+                title,
+                started_at: now,
+                ended_at: now,
+            });
+        }
+    }
+    tracker.current.clone()
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        thread::sleep(SAMPLE_INTERVAL);
+        if !config::load().activity_tracking {
+            if let Ok(mut tracker) = TRACKER.lock() {
+                close_current(&mut tracker);
+            }
+            continue;
+        }
+        if power::is_settling() {
+            continue;
+        }
+        match frontmost() {
+            Ok((name, title)) => {
+                if let Some(span) = record(name, title) {
+                    let _ = app.emit("activity", span);
+                }
+            }
+            Err(e) => eprintln!("[Claude PM] Activity sample failed: {}", e),
+        }
+    });
+}
+
+/// Opt in or out; history is cleared when switched off
+#[tauri::command]
+pub fn set_activity_tracking(enabled: bool) -> Result<(), Error> {
+    config::update(|c| c.activity_tracking = enabled)?;
+    if !enabled {
+        if let Ok(mut tracker) = TRACKER.lock() {
+            tracker.current = None;
+            tracker.history.clear();
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_activity_status() -> ActivityStatus {
+    ActivityStatus {
+        enabled: config::load().activity_tracking,
+        current: TRACKER.lock().ok().and_then(|t| t.current.clone()),
+    }
+}
+
+/// Spans that ended after `since` (unix millis), oldest first, including the open one
+#[tauri::command]
+pub fn get_recent_activity(since: i64) -> Vec<ActivitySpan> {
+    TRACKER
+        .lock()
+        .map(|tracker| {
+            tracker
+                .history
+                .iter()
+                .chain(tracker.current.iter())
+                .filter(|span| span.ended_at > since)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
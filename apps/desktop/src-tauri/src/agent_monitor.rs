@@ -0,0 +1,349 @@
+//! Background monitor for multiplexer panes running Claude agents
+//!
+//! Polls each watched pane and, when a permission prompt or question appears, checks it
+//! against the auto-approval rules (see `approval_policy`). Prompts the rules leave to
+//! the user are surfaced with the question (and the menu options Claude Code offers) so
+//! they can be answered from the app or from the notification's action buttons via
+//! `respond_to_agent`. Attention is requested when the app is in the background.
+//!
+//! Events:
+//! - `agent-awaiting-approval` with an [`AwaitingApproval`] when a pane starts waiting
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::audit::{self, AuditKind};
+use crate::error::Error;
+use crate::event_bus::{self, AppEvent};
+use crate::notifications::{self, NotificationAction, NotificationRequest};
+use crate::{approval_policy, attention, multiplexer, rate_limits, store};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Only the tail of the pane is considered, so old prompts scrolled up don't re-trigger
+const TAIL_LINES: usize = 15;
+
+/// Markers Claude Code prints when it is blocked waiting for the user
+const PROMPT_PATTERNS: &[&str] = &[
+    "Do you want to proceed?",
+    "Do you want to make this edit",
+    "Do you want to create",
+    "Do you want to allow",
+    "❯ 1. Yes",
+    "(y/n)",
+    "[Y/n]",
+    "[y/N]",
+];
+
+/// Notification keys for approval prompts; action buttons on them answer the prompt
+const APPROVAL_KEY_PREFIX: &str = "approval:";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptOption {
+    pub number: u32,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prompt {
+    pub question: String,
+    /// What the prompt is about: the command, file or tool shown above the question
+    pub detail: String,
+    /// Numbered menu entries; empty for `(y/n)` style prompts
+    pub options: Vec<PromptOption>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AwaitingApproval {
+    pub target: String,
+    /// Store session recorded for the pane, if any
+    pub session_id: Option<String>,
+    pub question: String,
+    pub detail: String,
+    pub options: Vec<PromptOption>,
+}
+
+#[derive(Default)]
+struct PaneState {
+    /// The prompt seen by the last poll; attention is requested on the rising edge only
+    prompt: Option<Prompt>,
+}
+
+static WATCHED: Mutex<BTreeMap<String, PaneState>> = Mutex::new(BTreeMap::new());
+static MONITOR_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Claude Code draws prompts inside a box; drop the border
+fn clean(line: &str) -> &str {
+    line.trim_matches(|c: char| c.is_whitespace() || matches!(c, '│' | '╭' | '╮' | '╰' | '╯' | '─'))
+}
+
+/// `❯ 1. Yes` / `2. Yes, and don't ask again` as a menu entry
+fn menu_option(line: &str) -> Option<PromptOption> {
+    let (number, label) = line.trim_start_matches('❯').trim().split_once(". ")?;
+    Some(PromptOption {
+        number: number.parse().ok()?,
+        label: label.trim().to_string(),
+    })
+}
+
+/// Parse the prompt the pane output ends in, if any
+pub fn parse_prompt(output: &str) -> Option<Prompt> {
+    let lines: Vec<&str> = output.lines().filter(|l| !l.trim().is_empty()).collect();
+    let tail = &lines[lines.len().saturating_sub(TAIL_LINES)..];
+    let matched = tail
+        .iter()
+        .rposition(|line| PROMPT_PATTERNS.iter().any(|p| line.contains(p)))?;
+    // A menu marker matches below the question it belongs to
+    let asked = tail[..=matched]
+        .iter()
+        .rposition(|line| line.contains('?') && menu_option(clean(line)).is_none())
+        .unwrap_or(matched);
+    // The detail runs from the top of the prompt box (or of the tail) to the question
+    let top = tail[..asked]
+        .iter()
+        .rposition(|line| line.trim_start().starts_with('╭'))
+        .map_or(0, |i| i + 1);
+    Some(Prompt {
+        question: clean(tail[asked]).to_string(),
+        detail: cleaned(&tail[top..asked]).collect::<Vec<_>>().join("\n"),
+        options: cleaned(&tail[asked + 1..])
+            .filter_map(menu_option)
+            .collect(),
+    })
+}
+
+fn cleaned<'a>(lines: &'a [&'a str]) -> impl Iterator<Item = &'a str> + 'a {
+    lines.iter().map(|l| clean(l)).filter(|l| !l.is_empty())
+}
+
+/// Return the question if the pane output ends in a known prompt
+pub fn detect_prompt(output: &str) -> Option<String> {
+    parse_prompt(output).map(|prompt| prompt.question)
+}
+
+fn session_for_pane(target: &str) -> Option<String> {
+    store::with_conn(|conn| store::open_session_for_pane(conn, target))
+        .ok()
+        .flatten()
+        .map(|session| session.id)
+}
+
+fn poll_once(app: &AppHandle) {
+    let targets: Vec<String> = match WATCHED.lock() {
+        Ok(watched) => watched.keys().cloned().collect(),
+        Err(_) => return,
+    };
+
+    for target in targets {
+        // A pane that disappeared (session killed) simply reads as not waiting
+        let output = multiplexer::active()
+            .and_then(|mux| mux.capture(&target))
+            .ok();
+        if let Some(output) = &output {
+            rate_limits::scan(output, &target);
+        }
+        let prompt = output.and_then(|out| parse_prompt(&out));
+
+        let became_waiting = match WATCHED.lock() {
+            Ok(mut watched) => match watched.get_mut(&target) {
+                Some(state) => {
+                    let rising = prompt.is_some() && state.prompt.is_none();
+                    state.prompt = prompt.clone();
+                    rising
+                }
+                None => false,
+            },
+            Err(_) => false,
+        };
+
+        if let (true, Some(prompt)) = (became_waiting, prompt) {
+            let session_id = session_for_pane(&target);
+            if approval_policy::apply(&target, session_id.as_deref(), &prompt) {
+                continue;
+            }
+            println!("[Claude PM] Agent in {} is waiting for input", target);
+            attention::request_if_backgrounded(app, true);
+            let _ = app.emit(
+                "agent-awaiting-approval",
+                AwaitingApproval {
+                    target: target.clone(),
+                    session_id,
+                    question: prompt.question.clone(),
+                    detail: prompt.detail.clone(),
+                    options: prompt.options.clone(),
+                },
+            );
+            event_bus::publish(AppEvent::AgentBlocked {
+                target: target.clone(),
+                prompt: prompt.question.clone(),
+            });
+            notifications::notify(
+                app,
+                NotificationRequest {
+                    title: format!("Agent in {} needs input", target),
+                    body: prompt.question,
+                    key: Some(format!("{}{}", APPROVAL_KEY_PREFIX, target)),
+                    actions: vec![
+                        NotificationAction {
+                            id: "yes".to_string(),
+                            label: "Approve".to_string(),
+                        },
+                        NotificationAction {
+                            id: "no".to_string(),
+                            label: "Deny".to_string(),
+                        },
+                    ],
+                    category: Some("approval".to_string()),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
+
+fn ensure_monitor(app: &AppHandle) {
+    if MONITOR_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || loop {
+        // Panes may briefly show stale output right after wake
+        if !crate::power::is_settling() {
+            poll_once(&app);
+        }
+        thread::sleep(POLL_INTERVAL);
+    });
+}
+
+/// Keys that give `answer` to `prompt`, and whether Enter follows them
+///
+/// Menus take the option number (or `yes`, `always`, `no`); `(y/n)` prompts take
+/// `yes`/`no`; anything else is typed as a free-text reply.
+fn keys_for(prompt: &Prompt, answer: &str) -> Result<(String, bool), String> {
+    let answer = answer.trim();
+    let normalized = answer.to_lowercase();
+    if prompt.options.is_empty() {
+        return Ok(match normalized.as_str() {
+            "yes" | "y" | "approve" => ("y".to_string(), true),
+            "no" | "n" | "deny" => ("n".to_string(), true),
+            _ => (answer.to_string(), true),
+        });
+    }
+    let find = |pred: &dyn Fn(&str) -> bool| {
+        prompt
+            .options
+            .iter()
+            .find(|o| pred(&o.label.to_lowercase()))
+            .map(|o| o.number)
+    };
+    let number = match normalized.as_str() {
+        "yes" | "y" | "approve" => prompt.options.first().map(|o| o.number),
+        "always" => find(&|l| l.contains("don't ask again") || l.contains("always")),
+        "no" | "n" | "deny" => find(&|l| l.starts_with("no")),
+        _ => normalized
+            .parse()
+            .ok()
+            .filter(|n| prompt.options.iter().any(|o| o.number == *n)),
+    };
+    match number {
+        // Claude Code selects a menu entry on its digit alone
+        Some(number) => Ok((number.to_string(), false)),
+        None => Err(format!(
+            "\"{}\" isn't one of the prompt's options: {}",
+            answer,
+            prompt
+                .options
+                .iter()
+                .map(|o| format!("{}. {}", o.number, o.label))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Answer the prompt waiting in pane `target`
+pub fn respond(target: &str, answer: &str) -> Result<(), Error> {
+    let known = WATCHED
+        .lock()
+        .map_err(|e| e.to_string())?
+        .get(target)
+        .and_then(|state| state.prompt.clone());
+    // Unwatched panes, or ones that started waiting since the last poll
+    let prompt = match known {
+        Some(prompt) => prompt,
+        None => multiplexer::active()
+            .and_then(|mux| mux.capture(target))
+            .ok()
+            .and_then(|out| parse_prompt(&out))
+            .ok_or_else(|| {
+                Error::InvalidInput(format!("The agent in {} isn't waiting for input", target))
+            })?,
+    };
+    let (keys, enter) = keys_for(&prompt, answer).map_err(Error::InvalidInput)?;
+    let mux = multiplexer::active()?;
+    mux.send_text(target, &keys)?;
+    if enter {
+        mux.send_enter(target)?;
+    }
+    if let Ok(mut watched) = WATCHED.lock() {
+        if let Some(state) = watched.get_mut(target) {
+            state.prompt = None;
+        }
+    }
+    println!("[Claude PM] Answered prompt in {}", target);
+    audit::record(
+        AuditKind::Approval,
+        "answer",
+        Some(target),
+        serde_json::json!({ "question": prompt.question, "answer": answer }),
+    );
+    Ok(())
+}
+
+/// Answer an approval prompt from its notification's action button
+pub fn handle_notification_action(key: Option<&str>, action: &str) {
+    let Some(target) = key.and_then(|k| k.strip_prefix(APPROVAL_KEY_PREFIX)) else {
+        return;
+    };
+    if let Err(e) = respond(target, action) {
+        eprintln!("[Claude PM] Failed to answer prompt in {}: {}", target, e);
+    }
+}
+
+/// Start monitoring a pane (e.g. tmux `claude-task-42:0.0`, see `multiplexer`) for agent prompts
+#[tauri::command]
+pub fn watch_tmux_pane(app: AppHandle, target: String) -> Result<(), Error> {
+    WATCHED
+        .lock()
+        .map_err(|e| e.to_string())?
+        .entry(target)
+        .or_default();
+    ensure_monitor(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn unwatch_tmux_pane(target: String) -> Result<(), Error> {
+    WATCHED.lock().map_err(|e| e.to_string())?.remove(&target);
+    Ok(())
+}
+
+/// Answer a waiting agent; `session` is a store session id or a tmux target
+#[tauri::command]
+pub fn respond_to_agent(session: String, answer: String) -> Result<(), Error> {
+    let target = match store::with_conn(|conn| store::get_session(conn, &session))? {
+        Some(recorded) => recorded
+            .pane_id
+            .ok_or_else(|| Error::InvalidInput(format!("Session {} has no tmux pane", session)))?,
+        None => session,
+    };
+    respond(&target, &answer)
+}
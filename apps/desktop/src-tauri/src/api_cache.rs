@@ -0,0 +1,356 @@
+//! Persisted cache of server and GitHub reads, served while the backend is unavailable
+//!
+//! GET requests to the server's `/api/` routes are recorded by the proxy as they stream
+//! through. While the server is down or hot-reloading, the proxy answers those routes
+//! from the cache instead of with a 503, marked with `X-ClaudePM-Cache: stale` and
+//! `X-ClaudePM-Cached-At`, so screens keep their data. Once the server is back every
+//! route served stale is fetched again in the background. GitHub reads (see `github`)
+//! fall back to the same cache when GitHub can't be reached.
+//!
+//! Events:
+//! - `api-cache-revalidated` with the paths refreshed after the server came back
+//! - `api-cache-stale` when a GitHub read was answered from the cache
+
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{config, proxy, secrets, store};
+
+/// Rows kept; the least recently fetched go first
+const MAX_ENTRIES: usize = 500;
+/// Larger responses (and endless ones, like event streams) aren't cached
+const MAX_RESPONSE: usize = 2 * 1024 * 1024;
+/// How long revalidation waits for the server to come back
+const REVALIDATE_WAIT: Duration = Duration::from_secs(5 * 60);
+const REVALIDATE_POLL: Duration = Duration::from_secs(1);
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+/// Key to request head of every route served stale since the server went away
+static STALE: Mutex<BTreeMap<String, (String, Vec<u8>)>> = Mutex::new(BTreeMap::new());
+static REVALIDATING: AtomicBool = AtomicBool::new(false);
+
+/// A server read the proxy can cache
+pub struct CachedRequest {
+    pub key: String,
+    pub path: String,
+    /// The request head as sent upstream, asking for the connection to be closed
+    pub head: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiCacheStatus {
+    pub enabled: bool,
+    pub entries: usize,
+    pub bytes: i64,
+    /// Paths served from the cache and waiting for the server to come back
+    pub stale: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StaleEvent {
+    key: String,
+    fetched_at: i64,
+}
+
+fn enabled() -> bool {
+    config::load().offline_cache.unwrap_or(true)
+}
+
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+fn emit<S: Serialize + Clone>(event: &str, payload: S) {
+    if let Some(app) = APP.get() {
+        let _ = app.emit(event, payload);
+    }
+}
+
+fn header_end(bytes: &[u8]) -> Option<usize> {
+    bytes.windows(4).position(|w| w == b"\r\n\r\n")
+}
+
+/// A plain GET of an API route, with the connection made one-shot so the response
+/// ends where the stream does
+pub fn cacheable(head: &[u8]) -> Option<CachedRequest> {
+    if !enabled() {
+        return None;
+    }
+    let end = header_end(head)?;
+    let text = std::str::from_utf8(&head[..end]).ok()?;
+    let mut lines = text.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let (method, path) = (request_line.next()?, request_line.next()?);
+    if method != "GET" || !path.starts_with("/api/") {
+        return None;
+    }
+    let mut headers = Vec::new();
+    let mut credentials = Sha256::new();
+    for line in lines {
+        let name = line
+            .split(':')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        match name.as_str() {
+            "upgrade" => return None,
+            "connection" | "keep-alive" => continue,
+            "authorization" | "cookie" => credentials.update(line.as_bytes()),
+            _ => {}
+        }
+        headers.push(line);
+    }
+    // Responses are only replayed to requests with the same credentials
+    let hash = credentials.finalize();
+    let key = format!("server:{}#{}", path, secrets::hex(&hash[..8]));
+    let head = format!(
+        "{} {} HTTP/1.1\r\n{}\r\nConnection: close\r\n\r\n",
+        method,
+        path,
+        headers.join("\r\n")
+    );
+    Some(CachedRequest {
+        key,
+        path: path.to_string(),
+        head: head.into_bytes(),
+    })
+}
+
+/// Whether `response` is a whole 200 the server didn't forbid storing
+fn storable(response: &[u8]) -> bool {
+    let Some(end) = header_end(response) else {
+        return false;
+    };
+    let head = String::from_utf8_lossy(&response[..end]).to_ascii_lowercase();
+    let body = &response[end + 4..];
+    if !head.starts_with("http/1.1 200") && !head.starts_with("http/1.0 200") {
+        return false;
+    }
+    let header = |name: &str| {
+        head.split("\r\n")
+            .filter_map(|line| line.split_once(':'))
+            .find(|(n, _)| n.trim() == name)
+            .map(|(_, v)| v.trim().to_string())
+    };
+    if header("cache-control").is_some_and(|v| v.contains("no-store"))
+        || header("content-type").is_some_and(|v| v.starts_with("text/event-stream"))
+    {
+        return false;
+    }
+    // A response cut off by the server going down isn't worth replaying
+    if let Some(length) = header("content-length") {
+        return length.parse::<usize>().ok() == Some(body.len());
+    }
+    if header("transfer-encoding").is_some_and(|v| v.contains("chunked")) {
+        return body.ends_with(b"0\r\n\r\n");
+    }
+    true
+}
+
+fn put(key: &str, path: &str, request: &[u8], response: &[u8]) {
+    let result = store::with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO api_cache (key, path, request, response, fetched_at) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(key) DO UPDATE SET request = excluded.request, response = excluded.response, fetched_at = excluded.fetched_at",
+            params![key, path, request, response, store::now_ms()],
+        )?;
+        conn.execute(
+            "DELETE FROM api_cache WHERE key NOT IN (SELECT key FROM api_cache ORDER BY fetched_at DESC LIMIT ?1)",
+            [MAX_ENTRIES as i64],
+        )
+    });
+    if let Err(e) = result {
+        eprintln!("[Claude PM] Failed to cache {}: {}", path, e);
+    }
+}
+
+fn get(key: &str) -> Option<(Vec<u8>, i64)> {
+    store::with_conn(|conn| {
+        conn.query_row(
+            "SELECT response, fetched_at FROM api_cache WHERE key = ?1",
+            [key],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+    })
+    .ok()
+    .flatten()
+}
+
+/// Pipe the server's response to the client, caching it if it came through whole
+pub fn record(request: CachedRequest, upstream: &mut TcpStream, client: &mut TcpStream) {
+    let mut response = Vec::new();
+    let mut chunk = [0u8; 16 * 1024];
+    loop {
+        let n = match upstream.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        if client.write_all(&chunk[..n]).is_err() {
+            return;
+        }
+        if response.len() + n <= MAX_RESPONSE {
+            response.extend_from_slice(&chunk[..n]);
+        } else {
+            // Keep streaming, just stop recording
+            response.clear();
+            response.shrink_to_fit();
+            let _ = io::copy(upstream, client);
+            return;
+        }
+    }
+    if storable(&response) {
+        put(&request.key, &request.path, &request.head, &response);
+        if let Ok(mut stale) = STALE.lock() {
+            stale.remove(&request.key);
+        }
+    }
+}
+
+/// Answer from the cache while the server is unavailable; false if nothing is cached
+pub fn serve_stale(client: &mut TcpStream, request: &CachedRequest) -> io::Result<bool> {
+    let Some((response, fetched_at)) = get(&request.key) else {
+        return Ok(false);
+    };
+    let Some(status_end) = response.windows(2).position(|w| w == b"\r\n") else {
+        return Ok(false);
+    };
+    let marks = format!(
+        "\r\nX-ClaudePM-Cache: stale\r\nX-ClaudePM-Cached-At: {}\r\nAccess-Control-Expose-Headers: X-ClaudePM-Cache, X-ClaudePM-Cached-At",
+        fetched_at
+    );
+    client.write_all(&response[..status_end])?;
+    client.write_all(marks.as_bytes())?;
+    client.write_all(&response[status_end..])?;
+
+    if let Ok(mut stale) = STALE.lock() {
+        stale.insert(
+            request.key.clone(),
+            (request.path.clone(), request.head.clone()),
+        );
+    }
+    ensure_revalidation();
+    Ok(true)
+}
+
+fn fetch(head: &[u8]) -> Option<Vec<u8>> {
+    let mut upstream = proxy::connect_upstream(false)?;
+    upstream
+        .set_read_timeout(Some(Duration::from_secs(30)))
+        .ok()?;
+    upstream.write_all(head).ok()?;
+    let mut response = Vec::new();
+    upstream
+        .take(MAX_RESPONSE as u64 + 1)
+        .read_to_end(&mut response)
+        .ok()?;
+    Some(response)
+}
+
+/// Once the server is back, fetch every route that was served stale again
+fn ensure_revalidation() {
+    if REVALIDATING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| {
+        let started = Instant::now();
+        while proxy::connect_upstream(false).is_none() {
+            if started.elapsed() >= REVALIDATE_WAIT {
+                REVALIDATING.store(false, Ordering::SeqCst);
+                return;
+            }
+            thread::sleep(REVALIDATE_POLL);
+        }
+        let stale = STALE.lock().map(|s| s.clone()).unwrap_or_default();
+        let mut refreshed = Vec::new();
+        for (key, (path, head)) in stale {
+            if let Some(response) = fetch(&head).filter(|r| storable(r)) {
+                put(&key, &path, &head, &response);
+                refreshed.push(path);
+            }
+            if let Ok(mut stale) = STALE.lock() {
+                stale.remove(&key);
+            }
+        }
+        REVALIDATING.store(false, Ordering::SeqCst);
+        if !refreshed.is_empty() {
+            println!(
+                "[Claude PM] Revalidated {} cached route(s)",
+                refreshed.len()
+            );
+            emit("api-cache-revalidated", refreshed);
+        }
+    });
+}
+
+/// Keep a JSON read (e.g. from GitHub) for when its source is unreachable
+pub fn put_json(key: &str, value: &Value) {
+    if !enabled() {
+        return;
+    }
+    if let Ok(bytes) = serde_json::to_vec(value) {
+        put(key, key, &[], &bytes);
+    }
+}
+
+/// The last value kept for `key`, announced with `api-cache-stale` since it's being
+/// served in place of a fresh one
+pub fn stale_json(key: &str) -> Option<Value> {
+    if !enabled() {
+        return None;
+    }
+    let (bytes, fetched_at) = get(key)?;
+    let value = serde_json::from_slice(&bytes).ok()?;
+    emit(
+        "api-cache-stale",
+        StaleEvent {
+            key: key.to_string(),
+            fetched_at,
+        },
+    );
+    Some(value)
+}
+
+#[tauri::command]
+pub fn get_api_cache_status() -> Result<ApiCacheStatus, Error> {
+    let (entries, bytes) = store::with_conn(|conn| {
+        conn.query_row(
+            "SELECT COUNT(*), COALESCE(SUM(LENGTH(response)), 0) FROM api_cache",
+            [],
+            |row| Ok((row.get::<_, i64>(0)? as usize, row.get(1)?)),
+        )
+    })?;
+    let stale = STALE
+        .lock()
+        .map(|s| s.values().map(|(path, _)| path.clone()).collect())
+        .unwrap_or_default();
+    Ok(ApiCacheStatus {
+        enabled: enabled(),
+        entries,
+        bytes,
+        stale,
+    })
+}
+
+#[tauri::command]
+pub fn clear_api_cache() -> Result<(), Error> {
+    store::with_conn(|conn| conn.execute("DELETE FROM api_cache", []))?;
+    if let Ok(mut stale) = STALE.lock() {
+        stale.clear();
+    }
+    Ok(())
+}
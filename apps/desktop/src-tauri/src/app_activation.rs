@@ -0,0 +1,140 @@
+//! Bringing another app, or one of its windows, to the front
+//!
+//! An app is picked by name or by bundle identifier (`com.googlecode.iterm2`), and with
+//! a window title only the window whose title contains it is raised, e.g. the iTerm
+//! window running a task's tmux session. On macOS the window is first raised through
+//! the app's own scripting dictionary, which also reaches windows on other Spaces, then
+//! through System Events (needs Accessibility access); activating the app afterwards
+//! switches to the Space the raised window is on. On Linux `wmctrl` does the same,
+//! switching to the window's desktop. Windows isn't supported yet.
+
+use serde::Deserialize;
+use std::process::Command;
+
+use crate::applescript;
+use crate::error::Error;
+use crate::process;
+
+/// System Events error when the app lacks Accessibility access
+const ERR_NOT_TRUSTED: &str = "-25211";
+const ERR_NOT_TRUSTED_UI: &str = "-1719";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivateTarget {
+    /// e.g. `com.googlecode.iterm2`; used over `app_name` when both are set
+    #[serde(default)]
+    pub bundle_id: Option<String>,
+    #[serde(default)]
+    pub window_title: Option<String>,
+}
+
+/// `tell` target and System Events process filter for the app
+fn app_refs(app_name: Option<&str>, bundle_id: Option<&str>) -> Result<(String, String), Error> {
+    match (bundle_id, app_name) {
+        (Some(id), _) => Ok((
+            format!("application id \"{}\"", applescript::escape(id)),
+            format!("bundle identifier is \"{}\"", applescript::escape(id)),
+        )),
+        (None, Some(name)) => Ok((
+            format!("application \"{}\"", applescript::escape(name)),
+            format!("name is \"{}\"", applescript::escape(name)),
+        )),
+        (None, None) => Err(Error::InvalidInput(
+            "An app name or bundle identifier is required".to_string(),
+        )),
+    }
+}
+
+fn activate_macos(app: &str, process: &str, window_title: Option<&str>) -> Result<(), Error> {
+    let Some(title) = window_title else {
+        return applescript::run(&format!("tell {} to activate", app)).map(|_| ());
+    };
+    let script = format!(
+        r#"set wanted to "{title}"
+set raised to false
+try
+    tell {app}
+        set index of (first window whose name contains wanted) to 1
+        set raised to true
+    end tell
+end try
+if not raised then
+    tell application "System Events"
+        tell (first process whose {process})
+            set matches to (windows whose name contains wanted)
+            if (count of matches) is 0 then return "missing"
+            perform action "AXRaise" of item 1 of matches
+        end tell
+    end tell
+end if
+tell {app} to activate
+return "raised""#,
+        title = applescript::escape(title),
+        app = app,
+        process = process,
+    );
+    match applescript::run(&script) {
+        Ok(result) if result == "missing" => Err(Error::NotFound(format!(
+            "No window whose title contains \"{}\"",
+            title
+        ))),
+        Ok(_) => Ok(()),
+        Err(Error::Internal(message))
+            if message.contains(ERR_NOT_TRUSTED) || message.contains(ERR_NOT_TRUSTED_UI) =>
+        {
+            Err(Error::PermissionDenied {
+                permission: "accessibility",
+                message: "Accessibility permission denied. Allow Claude PM under System Settings → Privacy & Security → Accessibility".to_string(),
+            })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// `wmctrl -a` raises the first window whose title contains the text, switching desktops;
+/// `-x` matches the WM_CLASS instead, which is the closest to an app name or id there is
+fn activate_linux(app: &str, window_title: Option<&str>) -> Result<(), Error> {
+    if process::which("wmctrl").is_none() {
+        return Err(Error::Unsupported(
+            "Activating windows needs wmctrl installed".to_string(),
+        ));
+    }
+    let mut cmd = Command::new("wmctrl");
+    match window_title {
+        Some(title) => cmd.args(["-a", title]),
+        None => cmd.args(["-x", "-a", app]),
+    };
+    let output = process::output(&mut cmd)?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::NotFound(format!(
+            "No window matching \"{}\"",
+            window_title.unwrap_or(app)
+        )))
+    }
+}
+
+/// Bring an app forward by name or bundle id, optionally just the window whose title
+/// contains `target.window_title`, switching to its Space or desktop
+#[tauri::command]
+pub fn activate_app(app_name: Option<String>, target: Option<ActivateTarget>) -> Result<(), Error> {
+    let target = target.unwrap_or_default();
+    let app_name = app_name.filter(|name| !name.trim().is_empty());
+    let bundle_id = target.bundle_id.filter(|id| !id.trim().is_empty());
+    let window_title = target.window_title.filter(|title| !title.is_empty());
+    if cfg!(target_os = "macos") {
+        let (app, process) = app_refs(app_name.as_deref(), bundle_id.as_deref())?;
+        activate_macos(&app, &process, window_title.as_deref())
+    } else if cfg!(target_os = "linux") {
+        let app = bundle_id.or(app_name).ok_or_else(|| {
+            Error::InvalidInput("An app name or bundle identifier is required".to_string())
+        })?;
+        activate_linux(&app, window_title.as_deref())
+    } else {
+        Err(Error::Unsupported(
+            "Activating other apps is not supported on this platform".to_string(),
+        ))
+    }
+}
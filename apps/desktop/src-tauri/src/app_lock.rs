@@ -0,0 +1,244 @@
+//! Locking the app behind Touch ID / system authentication
+//!
+//! With the lock enabled the app starts locked (unless `lock_on_launch` is off) and
+//! locks again after the configured minutes without input or on `lock_now`. While
+//! locked the frontend shows only the lock screen and the invoke handler rejects every
+//! command but the few in `UNLOCKED_COMMANDS`; entry points outside the webview (local
+//! API, launcher, LAN share) check `ensure_unlocked` themselves. `unlock_app` asks the OS to verify the user: LocalAuthentication
+//! on macOS (Touch ID, falling back to the account password), Windows Hello on Windows
+//! and polkit (`pkexec`) on Linux.
+//!
+//! Events:
+//! - `app-locked`
+//! - `app-unlocked`
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{config, idle};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+const REASON: &str = "unlock Claude PM";
+
+/// What the lock screen needs: reading the status, unlocking, and locking again
+const UNLOCKED_COMMANDS: &[&str] = &["get_app_lock_status", "unlock_app", "lock_now"];
+
+static LOCKED: AtomicBool = AtomicBool::new(false);
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AppLockSettings {
+    pub enabled: bool,
+    pub lock_on_launch: bool,
+    /// Minutes without input before the app locks itself; never when unset
+    pub idle_lock_mins: Option<u64>,
+}
+
+impl Default for AppLockSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            lock_on_launch: true,
+            idle_lock_mins: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppLockStatus {
+    pub locked: bool,
+    pub settings: AppLockSettings,
+}
+
+fn status() -> AppLockStatus {
+    AppLockStatus {
+        locked: is_locked(),
+        settings: config::load().app_lock,
+    }
+}
+
+pub fn is_locked() -> bool {
+    LOCKED.load(Ordering::SeqCst)
+}
+
+/// Guard for commands that hand out secrets
+pub fn ensure_unlocked() -> Result<(), Error> {
+    if is_locked() {
+        Err(Error::Locked)
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether the webview may run `command` right now
+pub fn allows(command: &str) -> bool {
+    !is_locked() || UNLOCKED_COMMANDS.contains(&command)
+}
+
+fn lock(app: &AppHandle) {
+    if !LOCKED.swap(true, Ordering::SeqCst) {
+        println!("[Claude PM] App locked");
+        let _ = app.emit("app-locked", ());
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn authenticate(reason: &str) -> Result<(), String> {
+    use block2::RcBlock;
+    use objc2::msg_send;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject, Bool};
+    use objc2_foundation::{NSError, NSString};
+    use std::sync::mpsc;
+
+    #[link(name = "LocalAuthentication", kind = "framework")]
+    extern "C" {}
+
+    /// Biometrics with the account password as fallback
+    const LA_POLICY_DEVICE_OWNER_AUTHENTICATION: isize = 2;
+
+    let class = AnyClass::get(c"LAContext").ok_or("LocalAuthentication is unavailable")?;
+    let context: Retained<AnyObject> = unsafe { msg_send![class, new] };
+    let (tx, rx) = mpsc::channel();
+    // Called once on a private queue with the outcome
+    let reply = RcBlock::new(move |success: Bool, error: *mut NSError| {
+        let result = if success.as_bool() {
+            Ok(())
+        } else {
+            Err(unsafe { error.as_ref() }
+                .map(|e| e.localizedDescription().to_string())
+                .unwrap_or_else(|| "Authentication failed".to_string()))
+        };
+        let _ = tx.send(result);
+    });
+    let reason = NSString::from_str(reason);
+    let _: () = unsafe {
+        msg_send![
+            &context,
+            evaluatePolicy: LA_POLICY_DEVICE_OWNER_AUTHENTICATION,
+            localizedReason: &*reason,
+            reply: &*reply
+        ]
+    };
+    rx.recv().map_err(|e| e.to_string())?
+}
+
+#[cfg(windows)]
+fn authenticate(reason: &str) -> Result<(), String> {
+    use windows::core::HSTRING;
+    use windows::Security::Credentials::UI::{UserConsentVerificationResult, UserConsentVerifier};
+
+    let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+        .and_then(|operation| operation.get())
+        .map_err(|e| format!("Windows Hello is unavailable: {}", e))?;
+    if result == UserConsentVerificationResult::Verified {
+        Ok(())
+    } else {
+        Err(format!("Verification failed ({:?})", result))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn authenticate(_reason: &str) -> Result<(), String> {
+    // polkit prompts for the user's password; `true` is only there to have something to run
+    let output = crate::process::output(std::process::Command::new("pkexec").arg("true"))
+        .map_err(|_| "pkexec (polkit) is required to unlock on Linux".to_string())?;
+    match output.status.code() {
+        Some(0) => Ok(()),
+        Some(126) => Err("Authentication was cancelled".to_string()),
+        _ => Err("Authentication failed".to_string()),
+    }
+}
+
+/// Lock on launch if configured, and watch for the idle timeout
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let settings = config::load().app_lock;
+    if settings.enabled && settings.lock_on_launch {
+        LOCKED.store(true, Ordering::SeqCst);
+    }
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        let settings = config::load().app_lock;
+        let Some(mins) = settings.idle_lock_mins.filter(|_| settings.enabled) else {
+            continue;
+        };
+        if idle::idle_time().is_ok_and(|idle| idle >= Duration::from_secs(mins.max(1) * 60)) {
+            lock(&app);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_app_lock_status() -> AppLockStatus {
+    status()
+}
+
+/// Change the lock policy; needs an unlocked app, and turning the lock on verifies the
+/// user first so nobody enables a lock they can't open
+#[tauri::command]
+pub async fn set_app_lock_policy(settings: AppLockSettings) -> Result<AppLockStatus, Error> {
+    ensure_unlocked()?;
+    if settings.idle_lock_mins == Some(0) {
+        return Err(Error::InvalidInput(
+            "Idle lock must be at least one minute".to_string(),
+        ));
+    }
+    if settings.enabled && !config::load().app_lock.enabled {
+        tauri::async_runtime::spawn_blocking(|| authenticate(REASON))
+            .await
+            .map_err(|e| e.to_string())??;
+    }
+    config::update(|c| c.app_lock = settings)?;
+    Ok(status())
+}
+
+#[tauri::command]
+pub fn lock_now(app: AppHandle) -> Result<(), Error> {
+    if !config::load().app_lock.enabled {
+        return Err(Error::InvalidInput(
+            "The app lock is not enabled".to_string(),
+        ));
+    }
+    lock(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn unlock_app(app: AppHandle) -> Result<(), Error> {
+    if !is_locked() {
+        return Ok(());
+    }
+    tauri::async_runtime::spawn_blocking(|| authenticate(REASON))
+        .await
+        .map_err(|e| e.to_string())??;
+    LOCKED.store(false, Ordering::SeqCst);
+    println!("[Claude PM] App unlocked");
+    let _ = app.emit("app-unlocked", ());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_lock_screen_commands_run_while_locked() {
+        LOCKED.store(true, Ordering::SeqCst);
+        let allowed = ["get_app_lock_status", "unlock_app", "lock_now"].map(allows);
+        let blocked = ["export_backup", "get_env_set", "set_github_token", "search"].map(allows);
+        LOCKED.store(false, Ordering::SeqCst);
+
+        assert!(allowed.iter().all(|&ok| ok));
+        assert!(blocked.iter().all(|&ok| !ok));
+        assert!(allows("export_backup"));
+    }
+}
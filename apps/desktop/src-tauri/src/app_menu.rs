@@ -0,0 +1,355 @@
+//! Native application menu
+//!
+//! File (New Task, Open Project, Recent Projects), Edit, Server (Restart, Show Logs) and
+//! Window menus. Item accelerators can be overridden in the config file, and the Recent
+//! Projects submenu is rebuilt from the `recent_projects` table whenever it changes.
+//!
+//! Events:
+//! - `menu-action` `{ action, projectId }` for every custom item that is clicked
+
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::thread;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::{AppHandle, Emitter, Wry};
+
+use crate::config;
+#[cfg(any(target_os = "macos", windows))]
+use crate::dock_menu;
+use crate::error::Error;
+use crate::session_windows::route_navigation;
+use crate::store::{self, Project};
+use crate::windows::focus_main_window;
+
+/// Items that can be rebound, with their labels and default accelerators
+const ACTIONS: &[(&str, &str, &str)] = &[
+    ("newTask", "New Task", "CmdOrCtrl+N"),
+    ("openProject", "Open Project…", "CmdOrCtrl+O"),
+    ("restartServer", "Restart Server", "CmdOrCtrl+Shift+R"),
+    ("showLogs", "Show Logs", "CmdOrCtrl+Shift+L"),
+];
+const MODIFIERS: &[&str] = &[
+    "cmd",
+    "command",
+    "ctrl",
+    "control",
+    "cmdorctrl",
+    "cmdorcontrol",
+    "commandorcontrol",
+    "commandorctrl",
+    "alt",
+    "option",
+    "shift",
+    "super",
+    "meta",
+];
+/// Projects kept in Recent Projects
+const RECENT_LIMIT: usize = 10;
+const RECENT_PREFIX: &str = "recent:";
+const CLEAR_RECENT: &str = "clearRecent";
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenuAccelerator {
+    pub action: String,
+    pub label: String,
+    /// Empty when the item has no accelerator
+    pub accelerator: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MenuAction<'a> {
+    action: &'a str,
+    project_id: Option<&'a str>,
+}
+
+/// Accelerator per action after applying config overrides
+fn configured_accelerators() -> Vec<MenuAccelerator> {
+    let overrides = config::load().menu_accelerators;
+    ACTIONS
+        .iter()
+        .map(|(action, label, default)| MenuAccelerator {
+            action: action.to_string(),
+            label: label.to_string(),
+            accelerator: overrides
+                .get(*action)
+                .cloned()
+                .unwrap_or_else(|| default.to_string()),
+        })
+        .collect()
+}
+
+/// `Modifier+...+Key`, e.g. `CmdOrCtrl+Shift+R`
+fn validate_accelerator(accelerator: &str) -> Result<(), String> {
+    let parts: Vec<&str> = accelerator.split('+').map(str::trim).collect();
+    let (key, modifiers) = parts.split_last().ok_or("Empty accelerator")?;
+    let is_modifier = |part: &str| MODIFIERS.contains(&part.to_lowercase().as_str());
+    if key.is_empty() || is_modifier(key) {
+        return Err(format!("Accelerator {} has no key", accelerator));
+    }
+    if let Some(unknown) = modifiers.iter().find(|m| !is_modifier(m)) {
+        return Err(format!(
+            "Unknown modifier {} in accelerator {}",
+            unknown, accelerator
+        ));
+    }
+    Ok(())
+}
+
+fn item(app: &AppHandle, binding: &MenuAccelerator) -> tauri::Result<MenuItem<Wry>> {
+    let accelerator = Some(binding.accelerator.as_str()).filter(|a| !a.is_empty());
+    MenuItem::with_id(app, &binding.action, &binding.label, true, accelerator)
+}
+
+fn recent_submenu(app: &AppHandle) -> tauri::Result<Submenu<Wry>> {
+    let projects = store::recent_projects(RECENT_LIMIT).unwrap_or_else(|e| {
+        eprintln!("[Claude PM] Failed to load recent projects: {}", e);
+        Vec::new()
+    });
+    let submenu = Submenu::new(app, "Recent Projects", true)?;
+    for project in &projects {
+        submenu.append(&MenuItem::with_id(
+            app,
+            format!("{}{}", RECENT_PREFIX, project.id),
+            &project.name,
+            true,
+            None::<&str>,
+        )?)?;
+    }
+    if !projects.is_empty() {
+        submenu.append(&PredefinedMenuItem::separator(app)?)?;
+    }
+    submenu.append(&MenuItem::with_id(
+        app,
+        CLEAR_RECENT,
+        "Clear Recent",
+        !projects.is_empty(),
+        None::<&str>,
+    )?)?;
+    Ok(submenu)
+}
+
+fn build(app: &AppHandle) -> tauri::Result<Menu<Wry>> {
+    let bindings = configured_accelerators();
+    let binding = |action: &str| {
+        bindings
+            .iter()
+            .find(|b| b.action == action)
+            .expect("known action")
+    };
+    let menu = Menu::new(app)?;
+
+    #[cfg(target_os = "macos")]
+    menu.append(&Submenu::with_items(
+        app,
+        "Claude PM",
+        true,
+        &[
+            &PredefinedMenuItem::about(app, None, None)?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::services(app, None)?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::hide(app, None)?,
+            &PredefinedMenuItem::hide_others(app, None)?,
+            &PredefinedMenuItem::show_all(app, None)?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::quit(app, None)?,
+        ],
+    )?)?;
+
+    let file = Submenu::with_items(
+        app,
+        "File",
+        true,
+        &[
+            &item(app, binding("newTask"))?,
+            &item(app, binding("openProject"))?,
+            &recent_submenu(app)?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::close_window(app, None)?,
+        ],
+    )?;
+    #[cfg(not(target_os = "macos"))]
+    file.append_items(&[
+        &PredefinedMenuItem::separator(app)?,
+        &PredefinedMenuItem::quit(app, None)?,
+    ])?;
+    menu.append(&file)?;
+
+    menu.append(&Submenu::with_items(
+        app,
+        "Edit",
+        true,
+        &[
+            &PredefinedMenuItem::undo(app, None)?,
+            &PredefinedMenuItem::redo(app, None)?,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::cut(app, None)?,
+            &PredefinedMenuItem::copy(app, None)?,
+            &PredefinedMenuItem::paste(app, None)?,
+            &PredefinedMenuItem::select_all(app, None)?,
+        ],
+    )?)?;
+
+    menu.append(&Submenu::with_items(
+        app,
+        "Server",
+        true,
+        &[
+            &item(app, binding("restartServer"))?,
+            &item(app, binding("showLogs"))?,
+        ],
+    )?)?;
+
+    menu.append(&Submenu::with_items(
+        app,
+        "Window",
+        true,
+        &[
+            &PredefinedMenuItem::minimize(app, None)?,
+            &PredefinedMenuItem::maximize(app, None)?,
+            &PredefinedMenuItem::fullscreen(app, None)?,
+        ],
+    )?)?;
+
+    Ok(menu)
+}
+
+/// Rebuild and install the menu, e.g. after the recent list or an accelerator changed
+fn refresh(app: &AppHandle) -> Result<(), String> {
+    let menu = build(app).map_err(|e| format!("Failed to build menu: {}", e))?;
+    app.set_menu(menu)
+        .map_err(|e| format!("Failed to set menu: {}", e))?;
+    #[cfg(any(target_os = "macos", windows))]
+    dock_menu::refresh();
+    Ok(())
+}
+
+fn handle(app: &AppHandle, event: MenuEvent) {
+    let id = event.id().as_ref();
+    let (action, project_id) = match id.strip_prefix(RECENT_PREFIX) {
+        Some(project_id) => ("openRecent", Some(project_id)),
+        None if ACTIONS.iter().any(|(action, _, _)| *action == id) || id == CLEAR_RECENT => {
+            (id, None)
+        }
+        // Predefined items are handled by the OS
+        None => return,
+    };
+
+    match action {
+        "openRecent" => {
+            let project_id = project_id.unwrap_or_default();
+            route_navigation(app, &format!("/projects/{}", project_id));
+            project_opened(project_id);
+        }
+        "newTask" | "openProject" | "showLogs" => focus_main_window(app),
+        "restartServer" => {
+            thread::spawn(|| {
+                if let Err(e) = crate::restart_server() {
+                    eprintln!("[Claude PM] Restart from menu failed: {}", e);
+                }
+            });
+        }
+        CLEAR_RECENT => {
+            if let Err(e) = store::clear_recent_projects().and_then(|_| refresh(app)) {
+                eprintln!("[Claude PM] {}", e);
+            }
+        }
+        _ => {}
+    }
+    let _ = app.emit("menu-action", MenuAction { action, project_id });
+}
+
+/// Bring up the New Task form, as File → New Task does
+pub fn new_task(app: &AppHandle, project_id: Option<&str>) {
+    focus_main_window(app);
+    let _ = app.emit(
+        "menu-action",
+        MenuAction {
+            action: "newTask",
+            project_id,
+        },
+    );
+}
+
+/// Install the menu and its event handler
+pub fn init(app: &AppHandle) -> Result<(), String> {
+    let _ = APP.set(app.clone());
+    app.on_menu_event(handle);
+    refresh(app)
+}
+
+/// Move a project to the top of Recent Projects
+pub fn project_opened(project_id: &str) {
+    let result = store::touch_recent_project(project_id, RECENT_LIMIT)
+        .and_then(|_| APP.get().map_or(Ok(()), refresh));
+    if let Err(e) = result {
+        eprintln!("[Claude PM] Failed to update recent projects: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn list_recent_projects() -> Result<Vec<Project>, Error> {
+    store::recent_projects(RECENT_LIMIT).map_err(Error::from)
+}
+
+/// Record that the user opened a project (the frontend calls this on navigation)
+#[tauri::command]
+pub fn mark_project_opened(project_id: String) -> Result<(), Error> {
+    store::touch_recent_project(&project_id, RECENT_LIMIT)?;
+    if let Some(app) = APP.get() {
+        refresh(app)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_recent_projects() -> Result<(), Error> {
+    store::clear_recent_projects()?;
+    if let Some(app) = APP.get() {
+        refresh(app)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_menu_accelerators() -> Vec<MenuAccelerator> {
+    configured_accelerators()
+}
+
+/// Bind a menu item to `accelerator` (e.g. `CmdOrCtrl+Shift+N`); `None` removes it
+#[tauri::command]
+pub fn set_menu_accelerator(
+    app: AppHandle,
+    action: String,
+    accelerator: Option<String>,
+) -> Result<(), Error> {
+    if !ACTIONS.iter().any(|(known, _, _)| *known == action) {
+        return Err(Error::InvalidInput(format!(
+            "Unknown menu action: {}",
+            action
+        )));
+    }
+    let accelerator = accelerator.unwrap_or_default();
+    if !accelerator.is_empty() {
+        validate_accelerator(&accelerator).map_err(Error::InvalidInput)?;
+        let normalized = accelerator.to_lowercase();
+        if let Some(other) = configured_accelerators()
+            .into_iter()
+            .find(|b| b.action != action && b.accelerator.to_lowercase() == normalized)
+        {
+            return Err(Error::InvalidInput(format!(
+                "{} is already used by {}",
+                accelerator, other.action
+            )));
+        }
+    }
+
+    config::update(|c| {
+        c.menu_accelerators.insert(action, accelerator);
+    })?;
+    refresh(&app).map_err(Error::from)
+}
@@ -0,0 +1,48 @@
+//! macOS automation via AppleScript (`osascript`)
+//!
+//! All AppleScript in the app goes through here so errors are reported consistently,
+//! in particular the Automation permission being denied.
+
+use std::process::Command;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::process::{self, CancelToken};
+
+/// AppleEvent error returned when the user has denied Automation access
+const ERR_EVENT_NOT_PERMITTED: &str = "-1743";
+/// The first AppleEvent to an app can sit on a consent prompt until the user answers
+const CONSENT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Escape a string for use inside an AppleScript double-quoted literal
+pub fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Run a script, returning stdout
+pub fn run(script: &str) -> Result<String, Error> {
+    run_with_args(script, &[])
+}
+
+/// Run a script whose `on run argv` handler receives `args`; avoids escaping user text
+pub fn run_with_args(script: &str, args: &[&str]) -> Result<String, Error> {
+    let output = process::run(
+        Command::new("osascript").args(["-e", script]).args(args),
+        process::default_timeout().max(CONSENT_TIMEOUT),
+        &CancelToken::default(),
+    )?;
+
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains(ERR_EVENT_NOT_PERMITTED) {
+        Err(Error::PermissionDenied {
+            permission: "automation",
+            message: "Automation permission denied. Allow Claude PM under System Settings → Privacy & Security → Automation".to_string(),
+        })
+    } else {
+        Err(format!("osascript failed: {}", stderr.trim()).into())
+    }
+}
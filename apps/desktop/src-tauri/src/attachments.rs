@@ -0,0 +1,395 @@
+//! Files attached to tasks (screenshots, recordings, logs)
+//!
+//! Files live under `attachments/` in the data directory and are indexed in the store,
+//! so they're included in backups and removed with their task's rows. Files dropped on
+//! the main window or imported by path are content-addressed (`<sha256>.<ext>`), so the
+//! same file dropped twice is stored once.
+//!
+//! Events:
+//! - `attachment-added`: a dropped file was ingested (or matched an existing one)
+
+use rusqlite::{params, OptionalExtension, Row};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{config, preview, secrets, store};
+
+const ATTACHMENTS_DIR: &str = "attachments";
+/// Characters of a text file kept as its preview
+const PREVIEW_CHARS: usize = 500;
+
+/// Extension -> MIME type for the files people typically attach
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("json", "application/json"),
+    ("cast", "application/x-asciicast"),
+    ("mp4", "video/mp4"),
+    ("mov", "video/quicktime"),
+    ("txt", "text/plain"),
+    ("log", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("html", "text/html"),
+    ("diff", "text/x-diff"),
+    ("patch", "text/x-diff"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: String,
+    pub task_id: Option<String>,
+    pub file_name: String,
+    /// Absolute path, for previews and "reveal in Finder"
+    pub path: String,
+    pub mime_type: String,
+    pub size: u64,
+    /// SHA-256 of the contents, for content-addressed files
+    pub hash: Option<String>,
+    /// Leading text of text files; images are previewed from `path`
+    pub preview: Option<String>,
+    pub created_at: i64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AttachmentAdded {
+    attachment: Attachment,
+    /// The contents were already stored; `attachment` is the existing entry
+    duplicate: bool,
+}
+
+impl Attachment {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            file_name: row.get("file_name")?,
+            path: row.get("path")?,
+            mime_type: row.get("mime_type")?,
+            size: row.get("size")?,
+            hash: row.get("hash")?,
+            preview: row.get("preview")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+pub fn attachments_dir() -> Result<PathBuf, String> {
+    let dir = config::data_dir()
+        .ok_or("Could not determine data directory")?
+        .join(ATTACHMENTS_DIR);
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create attachments directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Where a new attachment with `file_name` should be written
+pub fn new_path(file_name: &str) -> Result<PathBuf, String> {
+    Ok(attachments_dir()?.join(format!("{}-{}", store::new_id(), file_name)))
+}
+
+/// Files in the attachment store that no attachment row points at, older than `grace`
+/// (a file from [`new_path`] exists briefly before it is indexed)
+pub fn orphaned_files(grace: Duration) -> Result<Vec<PathBuf>, String> {
+    let referenced: HashSet<PathBuf> = store::with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT path FROM attachments")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        rows.map(|path| path.map(PathBuf::from)).collect()
+    })?;
+    let entries = fs::read_dir(attachments_dir()?).map_err(|e| e.to_string())?;
+    Ok(entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && !referenced.contains(path))
+        .filter(|path| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age >= grace)
+        })
+        .collect())
+}
+
+fn mime_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    MIME_TYPES
+        .iter()
+        .find(|(known, _)| *known == ext)
+        .map(|(_, mime)| *mime)
+        .unwrap_or("application/octet-stream")
+}
+
+fn sha256(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(secrets::hex(&hasher.finalize()))
+}
+
+fn text_preview(path: &Path, mime: &str) -> Option<String> {
+    if !mime.starts_with("text/") && mime != "application/json" {
+        return None;
+    }
+    let mut buf = Vec::new();
+    File::open(path)
+        .ok()?
+        .take((PREVIEW_CHARS * 4) as u64)
+        .read_to_end(&mut buf)
+        .ok()?;
+    Some(
+        String::from_utf8_lossy(&buf)
+            .chars()
+            .take(PREVIEW_CHARS)
+            .collect(),
+    )
+}
+
+pub fn get(id: &str) -> Result<Option<Attachment>, String> {
+    store::with_conn(|conn| {
+        conn.query_row(
+            "SELECT * FROM attachments WHERE id = ?1",
+            [id],
+            Attachment::from_row,
+        )
+        .optional()
+    })
+}
+
+/// An attachment with these contents, preferring one already on `task_id`, then an
+/// unlinked one
+fn find_by_hash(hash: &str, task_id: Option<&str>) -> Result<Option<Attachment>, String> {
+    store::with_conn(|conn| {
+        conn.query_row(
+            "SELECT * FROM attachments WHERE hash = ?1
+             ORDER BY task_id IS NOT ?2, task_id IS NOT NULL, created_at LIMIT 1",
+            params![hash, task_id],
+            Attachment::from_row,
+        )
+        .optional()
+    })
+}
+
+/// Copy a file into the store, reusing the stored copy when the contents are already
+/// there: an unlinked entry is linked to `task_id`, and one on another task gets a new
+/// entry for `task_id` pointing at the same file. Returns the attachment and whether the
+/// contents were a duplicate
+pub fn ingest(source: &Path, task_id: Option<&str>) -> Result<(Attachment, bool), Error> {
+    if !source.is_file() {
+        return Err(Error::InvalidInput(format!(
+            "Not a file: {}",
+            source.display()
+        )));
+    }
+    let hash = sha256(source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+    if let Some(mut existing) = find_by_hash(&hash, task_id)? {
+        let Some(task_id) = task_id.filter(|&id| existing.task_id.as_deref() != Some(id)) else {
+            return Ok((existing, true));
+        };
+        if existing.task_id.is_none() {
+            set_attachment_task(existing.id.clone(), Some(task_id.to_string()))?;
+            existing.task_id = Some(task_id.to_string());
+            return Ok((existing, true));
+        }
+        let attachment = Attachment {
+            id: store::new_id(),
+            task_id: Some(task_id.to_string()),
+            file_name: source
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or(existing.file_name),
+            created_at: store::now_ms(),
+            ..existing
+        };
+        insert(&attachment)?;
+        preview::queue(&attachment);
+        return Ok((attachment, true));
+    }
+
+    let mime = mime_type(source);
+    let stored = match source.extension() {
+        Some(ext) => attachments_dir()?.join(format!("{}.{}", hash, ext.to_string_lossy())),
+        None => attachments_dir()?.join(&hash),
+    };
+    if !stored.exists() {
+        fs::copy(source, &stored).map_err(|e| format!("Failed to copy attachment: {}", e))?;
+    }
+    let attachment = Attachment {
+        id: store::new_id(),
+        task_id: task_id.map(str::to_string),
+        file_name: source
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| hash.clone()),
+        path: stored.display().to_string(),
+        mime_type: mime.to_string(),
+        size: fs::metadata(&stored).map(|m| m.len()).unwrap_or_default(),
+        preview: text_preview(&stored, mime),
+        hash: Some(hash),
+        created_at: store::now_ms(),
+    };
+    insert(&attachment)?;
+    preview::queue(&attachment);
+    Ok((attachment, false))
+}
+
+/// Ingest files dropped on the main window; hashing can be slow, so off the event loop
+pub fn on_drop(app: &AppHandle, paths: Vec<PathBuf>) {
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for path in paths.iter().filter(|path| path.is_file()) {
+            match ingest(path, None) {
+                Ok((attachment, duplicate)) => {
+                    let _ = app.emit(
+                        "attachment-added",
+                        AttachmentAdded {
+                            attachment,
+                            duplicate,
+                        },
+                    );
+                }
+                Err(e) => eprintln!("[Claude PM] Failed to ingest {}: {}", path.display(), e),
+            }
+        }
+    });
+}
+
+fn insert(attachment: &Attachment) -> Result<(), String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO attachments (id, task_id, file_name, path, mime_type, size, hash, preview, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                attachment.id,
+                attachment.task_id,
+                attachment.file_name,
+                attachment.path,
+                attachment.mime_type,
+                attachment.size,
+                attachment.hash,
+                attachment.preview,
+                attachment.created_at
+            ],
+        )
+        .map(|_| ())
+    })
+}
+
+/// Index a file already written to a path from [`new_path`]
+pub fn add(task_id: Option<&str>, path: &Path, mime_type: &str) -> Result<Attachment, Error> {
+    let size = fs::metadata(path)
+        .map_err(|e| format!("Attachment file missing: {}", e))?
+        .len();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .and_then(|name| name.split_once('-').map(|(_, rest)| rest.to_string()))
+        .unwrap_or_default();
+    let attachment = Attachment {
+        id: store::new_id(),
+        task_id: task_id.map(str::to_string),
+        file_name,
+        path: path.display().to_string(),
+        mime_type: mime_type.to_string(),
+        size,
+        hash: None,
+        preview: None,
+        created_at: store::now_ms(),
+    };
+    insert(&attachment)?;
+    preview::queue(&attachment);
+    Ok(attachment)
+}
+
+/// Import files by path (e.g. from a file picker), deduplicated like dropped files
+#[tauri::command]
+pub async fn import_attachments(
+    paths: Vec<String>,
+    task_id: Option<String>,
+) -> Result<Vec<Attachment>, Error> {
+    tauri::async_runtime::spawn_blocking(move || {
+        paths
+            .iter()
+            .map(|path| ingest(Path::new(path), task_id.as_deref()).map(|(a, _)| a))
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn list_attachments(task_id: String) -> Result<Vec<Attachment>, Error> {
+    store::with_conn(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT * FROM attachments WHERE task_id = ?1 ORDER BY created_at DESC")?;
+        let rows = stmt.query_map([&task_id], Attachment::from_row)?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
+
+/// Link an attachment to a task, or unlink it with `None`
+#[tauri::command]
+pub fn set_attachment_task(id: String, task_id: Option<String>) -> Result<(), Error> {
+    let updated = store::with_conn(|conn| {
+        conn.execute(
+            "UPDATE attachments SET task_id = ?2 WHERE id = ?1",
+            params![id, task_id],
+        )
+    })?;
+    if updated == 0 {
+        return Err(Error::NotFound(format!("Attachment not found: {}", id)));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_attachment(id: String) -> Result<(), Error> {
+    let path: Option<String> = store::with_conn(|conn| {
+        conn.query_row("SELECT path FROM attachments WHERE id = ?1", [&id], |row| {
+            row.get(0)
+        })
+        .optional()
+    })?;
+    let Some(path) = path else {
+        return Err(Error::NotFound(format!("Attachment not found: {}", id)));
+    };
+    preview::remove(&id);
+    // Entries with the same contents share the stored file
+    let shared = store::with_conn(|conn| {
+        conn.execute("DELETE FROM attachments WHERE id = ?1", [&id])?;
+        conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM attachments WHERE path = ?1)",
+            [&path],
+            |row| row.get::<_, bool>(0),
+        )
+    })?;
+    if !shared {
+        let _ = fs::remove_file(path);
+    }
+    Ok(())
+}
@@ -0,0 +1,35 @@
+//! Drawing the user's attention: dock bounce on macOS, taskbar flash on Windows
+
+use tauri::{AppHandle, UserAttentionType};
+
+use crate::error::Error;
+use crate::windows::main_window;
+
+fn attention_type(critical: bool) -> UserAttentionType {
+    if critical {
+        UserAttentionType::Critical
+    } else {
+        UserAttentionType::Informational
+    }
+}
+
+/// Request attention only when the app is in the background; used by monitors
+pub fn request_if_backgrounded(app: &AppHandle, critical: bool) {
+    let Ok(window) = main_window(app) else {
+        return;
+    };
+    if window.is_focused().unwrap_or(false) {
+        return;
+    }
+    if let Err(e) = window.request_user_attention(Some(attention_type(critical))) {
+        eprintln!("[Claude PM] Failed to request attention: {}", e);
+    }
+}
+
+/// Bounce the dock icon / flash the taskbar. Critical requests repeat until the app is focused
+#[tauri::command]
+pub fn request_attention(app: AppHandle, critical: bool) -> Result<(), Error> {
+    main_window(&app)?
+        .request_user_attention(Some(attention_type(critical)))
+        .map_err(|e| format!("Failed to request attention: {}", e).into())
+}
@@ -0,0 +1,380 @@
+//! Append-only audit log of what the app and its agents did
+//!
+//! Records Tauri command invocations (everything but reads: `get_*`, `list_*`, …, and the
+//! UI feedback calls the webview makes on every tick), agent runs being started, approval decisions and user answers to prompts. Hook events aren't
+//! recorded: anything local can post them, so they prove nothing. Entries go into the
+//! `audit_log` table, where triggers refuse updates and deletes, and each one carries a
+//! SHA-256 over its fields and the previous entry's hash, so `verify_audit_log` can tell
+//! if rows were altered or removed behind the app's back. The chain starts at seq 1, so
+//! dropping the oldest rows shows up as a gap too.
+//!
+//! Command arguments are logged by name only; their values may be secrets.
+
+use rusqlite::{params, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::ipc::{Invoke, InvokeBody};
+use tauri::Runtime;
+
+use crate::error::Error;
+use crate::{secrets, store};
+
+const DEFAULT_LIMIT: usize = 500;
+/// Commands that only read state aren't worth an entry each time the UI polls
+const READ_PREFIXES: &[&str] = &["get_", "list_", "is_", "search_", "preview_", "query_"];
+/// Badge, progress, overlay and subscription updates fire on every tick or keystroke and
+/// change nothing the log is there to account for
+const UI_COMMANDS: &[&str] = &[
+    "activate_app",
+    "set_badge_count",
+    "set_dock_progress",
+    "request_attention",
+    "set_tray_badge",
+    "set_overlay_badge",
+    "show_quick_switcher",
+    "hide_quick_switcher",
+    "toggle_quick_switcher",
+    "show_notification",
+    "speak",
+    "stop_speaking",
+    "bridge_subscribe",
+    "bridge_unsubscribe",
+    "watch_tmux_pane",
+    "unwatch_tmux_pane",
+    "follow_transcript",
+    "unfollow_transcript",
+    "estimate_run_cost",
+    "parse_due_date",
+    "render_report_markdown",
+    "take_opened_project",
+    "probe_server",
+];
+
+/// Serializes appends so each entry chains onto the one before it
+static APPEND: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditKind {
+    Command,
+    AgentSpawn,
+    Approval,
+    /// Written by earlier versions from hook events; kept so those entries still read back
+    FileChange,
+}
+
+impl AuditKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Command => "command",
+            Self::AgentSpawn => "agentSpawn",
+            Self::Approval => "approval",
+            Self::FileChange => "fileChange",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "agentSpawn" => Self::AgentSpawn,
+            "approval" => Self::Approval,
+            "fileChange" => Self::FileChange,
+            _ => Self::Command,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub seq: i64,
+    pub at: i64,
+    pub kind: AuditKind,
+    /// Command name, tool, or decision
+    pub action: String,
+    /// What it was about: a task, pane, file path…
+    pub subject: Option<String>,
+    pub detail: Value,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            seq: row.get("seq")?,
+            at: row.get("at")?,
+            kind: AuditKind::parse(&row.get::<_, String>("kind")?),
+            action: row.get("action")?,
+            subject: row.get("subject")?,
+            detail: serde_json::from_str(&row.get::<_, String>("detail")?).unwrap_or_default(),
+            hash: row.get("hash")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AuditFilter {
+    pub kind: Option<AuditKind>,
+    pub action: Option<String>,
+    /// Substring of the subject
+    pub subject: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditVerification {
+    pub ok: bool,
+    pub entries: usize,
+    /// First entry whose hash doesn't match, or that follows a gap
+    pub first_bad_seq: Option<i64>,
+}
+
+fn digest(
+    prev: &str,
+    at: i64,
+    kind: &str,
+    action: &str,
+    subject: Option<&str>,
+    detail: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        prev,
+        &at.to_string(),
+        kind,
+        action,
+        subject.unwrap_or(""),
+        detail,
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    secrets::hex(&hasher.finalize())
+}
+
+/// Append an entry; failures are logged rather than failing what's being audited
+pub fn record(kind: AuditKind, action: &str, subject: Option<&str>, detail: Value) {
+    let _guard = APPEND.lock();
+    let detail = detail.to_string();
+    let at = store::now_ms();
+    let result = store::with_conn(|conn| {
+        let prev: String = conn
+            .query_row(
+                "SELECT hash FROM audit_log ORDER BY seq DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(String::new()),
+                e => Err(e),
+            })?;
+        let hash = digest(&prev, at, kind.as_str(), action, subject, &detail);
+        conn.execute(
+            "INSERT INTO audit_log (at, kind, action, subject, detail, hash) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![at, kind.as_str(), action, subject, detail, hash],
+        )
+        .map(|_| ())
+    });
+    if let Err(e) = result {
+        eprintln!("[Claude PM] Failed to write audit entry: {}", e);
+    }
+}
+
+/// Log a command invocation from the webview; called before it's dispatched
+pub fn command<R: Runtime>(invoke: &Invoke<R>) {
+    let command = invoke.message.command();
+    if READ_PREFIXES
+        .iter()
+        .any(|prefix| command.starts_with(prefix))
+        || UI_COMMANDS.contains(&command)
+    {
+        return;
+    }
+    let args: Vec<&String> = match invoke.message.payload() {
+        InvokeBody::Json(Value::Object(args)) => args.keys().collect(),
+        _ => Vec::new(),
+    };
+    record(
+        AuditKind::Command,
+        command,
+        None,
+        serde_json::json!({ "args": args }),
+    );
+}
+
+fn query(filter: &AuditFilter) -> Result<Vec<AuditEntry>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT * FROM audit_log
+             WHERE (?1 IS NULL OR kind = ?1) AND (?2 IS NULL OR action = ?2)
+               AND (?3 IS NULL OR instr(subject, ?3) > 0)
+               AND (?4 IS NULL OR at >= ?4) AND (?5 IS NULL OR at <= ?5)
+             ORDER BY seq DESC LIMIT ?6",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                filter.kind.map(AuditKind::as_str),
+                filter.action,
+                filter.subject,
+                filter.since,
+                filter.until,
+                filter.limit.unwrap_or(DEFAULT_LIMIT) as i64
+            ],
+            AuditEntry::from_row,
+        )?;
+        rows.collect()
+    })
+}
+
+/// Entries matching `filter`, newest first
+#[tauri::command]
+pub fn query_audit_log(filter: Option<AuditFilter>) -> Result<Vec<AuditEntry>, Error> {
+    query(&filter.unwrap_or_default()).map_err(Error::from)
+}
+
+/// Write matching entries, oldest first, as JSON lines (`.jsonl`) or CSV (otherwise)
+#[tauri::command]
+pub fn export_audit_log(path: String, filter: Option<AuditFilter>) -> Result<usize, Error> {
+    let mut filter = filter.unwrap_or_default();
+    filter.limit = Some(filter.limit.unwrap_or(usize::MAX >> 1));
+    let mut entries = query(&filter)?;
+    entries.reverse();
+    let path = PathBuf::from(path);
+    let contents = if path.extension().is_some_and(|ext| ext == "jsonl") {
+        entries
+            .iter()
+            .filter_map(|entry| serde_json::to_string(entry).ok())
+            .map(|line| line + "\n")
+            .collect::<String>()
+    } else {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer
+            .write_record(["seq", "at", "kind", "action", "subject", "detail", "hash"])
+            .map_err(|e| e.to_string())?;
+        for entry in &entries {
+            writer
+                .write_record([
+                    entry.seq.to_string(),
+                    entry.at.to_string(),
+                    entry.kind.as_str().to_string(),
+                    entry.action.clone(),
+                    entry.subject.clone().unwrap_or_default(),
+                    entry.detail.to_string(),
+                    entry.hash.clone(),
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+        String::from_utf8(writer.into_inner().map_err(|e| e.to_string())?)
+            .map_err(|e| e.to_string())?
+    };
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(entries.len())
+}
+
+/// `seq, at, kind, action, subject, detail, hash` as stored
+type ChainRow = (i64, i64, String, String, Option<String>, String, String);
+
+/// Recompute the hash chain from the first entry
+#[tauri::command]
+pub fn verify_audit_log() -> Result<AuditVerification, Error> {
+    let rows: Vec<ChainRow> = store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT seq, at, kind, action, subject, detail, hash FROM audit_log ORDER BY seq",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+            ))
+        })?;
+        rows.collect()
+    })?;
+    Ok(verify_chain(&rows))
+}
+
+/// The first entry must be seq 1 with an empty previous hash; each one after follows on
+fn verify_chain(rows: &[ChainRow]) -> AuditVerification {
+    let mut prev = String::new();
+    let mut expected_seq = 1;
+    for (seq, at, kind, action, subject, detail, hash) in rows {
+        let intact = *seq == expected_seq
+            && digest(&prev, *at, kind, action, subject.as_deref(), detail) == *hash;
+        if !intact {
+            return AuditVerification {
+                ok: false,
+                entries: rows.len(),
+                first_bad_seq: Some(*seq),
+            };
+        }
+        prev = hash.clone();
+        expected_seq = seq + 1;
+    }
+    AuditVerification {
+        ok: true,
+        entries: rows.len(),
+        first_bad_seq: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(len: i64) -> Vec<ChainRow> {
+        let mut prev = String::new();
+        (1..=len)
+            .map(|seq| {
+                let hash = digest(&prev, seq, "command", "lock_now", None, "{}");
+                prev = hash.clone();
+                let row: ChainRow = (
+                    seq,
+                    seq,
+                    "command".into(),
+                    "lock_now".into(),
+                    None,
+                    "{}".into(),
+                    hash,
+                );
+                row
+            })
+            .collect()
+    }
+
+    #[test]
+    fn accepts_an_intact_chain() {
+        let verification = verify_chain(&chain(4));
+        assert!(verification.ok);
+        assert_eq!(verification.entries, 4);
+    }
+
+    #[test]
+    fn flags_the_oldest_rows_being_removed() {
+        let rows = chain(4);
+        let verification = verify_chain(&rows[2..]);
+        assert!(!verification.ok);
+        assert_eq!(verification.first_bad_seq, Some(3));
+    }
+
+    #[test]
+    fn flags_a_gap_and_an_edit() {
+        let mut rows = chain(4);
+        rows.remove(1);
+        assert_eq!(verify_chain(&rows).first_bad_seq, Some(3));
+
+        let mut rows = chain(4);
+        rows[2].3 = "unlock_app".into();
+        assert_eq!(verify_chain(&rows).first_bad_seq, Some(3));
+    }
+}
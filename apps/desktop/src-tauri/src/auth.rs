@@ -0,0 +1,87 @@
+//! Per-launch token shared between the webview and the local Node server
+//!
+//! The server is started with `DESKTOP_AUTH_TOKEN` and rejects localhost requests that
+//! don't echo it back in `X-Desktop-Token`, so other local processes can't drive the API.
+//! The server's long-lived `API_KEY` (for remote clients) is kept in the keychain, as is
+//! the token while the server runs as a background service (see `service`).
+
+use std::sync::OnceLock;
+
+use crate::error::Error;
+use crate::{app_lock, secrets};
+
+const KEYCHAIN_ACCOUNT: &str = "server-api-key";
+const TOKEN_ACCOUNT: &str = "desktop-token";
+/// Environment variable the server reads its API key from
+pub const API_KEY_ENV: &str = "API_KEY";
+/// The server rejects shorter keys
+pub const MIN_API_KEY_LEN: usize = 32;
+
+/// Environment variable the server reads the token from
+pub const TOKEN_ENV: &str = "DESKTOP_AUTH_TOKEN";
+/// Header (or `desktopToken` query parameter for WebSockets) carrying the token
+pub const TOKEN_HEADER: &str = "X-Desktop-Token";
+
+static TOKEN: OnceLock<String> = OnceLock::new();
+
+pub fn random_hex() -> String {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).expect("OS random number generator unavailable");
+    secrets::hex(&bytes)
+}
+
+/// Compare without short-circuiting, so response timing doesn't leak a token prefix
+pub fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+/// Random 256-bit token, generated on first use and stable for the life of the process;
+/// the persisted one while a service-managed server is installed
+pub fn token() -> &'static str {
+    TOKEN.get_or_init(|| {
+        crate::service::is_installed()
+            .then(persistent_token)
+            .flatten()
+            .unwrap_or_else(random_hex)
+    })
+}
+
+fn persistent_token() -> Option<String> {
+    secrets::get(TOKEN_ACCOUNT).ok().flatten()
+}
+
+/// Persist this process's token so later launches share it with the service
+pub fn use_persistent_token() -> Result<(), String> {
+    secrets::set(TOKEN_ACCOUNT, token())
+}
+
+pub fn clear_persistent_token() -> Result<(), String> {
+    secrets::delete(TOKEN_ACCOUNT)
+}
+
+/// The stored server API key, if one has been set up
+pub fn api_key() -> Option<String> {
+    secrets::get(KEYCHAIN_ACCOUNT).ok().flatten()
+}
+
+/// Store `key` as the server API key, generating a random one when `None`
+pub fn store_api_key(key: Option<String>) -> Result<(), String> {
+    let key = key.unwrap_or_else(random_hex);
+    if key.len() < MIN_API_KEY_LEN {
+        return Err(format!(
+            "API key must be at least {} characters",
+            MIN_API_KEY_LEN
+        ));
+    }
+    secrets::set(KEYCHAIN_ACCOUNT, &key)
+}
+
+#[tauri::command]
+pub fn get_auth_token() -> Result<String, Error> {
+    app_lock::ensure_unlocked()?;
+    Ok(token().to_string())
+}
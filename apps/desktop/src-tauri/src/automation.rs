@@ -0,0 +1,142 @@
+//! `claudepm://` URL commands, so Shortcuts, AppleScript (`open location`), Raycast
+//! or a shell `open` can drive the app from anywhere
+//!
+//! - `claudepm://tasks/new?project=<name or id>&title=<title>[&description=…][&state=…]`
+//! - `claudepm://tasks/new[?project=<id>]` without a title opens the New Task form
+//! - `claudepm://open?path=/sessions/<id>`
+//! - `claudepm://server/restart`
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Emitter, Url};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+use crate::notifications::{self, NotificationRequest};
+use crate::store::{self, NewTask};
+use crate::{app_menu, session_windows};
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AutomationResult {
+    url: String,
+    ok: bool,
+    message: String,
+}
+
+fn param<'a>(params: &'a BTreeMap<String, String>, name: &str) -> Result<&'a str, String> {
+    params
+        .get(name)
+        .map(String::as_str)
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| format!("Missing \"{}\" parameter", name))
+}
+
+fn create_task(params: &BTreeMap<String, String>) -> Result<String, String> {
+    let project = param(params, "project")?;
+    let title = param(params, "title")?;
+    let project = store::list_projects()?
+        .into_iter()
+        .find(|p| p.id == project || p.name.eq_ignore_ascii_case(project))
+        .ok_or_else(|| format!("Project not found: {}", project))?;
+
+    store::create_task(NewTask {
+        project_id: project.id,
+        title: title.to_string(),
+        description: params.get("description").cloned(),
+        state: params.get("state").cloned(),
+    })?;
+    Ok(format!("Created \"{}\" in {}", title, project.name))
+}
+
+fn execute(app: &AppHandle, url: &Url) -> Result<String, String> {
+    let params: BTreeMap<String, String> = url.query_pairs().into_owned().collect();
+    match (url.host_str(), url.path().trim_end_matches('/')) {
+        (Some("tasks"), "/new") if !params.contains_key("title") => {
+            app_menu::new_task(app, params.get("project").map(String::as_str));
+            Ok("Opened New Task".to_string())
+        }
+        (Some("tasks"), "/new") => create_task(&params),
+        (Some("open"), "") => {
+            let path = param(&params, "path")?;
+            session_windows::route_navigation(app, path);
+            Ok(format!("Opened {}", path))
+        }
+        (Some("server"), "/restart") => crate::restart_server()
+            .map(|_| "Restarted the server".to_string())
+            .map_err(|e| e.to_string()),
+        _ => Err(format!("Unknown command: {}", url)),
+    }
+}
+
+/// Commands that only bring something up on screen
+fn is_navigation(url: &Url) -> bool {
+    match (url.host_str(), url.path().trim_end_matches('/')) {
+        (Some("open"), _) => true,
+        (Some("tasks"), "/new") => !url.query_pairs().any(|(key, _)| key == "title"),
+        _ => false,
+    }
+}
+
+fn handle_url(app: &AppHandle, url: &Url) {
+    let result = execute(app, url);
+    if let Err(ref e) = result {
+        eprintln!("[Claude PM] Automation command failed: {}", e);
+    }
+
+    // Navigation is its own feedback; everything else gets a notification
+    if !is_navigation(url) || result.is_err() {
+        notifications::notify(
+            app,
+            NotificationRequest {
+                title: if result.is_ok() {
+                    "Claude PM".to_string()
+                } else {
+                    "Claude PM command failed".to_string()
+                },
+                body: result.clone().unwrap_or_else(|e| e),
+                category: Some("automation".to_string()),
+                ..Default::default()
+            },
+        );
+    }
+
+    let _ = app.emit(
+        "automation-command",
+        AutomationResult {
+            url: url.to_string(),
+            ok: result.is_ok(),
+            message: result.unwrap_or_else(|e| e),
+        },
+    );
+}
+
+/// Run a `claudepm://` URL from inside the app (dock menu, jump list)
+#[cfg(any(target_os = "macos", windows))]
+pub fn open_url(app: &AppHandle, url: &str) {
+    match Url::parse(url) {
+        Ok(url) => handle_url(app, &url),
+        Err(e) => eprintln!("[Claude PM] Invalid automation URL {}: {}", url, e),
+    }
+}
+
+/// Start handling `claudepm://` URLs, including the one the app was launched with
+pub fn init(app: &AppHandle) {
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            handle_url(&handle, &url);
+        }
+    });
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls {
+            handle_url(app, &url);
+        }
+    }
+
+    // macOS registers the scheme from Info.plist; elsewhere it's registered at runtime
+    #[cfg(any(windows, target_os = "linux"))]
+    if let Err(e) = app.deep_link().register_all() {
+        eprintln!("[Claude PM] Failed to register URL scheme: {}", e);
+    }
+}
@@ -0,0 +1,314 @@
+//! Whole-app backup and restore as a single zip
+//!
+//! Layout inside the archive:
+//! - `manifest.json`: format/app version and creation time
+//! - `config/…`: everything in the config directory
+//! - `data/…`: the data directory (database snapshot, attachments, event logs)
+//! - `claude/claude.json`, `claude/settings.json`: the user's Claude config
+//!
+//! Imports refuse archives from a newer format or with a database schema newer than this
+//! build's, and entries whose names would land outside the config and data directories.
+//!
+//! The vault key stays in the OS keychain, so vault.json is only readable on a machine
+//! that has the same key.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::error::Error;
+use crate::{config, json_file, search, store};
+
+/// Bump when the archive layout changes incompatibly
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+/// SQLite sidecar files, which the snapshot replaces, and the search index, which is
+/// rebuilt after a restore
+const SKIPPED_DATA_FILES: &[&str] = &[
+    "claudepm.db",
+    "claudepm.db-wal",
+    "claudepm.db-shm",
+    search::INDEX_DIR,
+];
+const DATABASE_ENTRY: &str = "data/claudepm.db";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub manifest: BackupManifest,
+    pub dry_run: bool,
+    /// Destination paths written (or that would be written)
+    pub files: Vec<PathBuf>,
+    /// Existing files that were (or would be) overwritten
+    pub overwritten: Vec<PathBuf>,
+}
+
+fn claude_files() -> Result<Vec<(&'static str, PathBuf)>, String> {
+    let home = dirs::home_dir().ok_or("Could not determine home directory")?;
+    Ok(vec![
+        ("claude/claude.json", home.join(".claude.json")),
+        (
+            "claude/settings.json",
+            home.join(".claude").join("settings.json"),
+        ),
+    ])
+}
+
+fn add_file(zip: &mut ZipWriter<File>, name: &str, path: &Path) -> Result<(), String> {
+    let mut contents = Vec::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_end(&mut contents))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    zip.start_file(name, SimpleFileOptions::default())
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&contents).map_err(|e| e.to_string())
+}
+
+fn add_dir(
+    zip: &mut ZipWriter<File>,
+    prefix: &str,
+    dir: &Path,
+    skip: &[&str],
+) -> Result<(), String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if skip.contains(&name.as_str()) {
+            continue;
+        }
+        let archived = format!("{}/{}", prefix, name);
+        if path.is_dir() {
+            add_dir(zip, &archived, &path, &[])?;
+        } else {
+            add_file(zip, &archived, &path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Where an archive entry restores to, or `None` for entries we don't recognise
+///
+/// `name` is the entry's `enclosed_name`; past that, only plain components are taken, so
+/// neither `..`, a drive or a backslash-separated name can climb out of the directory.
+fn destination(name: &Path) -> Result<Option<PathBuf>, String> {
+    let plain = name.components().all(|component| match component {
+        Component::Normal(part) => !part.to_string_lossy().contains(['\\', ':']),
+        _ => false,
+    });
+    if !plain {
+        return Ok(None);
+    }
+    let under = |prefix: &str| {
+        name.strip_prefix(prefix)
+            .ok()
+            .filter(|rest| !rest.as_os_str().is_empty())
+    };
+    if let Some(rest) = under("config") {
+        return Ok(config::config_dir().map(|dir| dir.join(rest)));
+    }
+    if let Some(rest) = under("data") {
+        return Ok(config::data_dir().map(|dir| dir.join(rest)));
+    }
+    Ok(claude_files()?
+        .into_iter()
+        .find(|(archived, _)| Path::new(archived) == name)
+        .map(|(_, path)| path))
+}
+
+/// Schema version of the database in the backup, if it has one
+fn backup_schema_version(zip: &mut ZipArchive<File>) -> Result<Option<usize>, String> {
+    let Ok(mut entry) = zip.by_name(DATABASE_ENTRY) else {
+        return Ok(None);
+    };
+    let copy = std::env::temp_dir().join(format!("claudepm-import-{}.db", store::new_id()));
+    let version = File::create(&copy)
+        .and_then(|mut file| std::io::copy(&mut entry, &mut file))
+        .map_err(|e| format!("Failed to read the database from the backup: {}", e))
+        .and_then(|_| store::open(&copy))
+        .and_then(|conn| {
+            conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+                .map_err(|e| format!("The database in the backup is unreadable: {}", e))
+        });
+    let _ = fs::remove_file(&copy);
+    version.map(Some)
+}
+
+/// Write a backup archive to `path`
+#[tauri::command]
+pub fn export_backup(path: String) -> Result<BackupManifest, Error> {
+    let file = File::create(&path).map_err(|e| format!("Failed to create {}: {}", path, e))?;
+    let mut zip = ZipWriter::new(file);
+
+    let manifest = BackupManifest {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default(),
+    };
+    zip.start_file(MANIFEST, SimpleFileOptions::default())
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+
+    let config_dir = config::config_dir();
+    if let Some(ref dir) = config_dir {
+        add_dir(&mut zip, "config", dir, SKIPPED_DATA_FILES)?;
+    }
+    // On macOS both resolve to Application Support; don't archive it twice
+    if let Some(dir) = config::data_dir().filter(|dir| Some(dir) != config_dir.as_ref()) {
+        add_dir(&mut zip, "data", &dir, SKIPPED_DATA_FILES)?;
+    }
+
+    let snapshot = std::env::temp_dir().join(format!("claudepm-backup-{}.db", manifest.created_at));
+    let _ = fs::remove_file(&snapshot);
+    store::snapshot_to(&snapshot)?;
+    let added = add_file(&mut zip, DATABASE_ENTRY, &snapshot);
+    let _ = fs::remove_file(&snapshot);
+    added?;
+
+    for (name, path) in claude_files()? {
+        if path.exists() {
+            add_file(&mut zip, name, &path)?;
+        }
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish backup: {}", e))?;
+    Ok(manifest)
+}
+
+/// Restore a backup; with `dry_run` only report what would change
+#[tauri::command]
+pub fn import_backup(path: String, dry_run: bool) -> Result<ImportReport, Error> {
+    let file = File::open(&path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Not a valid backup: {}", e))?;
+
+    let manifest: BackupManifest = {
+        let entry = zip
+            .by_name(MANIFEST)
+            .map_err(|_| "Backup is missing its manifest".to_string())?;
+        serde_json::from_reader(entry).map_err(|e| format!("Invalid backup manifest: {}", e))?
+    };
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(Error::InvalidInput(format!(
+            "Backup was made by a newer version of Claude PM ({}); update before importing",
+            manifest.app_version
+        )));
+    }
+    if let Some(version) = backup_schema_version(&mut zip)? {
+        if version > store::latest_schema_version() {
+            return Err(Error::Unsupported(format!(
+                "Backup was made by a newer version of Claude PM ({}) with database schema v{}; update before importing",
+                manifest.app_version, version
+            )));
+        }
+    }
+
+    let mut report = ImportReport {
+        manifest,
+        dry_run,
+        files: Vec::new(),
+        overwritten: Vec::new(),
+    };
+    // Held until the files are in place, so nothing reopens the old database meanwhile
+    let closed = if dry_run { None } else { Some(store::close()?) };
+
+    let claude_targets: Vec<PathBuf> = claude_files()?.into_iter().map(|(_, p)| p).collect();
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index).map_err(|e| e.to_string())?;
+        if entry.is_dir() || entry.name() == MANIFEST {
+            continue;
+        }
+        let Some(name) = entry.enclosed_name() else {
+            eprintln!(
+                "[Claude PM] Skipped unsafe path in backup: {}",
+                entry.name()
+            );
+            continue;
+        };
+        let Some(target) = destination(&name)? else {
+            continue;
+        };
+        if target.exists() {
+            report.overwritten.push(target.clone());
+        }
+        report.files.push(target.clone());
+        if dry_run {
+            continue;
+        }
+
+        let mut contents = Vec::new();
+        entry
+            .read_to_end(&mut contents)
+            .map_err(|e| format!("Failed to read {} from backup: {}", entry.name(), e))?;
+
+        if claude_targets.contains(&target) {
+            // Claude's files go through json_file so the current versions are backed up
+            let value = serde_json::from_slice(&contents)
+                .map_err(|e| format!("{} in backup is not valid JSON: {}", entry.name(), e))?;
+            json_file::write(&target, "claude-import", &value)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&target, contents)
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+    }
+
+    if !dry_run {
+        if let Some(db) = store::db_path() {
+            for sidecar in ["db-wal", "db-shm"] {
+                let _ = fs::remove_file(db.with_extension(sidecar));
+            }
+        }
+        drop(closed);
+        search::rebuild()?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restores_inside_the_app_directories_only() {
+        let config_dir = config::config_dir().unwrap();
+        assert_eq!(
+            destination(Path::new("config/vault.json")).unwrap(),
+            Some(config_dir.join("vault.json"))
+        );
+        assert!(destination(Path::new("claude/claude.json"))
+            .unwrap()
+            .is_some());
+        for name in [
+            "config/../../.ssh/authorized_keys",
+            "..\\..\\x",
+            "data/..\\..\\x",
+            "data/C:\\Windows\\x",
+            "/etc/passwd",
+            "config",
+            "other/file",
+        ] {
+            assert_eq!(destination(Path::new(name)).unwrap(), None, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,3 @@
+fn main() -> std::process::ExitCode {
+    claude_pm_desktop_lib::cli::main()
+}
@@ -0,0 +1,186 @@
+//! Installing the server's dependencies on a fresh clone
+//!
+//! `start_server` refuses to run without node_modules. On launch (or via
+//! `install_server_dependencies`) the server directory's package manager is run in the
+//! background, its output streamed as events; when it succeeds the server is started.
+//!
+//! Events:
+//! - `server-install` — one per output line, then a final `done`/`failed`/`cancelled`
+
+use serde::Serialize;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::process::{self, kill_tree, new_process_group, CancelToken};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Lockfile → package manager, checked in order; npm otherwise
+const PACKAGE_MANAGERS: &[(&str, &str)] = &[
+    ("pnpm-lock.yaml", "pnpm"),
+    ("yarn.lock", "yarn"),
+    ("bun.lockb", "bun"),
+];
+
+/// Cancellation for the install in progress, if any
+static RUNNING: Mutex<Option<CancelToken>> = Mutex::new(None);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InstallEvent {
+    /// `output`, `done`, `failed` or `cancelled`
+    stage: &'static str,
+    line: Option<String>,
+}
+
+pub fn needs_install(server_path: &Path) -> bool {
+    server_path.join("package.json").exists() && !server_path.join("node_modules").exists()
+}
+
+/// The package manager matching the lockfile, found on PATH or next to npm
+fn package_manager(server_path: &Path, npm_path: &Path) -> PathBuf {
+    PACKAGE_MANAGERS
+        .iter()
+        .filter(|(lockfile, _)| server_path.join(lockfile).exists())
+        .find_map(|(_, program)| {
+            process::which(program).or_else(|| {
+                npm_path
+                    .parent()
+                    .map(|dir| dir.join(program))
+                    .filter(|path| path.exists())
+            })
+        })
+        .unwrap_or_else(|| npm_path.to_path_buf())
+}
+
+fn emit(app: &AppHandle, stage: &'static str, line: Option<String>) {
+    let _ = app.emit("server-install", InstallEvent { stage, line });
+}
+
+fn stream(reader: impl Read + Send + 'static, on_line: impl Fn(String) + Send + 'static) {
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            on_line(line);
+        }
+    });
+}
+
+pub fn is_installing() -> bool {
+    RUNNING.lock().is_ok_and(|running| running.is_some())
+}
+
+/// Run the package manager's install in the server directory, passing each output line to `on_line`
+pub fn run_package_install(
+    cancel: &CancelToken,
+    on_line: impl Fn(String) + Clone + Send + 'static,
+) -> Result<(), Error> {
+    let npm_path = crate::find_npm().ok_or(Error::NpmNotFound)?;
+    let server_path = crate::get_server_path().ok_or(Error::ServerPathMissing)?;
+    let program = package_manager(&server_path, &npm_path);
+    println!(
+        "[Claude PM] Installing server dependencies with {:?}",
+        program
+    );
+
+    let mut cmd = Command::new(&program);
+    cmd.arg("install")
+        .current_dir(&server_path)
+        .env("PATH", crate::node_path_env(&npm_path))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    new_process_group(&mut cmd);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program.display(), e))?;
+    if let Some(stdout) = child.stdout.take() {
+        stream(stdout, on_line.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        stream(stderr, on_line);
+    }
+
+    // No timeout: a cold install over a slow connection can legitimately take minutes
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break status;
+        }
+        if cancel.is_cancelled() {
+            kill_tree(&mut child);
+            let _ = child.wait();
+            return Err(Error::Cancelled(
+                "Dependency install was cancelled".to_string(),
+            ));
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    if !status.success() {
+        return Err(format!("{} install failed ({})", program.display(), status).into());
+    }
+    Ok(())
+}
+
+fn run_install(app: &AppHandle, cancel: &CancelToken) -> Result<(), Error> {
+    let app = app.clone();
+    run_package_install(cancel, move |line| emit(&app, "output", Some(line)))?;
+    crate::start_server()
+}
+
+/// Start installing in the background; errors if an install is already running
+pub fn install(app: AppHandle) -> Result<(), Error> {
+    let cancel = CancelToken::default();
+    {
+        let mut running = RUNNING.lock().map_err(|e| e.to_string())?;
+        if running.is_some() {
+            return Err(Error::InvalidInput(
+                "Dependencies are already being installed".to_string(),
+            ));
+        }
+        *running = Some(cancel.clone());
+    }
+
+    thread::spawn(move || {
+        let result = run_install(&app, &cancel);
+        if let Ok(mut running) = RUNNING.lock() {
+            *running = None;
+        }
+        match result {
+            Ok(()) => emit(&app, "done", None),
+            Err(Error::Cancelled(_)) => emit(&app, "cancelled", None),
+            Err(e) => {
+                eprintln!("[Claude PM] {}", e);
+                emit(&app, "failed", Some(e.to_string()));
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Kick off an install at launch if the server directory has never been installed
+pub fn install_if_needed(app: AppHandle) {
+    if crate::get_server_path().is_some_and(|path| needs_install(&path)) {
+        let _ = install(app);
+    }
+}
+
+#[tauri::command]
+pub fn install_server_dependencies(app: AppHandle) -> Result<(), Error> {
+    install(app)
+}
+
+#[tauri::command]
+pub fn cancel_server_install() -> Result<(), Error> {
+    RUNNING
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_ref()
+        .ok_or_else(|| Error::NotFound("No dependency install is running".to_string()))?
+        .cancel();
+    Ok(())
+}
@@ -0,0 +1,294 @@
+//! Exporting task due dates and milestones to Calendar events or Reminders (macOS)
+//!
+//! `calendar_items` maps each source (a task id, or e.g. `milestone:<id>`) to the
+//! Calendar event uid / Reminder id we created, so re-exporting updates the same item
+//! and removing a deadline deletes it.
+
+use chrono::{Datelike, Local, TimeZone, Timelike};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::ics::{self, FeedDeadline};
+use crate::{applescript, store};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeadlineKind {
+    Event,
+    Reminder,
+}
+
+impl DeadlineKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Event => "event",
+            Self::Reminder => "reminder",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deadline {
+    /// Task id, or another stable id such as `milestone:<id>`
+    pub source_id: String,
+    pub title: String,
+    #[serde(default)]
+    pub notes: String,
+    /// Unix millis
+    pub due_at: i64,
+    pub kind: DeadlineKind,
+    /// Calendar or Reminders list name; the default one when unset
+    #[serde(default)]
+    pub container: Option<String>,
+    /// Project for the .ics feed; looked up from the task when unset
+    #[serde(default)]
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedDeadline {
+    pub source_id: String,
+    pub kind: DeadlineKind,
+    pub external_id: String,
+    pub container: Option<String>,
+    pub title: String,
+    pub due_at: i64,
+}
+
+/// Shared prologue: builds `d` from argv items 1-5 (year, month, day, hours, minutes)
+const DATE_FROM_ARGV: &str = r#"
+    set d to current date
+    set day of d to 1
+    set year of d to (item 1 of argv) as integer
+    set month of d to (item 2 of argv) as integer
+    set day of d to (item 3 of argv) as integer
+    set hours of d to (item 4 of argv) as integer
+    set minutes of d to (item 5 of argv) as integer
+    set seconds of d to 0
+"#;
+
+/// argv: date parts, container, title, notes, existing uid (may be empty)
+const UPSERT_EVENT: &str = r#"
+    tell application "Calendar"
+        if (item 6 of argv) is "" then
+            set cal to first calendar whose writable is true
+        else
+            set cal to calendar (item 6 of argv)
+        end if
+        set matches to {}
+        if (item 9 of argv) is not "" then
+            set matches to (every event of cal whose uid is (item 9 of argv))
+        end if
+        if (count of matches) > 0 then
+            set ev to item 1 of matches
+            set summary of ev to (item 7 of argv)
+            set description of ev to (item 8 of argv)
+            set start date of ev to d
+            set end date of ev to d + 30 * minutes
+        else
+            set ev to make new event at end of events of cal with properties {summary:(item 7 of argv), description:(item 8 of argv), start date:d, end date:(d + 30 * minutes)}
+        end if
+        return uid of ev
+    end tell
+"#;
+
+const UPSERT_REMINDER: &str = r#"
+    tell application "Reminders"
+        if (item 6 of argv) is "" then
+            set theList to default list
+        else
+            set theList to list (item 6 of argv)
+        end if
+        set matches to {}
+        if (item 9 of argv) is not "" then
+            set matches to (every reminder whose id is (item 9 of argv))
+        end if
+        if (count of matches) > 0 then
+            set r to item 1 of matches
+            set name of r to (item 7 of argv)
+            set body of r to (item 8 of argv)
+            set due date of r to d
+        else
+            set r to make new reminder at end of reminders of theList with properties {name:(item 7 of argv), body:(item 8 of argv), due date:d}
+        end if
+        return id of r
+    end tell
+"#;
+
+/// argv: uid
+const DELETE_EVENT: &str = r#"
+on run argv
+    tell application "Calendar"
+        repeat with cal in calendars
+            delete (every event of cal whose uid is (item 1 of argv))
+        end repeat
+    end tell
+end run
+"#;
+
+const DELETE_REMINDER: &str = r#"
+on run argv
+    tell application "Reminders"
+        delete (every reminder whose id is (item 1 of argv))
+    end tell
+end run
+"#;
+
+fn upsert(deadline: &Deadline, existing: Option<&str>) -> Result<String, Error> {
+    if !cfg!(target_os = "macos") {
+        return Err(Error::Unsupported(
+            "Calendar and Reminders export is only available on macOS".to_string(),
+        ));
+    }
+    let due = Local
+        .timestamp_millis_opt(deadline.due_at)
+        .single()
+        .ok_or("Invalid due date")?;
+    let body = match deadline.kind {
+        DeadlineKind::Event => UPSERT_EVENT,
+        DeadlineKind::Reminder => UPSERT_REMINDER,
+    };
+    let script = format!("on run argv\n{}\n{}\nend run", DATE_FROM_ARGV, body);
+
+    let parts = [
+        due.year().to_string(),
+        due.month().to_string(),
+        due.day().to_string(),
+        due.hour().to_string(),
+        due.minute().to_string(),
+    ];
+    let mut args: Vec<&str> = parts.iter().map(String::as_str).collect();
+    args.extend([
+        deadline.container.as_deref().unwrap_or(""),
+        deadline.title.as_str(),
+        deadline.notes.as_str(),
+        existing.unwrap_or(""),
+    ]);
+    applescript::run_with_args(&script, &args)
+}
+
+fn mapped_id(source_id: &str, kind: DeadlineKind) -> Result<Option<String>, String> {
+    store::with_conn(|conn| {
+        conn.query_row(
+            "SELECT external_id FROM calendar_items WHERE source_id = ?1 AND kind = ?2",
+            params![source_id, kind.as_str()],
+            |row| row.get(0),
+        )
+        .optional()
+    })
+}
+
+/// Create or update the Calendar event / Reminder for a deadline
+#[tauri::command]
+pub fn export_deadline(deadline: Deadline) -> Result<ExportedDeadline, Error> {
+    let existing = mapped_id(&deadline.source_id, deadline.kind)?;
+    let external_id = upsert(&deadline, existing.as_deref())?;
+
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO calendar_items (source_id, kind, external_id, container, title, due_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                deadline.source_id,
+                deadline.kind.as_str(),
+                external_id,
+                deadline.container,
+                deadline.title,
+                deadline.due_at
+            ],
+        )
+        .map(|_| ())
+    })?;
+    ics::record(&FeedDeadline {
+        source_id: deadline.source_id.clone(),
+        project_id: deadline.project_id.clone(),
+        title: deadline.title.clone(),
+        notes: deadline.notes.clone(),
+        due_at: deadline.due_at,
+    })?;
+
+    Ok(ExportedDeadline {
+        source_id: deadline.source_id,
+        kind: deadline.kind,
+        external_id,
+        container: deadline.container,
+        title: deadline.title,
+        due_at: deadline.due_at,
+    })
+}
+
+/// Export a task's due date, using the task's title and description
+#[tauri::command]
+pub fn export_task_due_date(
+    task_id: String,
+    due_at: i64,
+    kind: DeadlineKind,
+    container: Option<String>,
+) -> Result<ExportedDeadline, Error> {
+    let task = store::with_conn(|conn| store::get_task(conn, &task_id))?
+        .ok_or_else(|| Error::NotFound(format!("Task not found: {}", task_id)))?;
+    export_deadline(Deadline {
+        source_id: task.id,
+        title: task.title,
+        notes: task.description.unwrap_or_default(),
+        due_at,
+        kind,
+        container,
+        project_id: Some(task.project_id),
+    })
+}
+
+/// Delete the exported item and forget the mapping (and the deadline, once nothing else exports it)
+#[tauri::command]
+pub fn remove_deadline(source_id: String, kind: DeadlineKind) -> Result<(), Error> {
+    let Some(external_id) = mapped_id(&source_id, kind)? else {
+        return Ok(());
+    };
+    let script = match kind {
+        DeadlineKind::Event => DELETE_EVENT,
+        DeadlineKind::Reminder => DELETE_REMINDER,
+    };
+    applescript::run_with_args(script, &[&external_id])?;
+
+    store::with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM calendar_items WHERE source_id = ?1 AND kind = ?2",
+            params![source_id, kind.as_str()],
+        )?;
+        // Keep it in the feed while it's still exported as the other kind
+        conn.execute(
+            "DELETE FROM deadlines WHERE source_id = ?1 AND NOT EXISTS (SELECT 1 FROM calendar_items WHERE source_id = ?1)",
+            [&source_id],
+        )
+        .map(|_| ())
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn list_exported_deadlines() -> Result<Vec<ExportedDeadline>, Error> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT source_id, kind, external_id, container, title, due_at FROM calendar_items ORDER BY due_at",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let kind: String = row.get(1)?;
+            Ok(ExportedDeadline {
+                source_id: row.get(0)?,
+                kind: if kind == "reminder" {
+                    DeadlineKind::Reminder
+                } else {
+                    DeadlineKind::Event
+                },
+                external_id: row.get(2)?,
+                container: row.get(3)?,
+                title: row.get(4)?,
+                due_at: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
@@ -0,0 +1,263 @@
+//! CI status of the pull requests linked to tasks
+//!
+//! Every couple of minutes (while online and connected to GitHub) the check runs and
+//! commit statuses on each open linked PR's head commit are fetched and rolled up into
+//! one state: `failure` if anything failed, `pending` while anything is still running,
+//! `success` once everything passed, `none` when nothing reports. Results are cached for
+//! the UI. A PR turning red or all green raises a notification, optionally only for PRs
+//! the user opened; states seen on the first poll after launch don't notify.
+//!
+//! Events:
+//! - `pr-ci-status` with the [`PrCiStatus`] whenever a PR's state or head commit changes
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::github::{self, CheckStatus};
+use crate::notifications::{self, NotificationRequest};
+use crate::{config, connectivity, power, store};
+
+const DEFAULT_INTERVAL_SECS: u64 = 120;
+const MIN_INTERVAL_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CiWatchSettings {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Seconds between polls (120 when unset)
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    /// Only notify about PRs the connected GitHub account opened
+    #[serde(default)]
+    pub only_mine: bool,
+}
+
+impl Default for CiWatchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: None,
+            only_mine: false,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CiState {
+    None,
+    Pending,
+    Success,
+    Failure,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrCiStatus {
+    pub task_id: String,
+    pub repo: String,
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    pub author: Option<String>,
+    /// Opened by the connected GitHub account
+    pub mine: bool,
+    pub head_sha: String,
+    pub state: CiState,
+    pub checks: Vec<CheckStatus>,
+    pub checked_at: i64,
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static STARTED: AtomicBool = AtomicBool::new(false);
+static CACHE: Mutex<BTreeMap<String, PrCiStatus>> = Mutex::new(BTreeMap::new());
+static VIEWER: Mutex<Option<String>> = Mutex::new(None);
+/// Set while a poll runs, so "refresh now" and the timer don't overlap
+static POLLING: AtomicBool = AtomicBool::new(false);
+
+fn rollup(checks: &[CheckStatus]) -> CiState {
+    let any = |state: &str| checks.iter().any(|check| check.state == state);
+    if any("failure") {
+        CiState::Failure
+    } else if any("pending") {
+        CiState::Pending
+    } else if checks.is_empty() {
+        CiState::None
+    } else {
+        CiState::Success
+    }
+}
+
+fn key(repo: &str, number: u64) -> String {
+    format!("{}#{}", repo, number)
+}
+
+fn notify(app: &AppHandle, status: &PrCiStatus) {
+    let body = match status.state {
+        CiState::Failure => {
+            let failed: Vec<&str> = status
+                .checks
+                .iter()
+                .filter(|check| check.state == "failure")
+                .map(|check| check.name.as_str())
+                .collect();
+            format!(
+                "Checks failed on {}#{}: {}",
+                status.repo,
+                status.number,
+                failed.join(", ")
+            )
+        }
+        CiState::Success => format!("All checks passed on {}#{}", status.repo, status.number),
+        _ => return,
+    };
+    notifications::notify(
+        app,
+        NotificationRequest {
+            title: status.title.clone(),
+            body,
+            key: Some(format!("ci:{}", key(&status.repo, status.number))),
+            category: Some("ci".to_string()),
+            ..Default::default()
+        },
+    );
+}
+
+async fn viewer() -> Option<String> {
+    if let Some(login) = VIEWER.lock().ok().and_then(|viewer| viewer.clone()) {
+        return Some(login);
+    }
+    let login = github::viewer_login().await.ok()?;
+    if let Ok(mut viewer) = VIEWER.lock() {
+        *viewer = Some(login.clone());
+    }
+    Some(login)
+}
+
+/// Fetch every open linked PR, updating the cache and reporting changes
+async fn poll(app: Option<&AppHandle>) -> Result<Vec<PrCiStatus>, String> {
+    let links = github::linked_prs()?;
+    let settings = config::load().ci_watch;
+    let viewer = viewer().await;
+    let mut results = Vec::new();
+    let mut seen = Vec::new();
+    for link in links {
+        let key = key(&link.repo, link.number);
+        seen.push(key.clone());
+        let checks = match github::fetch_pr_checks(&link.repo, link.number).await {
+            Ok(checks) => checks,
+            Err(e) => {
+                eprintln!("[Claude PM] CI status for {}: {}", key, e);
+                continue;
+            }
+        };
+        if checks.item.state != "open" {
+            if let Ok(mut cache) = CACHE.lock() {
+                cache.remove(&key);
+            }
+            continue;
+        }
+        let status = PrCiStatus {
+            task_id: link.task_id,
+            repo: link.repo,
+            number: link.number,
+            title: checks.item.title,
+            url: checks.item.url,
+            mine: viewer.is_some() && checks.author == viewer,
+            author: checks.author,
+            head_sha: checks.head_sha,
+            state: rollup(&checks.checks),
+            checks: checks.checks,
+            checked_at: store::now_ms(),
+        };
+        let previous = CACHE
+            .lock()
+            .map_err(|e| e.to_string())?
+            .insert(key, status.clone());
+        let changed = previous.as_ref().is_none_or(|previous| {
+            previous.state != status.state || previous.head_sha != status.head_sha
+        });
+        if let (Some(app), true) = (app, changed) {
+            let _ = app.emit("pr-ci-status", &status);
+            let transitioned = previous.is_some_and(|previous| previous.state != status.state);
+            if transitioned && (!settings.only_mine || status.mine) {
+                notify(app, &status);
+            }
+        }
+        results.push(status);
+    }
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.retain(|key, _| seen.contains(key));
+    }
+    Ok(results)
+}
+
+fn interval() -> Duration {
+    let secs = config::load()
+        .ci_watch
+        .interval_secs
+        .unwrap_or(DEFAULT_INTERVAL_SECS)
+        .max(MIN_INTERVAL_SECS);
+    Duration::from_secs(secs)
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let _ = APP.set(app.clone());
+    thread::spawn(move || loop {
+        let due =
+            config::load().ci_watch.enabled && connectivity::is_online() && github::is_connected();
+        if due && !POLLING.swap(true, Ordering::SeqCst) {
+            if let Err(e) = tauri::async_runtime::block_on(poll(Some(&app))) {
+                eprintln!("[Claude PM] CI status poll failed: {}", e);
+            }
+            POLLING.store(false, Ordering::SeqCst);
+        }
+        power::wait(interval());
+    });
+}
+
+/// Cached CI states, optionally only for one task's PRs
+#[tauri::command]
+pub fn list_pr_ci_statuses(task_id: Option<String>) -> Result<Vec<PrCiStatus>, Error> {
+    let cache = CACHE.lock().map_err(|e| e.to_string())?;
+    Ok(cache
+        .values()
+        .filter(|status| task_id.as_ref().is_none_or(|id| &status.task_id == id))
+        .cloned()
+        .collect())
+}
+
+/// Poll now instead of waiting for the next interval; while a poll is already running
+/// this returns the cached states
+#[tauri::command]
+pub async fn refresh_pr_ci_statuses() -> Result<Vec<PrCiStatus>, Error> {
+    if !github::is_connected() {
+        return Err(Error::InvalidInput("GitHub is not connected".to_string()));
+    }
+    if POLLING.swap(true, Ordering::SeqCst) {
+        return list_pr_ci_statuses(None);
+    }
+    let result = poll(APP.get()).await;
+    POLLING.store(false, Ordering::SeqCst);
+    result.map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn set_ci_watch_settings(settings: CiWatchSettings) -> Result<CiWatchSettings, Error> {
+    let config = config::update(|c| c.ci_watch = settings)?;
+    Ok(config.ci_watch)
+}
@@ -0,0 +1,533 @@
+//! Project memory files: CLAUDE.md and `.claude/` rules
+//!
+//! Covers `CLAUDE.md`, `CLAUDE.local.md`, `.claude/CLAUDE.md` and `.claude/rules/*.md`
+//! in a project's repository. Files can be created from a template, diffed against an
+//! edit before saving, and linted for common problems: files too long to load whole,
+//! instructions that contradict each other, duplicates, unclosed code fences and
+//! `@imports` of missing files. Every write first copies the previous version to
+//! `<config_dir>/backups/claude-md/<project>/`.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+use crate::{json_file, store};
+
+pub const ROOT_FILES: &[&str] = &["CLAUDE.md", "CLAUDE.local.md", ".claude/CLAUDE.md"];
+const RULES_DIR: &str = ".claude/rules";
+const MAX_BACKUPS: usize = 20;
+/// Claude Code warns when a memory file is larger than this
+const MAX_CHARS: usize = 40_000;
+const MAX_LINES: usize = 500;
+/// Diffs of larger files fall back to replacing everything
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// Built-in templates: id, name, contents (`{{project}}` is replaced by the project name)
+const TEMPLATES: &[(&str, &str, &str)] = &[
+    (
+        "general",
+        "General",
+        "# {{project}}\n\n## Overview\n\nWhat this project does and who uses it.\n\n## Commands\n\n- Build:\n- Test:\n- Lint:\n\n## Conventions\n\n- \n\n## Don'ts\n\n- \n",
+    ),
+    (
+        "node",
+        "Node / TypeScript",
+        "# {{project}}\n\n## Commands\n\n- Install: `npm install`\n- Dev server: `npm run dev`\n- Test: `npm test`\n- Type check: `npx tsc --noEmit`\n\n## Conventions\n\n- TypeScript strict mode; avoid `any`\n- Keep modules small and colocate tests as `*.test.ts`\n\n## Before finishing\n\n- Run the tests and the type check\n",
+    ),
+    (
+        "rust",
+        "Rust",
+        "# {{project}}\n\n## Commands\n\n- Build: `cargo build`\n- Test: `cargo test`\n- Lint: `cargo clippy --all-targets -- -D warnings`\n- Format: `cargo fmt`\n\n## Conventions\n\n- Return `Result` with the crate's error type; no `unwrap()` outside tests\n\n## Before finishing\n\n- Run clippy and the tests\n",
+    ),
+    (
+        "python",
+        "Python",
+        "# {{project}}\n\n## Commands\n\n- Install: `pip install -e .[dev]`\n- Test: `pytest`\n- Lint: `ruff check .`\n- Format: `ruff format .`\n\n## Conventions\n\n- Type hints on public functions\n\n## Before finishing\n\n- Run the linter and the tests\n",
+    ),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryFile {
+    /// Relative to the repository, e.g. `.claude/rules/testing.md`
+    pub path: String,
+    pub exists: bool,
+    pub chars: usize,
+    pub lines: usize,
+    pub modified: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub contents: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiffKind {
+    Same,
+    Added,
+    Removed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintIssue {
+    pub severity: Severity,
+    /// 1-based; none for issues about the whole file
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveResult {
+    pub path: String,
+    pub backup: Option<String>,
+    pub issues: Vec<LintIssue>,
+}
+
+fn repo_path(project_id: &str) -> Result<PathBuf, Error> {
+    store::list_projects()?
+        .into_iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project_id)))?
+        .repo_path
+        .map(PathBuf::from)
+        .ok_or_else(|| Error::InvalidInput("The project has no repository path".to_string()))
+}
+
+/// Resolve a memory file's relative path, refusing anything that isn't one
+fn resolve(repo: &Path, relative: &str) -> Result<PathBuf, Error> {
+    let path = Path::new(relative);
+    let plain = path.components().all(|c| matches!(c, Component::Normal(_)));
+    let in_rules = path.parent() == Some(Path::new(RULES_DIR))
+        && path.extension().is_some_and(|ext| ext == "md");
+    if !plain || !(ROOT_FILES.contains(&relative) || in_rules) {
+        return Err(Error::InvalidInput(format!(
+            "{} is not a CLAUDE.md or .claude/rules file",
+            relative
+        )));
+    }
+    Ok(repo.join(path))
+}
+
+fn describe(repo: &Path, relative: String) -> MemoryFile {
+    let full = repo.join(&relative);
+    let contents = fs::read_to_string(&full).ok();
+    let modified = fs::metadata(&full)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64);
+    MemoryFile {
+        path: relative,
+        exists: contents.is_some(),
+        chars: contents.as_ref().map_or(0, |c| c.chars().count()),
+        lines: contents.as_ref().map_or(0, |c| c.lines().count()),
+        modified,
+    }
+}
+
+fn backup_dir(repo: &Path) -> Result<PathBuf, String> {
+    let slug: String = repo
+        .display()
+        .to_string()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    Ok(json_file::backup_dir("claude-md")?.join(slug.trim_matches('-')))
+}
+
+/// Copy the current file aside before it is replaced; returns the backup's path
+fn backup(repo: &Path, relative: &str, path: &Path) -> Result<Option<PathBuf>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let dir = backup_dir(repo)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let name = relative.replace('/', "__");
+    let target = dir.join(format!("{}.{}", millis, name));
+    fs::copy(path, &target).map_err(|e| format!("Failed to back up {}: {}", relative, e))?;
+
+    let suffix = format!(".{}", name);
+    let mut backups: Vec<PathBuf> = fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .is_some_and(|n| n.to_string_lossy().ends_with(&suffix))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    backups.sort();
+    while backups.len() > MAX_BACKUPS {
+        let _ = fs::remove_file(backups.remove(0));
+    }
+    Ok(Some(target))
+}
+
+fn write(repo: &Path, relative: &str, contents: &str) -> Result<Option<PathBuf>, Error> {
+    let path = resolve(repo, relative)?;
+    let backup = backup(repo, relative, &path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let tmp = path.with_extension("md.tmp");
+    fs::write(&tmp, contents).map_err(|e| format!("Failed to write {}: {}", relative, e))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("Failed to replace {}: {}", relative, e))?;
+    println!("[Claude PM] Saved {}", path.display());
+    Ok(backup)
+}
+
+/// Line diff by longest common subsequence
+pub fn diff_lines(before: &str, after: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+    let line = |kind, text: &str| DiffLine {
+        kind,
+        text: text.to_string(),
+    };
+    if a.len() * b.len() > MAX_DIFF_CELLS {
+        return a
+            .iter()
+            .map(|t| line(DiffKind::Removed, t))
+            .chain(b.iter().map(|t| line(DiffKind::Added, t)))
+            .collect();
+    }
+    // lcs[i][j]: common lines between a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push(line(DiffKind::Same, a[i]));
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            out.push(line(DiffKind::Added, b[j]));
+            j += 1;
+        } else {
+            out.push(line(DiffKind::Removed, a[i]));
+            i += 1;
+        }
+    }
+    out
+}
+
+/// The instruction a bullet or sentence gives, lowercased without list markers
+fn instruction(line: &str) -> String {
+    line.trim()
+        .trim_start_matches(['-', '*', '+', '>'])
+        .trim()
+        .trim_end_matches(['.', '!'])
+        .to_lowercase()
+}
+
+/// `always X` / `never X` style pairs: (polarity, subject)
+fn directive(text: &str) -> Option<(bool, String)> {
+    const POSITIVE: &[&str] = &["always ", "you must ", "must ", "do "];
+    const NEGATIVE: &[&str] = &["never ", "do not ", "don't ", "must not ", "avoid "];
+    let subject = |rest: &str| {
+        rest.split_whitespace()
+            .take(4)
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    if let Some(rest) = NEGATIVE.iter().find_map(|p| text.strip_prefix(p)) {
+        return Some((false, subject(rest)));
+    }
+    POSITIVE
+        .iter()
+        .find_map(|p| text.strip_prefix(p))
+        .map(|rest| (true, subject(rest)))
+}
+
+/// Check a memory file's contents; `file_dir` resolves `@imports`
+pub fn lint(contents: &str, file_dir: Option<&Path>) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let issue = |severity, line, message: String| LintIssue {
+        severity,
+        line,
+        message,
+    };
+    if contents.trim().is_empty() {
+        issues.push(issue(
+            Severity::Warning,
+            None,
+            "The file is empty".to_string(),
+        ));
+        return issues;
+    }
+    let chars = contents.chars().count();
+    if chars > MAX_CHARS {
+        issues.push(issue(
+            Severity::Error,
+            None,
+            format!(
+                "{} characters; Claude Code warns above {} and large memory files crowd out the conversation",
+                chars, MAX_CHARS
+            ),
+        ));
+    }
+    let line_count = contents.lines().count();
+    if line_count > MAX_LINES {
+        issues.push(issue(
+            Severity::Warning,
+            None,
+            format!(
+                "{} lines; consider moving topics into .claude/rules files",
+                line_count
+            ),
+        ));
+    }
+
+    let mut fence_open: Option<usize> = None;
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    let mut directives: BTreeMap<String, (bool, usize)> = BTreeMap::new();
+    let mut package_managers: BTreeMap<&str, usize> = BTreeMap::new();
+    for (index, raw) in contents.lines().enumerate() {
+        let number = index + 1;
+        if raw.trim_start().starts_with("```") {
+            fence_open = match fence_open {
+                Some(_) => None,
+                None => Some(number),
+            };
+            continue;
+        }
+        if fence_open.is_some() {
+            continue;
+        }
+        let text = instruction(raw);
+        if text.len() < 12 || raw.trim_start().starts_with('#') {
+            continue;
+        }
+        if let Some(first) = seen.get(&text) {
+            issues.push(issue(
+                Severity::Warning,
+                Some(number),
+                format!("Repeats line {}", first),
+            ));
+        } else {
+            seen.insert(text.clone(), number);
+        }
+        if let Some((positive, subject)) = directive(&text) {
+            match directives.get(&subject) {
+                Some((earlier, line)) if *earlier != positive => issues.push(issue(
+                    Severity::Error,
+                    Some(number),
+                    format!("Contradicts line {} about \"{}\"", line, subject),
+                )),
+                Some(_) => {}
+                None => {
+                    directives.insert(subject, (positive, number));
+                }
+            }
+        }
+        for manager in ["npm", "yarn", "pnpm", "bun"] {
+            let uses = text.starts_with(&format!("use {} ", manager))
+                || text == format!("use {}", manager)
+                || text.starts_with(&format!("always use {}", manager));
+            if uses {
+                package_managers.entry(manager).or_insert(number);
+            }
+        }
+        if let Some(dir) = file_dir {
+            for import in raw
+                .split_whitespace()
+                .filter_map(|w| w.strip_prefix('@'))
+                .filter(|w| w.contains('/') || w.ends_with(".md"))
+            {
+                let target = match import.strip_prefix("~/") {
+                    Some(home) => dirs::home_dir().map(|h| h.join(home)),
+                    None => Some(dir.join(import)),
+                };
+                if target.is_some_and(|t| !t.exists()) {
+                    issues.push(issue(
+                        Severity::Warning,
+                        Some(number),
+                        format!("Imported file @{} does not exist", import),
+                    ));
+                }
+            }
+        }
+    }
+    if let Some(line) = fence_open {
+        issues.push(issue(
+            Severity::Error,
+            Some(line),
+            "Code fence is never closed".to_string(),
+        ));
+    }
+    if package_managers.len() > 1 {
+        let names: Vec<String> = package_managers
+            .iter()
+            .map(|(name, line)| format!("{} (line {})", name, line))
+            .collect();
+        issues.push(issue(
+            Severity::Error,
+            None,
+            format!("Conflicting package managers: {}", names.join(", ")),
+        ));
+    }
+    issues
+}
+
+/// Memory files in the project, including the standard ones that don't exist yet
+#[tauri::command]
+pub fn list_claude_md_files(project_id: String) -> Result<Vec<MemoryFile>, Error> {
+    let repo = repo_path(&project_id)?;
+    let mut relative: Vec<String> = ROOT_FILES.iter().map(|f| f.to_string()).collect();
+    let mut rules: Vec<String> = fs::read_dir(repo.join(RULES_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+                .filter_map(|p| Some(format!("{}/{}", RULES_DIR, p.file_name()?.to_str()?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    rules.sort();
+    relative.extend(rules);
+    Ok(relative
+        .into_iter()
+        .map(|path| describe(&repo, path))
+        .collect())
+}
+
+#[tauri::command]
+pub fn read_claude_md(project_id: String, path: String) -> Result<String, Error> {
+    let full = resolve(&repo_path(&project_id)?, &path)?;
+    match fs::read_to_string(&full) {
+        Ok(contents) => Ok(contents),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(Error::NotFound(format!("{} does not exist", path)))
+        }
+        Err(e) => Err(Error::Internal(format!("Failed to read {}: {}", path, e))),
+    }
+}
+
+/// Contents of the built-in template `id`
+pub fn template(id: &str) -> Option<&'static str> {
+    TEMPLATES
+        .iter()
+        .find(|(template, ..)| *template == id)
+        .map(|(_, _, contents)| *contents)
+}
+
+#[tauri::command]
+pub fn list_claude_md_templates() -> Vec<Template> {
+    TEMPLATES
+        .iter()
+        .map(|(id, name, contents)| Template { id, name, contents })
+        .collect()
+}
+
+/// Create a memory file from a template; refuses to overwrite an existing file
+#[tauri::command]
+pub fn create_claude_md_from_template(
+    project_id: String,
+    template: String,
+    path: Option<String>,
+) -> Result<SaveResult, Error> {
+    let repo = repo_path(&project_id)?;
+    let path = path.unwrap_or_else(|| "CLAUDE.md".to_string());
+    if resolve(&repo, &path)?.exists() {
+        return Err(Error::InvalidInput(format!("{} already exists", path)));
+    }
+    let contents = self::template(&template)
+        .ok_or_else(|| Error::NotFound(format!("Template not found: {}", template)))?;
+    let name = store::list_projects()?
+        .into_iter()
+        .find(|p| p.id == project_id)
+        .map(|p| p.name)
+        .unwrap_or_default();
+    let contents = contents.replace("{{project}}", &name);
+    write(&repo, &path, &contents)?;
+    Ok(SaveResult {
+        issues: lint(&contents, resolve(&repo, &path)?.parent()),
+        path,
+        backup: None,
+    })
+}
+
+/// How saving `contents` would change the file
+#[tauri::command]
+pub fn diff_claude_md(
+    project_id: String,
+    path: String,
+    contents: String,
+) -> Result<Vec<DiffLine>, Error> {
+    let full = resolve(&repo_path(&project_id)?, &path)?;
+    let before = fs::read_to_string(full).unwrap_or_default();
+    Ok(diff_lines(&before, &contents))
+}
+
+/// Save a memory file, backing up the previous version; returns lint issues of the new contents
+#[tauri::command]
+pub fn update_claude_md(
+    project_id: String,
+    path: String,
+    contents: String,
+) -> Result<SaveResult, Error> {
+    let repo = repo_path(&project_id)?;
+    let backup = write(&repo, &path, &contents)?;
+    Ok(SaveResult {
+        issues: lint(&contents, resolve(&repo, &path)?.parent()),
+        path,
+        backup: backup.map(|p| p.display().to_string()),
+    })
+}
+
+/// Lint `contents`, or the file as saved when omitted
+#[tauri::command]
+pub fn lint_claude_md(
+    project_id: String,
+    path: String,
+    contents: Option<String>,
+) -> Result<Vec<LintIssue>, Error> {
+    let full = resolve(&repo_path(&project_id)?, &path)?;
+    let contents = match contents {
+        Some(contents) => contents,
+        None => fs::read_to_string(&full).map_err(|e| format!("Failed to read {}: {}", path, e))?,
+    };
+    Ok(lint(&contents, full.parent()))
+}
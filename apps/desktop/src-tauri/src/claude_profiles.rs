@@ -0,0 +1,247 @@
+//! Claude accounts and config sets (e.g. work and personal) agents run as
+//!
+//! A profile can have its own Claude config directory, passed to Claude Code as
+//! `CLAUDE_CONFIG_DIR`: its own sign-in, settings, MCP servers and transcripts. It can
+//! also carry an API key (kept in the keychain) and extra env vars. The active profile
+//! is what newly queued agent runs start with, and the config the settings and MCP
+//! editors work on; runs already queued keep theirs. The built-in `default` profile is
+//! the normal `~/.claude` setup.
+//!
+//! Runs record the profile they ran under, so usage and cost are tracked per profile
+//! from the run history (see `run_history`).
+//!
+//! Events:
+//! - `claude-profile-changed` with the active profile's name
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{config, run_history, search, secrets, usage};
+
+pub const DEFAULT_PROFILE: &str = "default";
+const KEYCHAIN_PREFIX: &str = "claude-profile:";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaudeProfile {
+    pub name: String,
+    /// Claude config directory for its agents; `~/.claude` when unset
+    #[serde(default)]
+    pub config_dir: Option<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+/// A profile's agent runs this month
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileUsage {
+    pub runs: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaudeProfileInfo {
+    #[serde(flatten)]
+    pub profile: ClaudeProfile,
+    pub has_api_key: bool,
+    /// Signs this profile's config directory in to Claude, for profiles that have one
+    pub sign_in_command: Option<String>,
+    pub usage: ProfileUsage,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClaudeProfileList {
+    pub profiles: Vec<ClaudeProfileInfo>,
+    pub active: String,
+}
+
+fn builtin() -> ClaudeProfile {
+    ClaudeProfile {
+        name: DEFAULT_PROFILE.to_string(),
+        config_dir: None,
+        env: BTreeMap::new(),
+    }
+}
+
+fn profiles() -> Vec<ClaudeProfile> {
+    let mut profiles = vec![builtin()];
+    profiles.extend(config::load().claude_profiles);
+    profiles
+}
+
+fn find(name: &str) -> Option<ClaudeProfile> {
+    profiles().into_iter().find(|p| p.name == name)
+}
+
+/// The profile new agent runs start with; `default` if the configured one is gone
+pub fn active_name() -> String {
+    config::load()
+        .active_claude_profile
+        .filter(|name| find(name).is_some())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+fn expand(dir: &str) -> PathBuf {
+    match dir.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(dir)),
+        None => PathBuf::from(dir),
+    }
+}
+
+fn config_dir_of(profile: &ClaudeProfile) -> Option<PathBuf> {
+    profile
+        .config_dir
+        .as_deref()
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+        .map(expand)
+}
+
+fn config_dir(name: &str) -> Option<PathBuf> {
+    find(name).as_ref().and_then(config_dir_of)
+}
+
+/// The active profile's config directory, if it has its own
+pub fn active_config_dir() -> Option<PathBuf> {
+    config_dir(&active_name())
+}
+
+/// Where Claude writes transcripts for agents run as `name`
+pub fn transcripts_dir(name: Option<&str>) -> Option<PathBuf> {
+    match name.and_then(config_dir) {
+        Some(dir) => Some(dir.join("projects")),
+        None => search::transcripts_dir(),
+    }
+}
+
+fn keychain_account(name: &str) -> String {
+    format!("{}{}", KEYCHAIN_PREFIX, name)
+}
+
+fn api_key(name: &str) -> Option<String> {
+    secrets::get(&keychain_account(name)).ok().flatten()
+}
+
+/// Whether the active profile signs agents in with an API key
+pub fn uses_api_key() -> bool {
+    api_key(&active_name()).is_some()
+}
+
+/// Env vars agents run as `name` are started with
+pub fn agent_env(name: &str) -> Vec<(String, String)> {
+    let Some(profile) = find(name) else {
+        return Vec::new();
+    };
+    let mut env: Vec<(String, String)> = profile.env.into_iter().collect();
+    if let Some(dir) = config_dir(name) {
+        env.push(("CLAUDE_CONFIG_DIR".to_string(), dir.display().to_string()));
+    }
+    if let Some(key) = api_key(name) {
+        env.push(("ANTHROPIC_API_KEY".to_string(), key));
+    }
+    env
+}
+
+fn info(profile: ClaudeProfile, usage: &BTreeMap<String, ProfileUsage>) -> ClaudeProfileInfo {
+    let sign_in_command = config_dir(&profile.name)
+        .map(|dir| format!("CLAUDE_CONFIG_DIR=\"{}\" claude /login", dir.display()));
+    ClaudeProfileInfo {
+        has_api_key: api_key(&profile.name).is_some(),
+        sign_in_command,
+        usage: usage.get(&profile.name).cloned().unwrap_or_default(),
+        profile,
+    }
+}
+
+#[tauri::command]
+pub fn list_claude_profiles() -> Result<ClaudeProfileList, Error> {
+    let usage = run_history::usage_by_profile(usage::month_start())?;
+    Ok(ClaudeProfileList {
+        profiles: profiles().into_iter().map(|p| info(p, &usage)).collect(),
+        active: active_name(),
+    })
+}
+
+/// Add or replace a profile; `api_key` sets its key, or removes it when empty
+#[tauri::command]
+pub fn save_claude_profile(
+    profile: ClaudeProfile,
+    api_key: Option<String>,
+) -> Result<ClaudeProfileList, Error> {
+    let name = profile.name.trim().to_string();
+    if name.is_empty() {
+        return Err(Error::InvalidInput("Profile name is required".to_string()));
+    }
+    if name == DEFAULT_PROFILE {
+        return Err(Error::InvalidInput(format!(
+            "\"{}\" is the built-in profile",
+            DEFAULT_PROFILE
+        )));
+    }
+    let profile = ClaudeProfile { name, ..profile };
+    // Claude creates the rest of the directory when the profile first signs in
+    if let Some(dir) = config_dir_of(&profile) {
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    match api_key.as_deref().map(str::trim) {
+        Some("") => secrets::delete(&keychain_account(&profile.name))?,
+        Some(key) => secrets::set(&keychain_account(&profile.name), key)?,
+        None => {}
+    }
+    config::update(|c| {
+        c.claude_profiles.retain(|p| p.name != profile.name);
+        c.claude_profiles.push(profile);
+    })?;
+    list_claude_profiles()
+}
+
+/// Remove a profile and its API key; its config directory is left alone
+#[tauri::command]
+pub fn delete_claude_profile(app: AppHandle, name: String) -> Result<ClaudeProfileList, Error> {
+    if name == DEFAULT_PROFILE {
+        return Err(Error::InvalidInput(
+            "The built-in profile can't be deleted".to_string(),
+        ));
+    }
+    if find(&name).is_none() {
+        return Err(Error::NotFound(format!("No Claude profile named {}", name)));
+    }
+    let was_active = active_name() == name;
+    secrets::delete(&keychain_account(&name))?;
+    config::update(|c| {
+        c.claude_profiles.retain(|p| p.name != name);
+        if was_active {
+            c.active_claude_profile = None;
+        }
+    })?;
+    if was_active {
+        let _ = app.emit("claude-profile-changed", DEFAULT_PROFILE);
+    }
+    list_claude_profiles()
+}
+
+/// Make `name` the profile newly queued agent runs start with
+#[tauri::command]
+pub fn switch_claude_profile(app: AppHandle, name: String) -> Result<ClaudeProfileList, Error> {
+    if find(&name).is_none() {
+        return Err(Error::NotFound(format!("No Claude profile named {}", name)));
+    }
+    config::update(|c| {
+        c.active_claude_profile = (name != DEFAULT_PROFILE).then(|| name.clone());
+    })?;
+    println!("[Claude PM] Switched Claude profile to {}", name);
+    let _ = app.emit("claude-profile-changed", &name);
+    list_claude_profiles()
+}
@@ -0,0 +1,398 @@
+//! Reading and merging changes into Claude Code's settings files
+//!
+//! User settings live in `~/.claude/settings.json`, project settings in
+//! `<project>/.claude/settings.json`. Changes are JSON merge patches (RFC 7386): objects
+//! merge recursively, `null` removes a key, anything else replaces. Only `hooks`,
+//! `permissions`, `model` and `env` are validated; other keys pass through untouched.
+//!
+//! The model commands manage the `model` key: the user's default and per-project
+//! overrides, checked against [`KNOWN_MODELS`].
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+
+use crate::error::Error;
+use crate::{claude_profiles, json_file, store};
+
+/// Hook events Claude Code fires
+const HOOK_EVENTS: &[&str] = &[
+    "PreToolUse",
+    "PostToolUse",
+    "Notification",
+    "UserPromptSubmit",
+    "Stop",
+    "SubagentStop",
+    "PreCompact",
+    "SessionStart",
+    "SessionEnd",
+];
+
+/// Model aliases and ids Claude Code accepts; dated ids are also accepted without the date
+pub const KNOWN_MODELS: &[&str] = &[
+    "default",
+    "opus",
+    "sonnet",
+    "haiku",
+    "opusplan",
+    "sonnet[1m]",
+    "claude-opus-4-5-20251101",
+    "claude-opus-4-1-20250805",
+    "claude-opus-4-20250514",
+    "claude-sonnet-4-5-20250929",
+    "claude-sonnet-4-20250514",
+    "claude-haiku-4-5-20251001",
+    "claude-3-7-sonnet-20250219",
+    "claude-3-5-haiku-20241022",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectModel {
+    pub project_id: String,
+    pub name: String,
+    /// The project's override; the default applies when unset
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelConfig {
+    /// The user-level default; Claude Code's own default when unset
+    pub default_model: Option<String>,
+    pub projects: Vec<ProjectModel>,
+    pub known_models: &'static [&'static str],
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsChange {
+    /// JSON pointer to the changed value
+    pub path: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsPreview {
+    pub before: Value,
+    pub after: Value,
+    pub changes: Vec<SettingsChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsBackup {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+fn settings_path(project: Option<&str>) -> Result<PathBuf, String> {
+    let base = match project {
+        Some(project) => PathBuf::from(project),
+        // The active Claude profile's own config directory, if it has one
+        None => match claude_profiles::active_config_dir() {
+            Some(dir) => return Ok(dir.join("settings.json")),
+            None => dirs::home_dir().ok_or("Could not determine home directory")?,
+        },
+    };
+    Ok(base.join(".claude").join("settings.json"))
+}
+
+fn backup_kind(project: Option<&str>) -> String {
+    match project {
+        Some(project) => {
+            let slug: String = project
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+                .collect();
+            format!("claude-settings/{}", slug.trim_matches('-'))
+        }
+        None => "claude-settings/user".to_string(),
+    }
+}
+
+fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let target = target.as_object_mut().expect("just made an object");
+    for (key, value) in patch {
+        if value.is_null() {
+            target.shift_remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+fn is_string_array(value: &Value) -> bool {
+    value
+        .as_array()
+        .is_some_and(|a| a.iter().all(Value::is_string))
+}
+
+fn validate_hooks(hooks: &Value) -> Result<(), String> {
+    let hooks = hooks.as_object().ok_or("\"hooks\" must be an object")?;
+    for (event, matchers) in hooks {
+        if !HOOK_EVENTS.contains(&event.as_str()) {
+            return Err(format!("Unknown hook event: {}", event));
+        }
+        let matchers = matchers
+            .as_array()
+            .ok_or_else(|| format!("hooks.{} must be an array", event))?;
+        for matcher in matchers {
+            if matcher.get("matcher").is_some_and(|m| !m.is_string()) {
+                return Err(format!("hooks.{}: \"matcher\" must be a string", event));
+            }
+            let commands = matcher
+                .get("hooks")
+                .and_then(Value::as_array)
+                .ok_or_else(|| format!("hooks.{}: each entry needs a \"hooks\" array", event))?;
+            for command in commands {
+                if command.get("type").and_then(Value::as_str) != Some("command")
+                    || !command.get("command").is_some_and(Value::is_string)
+                {
+                    return Err(format!(
+                        "hooks.{}: hooks must be {{\"type\": \"command\", \"command\": \"...\"}}",
+                        event
+                    ));
+                }
+                if command.get("timeout").is_some_and(|t| !t.is_number()) {
+                    return Err(format!("hooks.{}: \"timeout\" must be a number", event));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn validate_permissions(permissions: &Value) -> Result<(), String> {
+    let permissions = permissions
+        .as_object()
+        .ok_or("\"permissions\" must be an object")?;
+    for key in ["allow", "deny", "ask", "additionalDirectories"] {
+        if permissions.get(key).is_some_and(|v| !is_string_array(v)) {
+            return Err(format!("permissions.{} must be an array of strings", key));
+        }
+    }
+    if permissions
+        .get("defaultMode")
+        .is_some_and(|m| !m.is_string())
+    {
+        return Err("permissions.defaultMode must be a string".to_string());
+    }
+    Ok(())
+}
+
+fn validate(settings: &Value) -> Result<(), String> {
+    let settings = settings
+        .as_object()
+        .ok_or("Settings must be a JSON object")?;
+    if let Some(hooks) = settings.get("hooks") {
+        validate_hooks(hooks)?;
+    }
+    if let Some(permissions) = settings.get("permissions") {
+        validate_permissions(permissions)?;
+    }
+    if settings.get("model").is_some_and(|m| !m.is_string()) {
+        return Err("\"model\" must be a string".to_string());
+    }
+    if let Some(env) = settings.get("env") {
+        let valid = env
+            .as_object()
+            .is_some_and(|m| m.values().all(Value::is_string));
+        if !valid {
+            return Err("\"env\" must map names to strings".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Leaf-level differences between two documents
+fn diff(path: &str, before: Option<&Value>, after: Option<&Value>, out: &mut Vec<SettingsChange>) {
+    if let (Some(Value::Object(a)), Some(Value::Object(b))) = (before, after) {
+        for (key, value) in a {
+            diff(&child_path(path, key), Some(value), b.get(key), out);
+        }
+        for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+            diff(&child_path(path, key), None, Some(value), out);
+        }
+    } else if before != after {
+        out.push(SettingsChange {
+            path: path.to_string(),
+            before: before.cloned(),
+            after: after.cloned(),
+        });
+    }
+}
+
+fn child_path(parent: &str, key: &str) -> String {
+    format!("{}/{}", parent, key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn preview(project: Option<&str>, patch: &Value) -> Result<SettingsPreview, String> {
+    let before = json_file::read(&settings_path(project)?)?;
+    let mut after = before.clone();
+    merge_patch(&mut after, patch);
+    validate(&after)?;
+
+    let mut changes = Vec::new();
+    diff("", Some(&before), Some(&after), &mut changes);
+    Ok(SettingsPreview {
+        before,
+        after,
+        changes,
+    })
+}
+
+pub fn validate_model(model: &str) -> Result<(), String> {
+    let known = KNOWN_MODELS.iter().any(|known| {
+        *known == model
+            || known
+                .rsplit_once('-')
+                .is_some_and(|(undated, date)| date.len() == 8 && undated == model)
+    });
+    if known {
+        Ok(())
+    } else {
+        Err(format!("Unknown model \"{}\"", model))
+    }
+}
+
+fn model_in(project: Option<&str>) -> Option<String> {
+    let settings = json_file::read(&settings_path(project).ok()?).ok()?;
+    settings["model"].as_str().map(str::to_string)
+}
+
+/// The model agents in `repo_path` should use: the project's override, else the default
+pub fn effective_model(repo_path: Option<&str>) -> Option<String> {
+    repo_path
+        .and_then(|repo| model_in(Some(repo)))
+        .or_else(|| model_in(None))
+}
+
+fn set_model(project: Option<&str>, model: Option<String>) -> Result<(), Error> {
+    if let Some(model) = &model {
+        validate_model(model).map_err(Error::InvalidInput)?;
+    }
+    let patch = serde_json::json!({ "model": model });
+    let result = preview(project, &patch)?;
+    if !result.changes.is_empty() {
+        json_file::write(
+            &settings_path(project)?,
+            &backup_kind(project),
+            &result.after,
+        )?;
+    }
+    Ok(())
+}
+
+fn project_repo(project_id: &str) -> Result<String, Error> {
+    store::list_projects()?
+        .into_iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project_id)))?
+        .repo_path
+        .ok_or_else(|| Error::InvalidInput("The project has no repository path".to_string()))
+}
+
+/// Current settings for a project, or the user's settings when `project` is omitted
+#[tauri::command]
+pub fn read_claude_settings(project: Option<String>) -> Result<Value, Error> {
+    json_file::read(&settings_path(project.as_deref())?).map_err(Error::from)
+}
+
+/// What applying `patch` would change, without writing anything
+#[tauri::command]
+pub fn preview_claude_settings(
+    project: Option<String>,
+    patch: Value,
+) -> Result<SettingsPreview, Error> {
+    preview(project.as_deref(), &patch).map_err(Error::from)
+}
+
+/// Merge `patch` into the settings file, backing up the previous version
+#[tauri::command]
+pub fn apply_claude_settings(
+    project: Option<String>,
+    patch: Value,
+) -> Result<SettingsPreview, Error> {
+    let project = project.as_deref();
+    let result = preview(project, &patch)?;
+    if !result.changes.is_empty() {
+        json_file::write(
+            &settings_path(project)?,
+            &backup_kind(project),
+            &result.after,
+        )?;
+    }
+    Ok(result)
+}
+
+/// Backups of a settings file, newest first
+#[tauri::command]
+pub fn list_claude_settings_backups(project: Option<String>) -> Result<Vec<SettingsBackup>, Error> {
+    let mut backups = json_file::list_backups(&backup_kind(project.as_deref()))?;
+    backups.reverse();
+    Ok(backups
+        .into_iter()
+        .filter_map(|path| {
+            let id = path.file_stem()?.to_string_lossy().to_string();
+            Some(SettingsBackup { id, path })
+        })
+        .collect())
+}
+
+/// Restore a backup by id; the current file is itself backed up first
+#[tauri::command]
+pub fn restore_claude_settings_backup(project: Option<String>, id: String) -> Result<(), Error> {
+    let project = project.as_deref();
+    let kind = backup_kind(project);
+    let backup = json_file::list_backups(&kind)?
+        .into_iter()
+        .find(|path| path.file_stem().is_some_and(|stem| stem == id.as_str()))
+        .ok_or_else(|| Error::NotFound(format!("Backup not found: {}", id)))?;
+
+    let contents = json_file::read(&backup)?;
+    validate(&contents)?;
+    json_file::write(&settings_path(project)?, &kind, &contents).map_err(Error::from)
+}
+
+/// The default model and each project's override
+#[tauri::command]
+pub fn get_model_config() -> Result<ModelConfig, Error> {
+    let projects = store::list_projects()?
+        .into_iter()
+        .map(|project| ProjectModel {
+            model: project
+                .repo_path
+                .as_deref()
+                .and_then(|repo| model_in(Some(repo))),
+            project_id: project.id,
+            name: project.name,
+        })
+        .collect();
+    Ok(ModelConfig {
+        default_model: model_in(None),
+        projects,
+        known_models: KNOWN_MODELS,
+    })
+}
+
+/// Set or clear (`None`) the model in the user's settings
+#[tauri::command]
+pub fn set_default_model(model: Option<String>) -> Result<(), Error> {
+    set_model(None, model)
+}
+
+/// Set or clear (`None`) a project's model override in its `.claude/settings.json`
+#[tauri::command]
+pub fn set_project_model(project_id: String, model: Option<String>) -> Result<(), Error> {
+    set_model(Some(&project_repo(&project_id)?), model)
+}
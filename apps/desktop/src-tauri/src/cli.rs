@@ -0,0 +1,251 @@
+//! `claudepm`, a headless CLI over the same core as the desktop app
+//!
+//! Reads the local store and drives the server lifecycle directly, so scripts work
+//! whether or not the app is open:
+//!
+//! - `claudepm status [--json]`
+//! - `claudepm restart-server`
+//! - `claudepm tasks list [--project <name or id>] [--state <state>] [--json]`
+//! - `claudepm agent run [--task <id>] [--cwd <dir>] -- <program> [args...]`
+//! - `claudepm export site --project <name or id> --out <dir>`
+//!
+//! When the app owns the server process, `restart-server` asks it to restart through a
+//! `claudepm://server/restart` URL rather than racing it for the port.
+
+use serde_json::json;
+use std::env;
+use std::path::Path;
+use std::process::{Command, ExitCode};
+
+use crate::error::Error;
+use crate::store::{self, NewSession, SessionUpdate};
+use crate::{config, docker, profiles, service, static_site};
+
+const USAGE: &str = "Usage:
+  claudepm status [--json]
+  claudepm restart-server
+  claudepm tasks list [--project <name or id>] [--state <state>] [--json]
+  claudepm agent run [--task <id>] [--cwd <dir>] -- <program> [args...]
+  claudepm export site --project <name or id> --out <dir>";
+
+/// `--flag value` pairs; `--json` has no value
+type Flags = Vec<(String, Option<String>)>;
+
+/// Split `--flag value` options from positional arguments, stopping at `--`
+fn parse(args: &[String]) -> Result<(Flags, Vec<String>), Error> {
+    let mut flags = Vec::new();
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--" => {
+                rest.extend(iter.cloned());
+                break;
+            }
+            "--json" => flags.push(("json".to_string(), None)),
+            flag if flag.starts_with("--") => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| Error::InvalidInput(format!("Missing value for {}", flag)))?;
+                flags.push((flag[2..].to_string(), Some(value.clone())));
+            }
+            _ => rest.push(arg.clone()),
+        }
+    }
+    Ok((flags, rest))
+}
+
+fn flag<'a>(flags: &'a Flags, name: &str) -> Option<&'a str> {
+    flags
+        .iter()
+        .find(|(key, _)| key == name)
+        .and_then(|(_, value)| value.as_deref())
+}
+
+fn has(flags: &Flags, name: &str) -> bool {
+    flags.iter().any(|(key, _)| key == name)
+}
+
+fn backend() -> &'static str {
+    if service::is_installed() {
+        "service"
+    } else if config::load().docker.enabled {
+        "docker"
+    } else {
+        "app"
+    }
+}
+
+fn status(json_output: bool) -> Result<(), Error> {
+    let profile = profiles::active();
+    let running = crate::is_server_running(profile.port);
+    let backend = backend();
+    let container = (backend == "docker").then(docker::status);
+    if json_output {
+        println!(
+            "{}",
+            json!({
+                "running": running,
+                "port": profile.port,
+                "profile": profile.name,
+                "backend": backend,
+                "container": container,
+            })
+        );
+        return Ok(());
+    }
+    println!(
+        "Server:  {} on port {}",
+        if running { "running" } else { "stopped" },
+        profile.port
+    );
+    println!("Profile: {}", profile.name);
+    println!("Backend: {}", backend);
+    if let Some(container) = container {
+        println!("Container: {}", container.status);
+    }
+    Ok(())
+}
+
+fn restart_server() -> Result<(), Error> {
+    match backend() {
+        "service" | "docker" => crate::restart_server()?,
+        _ => {
+            let opener = if cfg!(target_os = "macos") {
+                "open"
+            } else {
+                "xdg-open"
+            };
+            let status = Command::new(opener)
+                .arg("claudepm://server/restart")
+                .status()
+                .map_err(|e| format!("Failed to reach the desktop app: {}", e))?;
+            if !status.success() {
+                return Err("Failed to reach the desktop app".into());
+            }
+        }
+    }
+    println!("Server restart requested");
+    Ok(())
+}
+
+fn project_id(project: &str) -> Result<String, Error> {
+    store::list_projects()?
+        .into_iter()
+        .find(|p| p.id == project || p.name.eq_ignore_ascii_case(project))
+        .map(|p| p.id)
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project)))
+}
+
+fn list_tasks(flags: &Flags) -> Result<(), Error> {
+    let project_id = flag(flags, "project").map(project_id).transpose()?;
+    let tasks = store::list_tasks(project_id, flag(flags, "state").map(str::to_string))?;
+    if has(flags, "json") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&tasks).map_err(|e| e.to_string())?
+        );
+        return Ok(());
+    }
+    for task in tasks {
+        println!("{}  {:<12}  {}", task.id, task.state, task.title);
+    }
+    Ok(())
+}
+
+fn export_site(flags: &Flags) -> Result<(), Error> {
+    let project = flag(flags, "project")
+        .ok_or_else(|| Error::InvalidInput("Missing --project".to_string()))?;
+    let out = flag(flags, "out").ok_or_else(|| Error::InvalidInput("Missing --out".to_string()))?;
+    let export = static_site::export(&project_id(project)?, Path::new(out))?;
+    println!(
+        "Exported {} tasks to {}",
+        export.tasks,
+        Path::new(&export.path).join("index.html").display()
+    );
+    Ok(())
+}
+
+/// Run an agent in the foreground, recorded as a session (linked to `--task` if given)
+fn run_agent(flags: &Flags, command: &[String]) -> Result<i32, Error> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| Error::InvalidInput("Missing program to run after --".to_string()))?;
+    let task = match flag(flags, "task") {
+        Some(id) => Some(
+            store::with_conn(|conn| store::get_task(conn, id))?
+                .ok_or_else(|| Error::NotFound(format!("Task not found: {}", id)))?,
+        ),
+        None => None,
+    };
+    // Default to the task's repository
+    let cwd = flag(flags, "cwd").map(str::to_string).or_else(|| {
+        let project_id = task.as_ref()?.project_id.clone();
+        store::list_projects()
+            .ok()?
+            .into_iter()
+            .find(|p| p.id == project_id)?
+            .repo_path
+    });
+    let session = match &task {
+        Some(task) => Some(store::create_session(NewSession {
+            project_id: task.project_id.clone(),
+            task_id: Some(task.id.clone()),
+            pane_id: None,
+        })?),
+        None => None,
+    };
+
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    if let Some(cwd) = &cwd {
+        cmd.current_dir(cwd);
+    }
+    let result = cmd
+        .status()
+        .map_err(|e| Error::from(format!("Failed to run {}: {}", program, e)));
+
+    if let Some(session) = session {
+        let succeeded = result.as_ref().is_ok_and(|s| s.success());
+        store::update_session(
+            session.id,
+            SessionUpdate {
+                status: Some(if succeeded { "completed" } else { "failed" }.to_string()),
+                pane_id: None,
+                ended_at: Some(store::now_ms()),
+            },
+        )?;
+    }
+    Ok(result?.code().unwrap_or(1))
+}
+
+fn dispatch(args: &[String]) -> Result<i32, Error> {
+    let (flags, rest) = parse(args)?;
+    let words: Vec<&str> = rest.iter().map(String::as_str).collect();
+    match words.as_slice() {
+        ["status"] => status(has(&flags, "json")).map(|_| 0),
+        ["restart-server"] => restart_server().map(|_| 0),
+        ["tasks", "list"] => list_tasks(&flags).map(|_| 0),
+        ["export", "site"] => export_site(&flags).map(|_| 0),
+        ["agent", "run", command @ ..] => {
+            let command: Vec<String> = command.iter().map(|s| s.to_string()).collect();
+            run_agent(&flags, &command)
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            Ok(2)
+        }
+    }
+}
+
+/// Entry point for the `claudepm` binary
+pub fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match dispatch(&args) {
+        Ok(code) => ExitCode::from(code.clamp(0, 255) as u8),
+        Err(e) => {
+            eprintln!("claudepm: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
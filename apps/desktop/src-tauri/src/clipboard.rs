@@ -0,0 +1,240 @@
+//! Clipboard history and rich clipboard writes
+//!
+//! While `clipboardHistory` is enabled in the config the clipboard is polled for text
+//! changes, and each new value is kept with the app it was copied from. The history
+//! is memory-only so copied secrets never reach disk.
+//!
+//! Images and HTML go through the clipboard plugin; RTF isn't supported there, so it is
+//! handed to `pbcopy` (macOS) or `xclip` (Linux), which tag it with the RTF type.
+//!
+//! Events:
+//! - `clipboard-changed`: a new entry was added
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::error::Error;
+use crate::process::which;
+use crate::{activity, config, store};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const HISTORY_CAPACITY: usize = 100;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardEntry {
+    pub id: String,
+    pub text: String,
+    /// Frontmost app when the change was seen, if it could be determined
+    pub source_app: Option<String>,
+    pub source_title: Option<String>,
+    pub copied_at: i64,
+}
+
+struct History {
+    last_seen: Option<String>,
+    entries: VecDeque<ClipboardEntry>,
+}
+
+static HISTORY: Mutex<History> = Mutex::new(History {
+    last_seen: None,
+    entries: VecDeque::new(),
+});
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+fn poll(app: &AppHandle) {
+    let Ok(text) = app.clipboard().read_text() else {
+        return;
+    };
+    let entry = {
+        let Ok(mut history) = HISTORY.lock() else {
+            return;
+        };
+        if history.last_seen.as_deref() == Some(text.as_str()) {
+            return;
+        }
+        history.last_seen = Some(text.clone());
+        if text.trim().is_empty() {
+            return;
+        }
+        let (source_app, source_title) = match activity::frontmost() {
+            Ok((app, title)) => (Some(app), Some(title).filter(|t| !t.is_empty())),
+            Err(_) => (None, None),
+        };
+        let entry = ClipboardEntry {
+            id: store::new_id(),
+            text,
+            source_app,
+            source_title,
+            copied_at: store::now_ms(),
+        };
+        // Copying the same text again moves it back to the top
+        history.entries.retain(|e| e.text != entry.text);
+        if history.entries.len() == HISTORY_CAPACITY {
+            history.entries.pop_back();
+        }
+        history.entries.push_front(entry.clone());
+        entry
+    };
+    let _ = app.emit("clipboard-changed", entry);
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        if config::load().clipboard_history {
+            poll(&app);
+        }
+    });
+}
+
+/// Opt in or out; the history is cleared when switched off
+#[tauri::command]
+pub fn set_clipboard_history_enabled(enabled: bool) -> Result<(), Error> {
+    config::update(|c| c.clipboard_history = enabled)?;
+    if !enabled {
+        clear_clipboard_history();
+    }
+    Ok(())
+}
+
+/// Newest first
+#[tauri::command]
+pub fn get_clipboard_history() -> Vec<ClipboardEntry> {
+    HISTORY
+        .lock()
+        .map(|history| history.entries.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn clear_clipboard_history() {
+    if let Ok(mut history) = HISTORY.lock() {
+        history.entries.clear();
+    }
+}
+
+/// Append a history entry to a task's description
+#[tauri::command]
+pub fn paste_clipboard_into_task(entry_id: String, task_id: String) -> Result<store::Task, Error> {
+    let entry = HISTORY
+        .lock()
+        .map_err(|e| e.to_string())?
+        .entries
+        .iter()
+        .find(|e| e.id == entry_id)
+        .cloned()
+        .ok_or_else(|| Error::NotFound(format!("Clipboard entry not found: {}", entry_id)))?;
+    let task = store::with_conn(|conn| store::get_task(conn, &task_id))?
+        .ok_or_else(|| Error::NotFound(format!("Task not found: {}", task_id)))?;
+    let description = match task.description.filter(|d| !d.trim().is_empty()) {
+        Some(existing) => format!("{}\n\n{}", existing.trim_end(), entry.text),
+        None => entry.text,
+    };
+    store::update_task(
+        task_id,
+        store::TaskUpdate {
+            title: None,
+            description: Some(description),
+            state: None,
+        },
+    )
+}
+
+/// Copy a PNG, given either as base64 (e.g. a rendered canvas) or a file path
+#[tauri::command]
+pub async fn copy_image(
+    app: AppHandle,
+    png_base64: Option<String>,
+    path: Option<String>,
+) -> Result<(), Error> {
+    let bytes = match (png_base64, path) {
+        (Some(data), _) => {
+            // Accept data URLs as produced by `canvas.toDataURL()`
+            let data = data.rsplit(',').next().unwrap_or_default();
+            BASE64
+                .decode(data.trim())
+                .map_err(|e| Error::InvalidInput(format!("Invalid base64 image: {}", e)))?
+        }
+        (None, Some(path)) => std::fs::read(PathBuf::from(&path))
+            .map_err(|e| Error::NotFound(format!("Failed to read {}: {}", path, e)))?,
+        (None, None) => {
+            return Err(Error::InvalidInput(
+                "Either pngBase64 or path is required".to_string(),
+            ))
+        }
+    };
+    let image = Image::from_bytes(&bytes)
+        .map_err(|e| Error::InvalidInput(format!("Not a PNG image: {}", e)))?;
+    app.clipboard()
+        .write_image(&image)
+        .map_err(|e| format!("Failed to copy image: {}", e).into())
+}
+
+/// Copy HTML, with a plain-text fallback for apps that don't accept it
+#[tauri::command]
+pub async fn copy_html(
+    app: AppHandle,
+    html: String,
+    plain_text: Option<String>,
+) -> Result<(), Error> {
+    app.clipboard()
+        .write_html(html, plain_text)
+        .map_err(|e| format!("Failed to copy HTML: {}", e).into())
+}
+
+#[tauri::command]
+pub async fn copy_rtf(rtf: String) -> Result<(), Error> {
+    if !rtf.trim_start().starts_with("{\\rtf") {
+        return Err(Error::InvalidInput("Content is not RTF".to_string()));
+    }
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("pbcopy");
+        cmd.args(["-Prefer", "rtf"]);
+        cmd
+    } else if cfg!(target_os = "linux") {
+        let xclip = which("xclip")
+            .ok_or_else(|| Error::NotFound("xclip is required on Linux".to_string()))?;
+        let mut cmd = Command::new(xclip);
+        cmd.args(["-selection", "clipboard", "-t", "text/rtf"]);
+        cmd
+    } else {
+        return Err(Error::Unsupported(
+            "Copying RTF is not supported on this platform".to_string(),
+        ));
+    };
+    // xclip stays running to serve the selection, so don't wait on its output pipes
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to copy RTF: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(rtf.as_bytes())
+            .map_err(|e| format!("Failed to copy RTF: {}", e))?;
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("Failed to copy RTF: {}", e))?;
+    if !status.success() {
+        return Err(format!("Clipboard helper exited with {}", status).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,274 @@
+//! Persistent desktop configuration, stored as JSON in the app config directory
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::app_lock::AppLockSettings;
+use crate::ci_status::CiWatchSettings;
+use crate::claude_profiles::ClaudeProfile;
+use crate::digest::DigestSettings;
+use crate::dnd::FocusPolicy;
+use crate::docker::DockerSettings;
+use crate::email::EmailSettings;
+use crate::exec_policy::ExecPolicy;
+use crate::focus_session::FocusSessionSettings;
+use crate::http_proxy::ProxySettings;
+use crate::jira::JiraSettings;
+use crate::lan_share::LanShareSettings;
+use crate::linear::LinearSettings;
+use crate::pane_watch::PaneWatch;
+use crate::pomodoro::PomodoroSettings;
+use crate::profiles::ServerProfile;
+use crate::server_paths::ServerPathEntry;
+use crate::sounds::SoundConfig;
+use crate::speech::SpeechSettings;
+use crate::storage::StorageSettings;
+use crate::sync::SyncSettings;
+use crate::tmux_layout::LayoutTemplate;
+use crate::transcript_retention::TranscriptRetention;
+use crate::usage::BudgetSettings;
+use crate::voice::VoiceSettings;
+use crate::watchdog::WatchdogSettings;
+use crate::working_hours::WorkingHoursSettings;
+
+/// Matches the bundle identifier in tauri.conf.json so we share Tauri's directories
+const APP_IDENTIFIER: &str = "com.claudepm.desktop";
+pub const CONFIG_FILE: &str = "config.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AppConfig {
+    /// Preferred editor id (see `editor::EDITORS`); auto-detected when unset
+    pub editor: Option<String>,
+    /// Preferred terminal id (see `terminal::TERMINALS`); platform default when unset
+    pub terminal: Option<String>,
+    /// Per notification category behaviour while macOS Focus is on
+    pub focus_policies: BTreeMap<String, FocusPolicy>,
+    /// Batching of non-critical notifications into periodic summaries (see `digest`)
+    pub digest: DigestSettings,
+    /// Days and hours outside which notifications and schedules are held (see
+    /// `working_hours`)
+    pub working_hours: WorkingHoursSettings,
+    pub sounds: SoundConfig,
+    /// Global shortcut overrides: action -> accelerator, empty string disables
+    pub shortcuts: BTreeMap<String, String>,
+    /// App menu accelerator overrides: action -> accelerator, empty string removes it
+    pub menu_accelerators: BTreeMap<String, String>,
+    /// Vault env set injected into the Node server's environment
+    pub server_env_set: Option<String>,
+    /// Outbound proxy passed to the server and spawned processes
+    pub http_proxy: ProxySettings,
+    /// Selected server directory; falls back to `CLAUDE_PM_SERVER_PATH` and the
+    /// registry (see `server_paths`)
+    pub server_path: Option<String>,
+    /// Known server directories (see `server_paths`)
+    pub server_paths: Vec<ServerPathEntry>,
+    /// Host the server is also probed on besides IPv4/IPv6 loopback (see `server_probe`)
+    pub server_host: Option<String>,
+    /// Leave the server down at launch and start it on first use (see `lifecycle`)
+    pub lazy_server_start: bool,
+    /// Forward app and server output to the OS log (on when unset, see `system_log`)
+    pub os_logging: Option<bool>,
+    /// Rerun a `tsx watch` server in place on restart (on when unset, see `warm_restart`)
+    pub warm_restart: Option<bool>,
+    /// Startup phases slower than this many milliseconds are reported (2000 when unset,
+    /// see `startup_timing`)
+    pub startup_warn_ms: Option<u64>,
+    /// Seconds before a hung helper process (osascript, tmux, which...) is killed
+    pub process_timeout_secs: Option<u64>,
+    /// `tmux`, `zellij` or `screen`; the first one installed when unset (see `multiplexer`)
+    pub multiplexer: Option<String>,
+    /// Panes watched for activity, silence and bells (see `pane_watch`)
+    pub pane_watches: Vec<PaneWatch>,
+    /// Restrictions on commands run for agents (see `exec_policy`)
+    pub exec_policy: ExecPolicy,
+    /// Server profiles; the built-in dev/staging/prod set when empty
+    pub server_profiles: Vec<ServerProfile>,
+    /// tmux layout templates; the built-in one when empty (see `tmux_layout`)
+    pub tmux_layouts: Vec<LayoutTemplate>,
+    /// Name of the profile the server is started with (`dev` when unset)
+    pub active_profile: Option<String>,
+    /// User consent for sampling the frontmost app (see `activity`)
+    pub activity_tracking: bool,
+    /// Minutes without input before the user counts as idle (5 when unset)
+    pub idle_threshold_mins: Option<u64>,
+    pub pomodoro: PomodoroSettings,
+    /// Shortcuts that switch a macOS Focus mode with focus sessions (see `focus_session`)
+    pub focus_session: FocusSessionSettings,
+    /// User consent for keeping a clipboard history (see `clipboard`)
+    pub clipboard_history: bool,
+    /// Keep the frontmost app and URL with quick captures (on when unset, see `quick_capture`)
+    pub capture_context: Option<bool>,
+    /// Access token for the local .ics feed; the feed is off when unset (see `ics`)
+    pub ics_feed_token: Option<String>,
+    /// Bearer token for the local REST API; the API is off when unset (see `local_api`)
+    pub local_api_token: Option<String>,
+    /// Bearer token agents send to the embedded MCP server; generated on first start (see `mcp`)
+    pub mcp_token: Option<String>,
+    /// SMTP server for reports and alerts; the password is in the keychain
+    pub email: EmailSettings,
+    /// Run the server in a container instead of via npm (see `docker`)
+    pub docker: DockerSettings,
+    /// Agent runs allowed at once before the rest queue (3 when unset, see `orchestrator`)
+    pub max_parallel_agents: Option<usize>,
+    /// Claude accounts and config sets agents can run as (see `claude_profiles`)
+    pub claude_profiles: Vec<ClaudeProfile>,
+    /// Profile newly queued agent runs start with; `default` when unset
+    pub active_claude_profile: Option<String>,
+    /// Queue an agent for a task as soon as its dependencies are done, instead of
+    /// offering to in a notification (see `task_graph`)
+    pub start_unblocked_agents: bool,
+    /// Tray title and taskbar overlays following the agent queue (on when unset, see
+    /// `queue_badge`)
+    pub queue_badges: Option<bool>,
+    /// Serve cached server and GitHub reads while they're unreachable (on when unset, see
+    /// `api_cache`)
+    pub offline_cache: Option<bool>,
+    /// Seconds between `heartbeat` events (30 when unset, see `heartbeat`)
+    pub heartbeat_interval_secs: Option<u64>,
+    /// Estimated run cost in USD from which launching asks first (1 when unset, see
+    /// `cost_estimate`)
+    pub cost_confirm_usd: Option<f64>,
+    /// Pre-run snapshots kept per task (10 when unset, see `snapshots`)
+    pub snapshots_per_task: Option<usize>,
+    /// Age and size caps for Claude transcripts and archived deletions (see
+    /// `transcript_retention`)
+    pub transcript_retention: TranscriptRetention,
+    /// Days of agent run history kept (90 when unset, see `run_history`)
+    pub run_history_days: Option<u32>,
+    /// Agent runs kept in history (5000 when unset, see `run_history`)
+    pub run_history_limit: Option<usize>,
+    /// Issue sync, status and field mappings for Jira Cloud (see `jira`)
+    pub jira: JiraSettings,
+    /// Linked teams, status mapping and run comments for Linear (see `linear`)
+    pub linear: LinearSettings,
+    /// Polling of linked PRs' checks (see `ci_status`)
+    pub ci_watch: CiWatchSettings,
+    /// Health checks of the server and subsystems and what to do on failure (see `watchdog`)
+    pub watchdog: WatchdogSettings,
+    /// Monthly spend caps and alert thresholds (see `usage`)
+    pub budget: BudgetSettings,
+    /// Recorder and whisper.cpp setup for dictation (see `voice`)
+    pub voice: VoiceSettings,
+    /// Spoken announcements of agent events (see `speech`)
+    pub speech: SpeechSettings,
+    /// Size caps and minimum free disk space (see `storage`)
+    pub storage: StorageSettings,
+    /// Data directory chosen by the user (see `data_location`); the platform default when unset
+    pub data_dir: Option<String>,
+    /// Touch ID / system authentication lock (see `app_lock`)
+    pub app_lock: AppLockSettings,
+    /// Encrypted sync through the user's S3 bucket or WebDAV server (see `sync`)
+    pub sync: SyncSettings,
+    /// mDNS discovery of other instances on the LAN (see `lan_share`)
+    pub lan_share: LanShareSettings,
+}
+
+/// The data directory in use, resolved once per launch (and on relocation)
+static DATA_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+/// The configured data directory, if it was missing when resolved
+static MISSING_DATA_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Directory holding config.json and other small settings files
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_IDENTIFIER))
+}
+
+/// Where app data lives unless the user moved it
+pub fn default_data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER))
+}
+
+/// Directory for app data that isn't settings (logs, event history, caches)
+///
+/// A configured directory that doesn't exist (an unplugged external drive) is never
+/// created; the default is used for this launch instead and `missing_data_dir` reports it.
+pub fn data_dir() -> Option<PathBuf> {
+    let mut cached = DATA_DIR.lock().ok()?;
+    if cached.is_none() {
+        *cached = match load().data_dir.map(PathBuf::from) {
+            Some(custom) if custom.is_dir() => Some(custom),
+            Some(custom) => {
+                eprintln!(
+                    "[Claude PM] Data directory {} is missing, using the default",
+                    custom.display()
+                );
+                if let Ok(mut missing) = MISSING_DATA_DIR.lock() {
+                    *missing = Some(custom);
+                }
+                default_data_dir()
+            }
+            None => default_data_dir(),
+        };
+    }
+    cached.clone()
+}
+
+/// The configured data directory, if it couldn't be found at launch
+pub fn missing_data_dir() -> Option<PathBuf> {
+    MISSING_DATA_DIR
+        .lock()
+        .ok()
+        .and_then(|missing| missing.clone())
+}
+
+/// Point the app at a new data directory (already populated) and remember it
+pub fn set_data_dir(dir: &Path) -> Result<(), String> {
+    let default = default_data_dir();
+    update(|c| {
+        c.data_dir = (default.as_deref() != Some(dir)).then(|| dir.display().to_string());
+    })?;
+    if let Ok(mut cached) = DATA_DIR.lock() {
+        *cached = Some(dir.to_path_buf());
+    }
+    if let Ok(mut missing) = MISSING_DATA_DIR.lock() {
+        *missing = None;
+    }
+    Ok(())
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(CONFIG_FILE))
+}
+
+/// Load the config, falling back to defaults if the file is missing or invalid
+pub fn load() -> AppConfig {
+    let Some(path) = config_path() else {
+        return AppConfig::default();
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!(
+                "[Claude PM] Invalid config at {:?}, using defaults: {}",
+                path, e
+            );
+            AppConfig::default()
+        }),
+        Err(_) => AppConfig::default(),
+    }
+}
+
+/// Write the config to disk, creating the config directory if needed
+pub fn save(config: &AppConfig) -> Result<(), String> {
+    let path = config_path().ok_or("Could not determine config directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    let contents = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write config: {}", e))
+}
+
+/// Load, modify and save the config in one step
+pub fn update(f: impl FnOnce(&mut AppConfig)) -> Result<AppConfig, String> {
+    let mut config = load();
+    f(&mut config);
+    save(&config)?;
+    Ok(config)
+}
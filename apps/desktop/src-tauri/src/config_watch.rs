@@ -0,0 +1,147 @@
+//! Applying config changes without restarting the app
+//!
+//! Most settings (editor, sounds, focus policies...) are read from disk on use and need
+//! nothing. The rest is applied here whenever config.json changes, whether edited by
+//! hand or through a command: shortcuts are re-registered, and the server is restarted
+//! only if something it was started with (path, profile, env set, proxy) changed.
+//!
+//! Events:
+//! - `config-applied` — after each apply, with what changed
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{config, http_proxy, profiles};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Fingerprint of the settings the running server was started with
+static SERVER_APPLIED: Mutex<Option<Value>> = Mutex::new(None);
+/// Config as of the last apply, to report which settings changed
+static LAST_CONFIG: Mutex<Option<Value>> = Mutex::new(None);
+/// Serializes applies so a command and the watcher can't both restart the server
+static APPLYING: Mutex<()> = Mutex::new(());
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyReport {
+    /// Top-level config keys that changed since the last apply
+    pub changed: Vec<String>,
+    pub server_restarted: bool,
+    pub shortcuts_reloaded: bool,
+}
+
+fn server_fingerprint() -> Value {
+    let config = config::load();
+    json!({
+        "serverPath": config.server_path,
+        "profile": profiles::active(),
+        "serverEnvSet": config.server_env_set,
+        "proxy": http_proxy::env(),
+    })
+}
+
+/// Record what the server was just started with
+pub fn mark_server_started() {
+    if let Ok(mut applied) = SERVER_APPLIED.lock() {
+        *applied = Some(server_fingerprint());
+    }
+}
+
+fn changed_keys(previous: Option<&Value>, current: &Value) -> Vec<String> {
+    let (Some(Value::Object(previous)), Value::Object(current)) = (previous, current) else {
+        return Vec::new();
+    };
+    current
+        .iter()
+        .filter(|(key, value)| previous.get(*key) != Some(value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+/// Bring the running app in line with config.json
+pub fn apply(app: &AppHandle) -> Result<ApplyReport, Error> {
+    let _guard = APPLYING.lock().map_err(|e| e.to_string())?;
+
+    let current = serde_json::to_value(config::load()).map_err(|e| e.to_string())?;
+    let changed = {
+        let mut last = LAST_CONFIG.lock().map_err(|e| e.to_string())?;
+        let changed = changed_keys(last.as_ref(), &current);
+        *last = Some(current);
+        changed
+    };
+
+    let shortcuts_reloaded = changed.iter().any(|key| key == "shortcuts");
+    #[cfg(desktop)]
+    if shortcuts_reloaded {
+        crate::shortcuts::register_all(app);
+    }
+
+    let applied = SERVER_APPLIED.lock().map_err(|e| e.to_string())?.clone();
+    let server_changed = match applied {
+        Some(applied) => applied != server_fingerprint(),
+        // We never got a server up (e.g. bad path); a config change is worth another try
+        None => {
+            !changed.is_empty()
+                && !crate::lifecycle::is_deferred()
+                && !crate::is_server_running(crate::server_port())
+        }
+    };
+    if server_changed {
+        println!("[Claude PM] Server settings changed, restarting server");
+        crate::restart_server()?;
+    }
+
+    let report = ApplyReport {
+        changed,
+        server_restarted: server_changed,
+        shortcuts_reloaded,
+    };
+    let _ = app.emit("config-applied", report.clone());
+    Ok(report)
+}
+
+fn modified_at() -> Option<SystemTime> {
+    config::config_path()
+        .and_then(|path| fs::metadata(path).ok())
+        .and_then(|meta| meta.modified().ok())
+}
+
+/// Watch config.json and apply changes as they land
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    if let (Ok(mut last), Ok(current)) = (LAST_CONFIG.lock(), serde_json::to_value(config::load()))
+    {
+        *last = Some(current);
+    }
+    thread::spawn(move || {
+        let mut last_modified = modified_at();
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let modified = modified_at();
+            if modified == last_modified {
+                continue;
+            }
+            last_modified = modified;
+            if let Err(e) = apply(&app) {
+                eprintln!("[Claude PM] Failed to apply config change: {}", e);
+            }
+        }
+    });
+}
+
+/// Apply config changes now instead of waiting for the file watcher
+#[tauri::command]
+pub fn apply_config(app: AppHandle) -> Result<ApplyReport, Error> {
+    apply(&app)
+}
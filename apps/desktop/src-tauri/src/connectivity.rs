@@ -0,0 +1,285 @@
+//! Online/offline monitoring and a persisted queue for outbound work
+//!
+//! Connectivity is probed with HEAD requests to the endpoints the app and its agents
+//! actually use (the GitHub and Anthropic APIs), through the configured HTTP proxy (see
+//! `http_proxy`), so a network that only lets traffic out through a corporate proxy
+//! still counts as online. Any HTTP response means the host is reachable.
+//!
+//! One-off work that needs the internet, such as opening a GitHub issue from a task or
+//! a request queued from the frontend, is submitted as a [`Job`]; it runs immediately
+//! when online, otherwise it waits in outbound-queue.json in the data directory and
+//! drains on the next offline → online transition. Work with its own persisted retries
+//! (webhook deliveries, the outbox, tracker sync and CI polling) doesn't go through the
+//! queue; it checks [`is_online`] and waits.
+//!
+//! Events:
+//! - `connectivity-changed` — on every transition, with the current queue length
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{config, github, http_proxy, power, store};
+
+const QUEUE_FILE: &str = "outbound-queue.json";
+const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+const PROBE_URLS: &[&str] = &["https://api.github.com", "https://api.anthropic.com"];
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Jobs that keep failing while online are dropped after this many attempts
+const MAX_ATTEMPTS: u32 = 10;
+
+static ONLINE: AtomicBool = AtomicBool::new(true);
+static STARTED: AtomicBool = AtomicBool::new(false);
+static DRAINING: AtomicBool = AtomicBool::new(false);
+static QUEUE: Mutex<Option<Vec<QueuedJob>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Job {
+    /// Plain HTTP request to an external endpoint (webhooks, update checks)
+    Http {
+        method: String,
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        body: Option<Value>,
+    },
+    /// Open a GitHub issue from a task once GitHub is reachable
+    #[serde(rename_all = "camelCase")]
+    GithubIssue { task_id: String, repo: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedJob {
+    pub id: String,
+    pub job: Job,
+    pub created_at: i64,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectivityStatus {
+    pub online: bool,
+    pub queued: usize,
+}
+
+pub fn is_online() -> bool {
+    ONLINE.load(Ordering::SeqCst)
+}
+
+fn queue_path() -> Result<PathBuf, String> {
+    config::data_dir()
+        .map(|dir| dir.join(QUEUE_FILE))
+        .ok_or_else(|| "Could not determine data directory".to_string())
+}
+
+fn load() -> Vec<QueuedJob> {
+    queue_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save(queue: &[QueuedJob]) -> Result<(), String> {
+    let path = queue_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+    let contents = serde_json::to_string_pretty(queue).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write outbound queue: {}", e))
+}
+
+/// Run `f` against the in-memory queue, loading it on first use; saves if `f` returns true
+fn with_queue<T>(f: impl FnOnce(&mut Vec<QueuedJob>) -> (T, bool)) -> Result<T, String> {
+    let mut guard = QUEUE.lock().map_err(|e| e.to_string())?;
+    let queue = guard.get_or_insert_with(load);
+    let (result, changed) = f(queue);
+    if changed {
+        save(queue)?;
+    }
+    Ok(result)
+}
+
+fn status() -> ConnectivityStatus {
+    ConnectivityStatus {
+        online: is_online(),
+        queued: with_queue(|queue| (queue.len(), false)).unwrap_or_default(),
+    }
+}
+
+fn probe() -> bool {
+    PROBE_URLS.iter().any(
+        |url| match http_proxy::agent(url, PROBE_TIMEOUT).head(url).call() {
+            Ok(_) | Err(ureq::Error::Status(..)) => true,
+            Err(ureq::Error::Transport(_)) => false,
+        },
+    )
+}
+
+fn run_job(job: &Job) -> Result<(), String> {
+    match job {
+        Job::Http {
+            method,
+            url,
+            headers,
+            body,
+        } => {
+            let mut request = http_proxy::agent(url, REQUEST_TIMEOUT).request(method, url);
+            for (name, value) in headers {
+                request = request.set(name, value);
+            }
+            match body {
+                Some(body) => request.send_json(body),
+                None => request.call(),
+            }
+            .map(|_| ())
+            .map_err(|e| format!("{} {} failed: {}", method, url, e))
+        }
+        Job::GithubIssue { task_id, repo } => {
+            tauri::async_runtime::block_on(github::create_issue_for_task(task_id, repo)).map(|_| ())
+        }
+    }
+}
+
+/// Run queued jobs in order until the queue is empty or one fails
+fn drain(app: AppHandle) {
+    if DRAINING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || {
+        while is_online() {
+            let Ok(Some(next)) = with_queue(|queue| (queue.first().cloned(), false)) else {
+                break;
+            };
+            let result = run_job(&next.job);
+            let failed = result.is_err();
+            let _ = with_queue(|queue| {
+                match result {
+                    Ok(()) => queue.retain(|queued| queued.id != next.id),
+                    Err(e) => {
+                        eprintln!("[Claude PM] Queued job {} failed: {}", next.id, e);
+                        if let Some(queued) = queue.iter_mut().find(|q| q.id == next.id) {
+                            queued.attempts += 1;
+                            queued.last_error = Some(e);
+                        }
+                        queue.retain(|queued| queued.attempts < MAX_ATTEMPTS);
+                    }
+                }
+                ((), true)
+            });
+            // Retry on the next transition or probe rather than spinning on a failing job
+            if failed {
+                break;
+            }
+        }
+        DRAINING.store(false, Ordering::SeqCst);
+        let _ = app.emit("connectivity-changed", status());
+    });
+}
+
+/// Queue `job` and run it right away if we're online
+pub fn submit(app: &AppHandle, job: Job) -> Result<QueuedJob, String> {
+    let queued = QueuedJob {
+        id: store::new_id(),
+        job,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default(),
+        attempts: 0,
+        last_error: None,
+    };
+    with_queue(|queue| {
+        queue.push(queued.clone());
+        ((), true)
+    })?;
+    if is_online() {
+        drain(app.clone());
+    }
+    Ok(queued)
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        let online = probe();
+        let was_online = ONLINE.swap(online, Ordering::SeqCst);
+        if online != was_online {
+            println!(
+                "[Claude PM] Connectivity: {}",
+                if online { "online" } else { "offline" }
+            );
+            let _ = app.emit("connectivity-changed", status());
+        }
+        // Also picks up jobs left over from a previous run, or a failure last round
+        if online {
+            drain(app.clone());
+        }
+        power::wait(PROBE_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn get_connectivity_status() -> ConnectivityStatus {
+    status()
+}
+
+#[tauri::command]
+pub fn list_outbound_queue() -> Result<Vec<QueuedJob>, Error> {
+    with_queue(|queue| (queue.clone(), false)).map_err(Error::from)
+}
+
+/// Queue an outbound HTTP request (e.g. a webhook) to be delivered once online
+#[tauri::command]
+pub fn queue_outbound_request(
+    app: AppHandle,
+    method: String,
+    url: String,
+    headers: Option<HashMap<String, String>>,
+    body: Option<Value>,
+) -> Result<QueuedJob, Error> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(Error::InvalidInput(format!("Not an http(s) URL: {}", url)));
+    }
+    submit(
+        &app,
+        Job::Http {
+            method: method.to_uppercase(),
+            url,
+            headers: headers.unwrap_or_default(),
+            body,
+        },
+    )
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn discard_queued_job(id: String) -> Result<(), Error> {
+    with_queue(|queue| {
+        let before = queue.len();
+        queue.retain(|queued| queued.id != id);
+        (queue.len() != before, queue.len() != before)
+    })?
+    .then_some(())
+    .ok_or_else(|| format!("No queued job with id {}", id))
+    .map_err(Error::from)
+}
@@ -0,0 +1,330 @@
+//! What an agent run is likely to cost, shown before launching an expensive one
+//!
+//! The prompt, the files it references, and the project's and user's CLAUDE.md are
+//! counted with a local approximation of Claude's tokenizer (see [`count_tokens`]) on
+//! top of a fixed allowance for Claude Code's own system prompt and tools, and priced
+//! with the model price table (see `usage`). Output can't be counted up front, so it
+//! is taken as the average of the project's recent runs (see `run_history`), or
+//! `DEFAULT_OUTPUT_TOKENS` without any. Agents re-read context across many turns, so
+//! the median cost of those runs is returned alongside as a sanity check. Runs
+//! estimated at `cost_confirm_usd` (1 USD unless set) or more count as expensive.
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::run_history::{self, RunHistoryFilter};
+use crate::usage::PRICING;
+use crate::{claude_md, claude_profiles, claude_settings, config, orchestrator, store};
+
+/// Claude Code's system prompt and tool definitions, roughly
+const BASE_CONTEXT_TOKENS: u64 = 15_000;
+const DEFAULT_OUTPUT_TOKENS: u64 = 4_000;
+const DEFAULT_CONFIRM_USD: f64 = 1.0;
+/// Priced as this when the model isn't in the table, e.g. the `default` alias
+const FALLBACK_MODEL: &str = "sonnet";
+/// Recent runs the output guess and typical cost are taken from
+const HISTORY_RUNS: usize = 20;
+/// Files read from a referenced directory, and the largest one that's counted
+const MAX_FILES: usize = 500;
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+const SKIPPED_DIRS: &[&str] = &[
+    ".git",
+    ".jj",
+    ".hg",
+    "node_modules",
+    "target",
+    "dist",
+    "build",
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTokens {
+    pub path: String,
+    pub tokens: u64,
+    /// Why it wasn't counted: missing, binary or too large
+    pub skipped: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostEstimate {
+    pub model: String,
+    /// The price table entry used, e.g. `sonnet`
+    pub priced_as: String,
+    pub base_tokens: u64,
+    pub prompt_tokens: u64,
+    /// CLAUDE.md and friends, which Claude loads on its own
+    pub memory_tokens: u64,
+    pub files: Vec<FileTokens>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub input_cost_usd: f64,
+    pub output_cost_usd: f64,
+    pub estimated_cost_usd: f64,
+    /// Median cost of the project's recent runs, when it has any
+    pub typical_cost_usd: Option<f64>,
+    /// Recent runs the output guess and typical cost are based on
+    pub history_runs: usize,
+    /// At or above the confirmation threshold
+    pub expensive: bool,
+    pub confirm_threshold_usd: f64,
+}
+
+/// Approximate Claude token count: each symbol and line break is a token, words one per
+/// four letters, numbers one per three digits, other scripts one per character, and
+/// indentation one per four spaces
+pub fn count_tokens(text: &str) -> u64 {
+    let mut tokens = 0u64;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut run = 1u64;
+        if c.is_ascii_alphabetic() {
+            while chars.next_if(char::is_ascii_alphabetic).is_some() {
+                run += 1;
+            }
+            tokens += run.div_ceil(4);
+        } else if c.is_ascii_digit() {
+            while chars.next_if(char::is_ascii_digit).is_some() {
+                run += 1;
+            }
+            tokens += run.div_ceil(3);
+        } else if c == ' ' || c == '\t' {
+            while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {
+                run += 1;
+            }
+            // A single space belongs to the word after it
+            if run > 1 {
+                tokens += run.div_ceil(4);
+            }
+        } else if !c.is_whitespace() || c == '\n' {
+            tokens += 1;
+        }
+    }
+    tokens
+}
+
+fn count_file(path: &Path) -> FileTokens {
+    let display = path.display().to_string();
+    let skipped = |reason: &str| FileTokens {
+        path: display.clone(),
+        tokens: 0,
+        skipped: Some(reason.to_string()),
+    };
+    match fs::metadata(path) {
+        Err(_) => return skipped("missing"),
+        Ok(metadata) if metadata.len() > MAX_FILE_BYTES => return skipped("too large"),
+        Ok(_) => {}
+    }
+    match fs::read(path) {
+        Ok(bytes) if bytes.contains(&0) => skipped("binary"),
+        Ok(bytes) => FileTokens {
+            path: display.clone(),
+            tokens: count_tokens(&String::from_utf8_lossy(&bytes)),
+            skipped: None,
+        },
+        Err(_) => skipped("unreadable"),
+    }
+}
+
+/// Files under `path` (itself, if a file), depth first, skipping VCS and build output
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if files.len() >= MAX_FILES {
+        return;
+    }
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return;
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    let mut entries: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    entries.sort();
+    for entry in entries {
+        let skipped = entry
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| SKIPPED_DIRS.contains(&name));
+        if !skipped {
+            collect_files(&entry, files);
+        }
+    }
+}
+
+fn memory_tokens(repo: Option<&Path>) -> u64 {
+    let user = claude_profiles::active_config_dir()
+        .or_else(|| dirs::home_dir().map(|home| home.join(".claude")))
+        .map(|dir| dir.join("CLAUDE.md"));
+    let project = repo.into_iter().flat_map(|repo| {
+        claude_md::ROOT_FILES
+            .iter()
+            .map(move |file| repo.join(file))
+    });
+    user.into_iter()
+        .chain(project)
+        .filter(|path| path.is_file())
+        .map(|path| count_file(&path).tokens)
+        .sum()
+}
+
+/// Price per million input and output tokens, and the table entry they come from
+fn pricing(model: &str) -> (&'static str, f64, f64) {
+    PRICING
+        .iter()
+        .find(|(id, ..)| model.contains(id))
+        .or_else(|| PRICING.iter().find(|(id, ..)| *id == FALLBACK_MODEL))
+        .map(|(id, input, output, ..)| (*id, *input, *output))
+        .unwrap_or((FALLBACK_MODEL, 0.0, 0.0))
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+pub fn estimate(
+    task_id: &str,
+    prompt: Option<String>,
+    context_paths: &[String],
+    model: Option<String>,
+) -> Result<CostEstimate, Error> {
+    let (task, project) = store::with_conn(|conn| {
+        let task = store::get_task(conn, task_id)?;
+        let project = match &task {
+            Some(task) => store::get_project(conn, &task.project_id)?,
+            None => None,
+        };
+        Ok((task, project))
+    })?;
+    let task = task.ok_or_else(|| Error::NotFound(format!("Task not found: {}", task_id)))?;
+    let repo = project.and_then(|p| p.repo_path);
+    let prompt = prompt
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| orchestrator::default_prompt(&task));
+    let model = model
+        .or_else(|| claude_settings::effective_model(repo.as_deref()))
+        .unwrap_or_else(|| FALLBACK_MODEL.to_string());
+
+    let mut paths = Vec::new();
+    for path in context_paths {
+        let path = Path::new(path);
+        let path = match &repo {
+            Some(repo) if path.is_relative() => Path::new(repo).join(path),
+            _ => path.to_path_buf(),
+        };
+        collect_files(&path, &mut paths);
+    }
+    let files: Vec<FileTokens> = paths.iter().map(|path| count_file(path)).collect();
+
+    let prompt_tokens = count_tokens(&prompt);
+    let memory_tokens = memory_tokens(repo.as_deref().map(Path::new));
+    let input_tokens = BASE_CONTEXT_TOKENS
+        + prompt_tokens
+        + memory_tokens
+        + files.iter().map(|f| f.tokens).sum::<u64>();
+
+    let history = run_history::query(&RunHistoryFilter {
+        project_id: Some(task.project_id.clone()),
+        limit: Some(HISTORY_RUNS),
+        ..Default::default()
+    })?;
+    let outputs: Vec<u64> = history
+        .iter()
+        .map(|run| run.output_tokens)
+        .filter(|tokens| *tokens > 0)
+        .collect();
+    let output_tokens = match outputs.len() {
+        0 => DEFAULT_OUTPUT_TOKENS,
+        n => outputs.iter().sum::<u64>() / n as u64,
+    };
+    let typical_cost_usd = median(
+        history
+            .iter()
+            .map(|run| run.cost_usd)
+            .filter(|cost| *cost > 0.0)
+            .collect(),
+    );
+
+    let (priced_as, input_price, output_price) = pricing(&model);
+    let input_cost_usd = input_tokens as f64 / 1_000_000.0 * input_price;
+    let output_cost_usd = output_tokens as f64 / 1_000_000.0 * output_price;
+    let estimated_cost_usd = input_cost_usd + output_cost_usd;
+    let confirm_threshold_usd = config::load()
+        .cost_confirm_usd
+        .unwrap_or(DEFAULT_CONFIRM_USD);
+    Ok(CostEstimate {
+        priced_as: priced_as.to_string(),
+        model,
+        base_tokens: BASE_CONTEXT_TOKENS,
+        prompt_tokens,
+        memory_tokens,
+        files,
+        input_tokens,
+        output_tokens,
+        input_cost_usd,
+        output_cost_usd,
+        estimated_cost_usd,
+        expensive: estimated_cost_usd.max(typical_cost_usd.unwrap_or(0.0)) >= confirm_threshold_usd,
+        typical_cost_usd,
+        history_runs: outputs.len(),
+        confirm_threshold_usd,
+    })
+}
+
+/// Estimate what running an agent on the task would cost; `prompt` defaults to the
+/// task's, `context_paths` (files or directories, relative to the project's repository)
+/// are counted as if the agent reads them, `model` defaults to the project's
+#[tauri::command]
+pub async fn estimate_run_cost(
+    task_id: String,
+    prompt: Option<String>,
+    context_paths: Option<Vec<String>>,
+    model: Option<String>,
+) -> Result<CostEstimate, Error> {
+    tauri::async_runtime::spawn_blocking(move || {
+        estimate(&task_id, prompt, &context_paths.unwrap_or_default(), model)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_words_numbers_and_symbols() {
+        assert_eq!(count_tokens(""), 0);
+        assert_eq!(count_tokens("hello world"), 4);
+        assert_eq!(count_tokens("2024"), 2);
+        assert_eq!(count_tokens("fn main() {\n    x\n}"), 10);
+        assert_eq!(count_tokens("日本"), 2);
+        assert_eq!(count_tokens("a\r\nb"), 3);
+    }
+
+    #[test]
+    fn prices_by_model_family() {
+        assert_eq!(pricing("claude-opus-4-6").0, "opus-4-6");
+        assert_eq!(pricing("claude-3-opus-20240229").0, "opus");
+        assert_eq!(pricing("claude-haiku-4-5").0, "haiku-4-5");
+        assert_eq!(pricing("something-new").0, FALLBACK_MODEL);
+    }
+
+    #[test]
+    fn takes_the_median() {
+        assert_eq!(median(Vec::new()), None);
+        assert_eq!(median(vec![3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(vec![4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+}
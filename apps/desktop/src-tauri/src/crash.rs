@@ -0,0 +1,249 @@
+//! Crash reports and unclean-exit recovery
+//!
+//! A panic hook writes a report (backtrace, recent server output, app state) to
+//! `crashes/` in the data directory. A marker file records the running app and server
+//! pids; if it is still there on the next launch the previous run died without shutting
+//! down, so a report is synthesized and the orphaned server is stopped rather than
+//! mistaken for one that is "already running".
+
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::panic;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::{config, process, profiles, server_logs, server_output, store, system_log};
+
+const CRASH_DIR: &str = "crashes";
+const MARKER_FILE: &str = "running.json";
+const MAX_REPORTS: usize = 10;
+const LOG_LINES: usize = 200;
+
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub id: String,
+    pub timestamp: i64,
+    /// Panic message; a generic note for an unclean exit with no panic recorded
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: Option<String>,
+    pub recent_logs: Vec<String>,
+    pub app_version: String,
+    pub server_pid: Option<u32>,
+    #[serde(default)]
+    pub dismissed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RunMarker {
+    app_pid: u32,
+    server_pid: Option<u32>,
+    started_at: i64,
+}
+
+fn crash_dir() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join(CRASH_DIR))
+}
+
+fn marker_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join(MARKER_FILE))
+}
+
+fn write_marker(marker: &RunMarker) {
+    let Some(path) = marker_path() else {
+        return;
+    };
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(contents) = serde_json::to_string(marker) {
+        let _ = fs::write(path, contents);
+    }
+}
+
+fn save_report(report: &CrashReport) -> Result<(), String> {
+    let dir = crash_dir().ok_or("Could not determine data directory")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create crash directory: {}", e))?;
+    let contents = serde_json::to_string_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(dir.join(format!("{}.json", report.id)), contents)
+        .map_err(|e| format!("Failed to write crash report: {}", e))
+}
+
+/// All reports, newest first
+fn load_reports() -> Vec<CrashReport> {
+    let Some(entries) = crash_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut reports: Vec<CrashReport> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| fs::read_to_string(entry.path()).ok())
+        .filter_map(|contents| serde_json::from_str(&contents).ok())
+        .collect();
+    reports.sort_by_key(|report| std::cmp::Reverse(report.timestamp));
+    reports
+}
+
+fn prune_reports() {
+    let Some(dir) = crash_dir() else {
+        return;
+    };
+    for report in load_reports().into_iter().skip(MAX_REPORTS) {
+        let _ = fs::remove_file(dir.join(format!("{}.json", report.id)));
+    }
+}
+
+fn recent_logs() -> Vec<String> {
+    // try_lock: a panic while holding the lock must not deadlock the hook
+    RECENT_LOGS
+        .try_lock()
+        .map(|logs| logs.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn server_pid() -> Option<u32> {
+    crate::SERVER_PROCESS
+        .try_lock()
+        .ok()
+        .and_then(|server| server.as_ref().map(|child| child.id()))
+}
+
+fn report(message: String) -> CrashReport {
+    let timestamp = store::now_ms();
+    CrashReport {
+        id: timestamp.to_string(),
+        timestamp,
+        message,
+        location: None,
+        thread: None,
+        backtrace: None,
+        recent_logs: recent_logs(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        server_pid: server_pid(),
+        dismissed: false,
+    }
+}
+
+/// Keep the last lines of the server's stdout/stderr for crash reports, log them for
+/// search, and scan them for readiness and known failures
+pub fn capture_output(reader: impl Read + Send + 'static) {
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            server_output::scan(&line);
+            server_logs::append(&line);
+            system_log::server_line(&line);
+            if let Ok(mut logs) = RECENT_LOGS.lock() {
+                if logs.len() == LOG_LINES {
+                    logs.pop_front();
+                }
+                logs.push_back(line);
+            }
+        }
+    });
+}
+
+/// Record the server we spawned so an unclean exit can clean it up next launch
+pub fn set_server_pid(pid: Option<u32>) {
+    let started_at = marker_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str::<RunMarker>(&contents).ok())
+        .map(|marker| marker.started_at)
+        .unwrap_or_else(store::now_ms);
+    write_marker(&RunMarker {
+        app_pid: std::process::id(),
+        server_pid: pid,
+        started_at,
+    });
+}
+
+/// Install the panic hook and check how the previous run ended; call before starting the server
+pub fn init() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let mut crash = report(
+            info.payload()
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| info.payload().downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Unknown panic".to_string()),
+        );
+        crash.location = info.location().map(|l| l.to_string());
+        crash.thread = thread::current().name().map(str::to_string);
+        crash.backtrace = Some(Backtrace::force_capture().to_string());
+        if let Err(e) = save_report(&crash) {
+            eprintln!("[Claude PM] {}", e);
+        }
+        default_hook(info);
+    }));
+
+    let previous = marker_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str::<RunMarker>(&contents).ok());
+    if let Some(previous) = previous {
+        eprintln!("[Claude PM] Previous run did not exit cleanly");
+        // A panic report already covers it; otherwise note the unclean exit
+        let reported = load_reports()
+            .first()
+            .is_some_and(|r| r.timestamp >= previous.started_at);
+        if !reported {
+            let mut crash = report("Claude PM did not shut down cleanly".to_string());
+            crash.server_pid = previous.server_pid;
+            let _ = save_report(&crash);
+        }
+        // Its server outlived it and would otherwise pass for "already running"
+        // (only if the port is still taken, so a recycled pid isn't signalled for nothing)
+        if let Some(pid) = previous
+            .server_pid
+            .filter(|_| crate::is_server_running(profiles::active().port))
+        {
+            println!("[Claude PM] Stopping orphaned server (PID: {})", pid);
+            process::kill_group(pid);
+            for _ in 0..25 {
+                if !crate::is_server_running(profiles::active().port) {
+                    break;
+                }
+                thread::sleep(Duration::from_millis(200));
+            }
+        }
+    }
+    prune_reports();
+
+    write_marker(&RunMarker {
+        app_pid: std::process::id(),
+        server_pid: None,
+        started_at: store::now_ms(),
+    });
+}
+
+/// Mark this run as having exited cleanly
+pub fn clean_exit() {
+    if let Some(path) = marker_path() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// The most recent crash the user hasn't dismissed yet
+#[tauri::command]
+pub fn get_last_crash() -> Option<CrashReport> {
+    load_reports().into_iter().find(|report| !report.dismissed)
+}
+
+#[tauri::command]
+pub fn dismiss_crash(id: String) -> Result<(), Error> {
+    let mut report = load_reports()
+        .into_iter()
+        .find(|report| report.id == id)
+        .ok_or_else(|| Error::NotFound(format!("No crash report with id {}", id)))?;
+    report.dismissed = true;
+    save_report(&report).map_err(Error::from)
+}
@@ -0,0 +1,400 @@
+//! Moving the data directory (database, attachments, logs) somewhere else
+//!
+//! `move_data_dir` copies everything into the new location and verifies it before the
+//! app switches over: files are compared by SHA-256, and the database is copied with
+//! `VACUUM INTO` (consistent while in use) and must pass `PRAGMA integrity_check`. The
+//! database stays locked from the snapshot until the config points at the new
+//! directory, so no write lands in the old copy unseen. The source is kept unless asked
+//! to delete it, and then only the files that moved are removed.
+//!
+//! Settings stay where they are: on macOS and Windows the default data directory is the
+//! config directory too, so its config files are neither copied nor deleted.
+//!
+//! A configured directory that's missing at launch (an unplugged drive) isn't recreated
+//! empty; the app runs on the default directory and tells the user, who can reconnect
+//! the drive and restart, or go back to the default with `reset_data_dir`.
+//!
+//! Events:
+//! - `data-dir-missing` with the path of the configured directory, at launch
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::notifications::{self, NotificationRequest};
+use crate::{
+    approval_policy, config, integrations, json_file, onboarding, plugins, project_template,
+    scheduler, scripting, search, server_logs, store, vault, window_state,
+};
+
+/// Marker of the running app (see `crash`); left behind it would look like a crash
+const RUN_MARKER: &str = "running.json";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDirStatus {
+    /// Directory in use
+    pub path: Option<String>,
+    pub default_path: Option<String>,
+    /// A directory other than the default is configured
+    pub custom: bool,
+    /// The configured directory, if it was missing at launch
+    pub missing: Option<String>,
+}
+
+fn status() -> DataDirStatus {
+    DataDirStatus {
+        path: config::data_dir().map(|p| p.display().to_string()),
+        default_path: config::default_data_dir().map(|p| p.display().to_string()),
+        custom: config::load().data_dir.is_some(),
+        missing: config::missing_data_dir().map(|p| p.display().to_string()),
+    }
+}
+
+/// What the config directory holds besides `config.json`
+const CONFIG_ENTRIES: &[&str] = &[
+    config::CONFIG_FILE,
+    approval_policy::RULES_FILE,
+    integrations::INTEGRATIONS_FILE,
+    json_file::BACKUPS_DIR,
+    onboarding::ONBOARDING_FILE,
+    plugins::PLUGINS_DIR,
+    plugins::STATE_FILE,
+    project_template::TEMPLATES_DIR,
+    scheduler::SCHEDULES_FILE,
+    scripting::SCRIPTS_FILE,
+    vault::VAULT_FILE,
+    window_state::STATE_FILE,
+];
+
+/// Paths under `source`, relative to it, that belong to the config directory
+fn config_paths(source: &Path, config_dir: Option<&Path>) -> Vec<PathBuf> {
+    match config_dir.and_then(|dir| dir.strip_prefix(source).ok()) {
+        Some(relative) if relative.as_os_str().is_empty() => {
+            CONFIG_ENTRIES.iter().map(PathBuf::from).collect()
+        }
+        Some(relative) => vec![relative.to_path_buf()],
+        None => Vec::new(),
+    }
+}
+
+/// The database and its WAL files are copied through SQLite, not byte for byte
+fn is_database(name: &str) -> bool {
+    name == store::DB_FILE
+        || name
+            .strip_prefix(store::DB_FILE)
+            .is_some_and(|suffix| suffix == "-wal" || suffix == "-shm")
+}
+
+fn sha256(path: &Path) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Copy `from` into `to` except `skipped`, returning the copied files relative to `from`
+fn copy_tree(
+    from: &Path,
+    to: &Path,
+    relative: &Path,
+    skipped: &[PathBuf],
+    copied: &mut Vec<PathBuf>,
+) -> Result<(), String> {
+    let dir = from.join(relative);
+    let entries =
+        fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    fs::create_dir_all(to.join(relative))
+        .map_err(|e| format!("Failed to create {}: {}", to.join(relative).display(), e))?;
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name();
+        let path = relative.join(&name);
+        if skipped.contains(&path) {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            copy_tree(from, to, &path, skipped, copied)?;
+        } else if file_type.is_file() {
+            if relative.as_os_str().is_empty()
+                && (is_database(&name.to_string_lossy()) || name == RUN_MARKER)
+            {
+                continue;
+            }
+            fs::copy(entry.path(), to.join(&path))
+                .map_err(|e| format!("Failed to copy {}: {}", path.display(), e))?;
+            copied.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn verify_files(from: &Path, to: &Path, files: &[PathBuf]) -> Result<(), String> {
+    for file in files {
+        let source = sha256(&from.join(file));
+        let copy = sha256(&to.join(file));
+        match (source, copy) {
+            (Ok(source), Ok(copy)) if source == copy => {}
+            // Logs keep being appended to while we copy; a shorter copy is still valid
+            _ if file.starts_with(server_logs::LOG_DIR)
+                || file.extension().is_some_and(|ext| ext == "log") => {}
+            _ => {
+                return Err(format!(
+                    "Copy of {} doesn't match the original",
+                    file.display()
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn verify_database(dir: &Path) -> Result<(), String> {
+    let conn = store::open(&dir.join(store::DB_FILE))
+        .map_err(|e| format!("Failed to open the copied database: {}", e))?;
+    let result: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to check the copied database: {}", e))?;
+    if result == "ok" {
+        Ok(())
+    } else {
+        Err(format!("Copied database is damaged: {}", result))
+    }
+}
+
+fn validate_target(source: &Path, target: &Path) -> Result<(), Error> {
+    if !target.is_absolute() {
+        return Err(Error::InvalidInput(format!(
+            "{} is not an absolute path",
+            target.display()
+        )));
+    }
+    if target.starts_with(source) || source.starts_with(target) {
+        return Err(Error::InvalidInput(
+            "The new location can't contain or be inside the current data directory".to_string(),
+        ));
+    }
+    if config::config_dir().is_some_and(|dir| dir.starts_with(target)) {
+        return Err(Error::InvalidInput(
+            "The new location can't contain the settings directory".to_string(),
+        ));
+    }
+    if target.exists() {
+        let empty = fs::read_dir(target)
+            .map_err(|e| format!("Failed to read {}: {}", target.display(), e))?
+            .next()
+            .is_none();
+        if !empty {
+            return Err(Error::InvalidInput(format!(
+                "{} is not empty",
+                target.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn relocate(target: PathBuf, delete_source: bool) -> Result<DataDirStatus, Error> {
+    let source = config::data_dir().ok_or("Could not determine data directory")?;
+    validate_target(&source, &target)?;
+    println!(
+        "[Claude PM] Moving data directory from {} to {}",
+        source.display(),
+        target.display()
+    );
+
+    let mut skipped = config_paths(&source, config::config_dir().as_deref());
+    // Rebuilt at the new location rather than copied while it's being written
+    skipped.push(PathBuf::from(search::INDEX_DIR));
+    let mut copied = Vec::new();
+    let result = (|| {
+        if source.is_dir() {
+            copy_tree(&source, &target, Path::new(""), &skipped, &mut copied)?;
+        } else {
+            fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+        }
+        verify_files(&source, &target, &copied)?;
+        store::relocate(&target, || {
+            verify_database(&target)?;
+            config::set_data_dir(&target)
+        })
+    })();
+    if let Err(e) = result {
+        // Nothing points at the partial copy; don't leave it behind
+        let _ = fs::remove_dir_all(&target);
+        return Err(Error::Internal(e));
+    }
+    server_logs::reopen();
+    crate::crash::set_server_pid(
+        crate::SERVER_PROCESS
+            .lock()
+            .ok()
+            .and_then(|server| server.as_ref().map(|child| child.id())),
+    );
+    let _ = fs::remove_file(source.join(RUN_MARKER));
+
+    if delete_source {
+        remove_moved(&source, &copied);
+    }
+    println!("[Claude PM] Data directory is now {}", target.display());
+    Ok(status())
+}
+
+/// Remove the moved files, the database and the search index from `source`, then the
+/// directories they leave empty; anything else there, settings included, stays
+fn remove_moved(source: &Path, copied: &[PathBuf]) {
+    let _ = fs::remove_dir_all(source.join(search::INDEX_DIR));
+    let database = [
+        store::DB_FILE.to_string(),
+        format!("{}-wal", store::DB_FILE),
+        format!("{}-shm", store::DB_FILE),
+    ];
+    let files = copied
+        .iter()
+        .cloned()
+        .chain(database.iter().map(PathBuf::from));
+    for file in files {
+        match fs::remove_file(source.join(&file)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => eprintln!(
+                "[Claude PM] Failed to remove {} from the old data directory: {}",
+                file.display(),
+                e
+            ),
+        }
+    }
+    // Deepest first; a directory that still has something in it is kept
+    let dirs: BTreeSet<&Path> = copied
+        .iter()
+        .flat_map(|file| file.ancestors().skip(1))
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .collect();
+    let mut dirs: Vec<&Path> = dirs.into_iter().collect();
+    dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+    for dir in dirs {
+        let _ = fs::remove_dir(source.join(dir));
+    }
+    let _ = fs::remove_dir(source);
+}
+
+/// Tell the user if the configured data directory wasn't there at launch
+pub fn start(app: &AppHandle) {
+    let Some(missing) = config::missing_data_dir() else {
+        return;
+    };
+    let path = missing.display().to_string();
+    let _ = app.emit("data-dir-missing", &path);
+    notifications::notify(
+        app,
+        NotificationRequest {
+            title: "Data directory not found".to_string(),
+            body: format!(
+                "{} isn't available, so Claude PM is using its default data directory. Reconnect the drive and restart to use it again.",
+                path
+            ),
+            key: Some("data-dir-missing".to_string()),
+            ..Default::default()
+        },
+    );
+}
+
+#[tauri::command]
+pub fn get_data_dir_status() -> DataDirStatus {
+    status()
+}
+
+/// Copy the data directory to `target` (which must be empty or not exist), verify the
+/// copy and switch to it; with `delete_source` the old directory is removed afterwards
+#[tauri::command]
+pub async fn move_data_dir(target: String, delete_source: bool) -> Result<DataDirStatus, Error> {
+    tauri::async_runtime::spawn_blocking(move || relocate(PathBuf::from(target), delete_source))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Forget the configured directory and use the default from the next launch; its data
+/// isn't copied back
+#[tauri::command]
+pub fn reset_data_dir() -> Result<DataDirStatus, Error> {
+    config::update(|c| c.data_dir = None)?;
+    Ok(status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "x").unwrap();
+    }
+
+    #[test]
+    fn finds_the_config_directory_inside_the_source() {
+        let source = Path::new("/support/com.claudepm.desktop");
+        let shared = config_paths(source, Some(source));
+        assert!(shared.contains(&PathBuf::from(config::CONFIG_FILE)));
+        assert!(shared.contains(&PathBuf::from(vault::VAULT_FILE)));
+        assert_eq!(
+            config_paths(source, Some(&source.join("settings"))),
+            [PathBuf::from("settings")]
+        );
+        assert!(config_paths(source, Some(Path::new("/config/claudepm"))).is_empty());
+    }
+
+    /// The default data directory on macOS, which holds the settings too
+    #[test]
+    fn moves_data_but_not_settings_out_of_a_shared_directory() {
+        let root = std::env::temp_dir().join(format!("claudepm-move-{}", store::new_id()));
+        let (source, target) = (root.join("source"), root.join("target"));
+        for file in [
+            config::CONFIG_FILE,
+            vault::VAULT_FILE,
+            "backups/claude-md/1.json",
+            "attachments/a1-notes.txt",
+            "logs/server.log",
+            store::DB_FILE,
+        ] {
+            write(&source.join(file));
+        }
+
+        let mut copied = Vec::new();
+        let skipped = config_paths(&source, Some(&source));
+        copy_tree(&source, &target, Path::new(""), &skipped, &mut copied).unwrap();
+        copied.sort();
+        assert_eq!(
+            copied,
+            [
+                PathBuf::from("attachments/a1-notes.txt"),
+                PathBuf::from("logs/server.log")
+            ]
+        );
+        assert!(!target.join(config::CONFIG_FILE).exists());
+        assert!(!target.join("backups").exists());
+
+        remove_moved(&source, &copied);
+        assert!(source.join(config::CONFIG_FILE).exists());
+        assert!(source.join(vault::VAULT_FILE).exists());
+        assert!(source.join("backups/claude-md/1.json").exists());
+        assert!(!source.join("attachments").exists());
+        assert!(!source.join("logs").exists());
+        assert!(!source.join(store::DB_FILE).exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
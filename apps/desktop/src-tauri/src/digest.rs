@@ -0,0 +1,255 @@
+//! Notification digests: non-critical notifications batched into a periodic summary
+//!
+//! With digests on, a notification whose category isn't critical is held instead of
+//! shown, and everything held goes out together once the window (15 minutes by
+//! default) has passed since the first one, as e.g. "4 tasks finished, 1 needs review".
+//! Critical categories such as approvals and failures are always delivered right away;
+//! which categories count as critical can be overridden per category.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::dnd::Gate;
+use crate::error::Error;
+use crate::notifications::{self, NotificationRequest};
+use crate::{config, store};
+
+const DEFAULT_INTERVAL_MINUTES: u32 = 15;
+/// How often the watcher checks whether the window has passed
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DigestSettings {
+    /// Batch non-critical notifications instead of showing each one
+    pub enabled: bool,
+    /// Minutes between the first held notification and the summary (15 when unset)
+    pub interval_minutes: Option<u32>,
+    /// Category to whether it is delivered right away; unlisted categories use the defaults
+    pub critical: BTreeMap<String, bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestState {
+    pub enabled: bool,
+    pub interval_minutes: u32,
+    /// Notifications waiting for the next summary
+    pub pending: usize,
+    /// Held notifications per category
+    pub categories: BTreeMap<String, usize>,
+    pub next_flush_at: Option<i64>,
+}
+
+struct Digest {
+    held: Vec<NotificationRequest>,
+    /// When the first held notification arrived
+    opened_at: Option<i64>,
+}
+
+static DIGEST: Mutex<Digest> = Mutex::new(Digest {
+    held: Vec::new(),
+    opened_at: None,
+});
+static WATCHER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+fn default_critical(category: &str) -> bool {
+    // Waiting on the user, answering something they just did, or something going wrong
+    matches!(
+        category,
+        "approval"
+            | "capture"
+            | "automation"
+            | "failed"
+            | "watchdog"
+            | "budget"
+            | "pomodoro"
+            | "focus"
+    )
+}
+
+fn is_critical(settings: &DigestSettings, category: Option<&str>) -> bool {
+    let category = category.unwrap_or("general");
+    settings
+        .critical
+        .get(category)
+        .copied()
+        .unwrap_or_else(|| default_critical(category))
+}
+
+/// Whether `category` counts as critical; `working_hours` shares the setting
+pub fn is_critical_category(category: Option<&str>) -> bool {
+    is_critical(&config::load().digest, category)
+}
+
+fn interval_ms(settings: &DigestSettings) -> i64 {
+    settings
+        .interval_minutes
+        .unwrap_or(DEFAULT_INTERVAL_MINUTES)
+        .max(1) as i64
+        * 60_000
+}
+
+/// "4 tasks finished", "1 needs review"
+fn phrase(category: &str, count: usize) -> String {
+    let one = count == 1;
+    match category {
+        "completed" => format!("{} {} finished", count, if one { "task" } else { "tasks" }),
+        "review" => format!("{} {} review", count, if one { "needs" } else { "need" }),
+        "ready" => format!("{} {} unblocked", count, if one { "task" } else { "tasks" }),
+        "failed" => format!("{} failed", count),
+        "ci" => format!("{} CI {}", count, if one { "update" } else { "updates" }),
+        "report" => format!("{} {}", count, if one { "report" } else { "reports" }),
+        "agent" => format!(
+            "{} agent {}",
+            count,
+            if one { "message" } else { "messages" }
+        ),
+        other => format!(
+            "{} {} {}",
+            count,
+            other.replace('-', " "),
+            if one { "notification" } else { "notifications" }
+        ),
+    }
+}
+
+/// One notification for everything held, counted by category in order of arrival
+fn summarize(held: &[NotificationRequest]) -> NotificationRequest {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for request in held {
+        let category = request.category.as_deref().unwrap_or("general");
+        match counts.iter_mut().find(|(c, _)| *c == category) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((category, 1)),
+        }
+    }
+    let body: Vec<String> = counts
+        .iter()
+        .map(|(category, count)| phrase(category, *count))
+        .collect();
+    // Open what they all point at; otherwise the main window
+    let first = held.first().and_then(|r| r.target.clone());
+    let target = first.filter(|t| held.iter().all(|r| r.target.as_ref() == Some(t)));
+    NotificationRequest {
+        title: "Claude PM digest".to_string(),
+        body: body.join(", "),
+        key: Some(format!("digest:{}", store::now_ms())),
+        target,
+        category: Some("digest".to_string()),
+        ..Default::default()
+    }
+}
+
+fn take_held() -> Vec<NotificationRequest> {
+    match DIGEST.lock() {
+        Ok(mut digest) => {
+            digest.opened_at = None;
+            std::mem::take(&mut digest.held)
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Deliver everything held as one summary (or itself, if only one); returns how many
+fn flush(app: &AppHandle) -> usize {
+    let held = take_held();
+    let count = held.len();
+    match count {
+        0 => {}
+        1 => notifications::deliver_now(app, held.into_iter().next().unwrap_or_default()),
+        _ => notifications::deliver_now(app, summarize(&held)),
+    }
+    count
+}
+
+/// Flush once the window has passed, until nothing is held
+fn ensure_watcher(app: &AppHandle) {
+    if WATCHER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            let Some(opened_at) = DIGEST.lock().ok().and_then(|d| d.opened_at) else {
+                break;
+            };
+            if store::now_ms() - opened_at >= interval_ms(&config::load().digest) {
+                flush(&app);
+                break;
+            }
+        }
+        WATCHER_RUNNING.store(false, Ordering::SeqCst);
+        // Something may have been held between the last check and the flag clearing
+        if DIGEST.lock().is_ok_and(|d| d.opened_at.is_some()) {
+            ensure_watcher(&app);
+        }
+    });
+}
+
+/// Hold the notification for the next summary unless digests are off or it's critical
+pub fn gate(app: &AppHandle, request: &NotificationRequest) -> Gate {
+    let settings = config::load().digest;
+    if !settings.enabled || is_critical(&settings, request.category.as_deref()) {
+        return Gate::Deliver;
+    }
+    let Ok(mut digest) = DIGEST.lock() else {
+        return Gate::Deliver;
+    };
+    digest.held.push(request.clone());
+    digest.opened_at.get_or_insert_with(store::now_ms);
+    drop(digest);
+    ensure_watcher(app);
+    Gate::Held
+}
+
+#[tauri::command]
+pub fn get_digest_state() -> DigestState {
+    let settings = config::load().digest;
+    let (categories, pending, opened_at) = match DIGEST.lock() {
+        Ok(digest) => {
+            let mut categories = BTreeMap::new();
+            for request in &digest.held {
+                let category = request.category.as_deref().unwrap_or("general");
+                *categories.entry(category.to_string()).or_insert(0) += 1;
+            }
+            (categories, digest.held.len(), digest.opened_at)
+        }
+        Err(_) => (BTreeMap::new(), 0, None),
+    };
+    DigestState {
+        enabled: settings.enabled,
+        interval_minutes: (interval_ms(&settings) / 60_000) as u32,
+        pending,
+        categories,
+        next_flush_at: opened_at.map(|at| at + interval_ms(&settings)),
+    }
+}
+
+/// Send the summary now instead of waiting for the window; returns how many it covered
+#[tauri::command]
+pub fn flush_digest_now(app: AppHandle) -> usize {
+    flush(&app)
+}
+
+/// Turning digests off delivers whatever is held
+#[tauri::command]
+pub fn set_digest_settings(app: AppHandle, settings: DigestSettings) -> Result<(), Error> {
+    if settings.interval_minutes == Some(0) {
+        return Err(Error::InvalidInput(
+            "The digest interval must be at least a minute".to_string(),
+        ));
+    }
+    let enabled = settings.enabled;
+    config::update(|c| c.digest = settings)?;
+    if !enabled {
+        flush(&app);
+    }
+    Ok(())
+}
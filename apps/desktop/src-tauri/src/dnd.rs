@@ -0,0 +1,192 @@
+//! Do Not Disturb / Focus awareness for notifications
+//!
+//! While Focus is on, each notification category follows a policy: dropped, queued
+//! until Focus ends, or delivered anyway (for "agent needs approval" style events).
+//! A focus session in the app (see `focus_session`) counts as Focus being on.
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::error::Error;
+use crate::notifications::{self, NotificationRequest};
+use crate::{config, focus_session, process};
+
+/// Focus state is read from disk, so cache it briefly
+const STATE_CACHE_TTL: Duration = Duration::from_secs(5);
+/// How often to check whether Focus has ended while notifications are queued
+const QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FocusPolicy {
+    Suppress,
+    Queue,
+    BreakThrough,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusState {
+    /// False on platforms where Focus/DND state can't be detected
+    pub supported: bool,
+    pub active: bool,
+    pub queued: usize,
+}
+
+/// Outcome of checking a notification against the Focus policy
+pub enum Gate {
+    Deliver,
+    Held,
+}
+
+static CACHED_STATE: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+static QUEUE: Mutex<Vec<NotificationRequest>> = Mutex::new(Vec::new());
+static QUEUE_WATCHER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+fn default_policy(category: &str) -> FocusPolicy {
+    match category {
+        // A capture confirmation answers something the user just did
+        "approval" | "capture" => FocusPolicy::BreakThrough,
+        _ => FocusPolicy::Queue,
+    }
+}
+
+fn policy_for(category: Option<&str>) -> FocusPolicy {
+    let category = category.unwrap_or("general");
+    config::load()
+        .focus_policies
+        .get(category)
+        .copied()
+        .unwrap_or_else(|| default_policy(category))
+}
+
+/// macOS 12+ records active Focus modes as assertions in this file
+fn assertions_path() -> Option<PathBuf> {
+    env::var("HOME")
+        .ok()
+        .map(|home| PathBuf::from(home).join("Library/DoNotDisturb/DB/Assertions.json"))
+}
+
+fn focus_from_assertions() -> Option<bool> {
+    let contents = fs::read_to_string(assertions_path()?).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    let records = json["data"]
+        .as_array()?
+        .iter()
+        .filter_map(|entry| entry["storeAssertionRecords"].as_array())
+        .map(|records| records.len())
+        .sum::<usize>();
+    Some(records > 0)
+}
+
+/// Pre-Monterey Do Not Disturb flag
+fn focus_from_defaults() -> Option<bool> {
+    let output = process::output(Command::new("defaults").args([
+        "-currentHost",
+        "read",
+        "com.apple.notificationcenterui",
+        "doNotDisturb",
+    ]))
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim() == "1")
+}
+
+fn detect_focus() -> Option<bool> {
+    if !cfg!(target_os = "macos") {
+        return None;
+    }
+    focus_from_assertions().or_else(focus_from_defaults)
+}
+
+pub fn is_focus_active() -> bool {
+    if let Ok(cached) = CACHED_STATE.lock() {
+        if let Some((at, active)) = *cached {
+            if at.elapsed() < STATE_CACHE_TTL {
+                return active;
+            }
+        }
+    }
+
+    let active = detect_focus().unwrap_or(false);
+    if let Ok(mut cached) = CACHED_STATE.lock() {
+        *cached = Some((Instant::now(), active));
+    }
+    active
+}
+
+/// OS Focus or an in-app focus session
+fn holding() -> bool {
+    is_focus_active() || focus_session::is_active()
+}
+
+/// Deliver queued notifications once Focus ends
+fn ensure_queue_watcher(app: &AppHandle) {
+    if QUEUE_WATCHER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        loop {
+            thread::sleep(QUEUE_POLL_INTERVAL);
+            if holding() {
+                continue;
+            }
+            let queued = QUEUE.lock().map(|mut q| std::mem::take(&mut *q));
+            for request in queued.unwrap_or_default() {
+                notifications::deliver_now(&app, request);
+            }
+            break;
+        }
+        QUEUE_WATCHER_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Apply the Focus policy for the notification's category
+pub fn gate(app: &AppHandle, request: &NotificationRequest) -> Gate {
+    if !holding() {
+        return Gate::Deliver;
+    }
+
+    match policy_for(request.category.as_deref()) {
+        FocusPolicy::BreakThrough => Gate::Deliver,
+        FocusPolicy::Suppress => Gate::Held,
+        FocusPolicy::Queue => {
+            if let Ok(mut queue) = QUEUE.lock() {
+                queue.push(request.clone());
+            }
+            ensure_queue_watcher(app);
+            Gate::Held
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_focus_state() -> FocusState {
+    let detected = detect_focus();
+    FocusState {
+        supported: detected.is_some(),
+        active: detected.unwrap_or(false),
+        queued: QUEUE.lock().map(|q| q.len()).unwrap_or(0),
+    }
+}
+
+/// Set what happens to a notification category (e.g. `approval`, `completed`) during Focus
+#[tauri::command]
+pub fn set_focus_policy(category: String, policy: FocusPolicy) -> Result<(), Error> {
+    config::update(|c| {
+        c.focus_policies.insert(category, policy);
+    })
+    .map(|_| ())
+    .map_err(Error::from)
+}
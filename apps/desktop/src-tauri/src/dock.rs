@@ -0,0 +1,35 @@
+//! Dock (macOS) / taskbar badge and progress indicators
+
+use tauri::window::{ProgressBarState, ProgressBarStatus};
+use tauri::AppHandle;
+
+use crate::error::Error;
+use crate::windows::main_window;
+
+/// Show `count` on the dock icon badge; 0 clears it
+#[tauri::command]
+pub fn set_badge_count(app: AppHandle, count: u32) -> Result<(), Error> {
+    let badge = if count == 0 { None } else { Some(count as i64) };
+    main_window(&app)?
+        .set_badge_count(badge)
+        .map_err(|e| format!("Failed to set badge: {}", e).into())
+}
+
+/// Show a progress bar on the dock icon; `fraction` is 0.0–1.0, `None` hides it
+#[tauri::command]
+pub fn set_dock_progress(app: AppHandle, fraction: Option<f64>) -> Result<(), Error> {
+    let state = match fraction {
+        Some(fraction) => ProgressBarState {
+            status: Some(ProgressBarStatus::Normal),
+            progress: Some((fraction.clamp(0.0, 1.0) * 100.0).round() as u64),
+        },
+        None => ProgressBarState {
+            status: Some(ProgressBarStatus::None),
+            progress: None,
+        },
+    };
+
+    main_window(&app)?
+        .set_progress_bar(state)
+        .map_err(|e| format!("Failed to set dock progress: {}", e).into())
+}
@@ -0,0 +1,274 @@
+//! Dock menu (macOS) and taskbar jump list (Windows)
+//!
+//! Both offer the recent projects (see `app_menu`) plus New Task and Restart Server. Each
+//! entry is a `claudepm://` URL run through `automation`, so it navigates and reports
+//! exactly like the equivalent deep link. Jump list entries launch the executable with
+//! the URL as its argument, which is how Windows hands deep links to the app anyway.
+
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use tauri::{AppHandle, Url};
+
+use crate::{automation, store};
+
+const RECENT_LIMIT: usize = 5;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static ENTRIES: Mutex<Entries> = Mutex::new(Entries {
+    recent: Vec::new(),
+    actions: Vec::new(),
+});
+
+#[derive(Clone)]
+struct Entry {
+    label: String,
+    url: String,
+}
+
+#[derive(Clone)]
+struct Entries {
+    recent: Vec<Entry>,
+    actions: Vec<Entry>,
+}
+
+impl Entries {
+    /// Recent projects then actions, the order menu item tags index into
+    fn all(&self) -> impl Iterator<Item = &Entry> {
+        self.recent.iter().chain(&self.actions)
+    }
+}
+
+fn load_entries() -> Entries {
+    let recent = store::recent_projects(RECENT_LIMIT)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|project| Entry {
+            url: Url::parse_with_params(
+                "claudepm://open",
+                &[("path", format!("/projects/{}", project.id))],
+            )
+            .map(String::from)
+            .unwrap_or_default(),
+            label: project.name,
+        })
+        .collect();
+    let actions = vec![
+        Entry {
+            label: "New Task".to_string(),
+            url: "claudepm://tasks/new".to_string(),
+        },
+        Entry {
+            label: "Restart Server".to_string(),
+            url: "claudepm://server/restart".to_string(),
+        },
+    ];
+    Entries { recent, actions }
+}
+
+/// Run the entry at `index` (see `Entries::all`) off the UI thread
+fn activate(index: usize) {
+    let entry = ENTRIES
+        .lock()
+        .ok()
+        .and_then(|entries| entries.all().nth(index).cloned());
+    if let (Some(entry), Some(app)) = (entry, APP.get()) {
+        let app = app.clone();
+        thread::spawn(move || automation::open_url(&app, &entry.url));
+    }
+}
+
+/// Rebuild the entries, e.g. after the recent projects changed
+pub fn refresh() {
+    let entries = load_entries();
+    #[cfg(windows)]
+    {
+        let entries = entries.clone();
+        // COM wants its own apartment; don't borrow whichever thread called us
+        thread::spawn(move || {
+            if let Err(e) = jump_list::commit(&entries) {
+                eprintln!("[Claude PM] Failed to update jump list: {}", e);
+            }
+        });
+    }
+    if let Ok(mut current) = ENTRIES.lock() {
+        *current = entries;
+    }
+}
+
+/// Install the dock menu (macOS) and build the jump list (Windows); call on the main thread
+pub fn init(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+    #[cfg(target_os = "macos")]
+    macos::install();
+    refresh();
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use objc2::ffi;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject, ClassBuilder, Imp, NSObject, Sel};
+    use objc2::{sel, ClassType, MainThreadMarker, MainThreadOnly};
+    use objc2_app_kit::{NSApplication, NSMenu, NSMenuItem};
+    use objc2_foundation::NSString;
+    use std::ffi::CStr;
+
+    use super::ENTRIES;
+
+    const TARGET_CLASS: &CStr = c"ClaudePMDockMenuTarget";
+
+    type DockMenuFn = extern "C-unwind" fn(&AnyObject, Sel, *mut AnyObject) -> *mut NSMenu;
+
+    /// `+[ClaudePMDockMenuTarget openDockItem:]`; the item's tag is its entry index
+    extern "C-unwind" fn open_item(_cls: &AnyClass, _cmd: Sel, sender: &NSMenuItem) {
+        super::activate(sender.tag() as usize);
+    }
+
+    /// `-applicationDockMenu:`, built fresh each time AppKit asks
+    extern "C-unwind" fn dock_menu(
+        _this: &AnyObject,
+        _cmd: Sel,
+        _app: *mut AnyObject,
+    ) -> *mut NSMenu {
+        let (Some(mtm), Some(target)) = (MainThreadMarker::new(), AnyClass::get(TARGET_CLASS))
+        else {
+            return std::ptr::null_mut();
+        };
+        let Some(entries) = ENTRIES.lock().ok().map(|entries| entries.clone()) else {
+            return std::ptr::null_mut();
+        };
+        let menu = NSMenu::new(mtm);
+        for (index, entry) in entries.all().enumerate() {
+            if index == entries.recent.len() && index > 0 {
+                menu.addItem(&NSMenuItem::separatorItem(mtm));
+            }
+            // SAFETY: `openDockItem:` is registered on the target class in `install`
+            let item = unsafe {
+                let item = NSMenuItem::initWithTitle_action_keyEquivalent(
+                    NSMenuItem::alloc(mtm),
+                    &NSString::from_str(&entry.label),
+                    Some(sel!(openDockItem:)),
+                    &NSString::new(),
+                );
+                item.setTarget(Some(target.as_ref()));
+                item
+            };
+            item.setTag(index as isize);
+            menu.addItem(&item);
+        }
+        Retained::autorelease_return(menu)
+    }
+
+    /// Teach tao's application delegate to answer `applicationDockMenu:`
+    pub fn install() {
+        let Some(mtm) = MainThreadMarker::new() else {
+            eprintln!("[Claude PM] Dock menu must be installed on the main thread");
+            return;
+        };
+        if AnyClass::get(TARGET_CLASS).is_none() {
+            let Some(mut builder) = ClassBuilder::new(TARGET_CLASS, NSObject::class()) else {
+                return;
+            };
+            // SAFETY: the signature matches `- (void)action:(NSMenuItem *)sender`
+            unsafe {
+                builder.add_class_method(
+                    sel!(openDockItem:),
+                    open_item as extern "C-unwind" fn(_, _, _),
+                );
+            }
+            builder.register();
+        }
+
+        let Some(delegate) = NSApplication::sharedApplication(mtm).delegate() else {
+            eprintln!("[Claude PM] No application delegate to attach the dock menu to");
+            return;
+        };
+        let delegate: &AnyObject = (*delegate).as_ref();
+        let class: *const AnyClass = delegate.class();
+        // SAFETY: `@@:@` is `- (NSMenu *)applicationDockMenu:(NSApplication *)sender`,
+        // which is what `dock_menu` implements
+        let added = unsafe {
+            let imp = std::mem::transmute::<DockMenuFn, Imp>(dock_menu);
+            ffi::class_addMethod(
+                class as *mut AnyClass,
+                sel!(applicationDockMenu:),
+                imp,
+                c"@@:@".as_ptr(),
+            )
+        };
+        if !added.as_bool() {
+            eprintln!("[Claude PM] The application delegate already provides a dock menu");
+        }
+    }
+}
+
+#[cfg(windows)]
+mod jump_list {
+    use windows::core::{Interface, HSTRING};
+    use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+    use windows::Win32::System::Com::StructuredStorage::PROPVARIANT;
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+    };
+    use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+    use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+    use windows::Win32::UI::Shell::{
+        DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW, ShellLink,
+    };
+
+    use super::{Entries, Entry};
+
+    fn link(exe: &HSTRING, entry: &Entry) -> windows::core::Result<IShellLinkW> {
+        // SAFETY: plain COM calls on objects we own
+        unsafe {
+            let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+            link.SetPath(exe)?;
+            link.SetArguments(&HSTRING::from(entry.url.as_str()))?;
+            link.SetIconLocation(exe, 0)?;
+            // Jump list entries show the title property, not the link's file name
+            let properties: IPropertyStore = link.cast()?;
+            properties.SetValue(&PKEY_Title, &PROPVARIANT::from(entry.label.as_str()))?;
+            properties.Commit()?;
+            Ok(link)
+        }
+    }
+
+    fn collection(exe: &HSTRING, entries: &[Entry]) -> windows::core::Result<IObjectArray> {
+        // SAFETY: plain COM calls on objects we own
+        unsafe {
+            let collection: IObjectCollection =
+                CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+            for entry in entries {
+                collection.AddObject(&link(exe, entry)?)?;
+            }
+            collection.cast()
+        }
+    }
+
+    /// Replace the jump list with a Recent Projects category and the action tasks
+    pub fn commit(entries: &Entries) -> Result<(), String> {
+        let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+        let exe = HSTRING::from(exe.as_os_str());
+        // SAFETY: COM is initialised for this thread before any other call
+        unsafe {
+            CoInitializeEx(None, COINIT_APARTMENTTHREADED)
+                .ok()
+                .map_err(|e| e.to_string())?;
+            let list: ICustomDestinationList =
+                CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)
+                    .map_err(|e| e.to_string())?;
+            let mut slots = 0u32;
+            let _removed: IObjectArray = list.BeginList(&mut slots).map_err(|e| e.to_string())?;
+            if !entries.recent.is_empty() {
+                list.AppendCategory(
+                    &HSTRING::from("Recent Projects"),
+                    &collection(&exe, &entries.recent).map_err(|e| e.to_string())?,
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            list.AddUserTasks(&collection(&exe, &entries.actions).map_err(|e| e.to_string())?)
+                .map_err(|e| e.to_string())?;
+            list.CommitList().map_err(|e| e.to_string())
+        }
+    }
+}
@@ -0,0 +1,452 @@
+//! Running the server in Docker instead of as a child process
+//!
+//! With `docker.enabled` set, `start_server` runs the active profile in a container.
+//! `build_server_image` builds the image from the server directory's Dockerfile, or
+//! pulls it when there is none. The port is published on localhost only and the data
+//! directory lives on a named volume. A compose file can be used instead of a single
+//! image. Container logs feed the same buffer as the child process's output, so crash
+//! reports include them.
+//!
+//! Events:
+//! - `server-status` — when the container's state or health changes (via `event_bus`)
+//! - `docker-build` — each line of output from `build_server_image`
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::event_bus::{self, AppEvent};
+use crate::process::{self, first_existing, kill_tree, which, CancelToken};
+use crate::profiles::ServerProfile;
+use crate::server_output::{self, ServerStatus};
+use crate::{config, crash, power};
+
+const DEFAULT_IMAGE: &str = "claudepm-server:latest";
+const DEFAULT_CONTAINER: &str = "claudepm-server";
+const DEFAULT_VOLUME: &str = "claudepm-data";
+/// Where the server keeps its database inside the image
+const DATA_MOUNT: &str = "/app/data";
+const COMPOSE_PROJECT: &str = "claudepm";
+/// `compose up` may need to pull images
+const COMPOSE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const HEALTH_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DockerSettings {
+    pub enabled: bool,
+    /// Image to run (`claudepm-server:latest` when unset)
+    pub image: Option<String>,
+    pub container_name: Option<String>,
+    /// Named volume or host path mounted at `/app/data`
+    pub data_volume: Option<String>,
+    /// Extra `-v` mappings, e.g. `/Users/me/code:/code:ro`
+    pub volumes: Vec<String>,
+    /// Use `docker compose` with this file instead of running the image directly
+    pub compose_file: Option<String>,
+}
+
+impl DockerSettings {
+    fn image(&self) -> &str {
+        self.image.as_deref().unwrap_or(DEFAULT_IMAGE)
+    }
+
+    fn container(&self) -> &str {
+        self.container_name.as_deref().unwrap_or(DEFAULT_CONTAINER)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerInfo {
+    pub path: Option<String>,
+    pub client_version: Option<String>,
+    /// Unset when the daemon isn't running
+    pub server_version: Option<String>,
+    pub compose_version: Option<String>,
+}
+
+static LOGS: Mutex<Option<Child>> = Mutex::new(None);
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+fn docker() -> Option<PathBuf> {
+    which("docker").or_else(|| {
+        first_existing(&[
+            PathBuf::from("/usr/local/bin/docker"),
+            PathBuf::from("/opt/homebrew/bin/docker"),
+            PathBuf::from("/Applications/Docker.app/Contents/Resources/bin/docker"),
+        ])
+    })
+}
+
+fn command() -> Result<Command, Error> {
+    docker()
+        .map(Command::new)
+        .ok_or_else(|| Error::NotFound("Docker is not installed".to_string()))
+}
+
+/// `docker compose -f <file> -p claudepm` for compose setups
+fn compose(file: &str) -> Result<Command, Error> {
+    let mut cmd = command()?;
+    cmd.args(["compose", "-f", file, "-p", COMPOSE_PROJECT]);
+    Ok(cmd)
+}
+
+/// Run a docker command, returning trimmed stdout or stderr as the error
+fn run(cmd: &mut Command, timeout: Duration) -> Result<String, Error> {
+    let output = process::run(cmd, timeout, &CancelToken::default())?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(Error::Internal(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+fn version(args: &[&str]) -> Option<String> {
+    let mut cmd = command().ok()?;
+    run(cmd.args(args), process::default_timeout())
+        .ok()
+        .filter(|v| !v.is_empty())
+}
+
+pub fn detect() -> DockerInfo {
+    DockerInfo {
+        path: docker().map(|p| p.display().to_string()),
+        client_version: version(&["version", "--format", "{{.Client.Version}}"]),
+        server_version: version(&["version", "--format", "{{.Server.Version}}"]),
+        compose_version: version(&["compose", "version", "--short"]),
+    }
+}
+
+fn image_exists(image: &str) -> bool {
+    command()
+        .and_then(|mut cmd| {
+            run(
+                cmd.args(["image", "inspect", image]),
+                process::default_timeout(),
+            )
+        })
+        .is_ok()
+}
+
+/// Build from the server directory's Dockerfile when there is one, otherwise pull
+fn image_command(settings: &DockerSettings) -> Result<Command, Error> {
+    let mut cmd = command()?;
+    match crate::get_server_path().filter(|dir| dir.join("Dockerfile").exists()) {
+        Some(dir) => {
+            cmd.args(["build", "--progress", "plain", "-t", settings.image()])
+                .arg(dir);
+        }
+        None => {
+            cmd.args(["pull", settings.image()]);
+        }
+    }
+    Ok(cmd)
+}
+
+/// `-e KEY` without a value makes docker read it from its own environment, keeping
+/// secrets off the command line
+fn env_args(cmd: &mut Command, env: &BTreeMap<String, String>) {
+    for key in env.keys() {
+        cmd.args(["-e", key]);
+    }
+    cmd.envs(env);
+}
+
+fn follow_logs(mut cmd: Command) {
+    server_output::reset("docker");
+    let child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    match child {
+        Ok(mut child) => {
+            if let Some(stdout) = child.stdout.take() {
+                crash::capture_output(stdout);
+            }
+            if let Some(stderr) = child.stderr.take() {
+                crash::capture_output(stderr);
+            }
+            if let Ok(mut logs) = LOGS.lock() {
+                if let Some(mut previous) = logs.replace(child) {
+                    kill_tree(&mut previous);
+                    let _ = previous.wait();
+                }
+            }
+        }
+        Err(e) => eprintln!("[Claude PM] Failed to follow container logs: {}", e),
+    }
+}
+
+/// Start the server container for `profile`, replacing any previous one
+pub fn start(profile: &ServerProfile, env: &BTreeMap<String, String>) -> Result<(), Error> {
+    let settings = config::load().docker;
+
+    if let Some(file) = settings.compose_file.as_deref() {
+        println!("[Claude PM] Starting server with docker compose ({})", file);
+        let mut up = compose(file)?;
+        up.args(["up", "-d"]).envs(env);
+        run(&mut up, COMPOSE_TIMEOUT)?;
+        let mut logs = compose(file)?;
+        logs.args(["logs", "-f", "--no-color", "--tail", "0"]);
+        follow_logs(logs);
+        return Ok(());
+    }
+
+    // Building can take minutes, so it's left to `build_server_image` rather than
+    // blocking startup
+    if !image_exists(settings.image()) {
+        return Err(Error::NotFound(format!(
+            "Server image {} not found; build or pull it first",
+            settings.image()
+        )));
+    }
+
+    let name = settings.container();
+    let _ =
+        command().and_then(|mut cmd| run(cmd.args(["rm", "-f", name]), process::default_timeout()));
+
+    let mut cmd = command()?;
+    cmd.args(["run", "-d", "--name", name])
+        .args([
+            "-p",
+            &format!("127.0.0.1:{}:{}", profile.port, profile.port),
+        ])
+        // Lets the server reach services (e.g. tmux helpers, the hook receiver) on the host
+        .args(["--add-host", "host.docker.internal:host-gateway"])
+        .args([
+            "-v",
+            &format!(
+                "{}:{}",
+                settings.data_volume.as_deref().unwrap_or(DEFAULT_VOLUME),
+                DATA_MOUNT
+            ),
+        ]);
+    for volume in &settings.volumes {
+        cmd.args(["-v", volume]);
+    }
+    env_args(&mut cmd, env);
+    cmd.arg(settings.image());
+    let id = run(&mut cmd, process::default_timeout())?;
+    println!(
+        "[Claude PM] Server container {} started ({})",
+        name,
+        id.get(..12).unwrap_or(&id)
+    );
+
+    let mut logs = command()?;
+    logs.args(["logs", "-f", "--tail", "0", name]);
+    follow_logs(logs);
+    Ok(())
+}
+
+/// Stop the container (or compose project) and the log follower
+pub fn stop() {
+    let settings = config::load().docker;
+    let result = match settings.compose_file.as_deref() {
+        Some(file) => compose(file).and_then(|mut cmd| run(cmd.arg("down"), COMPOSE_TIMEOUT)),
+        None => command().and_then(|mut cmd| {
+            run(
+                cmd.args(["stop", "-t", "10", settings.container()]),
+                Duration::from_secs(30),
+            )
+        }),
+    };
+    if let Err(e) = result {
+        eprintln!("[Claude PM] Failed to stop server container: {}", e);
+    }
+    if let Some(mut child) = LOGS.lock().ok().and_then(|mut logs| logs.take()) {
+        kill_tree(&mut child);
+        let _ = child.wait();
+    }
+}
+
+/// The container to inspect: the named one, or the compose project's first
+fn container_id(settings: &DockerSettings) -> Option<String> {
+    match settings.compose_file.as_deref() {
+        Some(file) => {
+            let mut cmd = compose(file).ok()?;
+            run(cmd.args(["ps", "-q"]), process::default_timeout())
+                .ok()?
+                .lines()
+                .next()
+                .map(str::to_string)
+        }
+        None => Some(settings.container().to_string()),
+    }
+}
+
+/// Map container state and health check to the same states as the process backend
+pub fn status() -> ServerStatus {
+    let settings = config::load().docker;
+    let inspected = container_id(&settings).and_then(|id| {
+        let mut cmd = command().ok()?;
+        run(
+            cmd.args([
+                "inspect",
+                "--format",
+                "{{.State.Status}} {{if .State.Health}}{{.State.Health.Status}}{{end}}",
+                &id,
+            ]),
+            process::default_timeout(),
+        )
+        .ok()
+    });
+    let (state, health) = match inspected
+        .as_deref()
+        .map(|s| s.split_once(' ').unwrap_or((s, "")))
+    {
+        Some((state, health)) => (state.to_string(), health.trim().to_string()),
+        None => ("missing".to_string(), String::new()),
+    };
+    let status = match (state.as_str(), health.as_str()) {
+        ("running", "starting") | ("created" | "restarting", _) => "starting",
+        ("running", "unhealthy") => "unhealthy",
+        ("running", _) => "running",
+        _ => "stopped",
+    };
+    ServerStatus {
+        status: status.to_string(),
+        backend: "docker",
+        detail: Some(state),
+        code: None,
+        hint: None,
+    }
+}
+
+/// Poll container health while the docker backend is enabled; safe to call more than once
+pub fn watch() {
+    if WATCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| {
+        let mut last: Option<ServerStatus> = None;
+        loop {
+            if config::load().docker.enabled {
+                let current = status();
+                if last.as_ref() != Some(&current) {
+                    event_bus::publish(AppEvent::ServerStatus(current.clone()));
+                    last = Some(current);
+                }
+            } else {
+                last = None;
+            }
+            power::wait(HEALTH_INTERVAL);
+        }
+    });
+}
+
+fn emit_lines(app: &AppHandle, reader: impl Read) {
+    for line in BufReader::new(reader).lines().map_while(Result::ok) {
+        let _ = app.emit("docker-build", line);
+    }
+}
+
+#[tauri::command]
+pub async fn detect_docker() -> Result<DockerInfo, Error> {
+    tauri::async_runtime::spawn_blocking(detect)
+        .await
+        .map_err(|e| Error::from(e.to_string()))
+}
+
+#[tauri::command]
+pub fn get_docker_settings() -> DockerSettings {
+    config::load().docker
+}
+
+/// Save docker settings; takes effect on the next server restart
+#[tauri::command]
+pub fn set_docker_settings(settings: DockerSettings) -> Result<DockerSettings, Error> {
+    if let Some(file) = settings.compose_file.as_deref() {
+        if !PathBuf::from(file).is_file() {
+            return Err(Error::NotFound(format!("Compose file not found: {}", file)));
+        }
+    }
+    config::update(|c| c.docker = settings)
+        .map(|c| c.docker)
+        .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn get_docker_status() -> ServerStatus {
+    status()
+}
+
+/// Build (or pull) the server image, streaming output as `docker-build` events
+#[tauri::command]
+pub async fn build_server_image(app: AppHandle) -> Result<(), Error> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let settings = config::load().docker;
+        let mut child = image_command(&settings)?
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run docker: {}", e))?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let out_app = app.clone();
+        let reader = thread::spawn(move || {
+            if let Some(stdout) = stdout {
+                emit_lines(&out_app, stdout);
+            }
+        });
+        if let Some(stderr) = stderr {
+            emit_lines(&app, stderr);
+        }
+        let _ = reader.join();
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(Error::Internal(format!("Image build failed ({})", status)))
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// The last `tail` lines of container output (200 by default)
+#[tauri::command]
+pub async fn get_docker_logs(tail: Option<u32>) -> Result<String, Error> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let settings = config::load().docker;
+        let tail = tail.unwrap_or(200).to_string();
+        let mut cmd = match settings.compose_file.as_deref() {
+            Some(file) => {
+                let mut cmd = compose(file)?;
+                cmd.args(["logs", "--no-color", "--tail", &tail]);
+                cmd
+            }
+            None => {
+                let mut cmd = command()?;
+                cmd.args(["logs", "--tail", &tail, settings.container()]);
+                cmd
+            }
+        };
+        // docker logs replays the container's stderr on stderr, so keep both
+        let output = process::run(
+            &mut cmd,
+            process::default_timeout(),
+            &CancelToken::default(),
+        )?;
+        Ok(format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
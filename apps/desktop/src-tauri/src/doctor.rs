@@ -0,0 +1,298 @@
+//! Environment checks for the setup checklist
+//!
+//! `run_doctor` verifies everything the app depends on and returns one entry per check
+//! with a status and, when something is wrong, a suggested fix.
+
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::Command;
+use tauri::AppHandle;
+
+use crate::error::Error;
+use crate::permissions::{self, PermissionStatus};
+use crate::process::{self, first_existing, which};
+use crate::{bootstrap, multiplexer};
+
+/// Oldest Node major version the server supports
+const MIN_NODE_MAJOR: u32 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorCheck {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorReport {
+    /// False if any check is an error; warnings don't block setup
+    pub ok: bool,
+    pub checks: Vec<DoctorCheck>,
+}
+
+fn check(
+    id: &'static str,
+    label: &'static str,
+    status: CheckStatus,
+    detail: impl Into<String>,
+    fix: Option<&str>,
+) -> DoctorCheck {
+    DoctorCheck {
+        id,
+        label,
+        status,
+        detail: detail.into(),
+        fix: fix.map(str::to_string),
+    }
+}
+
+/// First line of `program <flag>`, e.g. `v20.18.0`
+fn version(program: &PathBuf, flag: &str) -> Option<String> {
+    let output = process::output(Command::new(program).arg(flag)).ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.lines().next().map(|line| line.trim().to_string())
+}
+
+/// Check a tool is installed, reporting its version
+fn tool(
+    id: &'static str,
+    label: &'static str,
+    path: Option<PathBuf>,
+    flag: &str,
+    missing: CheckStatus,
+    fix: &str,
+) -> DoctorCheck {
+    match path {
+        Some(path) => {
+            let detail = match version(&path, flag) {
+                Some(version) => format!("{} at {}", version, path.display()),
+                None => format!("Found at {}", path.display()),
+            };
+            check(id, label, CheckStatus::Ok, detail, None)
+        }
+        None => check(id, label, missing, "Not found", Some(fix)),
+    }
+}
+
+fn check_multiplexer() -> DoctorCheck {
+    match multiplexer::active() {
+        Ok(mux) => tool(
+            "tmux",
+            "Terminal multiplexer",
+            mux.path(),
+            mux.version_flag(),
+            CheckStatus::Error,
+            "",
+        ),
+        Err(e) => check(
+            "tmux",
+            "Terminal multiplexer",
+            CheckStatus::Error,
+            e,
+            Some("Install tmux (brew install tmux), zellij or GNU screen"),
+        ),
+    }
+}
+
+pub fn check_node() -> DoctorCheck {
+    let path = which("node").or_else(|| {
+        crate::find_npm()
+            .and_then(|npm| npm.parent().map(|dir| dir.join("node")))
+            .filter(|node| node.exists())
+    });
+    let Some(path) = path else {
+        return check(
+            "node",
+            "Node.js",
+            CheckStatus::Error,
+            "Not found",
+            Some(
+                "Install Node.js 20 or later from nodejs.org or with Homebrew (brew install node)",
+            ),
+        );
+    };
+    let version = version(&path, "--version").unwrap_or_default();
+    let major = version
+        .trim_start_matches('v')
+        .split('.')
+        .next()
+        .and_then(|major| major.parse::<u32>().ok());
+    match major {
+        Some(major) if major < MIN_NODE_MAJOR => check(
+            "node",
+            "Node.js",
+            CheckStatus::Error,
+            format!("{} is too old", version),
+            Some("Upgrade to Node.js 20 or later"),
+        ),
+        _ => check(
+            "node",
+            "Node.js",
+            CheckStatus::Ok,
+            format!("{} at {}", version, path.display()),
+            None,
+        ),
+    }
+}
+
+/// Locate the Claude CLI, including installs outside a GUI app's PATH
+pub fn claude_path() -> Option<PathBuf> {
+    let home = dirs::home_dir().unwrap_or_default();
+    which("claude").or_else(|| {
+        first_existing(&[
+            home.join(".claude/local/claude"),
+            home.join(".npm-global/bin/claude"),
+            PathBuf::from("/opt/homebrew/bin/claude"),
+            PathBuf::from("/usr/local/bin/claude"),
+        ])
+    })
+}
+
+fn check_claude() -> DoctorCheck {
+    tool(
+        "claude",
+        "Claude CLI",
+        claude_path(),
+        "--version",
+        CheckStatus::Error,
+        "Install it with npm install -g @anthropic-ai/claude-code",
+    )
+}
+
+fn check_server_path() -> DoctorCheck {
+    match crate::get_server_path() {
+        Some(path) if bootstrap::needs_install(&path) => check(
+            "serverPath",
+            "Server directory",
+            CheckStatus::Warning,
+            format!("{} (dependencies not installed)", path.display()),
+            Some("Install the server dependencies from the setup screen"),
+        ),
+        Some(path) => check(
+            "serverPath",
+            "Server directory",
+            CheckStatus::Ok,
+            path.display().to_string(),
+            None,
+        ),
+        None => check(
+            "serverPath",
+            "Server directory",
+            CheckStatus::Error,
+            "Not found",
+            Some("Choose the server directory in settings or set CLAUDE_PM_SERVER_PATH"),
+        ),
+    }
+}
+
+fn check_port() -> DoctorCheck {
+    let port = crate::server_port();
+    let owned = crate::SERVER_PROCESS
+        .lock()
+        .is_ok_and(|server| server.is_some());
+    match (crate::is_server_running(port), owned) {
+        (true, true) => check(
+            "port",
+            "Server port",
+            CheckStatus::Ok,
+            format!("Port {} is in use by the Claude PM server", port),
+            None,
+        ),
+        (true, false) => check(
+            "port",
+            "Server port",
+            CheckStatus::Warning,
+            format!("Port {} is in use by a process Claude PM didn't start", port),
+            Some("If it isn't a Claude PM server you started yourself, stop it or pick another port in the server profile"),
+        ),
+        (false, _) => check(
+            "port",
+            "Server port",
+            CheckStatus::Ok,
+            format!("Port {} is free", port),
+            None,
+        ),
+    }
+}
+
+fn check_permission(
+    id: &'static str,
+    label: &'static str,
+    status: PermissionStatus,
+    fix: &str,
+) -> Option<DoctorCheck> {
+    let (status, detail) = match status {
+        PermissionStatus::Granted => (CheckStatus::Ok, "Granted"),
+        PermissionStatus::Denied => (CheckStatus::Error, "Denied"),
+        PermissionStatus::NotDetermined => (CheckStatus::Warning, "Not granted yet"),
+        PermissionStatus::Unsupported => return None,
+    };
+    let fix = (status != CheckStatus::Ok).then_some(fix);
+    Some(check(id, label, status, detail, fix))
+}
+
+fn diagnose(app: AppHandle) -> DoctorReport {
+    let mut checks = vec![
+        check_node(),
+        tool(
+            "npm",
+            "npm",
+            crate::find_npm(),
+            "--version",
+            CheckStatus::Error,
+            "npm ships with Node.js; reinstall Node.js",
+        ),
+        check_multiplexer(),
+        tool(
+            "git",
+            "git",
+            which("git"),
+            "--version",
+            CheckStatus::Warning,
+            "Install the Xcode command line tools (xcode-select --install)",
+        ),
+        check_claude(),
+        check_server_path(),
+        check_port(),
+    ];
+
+    let report = permissions::get_permission_status(app);
+    checks.extend(check_permission(
+        "automation",
+        "Automation permission",
+        report.automation,
+        "Allow Claude PM under System Settings → Privacy & Security → Automation",
+    ));
+    checks.extend(check_permission(
+        "notifications",
+        "Notifications permission",
+        report.notifications,
+        "Allow notifications for Claude PM in System Settings → Notifications",
+    ));
+
+    DoctorReport {
+        ok: checks.iter().all(|c| c.status != CheckStatus::Error),
+        checks,
+    }
+}
+
+/// Run every check; they shell out (osascript can take up to two minutes), so off the
+/// async runtime
+#[tauri::command]
+pub async fn run_doctor(app: AppHandle) -> Result<DoctorReport, Error> {
+    tauri::async_runtime::spawn_blocking(move || diagnose(app))
+        .await
+        .map_err(|e| e.to_string().into())
+}
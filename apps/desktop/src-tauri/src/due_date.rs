@@ -0,0 +1,575 @@
+//! Natural-language due dates in free text, e.g. "send the report by eod tomorrow"
+//!
+//! The parser looks for the longest date expression anywhere in the text and returns when
+//! it means, local time, along with where it was so the caller can strip it from a title.
+//! It understands:
+//! - days: `today`, `tonight`, `tomorrow`, weekdays (`fri`, `this friday`, `next friday`
+//!   all mean the coming one, never today), `next week` (its Monday), `next month` (its
+//!   1st), `end of week` / `eow` (Friday), `end of month` / `eom`
+//! - dates: `March 5`, `5th of March`, `Mar 5 2027`, `2027-03-05`; without a year, the
+//!   next one to come
+//! - offsets: `in 3 weeks`, `in an hour`, `in two days`
+//! - times: `5pm`, `5:30 pm`, `17:00`, `at 5`, `noon`, `midnight`, `morning`,
+//!   `afternoon`, `evening`, `eod` / `end of day` (17:00), before or after the day
+//!
+//! A leading `by`, `on`, `due` or `at` is taken as part of the match. A day without a
+//! time is 09:00 with `has_time` false; a time without a day is the next time it comes.
+
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveTime, TimeZone};
+use serde::Serialize;
+
+const DEFAULT_HOUR: u32 = 9;
+const EOD_HOUR: u32 = 17;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParsedDueDate {
+    /// Unix millis
+    pub timestamp: i64,
+    /// Whether the text gave a time of day, not just a day
+    pub has_time: bool,
+    /// Character offsets of the match in the text, end exclusive
+    pub start: usize,
+    pub end: usize,
+    pub matched: String,
+    /// The text with the match taken out
+    pub remainder: String,
+}
+
+struct Token {
+    word: String,
+    /// Character offsets in the original text
+    start: usize,
+    end: usize,
+}
+
+/// A day, and the time it implies when none is given (`tonight`, `eow`)
+struct Day {
+    date: NaiveDate,
+    time: Option<NaiveTime>,
+}
+
+/// What an expression resolved to
+enum When {
+    Day(Day),
+    Time(NaiveTime),
+    /// An offset in minutes or hours, already exact
+    Instant(DateTime<Local>),
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    for (i, c) in text.chars().enumerate() {
+        if c.is_alphanumeric() || matches!(c, ':' | '-' | '/' | '.') {
+            current
+                .get_or_insert_with(|| (i, String::new()))
+                .1
+                .extend(c.to_lowercase());
+        } else if let Some((start, word)) = current.take() {
+            tokens.push(Token::new(start, word));
+        }
+    }
+    if let Some((start, word)) = current {
+        tokens.push(Token::new(start, word));
+    }
+    tokens
+}
+
+impl Token {
+    /// Trailing punctuation ("Fri.", "tomorrow:") isn't part of the word
+    fn new(start: usize, word: String) -> Self {
+        let word = word.trim_end_matches(['.', ':', '-', '/']).to_string();
+        Self {
+            end: start + word.chars().count(),
+            start,
+            word,
+        }
+    }
+}
+
+fn hm(hour: u32, minute: u32) -> Option<NaiveTime> {
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn weekday(word: &str) -> Option<chrono::Weekday> {
+    use chrono::Weekday::*;
+    Some(match word {
+        "mon" | "monday" => Mon,
+        "tue" | "tues" | "tuesday" => Tue,
+        "wed" | "weds" | "wednesday" => Wed,
+        "thu" | "thur" | "thurs" | "thursday" => Thu,
+        "fri" | "friday" => Fri,
+        "sat" | "saturday" => Sat,
+        "sun" | "sunday" => Sun,
+        _ => return None,
+    })
+}
+
+fn month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    MONTHS
+        .iter()
+        .position(|m| *m == word || (word.len() >= 3 && m.starts_with(word)))
+        .map(|i| i as u32 + 1)
+}
+
+fn number(word: &str) -> Option<u32> {
+    const WORDS: [&str; 12] = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten", "eleven",
+        "twelve",
+    ];
+    match word {
+        "a" | "an" => Some(1),
+        "couple" => Some(2),
+        "few" => Some(3),
+        _ => word
+            .parse()
+            .ok()
+            .or_else(|| WORDS.iter().position(|w| *w == word).map(|i| i as u32 + 1)),
+    }
+}
+
+/// `5`, `5th`, `21st` as a day of the month
+fn ordinal(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &word[digits.len()..];
+    if !matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+fn words<'a>(tokens: &'a [Token], i: usize) -> impl Fn(usize) -> Option<&'a str> {
+    move |n| tokens.get(i + n).map(|t| t.word.as_str())
+}
+
+/// The coming `day`, never today
+fn coming(today: NaiveDate, day: chrono::Weekday) -> NaiveDate {
+    let ahead = (day.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today + Duration::days(if ahead == 0 { 7 } else { ahead as i64 })
+}
+
+fn last_of_month(date: NaiveDate) -> NaiveDate {
+    NaiveDate::from_ymd_opt(date.year(), date.month(), 1)
+        .and_then(|first| first.checked_add_months(Months::new(1)))
+        .and_then(|next| next.pred_opt())
+        .unwrap_or(date)
+}
+
+/// A month and day, this year unless it's already gone
+fn month_day(today: NaiveDate, month: u32, day: u32, year: Option<i32>) -> Option<NaiveDate> {
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year, month, day),
+        None => NaiveDate::from_ymd_opt(today.year(), month, day)
+            .filter(|date| *date >= today)
+            .or_else(|| NaiveDate::from_ymd_opt(today.year() + 1, month, day)),
+    }
+}
+
+fn year(word: Option<&str>) -> Option<i32> {
+    word.filter(|w| w.len() == 4)
+        .and_then(|w| w.parse().ok())
+        .filter(|y| (1970..=9999).contains(y))
+}
+
+/// A day starting at token `i`: how many tokens it takes and the day
+fn day_at(tokens: &[Token], i: usize, today: NaiveDate) -> Option<(usize, Day)> {
+    let w = words(tokens, i);
+    let day = |date| Day { date, time: None };
+    let eod = |date| Day {
+        date,
+        time: hm(EOD_HOUR, 0),
+    };
+    let first = w(0)?;
+    match first {
+        "today" => return Some((1, day(today))),
+        "tonight" => {
+            return Some((
+                1,
+                Day {
+                    date: today,
+                    time: hm(20, 0),
+                },
+            ))
+        }
+        "tomorrow" | "tmrw" | "tmr" => return Some((1, day(today + Duration::days(1)))),
+        "eow" => return Some((1, eod(end_of_week(today)))),
+        "eom" => return Some((1, eod(last_of_month(today)))),
+        _ => {}
+    }
+    if first == "end" && w(1) == Some("of") {
+        match w(2) {
+            Some("week") => return Some((3, eod(end_of_week(today)))),
+            Some("month") => return Some((3, eod(last_of_month(today)))),
+            Some("the") if w(3) == Some("week") => return Some((4, eod(end_of_week(today)))),
+            Some("the") if w(3) == Some("month") => return Some((4, eod(last_of_month(today)))),
+            _ => {}
+        }
+    }
+    if matches!(first, "this" | "next") {
+        if let Some(weekday) = w(1).and_then(weekday) {
+            return Some((2, day(coming(today, weekday))));
+        }
+        if first == "next" {
+            match w(1) {
+                Some("week") => {
+                    return Some((2, day(coming(today, chrono::Weekday::Mon))));
+                }
+                Some("month") => {
+                    let first_of_next = NaiveDate::from_ymd_opt(today.year(), today.month(), 1)?
+                        .checked_add_months(Months::new(1))?;
+                    return Some((2, day(first_of_next)));
+                }
+                _ => {}
+            }
+        }
+    }
+    if let Some(weekday) = weekday(first) {
+        return Some((1, day(coming(today, weekday))));
+    }
+    // 2027-03-05
+    if let Ok(date) = NaiveDate::parse_from_str(first, "%Y-%m-%d") {
+        return Some((1, day(date)));
+    }
+    // March 5th [2027]
+    if let (Some(month), Some(d)) = (month(first), w(1).and_then(ordinal)) {
+        let year = year(w(2));
+        let taken = if year.is_some() { 3 } else { 2 };
+        return month_day(today, month, d, year).map(|date| (taken, day(date)));
+    }
+    // 5th [of] March [2027]
+    if let Some(d) = ordinal(first) {
+        let skip = usize::from(w(1) == Some("of"));
+        if let Some(month) = w(1 + skip).and_then(month) {
+            let year = year(w(2 + skip));
+            let taken = 2 + skip + usize::from(year.is_some());
+            return month_day(today, month, d, year).map(|date| (taken, day(date)));
+        }
+    }
+    None
+}
+
+/// This week's Friday, or next week's once it's the weekend
+fn end_of_week(today: NaiveDate) -> NaiveDate {
+    match today.weekday() {
+        chrono::Weekday::Fri => today,
+        _ => coming(today, chrono::Weekday::Fri),
+    }
+}
+
+/// `5pm`, `5:30`, `17`: hour, minute, whether it's pm if it says, and whether it had minutes
+fn clock(word: &str) -> Option<(u32, u32, Option<bool>, bool)> {
+    let (digits, meridiem) = match word.strip_suffix("am").or_else(|| word.strip_suffix("a.m")) {
+        Some(digits) => (digits, Some(false)),
+        None => match word.strip_suffix("pm").or_else(|| word.strip_suffix("p.m")) {
+            Some(digits) => (digits, Some(true)),
+            None => (word, None),
+        },
+    };
+    let (hour, minute, minutes) = match digits.split_once(':') {
+        Some((h, m)) if m.len() == 2 => (h.parse().ok()?, m.parse().ok()?, true),
+        Some(_) => return None,
+        None => (digits.parse().ok()?, 0, false),
+    };
+    (hour <= 23 && minute <= 59).then_some((hour, minute, meridiem, minutes))
+}
+
+/// A time of day starting at token `i`
+fn time_at(tokens: &[Token], i: usize) -> Option<(usize, NaiveTime)> {
+    let w = words(tokens, i);
+    let at = w(0)? == "at";
+    let start = usize::from(at);
+    let named = |hour: u32, taken: usize| Some((start + taken, hm(hour, 0)?));
+    match w(start)? {
+        "noon" | "midday" => return named(12, 1),
+        "midnight" => return Some((start + 1, hm(23, 59)?)),
+        "morning" => return named(DEFAULT_HOUR, 1),
+        "afternoon" => return named(14, 1),
+        "evening" => return named(18, 1),
+        "eod" | "cob" => return named(EOD_HOUR, 1),
+        "end" if w(start + 1) == Some("of") && w(start + 2) == Some("day") => {
+            return named(EOD_HOUR, 3)
+        }
+        "end"
+            if w(start + 1) == Some("of")
+                && w(start + 2) == Some("the")
+                && w(start + 3) == Some("day") =>
+        {
+            return named(EOD_HOUR, 4)
+        }
+        _ => {}
+    }
+    let (mut hour, minute, mut meridiem, minutes) = clock(w(start)?)?;
+    let mut taken = start + 1;
+    // "5 pm"
+    if meridiem.is_none() {
+        match w(taken) {
+            Some("am" | "a.m") => meridiem = Some(false),
+            Some("pm" | "p.m") => meridiem = Some(true),
+            _ => {}
+        }
+        if meridiem.is_some() {
+            taken += 1;
+        }
+    }
+    // A bare number is only a time after "at": "3 weeks" isn't three o'clock
+    if meridiem.is_none() && !minutes && !at {
+        return None;
+    }
+    match meridiem {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(true) if hour < 12 => hour += 12,
+        Some(false) if hour == 12 => hour = 0,
+        // "at 5" means the afternoon, "at 9" the morning
+        None if at && (1..=7).contains(&hour) => hour += 12,
+        _ => {}
+    }
+    Some((taken, hm(hour, minute)?))
+}
+
+/// `in 3 weeks` / `in an hour`
+fn offset_at(tokens: &[Token], i: usize, now: DateTime<Local>) -> Option<(usize, When)> {
+    let w = words(tokens, i);
+    if w(0)? != "in" {
+        return None;
+    }
+    let mut next = 1;
+    if w(next) == Some("a") && matches!(w(next + 1), Some("couple" | "few")) {
+        next += 1;
+    }
+    let amount = number(w(next)?)?;
+    next += 1;
+    if w(next) == Some("of") {
+        next += 1;
+    }
+    let today = now.date_naive();
+    let when = match w(next)? {
+        "min" | "mins" | "minute" | "minutes" => {
+            When::Instant(now + Duration::minutes(amount as i64))
+        }
+        "hr" | "hrs" | "hour" | "hours" => When::Instant(now + Duration::hours(amount as i64)),
+        "day" | "days" => When::Day(Day {
+            date: today + Duration::days(amount as i64),
+            time: None,
+        }),
+        "wk" | "wks" | "week" | "weeks" => When::Day(Day {
+            date: today + Duration::weeks(amount as i64),
+            time: None,
+        }),
+        "month" | "months" => When::Day(Day {
+            date: today.checked_add_months(Months::new(amount))?,
+            time: None,
+        }),
+        "year" | "years" => When::Day(Day {
+            date: today.checked_add_months(Months::new(amount * 12))?,
+            time: None,
+        }),
+        _ => return None,
+    };
+    Some((next + 1, when))
+}
+
+/// The longest expression starting at token `i`: tokens taken, when, and whether it has a time
+fn expression_at(
+    tokens: &[Token],
+    i: usize,
+    now: DateTime<Local>,
+) -> Option<(usize, DateTime<Local>, bool)> {
+    let today = now.date_naive();
+    let local = |date: NaiveDate, time: NaiveTime| {
+        Local.from_local_datetime(&date.and_time(time)).earliest()
+    };
+    let lead = usize::from(matches!(
+        tokens.get(i).map(|t| t.word.as_str()),
+        Some("by" | "on" | "due" | "before")
+    ));
+    let i = i + lead;
+
+    let (taken, when) = if let Some(offset) = offset_at(tokens, i, now) {
+        offset
+    } else if let Some((taken, day)) = day_at(tokens, i, today) {
+        (taken, When::Day(day))
+    } else if let Some((taken, time)) = time_at(tokens, i) {
+        (taken, When::Time(time))
+    } else {
+        return None;
+    };
+
+    // A day can be followed by a time and a time by a day: "tomorrow at 5", "5pm friday"
+    let after = i + taken;
+    let skip_on = usize::from(matches!(
+        tokens.get(after).map(|t| t.word.as_str()),
+        Some("on" | "at")
+    ));
+    let (extra, date, time) = match when {
+        When::Instant(instant) => return Some((lead + taken, instant, true)),
+        When::Day(day) => match time_at(tokens, after) {
+            Some((more, time)) => (more, day.date, Some(time)),
+            None => (0, day.date, day.time),
+        },
+        When::Time(time) => match day_at(tokens, after + skip_on, today) {
+            Some((more, day)) => (skip_on + more, day.date, Some(time)),
+            None => {
+                let date = if local(today, time).is_some_and(|t| t > now) {
+                    today
+                } else {
+                    today + Duration::days(1)
+                };
+                (0, date, Some(time))
+            }
+        },
+    };
+    let at = local(date, time.or(hm(DEFAULT_HOUR, 0))?)?;
+    Some((lead + taken + extra, at, time.is_some()))
+}
+
+/// The longest date expression in `text` relative to `now`, the earliest of equals
+pub fn parse(text: &str, now: DateTime<Local>) -> Option<ParsedDueDate> {
+    let tokens = tokenize(text);
+    let (first, taken, at, has_time) = (0..tokens.len())
+        .filter_map(|i| {
+            expression_at(&tokens, i, now).map(|(taken, at, has_time)| (i, taken, at, has_time))
+        })
+        .fold(
+            None,
+            |best: Option<(usize, usize, DateTime<Local>, bool)>, found| match best {
+                Some(best) if best.1 >= found.1 => Some(best),
+                _ => Some(found),
+            },
+        )?;
+    let start = tokens[first].start;
+    let end = tokens[first + taken - 1].end;
+    let chars: Vec<char> = text.chars().collect();
+    let before: String = chars[..start].iter().collect();
+    let after: String = chars[end..].iter().collect();
+    // Punctuation left dangling by the cut goes too: "tomorrow: ship it" is "ship it"
+    let remainder = format!("{} {}", before.trim_end(), after.trim_start())
+        .trim_matches(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ':' | '-'))
+        .to_string();
+    Some(ParsedDueDate {
+        timestamp: at.timestamp_millis(),
+        has_time,
+        start,
+        end,
+        matched: chars[start..end].iter().collect(),
+        remainder,
+    })
+}
+
+/// Find a due date in quick-capture or task text; `None` if it doesn't mention one
+#[tauri::command]
+pub fn parse_due_date(text: String) -> Option<ParsedDueDate> {
+    parse(&text, Local::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(y: i32, m: u32, d: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, hour, minute, 0).unwrap()
+    }
+
+    /// Wednesday 14 October 2026, 10:00
+    fn now() -> DateTime<Local> {
+        local(2026, 10, 14, 10, 0)
+    }
+
+    fn due(text: &str) -> ParsedDueDate {
+        parse(text, now()).unwrap_or_else(|| panic!("no due date in {:?}", text))
+    }
+
+    #[test]
+    fn days_default_to_the_morning() {
+        let friday = due("fix the bug friday");
+        assert_eq!(
+            friday.timestamp,
+            local(2026, 10, 16, 9, 0).timestamp_millis()
+        );
+        assert!(!friday.has_time);
+        assert_eq!(friday.remainder, "fix the bug");
+
+        // Never today, even on a Wednesday
+        assert_eq!(
+            due("wed").timestamp,
+            local(2026, 10, 21, 9, 0).timestamp_millis()
+        );
+        assert_eq!(
+            due("next week").timestamp,
+            local(2026, 10, 19, 9, 0).timestamp_millis()
+        );
+        assert_eq!(
+            due("in 3 days").timestamp,
+            local(2026, 10, 17, 9, 0).timestamp_millis()
+        );
+    }
+
+    #[test]
+    fn days_and_times_combine() {
+        let report = due("send the report by eod tomorrow");
+        assert_eq!(
+            report.timestamp,
+            local(2026, 10, 15, 17, 0).timestamp_millis()
+        );
+        assert!(report.has_time);
+        assert_eq!(report.matched, "by eod tomorrow");
+        assert_eq!(report.remainder, "send the report");
+
+        assert_eq!(
+            due("tomorrow at 5:30 pm").timestamp,
+            local(2026, 10, 15, 17, 30).timestamp_millis()
+        );
+    }
+
+    #[test]
+    fn bare_times_are_the_next_to_come() {
+        assert_eq!(
+            due("ship at 5pm").timestamp,
+            local(2026, 10, 14, 17, 0).timestamp_millis()
+        );
+        assert_eq!(
+            due("call at 9am").timestamp,
+            local(2026, 10, 15, 9, 0).timestamp_millis()
+        );
+        assert_eq!(
+            due("in 2 hours").timestamp,
+            (now() + Duration::hours(2)).timestamp_millis()
+        );
+    }
+
+    #[test]
+    fn dates_without_a_year_are_upcoming() {
+        assert_eq!(
+            due("March 5").timestamp,
+            local(2027, 3, 5, 9, 0).timestamp_millis()
+        );
+        assert_eq!(
+            due("due 2027-03-05").timestamp,
+            local(2027, 3, 5, 9, 0).timestamp_millis()
+        );
+        assert_eq!(
+            due("5th of November").timestamp,
+            local(2026, 11, 5, 9, 0).timestamp_millis()
+        );
+    }
+
+    #[test]
+    fn strips_the_match_and_dangling_punctuation() {
+        assert_eq!(due("tomorrow: ship it").remainder, "ship it");
+        assert!(parse("refactor the parser", now()).is_none());
+    }
+}
@@ -0,0 +1,203 @@
+//! Editor detection and "open file at line" support
+
+use serde::Serialize;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config;
+use crate::error::Error;
+use crate::process::{first_existing, which};
+
+/// How an editor's CLI expects the line/column to be passed
+#[derive(Clone, Copy)]
+enum LocationStyle {
+    /// `code -g file:line:col`
+    Goto,
+    /// `zed file:line:col`
+    Suffix,
+    /// `idea --line N --column M file`
+    JetBrains,
+}
+
+struct EditorSpec {
+    id: &'static str,
+    name: &'static str,
+    /// CLI names to look up on PATH
+    clis: &'static [&'static str],
+    /// Launcher locations inside macOS app bundles (GUI apps don't inherit the shell PATH)
+    bundle_paths: &'static [&'static str],
+    style: LocationStyle,
+}
+
+/// Supported editors, in auto-detection priority order
+const EDITORS: &[EditorSpec] = &[
+    EditorSpec {
+        id: "vscode",
+        name: "Visual Studio Code",
+        clis: &["code"],
+        bundle_paths: &["/Applications/Visual Studio Code.app/Contents/Resources/app/bin/code"],
+        style: LocationStyle::Goto,
+    },
+    EditorSpec {
+        id: "cursor",
+        name: "Cursor",
+        clis: &["cursor"],
+        bundle_paths: &["/Applications/Cursor.app/Contents/Resources/app/bin/cursor"],
+        style: LocationStyle::Goto,
+    },
+    EditorSpec {
+        id: "zed",
+        name: "Zed",
+        clis: &["zed", "zeditor"],
+        bundle_paths: &["/Applications/Zed.app/Contents/MacOS/cli"],
+        style: LocationStyle::Suffix,
+    },
+    EditorSpec {
+        id: "intellij",
+        name: "IntelliJ IDEA",
+        clis: &["idea"],
+        bundle_paths: &["/Applications/IntelliJ IDEA.app/Contents/MacOS/idea"],
+        style: LocationStyle::JetBrains,
+    },
+    EditorSpec {
+        id: "webstorm",
+        name: "WebStorm",
+        clis: &["webstorm"],
+        bundle_paths: &["/Applications/WebStorm.app/Contents/MacOS/webstorm"],
+        style: LocationStyle::JetBrains,
+    },
+    EditorSpec {
+        id: "rustrover",
+        name: "RustRover",
+        clis: &["rustrover"],
+        bundle_paths: &["/Applications/RustRover.app/Contents/MacOS/rustrover"],
+        style: LocationStyle::JetBrains,
+    },
+    EditorSpec {
+        id: "pycharm",
+        name: "PyCharm",
+        clis: &["pycharm", "charm"],
+        bundle_paths: &["/Applications/PyCharm.app/Contents/MacOS/pycharm"],
+        style: LocationStyle::JetBrains,
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedEditor {
+    pub id: String,
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// Find an editor's launcher: PATH first, then app bundles, then JetBrains Toolbox scripts
+fn locate(spec: &EditorSpec) -> Option<PathBuf> {
+    if let Some(path) = spec.clis.iter().find_map(|cli| which(cli)) {
+        return Some(path);
+    }
+
+    let mut candidates: Vec<PathBuf> = spec.bundle_paths.iter().map(PathBuf::from).collect();
+    if let (LocationStyle::JetBrains, Ok(home)) = (spec.style, env::var("HOME")) {
+        let scripts =
+            PathBuf::from(home).join("Library/Application Support/JetBrains/Toolbox/scripts");
+        candidates.extend(spec.clis.iter().map(|cli| scripts.join(cli)));
+    }
+
+    first_existing(&candidates)
+}
+
+fn detect() -> Vec<(&'static EditorSpec, PathBuf)> {
+    EDITORS
+        .iter()
+        .filter_map(|spec| locate(spec).map(|path| (spec, path)))
+        .collect()
+}
+
+fn location_args(
+    style: LocationStyle,
+    path: &Path,
+    line: Option<u32>,
+    column: Option<u32>,
+) -> Vec<String> {
+    let file = path.display().to_string();
+    let Some(line) = line else {
+        return vec![file];
+    };
+
+    match style {
+        LocationStyle::Goto | LocationStyle::Suffix => {
+            let target = match column {
+                Some(column) => format!("{}:{}:{}", file, line, column),
+                None => format!("{}:{}", file, line),
+            };
+            if matches!(style, LocationStyle::Goto) {
+                vec!["-g".to_string(), target]
+            } else {
+                vec![target]
+            }
+        }
+        LocationStyle::JetBrains => {
+            let mut args = vec!["--line".to_string(), line.to_string()];
+            if let Some(column) = column {
+                args.push("--column".to_string());
+                args.push(column.to_string());
+            }
+            args.push(file);
+            args
+        }
+    }
+}
+
+#[tauri::command]
+pub fn list_editors() -> Vec<DetectedEditor> {
+    detect()
+        .into_iter()
+        .map(|(spec, path)| DetectedEditor {
+            id: spec.id.to_string(),
+            name: spec.name.to_string(),
+            path,
+        })
+        .collect()
+}
+
+/// Set the preferred editor; `None` goes back to auto-detection
+#[tauri::command]
+pub fn set_default_editor(editor: Option<String>) -> Result<(), Error> {
+    if let Some(ref id) = editor {
+        if !EDITORS.iter().any(|spec| spec.id == id) {
+            return Err(Error::InvalidInput(format!("Unknown editor: {}", id)));
+        }
+    }
+    config::update(|c| c.editor = editor)
+        .map(|_| ())
+        .map_err(Error::from)
+}
+
+/// Open a file in the configured (or first detected) editor, jumping to line/column if given
+#[tauri::command]
+pub fn open_in_editor(path: String, line: Option<u32>, column: Option<u32>) -> Result<(), Error> {
+    let file = PathBuf::from(&path);
+    if !file.exists() {
+        return Err(Error::NotFound(format!("File not found: {}", path)));
+    }
+
+    let detected = detect();
+    let preferred = config::load().editor;
+    let (spec, launcher) = preferred
+        .and_then(|id| detected.iter().find(|(spec, _)| spec.id == id))
+        .or_else(|| detected.first())
+        .ok_or("No supported editor found (VS Code, Cursor, Zed or JetBrains)")?;
+
+    let mut child = Command::new(launcher)
+        .args(location_args(spec.style, &file, line, column))
+        .spawn()
+        .map_err(|e| format!("Failed to launch {}: {}", spec.name, e))?;
+
+    // Editor CLIs hand off to the running app and exit; reap them off the main thread
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+
+    Ok(())
+}
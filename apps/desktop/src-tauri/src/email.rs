@@ -0,0 +1,386 @@
+//! Sending reports and alerts by email over SMTP
+//!
+//! Server settings live in the config (`email`), with presets for common providers; the
+//! password is kept in the keychain. Mail goes out through lettre: implicit TLS and
+//! STARTTLS use rustls with the bundled web PKI roots, and plain connections are only
+//! allowed to a local relay. With `critical_alerts` on, agent failures and server crashes
+//! from the integrations event stream are emailed as they happen, through the outbox.
+
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::integrations::{Event, EventKind};
+use crate::outbox::{self, Effect};
+use crate::{app_lock, config, report, secrets, store};
+
+const KEYCHAIN_ACCOUNT: &str = "smtp-password";
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Security {
+    /// TLS from the first byte, usually port 465
+    Tls,
+    /// Upgrade with STARTTLS, usually port 587
+    #[default]
+    StartTls,
+    /// No encryption; only for a relay on this machine
+    None,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EmailSettings {
+    /// Preset id the settings came from, for display only
+    pub preset: Option<String>,
+    pub host: String,
+    pub port: u16,
+    pub security: Security,
+    pub username: Option<String>,
+    pub from: String,
+    /// Where reports and alerts go
+    pub to: String,
+    /// Also email the weekly summary when the scheduler publishes it
+    pub weekly_report: bool,
+    /// Email agent failures and server crashes as they happen
+    pub critical_alerts: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailPreset {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub host: &'static str,
+    pub port: u16,
+    pub security: Security,
+    /// Fixed username, e.g. SendGrid's `apikey`; the sender address otherwise
+    pub username: Option<&'static str>,
+}
+
+pub const PRESETS: &[EmailPreset] = &[
+    EmailPreset {
+        id: "gmail",
+        name: "Gmail",
+        host: "smtp.gmail.com",
+        port: 587,
+        security: Security::StartTls,
+        username: None,
+    },
+    EmailPreset {
+        id: "outlook",
+        name: "Outlook / Microsoft 365",
+        host: "smtp.office365.com",
+        port: 587,
+        security: Security::StartTls,
+        username: None,
+    },
+    EmailPreset {
+        id: "icloud",
+        name: "iCloud Mail",
+        host: "smtp.mail.me.com",
+        port: 587,
+        security: Security::StartTls,
+        username: None,
+    },
+    EmailPreset {
+        id: "fastmail",
+        name: "Fastmail",
+        host: "smtp.fastmail.com",
+        port: 465,
+        security: Security::Tls,
+        username: None,
+    },
+    EmailPreset {
+        id: "sendgrid",
+        name: "SendGrid",
+        host: "smtp.sendgrid.net",
+        port: 587,
+        security: Security::StartTls,
+        username: Some("apikey"),
+    },
+];
+
+fn password() -> Result<Option<String>, String> {
+    secrets::get(KEYCHAIN_ACCOUNT)
+}
+
+fn is_local(host: &str) -> bool {
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// Addresses in a settings field, refusing line breaks that could inject headers
+fn mailboxes(field: &str, value: &str) -> Result<Vec<Mailbox>, String> {
+    if value.contains(['\r', '\n']) {
+        return Err(format!("The {} address can't contain line breaks", field));
+    }
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(|address| {
+            address
+                .parse::<Mailbox>()
+                .map_err(|e| format!("Invalid {} address {}: {}", field, address, e))
+        })
+        .collect()
+}
+
+fn message(settings: &EmailSettings, subject: &str, body: &str) -> Result<Message, String> {
+    let from = mailboxes("sender", &settings.from)?
+        .pop()
+        .ok_or("A sender address is required")?;
+    let to = mailboxes("recipient", &settings.to)?;
+    if to.is_empty() {
+        return Err("A recipient address is required".to_string());
+    }
+    let mut builder = Message::builder()
+        .from(from)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN);
+    for mailbox in to {
+        builder = builder.to(mailbox);
+    }
+    builder
+        .body(body.to_string())
+        .map_err(|e| format!("Failed to build email: {}", e))
+}
+
+fn transport(settings: &EmailSettings, password: Option<String>) -> Result<SmtpTransport, String> {
+    let builder = match settings.security {
+        Security::Tls => SmtpTransport::relay(&settings.host),
+        Security::StartTls => SmtpTransport::starttls_relay(&settings.host),
+        Security::None if is_local(&settings.host) => {
+            if password.is_some() {
+                return Err("Refusing to send a password without TLS".to_string());
+            }
+            Ok(SmtpTransport::builder_dangerous(&settings.host))
+        }
+        Security::None => {
+            return Err("Unencrypted SMTP is only allowed to a relay on this machine".to_string())
+        }
+    }
+    .map_err(|e| format!("Invalid SMTP host {}: {}", settings.host, e))?
+    .port(settings.port)
+    .timeout(Some(IO_TIMEOUT));
+    let builder = match password {
+        Some(password) => {
+            let username = settings.username.as_deref().unwrap_or(&settings.from);
+            builder.credentials(Credentials::new(username.to_string(), password))
+        }
+        None => builder,
+    };
+    Ok(builder.build())
+}
+
+fn deliver(
+    settings: &EmailSettings,
+    password: Option<String>,
+    subject: &str,
+    body: &str,
+) -> Result<(), String> {
+    let message = message(settings, subject, body)?;
+    transport(settings, password)?
+        .send(&message)
+        .map(|_| ())
+        .map_err(|e| format!("SMTP error: {}", e))
+}
+
+/// Send a plain-text email to the configured address
+pub fn send(subject: &str, body: &str) -> Result<(), Error> {
+    let settings = config::load().email;
+    if settings.host.is_empty() || settings.from.is_empty() || settings.to.is_empty() {
+        return Err(Error::InvalidInput("Email is not configured".to_string()));
+    }
+    deliver(&settings, password()?, subject, body).map_err(Error::from)
+}
+
+/// Queue an email for a critical event from the integrations stream, if that's enabled
+pub fn alert(event: &Event, seq: i64) {
+    if !matches!(
+        event.kind,
+        EventKind::AgentFailed | EventKind::ServerCrashed
+    ) {
+        return;
+    }
+    let settings = config::load().email;
+    if !settings.critical_alerts || settings.host.is_empty() || settings.to.is_empty() {
+        return;
+    }
+    let effect = Effect::Email {
+        subject: format!("[Claude PM] {}", event.title),
+        body: event.detail.clone(),
+    };
+    // The bus sequence number makes a replayed event a no-op
+    let key = (seq > 0).then(|| format!("alert-email:{}", seq));
+    if let Err(e) = outbox::enqueue(effect, key) {
+        eprintln!("[Claude PM] Failed to queue alert email: {}", e);
+    }
+}
+
+async fn send_async(subject: String, body: String) -> Result<(), Error> {
+    tauri::async_runtime::spawn_blocking(move || send(&subject, &body))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn list_email_presets() -> &'static [EmailPreset] {
+    PRESETS
+}
+
+#[tauri::command]
+pub fn get_email_settings() -> Result<EmailSettings, Error> {
+    app_lock::ensure_unlocked()?;
+    Ok(config::load().email)
+}
+
+/// Save SMTP settings; `password` replaces the stored one, an empty string removes it
+#[tauri::command]
+pub fn set_email_settings(
+    settings: EmailSettings,
+    password: Option<String>,
+) -> Result<EmailSettings, Error> {
+    if settings.port == 0 {
+        return Err(Error::InvalidInput("SMTP port is required".to_string()));
+    }
+    mailboxes("sender", &settings.from).map_err(Error::InvalidInput)?;
+    mailboxes("recipient", &settings.to).map_err(Error::InvalidInput)?;
+    match password.as_deref() {
+        Some("") => secrets::delete(KEYCHAIN_ACCOUNT)?,
+        Some(password) => secrets::set(KEYCHAIN_ACCOUNT, password)?,
+        None => {}
+    }
+    config::update(|c| c.email = settings)
+        .map(|c| c.email)
+        .map_err(Error::from)
+}
+
+#[tauri::command]
+pub async fn send_test_email() -> Result<(), Error> {
+    send_async(
+        "Claude PM test email".to_string(),
+        "Email from Claude PM is set up correctly.".to_string(),
+    )
+    .await
+}
+
+/// Email the weekly summary for the week containing `week_of` (the current week when unset)
+#[tauri::command]
+pub async fn send_weekly_report_email(week_of: Option<i64>) -> Result<(), Error> {
+    let summary = tauri::async_runtime::spawn_blocking(move || {
+        report::summary_for_week_of(week_of.unwrap_or_else(store::now_ms))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    send_async(
+        format!("Claude PM weekly review: {}", report::week_label(&summary)),
+        summary.markdown,
+    )
+    .await
+}
+
+/// Send an alert such as "agent failed on release branch"
+#[tauri::command]
+pub async fn send_alert_email(subject: String, body: String) -> Result<(), Error> {
+    send_async(format!("[Claude PM] {}", subject), body).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn settings(port: u16) -> EmailSettings {
+        EmailSettings {
+            host: "127.0.0.1".to_string(),
+            port,
+            security: Security::None,
+            from: "Claude PM <pm@example.com>".to_string(),
+            to: "one@example.com, two@example.com".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// A one-shot SMTP server that accepts everything; returns what the client sent
+    fn fake_server() -> (u16, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut received = Vec::new();
+            let mut in_data = false;
+            writer.write_all(b"220 localhost ESMTP\r\n").unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end_matches(['\r', '\n']).to_string();
+                received.push(line.clone());
+                let reply: &[u8] = if in_data {
+                    if line != "." {
+                        continue;
+                    }
+                    in_data = false;
+                    b"250 queued\r\n"
+                } else if line.starts_with("EHLO") {
+                    b"250-localhost\r\n250 8BITMIME\r\n"
+                } else if line == "DATA" {
+                    in_data = true;
+                    b"354 go ahead\r\n"
+                } else if line == "QUIT" {
+                    writer.write_all(b"221 bye\r\n").unwrap();
+                    break;
+                } else {
+                    b"250 ok\r\n"
+                };
+                writer.write_all(reply).unwrap();
+            }
+            received
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn delivers_to_every_recipient() {
+        let (port, server) = fake_server();
+        deliver(&settings(port), None, "Weekly review", "All done.").unwrap();
+        let received = server.join().unwrap();
+        assert!(received.iter().any(|l| l == "MAIL FROM:<pm@example.com>"));
+        assert!(received.iter().any(|l| l == "RCPT TO:<one@example.com>"));
+        assert!(received.iter().any(|l| l == "RCPT TO:<two@example.com>"));
+        assert!(received.iter().any(|l| l == "Subject: Weekly review"));
+        assert!(received.iter().any(|l| l == "All done."));
+    }
+
+    #[test]
+    fn rejects_line_breaks_in_addresses() {
+        assert!(mailboxes("sender", "pm@example.com\r\nBcc: all@example.com").is_err());
+        assert!(mailboxes("recipient", "one@example.com\nbcc@example.com").is_err());
+        assert_eq!(
+            mailboxes("recipient", "one@example.com, two@example.com,")
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn refuses_plain_smtp_off_this_machine() {
+        let mut remote = settings(25);
+        remote.host = "smtp.example.com".to_string();
+        assert!(transport(&remote, None).is_err());
+        assert!(transport(&settings(25), Some("secret".to_string())).is_err());
+        assert!(transport(&settings(25), None).is_ok());
+    }
+}
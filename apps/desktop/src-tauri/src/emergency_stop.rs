@@ -0,0 +1,110 @@
+//! Panic button: stop everything the app has started
+//!
+//! Cancels queued and running agent runs (closing their sessions), commands started
+//! through `runner`, any other multiplexer session the app created (`claudepm-…`, e.g.
+//! layouts) and the server we spawned, with its process tree. A service-managed server
+//! isn't ours to stop and is left running. Bound to a global shortcut (`emergencyStop`,
+//! see `shortcuts`).
+//!
+//! Events:
+//! - `emergency-stop` with the [`StopResult`] list
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::notifications::{self, NotificationRequest};
+use crate::{multiplexer, orchestrator, runner};
+
+/// Session names the app gives the sessions it creates
+const SESSION_PREFIX: &str = "claudepm-";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StopResult {
+    /// `agentRun`, `command`, `session` or `server`
+    pub kind: &'static str,
+    pub name: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+fn result(kind: &'static str, name: String, outcome: Result<(), String>) -> StopResult {
+    StopResult {
+        kind,
+        name,
+        ok: outcome.is_ok(),
+        error: outcome.err(),
+    }
+}
+
+/// Stop everything; returns what was stopped and what couldn't be
+pub fn stop_all(app: &AppHandle) -> Vec<StopResult> {
+    let mut results = Vec::new();
+
+    let runs = orchestrator::list_agent_runs()
+        .map(|queue| queue.runs)
+        .unwrap_or_default();
+    for run in runs
+        .into_iter()
+        .filter(|r| r.state == orchestrator::RunState::Queued || r.state.is_active())
+    {
+        let outcome = orchestrator::cancel_agent_run(run.id.clone()).map_err(|e| e.to_string());
+        results.push(result("agentRun", run.title, outcome));
+    }
+
+    for id in runner::cancel_all() {
+        results.push(result("command", id, Ok(())));
+    }
+
+    if let Ok(mux) = multiplexer::active() {
+        for session in mux.list_sessions().unwrap_or_default() {
+            if session.starts_with(SESSION_PREFIX) {
+                let outcome = mux.kill_session(&session);
+                results.push(result("session", session, outcome));
+            }
+        }
+    }
+
+    let spawned = crate::SERVER_PROCESS
+        .lock()
+        .map(|server| server.is_some())
+        .unwrap_or(false);
+    if spawned {
+        crate::stop_server();
+        let outcome = match crate::SERVER_PROCESS.lock() {
+            Ok(server) if server.is_none() => Ok(()),
+            _ => Err("The server is still running".to_string()),
+        };
+        results.push(result("server", "Claude PM server".to_string(), outcome));
+    }
+
+    let failed = results.iter().filter(|r| !r.ok).count();
+    println!(
+        "[Claude PM] Emergency stop: {} stopped, {} failed",
+        results.len() - failed,
+        failed
+    );
+    notifications::notify(
+        app,
+        NotificationRequest {
+            title: "Emergency stop".to_string(),
+            body: match (results.len(), failed) {
+                (0, _) => "Nothing was running".to_string(),
+                (n, 0) => format!("Stopped {} processes", n),
+                (n, failed) => format!("Stopped {} of {} processes", n - failed, n),
+            },
+            category: Some("failed".to_string()),
+            ..Default::default()
+        },
+    );
+    let _ = app.emit("emergency-stop", &results);
+    results
+}
+
+#[tauri::command]
+pub async fn kill_all_managed_processes(app: AppHandle) -> Result<Vec<StopResult>, Error> {
+    tauri::async_runtime::spawn_blocking(move || stop_all(&app))
+        .await
+        .map_err(|e| Error::from(e.to_string()))
+}
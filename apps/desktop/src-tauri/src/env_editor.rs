@@ -0,0 +1,225 @@
+//! Viewing and editing the environment the server is started with
+//!
+//! The server's env is layered: proxy variables (see `http_proxy`), then the active
+//! profile's `env`, then the selected vault env set, then the variables the app always
+//! manages itself (port, log level, API key, auth token). Each variable is reported with
+//! the layer it comes from, secrets masked unless revealed. Only the profile layer is
+//! edited here; vault sets and proxy settings have their own commands.
+//!
+//! Required variables are the keys of the server's `.env.example`; one counts as
+//! missing when neither the injected env nor the server's own `.env` sets it. What the
+//! running server was started with is recorded at spawn, so pending changes can be
+//! listed and flagged as needing a restart.
+
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::error::Error;
+use crate::{app_lock, auth, config, http_proxy, profiles, vault};
+
+const MASK: &str = "••••••••";
+/// Set by the app on every start, so not editable
+const MANAGED: &[&str] = &[
+    "PORT",
+    "LOG_LEVEL",
+    "PATH",
+    auth::API_KEY_ENV,
+    auth::TOKEN_ENV,
+];
+/// Name fragments that mark a variable as a secret
+const SECRET_HINTS: &[&str] = &["SECRET", "TOKEN", "PASSWORD", "PASSWD", "KEY", "CREDENTIAL"];
+
+/// What the running server was started with
+static STARTED_ENV: Mutex<Option<BTreeMap<String, String>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvVar {
+    pub name: String,
+    /// Masked for secrets unless revealed
+    pub value: String,
+    /// `proxy`, `profile`, `vault` or `managed`
+    pub source: &'static str,
+    pub secret: bool,
+    pub editable: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvChange {
+    pub name: String,
+    /// `added`, `removed` or `changed`
+    pub change: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerEnv {
+    pub profile: String,
+    pub vars: Vec<EnvVar>,
+    /// Keys of the server's `.env.example` that nothing sets
+    pub missing: Vec<String>,
+    /// Differences between this env and the running server's
+    pub pending: Vec<EnvChange>,
+    pub restart_required: bool,
+}
+
+fn name_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap())
+}
+
+fn is_secret(name: &str, source: &str) -> bool {
+    let upper = name.to_uppercase();
+    source == "vault" || SECRET_HINTS.iter().any(|hint| upper.contains(hint))
+}
+
+/// Record the env the server was just spawned with
+pub fn mark_started(env: &BTreeMap<String, String>) {
+    if let Ok(mut started) = STARTED_ENV.lock() {
+        *started = Some(env.clone());
+    }
+}
+
+/// Keys of a dotenv file, skipping comments and blank lines
+fn dotenv_keys(path: &Path) -> BTreeSet<String> {
+    fs::read_to_string(path)
+        .map(|contents| {
+            contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .filter_map(|line| {
+                    let line = line.strip_prefix("export ").unwrap_or(line);
+                    line.split_once('=').map(|(key, _)| key.trim().to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn missing(env: &BTreeMap<String, String>) -> Vec<String> {
+    let Some(server_path) = crate::get_server_path() else {
+        return Vec::new();
+    };
+    let local = dotenv_keys(&server_path.join(".env"));
+    dotenv_keys(&server_path.join(".env.example"))
+        .into_iter()
+        .filter(|key| env.get(key).is_none_or(|v| v.is_empty()) && !local.contains(key))
+        .collect()
+}
+
+fn pending(env: &BTreeMap<String, String>) -> Vec<EnvChange> {
+    let Some(started) = STARTED_ENV.lock().ok().and_then(|started| started.clone()) else {
+        return Vec::new();
+    };
+    let names: BTreeSet<&String> = started.keys().chain(env.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let change = match (started.get(name), env.get(name)) {
+                (None, Some(_)) => "added",
+                (Some(_), None) => "removed",
+                (Some(old), Some(new)) if old != new => "changed",
+                _ => return None,
+            };
+            Some(EnvChange {
+                name: name.clone(),
+                change,
+            })
+        })
+        .collect()
+}
+
+/// The env the server would be started with now, layer by layer
+#[tauri::command]
+pub fn get_server_env(reveal: Option<bool>) -> Result<ServerEnv, Error> {
+    let reveal = reveal.unwrap_or(false);
+    if reveal {
+        app_lock::ensure_unlocked()?;
+    }
+    let profile = profiles::active();
+    let mut layers: Vec<(&'static str, BTreeMap<String, String>)> = vec![
+        ("proxy", http_proxy::env()),
+        ("profile", profile.env.clone()),
+    ];
+    if let Some(project) = config::load().server_env_set {
+        layers.push(("vault", vault::env_for(&project)?));
+    }
+    let env = crate::server_env(&profile)?;
+    let mut sources: BTreeMap<&str, &'static str> = BTreeMap::new();
+    for (source, vars) in &layers {
+        for name in vars.keys() {
+            sources.insert(name, source);
+        }
+    }
+    for name in MANAGED {
+        sources.insert(name, "managed");
+    }
+
+    let vars = env
+        .iter()
+        .map(|(name, value)| {
+            let source = sources.get(name.as_str()).copied().unwrap_or("managed");
+            let secret = is_secret(name, source);
+            EnvVar {
+                name: name.clone(),
+                value: if secret && !reveal {
+                    MASK.to_string()
+                } else {
+                    value.clone()
+                },
+                source,
+                secret,
+                editable: source == "profile",
+            }
+        })
+        .collect();
+    let pending = pending(&env);
+    Ok(ServerEnv {
+        profile: profile.name,
+        vars,
+        missing: missing(&env),
+        restart_required: !pending.is_empty(),
+        pending,
+    })
+}
+
+/// Set (or with `None`, remove) a variable in the active profile's env
+#[tauri::command]
+pub fn set_server_env_var(name: String, value: Option<String>) -> Result<ServerEnv, Error> {
+    let name = name.trim().to_string();
+    if !name_pattern().is_match(&name) {
+        return Err(Error::InvalidInput(format!(
+            "Invalid variable name: {:?} (letters, digits and underscores, not starting with a digit)",
+            name
+        )));
+    }
+    if MANAGED
+        .iter()
+        .any(|managed| managed.eq_ignore_ascii_case(&name))
+    {
+        return Err(Error::InvalidInput(format!(
+            "{} is set by the app and can't be edited",
+            name
+        )));
+    }
+    if value.as_ref().is_some_and(|v| v.contains('\0')) {
+        return Err(Error::InvalidInput(
+            "Values can't contain NUL characters".to_string(),
+        ));
+    }
+    profiles::update_active(|profile| match value {
+        Some(value) => {
+            profile.env.insert(name, value);
+        }
+        None => {
+            profile.env.remove(&name);
+        }
+    })?;
+    get_server_env(None)
+}
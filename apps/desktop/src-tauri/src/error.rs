@@ -0,0 +1,105 @@
+//! Error type returned by every command
+//!
+//! Serialized as `{ code, message, details }` so the frontend can switch on `code` and
+//! offer a targeted fix (install Node, set the server path, open a privacy pane) instead
+//! of matching on message text. Internal helpers still use `String` errors; `?` converts
+//! them to [`Error::Internal`].
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Could not find npm. Please ensure Node.js is installed.")]
+    NpmNotFound,
+    #[error("Could not find server directory. Set CLAUDE_PM_SERVER_PATH environment variable.")]
+    ServerPathMissing,
+    #[error("Server dependencies are not installed yet")]
+    DependenciesMissing,
+    /// Something other than a Claude PM server is listening on the server's port
+    #[error("Port {port} is in use by another program")]
+    PortInUse { port: u16 },
+    /// `permission` is the id accepted by `open_permission_settings`
+    #[error("{message}")]
+    PermissionDenied {
+        permission: &'static str,
+        message: String,
+    },
+    /// The app lock is engaged (see `app_lock`)
+    #[error("Claude PM is locked")]
+    Locked,
+    /// A local API or socket client sent a missing or wrong token
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    Unsupported(String),
+    #[error("{0}")]
+    Timeout(String),
+    #[error("{0}")]
+    Cancelled(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl Error {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::NpmNotFound => "npm-not-found",
+            Error::ServerPathMissing => "server-path-missing",
+            Error::DependenciesMissing => "dependencies-missing",
+            Error::PortInUse { .. } => "port-in-use",
+            Error::PermissionDenied { .. } => "permission-denied",
+            Error::Locked => "locked",
+            Error::Unauthorized(_) => "unauthorized",
+            Error::NotFound(_) => "not-found",
+            Error::InvalidInput(_) => "invalid-input",
+            Error::Unsupported(_) => "unsupported",
+            Error::Timeout(_) => "timeout",
+            Error::Cancelled(_) => "cancelled",
+            Error::Internal(_) => "internal",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            Error::ServerPathMissing => Some(json!({ "envVar": "CLAUDE_PM_SERVER_PATH" })),
+            Error::PortInUse { port } => Some(json!({ "port": port })),
+            Error::PermissionDenied { permission, .. } => Some(json!({ "permission": permission })),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Error", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Internal(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::Internal(message.to_string())
+    }
+}
+
+/// Lets typed errors flow back through helpers that still return `String`
+impl From<Error> for String {
+    fn from(error: Error) -> Self {
+        error.to_string()
+    }
+}
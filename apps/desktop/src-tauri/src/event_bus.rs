@@ -0,0 +1,231 @@
+//! Typed event bus shared by the app's subsystems
+//!
+//! Producers (the store, the agent monitor, the server supervisor) publish an
+//! [`AppEvent`]; consumers (integrations, webhooks, scripts) subscribe with a topic
+//! filter instead of being called directly. Every event gets a sequence number, is kept
+//! in a capped log in the `event_log` table, and is forwarded to the frontend by the
+//! bridge set up in [`start`].
+//!
+//! Events:
+//! - `app-event` with the [`Envelope`] of every event
+//! - one event per topic with just its data, e.g. `task-completed` or `server-status`
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::lifecycle::State;
+use crate::orchestrator::AgentRun;
+use crate::server_output::ServerStatus;
+use crate::store::{self, Task};
+use crate::working_hours;
+
+/// Older entries are pruned from `event_log`
+const LOG_LIMIT: i64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Topic {
+    TaskCompleted,
+    TaskUnblocked,
+    AgentBlocked,
+    AgentFailed,
+    ServerCrashed,
+    ServerStatus,
+    ServerLifecycle,
+}
+
+impl Topic {
+    /// Name of the frontend event carrying this topic
+    fn event_name(self) -> &'static str {
+        match self {
+            Self::TaskCompleted => "task-completed",
+            Self::TaskUnblocked => "task-unblocked",
+            Self::AgentBlocked => "agent-blocked",
+            Self::AgentFailed => "agent-failed",
+            Self::ServerCrashed => "server-crashed",
+            Self::ServerStatus => "server-status",
+            Self::ServerLifecycle => "server-lifecycle",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "topic", content = "data")]
+pub enum AppEvent {
+    TaskCompleted { task: Task },
+    TaskUnblocked { task: Task, unblocked_by: Task },
+    AgentBlocked { target: String, prompt: String },
+    AgentFailed { run: AgentRun },
+    ServerCrashed { status: String },
+    ServerStatus(ServerStatus),
+    ServerLifecycle { state: State },
+}
+
+impl AppEvent {
+    pub fn topic(&self) -> Topic {
+        match self {
+            Self::TaskCompleted { .. } => Topic::TaskCompleted,
+            Self::TaskUnblocked { .. } => Topic::TaskUnblocked,
+            Self::AgentBlocked { .. } => Topic::AgentBlocked,
+            Self::AgentFailed { .. } => Topic::AgentFailed,
+            Self::ServerCrashed { .. } => Topic::ServerCrashed,
+            Self::ServerStatus(_) => Topic::ServerStatus,
+            Self::ServerLifecycle { .. } => Topic::ServerLifecycle,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope {
+    /// Increases with every event; `0` if it couldn't be logged
+    pub seq: i64,
+    pub at: i64,
+    /// Published outside working hours (see `working_hours`)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub after_hours: bool,
+    #[serde(flatten)]
+    pub event: AppEvent,
+}
+
+/// An event read back from the log
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggedEvent {
+    pub seq: i64,
+    pub at: i64,
+    pub topic: Topic,
+    pub data: Value,
+}
+
+/// Topics a subscriber wants; empty means all of them
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub topics: BTreeSet<Topic>,
+}
+
+impl Filter {
+    pub fn topics(topics: &[Topic]) -> Self {
+        Self {
+            topics: topics.iter().copied().collect(),
+        }
+    }
+
+    fn matches(&self, topic: Topic) -> bool {
+        self.topics.is_empty() || self.topics.contains(&topic)
+    }
+}
+
+type Handler = Arc<dyn Fn(&Envelope) + Send + Sync>;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static SUBSCRIBERS: Mutex<Vec<(Filter, Handler)>> = Mutex::new(Vec::new());
+
+/// Call `handler` for every event matching `filter`, on the publishing thread, so
+/// anything slow should be handed off
+pub fn subscribe(filter: Filter, handler: impl Fn(&Envelope) + Send + Sync + 'static) {
+    if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+        subscribers.push((filter, Arc::new(handler)));
+    }
+}
+
+fn log(at: i64, topic: Topic, data: &Value) -> Result<i64, String> {
+    let topic = serde_json::to_value(topic).map_err(|e| e.to_string())?;
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO event_log (topic, data, at) VALUES (?1, ?2, ?3)",
+            params![topic.as_str(), data.to_string(), at],
+        )?;
+        let seq = conn.last_insert_rowid();
+        conn.execute(
+            "DELETE FROM event_log WHERE seq <= ?1",
+            params![seq - LOG_LIMIT],
+        )?;
+        Ok(seq)
+    })
+}
+
+/// Log `event`, hand it to matching subscribers and forward it to the frontend
+pub fn publish(event: AppEvent) {
+    let at = store::now_ms();
+    let topic = event.topic();
+    let data = serde_json::to_value(&event)
+        .ok()
+        .and_then(|mut v| v.get_mut("data").map(Value::take))
+        .unwrap_or(Value::Null);
+    let seq = log(at, topic, &data).unwrap_or_else(|e| {
+        eprintln!("[Claude PM] Failed to log event: {}", e);
+        0
+    });
+    let envelope = Envelope {
+        seq,
+        at,
+        after_hours: working_hours::is_after_hours(),
+        event,
+    };
+
+    let handlers: Vec<Handler> = SUBSCRIBERS
+        .lock()
+        .map(|subscribers| {
+            subscribers
+                .iter()
+                .filter(|(filter, _)| filter.matches(topic))
+                .map(|(_, handler)| handler.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    for handler in handlers {
+        handler(&envelope);
+    }
+
+    if let Some(app) = APP.get() {
+        let _ = app.emit(topic.event_name(), &data);
+        let _ = app.emit("app-event", &envelope);
+    }
+}
+
+/// Start forwarding events to the frontend
+pub fn start(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+/// Most recent logged events, newest first, optionally limited to some topics
+#[tauri::command]
+pub fn list_recent_events(
+    topics: Option<Vec<Topic>>,
+    limit: Option<u32>,
+) -> Result<Vec<LoggedEvent>, Error> {
+    let filter = Filter::topics(&topics.unwrap_or_default());
+    let events = store::with_conn(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT seq, at, topic, data FROM event_log ORDER BY seq DESC")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })?;
+    Ok(events
+        .into_iter()
+        .filter_map(|(seq, at, topic, data)| {
+            let topic: Topic = serde_json::from_value(Value::String(topic)).ok()?;
+            Some(LoggedEvent {
+                seq,
+                at,
+                topic,
+                data: serde_json::from_str(&data).unwrap_or(Value::Null),
+            })
+        })
+        .filter(|event| filter.matches(event.topic))
+        .take(limit.unwrap_or(100) as usize)
+        .collect())
+}
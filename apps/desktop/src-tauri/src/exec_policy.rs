@@ -0,0 +1,301 @@
+//! Execution policy for commands spawned on behalf of agents
+//!
+//! While enabled, every command started through `runner` is checked before it runs:
+//! the program must not be on the deny-list, its working directory must be inside a
+//! project repository or one of the allowed roots, and no argument may point into a
+//! denied path. The environment is cut down to an allow-list (plus the proxy variables,
+//! see `http_proxy`), and paths listed as read-only are mounted that way where the
+//! platform can do it: `bwrap` on Linux, `sandbox-exec` on macOS. Violations refuse the
+//! command, are logged and are kept for the settings view.
+//!
+//! Events:
+//! - `exec-policy-violation` with the [`Violation`]
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::process::which;
+use crate::{config, http_proxy, store};
+
+/// Violations kept for `list_exec_violations`
+const VIOLATION_LIMIT: usize = 100;
+const DEFAULT_ENV: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "LANG", "LC_ALL", "TERM", "TMPDIR",
+];
+const DEFAULT_DENIED_COMMANDS: &[&str] = &["sudo", "su", "doas", "pkexec", "launchctl"];
+const DEFAULT_DENIED_PATHS: &[&str] = &["~/.ssh", "~/.aws", "~/.gnupg", "~/.config/gh"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ExecPolicy {
+    pub enabled: bool,
+    /// Variables passed through from the app's environment; everything else is dropped
+    pub env_allowlist: Vec<String>,
+    /// Working directories allowed besides the project repositories
+    pub allowed_roots: Vec<String>,
+    /// Program names (or full paths) that may not be run
+    pub denied_commands: Vec<String>,
+    /// Paths no argument may point into
+    pub denied_paths: Vec<String>,
+    /// Paths mounted read-only for the command, where supported
+    pub read_only_paths: Vec<String>,
+}
+
+impl Default for ExecPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            env_allowlist: DEFAULT_ENV.iter().map(|s| s.to_string()).collect(),
+            allowed_roots: Vec::new(),
+            denied_commands: DEFAULT_DENIED_COMMANDS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            denied_paths: DEFAULT_DENIED_PATHS.iter().map(|s| s.to_string()).collect(),
+            read_only_paths: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Violation {
+    /// `deniedCommand`, `cwdOutsideRoots`, `deniedPath` or `readOnlyUnsupported`
+    pub rule: &'static str,
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    pub detail: String,
+    pub at: i64,
+}
+
+static VIOLATIONS: Mutex<VecDeque<Violation>> = Mutex::new(VecDeque::new());
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+fn expand(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Lexically resolve `.`/`..` and fall back to that when the path doesn't exist
+fn normalize(path: &Path) -> PathBuf {
+    if let Ok(path) = path.canonicalize() {
+        return path;
+    }
+    let mut out = PathBuf::new();
+    for part in path.components() {
+        match part {
+            std::path::Component::ParentDir => {
+                out.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn is_within(path: &Path, root: &Path) -> bool {
+    normalize(path).starts_with(normalize(root))
+}
+
+fn record(violation: Violation) -> Error {
+    eprintln!(
+        "[Claude PM] Exec policy refused {}: {}",
+        violation.program, violation.detail
+    );
+    if let Some(app) = APP.get() {
+        let _ = app.emit("exec-policy-violation", &violation);
+    }
+    let message = format!("Blocked by the execution policy: {}", violation.detail);
+    if let Ok(mut violations) = VIOLATIONS.lock() {
+        if violations.len() == VIOLATION_LIMIT {
+            violations.pop_front();
+        }
+        violations.push_back(violation);
+    }
+    Error::InvalidInput(message)
+}
+
+fn check(
+    policy: &ExecPolicy,
+    program: &str,
+    args: &[String],
+    cwd: Option<&str>,
+) -> Result<(), (&'static str, String)> {
+    let name = Path::new(program)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if policy
+        .denied_commands
+        .iter()
+        .any(|denied| denied == program || *denied == name)
+    {
+        return Err(("deniedCommand", format!("{} is on the deny-list", name)));
+    }
+
+    let cwd = cwd
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    let mut roots: Vec<PathBuf> = store::list_projects()
+        .map(|projects| {
+            projects
+                .into_iter()
+                .filter_map(|p| p.repo_path.map(PathBuf::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    roots.extend(policy.allowed_roots.iter().map(|root| expand(root)));
+    if !roots.iter().any(|root| is_within(&cwd, root)) {
+        return Err((
+            "cwdOutsideRoots",
+            format!(
+                "{} is outside the project repositories and allowed roots",
+                cwd.display()
+            ),
+        ));
+    }
+
+    for arg in args.iter().map(String::as_str).chain([program]) {
+        // `--out=/path` style arguments too
+        let value = arg.split_once('=').map_or(arg, |(_, v)| v);
+        if !(value.starts_with('/') || value.starts_with("~/") || value.contains("..")) {
+            continue;
+        }
+        let path = expand(value);
+        let path = if path.is_absolute() {
+            path
+        } else {
+            cwd.join(path)
+        };
+        if let Some(denied) = policy
+            .denied_paths
+            .iter()
+            .find(|denied| is_within(&path, &expand(denied)))
+        {
+            return Err((
+                "deniedPath",
+                format!("{} is inside denied path {}", arg, denied),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Wrap the command so `read_only` paths can't be written to
+fn confine(program: &str, args: &[String], read_only: &[PathBuf]) -> Option<(String, Vec<String>)> {
+    if read_only.is_empty() {
+        return Some((program.to_string(), args.to_vec()));
+    }
+    if cfg!(target_os = "linux") {
+        let bwrap = which("bwrap")?;
+        let mut wrapped = vec!["--dev-bind".to_string(), "/".to_string(), "/".to_string()];
+        for path in read_only.iter().filter(|p| p.exists()) {
+            let path = path.display().to_string();
+            wrapped.extend(["--ro-bind".to_string(), path.clone(), path]);
+        }
+        wrapped.push("--".to_string());
+        wrapped.push(program.to_string());
+        wrapped.extend(args.iter().cloned());
+        Some((bwrap.display().to_string(), wrapped))
+    } else if cfg!(target_os = "macos") {
+        let rules: String = read_only
+            .iter()
+            .map(|p| {
+                format!(
+                    "(deny file-write* (subpath \"{}\"))",
+                    normalize(p).display().to_string().replace('"', "\\\"")
+                )
+            })
+            .collect();
+        let mut wrapped = vec![
+            "-p".to_string(),
+            format!("(version 1)(allow default){}", rules),
+            program.to_string(),
+        ];
+        wrapped.extend(args.iter().cloned());
+        Some(("/usr/bin/sandbox-exec".to_string(), wrapped))
+    } else {
+        None
+    }
+}
+
+/// Build the command for `program` under the policy, or refuse it
+pub fn command(
+    program: &str,
+    args: &[String],
+    cwd: Option<&str>,
+    env: HashMap<String, String>,
+) -> Result<Command, Error> {
+    let policy = config::load().exec_policy;
+    let violation = |rule: &'static str, detail: String| {
+        record(Violation {
+            rule,
+            program: program.to_string(),
+            args: args.to_vec(),
+            cwd: cwd.map(str::to_string),
+            detail,
+            at: store::now_ms(),
+        })
+    };
+
+    let mut cmd = if policy.enabled {
+        check(&policy, program, args, cwd).map_err(|(rule, detail)| violation(rule, detail))?;
+        let read_only: Vec<PathBuf> = policy.read_only_paths.iter().map(|p| expand(p)).collect();
+        let (program, args) = confine(program, args, &read_only).ok_or_else(|| {
+            violation(
+                "readOnlyUnsupported",
+                "Read-only paths need bwrap (Linux) or sandbox-exec (macOS)".to_string(),
+            )
+        })?;
+        let mut cmd = Command::new(program);
+        cmd.args(args).env_clear();
+        for key in &policy.env_allowlist {
+            if let Ok(value) = std::env::var(key) {
+                cmd.env(key, value);
+            }
+        }
+        cmd
+    } else {
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+        cmd
+    };
+    cmd.envs(http_proxy::env()).envs(env);
+    if let Some(cwd) = cwd {
+        cmd.current_dir(cwd);
+    }
+    Ok(cmd)
+}
+
+#[tauri::command]
+pub fn get_exec_policy() -> ExecPolicy {
+    config::load().exec_policy
+}
+
+#[tauri::command]
+pub fn set_exec_policy(policy: ExecPolicy) -> Result<ExecPolicy, Error> {
+    Ok(config::update(|c| c.exec_policy = policy.clone())?.exec_policy)
+}
+
+/// Recent violations, newest first
+#[tauri::command]
+pub fn list_exec_violations() -> Vec<Violation> {
+    VIOLATIONS
+        .lock()
+        .map(|violations| violations.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,51 @@
+//! Revealing files and folders in the platform file manager
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::Error;
+
+/// Build the platform command that reveals `path` (selecting it where supported)
+fn reveal_command(path: &Path) -> Command {
+    if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("open");
+        cmd.arg("-R").arg(path);
+        cmd
+    } else if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("explorer");
+        cmd.arg(format!("/select,{}", path.display()));
+        cmd
+    } else {
+        // xdg-open can't select a file, so open the containing folder instead
+        let target = if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(path)
+        };
+        let mut cmd = Command::new("xdg-open");
+        cmd.arg(target);
+        cmd
+    }
+}
+
+/// Show a file or directory in Finder / Explorer / the default Linux file manager
+#[tauri::command]
+pub fn reveal_in_file_manager(path: String) -> Result<(), Error> {
+    let path = PathBuf::from(&path);
+    if !path.exists() {
+        return Err(Error::NotFound(format!(
+            "Path not found: {}",
+            path.display()
+        )));
+    }
+
+    // explorer.exe returns a non-zero status even on success, so only check spawn errors
+    let mut child = reveal_command(&path)
+        .spawn()
+        .map_err(|e| format!("Failed to open file manager: {}", e))?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+
+    Ok(())
+}
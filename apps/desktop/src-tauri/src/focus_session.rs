@@ -0,0 +1,243 @@
+//! Timed focus sessions on a task that hold back notifications
+//!
+//! While a session runs, notifications follow the Focus policies (see `dnd`) as they
+//! would under an OS Focus mode, the task and time left are shown in the tray (unless a
+//! pomodoro is using it), and on macOS the Shortcuts named in `focus_session` settings
+//! can switch a Focus mode on and off with it (Focus modes can't be set directly). When
+//! the session ends, on time or stopped early, it's logged as a `focus` time entry for
+//! the task unless a timer was already running on it.
+//!
+//! Events:
+//! - `focus-session-changed` with the [`FocusSession`], or `null` once it ends
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::menubar::TRAY_ID;
+use crate::notifications::{self, NotificationRequest};
+use crate::{config, pomodoro, process, queue_badge, store, timetracking};
+
+const TICK: Duration = Duration::from_secs(1);
+const MAX_MINUTES: u32 = 8 * 60;
+/// Tray titles longer than this are cut off
+const TITLE_CHARS: usize = 24;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FocusSessionSettings {
+    /// Shortcut (in the Shortcuts app) run when a session starts, e.g. one that turns on
+    /// a Focus mode
+    pub focus_on_shortcut: Option<String>,
+    /// Shortcut run when a session ends
+    pub focus_off_shortcut: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusSession {
+    pub task_id: String,
+    pub task_title: String,
+    pub started_at: i64,
+    pub ends_at: i64,
+    pub remaining_ms: i64,
+    /// Whether the Focus-on shortcut was run for this session
+    pub os_focus: bool,
+}
+
+static SESSION: Mutex<Option<FocusSession>> = Mutex::new(None);
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a focus session is holding back notifications
+pub fn is_active() -> bool {
+    SESSION.lock().map(|s| s.is_some()).unwrap_or(false)
+}
+
+fn run_shortcut(name: &str) -> Result<(), Error> {
+    if !cfg!(target_os = "macos") {
+        return Err(Error::Unsupported(
+            "Focus modes can only be switched on macOS".to_string(),
+        ));
+    }
+    let output = process::output(Command::new("shortcuts").args(["run", name]))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::Internal(format!(
+            "Shortcut \"{}\" failed: {}",
+            name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+fn tray_title(session: &FocusSession) -> String {
+    let secs = (session.remaining_ms.max(0) + 999) / 1000;
+    let mut title: String = session.task_title.chars().take(TITLE_CHARS).collect();
+    if session.task_title.chars().count() > TITLE_CHARS {
+        title.push('…');
+    }
+    format!("🎯 {} {:02}:{:02}", title, secs / 60, secs % 60)
+}
+
+fn update_tray(app: &AppHandle, session: Option<&FocusSession>) {
+    // The pomodoro countdown owns the title while it runs
+    if pomodoro::get_pomodoro_state().is_some() {
+        return;
+    }
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_title(session.map(tray_title).or_else(queue_badge::title));
+        let tooltip = session.map(|s| format!("Claude PM — focusing on {}", s.task_title));
+        let _ = tray.set_tooltip(Some(tooltip.as_deref().unwrap_or("Claude PM")));
+    }
+}
+
+/// End the running session, logging its time; `None` if none was running
+fn end(app: &AppHandle, completed: bool) -> Result<Option<FocusSession>, Error> {
+    let Some(session) = SESSION.lock().map_err(|e| e.to_string())?.take() else {
+        return Ok(None);
+    };
+    let ended_at = store::now_ms().min(session.ends_at);
+    update_tray(app, None);
+    if session.os_focus {
+        if let Some(name) = config::load().focus_session.focus_off_shortcut {
+            if let Err(e) = run_shortcut(&name) {
+                eprintln!("[Claude PM] {}", e);
+            }
+        }
+    }
+    // A running timer on the task already counts this time
+    let timed = timetracking::get_current_timer()?.is_some_and(|timer| {
+        timer.entry.task_id == session.task_id && timer.entry.ended_at.is_none()
+    });
+    if !timed {
+        timetracking::log(app, &session.task_id, session.started_at, ended_at, "focus")?;
+    }
+    println!(
+        "[Claude PM] Focus session on task {} ended after {} min",
+        session.task_id,
+        (ended_at - session.started_at) / 60_000
+    );
+    let _ = app.emit("focus-session-changed", None::<FocusSession>);
+    if completed {
+        notifications::notify(
+            app,
+            NotificationRequest {
+                title: "Focus session over".to_string(),
+                body: session.task_title.clone(),
+                key: Some(format!("focus:{}", session.started_at)),
+                category: Some("focus".to_string()),
+                ..Default::default()
+            },
+        );
+    }
+    Ok(Some(session))
+}
+
+fn ensure_ticker(app: &AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || loop {
+        thread::sleep(TICK);
+        let session = {
+            let Ok(mut guard) = SESSION.lock() else {
+                continue;
+            };
+            let Some(session) = guard.as_mut() else {
+                continue;
+            };
+            session.remaining_ms = session.ends_at - store::now_ms();
+            session.clone()
+        };
+        if session.remaining_ms <= 0 {
+            if let Err(e) = end(&app, true) {
+                eprintln!("[Claude PM] Failed to end focus session: {}", e);
+            }
+        } else {
+            update_tray(&app, Some(&session));
+        }
+    });
+}
+
+/// Focus on `task_id` for `minutes`, replacing any running session; `os_focus` runs the
+/// Focus-on shortcut (by default whenever one is configured)
+#[tauri::command]
+pub fn start_focus_session(
+    app: AppHandle,
+    task_id: String,
+    minutes: u32,
+    os_focus: Option<bool>,
+) -> Result<FocusSession, Error> {
+    if minutes == 0 || minutes > MAX_MINUTES {
+        return Err(Error::InvalidInput(format!(
+            "A focus session lasts between 1 and {} minutes",
+            MAX_MINUTES
+        )));
+    }
+    let task = store::with_conn(|conn| store::get_task(conn, &task_id))?
+        .ok_or_else(|| Error::NotFound(format!("Task not found: {}", task_id)))?;
+    end(&app, false)?;
+
+    let shortcut = config::load().focus_session.focus_on_shortcut;
+    let os_focus = match (os_focus.unwrap_or(shortcut.is_some()), shortcut) {
+        (false, _) => false,
+        (true, Some(name)) => {
+            run_shortcut(&name)?;
+            true
+        }
+        (true, None) => {
+            return Err(Error::InvalidInput(
+                "Choose a shortcut that turns on Focus first".to_string(),
+            ))
+        }
+    };
+    let started_at = store::now_ms();
+    let remaining_ms = minutes as i64 * 60_000;
+    let session = FocusSession {
+        task_id: task.id,
+        task_title: task.title,
+        started_at,
+        ends_at: started_at + remaining_ms,
+        remaining_ms,
+        os_focus,
+    };
+    *SESSION.lock().map_err(|e| e.to_string())? = Some(session.clone());
+    ensure_ticker(&app);
+    update_tray(&app, Some(&session));
+    println!(
+        "[Claude PM] Focus session on task {} for {} min",
+        session.task_id, minutes
+    );
+    let _ = app.emit("focus-session-changed", Some(&session));
+    Ok(session)
+}
+
+/// End the session early; its time so far is still logged
+#[tauri::command]
+pub fn stop_focus_session(app: AppHandle) -> Result<Option<FocusSession>, Error> {
+    end(&app, false)
+}
+
+#[tauri::command]
+pub fn get_focus_session() -> Option<FocusSession> {
+    SESSION.lock().ok().and_then(|session| session.clone())
+}
+
+#[tauri::command]
+pub fn set_focus_session_settings(settings: FocusSessionSettings) -> Result<(), Error> {
+    let blank = |name: &Option<String>| name.as_ref().is_some_and(|n| n.trim().is_empty());
+    if blank(&settings.focus_on_shortcut) || blank(&settings.focus_off_shortcut) {
+        return Err(Error::InvalidInput(
+            "Shortcut names can't be blank".to_string(),
+        ));
+    }
+    config::update(|c| c.focus_session = settings)?;
+    Ok(())
+}
@@ -0,0 +1,596 @@
+//! GitHub issues and pull requests linked to tasks
+//!
+//! The token lives in the OS keychain. Reads go through a short-lived cache, and once
+//! GitHub reports the rate limit is exhausted we serve cached data until it resets.
+//! Reads are also kept across restarts (see `api_cache`) for when GitHub is unreachable.
+
+use octocrab::models::{CombinedStatus, IssueState, StatusState};
+use octocrab::{params, Octocrab};
+use rusqlite::params as sql_params;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+use crate::error::Error;
+use crate::{api_cache, connectivity, secrets, store};
+
+const KEYCHAIN_ACCOUNT: &str = "github-token";
+const CACHE_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GithubItemKind {
+    Issue,
+    Pr,
+}
+
+impl GithubItemKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Issue => "issue",
+            Self::Pr => "pr",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubItem {
+    pub kind: GithubItemKind,
+    pub repo: String,
+    pub number: u64,
+    pub title: String,
+    /// `open`, `closed` or `merged`
+    pub state: String,
+    pub url: String,
+}
+
+/// One check run or commit status on a PR's head commit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckStatus {
+    pub name: String,
+    /// `pending`, `success`, `failure` or `neutral` (skipped, or neutral by design)
+    pub state: String,
+    pub url: Option<String>,
+}
+
+/// A PR's head commit and the checks reported on it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrChecks {
+    pub item: GithubItem,
+    pub author: Option<String>,
+    pub head_sha: String,
+    pub checks: Vec<CheckStatus>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubLink {
+    pub task_id: String,
+    pub kind: GithubItemKind,
+    pub repo: String,
+    pub number: u64,
+    pub url: String,
+}
+
+struct Cache {
+    entries: BTreeMap<String, (Instant, serde_json::Value)>,
+    rate_limited_until: Option<SystemTime>,
+}
+
+static CACHE: Mutex<Cache> = Mutex::new(Cache {
+    entries: BTreeMap::new(),
+    rate_limited_until: None,
+});
+
+/// Store a token in the keychain (also used by the device flow)
+pub fn store_token(token: &str) -> Result<(), String> {
+    secrets::set(KEYCHAIN_ACCOUNT, token)
+}
+
+pub fn is_connected() -> bool {
+    secrets::get(KEYCHAIN_ACCOUNT).is_ok_and(|token| token.is_some())
+}
+
+fn client() -> Result<Octocrab, String> {
+    let token = secrets::get(KEYCHAIN_ACCOUNT)?.ok_or("GitHub is not connected")?;
+    Octocrab::builder()
+        .personal_token(token)
+        .build()
+        .map_err(|e| format!("Failed to create GitHub client: {}", e))
+}
+
+fn split_repo(repo: &str) -> Result<(&str, &str), String> {
+    repo.split_once('/')
+        .filter(|(owner, name)| !owner.is_empty() && !name.is_empty())
+        .ok_or_else(|| format!("Expected owner/name, got: {}", repo))
+}
+
+fn is_rate_limited(err: &octocrab::Error) -> bool {
+    match err {
+        octocrab::Error::GitHub { source, .. } => {
+            matches!(source.status_code.as_u16(), 403 | 429)
+                && source.message.to_lowercase().contains("rate limit")
+        }
+        _ => false,
+    }
+}
+
+/// When the core rate limit resets, per GitHub
+async fn rate_limit_reset(octo: &Octocrab) -> SystemTime {
+    let fallback = SystemTime::now() + Duration::from_secs(60);
+    match octo.ratelimit().get().await {
+        Ok(limit) => UNIX_EPOCH + Duration::from_secs(limit.resources.core.reset),
+        Err(_) => fallback,
+    }
+}
+
+/// Serve `key` from cache when fresh (or when rate limited), otherwise fetch and cache;
+/// if GitHub can't be reached, fall back to the last value kept (see `api_cache`)
+async fn cached<T, F, Fut>(octo: &Octocrab, key: String, fetch: F) -> Result<T, String>
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, octocrab::Error>>,
+{
+    let stale = {
+        let cache = CACHE.lock().map_err(|e| e.to_string())?;
+        let limited = cache
+            .rate_limited_until
+            .is_some_and(|until| SystemTime::now() < until);
+        match cache.entries.get(&key) {
+            Some((at, value)) if limited || at.elapsed() < CACHE_TTL => {
+                return serde_json::from_value(value.clone()).map_err(|e| e.to_string());
+            }
+            Some((_, value)) => Some(value.clone()),
+            None if limited => {
+                return Err("GitHub rate limit reached; try again shortly".to_string())
+            }
+            None => None,
+        }
+    };
+
+    match fetch().await {
+        Ok(result) => {
+            let value = serde_json::to_value(&result).map_err(|e| e.to_string())?;
+            api_cache::put_json(&format!("github:{}", key), &value);
+            if let Ok(mut cache) = CACHE.lock() {
+                cache.entries.insert(key, (Instant::now(), value));
+            }
+            Ok(result)
+        }
+        Err(e) if is_rate_limited(&e) => {
+            let reset = rate_limit_reset(octo).await;
+            if let Ok(mut cache) = CACHE.lock() {
+                cache.rate_limited_until = Some(reset);
+            }
+            match stale.or_else(|| api_cache::stale_json(&format!("github:{}", key))) {
+                Some(value) => serde_json::from_value(value).map_err(|e| e.to_string()),
+                None => Err("GitHub rate limit reached; try again shortly".to_string()),
+            }
+        }
+        // Not an answer from GitHub, so it couldn't be reached
+        Err(e) if !matches!(e, octocrab::Error::GitHub { .. }) => {
+            match api_cache::stale_json(&format!("github:{}", key)) {
+                Some(value) => serde_json::from_value(value).map_err(|e| e.to_string()),
+                None => Err(format!("GitHub request failed: {}", e)),
+            }
+        }
+        Err(e) => Err(format!("GitHub request failed: {}", e)),
+    }
+}
+
+fn issue_state(state: &IssueState) -> String {
+    match state {
+        IssueState::Open => "open".to_string(),
+        _ => "closed".to_string(),
+    }
+}
+
+fn issue_item(repo: &str, issue: octocrab::models::issues::Issue) -> GithubItem {
+    GithubItem {
+        kind: GithubItemKind::Issue,
+        repo: repo.to_string(),
+        number: issue.number,
+        title: issue.title,
+        state: issue_state(&issue.state),
+        url: issue.html_url.to_string(),
+    }
+}
+
+fn pr_item(repo: &str, pr: octocrab::models::pulls::PullRequest) -> GithubItem {
+    let state = if pr.merged_at.is_some() {
+        "merged".to_string()
+    } else {
+        pr.state
+            .as_ref()
+            .map(issue_state)
+            .unwrap_or_else(|| "open".to_string())
+    };
+    GithubItem {
+        kind: GithubItemKind::Pr,
+        repo: repo.to_string(),
+        number: pr.number,
+        title: pr.title.unwrap_or_default(),
+        state,
+        url: pr.html_url.map(|u| u.to_string()).unwrap_or_default(),
+    }
+}
+
+fn list_state(state: Option<&str>) -> params::State {
+    match state {
+        Some("closed") => params::State::Closed,
+        Some("all") => params::State::All,
+        _ => params::State::Open,
+    }
+}
+
+async fn fetch_item(
+    octo: &Octocrab,
+    kind: GithubItemKind,
+    repo: &str,
+    number: u64,
+) -> Result<GithubItem, String> {
+    let (owner, name) = split_repo(repo)?;
+    let key = format!("{}:{}#{}", kind.as_str(), repo, number);
+    match kind {
+        GithubItemKind::Issue => {
+            cached(octo, key, || async {
+                octo.issues(owner, name)
+                    .get(number)
+                    .await
+                    .map(|issue| issue_item(repo, issue))
+            })
+            .await
+        }
+        GithubItemKind::Pr => {
+            cached(octo, key, || async {
+                octo.pulls(owner, name)
+                    .get(number)
+                    .await
+                    .map(|pr| pr_item(repo, pr))
+            })
+            .await
+        }
+    }
+}
+
+fn check_run_state(conclusion: Option<&str>) -> &'static str {
+    match conclusion {
+        None => "pending",
+        Some("success") => "success",
+        Some("neutral" | "skipped") => "neutral",
+        Some(_) => "failure",
+    }
+}
+
+/// Check runs and commit statuses on the head of PR `number`, bypassing the cache
+pub async fn fetch_pr_checks(repo: &str, number: u64) -> Result<PrChecks, String> {
+    let octo = client()?;
+    let (owner, name) = split_repo(repo)?;
+    let pr = octo
+        .pulls(owner, name)
+        .get(number)
+        .await
+        .map_err(|e| format!("GitHub request failed: {}", e))?;
+    let author = pr.user.as_ref().map(|user| user.login.clone());
+    let head_sha = pr.head.sha.clone();
+    let item = pr_item(repo, pr);
+
+    let runs = octo
+        .checks(owner, name)
+        .list_check_runs_for_git_ref(params::repos::Commitish(head_sha.clone()))
+        .per_page(100)
+        .send()
+        .await
+        .map_err(|e| format!("GitHub request failed: {}", e))?;
+    let mut checks: Vec<CheckStatus> = runs
+        .check_runs
+        .into_iter()
+        .map(|run| CheckStatus {
+            state: check_run_state(run.conclusion.as_deref()).to_string(),
+            url: run.html_url.or(run.details_url),
+            name: run.name,
+        })
+        .collect();
+    // octocrab's `Reference` has no commit variant, but the endpoint takes a sha
+    let combined: CombinedStatus = octo
+        .get(
+            format!("/repos/{}/{}/commits/{}/status", owner, name, head_sha),
+            None::<&()>,
+        )
+        .await
+        .map_err(|e| format!("GitHub request failed: {}", e))?;
+    checks.extend(combined.statuses.into_iter().map(|status| {
+        CheckStatus {
+            name: status.context.unwrap_or_else(|| "status".to_string()),
+            state: match status.state {
+                StatusState::Success => "success",
+                StatusState::Pending => "pending",
+                _ => "failure",
+            }
+            .to_string(),
+            url: status.target_url,
+        }
+    }));
+    Ok(PrChecks {
+        item,
+        author,
+        head_sha,
+        checks,
+    })
+}
+
+/// Run a GraphQL query (e.g. for Projects, which has no REST API), returning its `data`
+pub async fn graphql(
+    query: &str,
+    variables: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let mut response: serde_json::Value = client()?
+        .graphql(&serde_json::json!({ "query": query, "variables": variables }))
+        .await
+        .map_err(|e| format!("GitHub request failed: {}", e))?;
+    if let Some(errors) = response["errors"].as_array().filter(|e| !e.is_empty()) {
+        let messages: Vec<&str> = errors
+            .iter()
+            .filter_map(|e| e["message"].as_str())
+            .collect();
+        return Err(format!("GitHub request failed: {}", messages.join("; ")));
+    }
+    Ok(response["data"].take())
+}
+
+/// Login of the account the token belongs to
+pub async fn viewer_login() -> Result<String, String> {
+    client()?
+        .current()
+        .user()
+        .await
+        .map(|user| user.login)
+        .map_err(|e| format!("GitHub request failed: {}", e))
+}
+
+/// Every PR linked to any task
+pub fn linked_prs() -> Result<Vec<GithubLink>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT task_id, repo, number, url FROM github_links WHERE kind = 'pr' ORDER BY repo, number",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(GithubLink {
+                task_id: row.get(0)?,
+                kind: GithubItemKind::Pr,
+                repo: row.get(1)?,
+                number: row.get(2)?,
+                url: row.get(3)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+fn links_for(task_id: &str) -> Result<Vec<GithubLink>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT kind, repo, number, url FROM github_links WHERE task_id = ?1 ORDER BY repo, number",
+        )?;
+        let rows = stmt.query_map([task_id], |row| {
+            let kind: String = row.get(0)?;
+            Ok(GithubLink {
+                task_id: task_id.to_string(),
+                kind: if kind == "pr" {
+                    GithubItemKind::Pr
+                } else {
+                    GithubItemKind::Issue
+                },
+                repo: row.get(1)?,
+                number: row.get(2)?,
+                url: row.get(3)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+fn insert_link(task_id: &str, item: &GithubItem) -> Result<(), String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO github_links (task_id, kind, repo, number, url) VALUES (?1, ?2, ?3, ?4, ?5)",
+            sql_params![task_id, item.kind.as_str(), item.repo, item.number, item.url],
+        )
+        .map(|_| ())
+    })
+}
+
+#[tauri::command]
+pub fn set_github_token(token: String) -> Result<(), Error> {
+    if token.trim().is_empty() {
+        return Err(Error::InvalidInput("Token is empty".to_string()));
+    }
+    store_token(token.trim()).map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn disconnect_github() -> Result<(), Error> {
+    secrets::delete(KEYCHAIN_ACCOUNT).map_err(Error::from)
+}
+
+/// Issues in `repo` (`owner/name`), excluding pull requests
+#[tauri::command]
+pub async fn list_github_issues(
+    repo: String,
+    state: Option<String>,
+) -> Result<Vec<GithubItem>, Error> {
+    let octo = client()?;
+    let (owner, name) = split_repo(&repo)?;
+    let key = format!("issues:{}:{}", repo, state.as_deref().unwrap_or("open"));
+    cached(&octo, key, || async {
+        octo.issues(owner, name)
+            .list()
+            .state(list_state(state.as_deref()))
+            .per_page(50)
+            .send()
+            .await
+            .map(|page| {
+                page.items
+                    .into_iter()
+                    .filter(|issue| issue.pull_request.is_none())
+                    .map(|issue| issue_item(&repo, issue))
+                    .collect()
+            })
+    })
+    .await
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub async fn list_github_prs(
+    repo: String,
+    state: Option<String>,
+) -> Result<Vec<GithubItem>, Error> {
+    let octo = client()?;
+    let (owner, name) = split_repo(&repo)?;
+    let key = format!("prs:{}:{}", repo, state.as_deref().unwrap_or("open"));
+    cached(&octo, key, || async {
+        octo.pulls(owner, name)
+            .list()
+            .state(list_state(state.as_deref()))
+            .per_page(50)
+            .send()
+            .await
+            .map(|page| {
+                page.items
+                    .into_iter()
+                    .map(|pr| pr_item(&repo, pr))
+                    .collect()
+            })
+    })
+    .await
+    .map_err(Error::from)
+}
+
+/// Link an existing issue or PR to a task
+#[tauri::command]
+pub async fn link_github_item(
+    task_id: String,
+    kind: GithubItemKind,
+    repo: String,
+    number: u64,
+) -> Result<GithubItem, Error> {
+    let octo = client()?;
+    let item = fetch_item(&octo, kind, &repo, number).await?;
+    insert_link(&task_id, &item)?;
+    Ok(item)
+}
+
+#[tauri::command]
+pub fn unlink_github_item(
+    task_id: String,
+    kind: GithubItemKind,
+    repo: String,
+    number: u64,
+) -> Result<(), Error> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM github_links WHERE task_id = ?1 AND kind = ?2 AND repo = ?3 AND number = ?4",
+            sql_params![task_id, kind.as_str(), repo, number],
+        )
+        .map(|_| ())
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn list_task_github_links(task_id: String) -> Result<Vec<GithubLink>, Error> {
+    links_for(&task_id).map_err(Error::from)
+}
+
+/// Current state of every issue/PR linked to a task
+#[tauri::command]
+pub async fn get_task_github_statuses(task_id: String) -> Result<Vec<GithubItem>, Error> {
+    let octo = client()?;
+    let mut items = Vec::new();
+    for link in links_for(&task_id)? {
+        items.push(fetch_item(&octo, link.kind, &link.repo, link.number).await?);
+    }
+    Ok(items)
+}
+
+/// Open an issue from a task's title/description and link it back
+pub async fn create_issue_for_task(task_id: &str, repo: &str) -> Result<GithubItem, String> {
+    let task = store::with_conn(|conn| store::get_task(conn, task_id))?
+        .ok_or_else(|| format!("Task not found: {}", task_id))?;
+    let octo = client()?;
+    let (owner, name) = split_repo(repo)?;
+
+    let issues = octo.issues(owner, name);
+    let mut builder = issues.create(&task.title);
+    if let Some(ref description) = task.description {
+        builder = builder.body(description);
+    }
+    let issue = builder
+        .send()
+        .await
+        .map_err(|e| format!("Failed to create issue: {}", e))?;
+
+    let item = issue_item(repo, issue);
+    insert_link(task_id, &item)?;
+    Ok(item)
+}
+
+/// Open a pull request from `head` and link it to the task; `base` defaults to the
+/// repository's default branch
+pub async fn create_pull_request(
+    task_id: &str,
+    repo: &str,
+    title: &str,
+    head: &str,
+    base: Option<&str>,
+    body: &str,
+    draft: bool,
+) -> Result<GithubItem, String> {
+    let octo = client()?;
+    let (owner, name) = split_repo(repo)?;
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => octo
+            .repos(owner, name)
+            .get()
+            .await
+            .map_err(|e| format!("Failed to look up {}: {}", repo, e))?
+            .default_branch
+            .unwrap_or_else(|| "main".to_string()),
+    };
+    let pr = octo
+        .pulls(owner, name)
+        .create(title, head, &base)
+        .body(body)
+        .draft(draft)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to create pull request: {}", e))?;
+
+    let item = pr_item(repo, pr);
+    insert_link(task_id, &item)?;
+    Ok(item)
+}
+
+/// Create the issue now, or queue it and return `None` while offline
+#[tauri::command]
+pub async fn create_github_issue_from_task(
+    app: AppHandle,
+    task_id: String,
+    repo: String,
+) -> Result<Option<GithubItem>, Error> {
+    if connectivity::is_online() {
+        return Ok(Some(create_issue_for_task(&task_id, &repo).await?));
+    }
+    split_repo(&repo)?;
+    connectivity::submit(&app, connectivity::Job::GithubIssue { task_id, repo })?;
+    Ok(None)
+}
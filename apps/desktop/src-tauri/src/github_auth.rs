@@ -0,0 +1,153 @@
+//! GitHub device authorization flow, so users never paste tokens into the UI
+//!
+//! `start_github_device_flow` returns the code to show the user and polls GitHub in the
+//! background, emitting `github-auth` events until the token arrives (and is stored in
+//! the keychain) or the code expires.
+
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::github;
+
+const DEVICE_CODE_URL: &str = "https://github.com/login/device/code";
+const ACCESS_TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+/// `project` is for board sync (see `github_projects`)
+const SCOPES: &str = "repo read:org project";
+
+/// Incremented per flow so a cancelled or superseded poller stops
+static FLOW_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Deserialize)]
+struct DeviceCodeResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+    interval: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceCode {
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthProgress {
+    /// `pending`, `authorized`, `expired`, `denied` or `error`
+    status: &'static str,
+    message: Option<String>,
+}
+
+/// OAuth app client id, from the build or the environment
+fn client_id() -> Result<String, String> {
+    option_env!("CLAUDE_PM_GITHUB_CLIENT_ID")
+        .map(str::to_string)
+        .or_else(|| env::var("CLAUDE_PM_GITHUB_CLIENT_ID").ok())
+        .ok_or_else(|| "GitHub sign-in isn't configured (CLAUDE_PM_GITHUB_CLIENT_ID)".to_string())
+}
+
+fn emit(app: &AppHandle, status: &'static str, message: Option<String>) {
+    let _ = app.emit("github-auth", AuthProgress { status, message });
+}
+
+fn poll(app: AppHandle, flow: u64, client_id: String, code: DeviceCodeResponse) {
+    let deadline = Instant::now() + Duration::from_secs(code.expires_in);
+    let mut interval = Duration::from_secs(code.interval.max(1));
+
+    loop {
+        thread::sleep(interval);
+        if FLOW_ID.load(Ordering::SeqCst) != flow {
+            return;
+        }
+        if Instant::now() >= deadline {
+            emit(&app, "expired", None);
+            return;
+        }
+
+        let response = ureq::post(ACCESS_TOKEN_URL)
+            .set("Accept", "application/json")
+            .send_form(&[
+                ("client_id", client_id.as_str()),
+                ("device_code", code.device_code.as_str()),
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+            ])
+            .map_err(|e| e.to_string())
+            .and_then(|r| r.into_json::<TokenResponse>().map_err(|e| e.to_string()));
+
+        let token = match response {
+            Ok(token) => token,
+            // Transient network errors: keep polling until the code expires
+            Err(_) => continue,
+        };
+
+        if let Some(access_token) = token.access_token {
+            match github::store_token(&access_token) {
+                Ok(()) => emit(&app, "authorized", None),
+                Err(e) => emit(&app, "error", Some(e)),
+            }
+            return;
+        }
+
+        match token.error.as_deref() {
+            Some("authorization_pending") => emit(&app, "pending", None),
+            Some("slow_down") => {
+                interval = Duration::from_secs(token.interval.unwrap_or(interval.as_secs() + 5));
+            }
+            Some("expired_token") => {
+                emit(&app, "expired", None);
+                return;
+            }
+            Some("access_denied") => {
+                emit(&app, "denied", None);
+                return;
+            }
+            _ => {
+                emit(&app, "error", token.error_description.or(token.error));
+                return;
+            }
+        }
+    }
+}
+
+/// Request a device code and start polling for the token
+#[tauri::command]
+pub fn start_github_device_flow(app: AppHandle) -> Result<DeviceCode, Error> {
+    let client_id = client_id()?;
+    let code: DeviceCodeResponse = ureq::post(DEVICE_CODE_URL)
+        .set("Accept", "application/json")
+        .send_form(&[("client_id", client_id.as_str()), ("scope", SCOPES)])
+        .map_err(|e| format!("Failed to start GitHub sign-in: {}", e))?
+        .into_json()
+        .map_err(|e| format!("Unexpected response from GitHub: {}", e))?;
+
+    let result = DeviceCode {
+        user_code: code.user_code.clone(),
+        verification_uri: code.verification_uri.clone(),
+        expires_in: code.expires_in,
+    };
+    let flow = FLOW_ID.fetch_add(1, Ordering::SeqCst) + 1;
+    thread::spawn(move || poll(app, flow, client_id, code));
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn cancel_github_device_flow() {
+    FLOW_ID.fetch_add(1, Ordering::SeqCst);
+}
@@ -0,0 +1,911 @@
+//! Two-way sync between a project's tasks and a GitHub Projects board
+//!
+//! A project is linked to one board. Each task is paired with a board item (a draft
+//! issue for tasks created here, or the issue/PR an item already holds), and the pair's
+//! title, body and status as of the last sync are kept in `github_board_items`. A sync
+//! compares both sides with that record field by field: a field changed on one side is
+//! copied to the other, and a field changed on both is a conflict, settled by the link's
+//! `prefer` (the more recently updated side unless set to `local` or `github`) and
+//! flagged in the report. Unpaired tasks become draft items and unpaired items become
+//! tasks; a pair whose task or item is gone is unlinked, never deleted on the other side.
+//!
+//! Task states map to the board's `Status` column per the link's `status_map` (see
+//! [`DEFAULT_STATUS_MAP`]); a column no state maps to leaves the task's state alone.
+//! The first sync must be previewed with a dry run. After that, linked boards are synced
+//! every ten minutes while online, writing only what changed since the last sync.
+//!
+//! Events:
+//! - `github-board-synced` with the [`SyncReport`] of a scheduled sync that changed something
+
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::store::{self, NewTask, Task, TaskUpdate, TASK_STATES};
+use crate::{connectivity, github, power};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const STATUS_FIELD: &str = "Status";
+/// Columns of GitHub's default board
+pub const DEFAULT_STATUS_MAP: &[(&str, &str)] = &[
+    ("backlog", "Todo"),
+    ("in_progress", "In Progress"),
+    ("review", "In Progress"),
+    ("done", "Done"),
+];
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static SYNCING: AtomicBool = AtomicBool::new(false);
+
+const BOARD_QUERY: &str = r#"
+query($owner: String!, $number: Int!) {
+  repositoryOwner(login: $owner) {
+    ... on ProjectV2Owner {
+      projectV2(number: $number) {
+        id
+        title
+        field(name: "Status") {
+          ... on ProjectV2SingleSelectField { id options { id name } }
+        }
+      }
+    }
+  }
+}"#;
+
+const ITEMS_QUERY: &str = r#"
+query($id: ID!, $cursor: String) {
+  node(id: $id) {
+    ... on ProjectV2 {
+      field(name: "Status") {
+        ... on ProjectV2SingleSelectField { id options { id name } }
+      }
+      items(first: 100, after: $cursor) {
+        pageInfo { hasNextPage endCursor }
+        nodes {
+          id
+          updatedAt
+          isArchived
+          fieldValueByName(name: "Status") {
+            ... on ProjectV2ItemFieldSingleSelectValue { name }
+          }
+          content {
+            __typename
+            ... on DraftIssue { id title body }
+            ... on Issue { id title body }
+            ... on PullRequest { id title body }
+          }
+        }
+      }
+    }
+  }
+}"#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Prefer {
+    /// The side updated most recently
+    Newest,
+    Local,
+    Github,
+}
+
+impl Prefer {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Newest => "newest",
+            Self::Local => "local",
+            Self::Github => "github",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "local" => Self::Local,
+            "github" => Self::Github,
+            _ => Self::Newest,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardLink {
+    pub project_id: String,
+    /// GraphQL node id of the board
+    pub board_id: String,
+    /// User or organization owning the board
+    pub owner: String,
+    pub number: u64,
+    pub title: String,
+    pub status_field_id: String,
+    /// Task state to Status column name
+    pub status_map: BTreeMap<String, String>,
+    pub prefer: Prefer,
+    pub previewed_at: Option<i64>,
+    pub last_synced_at: Option<i64>,
+}
+
+impl BoardLink {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            project_id: row.get("project_id")?,
+            board_id: row.get("board_id")?,
+            owner: row.get("owner")?,
+            number: row.get::<_, i64>("number")? as u64,
+            title: row.get("title")?,
+            status_field_id: row.get("status_field_id")?,
+            status_map: serde_json::from_str(&row.get::<_, String>("status_map")?)
+                .unwrap_or_default(),
+            prefer: Prefer::parse(&row.get::<_, String>("prefer")?),
+            previewed_at: row.get("previewed_at")?,
+            last_synced_at: row.get("last_synced_at")?,
+        })
+    }
+
+    /// Column for a task state
+    fn column(&self, state: &str) -> Option<&str> {
+        self.status_map.get(state).map(String::as_str)
+    }
+
+    /// Task state for a column, the first in `TASK_STATES` order that maps to it
+    fn state(&self, column: &str) -> Option<String> {
+        TASK_STATES
+            .iter()
+            .find(|state| {
+                self.column(state)
+                    .is_some_and(|c| c.eq_ignore_ascii_case(column))
+            })
+            .map(|state| state.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardColumn {
+    pub id: String,
+    pub name: String,
+}
+
+/// A board item with content we can sync
+#[derive(Debug, Clone)]
+struct RemoteItem {
+    id: String,
+    updated_at: i64,
+    column: Option<String>,
+    content_id: String,
+    /// `DraftIssue`, `Issue` or `PullRequest`
+    kind: String,
+    title: String,
+    body: String,
+}
+
+/// A paired task and item as of the last sync
+#[derive(Debug, Clone)]
+struct Pair {
+    task_id: String,
+    item_id: String,
+    content_id: Option<String>,
+    content_kind: String,
+    synced: Values,
+}
+
+/// The fields that sync; `state` is the task state
+#[derive(Debug, Clone, PartialEq)]
+struct Values {
+    title: String,
+    body: String,
+    state: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyncAction {
+    CreateTask,
+    CreateItem,
+    UpdateTask,
+    UpdateItem,
+    Unlink,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncChange {
+    pub action: SyncAction,
+    pub task_id: Option<String>,
+    pub item_id: Option<String>,
+    pub title: String,
+    /// Fields written, e.g. `status: Todo → Done`
+    pub fields: Vec<String>,
+    /// Fields changed on both sides, settled by the link's `prefer`
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub project_id: String,
+    pub dry_run: bool,
+    pub changes: Vec<SyncChange>,
+    /// Changes that failed to apply; the rest still went through
+    pub errors: Vec<String>,
+}
+
+/// A pair whose fields differ, merged
+struct Update {
+    pair: Pair,
+    task: Task,
+    item: RemoteItem,
+    merged: Values,
+    change_task: Option<SyncChange>,
+    change_item: Option<SyncChange>,
+}
+
+enum Step {
+    CreateItem(Task),
+    CreateTask(RemoteItem),
+    Update(Box<Update>),
+    Unlink(Pair, SyncChange),
+}
+
+fn parse_columns(field: &Value) -> Option<(String, Vec<BoardColumn>)> {
+    let id = field["id"].as_str()?.to_string();
+    let columns = serde_json::from_value(field["options"].clone()).ok()?;
+    Some((id, columns))
+}
+
+fn parse_time(value: &Value) -> i64 {
+    value
+        .as_str()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map(|t| t.timestamp_millis())
+        .unwrap_or_default()
+}
+
+/// The board's Status columns and every item with syncable content
+async fn fetch_board(board_id: &str) -> Result<(Vec<BoardColumn>, Vec<RemoteItem>), String> {
+    let mut cursor: Option<String> = None;
+    let mut columns = Vec::new();
+    let mut items = Vec::new();
+    loop {
+        let data =
+            github::graphql(ITEMS_QUERY, json!({ "id": board_id, "cursor": cursor })).await?;
+        let board = &data["node"];
+        if board.is_null() {
+            return Err("The GitHub board no longer exists".to_string());
+        }
+        if let Some((_, found)) = parse_columns(&board["field"]) {
+            columns = found;
+        }
+        for node in board["items"]["nodes"].as_array().into_iter().flatten() {
+            let content = &node["content"];
+            let (Some(id), Some(content_id)) = (node["id"].as_str(), content["id"].as_str()) else {
+                continue;
+            };
+            if node["isArchived"].as_bool().unwrap_or(false) {
+                continue;
+            }
+            items.push(RemoteItem {
+                id: id.to_string(),
+                updated_at: parse_time(&node["updatedAt"]),
+                column: node["fieldValueByName"]["name"]
+                    .as_str()
+                    .map(str::to_string),
+                content_id: content_id.to_string(),
+                kind: content["__typename"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                title: content["title"].as_str().unwrap_or_default().to_string(),
+                body: content["body"].as_str().unwrap_or_default().to_string(),
+            });
+        }
+        let page = &board["items"]["pageInfo"];
+        match page["endCursor"].as_str() {
+            Some(end) if page["hasNextPage"].as_bool().unwrap_or(false) => {
+                cursor = Some(end.to_string())
+            }
+            _ => return Ok((columns, items)),
+        }
+    }
+}
+
+fn get_link(project_id: &str) -> Result<Option<BoardLink>, String> {
+    store::with_conn(|conn| {
+        conn.query_row(
+            "SELECT * FROM github_boards WHERE project_id = ?1",
+            [project_id],
+            BoardLink::from_row,
+        )
+        .optional()
+    })
+}
+
+fn pairs(project_id: &str) -> Result<Vec<Pair>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT * FROM github_board_items WHERE project_id = ?1")?;
+        let rows = stmt.query_map([project_id], |row| {
+            Ok(Pair {
+                task_id: row.get("task_id")?,
+                item_id: row.get("item_id")?,
+                content_id: row.get("content_id")?,
+                content_kind: row.get("content_kind")?,
+                synced: Values {
+                    title: row.get("title")?,
+                    body: row.get("body")?,
+                    state: row.get("status")?,
+                },
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+fn save_pair(project_id: &str, pair: &Pair) -> Result<(), String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO github_board_items (task_id, project_id, item_id, content_id, content_kind, title, body, status, synced_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                pair.task_id,
+                project_id,
+                pair.item_id,
+                pair.content_id,
+                pair.content_kind,
+                pair.synced.title,
+                pair.synced.body,
+                pair.synced.state,
+                store::now_ms()
+            ],
+        )
+        .map(|_| ())
+    })
+}
+
+fn local_values(task: &Task) -> Values {
+    Values {
+        title: task.title.clone(),
+        body: task.description.clone().unwrap_or_default(),
+        state: Some(task.state.clone()),
+    }
+}
+
+/// Describe a field going from `from` to `to`
+fn field(name: &str, from: &str, to: &str) -> String {
+    if name == "status" {
+        format!("status: {} → {}", from, to)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Merge one pair field by field against what was last synced
+fn plan_update(link: &BoardLink, pair: Pair, task: Task, item: RemoteItem) -> Option<Step> {
+    let local = local_values(&task);
+    // An unmapped column says nothing about the task's state
+    let remote = Values {
+        title: item.title.clone(),
+        body: item.body.clone(),
+        state: item
+            .column
+            .as_deref()
+            .and_then(|c| link.state(c))
+            .or_else(|| pair.synced.state.clone()),
+    };
+    let local_wins = match link.prefer {
+        Prefer::Local => true,
+        Prefer::Github => false,
+        Prefer::Newest => task.updated_at >= item.updated_at,
+    };
+    let mut conflicts = Vec::new();
+    let mut pick = |name: &str, base: &str, local: &str, remote: &str| -> String {
+        match (local != base, remote != base) {
+            (true, true) if local != remote => {
+                conflicts.push(name.to_string());
+                if local_wins { local } else { remote }.to_string()
+            }
+            (true, _) => local.to_string(),
+            _ => remote.to_string(),
+        }
+    };
+    let state = |v: &Values| v.state.clone().unwrap_or_default();
+    let merged = Values {
+        title: pick("title", &pair.synced.title, &local.title, &remote.title),
+        body: pick("body", &pair.synced.body, &local.body, &remote.body),
+        state: Some(pick(
+            "status",
+            &state(&pair.synced),
+            &state(&local),
+            &state(&remote),
+        ))
+        .filter(|s| !s.is_empty()),
+    };
+
+    let diff = |from: &Values| -> Vec<String> {
+        let mut fields = Vec::new();
+        if from.title != merged.title {
+            fields.push(field("title", &from.title, &merged.title));
+        }
+        if from.body != merged.body {
+            fields.push(field("body", &from.body, &merged.body));
+        }
+        if from.state != merged.state {
+            fields.push(field("status", &state(from), &state(&merged)));
+        }
+        fields
+    };
+    let change = |action, fields: Vec<String>| {
+        (!fields.is_empty()).then(|| SyncChange {
+            action,
+            task_id: Some(task.id.clone()),
+            item_id: Some(item.id.clone()),
+            title: merged.title.clone(),
+            conflicts: conflicts.clone(),
+            fields,
+        })
+    };
+    let change_task = change(SyncAction::UpdateTask, diff(&local));
+    // A state the board has no column for isn't a change to write there
+    let mut item_fields = diff(&remote);
+    if merged
+        .state
+        .as_deref()
+        .is_some_and(|s| link.column(s).is_none())
+    {
+        item_fields.retain(|f| !f.starts_with("status"));
+    }
+    let change_item = change(SyncAction::UpdateItem, item_fields);
+    if change_task.is_none() && change_item.is_none() && pair.synced == merged {
+        return None;
+    }
+    Some(Step::Update(Box::new(Update {
+        pair,
+        task,
+        item,
+        merged,
+        change_task,
+        change_item,
+    })))
+}
+
+fn plan(link: &BoardLink, tasks: Vec<Task>, pairs: Vec<Pair>, items: Vec<RemoteItem>) -> Vec<Step> {
+    let mut tasks: BTreeMap<String, Task> = tasks.into_iter().map(|t| (t.id.clone(), t)).collect();
+    let mut items: BTreeMap<String, RemoteItem> =
+        items.into_iter().map(|i| (i.id.clone(), i)).collect();
+    let mut steps = Vec::new();
+    for pair in pairs {
+        match (tasks.remove(&pair.task_id), items.remove(&pair.item_id)) {
+            (Some(task), Some(item)) => steps.extend(plan_update(link, pair, task, item)),
+            (task, item) => {
+                let gone = if task.is_none() { "task" } else { "board item" };
+                let change = SyncChange {
+                    action: SyncAction::Unlink,
+                    task_id: Some(pair.task_id.clone()),
+                    item_id: Some(pair.item_id.clone()),
+                    title: task
+                        .map(|t| t.title)
+                        .or(item.map(|i| i.title))
+                        .unwrap_or_else(|| pair.synced.title.clone()),
+                    fields: vec![format!("{} removed", gone)],
+                    conflicts: Vec::new(),
+                };
+                steps.push(Step::Unlink(pair, change));
+            }
+        }
+    }
+    // Items already paired with another project's task aren't ours to import
+    let claimed: BTreeSet<String> = store::with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT item_id FROM github_board_items")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    })
+    .unwrap_or_default();
+    steps.extend(tasks.into_values().map(Step::CreateItem));
+    steps.extend(
+        items
+            .into_values()
+            .filter(|item| !claimed.contains(&item.id))
+            .map(Step::CreateTask),
+    );
+    steps
+}
+
+fn report_change(link: &BoardLink, step: &Step) -> Vec<SyncChange> {
+    match step {
+        Step::CreateItem(task) => vec![SyncChange {
+            action: SyncAction::CreateItem,
+            task_id: Some(task.id.clone()),
+            item_id: None,
+            title: task.title.clone(),
+            fields: link
+                .column(&task.state)
+                .map(|c| vec![format!("status: {}", c)])
+                .unwrap_or_default(),
+            conflicts: Vec::new(),
+        }],
+        Step::CreateTask(item) => vec![SyncChange {
+            action: SyncAction::CreateTask,
+            task_id: None,
+            item_id: Some(item.id.clone()),
+            title: item.title.clone(),
+            fields: item
+                .column
+                .as_deref()
+                .and_then(|c| link.state(c))
+                .map(|s| vec![format!("status: {}", s)])
+                .unwrap_or_default(),
+            conflicts: Vec::new(),
+        }],
+        Step::Update(update) => update
+            .change_task
+            .iter()
+            .chain(&update.change_item)
+            .cloned()
+            .collect(),
+        Step::Unlink(_, change) => vec![change.clone()],
+    }
+}
+
+async fn set_column(
+    link: &BoardLink,
+    columns: &[BoardColumn],
+    item_id: &str,
+    state: &str,
+) -> Result<(), String> {
+    let Some(column) = link.column(state) else {
+        return Ok(());
+    };
+    let option = columns
+        .iter()
+        .find(|c| c.name.eq_ignore_ascii_case(column))
+        .ok_or_else(|| format!("The board has no \"{}\" column", column))?;
+    github::graphql(
+        "mutation($project: ID!, $item: ID!, $field: ID!, $option: String!) {
+          updateProjectV2ItemFieldValue(input: { projectId: $project, itemId: $item, fieldId: $field, value: { singleSelectOptionId: $option } }) { projectV2Item { id } }
+        }",
+        json!({ "project": link.board_id, "item": item_id, "field": link.status_field_id, "option": option.id }),
+    )
+    .await
+    .map(|_| ())
+}
+
+async fn set_content(kind: &str, content_id: &str, title: &str, body: &str) -> Result<(), String> {
+    let mutation = match kind {
+        "DraftIssue" => "mutation($id: ID!, $title: String!, $body: String!) { updateProjectV2DraftIssue(input: { draftIssueId: $id, title: $title, body: $body }) { draftIssue { id } } }",
+        "Issue" => "mutation($id: ID!, $title: String!, $body: String!) { updateIssue(input: { id: $id, title: $title, body: $body }) { issue { id } } }",
+        "PullRequest" => "mutation($id: ID!, $title: String!, $body: String!) { updatePullRequest(input: { pullRequestId: $id, title: $title, body: $body }) { pullRequest { id } } }",
+        other => return Err(format!("Can't update {} items", other)),
+    };
+    github::graphql(
+        mutation,
+        json!({ "id": content_id, "title": title, "body": body }),
+    )
+    .await
+    .map(|_| ())
+}
+
+async fn apply(link: &BoardLink, columns: &[BoardColumn], step: Step) -> Result<(), String> {
+    match step {
+        Step::CreateItem(task) => {
+            let body = task.description.clone().unwrap_or_default();
+            let data = github::graphql(
+                "mutation($project: ID!, $title: String!, $body: String!) {
+                  addProjectV2DraftIssue(input: { projectId: $project, title: $title, body: $body }) {
+                    projectItem { id content { ... on DraftIssue { id } } }
+                  }
+                }",
+                json!({ "project": link.board_id, "title": task.title, "body": body }),
+            )
+            .await?;
+            let item = &data["addProjectV2DraftIssue"]["projectItem"];
+            let item_id = item["id"]
+                .as_str()
+                .ok_or("GitHub didn't return the new item")?
+                .to_string();
+            set_column(link, columns, &item_id, &task.state).await?;
+            save_pair(
+                &link.project_id,
+                &Pair {
+                    task_id: task.id.clone(),
+                    item_id,
+                    content_id: item["content"]["id"].as_str().map(str::to_string),
+                    content_kind: "DraftIssue".to_string(),
+                    synced: local_values(&task),
+                },
+            )
+        }
+        Step::CreateTask(item) => {
+            let state = item.column.as_deref().and_then(|c| link.state(c));
+            let task = store::create_task(NewTask {
+                project_id: link.project_id.clone(),
+                title: item.title.clone(),
+                description: Some(item.body.clone()).filter(|b| !b.is_empty()),
+                state,
+            })?;
+            save_pair(
+                &link.project_id,
+                &Pair {
+                    task_id: task.id.clone(),
+                    item_id: item.id,
+                    content_id: Some(item.content_id),
+                    content_kind: item.kind,
+                    synced: local_values(&task),
+                },
+            )
+        }
+        Step::Update(update) => {
+            let Update {
+                mut pair,
+                task,
+                item,
+                merged,
+                change_task,
+                change_item,
+            } = *update;
+            if change_task.is_some() {
+                store::update_task(
+                    task.id.clone(),
+                    TaskUpdate {
+                        title: Some(merged.title.clone()),
+                        description: Some(merged.body.clone()),
+                        state: merged.state.clone(),
+                    },
+                )?;
+            }
+            if change_item.is_some() {
+                if item.title != merged.title || item.body != merged.body {
+                    set_content(&item.kind, &item.content_id, &merged.title, &merged.body).await?;
+                }
+                if let Some(state) = &merged.state {
+                    let column = link.column(state);
+                    let current = item.column.as_deref();
+                    if column
+                        .is_some_and(|c| !current.is_some_and(|cur| cur.eq_ignore_ascii_case(c)))
+                    {
+                        set_column(link, columns, &item.id, state).await?;
+                    }
+                }
+            }
+            pair.content_id = Some(item.content_id);
+            pair.content_kind = item.kind;
+            pair.synced = merged;
+            save_pair(&link.project_id, &pair)
+        }
+        Step::Unlink(pair, _) => store::with_conn(|conn| {
+            conn.execute(
+                "DELETE FROM github_board_items WHERE task_id = ?1",
+                [&pair.task_id],
+            )
+            .map(|_| ())
+        }),
+    }
+}
+
+async fn sync(project_id: &str, dry_run: bool) -> Result<SyncReport, Error> {
+    let link = get_link(project_id)?
+        .ok_or_else(|| Error::NotFound("The project isn't linked to a GitHub board".to_string()))?;
+    if !dry_run && link.last_synced_at.is_none() && link.previewed_at.is_none() {
+        return Err(Error::InvalidInput(
+            "Preview the first sync with a dry run before applying it".to_string(),
+        ));
+    }
+    let (columns, items) = fetch_board(&link.board_id).await?;
+    let tasks = store::list_tasks(Some(project_id.to_string()), None)?;
+    let steps = plan(&link, tasks, pairs(project_id)?, items);
+
+    let mut report = SyncReport {
+        project_id: project_id.to_string(),
+        dry_run,
+        changes: Vec::new(),
+        errors: Vec::new(),
+    };
+    for step in steps {
+        let changes = report_change(&link, &step);
+        if !dry_run {
+            if let Err(e) = apply(&link, &columns, step).await {
+                let title = changes
+                    .first()
+                    .map(|c| c.title.as_str())
+                    .unwrap_or_default();
+                report.errors.push(format!("{}: {}", title, e));
+                continue;
+            }
+        }
+        report.changes.extend(changes);
+    }
+    let column = if dry_run {
+        "previewed_at"
+    } else {
+        "last_synced_at"
+    };
+    store::with_conn(|conn| {
+        conn.execute(
+            &format!(
+                "UPDATE github_boards SET {} = ?2 WHERE project_id = ?1",
+                column
+            ),
+            params![project_id, store::now_ms()],
+        )
+    })?;
+    if !dry_run && !report.changes.is_empty() {
+        println!(
+            "[Claude PM] Synced {} changes with GitHub board {}",
+            report.changes.len(),
+            link.title
+        );
+    }
+    Ok(report)
+}
+
+fn links() -> Result<Vec<BoardLink>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT * FROM github_boards ORDER BY title")?;
+        let rows = stmt.query_map([], BoardLink::from_row)?;
+        rows.collect()
+    })
+}
+
+async fn sync_all(app: &AppHandle) {
+    for link in links().unwrap_or_default() {
+        // Boards that have never been synced wait for the user to review a dry run
+        if link.last_synced_at.is_none() {
+            continue;
+        }
+        match sync(&link.project_id, false).await {
+            Ok(report) if !report.changes.is_empty() || !report.errors.is_empty() => {
+                let _ = app.emit("github-board-synced", &report);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!(
+                "[Claude PM] GitHub board sync failed for {}: {}",
+                link.title, e
+            ),
+        }
+    }
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        power::wait(SYNC_INTERVAL);
+        if connectivity::is_online()
+            && github::is_connected()
+            && !SYNCING.swap(true, Ordering::SeqCst)
+        {
+            tauri::async_runtime::block_on(sync_all(&app));
+            SYNCING.store(false, Ordering::SeqCst);
+        }
+    });
+}
+
+/// Link a project to board `number` of `owner`; `status_map` defaults to
+/// [`DEFAULT_STATUS_MAP`]. Relinking keeps the pairs made so far if it's the same board.
+#[tauri::command]
+pub async fn link_github_board(
+    project_id: String,
+    owner: String,
+    number: u64,
+    status_map: Option<BTreeMap<String, String>>,
+    prefer: Option<Prefer>,
+) -> Result<BoardLink, Error> {
+    store::with_conn(|conn| store::get_project(conn, &project_id))?
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project_id)))?;
+    let data = github::graphql(BOARD_QUERY, json!({ "owner": owner, "number": number })).await?;
+    let board = &data["repositoryOwner"]["projectV2"];
+    let board_id = board["id"]
+        .as_str()
+        .ok_or_else(|| Error::NotFound(format!("No board #{} for {}", number, owner)))?;
+    let (status_field_id, columns) = parse_columns(&board["field"]).ok_or_else(|| {
+        Error::InvalidInput(format!(
+            "The board has no single-select \"{}\" field",
+            STATUS_FIELD
+        ))
+    })?;
+    let status_map = status_map.unwrap_or_else(|| {
+        DEFAULT_STATUS_MAP
+            .iter()
+            .map(|(state, column)| (state.to_string(), column.to_string()))
+            .collect()
+    });
+    for (state, column) in &status_map {
+        store::validate_state(state)?;
+        if !columns.iter().any(|c| c.name.eq_ignore_ascii_case(column)) {
+            return Err(Error::InvalidInput(format!(
+                "The board has no \"{}\" column (it has {})",
+                column,
+                columns
+                    .iter()
+                    .map(|c| c.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+    }
+    let previous = get_link(&project_id)?;
+    let same_board = previous.as_ref().is_some_and(|p| p.board_id == board_id);
+    let link = BoardLink {
+        project_id: project_id.clone(),
+        board_id: board_id.to_string(),
+        owner,
+        number,
+        title: board["title"].as_str().unwrap_or_default().to_string(),
+        status_field_id,
+        status_map,
+        prefer: prefer.unwrap_or(Prefer::Newest),
+        previewed_at: previous
+            .as_ref()
+            .filter(|_| same_board)
+            .and_then(|p| p.previewed_at),
+        last_synced_at: previous
+            .as_ref()
+            .filter(|_| same_board)
+            .and_then(|p| p.last_synced_at),
+    };
+    store::with_conn(|conn| {
+        if !same_board {
+            conn.execute(
+                "DELETE FROM github_board_items WHERE project_id = ?1",
+                [&project_id],
+            )?;
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO github_boards (project_id, board_id, owner, number, title, status_field_id, status_map, prefer, previewed_at, last_synced_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                link.project_id,
+                link.board_id,
+                link.owner,
+                link.number as i64,
+                link.title,
+                link.status_field_id,
+                serde_json::to_string(&link.status_map).unwrap_or_default(),
+                link.prefer.as_str(),
+                link.previewed_at,
+                link.last_synced_at
+            ],
+        )
+        .map(|_| ())
+    })?;
+    Ok(link)
+}
+
+/// Stop syncing; tasks and board items are left as they are
+#[tauri::command]
+pub fn unlink_github_board(project_id: String) -> Result<(), Error> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM github_board_items WHERE project_id = ?1",
+            [&project_id],
+        )?;
+        conn.execute(
+            "DELETE FROM github_boards WHERE project_id = ?1",
+            [&project_id],
+        )
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_github_boards() -> Result<Vec<BoardLink>, Error> {
+    links().map_err(Error::from)
+}
+
+/// Sync now; with `dry_run`, only report what would change
+#[tauri::command]
+pub async fn sync_github_board(project_id: String, dry_run: bool) -> Result<SyncReport, Error> {
+    if SYNCING.swap(true, Ordering::SeqCst) {
+        return Err(Error::InvalidInput(
+            "A GitHub board sync is already running".to_string(),
+        ));
+    }
+    let result = sync(&project_id, dry_run).await;
+    SYNCING.store(false, Ordering::SeqCst);
+    result
+}
@@ -0,0 +1,165 @@
+//! Server/desktop version compatibility handshake
+//!
+//! Once the server is up, its `/api/health` is asked for its version and REST API
+//! version and compared with [`API_VERSION`], the one this build expects. A mismatch is
+//! reported with upgrade guidance instead of letting the UI run into missing endpoints.
+//!
+//! Events:
+//! - `version-mismatch` with the [`Handshake`] when the versions are incompatible
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::event_bus::{self, AppEvent, Filter, Topic};
+use crate::lifecycle::State;
+use crate::{process, server_api};
+
+/// REST API version this build of the desktop app talks to
+pub const API_VERSION: u32 = 1;
+/// How long to wait for a freshly started server to answer
+const ATTEMPTS: u32 = 15;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Health {
+    version: String,
+    /// Missing on servers from before the handshake
+    api_version: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Handshake {
+    pub desktop_version: &'static str,
+    pub expected_api_version: u32,
+    pub server_version: String,
+    pub server_api_version: Option<u32>,
+    /// Short commit of the server checkout, when it is a git repository
+    pub server_commit: Option<String>,
+    pub compatible: bool,
+    pub guidance: Option<String>,
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static LAST: Mutex<Option<Handshake>> = Mutex::new(None);
+static CHECKING: AtomicBool = AtomicBool::new(false);
+
+fn server_commit() -> Option<String> {
+    let dir = crate::get_server_path()?;
+    let output = process::output(
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .current_dir(dir),
+    )
+    .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|commit| !commit.is_empty())
+}
+
+fn guidance(server_api: Option<u32>) -> Option<String> {
+    match server_api {
+        Some(api) if api == API_VERSION => None,
+        Some(api) if api > API_VERSION => Some(format!(
+            "The server speaks API v{} but this app expects v{}. Update Claude PM Desktop to the latest release.",
+            api, API_VERSION
+        )),
+        Some(api) => Some(format!(
+            "The server speaks API v{} but this app expects v{}. Pull the latest server code and reinstall its dependencies, then restart the server.",
+            api, API_VERSION
+        )),
+        None => Some(
+            "The server is older than this app and doesn't report an API version. Pull the latest server code and reinstall its dependencies, then restart the server."
+                .to_string(),
+        ),
+    }
+}
+
+fn check() -> Result<Handshake, String> {
+    let health: Health = server_api::get_json("/api/health")?;
+    let guidance = guidance(health.api_version);
+    let handshake = Handshake {
+        desktop_version: env!("CARGO_PKG_VERSION"),
+        expected_api_version: API_VERSION,
+        server_version: health.version,
+        server_api_version: health.api_version,
+        server_commit: server_commit(),
+        compatible: guidance.is_none(),
+        guidance,
+    };
+    if let Ok(mut last) = LAST.lock() {
+        *last = Some(handshake.clone());
+    }
+    Ok(handshake)
+}
+
+/// Handshake in the background, retrying while the server comes up
+fn run() {
+    if CHECKING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| {
+        let mut result = Err(String::new());
+        for _ in 0..ATTEMPTS {
+            result = check();
+            if result.is_ok() {
+                break;
+            }
+            thread::sleep(RETRY_DELAY);
+        }
+        CHECKING.store(false, Ordering::SeqCst);
+        match result {
+            Ok(handshake) if !handshake.compatible => {
+                eprintln!(
+                    "[Claude PM] Server version mismatch: {}",
+                    handshake.guidance.as_deref().unwrap_or_default()
+                );
+                if let Some(app) = APP.get() {
+                    let _ = app.emit("version-mismatch", &handshake);
+                }
+            }
+            Ok(handshake) => println!(
+                "[Claude PM] Server {} (API v{}) is compatible",
+                handshake.server_version, API_VERSION
+            ),
+            Err(e) => eprintln!("[Claude PM] Version handshake failed: {}", e),
+        }
+    });
+}
+
+/// Handshake now, and again whenever the server (re)starts
+pub fn start(app: AppHandle) {
+    if APP.set(app).is_err() {
+        return;
+    }
+    event_bus::subscribe(Filter::topics(&[Topic::ServerLifecycle]), |envelope| {
+        if let AppEvent::ServerLifecycle {
+            state: State::Running,
+        } = envelope.event
+        {
+            run();
+        }
+    });
+    run();
+}
+
+/// The last handshake, or a fresh one if none has completed yet
+#[tauri::command]
+pub async fn get_server_handshake() -> Result<Handshake, Error> {
+    if let Some(handshake) = LAST.lock().ok().and_then(|last| last.clone()) {
+        return Ok(handshake);
+    }
+    tauri::async_runtime::spawn_blocking(check)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(Error::from)
+}
@@ -0,0 +1,199 @@
+//! One periodic snapshot of the app's health, instead of a poller per subsystem
+//!
+//! Every 30 seconds (`heartbeat_interval_secs`, stretched on battery) the server's health,
+//! free disk space, the agent queue, the rate limit and sync are gathered into a
+//! [`Heartbeat`] and emitted. Free space comes from `df`/`Get-PSDrive`, so it is re-read
+//! every five minutes rather than every beat. `get_heartbeat` gathers a fresh one on
+//! demand. It also says whether it is after hours (see `working_hours`). Fields that are empty or `false` are left out to keep the event small.
+//!
+//! Events:
+//! - `heartbeat` with the [`Heartbeat`]
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::orchestrator::{self, RunState};
+use crate::{
+    config, connectivity, power, rate_limits, server_port, server_probe, storage, store, sync,
+    working_hours,
+};
+
+const DEFAULT_INTERVAL_SECS: u64 = 30;
+const DISK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+/// Free bytes and the minimum, and when they were read
+static DISK: Mutex<Option<(Instant, Option<u64>, u64)>> = Mutex::new(None);
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Heartbeat {
+    pub at: i64,
+    pub server: ServerBeat,
+    pub disk: DiskBeat,
+    pub queue: QueueBeat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimitBeat>,
+    pub sync: SyncBeat,
+    pub online: bool,
+    /// Outside working hours (see `working_hours`)
+    #[serde(skip_serializing_if = "is_false")]
+    pub after_hours: bool,
+    /// Outbound requests waiting for the network (see `connectivity`)
+    #[serde(skip_serializing_if = "is_zero")]
+    pub outbox: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerBeat {
+    pub port: u16,
+    pub listening: bool,
+    /// `/api/health` answered
+    pub healthy: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskBeat {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub free_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub low: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueBeat {
+    pub queued: usize,
+    pub running: usize,
+    /// Waiting on the user, e.g. for an approval
+    #[serde(skip_serializing_if = "is_zero")]
+    pub blocked: usize,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub failed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitBeat {
+    pub account: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resets_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncBeat {
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    pub syncing: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_synced_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+fn disk() -> DiskBeat {
+    let mut cached = DISK.lock().ok();
+    let reading = cached
+        .as_deref()
+        .and_then(|disk| *disk)
+        .filter(|(read_at, _, _)| read_at.elapsed() < DISK_INTERVAL);
+    let (free_bytes, min_free_bytes) = match reading {
+        Some((_, free, min)) => (free, min),
+        None => {
+            let (free, min) = storage::disk_space();
+            if let Some(cached) = cached.as_deref_mut() {
+                *cached = Some((Instant::now(), free, min));
+            }
+            (free, min)
+        }
+    };
+    DiskBeat {
+        free_bytes,
+        low: free_bytes.is_some_and(|free| free < min_free_bytes),
+    }
+}
+
+fn queue() -> QueueBeat {
+    let runs = orchestrator::list_agent_runs()
+        .map(|queue| queue.runs)
+        .unwrap_or_default();
+    let count = |state: RunState| runs.iter().filter(|r| r.state == state).count();
+    QueueBeat {
+        queued: count(RunState::Queued),
+        running: count(RunState::Running),
+        blocked: count(RunState::Blocked),
+        failed: count(RunState::Failed),
+    }
+}
+
+/// Gather a heartbeat; probes the server, so call it off the main thread
+pub fn collect() -> Heartbeat {
+    let port = server_port();
+    let listening = server_probe::is_listening(port);
+    let connectivity = connectivity::get_connectivity_status();
+    Heartbeat {
+        at: store::now_ms(),
+        server: ServerBeat {
+            port,
+            listening,
+            healthy: listening && server_probe::is_healthy(),
+        },
+        disk: disk(),
+        queue: queue(),
+        rate_limit: rate_limits::limited_until().map(|resets_at| RateLimitBeat {
+            account: rate_limits::current_account(),
+            resets_at,
+        }),
+        sync: SyncBeat {
+            enabled: config::load().sync.enabled,
+            syncing: sync::is_syncing(),
+            last_synced_at: sync::last_synced_at(),
+            last_error: sync::last_error(),
+        },
+        online: connectivity.online,
+        after_hours: working_hours::is_after_hours(),
+        outbox: connectivity.queued,
+    }
+}
+
+fn interval() -> Duration {
+    let secs = config::load()
+        .heartbeat_interval_secs
+        .unwrap_or(DEFAULT_INTERVAL_SECS)
+        .max(5);
+    power::throttled(Duration::from_secs(secs))
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        let _ = app.emit("heartbeat", collect());
+        thread::sleep(interval());
+        power::wait_awake();
+    });
+}
+
+#[tauri::command]
+pub async fn get_heartbeat() -> Result<Heartbeat, Error> {
+    Ok(tauri::async_runtime::spawn_blocking(collect)
+        .await
+        .map_err(|e| e.to_string())?)
+}
@@ -0,0 +1,165 @@
+//! Local endpoint for Claude Code hooks
+//!
+//! Hooks POST their JSON payload to `http://127.0.0.1:4852/hooks`, e.g.
+//! `curl -s -X POST -H 'Content-Type: application/json' -d @- http://127.0.0.1:4852/hooks`.
+//! Events are validated, appended to `hook-events.jsonl` in the data directory and
+//! re-emitted as `agent-activity` so the UI updates live. The port takes no token, so
+//! bodies are capped and nothing here is trusted enough for the audit log.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use tauri::{AppHandle, Emitter};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::{config, store};
+
+pub const HOOK_PORT: u16 = 4852;
+const LOG_FILE: &str = "hook-events.jsonl";
+/// Rotate the event log past this size, keeping one previous file
+const MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+/// Larger payloads are refused unread
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+/// Fields common to every hook payload; the rest is kept in `payload`
+#[derive(Deserialize)]
+struct HookPayload {
+    hook_event_name: String,
+    session_id: String,
+    cwd: Option<String>,
+    tool_name: Option<String>,
+    message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentActivity {
+    pub event: String,
+    pub session_id: String,
+    pub cwd: Option<String>,
+    pub tool_name: Option<String>,
+    pub message: Option<String>,
+    pub received_at: i64,
+    pub payload: Value,
+}
+
+static LOG_LOCK: Mutex<()> = Mutex::new(());
+
+fn log_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join(LOG_FILE))
+}
+
+fn persist(activity: &AgentActivity) -> Result<(), String> {
+    let path = log_path().ok_or("Could not determine data directory")?;
+    let _guard = LOG_LOCK.lock().map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    if fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_BYTES) {
+        let _ = fs::rename(&path, path.with_extension("jsonl.1"));
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| e.to_string())?;
+    let line = serde_json::to_string(activity).map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
+
+fn parse(body: &str) -> Result<AgentActivity, String> {
+    let payload: Value = serde_json::from_str(body).map_err(|e| format!("Invalid JSON: {}", e))?;
+    let fields: HookPayload = serde_json::from_value(payload.clone())
+        .map_err(|e| format!("Invalid hook payload: {}", e))?;
+
+    Ok(AgentActivity {
+        event: fields.hook_event_name,
+        session_id: fields.session_id,
+        cwd: fields.cwd,
+        tool_name: fields.tool_name,
+        message: fields.message,
+        received_at: store::now_ms(),
+        payload,
+    })
+}
+
+/// Always answer 200 so a bad payload never fails the agent's hook
+fn respond(request: Request, warning: Option<&str>) {
+    let body = match warning {
+        Some(warning) => serde_json::json!({ "received": true, "warning": warning }),
+        None => serde_json::json!({ "received": true }),
+    };
+    let header = Header::from_bytes("Content-Type", "application/json").expect("static header");
+    let _ = request.respond(Response::from_string(body.to_string()).with_header(header));
+}
+
+fn handle(app: &AppHandle, mut request: Request) {
+    if *request.method() != Method::Post || request.url() != "/hooks" {
+        let _ = request.respond(Response::empty(404));
+        return;
+    }
+
+    if request
+        .body_length()
+        .is_some_and(|len| len as u64 > MAX_BODY_BYTES)
+    {
+        respond(request, Some("Payload too large"));
+        return;
+    }
+    let mut body = String::new();
+    if request
+        .as_reader()
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_string(&mut body)
+        .is_err()
+    {
+        respond(request, Some("Unreadable body"));
+        return;
+    }
+    if body.len() as u64 > MAX_BODY_BYTES {
+        respond(request, Some("Payload too large"));
+        return;
+    }
+
+    match parse(&body) {
+        Ok(activity) => {
+            if let Err(e) = persist(&activity) {
+                eprintln!("[Claude PM] Failed to persist hook event: {}", e);
+            }
+            let _ = app.emit("agent-activity", activity);
+            respond(request, None);
+        }
+        Err(e) => {
+            eprintln!("[Claude PM] {}", e);
+            respond(request, Some("Invalid payload format"));
+        }
+    }
+}
+
+pub fn start(app: AppHandle) {
+    let server = match Server::http(("127.0.0.1", HOOK_PORT)) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!(
+                "[Claude PM] Failed to start hook receiver on port {}: {}",
+                HOOK_PORT, e
+            );
+            return;
+        }
+    };
+    println!(
+        "[Claude PM] Hook receiver listening on 127.0.0.1:{}",
+        HOOK_PORT
+    );
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            handle(&app, request);
+        }
+    });
+}
@@ -0,0 +1,199 @@
+//! Outbound HTTP(S) proxy settings for the server and spawned agents
+//!
+//! GUI apps don't inherit a shell's `HTTP_PROXY`, so corporate proxies are either read
+//! from the macOS system settings (`scutil --proxy`) or configured manually, then passed
+//! to every process we spawn as the usual upper- and lower-case env vars.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::{config, process};
+
+/// Loopback must never go through the proxy or the app can't reach its own server
+const ALWAYS_BYPASS: &[&str] = &["localhost", "127.0.0.1", "::1"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProxyMode {
+    /// macOS system settings; elsewhere whatever the app inherited
+    #[default]
+    System,
+    Manual,
+    Off,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ProxySettings {
+    pub mode: ProxyMode,
+    /// Manual mode only, e.g. `http://proxy.corp:8080`
+    pub http: Option<String>,
+    pub https: Option<String>,
+    /// Hosts that bypass the proxy, in `NO_PROXY` syntax
+    pub no_proxy: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyInfo {
+    pub settings: ProxySettings,
+    /// Variables that will be set on spawned processes
+    pub env: BTreeMap<String, String>,
+}
+
+/// Resolved proxy configuration: (http, https, bypass list)
+type Resolved = (Option<String>, Option<String>, Vec<String>);
+
+/// Parse `scutil --proxy` output
+fn parse_scutil(output: &str) -> Resolved {
+    let mut values = BTreeMap::new();
+    let mut exceptions = Vec::new();
+    let mut in_exceptions = false;
+
+    for line in output.lines().map(str::trim) {
+        if in_exceptions {
+            match line.split_once(" : ") {
+                Some((_, host)) => exceptions.push(host.to_string()),
+                None => in_exceptions = false,
+            }
+        } else if line.starts_with("ExceptionsList") {
+            in_exceptions = true;
+        } else if let Some((key, value)) = line.split_once(" : ") {
+            values.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    let endpoint = |prefix: &str| {
+        if values.get(&format!("{}Enable", prefix)).map(String::as_str) != Some("1") {
+            return None;
+        }
+        let host = values.get(&format!("{}Proxy", prefix))?;
+        Some(match values.get(&format!("{}Port", prefix)) {
+            Some(port) => format!("http://{}:{}", host, port),
+            None => format!("http://{}", host),
+        })
+    };
+    (endpoint("HTTP"), endpoint("HTTPS"), exceptions)
+}
+
+fn system_proxy() -> Resolved {
+    if !cfg!(target_os = "macos") {
+        return (None, None, Vec::new());
+    }
+    process::output(Command::new("scutil").arg("--proxy"))
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| parse_scutil(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+fn resolve(settings: &ProxySettings) -> Resolved {
+    let (http, https, mut bypass) = match settings.mode {
+        ProxyMode::Off => return (None, None, Vec::new()),
+        ProxyMode::System => system_proxy(),
+        ProxyMode::Manual => (settings.http.clone(), settings.https.clone(), Vec::new()),
+    };
+    bypass.extend(settings.no_proxy.iter().cloned());
+    (http, https, bypass)
+}
+
+fn env_for(settings: &ProxySettings) -> BTreeMap<String, String> {
+    let mut env = BTreeMap::new();
+    let (http, https, bypass) = resolve(settings);
+    if http.is_none() && https.is_none() {
+        return env;
+    }
+
+    let mut no_proxy: Vec<String> = ALWAYS_BYPASS.iter().map(|h| h.to_string()).collect();
+    for host in bypass {
+        if !no_proxy.contains(&host) {
+            no_proxy.push(host);
+        }
+    }
+    let vars = [
+        ("HTTP_PROXY", http),
+        ("HTTPS_PROXY", https),
+        ("NO_PROXY", Some(no_proxy.join(","))),
+    ];
+    for (name, value) in vars {
+        if let Some(value) = value {
+            env.insert(name.to_lowercase(), value.clone());
+            env.insert(name.to_string(), value);
+        }
+    }
+    env
+}
+
+/// Whether a `NO_PROXY` entry (`corp.example`, `.corp.example`, `*.corp.example`, `*`)
+/// covers `host`
+fn bypasses(pattern: &str, host: &str) -> bool {
+    let domain = pattern
+        .trim()
+        .trim_start_matches('*')
+        .trim_start_matches('.');
+    domain.is_empty() || host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+/// The proxy requests to `url` go through, if any
+pub fn proxy_for(url: &str) -> Option<String> {
+    let (http, https, bypass) = resolve(&config::load().http_proxy);
+    let host = url.split_once("://")?.1.split(['/', ':', '?']).next()?;
+    if ALWAYS_BYPASS.contains(&host) || bypass.iter().any(|pattern| bypasses(pattern, host)) {
+        return None;
+    }
+    if url.starts_with("https://") {
+        https.or(http)
+    } else {
+        http
+    }
+}
+
+/// An HTTP client for requests to `url`, through the configured proxy
+pub fn agent(url: &str, timeout: Duration) -> ureq::Agent {
+    let mut builder = ureq::AgentBuilder::new().timeout(timeout);
+    if let Some(proxy) = proxy_for(url).and_then(|proxy| ureq::Proxy::new(proxy).ok()) {
+        builder = builder.proxy(proxy);
+    }
+    builder.build()
+}
+
+/// Proxy variables to set on a spawned process (empty when no proxy applies)
+pub fn env() -> BTreeMap<String, String> {
+    env_for(&config::load().http_proxy)
+}
+
+#[tauri::command]
+pub fn get_proxy_settings() -> ProxyInfo {
+    let settings = config::load().http_proxy;
+    ProxyInfo {
+        env: env_for(&settings),
+        settings,
+    }
+}
+
+/// Save proxy settings; they apply to processes spawned afterwards (restart the server to pick them up)
+#[tauri::command]
+pub fn set_proxy_settings(settings: ProxySettings) -> Result<ProxyInfo, Error> {
+    if settings.mode == ProxyMode::Manual {
+        let urls = [&settings.http, &settings.https];
+        if urls.iter().all(|url| url.is_none()) {
+            return Err(Error::InvalidInput(
+                "Manual proxy mode needs an HTTP or HTTPS proxy URL".to_string(),
+            ));
+        }
+        for url in urls.into_iter().flatten() {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                return Err(Error::InvalidInput(format!(
+                    "Proxy URL must start with http:// or https://: {}",
+                    url
+                )));
+            }
+        }
+    }
+    config::update(|c| c.http_proxy = settings)
+        .map(|_| get_proxy_settings())
+        .map_err(Error::from)
+}
@@ -0,0 +1,297 @@
+//! iCalendar export of task deadlines and milestones
+//!
+//! Deadlines live in the `deadlines` table, whether or not they were also pushed to
+//! Calendar/Reminders by `calendar_sync`. They can be written to an `.ics` file, or
+//! subscribed to through a read-only feed on `http://127.0.0.1:4853/<token>/<project>.ics`
+//! (`all.ics` for every project). The feed only runs while a token is configured.
+
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::error::Error;
+use crate::{app_lock, auth, config, store};
+
+pub const FEED_PORT: u16 = 4853;
+/// Exported deadlines are shown as 30 minute events
+const EVENT_MS: i64 = 30 * 60 * 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedDeadline {
+    /// Task id, or another stable id such as `milestone:<id>`
+    pub source_id: String,
+    /// Looked up from the task when unset
+    #[serde(default)]
+    pub project_id: Option<String>,
+    pub title: String,
+    #[serde(default)]
+    pub notes: String,
+    /// Unix millis
+    pub due_at: i64,
+}
+
+static FEED: Mutex<Option<Arc<Server>>> = Mutex::new(None);
+
+/// Record (or move) a deadline so it shows up in exports and the feed
+pub fn record(deadline: &FeedDeadline) -> Result<(), String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO deadlines (source_id, project_id, title, notes, due_at, updated_at)
+             VALUES (?1, COALESCE(?2, (SELECT project_id FROM tasks WHERE id = ?1)), ?3, ?4, ?5, ?6)",
+            params![
+                deadline.source_id,
+                deadline.project_id,
+                deadline.title,
+                deadline.notes,
+                deadline.due_at,
+                store::now_ms()
+            ],
+        )
+        .map(|_| ())
+    })
+}
+
+fn deadlines(project_id: Option<&str>) -> Result<Vec<FeedDeadline>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT source_id, project_id, title, notes, due_at FROM deadlines
+             WHERE (?1 IS NULL OR project_id = ?1) ORDER BY due_at",
+        )?;
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok(FeedDeadline {
+                source_id: row.get(0)?,
+                project_id: row.get(1)?,
+                title: row.get(2)?,
+                notes: row.get(3)?,
+                due_at: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+fn stamp(ms: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(ms)
+        .map(|d| d.format("%Y%m%dT%H%M%SZ").to_string())
+        .unwrap_or_default()
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11)
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Fold content lines longer than 75 octets, without splitting a UTF-8 sequence
+fn fold(line: &str, out: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn calendar_name(project_id: Option<&str>) -> Result<String, Error> {
+    let Some(project_id) = project_id else {
+        return Ok("Claude PM".to_string());
+    };
+    store::list_projects()?
+        .into_iter()
+        .find(|p| p.id == project_id)
+        .map(|p| format!("Claude PM: {}", p.name))
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project_id)))
+}
+
+/// The calendar for one project, or for every project when `project_id` is unset
+pub fn render(project_id: Option<&str>) -> Result<String, Error> {
+    let name = calendar_name(project_id)?;
+    let now = stamp(store::now_ms());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Claude PM//Deadlines//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape(&name)),
+    ];
+    for deadline in deadlines(project_id)? {
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}@claudepm", escape(&deadline.source_id)),
+            format!("DTSTAMP:{}", now),
+            format!("DTSTART:{}", stamp(deadline.due_at)),
+            format!("DTEND:{}", stamp(deadline.due_at + EVENT_MS)),
+            format!("SUMMARY:{}", escape(&deadline.title)),
+        ]);
+        if !deadline.notes.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(&deadline.notes)));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut out = String::new();
+    for line in &lines {
+        fold(line, &mut out);
+    }
+    Ok(out)
+}
+
+fn feed_url(token: &str, project_id: Option<&str>) -> String {
+    format!(
+        "http://127.0.0.1:{}/{}/{}.ics",
+        FEED_PORT,
+        token,
+        project_id.unwrap_or("all")
+    )
+}
+
+fn handle(token: &str, request: Request) {
+    // Query strings (some clients add cache-busters) are ignored
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+    let project = path
+        .strip_prefix('/')
+        .and_then(|rest| rest.strip_prefix(token))
+        .and_then(|rest| rest.strip_prefix('/'))
+        .and_then(|rest| rest.strip_suffix(".ics"));
+    let result = match (request.method(), project) {
+        (Method::Get | Method::Head, Some("all")) => render(None),
+        (Method::Get | Method::Head, Some(project)) => render(Some(project)),
+        _ => {
+            let _ = request.respond(Response::empty(404));
+            return;
+        }
+    };
+    match result {
+        Ok(body) => {
+            let header = Header::from_bytes("Content-Type", "text/calendar; charset=utf-8")
+                .expect("static header");
+            let _ = request.respond(Response::from_string(body).with_header(header));
+        }
+        Err(Error::NotFound(_)) => {
+            let _ = request.respond(Response::empty(404));
+        }
+        Err(e) => {
+            eprintln!("[Claude PM] Failed to render calendar feed: {}", e);
+            let _ = request.respond(Response::empty(500));
+        }
+    }
+}
+
+/// Serve the feed if a token is configured; safe to call more than once
+pub fn start() {
+    let Some(token) = config::load().ics_feed_token else {
+        return;
+    };
+    let Ok(mut feed) = FEED.lock() else {
+        return;
+    };
+    if feed.is_some() {
+        return;
+    }
+    let server = match Server::http(("127.0.0.1", FEED_PORT)) {
+        Ok(server) => Arc::new(server),
+        Err(e) => {
+            eprintln!(
+                "[Claude PM] Failed to start calendar feed on port {}: {}",
+                FEED_PORT, e
+            );
+            return;
+        }
+    };
+    println!(
+        "[Claude PM] Calendar feed listening on 127.0.0.1:{}",
+        FEED_PORT
+    );
+    *feed = Some(server.clone());
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            handle(&token, request);
+        }
+    });
+}
+
+fn stop() {
+    if let Some(server) = FEED.lock().ok().and_then(|mut feed| feed.take()) {
+        server.unblock();
+    }
+}
+
+/// Add or update a deadline (e.g. a milestone) without exporting it to Calendar
+#[tauri::command]
+pub fn set_deadline(deadline: FeedDeadline) -> Result<(), Error> {
+    record(&deadline).map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn clear_deadline(source_id: String) -> Result<(), Error> {
+    store::with_conn(|conn| {
+        conn.execute("DELETE FROM deadlines WHERE source_id = ?1", [source_id])
+            .map(|_| ())
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn list_deadlines(project_id: Option<String>) -> Result<Vec<FeedDeadline>, Error> {
+    deadlines(project_id.as_deref()).map_err(Error::from)
+}
+
+/// Write an `.ics` file, by default into Downloads; returns the file path
+#[tauri::command]
+pub fn export_ics(
+    project_id: Option<String>,
+    output_path: Option<String>,
+) -> Result<String, Error> {
+    let calendar = render(project_id.as_deref())?;
+    let path = match output_path {
+        Some(path) => PathBuf::from(path),
+        None => dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .ok_or_else(|| Error::NotFound("Could not determine Downloads directory".to_string()))?
+            .join(format!(
+                "{}.ics",
+                project_id.as_deref().unwrap_or("claudepm")
+            )),
+    };
+    fs::write(&path, calendar).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path.display().to_string())
+}
+
+/// Turn the feed on (returning its URL) or off; turning it on again keeps the same URL
+#[tauri::command]
+pub fn set_ics_feed_enabled(enabled: bool) -> Result<Option<String>, Error> {
+    app_lock::ensure_unlocked()?;
+    if !enabled {
+        config::update(|c| c.ics_feed_token = None)?;
+        stop();
+        return Ok(None);
+    }
+    let config = config::update(|c| {
+        c.ics_feed_token.get_or_insert_with(auth::random_hex);
+    })?;
+    start();
+    Ok(config.ics_feed_token.map(|token| feed_url(&token, None)))
+}
+
+/// Feed URL for a project (or all projects) while the feed is enabled
+#[tauri::command]
+pub fn get_ics_feed_url(project_id: Option<String>) -> Result<Option<String>, Error> {
+    app_lock::ensure_unlocked()?;
+    Ok(config::load()
+        .ics_feed_token
+        .map(|token| feed_url(&token, project_id.as_deref())))
+}
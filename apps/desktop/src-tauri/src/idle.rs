@@ -0,0 +1,135 @@
+//! System idle time (no keyboard or mouse input)
+//!
+//! Read from IOKit's `HIDIdleTime` on macOS (via `ioreg`) and from `xprintidle` on Linux.
+//! A watcher compares it against the configured threshold so running timers can pause
+//! when the user walks away and ask what to do with the gap on return.
+//!
+//! Events:
+//! - `idle-started`: idle time crossed the threshold
+//! - `idle-ended`: input resumed after an idle period
+
+use serde::Serialize;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{config, process, timetracking};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+const DEFAULT_THRESHOLD_MINS: u64 = 5;
+
+/// When the current idle period began (unix millis), 0 while active
+static IDLE_SINCE: AtomicI64 = AtomicI64::new(0);
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IdleEvent {
+    /// Unix millis of the last input
+    idle_since: i64,
+    idle_secs: u64,
+}
+
+fn threshold() -> Duration {
+    let mins = config::load()
+        .idle_threshold_mins
+        .unwrap_or(DEFAULT_THRESHOLD_MINS);
+    Duration::from_secs(mins.max(1) * 60)
+}
+
+/// Time since the last keyboard or mouse input
+pub fn idle_time() -> Result<Duration, Error> {
+    if cfg!(target_os = "macos") {
+        let output = process::output(Command::new("ioreg").args(["-c", "IOHIDSystem", "-d", "4"]))?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // "HIDIdleTime" = 123456789 (nanoseconds)
+        stdout
+            .lines()
+            .find(|line| line.contains("\"HIDIdleTime\""))
+            .and_then(|line| line.rsplit('=').next())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_nanos)
+            .ok_or_else(|| Error::Internal("HIDIdleTime not reported".to_string()))
+    } else if cfg!(target_os = "linux") {
+        let output = process::output(&mut Command::new("xprintidle"))
+            .map_err(|_| Error::NotFound("xprintidle is required on Linux".to_string()))?;
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse::<u64>()
+            .map(Duration::from_millis)
+            .map_err(|e| Error::Internal(format!("Unexpected xprintidle output: {}", e)))
+    } else {
+        Err(Error::Unsupported(
+            "Idle detection is not supported on this platform".to_string(),
+        ))
+    }
+}
+
+/// Unix millis the current idle period began, if the user is past the threshold
+pub fn idle_since() -> Option<i64> {
+    Some(IDLE_SINCE.load(Ordering::SeqCst)).filter(|&since| since > 0)
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        let Ok(idle) = idle_time() else {
+            continue;
+        };
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+        let was_idle = idle_since();
+        if idle >= threshold() {
+            if was_idle.is_none() {
+                let since = now - idle.as_millis() as i64;
+                IDLE_SINCE.store(since, Ordering::SeqCst);
+                println!("[Claude PM] User idle for {}s", idle.as_secs());
+                timetracking::pause_for_idle(&app, since);
+                let _ = app.emit(
+                    "idle-started",
+                    IdleEvent {
+                        idle_since: since,
+                        idle_secs: idle.as_secs(),
+                    },
+                );
+            }
+        } else if let Some(since) = was_idle {
+            IDLE_SINCE.store(0, Ordering::SeqCst);
+            // The idle period ended at the last input, not at this poll
+            let ended = now - idle.as_millis() as i64;
+            let _ = app.emit(
+                "idle-ended",
+                IdleEvent {
+                    idle_since: since,
+                    idle_secs: ((ended - since).max(0) / 1000) as u64,
+                },
+            );
+        }
+    });
+}
+
+/// Seconds since the last keyboard or mouse input
+#[tauri::command]
+pub fn get_idle_seconds() -> Result<u64, Error> {
+    idle_time().map(|idle| idle.as_secs())
+}
+
+/// Minutes without input before `idle-started` fires
+#[tauri::command]
+pub fn set_idle_threshold(minutes: u64) -> Result<(), Error> {
+    if minutes == 0 {
+        return Err(Error::InvalidInput(
+            "Idle threshold must be at least one minute".to_string(),
+        ));
+    }
+    config::update(|c| c.idle_threshold_mins = Some(minutes))?;
+    Ok(())
+}
@@ -0,0 +1,351 @@
+//! Importing tasks from Linear (CSV), Jira (JSON) and Trello (JSON) exports
+//!
+//! Every format is flattened into string records keyed by field path (e.g.
+//! `fields.status.name`), so one mapping shape works for all of them. `preview_import`
+//! shows the fields, a sample and a suggested mapping; `run_import` writes to the store.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::store::{self, NewProject, NewTask};
+
+const SAMPLE_SIZE: usize = 10;
+
+type Record = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportFormat {
+    LinearCsv,
+    JiraJson,
+    TrelloJson,
+}
+
+/// Which record fields feed which task fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportMapping {
+    pub title: String,
+    pub description: Option<String>,
+    pub state: Option<String>,
+    /// Field holding the project name; `default_project` is used when unset or empty
+    pub project: Option<String>,
+    pub default_project: String,
+    /// Source state -> ClaudePM state; unmatched states fall back to a name heuristic
+    #[serde(default)]
+    pub state_map: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportPreview {
+    pub format: ImportFormat,
+    pub total: usize,
+    pub fields: Vec<String>,
+    pub sample: Vec<Record>,
+    pub suggested_mapping: ImportMapping,
+    /// Distinct values of the suggested state field, for building `state_map`
+    pub states: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResult {
+    pub projects_created: usize,
+    pub tasks_created: usize,
+    pub skipped: usize,
+}
+
+fn detect(path: &Path, contents: &str) -> Result<ImportFormat, String> {
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    if is_csv {
+        return Ok(ImportFormat::LinearCsv);
+    }
+    let json: Value =
+        serde_json::from_str(contents).map_err(|e| format!("Unrecognised export: {}", e))?;
+    if json.get("cards").is_some() && json.get("lists").is_some() {
+        Ok(ImportFormat::TrelloJson)
+    } else if json.get("issues").is_some() {
+        Ok(ImportFormat::JiraJson)
+    } else {
+        Err("Unrecognised JSON export (expected Jira issues or a Trello board)".to_string())
+    }
+}
+
+fn flatten(prefix: &str, value: &Value, out: &mut Record) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&path, value, out);
+            }
+        }
+        Value::Null => {}
+        Value::String(s) => {
+            out.insert(prefix.to_string(), s.clone());
+        }
+        Value::Array(items) => {
+            let joined: Vec<String> = items
+                .iter()
+                .filter_map(|item| match item {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Object(o) => o.get("name").and_then(Value::as_str).map(str::to_string),
+                    _ => None,
+                })
+                .collect();
+            if !joined.is_empty() {
+                out.insert(prefix.to_string(), joined.join(", "));
+            }
+        }
+        other => {
+            out.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+fn parse_linear(contents: &str) -> Result<Vec<Record>, String> {
+    let mut reader = csv::Reader::from_reader(contents.as_bytes());
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    reader
+        .records()
+        .map(|row| {
+            let row = row.map_err(|e| format!("Invalid CSV: {}", e))?;
+            Ok(headers
+                .iter()
+                .zip(row.iter())
+                .filter(|(_, value)| !value.is_empty())
+                .map(|(h, v)| (h.to_string(), v.to_string()))
+                .collect())
+        })
+        .collect()
+}
+
+fn parse_jira(json: &Value) -> Vec<Record> {
+    json["issues"]
+        .as_array()
+        .map(|issues| {
+            issues
+                .iter()
+                .map(|issue| {
+                    let mut record = Record::new();
+                    flatten("", issue, &mut record);
+                    record
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_trello(json: &Value) -> Vec<Record> {
+    let lists: BTreeMap<&str, &str> = json["lists"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|l| Some((l["id"].as_str()?, l["name"].as_str()?)))
+        .collect();
+    let board = json["name"].as_str().unwrap_or_default();
+
+    json["cards"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|card| {
+            let mut record = Record::new();
+            let card_fields: Map<String, Value> = card
+                .as_object()
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|(k, _)| !matches!(k.as_str(), "actions" | "checklists" | "badges"))
+                .collect();
+            flatten("", &Value::Object(card_fields), &mut record);
+            if let Some(list) = card["idList"].as_str().and_then(|id| lists.get(id)) {
+                record.insert("list".to_string(), list.to_string());
+            }
+            record.insert("board".to_string(), board.to_string());
+            record
+        })
+        .collect()
+}
+
+fn parse(path: &Path) -> Result<(ImportFormat, Vec<Record>), String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let format = detect(path, &contents)?;
+    let records = match format {
+        ImportFormat::LinearCsv => parse_linear(&contents)?,
+        ImportFormat::JiraJson | ImportFormat::TrelloJson => {
+            let json: Value = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+            if format == ImportFormat::JiraJson {
+                parse_jira(&json)
+            } else {
+                parse_trello(&json)
+            }
+        }
+    };
+    Ok((format, records))
+}
+
+fn suggested_mapping(format: ImportFormat, records: &[Record]) -> ImportMapping {
+    let fallback_project = records
+        .first()
+        .and_then(|r| r.get("board").or_else(|| r.get("fields.project.name")))
+        .cloned()
+        .unwrap_or_else(|| "Imported".to_string());
+    let field = |name: &str| Some(name.to_string());
+
+    match format {
+        ImportFormat::LinearCsv => ImportMapping {
+            title: "Title".to_string(),
+            description: field("Description"),
+            state: field("Status"),
+            project: field("Project"),
+            default_project: fallback_project,
+            state_map: BTreeMap::new(),
+        },
+        ImportFormat::JiraJson => ImportMapping {
+            title: "fields.summary".to_string(),
+            description: field("fields.description"),
+            state: field("fields.status.name"),
+            project: field("fields.project.name"),
+            default_project: fallback_project,
+            state_map: BTreeMap::new(),
+        },
+        ImportFormat::TrelloJson => ImportMapping {
+            title: "name".to_string(),
+            description: field("desc"),
+            state: field("list"),
+            project: field("board"),
+            default_project: fallback_project,
+            state_map: BTreeMap::new(),
+        },
+    }
+}
+
+/// Best guess at a ClaudePM state from a source status name
+fn guess_state(source: &str) -> &'static str {
+    let source = source.to_lowercase();
+    if [
+        "done",
+        "complete",
+        "closed",
+        "resolved",
+        "shipped",
+        "canceled",
+        "cancelled",
+    ]
+    .iter()
+    .any(|s| source.contains(s))
+    {
+        "done"
+    } else if source.contains("review") || source.contains("qa") {
+        "review"
+    } else if source.contains("progress") || source.contains("doing") || source.contains("started")
+    {
+        "in_progress"
+    } else {
+        "backlog"
+    }
+}
+
+#[tauri::command]
+pub fn preview_import(file: String) -> Result<ImportPreview, Error> {
+    let (format, records) = parse(Path::new(&file))?;
+    let fields: BTreeSet<String> = records.iter().flat_map(|r| r.keys().cloned()).collect();
+    let mapping = suggested_mapping(format, &records);
+    let states: BTreeSet<String> = mapping
+        .state
+        .as_ref()
+        .map(|field| {
+            records
+                .iter()
+                .filter_map(|r| r.get(field).cloned())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(ImportPreview {
+        format,
+        total: records.len(),
+        fields: fields.into_iter().collect(),
+        sample: records.into_iter().take(SAMPLE_SIZE).collect(),
+        suggested_mapping: mapping,
+        states: states.into_iter().collect(),
+    })
+}
+
+#[tauri::command]
+pub fn run_import(file: String, mapping: ImportMapping) -> Result<ImportResult, Error> {
+    for state in mapping.state_map.values() {
+        store::validate_state(state)?;
+    }
+    let (_, records) = parse(Path::new(&file))?;
+    let mut result = ImportResult::default();
+    let mut project_ids: BTreeMap<String, String> = store::list_projects()?
+        .into_iter()
+        .map(|p| (p.name, p.id))
+        .collect();
+
+    for record in records {
+        let Some(title) = record.get(&mapping.title).filter(|t| !t.trim().is_empty()) else {
+            result.skipped += 1;
+            continue;
+        };
+
+        let project_name = mapping
+            .project
+            .as_ref()
+            .and_then(|field| record.get(field))
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or(&mapping.default_project)
+            .clone();
+        let project_id = match project_ids.get(&project_name) {
+            Some(id) => id.clone(),
+            None => {
+                let project = store::create_project(NewProject {
+                    name: project_name.clone(),
+                    repo_path: None,
+                })?;
+                result.projects_created += 1;
+                project_ids.insert(project_name, project.id.clone());
+                project.id
+            }
+        };
+
+        let state = mapping
+            .state
+            .as_ref()
+            .and_then(|field| record.get(field))
+            .map(|source| {
+                mapping
+                    .state_map
+                    .get(source)
+                    .cloned()
+                    .unwrap_or_else(|| guess_state(source).to_string())
+            });
+
+        store::create_task(NewTask {
+            project_id,
+            title: title.clone(),
+            description: mapping
+                .description
+                .as_ref()
+                .and_then(|field| record.get(field))
+                .cloned(),
+            state,
+        })?;
+        result.tasks_created += 1;
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,470 @@
+//! Chat integrations (Slack and Discord webhooks) notified of task and agent events
+//!
+//! Integrations live in integrations.json in the config directory, each subscribed to a
+//! set of [`EventKind`]s with an optional message template per event (`{{title}}`,
+//! `{{detail}}`, `{{event}}`). Messages go through the outbox, which posts them with a
+//! few quick retries and keeps them across restarts and offline periods. Each
+//! integration is rate limited so a burst of events (many agents blocking at once)
+//! can't flood a channel.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::error::Error;
+use crate::event_bus::{self, AppEvent, Filter, Topic};
+use crate::lifecycle;
+use crate::orchestrator::AgentRun;
+use crate::outbox::{self, Effect};
+use crate::scripting;
+use crate::store::{self, Task};
+use crate::webhooks;
+use crate::{config, email, plugins};
+
+pub const INTEGRATIONS_FILE: &str = "integrations.json";
+/// At most RATE_LIMIT messages per integration inside RATE_WINDOW; the rest are dropped
+const RATE_LIMIT: usize = 10;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+const CRASH_POLL: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Quick attempts per outbox delivery, before it backs off
+const ATTEMPTS: u32 = 3;
+/// Discord rejects longer message content
+const DISCORD_MAX_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventKind {
+    TaskCompleted,
+    AgentBlocked,
+    AgentFailed,
+    ServerCrashed,
+}
+
+const ALL_EVENTS: &[EventKind] = &[
+    EventKind::TaskCompleted,
+    EventKind::AgentBlocked,
+    EventKind::AgentFailed,
+    EventKind::ServerCrashed,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IntegrationKind {
+    Slack,
+    Discord,
+}
+
+impl IntegrationKind {
+    fn default_template(self) -> &'static str {
+        match self {
+            Self::Slack => "*{{title}}*\n{{detail}}",
+            Self::Discord => "**{{title}}**\n{{detail}}",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Integration {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub kind: IntegrationKind,
+    pub webhook_url: String,
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+    #[serde(default = "all_events")]
+    pub events: BTreeSet<EventKind>,
+    /// Per-event message overrides; the kind's default formatting otherwise
+    #[serde(default)]
+    pub templates: BTreeMap<EventKind, String>,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+fn all_events() -> BTreeSet<EventKind> {
+    ALL_EVENTS.iter().copied().collect()
+}
+
+/// Something worth telling a channel about
+#[derive(Debug, Clone)]
+pub struct Event {
+    pub kind: EventKind,
+    pub title: String,
+    pub detail: String,
+    /// Structured details for generic webhooks (see `webhooks`)
+    pub data: Value,
+}
+
+impl Event {
+    pub fn task_completed(task: &Task) -> Self {
+        let project = store::list_projects()
+            .ok()
+            .and_then(|projects| projects.into_iter().find(|p| p.id == task.project_id))
+            .map(|p| p.name)
+            .unwrap_or_default();
+        Self {
+            kind: EventKind::TaskCompleted,
+            title: format!("Task completed: {}", task.title),
+            detail: project,
+            data: json!({ "task": task }),
+        }
+    }
+
+    pub fn agent_blocked(target: &str, prompt: &str) -> Self {
+        Self {
+            kind: EventKind::AgentBlocked,
+            title: format!("Agent in {} needs input", target),
+            detail: prompt.to_string(),
+            data: json!({ "target": target, "prompt": prompt }),
+        }
+    }
+
+    pub fn agent_failed(run: &AgentRun) -> Self {
+        Self {
+            kind: EventKind::AgentFailed,
+            title: format!("Agent failed: {}", run.title),
+            detail: run.error.clone().unwrap_or_default(),
+            data: json!({ "run": run }),
+        }
+    }
+
+    pub fn server_crashed(status: &str) -> Self {
+        Self {
+            kind: EventKind::ServerCrashed,
+            title: "Claude PM server crashed".to_string(),
+            detail: status.to_string(),
+            data: json!({ "status": status }),
+        }
+    }
+}
+
+impl Event {
+    fn from_app_event(event: &AppEvent) -> Option<Self> {
+        match event {
+            AppEvent::TaskCompleted { task } => Some(Self::task_completed(task)),
+            AppEvent::AgentBlocked { target, prompt } => Some(Self::agent_blocked(target, prompt)),
+            AppEvent::AgentFailed { run } => Some(Self::agent_failed(run)),
+            AppEvent::ServerCrashed { status } => Some(Self::server_crashed(status)),
+            AppEvent::TaskUnblocked { .. }
+            | AppEvent::ServerStatus(_)
+            | AppEvent::ServerLifecycle { .. } => None,
+        }
+    }
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static INTEGRATIONS: Mutex<Option<Vec<Integration>>> = Mutex::new(None);
+static SENT: Mutex<BTreeMap<String, VecDeque<Instant>>> = Mutex::new(BTreeMap::new());
+
+fn integrations_path() -> Result<PathBuf, String> {
+    config::config_dir()
+        .map(|dir| dir.join(INTEGRATIONS_FILE))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+fn load() -> Vec<Integration> {
+    integrations_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save(integrations: &[Integration]) -> Result<(), String> {
+    let path = integrations_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let contents = serde_json::to_string_pretty(integrations).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write integrations: {}", e))
+}
+
+/// Run `f` against the in-memory integrations, loading them on first use; saves if `f` returns true
+fn with_integrations<T>(f: impl FnOnce(&mut Vec<Integration>) -> (T, bool)) -> Result<T, String> {
+    let mut guard = INTEGRATIONS.lock().map_err(|e| e.to_string())?;
+    let integrations = guard.get_or_insert_with(load);
+    let (result, changed) = f(integrations);
+    if changed {
+        save(integrations)?;
+    }
+    Ok(result)
+}
+
+fn validate(integration: &Integration) -> Result<(), String> {
+    if integration.name.trim().is_empty() {
+        return Err("Integration name is required".to_string());
+    }
+    let url = &integration.webhook_url;
+    match integration.kind {
+        IntegrationKind::Slack if !url.starts_with("https://hooks.slack.com/") => {
+            Err("Expected a Slack incoming webhook URL (https://hooks.slack.com/...)".to_string())
+        }
+        IntegrationKind::Discord
+            if !url.starts_with("https://discord.com/api/webhooks/")
+                && !url.starts_with("https://discordapp.com/api/webhooks/") =>
+        {
+            Err("Expected a Discord webhook URL (https://discord.com/api/webhooks/...)".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn event_name(kind: EventKind) -> &'static str {
+    match kind {
+        EventKind::TaskCompleted => "Task completed",
+        EventKind::AgentBlocked => "Agent blocked",
+        EventKind::AgentFailed => "Agent failed",
+        EventKind::ServerCrashed => "Server crashed",
+    }
+}
+
+fn render(integration: &Integration, event: &Event) -> String {
+    let template = integration
+        .templates
+        .get(&event.kind)
+        .map(String::as_str)
+        .unwrap_or(integration.kind.default_template());
+    template
+        .replace("{{title}}", &event.title)
+        .replace("{{detail}}", &event.detail)
+        .replace("{{event}}", event_name(event.kind))
+        .trim()
+        .to_string()
+}
+
+fn payload(integration: &Integration, event: &Event) -> Value {
+    text_payload(integration.kind, &render(integration, event))
+}
+
+fn text_payload(kind: IntegrationKind, text: &str) -> Value {
+    match kind {
+        IntegrationKind::Slack => json!({ "text": text }),
+        IntegrationKind::Discord => {
+            let content: String = text.chars().take(DISCORD_MAX_CHARS).collect();
+            json!({ "content": content, "username": "Claude PM" })
+        }
+    }
+}
+
+/// Post with backoff, honouring `Retry-After` on 429s
+fn post(url: &str, body: &Value) -> Result<(), String> {
+    let mut delay = Duration::from_secs(1);
+    let mut last_error = String::new();
+    for attempt in 1..=ATTEMPTS {
+        match ureq::post(url).timeout(REQUEST_TIMEOUT).send_json(body) {
+            Ok(_) => return Ok(()),
+            Err(ureq::Error::Status(429, response)) => {
+                last_error = "rate limited".to_string();
+                if let Some(secs) = response
+                    .header("Retry-After")
+                    .and_then(|v| v.parse::<f64>().ok())
+                {
+                    delay = Duration::from_secs_f64(secs.clamp(0.0, 60.0));
+                }
+            }
+            // Other client errors (bad URL, deleted webhook) won't succeed on retry
+            Err(ureq::Error::Status(code, _)) if (400..500).contains(&code) => {
+                return Err(format!("Webhook rejected the message ({})", code));
+            }
+            Err(e) => last_error = e.to_string(),
+        }
+        if attempt < ATTEMPTS {
+            thread::sleep(delay);
+            delay *= 2;
+        }
+    }
+    Err(format!("Webhook request failed: {}", last_error))
+}
+
+/// Record a send, unless the integration is over its rate limit
+fn allow(id: &str) -> bool {
+    let Ok(mut sent) = SENT.lock() else {
+        return false;
+    };
+    let window = sent.entry(id.to_string()).or_default();
+    let now = Instant::now();
+    while window
+        .front()
+        .is_some_and(|t| now.duration_since(*t) > RATE_WINDOW)
+    {
+        window.pop_front();
+    }
+    if window.len() >= RATE_LIMIT {
+        return false;
+    }
+    window.push_back(now);
+    true
+}
+
+/// Post an outbox message body to an integration
+pub fn deliver(integration_id: &str, body: &Value) -> Result<(), String> {
+    let url = with_integrations(|integrations| {
+        let url = integrations
+            .iter()
+            .find(|i| i.id == integration_id)
+            .map(|i| i.webhook_url.clone());
+        (url, false)
+    })?
+    .ok_or_else(|| format!("Integration not found: {}", integration_id))?;
+    post(&url, body)
+}
+
+/// Post `text` as-is to an integration, by name or id (used by automation scripts)
+pub fn send_message(name: &str, text: &str) -> Result<(), String> {
+    let integration = with_integrations(|integrations| {
+        let found = integrations
+            .iter()
+            .find(|i| i.id == name || i.name.eq_ignore_ascii_case(name))
+            .cloned();
+        (found, false)
+    })?
+    .ok_or_else(|| format!("Integration not found: {}", name))?;
+    if !allow(&integration.id) {
+        return Err(format!("Integration {} is rate limited", integration.name));
+    }
+    post(
+        &integration.webhook_url,
+        &text_payload(integration.kind, text),
+    )
+}
+
+/// Post `event` to every enabled integration subscribed to it, to generic webhooks and,
+/// for critical events, by email
+fn dispatch(event: Event, seq: i64) {
+    webhooks::enqueue(&event);
+    email::alert(&event, seq);
+    let Some(app) = APP.get() else {
+        return;
+    };
+    scripting::run_for_event(app, &event);
+    plugins::run_for_event(&event);
+    let targets = with_integrations(|integrations| {
+        let targets: Vec<Integration> = integrations
+            .iter()
+            .filter(|i| i.enabled && i.events.contains(&event.kind))
+            .cloned()
+            .collect();
+        (targets, false)
+    })
+    .unwrap_or_default();
+
+    for integration in targets {
+        if !allow(&integration.id) {
+            eprintln!(
+                "[Claude PM] Integration {} is rate limited, dropping: {}",
+                integration.name, event.title
+            );
+            continue;
+        }
+        let effect = Effect::Integration {
+            integration_id: integration.id.clone(),
+            body: payload(&integration, &event),
+        };
+        // The bus sequence number makes a replayed event a no-op
+        let key = (seq > 0).then(|| format!("integration:{}:{}", integration.id, seq));
+        if let Err(e) = outbox::enqueue(effect, key) {
+            eprintln!(
+                "[Claude PM] Failed to queue {} message: {}",
+                integration.name, e
+            );
+        }
+    }
+}
+
+/// Keep the handle for dispatching, subscribe to the bus and watch for the server dying
+pub fn start(app: AppHandle) {
+    if APP.set(app).is_err() {
+        return;
+    }
+    event_bus::subscribe(
+        Filter::topics(&[
+            Topic::TaskCompleted,
+            Topic::AgentBlocked,
+            Topic::AgentFailed,
+            Topic::ServerCrashed,
+        ]),
+        |envelope| {
+            if let Some(event) = Event::from_app_event(&envelope.event) {
+                dispatch(event, envelope.seq);
+            }
+        },
+    );
+    thread::spawn(|| {
+        let mut reported = None;
+        loop {
+            if let Some((pid, status)) = crate::server_exit_status() {
+                if reported != Some(pid) {
+                    reported = Some(pid);
+                    lifecycle::mark_crashed();
+                    event_bus::publish(AppEvent::ServerCrashed { status });
+                }
+            }
+            thread::sleep(CRASH_POLL);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn list_integrations() -> Result<Vec<Integration>, Error> {
+    with_integrations(|integrations| (integrations.clone(), false)).map_err(Error::from)
+}
+
+/// Create an integration (empty `id`) or replace an existing one
+#[tauri::command]
+pub fn upsert_integration(mut integration: Integration) -> Result<Integration, Error> {
+    validate(&integration).map_err(Error::InvalidInput)?;
+    if integration.id.is_empty() {
+        integration.id = store::new_id();
+    }
+    with_integrations(|integrations| {
+        match integrations.iter_mut().find(|i| i.id == integration.id) {
+            Some(existing) => *existing = integration.clone(),
+            None => integrations.push(integration.clone()),
+        }
+        (integration, true)
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn delete_integration(id: String) -> Result<(), Error> {
+    with_integrations(|integrations| (integrations.retain(|i| i.id != id), true))
+        .map_err(Error::from)
+}
+
+/// Post a test message right away, bypassing the queue so failures are reported
+#[tauri::command]
+pub async fn send_test_webhook(integration_id: String) -> Result<(), Error> {
+    let integration = with_integrations(|integrations| {
+        (
+            integrations
+                .iter()
+                .find(|i| i.id == integration_id)
+                .cloned(),
+            false,
+        )
+    })?
+    .ok_or_else(|| Error::NotFound(format!("Integration not found: {}", integration_id)))?;
+    let event = Event {
+        kind: EventKind::TaskCompleted,
+        title: "Test message from Claude PM".to_string(),
+        detail: format!("The {} integration is working.", integration.name),
+        data: Value::Null,
+    };
+    let body = payload(&integration, &event);
+    tauri::async_runtime::spawn_blocking(move || post(&integration.webhook_url, &body))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(Error::from)
+}
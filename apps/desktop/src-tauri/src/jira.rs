@@ -0,0 +1,779 @@
+//! Jira Cloud connector: assigned issues become tasks, task states become transitions
+//!
+//! Sign-in is Atlassian's OAuth 2.0 (3LO) flow: `start_jira_sign_in` returns the consent
+//! URL for the UI to open, and a one-shot listener on `127.0.0.1:4856` takes the
+//! redirect, trades the code for tokens and stores them, with the Jira site, in the
+//! keychain. The app's client id and secret come from the build or the environment
+//! (`CLAUDE_PM_JIRA_CLIENT_ID`, `CLAUDE_PM_JIRA_CLIENT_SECRET`). Access tokens are
+//! refreshed as they expire; Atlassian rotates the refresh token each time.
+//!
+//! A sync (every 15 minutes while `jira.enabled`, or on demand) first pushes tasks whose
+//! state changed here since the last sync, by taking the issue's transition into the
+//! mapped status (`status_map`, or else the matching status category), then pulls the
+//! issues matched by `jql` (unresolved issues assigned to the user by default). New
+//! issues become tasks in the project mapped to their Jira project. Titles and
+//! descriptions follow Jira; `field_map` pulls custom fields into either, or lists them
+//! under the description as details.
+//!
+//! Events:
+//! - `jira-auth` with the sign-in progress (`pending`, `connected` or `error`)
+//! - `jira-synced` with the [`JiraSyncReport`] of a scheduled sync that changed something
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tiny_http::{Header, Response, Server};
+
+use crate::error::Error;
+use crate::store::{self, NewTask, TaskUpdate};
+use crate::{auth, config, connectivity, power, secrets};
+
+pub const CALLBACK_PORT: u16 = 4856;
+const AUTHORIZE_URL: &str = "https://auth.atlassian.com/authorize";
+const TOKEN_URL: &str = "https://auth.atlassian.com/oauth/token";
+const RESOURCES_URL: &str = "https://api.atlassian.com/oauth/token/accessible-resources";
+const API_URL: &str = "https://api.atlassian.com/ex/jira";
+const SCOPES: &str = "read:jira-work write:jira-work read:jira-user offline_access";
+const KEYCHAIN_ACCOUNT: &str = "jira-oauth";
+const DEFAULT_JQL: &str =
+    "assignee = currentUser() AND statusCategory != Done ORDER BY updated DESC";
+const SYNC_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// How long the redirect listener waits for the user to finish consenting
+const SIGN_IN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const PAGE_SIZE: u64 = 100;
+/// Tokens count as expired this long before they actually do
+const EXPIRY_MARGIN_MS: i64 = 60_000;
+
+static TOKENS: Mutex<Option<Tokens>> = Mutex::new(None);
+static STARTED: AtomicBool = AtomicBool::new(false);
+static SYNCING: AtomicBool = AtomicBool::new(false);
+static SIGNING_IN: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FieldTarget {
+    Title,
+    Description,
+    /// A `label: value` line under the description
+    Detail,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldMapping {
+    /// Jira field id, e.g. `customfield_10016`
+    pub field: String,
+    pub target: FieldTarget,
+    /// Shown for details; the field id when unset
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct JiraSettings {
+    /// Sync on a schedule
+    pub enabled: bool,
+    /// Issues to pull (unresolved issues assigned to the user when unset)
+    pub jql: Option<String>,
+    /// Jira project key to ClaudePM project id
+    pub project_map: BTreeMap<String, String>,
+    /// Project for issues whose Jira project isn't mapped; they're skipped when unset
+    pub default_project_id: Option<String>,
+    /// Task state to Jira status name; unmapped states go by status category
+    pub status_map: BTreeMap<String, String>,
+    pub field_map: Vec<FieldMapping>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Tokens {
+    access_token: String,
+    refresh_token: String,
+    expires_at: i64,
+    cloud_id: String,
+    site_url: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: i64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthProgress {
+    status: &'static str,
+    message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraStatus {
+    /// Client id and secret are available
+    pub configured: bool,
+    pub connected: bool,
+    pub site_url: Option<String>,
+    pub linked_issues: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraField {
+    pub id: String,
+    pub name: String,
+    pub custom: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraSyncReport {
+    /// Issue keys that became tasks
+    pub imported: Vec<String>,
+    /// Issue keys whose task was updated from Jira
+    pub updated: Vec<String>,
+    /// Issue keys transitioned to follow their task
+    pub transitioned: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl JiraSyncReport {
+    fn is_empty(&self) -> bool {
+        self.imported.is_empty()
+            && self.updated.is_empty()
+            && self.transitioned.is_empty()
+            && self.errors.is_empty()
+    }
+}
+
+/// A task linked to an issue, as of the last sync
+struct Link {
+    task_id: String,
+    issue_key: String,
+    status: String,
+    task_state: String,
+}
+
+fn credentials() -> Result<(String, String), String> {
+    let var = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+    let id = option_env!("CLAUDE_PM_JIRA_CLIENT_ID")
+        .map(str::to_string)
+        .or_else(|| var("CLAUDE_PM_JIRA_CLIENT_ID"));
+    let secret = option_env!("CLAUDE_PM_JIRA_CLIENT_SECRET")
+        .map(str::to_string)
+        .or_else(|| var("CLAUDE_PM_JIRA_CLIENT_SECRET"));
+    id.zip(secret).ok_or_else(|| {
+        "Jira sign-in isn't configured (CLAUDE_PM_JIRA_CLIENT_ID and CLAUDE_PM_JIRA_CLIENT_SECRET)"
+            .to_string()
+    })
+}
+
+fn redirect_uri() -> String {
+    format!("http://localhost:{}/callback", CALLBACK_PORT)
+}
+
+fn save_tokens(tokens: &Tokens) -> Result<(), String> {
+    let json = serde_json::to_string(tokens).map_err(|e| e.to_string())?;
+    secrets::set(KEYCHAIN_ACCOUNT, &json)?;
+    if let Ok(mut cached) = TOKENS.lock() {
+        *cached = Some(tokens.clone());
+    }
+    Ok(())
+}
+
+fn load_tokens() -> Result<Option<Tokens>, String> {
+    if let Some(tokens) = TOKENS.lock().ok().and_then(|t| t.clone()) {
+        return Ok(Some(tokens));
+    }
+    let Some(json) = secrets::get(KEYCHAIN_ACCOUNT)? else {
+        return Ok(None);
+    };
+    let tokens: Tokens = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    if let Ok(mut cached) = TOKENS.lock() {
+        *cached = Some(tokens.clone());
+    }
+    Ok(Some(tokens))
+}
+
+fn request_tokens(form: Value) -> Result<TokenResponse, String> {
+    ureq::post(TOKEN_URL)
+        .timeout(REQUEST_TIMEOUT)
+        .send_json(form)
+        .map_err(|e| format!("Jira sign-in failed: {}", e))?
+        .into_json()
+        .map_err(|e| e.to_string())
+}
+
+/// A current access token, refreshing it first if it's about to expire
+fn access() -> Result<Tokens, String> {
+    let mut tokens = load_tokens()?.ok_or("Jira is not connected")?;
+    if tokens.expires_at - EXPIRY_MARGIN_MS > store::now_ms() {
+        return Ok(tokens);
+    }
+    let (client_id, client_secret) = credentials()?;
+    let response = request_tokens(json!({
+        "grant_type": "refresh_token",
+        "client_id": client_id,
+        "client_secret": client_secret,
+        "refresh_token": tokens.refresh_token,
+    }))?;
+    tokens.access_token = response.access_token;
+    if let Some(refresh) = response.refresh_token {
+        tokens.refresh_token = refresh;
+    }
+    tokens.expires_at = store::now_ms() + response.expires_in * 1000;
+    save_tokens(&tokens)?;
+    Ok(tokens)
+}
+
+/// Call the Jira REST API v3 at `path` (e.g. `/issue/KEY`)
+fn api(method: &str, path: &str, body: Option<Value>) -> Result<Value, String> {
+    let tokens = access()?;
+    let url = format!("{}/{}/rest/api/3{}", API_URL, tokens.cloud_id, path);
+    let request = ureq::request(method, &url)
+        .timeout(REQUEST_TIMEOUT)
+        .set("Authorization", &format!("Bearer {}", tokens.access_token))
+        .set("Accept", "application/json");
+    let response = match body {
+        Some(body) => request.send_json(body),
+        None => request.call(),
+    };
+    match response {
+        // Transitions answer 204 with no body
+        Ok(response) if response.status() == 204 => Ok(Value::Null),
+        Ok(response) => response.into_json().map_err(|e| e.to_string()),
+        Err(ureq::Error::Status(status, response)) => Err(format!(
+            "Jira request failed ({}): {}",
+            status,
+            response.into_string().unwrap_or_default()
+        )),
+        Err(e) => Err(format!("Jira request failed: {}", e)),
+    }
+}
+
+fn emit_auth(app: &AppHandle, status: &'static str, message: Option<String>) {
+    let _ = app.emit("jira-auth", AuthProgress { status, message });
+}
+
+/// Trade the authorization code for tokens and find the user's Jira site
+fn finish_sign_in(code: &str) -> Result<Tokens, String> {
+    let (client_id, client_secret) = credentials()?;
+    let response = request_tokens(json!({
+        "grant_type": "authorization_code",
+        "client_id": client_id,
+        "client_secret": client_secret,
+        "code": code,
+        "redirect_uri": redirect_uri(),
+    }))?;
+    let sites: Vec<Value> = ureq::get(RESOURCES_URL)
+        .timeout(REQUEST_TIMEOUT)
+        .set(
+            "Authorization",
+            &format!("Bearer {}", response.access_token),
+        )
+        .call()
+        .map_err(|e| format!("Failed to list Jira sites: {}", e))?
+        .into_json()
+        .map_err(|e| e.to_string())?;
+    let site = sites
+        .first()
+        .ok_or("The account has no Jira sites this app was granted")?;
+    let tokens = Tokens {
+        access_token: response.access_token,
+        refresh_token: response
+            .refresh_token
+            .ok_or("Jira didn't grant offline access")?,
+        expires_at: store::now_ms() + response.expires_in * 1000,
+        cloud_id: site["id"].as_str().unwrap_or_default().to_string(),
+        site_url: site["url"].as_str().unwrap_or_default().to_string(),
+    };
+    save_tokens(&tokens)?;
+    Ok(tokens)
+}
+
+fn query_param(url: &str, name: &str) -> Option<String> {
+    let url = tauri::Url::parse(&format!("http://localhost{}", url)).ok()?;
+    url.query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.to_string())
+}
+
+/// Wait for Atlassian's redirect back to us, then finish signing in
+fn await_redirect(app: AppHandle, server: Server, state: String) {
+    let page = |text: &str| {
+        Response::from_string(format!(
+            "<html><body style=\"font-family: sans-serif\"><p>{}</p></body></html>",
+            text
+        ))
+        .with_header(Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap())
+    };
+    let deadline = std::time::Instant::now() + SIGN_IN_TIMEOUT;
+    let result = loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        let request = match server.recv_timeout(remaining) {
+            Ok(Some(request)) => request,
+            Ok(None) => break Err("Jira sign-in timed out".to_string()),
+            Err(e) => break Err(e.to_string()),
+        };
+        if !request.url().starts_with("/callback") {
+            let _ = request.respond(Response::empty(404));
+            continue;
+        }
+        if query_param(request.url(), "state").as_deref() != Some(state.as_str()) {
+            let _ = request.respond(page(
+                "This sign-in link has expired. Try again from Claude PM.",
+            ));
+            continue;
+        }
+        let result = match query_param(request.url(), "code") {
+            Some(code) => finish_sign_in(&code),
+            None => Err(query_param(request.url(), "error_description")
+                .or_else(|| query_param(request.url(), "error"))
+                .unwrap_or_else(|| "Jira sign-in was cancelled".to_string())),
+        };
+        let _ = request.respond(page(match &result {
+            Ok(_) => "Connected to Jira. You can close this tab.",
+            Err(_) => "Jira sign-in failed. Check Claude PM for details.",
+        }));
+        break result;
+    };
+    match result {
+        Ok(tokens) => {
+            println!("[Claude PM] Connected to Jira at {}", tokens.site_url);
+            emit_auth(&app, "connected", Some(tokens.site_url));
+        }
+        Err(e) => emit_auth(&app, "error", Some(e)),
+    }
+    SIGNING_IN.store(false, Ordering::SeqCst);
+}
+
+/// Plain text of an Atlassian Document Format node
+fn adf_text(node: &Value) -> String {
+    let mut out = String::new();
+    fn walk(node: &Value, out: &mut String) {
+        if let Some(text) = node["text"].as_str() {
+            out.push_str(text);
+        }
+        if node["type"] == "hardBreak" {
+            out.push('\n');
+        }
+        for child in node["content"].as_array().into_iter().flatten() {
+            walk(child, out);
+        }
+        if matches!(
+            node["type"].as_str(),
+            Some("paragraph" | "heading" | "listItem" | "codeBlock" | "blockquote")
+        ) {
+            out.push('\n');
+        }
+    }
+    walk(node, &mut out);
+    out.trim().to_string()
+}
+
+/// A field value as text: options and users by name, lists comma-separated
+fn field_text(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::Null => return None,
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Array(items) => items
+            .iter()
+            .filter_map(field_text)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Value::Object(_) if value["type"] == "doc" => adf_text(value),
+        Value::Object(_) => ["value", "name", "displayName", "key"]
+            .iter()
+            .find_map(|key| value[*key].as_str().map(str::to_string))?,
+    };
+    Some(text).filter(|t| !t.trim().is_empty())
+}
+
+/// Title and description for an issue's task
+fn task_fields(issue: &Value, settings: &JiraSettings, site_url: &str) -> (String, String) {
+    let fields = &issue["fields"];
+    let key = issue["key"].as_str().unwrap_or_default();
+    let mapped = |target: FieldTarget| {
+        settings
+            .field_map
+            .iter()
+            .filter(move |m| m.target == target)
+            .filter_map(|m| field_text(&fields[&m.field]).map(|text| (m, text)))
+    };
+    let summary = fields["summary"].as_str().unwrap_or_default();
+    let title = mapped(FieldTarget::Title)
+        .map(|(_, text)| text)
+        .next()
+        .unwrap_or_else(|| summary.to_string());
+    let mut description = mapped(FieldTarget::Description)
+        .map(|(_, text)| text)
+        .next()
+        .or_else(|| field_text(&fields["description"]))
+        .unwrap_or_default();
+    let details: Vec<String> = mapped(FieldTarget::Detail)
+        .map(|(m, text)| format!("{}: {}", m.label.as_deref().unwrap_or(&m.field), text))
+        .collect();
+    if !details.is_empty() {
+        description.push_str("\n\n");
+        description.push_str(&details.join("\n"));
+    }
+    description.push_str(&format!("\n\nJira: {}/browse/{}", site_url, key));
+    (
+        format!("[{}] {}", key, title),
+        description.trim().to_string(),
+    )
+}
+
+/// The task state for a Jira status: mapped by name, else by status category
+fn state_for(status: &Value, settings: &JiraSettings) -> String {
+    let name = status["name"].as_str().unwrap_or_default();
+    if let Some((state, _)) = settings
+        .status_map
+        .iter()
+        .find(|(_, mapped)| mapped.eq_ignore_ascii_case(name))
+    {
+        return state.clone();
+    }
+    match status["statusCategory"]["key"].as_str() {
+        Some("done") => "done",
+        Some("indeterminate") => "in_progress",
+        _ => "backlog",
+    }
+    .to_string()
+}
+
+fn category_for(state: &str) -> &'static str {
+    match state {
+        "done" => "done",
+        "in_progress" | "review" => "indeterminate",
+        _ => "new",
+    }
+}
+
+/// Move issue `key` into the status task state `state` maps to; returns the new status
+fn transition(key: &str, state: &str, settings: &JiraSettings) -> Result<String, String> {
+    let transitions = api("GET", &format!("/issue/{}/transitions", key), None)?;
+    let target = settings.status_map.get(state);
+    let found = transitions["transitions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|t| match target {
+            Some(name) => t["to"]["name"]
+                .as_str()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name)),
+            None => t["to"]["statusCategory"]["key"] == category_for(state),
+        })
+        .ok_or_else(|| {
+            format!(
+                "{} has no transition to {}",
+                key,
+                target.map(String::as_str).unwrap_or(state)
+            )
+        })?;
+    api(
+        "POST",
+        &format!("/issue/{}/transitions", key),
+        Some(json!({ "transition": { "id": found["id"] } })),
+    )?;
+    Ok(found["to"]["name"].as_str().unwrap_or_default().to_string())
+}
+
+fn links() -> Result<Vec<Link>, String> {
+    store::with_conn(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT task_id, issue_key, status, task_state FROM jira_issues")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Link {
+                task_id: row.get(0)?,
+                issue_key: row.get(1)?,
+                status: row.get(2)?,
+                task_state: row.get(3)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+fn save_link(task_id: &str, key: &str, id: &str, status: &str, state: &str) -> Result<(), String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO jira_issues (task_id, issue_key, issue_id, status, task_state, synced_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![task_id, key, id, status, state, store::now_ms()],
+        )
+        .map(|_| ())
+    })
+}
+
+/// Issues matching the JQL, with the fields tasks are built from
+fn search(settings: &JiraSettings) -> Result<Vec<Value>, String> {
+    let mut fields = vec![
+        "summary".to_string(),
+        "description".to_string(),
+        "status".to_string(),
+        "project".to_string(),
+    ];
+    fields.extend(settings.field_map.iter().map(|m| m.field.clone()));
+    let jql = settings.jql.as_deref().unwrap_or(DEFAULT_JQL);
+    let mut issues = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let page = api(
+            "POST",
+            "/search/jql",
+            Some(json!({
+                "jql": jql,
+                "fields": fields,
+                "maxResults": PAGE_SIZE,
+                "nextPageToken": page_token,
+            })),
+        )?;
+        issues.extend(page["issues"].as_array().cloned().unwrap_or_default());
+        match page["nextPageToken"].as_str() {
+            Some(token) => page_token = Some(token.to_string()),
+            None => return Ok(issues),
+        }
+    }
+}
+
+fn sync() -> Result<JiraSyncReport, String> {
+    let settings = config::load().jira;
+    let tokens = access()?;
+    let mut report = JiraSyncReport::default();
+
+    // Push first, so a state changed here isn't overwritten by the issue's old status
+    let mut statuses: BTreeMap<String, String> = BTreeMap::new();
+    for link in links()? {
+        let Some(task) = store::with_conn(|conn| store::get_task(conn, &link.task_id))? else {
+            continue;
+        };
+        statuses.insert(link.issue_key.clone(), link.status.clone());
+        if task.state == link.task_state {
+            continue;
+        }
+        match transition(&link.issue_key, &task.state, &settings) {
+            Ok(status) => {
+                store::with_conn(|conn| {
+                    conn.execute(
+                        "UPDATE jira_issues SET status = ?2, task_state = ?3, synced_at = ?4 WHERE task_id = ?1",
+                        params![link.task_id, status, task.state, store::now_ms()],
+                    )
+                })?;
+                statuses.insert(link.issue_key.clone(), status);
+                report.transitioned.push(link.issue_key);
+            }
+            Err(e) => report.errors.push(e),
+        }
+    }
+
+    let linked: BTreeMap<String, String> = links()?
+        .into_iter()
+        .map(|link| (link.issue_key, link.task_id))
+        .collect();
+    for issue in search(&settings)? {
+        let key = issue["key"].as_str().unwrap_or_default().to_string();
+        let id = issue["id"].as_str().unwrap_or_default();
+        let status = &issue["fields"]["status"];
+        let status_name = status["name"].as_str().unwrap_or_default();
+        let (title, description) = task_fields(&issue, &settings, &tokens.site_url);
+        let result = match linked.get(&key) {
+            Some(task_id) => {
+                let Some(task) = store::with_conn(|conn| store::get_task(conn, task_id))? else {
+                    continue;
+                };
+                // The status only moves the task when it changed in Jira
+                let state = if statuses.get(&key).map(String::as_str) != Some(status_name) {
+                    state_for(status, &settings)
+                } else {
+                    task.state.clone()
+                };
+                let changed = task.title != title
+                    || task.description.as_deref() != Some(description.as_str())
+                    || task.state != state;
+                if changed {
+                    store::update_task(
+                        task.id.clone(),
+                        TaskUpdate {
+                            title: Some(title),
+                            description: Some(description),
+                            state: Some(state.clone()),
+                        },
+                    )
+                    .map_err(String::from)
+                    .and_then(|_| save_link(&task.id, &key, id, status_name, &state))
+                    .map(|_| report.updated.push(key.clone()))
+                } else {
+                    save_link(&task.id, &key, id, status_name, &state)
+                }
+            }
+            None => {
+                let jira_project = issue["fields"]["project"]["key"]
+                    .as_str()
+                    .unwrap_or_default();
+                let Some(project_id) = settings
+                    .project_map
+                    .get(jira_project)
+                    .or(settings.default_project_id.as_ref())
+                else {
+                    continue;
+                };
+                let state = state_for(status, &settings);
+                store::create_task(NewTask {
+                    project_id: project_id.clone(),
+                    title,
+                    description: Some(description),
+                    state: Some(state.clone()),
+                })
+                .map_err(String::from)
+                .and_then(|task| save_link(&task.id, &key, id, status_name, &state))
+                .map(|_| report.imported.push(key.clone()))
+            }
+        };
+        if let Err(e) = result {
+            report.errors.push(format!("{}: {}", key, e));
+        }
+    }
+    Ok(report)
+}
+
+fn run_sync() -> Result<JiraSyncReport, String> {
+    if SYNCING.swap(true, Ordering::SeqCst) {
+        return Err("A Jira sync is already running".to_string());
+    }
+    let result = sync();
+    SYNCING.store(false, Ordering::SeqCst);
+    result
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        power::wait(SYNC_INTERVAL);
+        let due = config::load().jira.enabled
+            && connectivity::is_online()
+            && load_tokens().ok().flatten().is_some();
+        if !due {
+            continue;
+        }
+        match run_sync() {
+            Ok(report) if !report.is_empty() => {
+                let _ = app.emit("jira-synced", &report);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("[Claude PM] Jira sync failed: {}", e),
+        }
+    });
+}
+
+/// Start signing in; returns the Atlassian consent URL for the UI to open
+#[tauri::command]
+pub fn start_jira_sign_in(app: AppHandle) -> Result<String, Error> {
+    let (client_id, _) = credentials()?;
+    if SIGNING_IN.swap(true, Ordering::SeqCst) {
+        return Err(Error::InvalidInput(
+            "Jira sign-in is already in progress".to_string(),
+        ));
+    }
+    let server = match Server::http(("127.0.0.1", CALLBACK_PORT)) {
+        Ok(server) => server,
+        Err(e) => {
+            SIGNING_IN.store(false, Ordering::SeqCst);
+            return Err(Error::Internal(format!(
+                "Could not listen on port {} for the Jira redirect: {}",
+                CALLBACK_PORT, e
+            )));
+        }
+    };
+    let state = auth::random_hex();
+    let mut url = tauri::Url::parse(AUTHORIZE_URL).map_err(|e| e.to_string())?;
+    url.query_pairs_mut()
+        .append_pair("audience", "api.atlassian.com")
+        .append_pair("client_id", &client_id)
+        .append_pair("scope", SCOPES)
+        .append_pair("redirect_uri", &redirect_uri())
+        .append_pair("state", &state)
+        .append_pair("response_type", "code")
+        .append_pair("prompt", "consent");
+    emit_auth(&app, "pending", None);
+    thread::spawn(move || await_redirect(app, server, state));
+    Ok(url.to_string())
+}
+
+/// Forget the tokens; linked tasks stay, but stop syncing
+#[tauri::command]
+pub fn disconnect_jira() -> Result<(), Error> {
+    secrets::delete(KEYCHAIN_ACCOUNT)?;
+    if let Ok(mut cached) = TOKENS.lock() {
+        *cached = None;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_jira_status() -> Result<JiraStatus, Error> {
+    let tokens = load_tokens()?;
+    let linked_issues: i64 = store::with_conn(|conn| {
+        conn.query_row("SELECT COUNT(*) FROM jira_issues", [], |row| row.get(0))
+            .optional()
+            .map(Option::unwrap_or_default)
+    })?;
+    Ok(JiraStatus {
+        configured: credentials().is_ok(),
+        connected: tokens.is_some(),
+        site_url: tokens.map(|t| t.site_url),
+        linked_issues: linked_issues as usize,
+    })
+}
+
+/// Fields on the Jira site, for building the field mapping
+#[tauri::command]
+pub async fn list_jira_fields() -> Result<Vec<JiraField>, Error> {
+    let fields = tauri::async_runtime::spawn_blocking(|| api("GET", "/field", None))
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok(fields
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|field| JiraField {
+            id: field["id"].as_str().unwrap_or_default().to_string(),
+            name: field["name"].as_str().unwrap_or_default().to_string(),
+            custom: field["custom"].as_bool().unwrap_or(false),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub fn set_jira_settings(settings: JiraSettings) -> Result<(), Error> {
+    for state in settings.status_map.keys() {
+        store::validate_state(state)?;
+    }
+    if settings.field_map.iter().any(|m| m.field.trim().is_empty()) {
+        return Err(Error::InvalidInput(
+            "Each field mapping needs a Jira field id".to_string(),
+        ));
+    }
+    config::update(|c| c.jira = settings)?;
+    Ok(())
+}
+
+/// Push task states and pull assigned issues now
+#[tauri::command]
+pub async fn sync_jira() -> Result<JiraSyncReport, Error> {
+    Ok(tauri::async_runtime::spawn_blocking(run_sync)
+        .await
+        .map_err(|e| e.to_string())??)
+}
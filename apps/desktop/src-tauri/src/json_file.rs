@@ -0,0 +1,85 @@
+//! Reading and rewriting user-owned JSON files (Claude's config and settings) safely
+//!
+//! Writes go through a temp file and rename, and the previous contents are copied into
+//! `<config_dir>/backups/<kind>/` first so any change can be rolled back.
+
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config;
+
+/// Under the config directory
+pub const BACKUPS_DIR: &str = "backups";
+/// Backups kept per kind; older ones are pruned
+const MAX_BACKUPS: usize = 20;
+
+/// Parse a JSON file, treating a missing file as an empty object
+pub fn read(path: &Path) -> Result<Value, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) if contents.trim().is_empty() => Ok(Value::Object(Default::default())),
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("{} is not valid JSON: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Value::Object(Default::default())),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+pub fn backup_dir(kind: &str) -> Result<PathBuf, String> {
+    config::config_dir()
+        .map(|dir| dir.join(BACKUPS_DIR).join(kind))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+/// Copy the current file into the backup directory, returning the backup's path
+fn backup(path: &Path, kind: &str) -> Result<Option<PathBuf>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let dir = backup_dir(kind)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let target = dir.join(format!("{}.json", millis));
+    fs::copy(path, &target).map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+
+    let mut backups = list_backups(kind)?;
+    while backups.len() > MAX_BACKUPS {
+        let _ = fs::remove_file(backups.remove(0));
+    }
+    Ok(Some(target))
+}
+
+/// Backups for `kind`, oldest first
+pub fn list_backups(kind: &str) -> Result<Vec<PathBuf>, String> {
+    let dir = backup_dir(kind)?;
+    let mut backups: Vec<PathBuf> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    backups.sort();
+    Ok(backups)
+}
+
+/// Back up the existing file, then atomically replace it with `value`
+pub fn write(path: &Path, kind: &str, value: &Value) -> Result<(), String> {
+    backup(path, kind)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    let contents = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, contents + "\n")
+        .map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
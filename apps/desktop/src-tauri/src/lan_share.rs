@@ -0,0 +1,585 @@
+//! Finding other Claude PM instances on the LAN and sharing a board with them read-only
+//!
+//! While discovery is on, this instance advertises `_claudepm._tcp.local` over mDNS
+//! (through `mdns-sd`) with its display name and browses for the others. Sharing a
+//! board starts an HTTP endpoint on port 4854 on all interfaces that answers
+//! `GET /board` with that project's tasks grouped by state. The share's token is handed
+//! over out of band (read aloud, pasted in chat) and never goes over the network: the
+//! request names the share by a hash of the token plus a fresh nonce, and the board
+//! comes back sealed under a key derived from the token with the nonce as associated
+//! data. mDNS answers are unauthenticated, so a peer that opens the reply is taken as
+//! proof it's the one that shared the board, wherever its address points; anyone else
+//! only ever sees ciphertext. The endpoint stops once the last share is removed, and
+//! shares don't survive a restart.
+//!
+//! Events:
+//! - `lan-peers-changed` with the current [`LanPeer`] list
+
+use chacha20poly1305::{ChaCha20Poly1305, Key, KeyInit};
+use mdns_sd::{RecvTimeoutError, ResolvedService, ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::net::{IpAddr, SocketAddr};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::error::Error;
+use crate::store::{self, TASK_STATES};
+use crate::{app_lock, auth, config, process, secrets};
+
+pub const SHARE_PORT: u16 = 4854;
+const SERVICE: &str = "_claudepm._tcp.local.";
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// Carries the requester's nonce, which the sealed board is bound to
+const NONCE_HEADER: &str = "X-Share-Nonce";
+const MAX_BOARD_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LanShareSettings {
+    /// Advertise this instance and look for others
+    pub discoverable: bool,
+    /// Shown to peers; the host name when unset
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanPeer {
+    pub id: String,
+    pub name: String,
+    pub address: String,
+    pub port: u16,
+    /// Boards the peer is currently sharing
+    pub shared_boards: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardShare {
+    pub token: String,
+    pub project_id: String,
+    pub project_name: String,
+    pub started_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardTask {
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BoardColumn {
+    pub state: String,
+    pub tasks: Vec<BoardTask>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedBoard {
+    pub project_name: String,
+    pub shared_by: String,
+    pub generated_at: i64,
+    pub columns: Vec<BoardColumn>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanStatus {
+    pub settings: LanShareSettings,
+    pub display_name: String,
+    pub peers: Vec<LanPeer>,
+    pub shares: Vec<BoardShare>,
+}
+
+static INSTANCE_ID: OnceLock<String> = OnceLock::new();
+static PEERS: Mutex<BTreeMap<String, LanPeer>> = Mutex::new(BTreeMap::new());
+static SHARES: Mutex<BTreeMap<String, BoardShare>> = Mutex::new(BTreeMap::new());
+static SERVER: Mutex<Option<Arc<Server>>> = Mutex::new(None);
+static DISCOVERING: AtomicBool = AtomicBool::new(false);
+/// Set when the display name or share count changed and the TXT record is stale
+static REANNOUNCE: AtomicBool = AtomicBool::new(false);
+
+/// Identifies this run to peers, so a restarted instance shows up once
+fn instance_id() -> &'static str {
+    INSTANCE_ID.get_or_init(|| store::new_id()[..12].to_string())
+}
+
+fn display_name() -> String {
+    config::load()
+        .lan_share
+        .display_name
+        .filter(|name| !name.trim().is_empty())
+        .or_else(|| {
+            process::output(&mut Command::new("hostname"))
+                .ok()
+                .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+                .filter(|name| !name.is_empty())
+        })
+        .unwrap_or_else(|| "Claude PM".to_string())
+}
+
+fn peers() -> Vec<LanPeer> {
+    PEERS
+        .lock()
+        .map(|peers| peers.values().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn shares() -> Vec<BoardShare> {
+    SHARES
+        .lock()
+        .map(|shares| shares.values().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn status() -> LanStatus {
+    LanStatus {
+        settings: config::load().lan_share,
+        display_name: display_name(),
+        peers: peers(),
+        shares: shares(),
+    }
+}
+
+/// What the share lookup sees instead of the token
+fn share_id(token: &str) -> String {
+    secrets::hex(&Sha256::digest(format!(
+        "claudepm-lan-share-id:{}",
+        token.trim()
+    )))[..32]
+        .to_string()
+}
+
+fn share_cipher(token: &str) -> ChaCha20Poly1305 {
+    let key = Sha256::digest(format!("claudepm-lan-share-key:{}", token.trim()));
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+fn service_info() -> Result<ServiceInfo, mdns_sd::Error> {
+    let id = instance_id();
+    let name = display_name();
+    let boards = shares().len().to_string();
+    let txt = [
+        ("v", "1"),
+        ("name", name.as_str()),
+        ("boards", boards.as_str()),
+    ];
+    Ok(ServiceInfo::new(
+        SERVICE,
+        id,
+        &format!("{}.local.", id),
+        (),
+        SHARE_PORT,
+        &txt[..],
+    )?
+    .enable_addr_auto())
+}
+
+/// The peer a resolved service describes; `None` for this instance or another type
+fn peer(service: &ResolvedService) -> Option<LanPeer> {
+    let id = service
+        .fullname
+        .strip_suffix(SERVICE)?
+        .strip_suffix('.')
+        .filter(|id| *id != instance_id())?;
+    // Prefer IPv4, then the lowest address, so the pick doesn't flip between answers
+    let address = service
+        .addresses
+        .iter()
+        .map(|ip| ip.to_ip_addr())
+        .min_by_key(|ip| (!ip.is_ipv4(), *ip))?;
+    Some(LanPeer {
+        id: id.to_string(),
+        name: service
+            .get_property_val_str("name")
+            .filter(|name| !name.is_empty())
+            .unwrap_or(id)
+            .to_string(),
+        address: address.to_string(),
+        port: service.port,
+        shared_boards: service
+            .get_property_val_str("boards")
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(0),
+    })
+}
+
+/// Fold a discovery event into the peer list; returns whether it changed
+fn observe(event: ServiceEvent) -> bool {
+    let Ok(mut peers) = PEERS.lock() else {
+        return false;
+    };
+    match event {
+        ServiceEvent::ServiceResolved(service) => match peer(&service) {
+            Some(peer) if peers.get(&peer.id) != Some(&peer) => {
+                peers.insert(peer.id.clone(), peer);
+                true
+            }
+            _ => false,
+        },
+        ServiceEvent::ServiceRemoved(_, fullname) => fullname
+            .strip_suffix(SERVICE)
+            .and_then(|id| id.strip_suffix('.'))
+            .is_some_and(|id| peers.remove(id).is_some()),
+        _ => false,
+    }
+}
+
+fn announce(daemon: &ServiceDaemon) {
+    match service_info() {
+        Ok(info) => {
+            if let Err(e) = daemon.register(info) {
+                eprintln!("[Claude PM] Failed to advertise on the LAN: {}", e);
+            }
+        }
+        Err(e) => eprintln!("[Claude PM] Failed to build the LAN advertisement: {}", e),
+    }
+}
+
+fn discover(app: AppHandle) {
+    let started = ServiceDaemon::new().and_then(|daemon| {
+        let events = daemon.browse(SERVICE)?;
+        Ok((daemon, events))
+    });
+    let (daemon, events) = match started {
+        Ok(started) => started,
+        Err(e) => {
+            eprintln!("[Claude PM] LAN discovery unavailable: {}", e);
+            DISCOVERING.store(false, Ordering::SeqCst);
+            return;
+        }
+    };
+    println!("[Claude PM] LAN discovery started");
+    announce(&daemon);
+    while DISCOVERING.load(Ordering::SeqCst) {
+        if REANNOUNCE.swap(false, Ordering::SeqCst) {
+            announce(&daemon);
+        }
+        match events.recv_timeout(Duration::from_secs(1)) {
+            Ok(event) => {
+                if observe(event) {
+                    let _ = app.emit("lan-peers-changed", peers());
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                eprintln!("[Claude PM] LAN discovery stopped unexpectedly");
+                break;
+            }
+        }
+    }
+    // Say goodbye so peers drop us now rather than when the records expire
+    if let Ok(done) = daemon.unregister(&format!("{}.{}", instance_id(), SERVICE)) {
+        let _ = done.recv_timeout(Duration::from_secs(1));
+    }
+    let _ = daemon.shutdown();
+    if let Ok(mut peers) = PEERS.lock() {
+        peers.clear();
+    }
+    let _ = app.emit("lan-peers-changed", Vec::<LanPeer>::new());
+    DISCOVERING.store(false, Ordering::SeqCst);
+    println!("[Claude PM] LAN discovery stopped");
+}
+
+fn board(project_id: &str) -> Result<SharedBoard, Error> {
+    let project = store::list_projects()?
+        .into_iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project_id)))?;
+    let tasks = store::list_tasks(Some(project_id.to_string()), None)?;
+    Ok(SharedBoard {
+        project_name: project.name,
+        shared_by: display_name(),
+        generated_at: store::now_ms(),
+        columns: TASK_STATES
+            .iter()
+            .map(|state| BoardColumn {
+                state: state.to_string(),
+                tasks: tasks
+                    .iter()
+                    .filter(|task| task.state == *state)
+                    .map(|task| BoardTask {
+                        id: task.id.clone(),
+                        title: task.title.clone(),
+                        description: task.description.clone(),
+                        updated_at: task.updated_at,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    })
+}
+
+fn header(request: &Request, name: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str().trim().to_string())
+}
+
+/// The board for `share`, sealed for the requester that sent `nonce`
+fn sealed_board(share: &BoardShare, nonce: &str) -> Result<Vec<u8>, String> {
+    let board = board(&share.project_id).map_err(|e| e.to_string())?;
+    let json = serde_json::to_vec(&board).map_err(|e| e.to_string())?;
+    secrets::seal(&share_cipher(&share.token), nonce.as_bytes(), &json)
+}
+
+fn handle(request: Request) {
+    let share = header(&request, "Authorization")
+        .and_then(|value| value.strip_prefix("Share ").map(str::to_string))
+        .and_then(|id| {
+            SHARES
+                .lock()
+                .ok()?
+                .values()
+                .find(|share| auth::same_token(&share_id(&share.token), &id))
+                .cloned()
+        });
+    let nonce = header(&request, NONCE_HEADER).filter(|n| (32..=128).contains(&n.len()));
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+    let response = match (request.method(), path.as_str(), share, nonce) {
+        (Method::Get, "/board", Some(share), Some(nonce)) => sealed_board(&share, &nonce),
+        (Method::Get, "/board", _, None) => {
+            let _ = request.respond(Response::empty(400));
+            return;
+        }
+        (Method::Get, "/board", None, _) => {
+            let _ = request.respond(Response::empty(401));
+            return;
+        }
+        _ => {
+            let _ = request.respond(Response::empty(404));
+            return;
+        }
+    };
+    match response {
+        Ok(body) => {
+            let header = Header::from_bytes("Content-Type", "application/octet-stream")
+                .expect("static header");
+            let _ = request.respond(Response::from_data(body).with_header(header));
+        }
+        Err(e) => {
+            eprintln!("[Claude PM] Failed to serve shared board: {}", e);
+            let _ = request.respond(Response::empty(500));
+        }
+    }
+}
+
+fn start_server() -> Result<(), String> {
+    let mut server = SERVER.lock().map_err(|e| e.to_string())?;
+    if server.is_some() {
+        return Ok(());
+    }
+    let started = Arc::new(
+        Server::http(("0.0.0.0", SHARE_PORT))
+            .map_err(|e| format!("Failed to listen on port {}: {}", SHARE_PORT, e))?,
+    );
+    println!("[Claude PM] Sharing boards on port {}", SHARE_PORT);
+    *server = Some(started.clone());
+    thread::spawn(move || {
+        for request in started.incoming_requests() {
+            handle(request);
+        }
+    });
+    Ok(())
+}
+
+fn stop_server() {
+    if let Some(server) = SERVER.lock().ok().and_then(|mut server| server.take()) {
+        server.unblock();
+        println!("[Claude PM] Stopped sharing boards");
+    }
+}
+
+/// Start discovery if it's turned on; safe to call more than once
+pub fn start(app: AppHandle) {
+    if config::load().lan_share.discoverable && !DISCOVERING.swap(true, Ordering::SeqCst) {
+        thread::spawn(move || discover(app));
+    }
+}
+
+#[tauri::command]
+pub fn get_lan_status() -> Result<LanStatus, Error> {
+    app_lock::ensure_unlocked()?;
+    Ok(status())
+}
+
+#[tauri::command]
+pub fn set_lan_discovery(app: AppHandle, settings: LanShareSettings) -> Result<LanStatus, Error> {
+    app_lock::ensure_unlocked()?;
+    config::update(|c| c.lan_share = settings.clone())?;
+    REANNOUNCE.store(true, Ordering::SeqCst);
+    if settings.discoverable {
+        start(app);
+    } else {
+        // The discovery thread says goodbye and exits within a second
+        DISCOVERING.store(false, Ordering::SeqCst);
+    }
+    Ok(status())
+}
+
+/// Share a project's board with whoever is given the returned token
+#[tauri::command]
+pub fn share_board(project_id: String) -> Result<BoardShare, Error> {
+    app_lock::ensure_unlocked()?;
+    let project_name = board(&project_id)?.project_name;
+    start_server()?;
+    let share = BoardShare {
+        token: auth::random_hex(),
+        project_id,
+        project_name,
+        started_at: store::now_ms(),
+    };
+    SHARES
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(share.token.clone(), share.clone());
+    REANNOUNCE.store(true, Ordering::SeqCst);
+    Ok(share)
+}
+
+#[tauri::command]
+pub fn stop_sharing_board(token: String) -> Result<(), Error> {
+    let mut shares = SHARES.lock().map_err(|e| e.to_string())?;
+    shares.remove(&token);
+    REANNOUNCE.store(true, Ordering::SeqCst);
+    if shares.is_empty() {
+        stop_server();
+    }
+    Ok(())
+}
+
+/// Open a board sealed by `sealed_board`; only the holder of `token` can produce one
+fn open_board(token: &str, nonce: &str, sealed: &[u8]) -> Option<SharedBoard> {
+    let json = secrets::open(&share_cipher(token), nonce.as_bytes(), sealed).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+/// Fetch the board a peer shared under `token`
+#[tauri::command]
+pub async fn fetch_shared_board(peer_id: String, token: String) -> Result<SharedBoard, Error> {
+    let peer = peers()
+        .into_iter()
+        .find(|peer| peer.id == peer_id)
+        .ok_or_else(|| Error::NotFound(format!("Peer {} is no longer on the network", peer_id)))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let address: IpAddr = peer.address.parse().map_err(|e| format!("{}", e))?;
+        let url = format!("http://{}/board", SocketAddr::new(address, peer.port));
+        let nonce = auth::random_hex();
+        match ureq::get(&url)
+            .timeout(FETCH_TIMEOUT)
+            .set("Authorization", &format!("Share {}", share_id(&token)))
+            .set(NONCE_HEADER, &nonce)
+            .call()
+        {
+            Ok(response) => {
+                let mut sealed = Vec::new();
+                response
+                    .into_reader()
+                    .take(MAX_BOARD_BYTES)
+                    .read_to_end(&mut sealed)
+                    .map_err(|e| format!("Failed to read the board from {}: {}", peer.name, e))?;
+                open_board(&token, &nonce, &sealed).ok_or_else(|| {
+                    Error::InvalidInput(format!(
+                        "{} at {} couldn't prove it shared that board",
+                        peer.name, peer.address
+                    ))
+                })
+            }
+            Err(ureq::Error::Status(401, _)) => Err(Error::InvalidInput(
+                "That token doesn't match a board shared by this peer".to_string(),
+            )),
+            Err(e) => Err(Error::Internal(format!(
+                "Failed to reach {}: {}",
+                peer.name, e
+            ))),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_for(project_name: &str) -> SharedBoard {
+        SharedBoard {
+            project_name: project_name.to_string(),
+            shared_by: "Ada's Mac".to_string(),
+            generated_at: 1,
+            columns: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn sealed_boards_open_only_with_the_token_and_nonce() {
+        let token = auth::random_hex();
+        let nonce = auth::random_hex();
+        let json = serde_json::to_vec(&board_for("Apollo")).unwrap();
+        let sealed = secrets::seal(&share_cipher(&token), nonce.as_bytes(), &json).unwrap();
+
+        let board = open_board(&token, &nonce, &sealed).expect("opens with the token");
+        assert_eq!(board.project_name, "Apollo");
+        // A replayed reply was bound to someone else's nonce
+        assert!(open_board(&token, &auth::random_hex(), &sealed).is_none());
+        // An impostor without the token can't produce a reply that opens
+        let forged =
+            secrets::seal(&share_cipher(&auth::random_hex()), nonce.as_bytes(), &json).unwrap();
+        assert!(open_board(&token, &nonce, &forged).is_none());
+    }
+
+    #[test]
+    fn share_id_does_not_reveal_the_token() {
+        let token = auth::random_hex();
+        let id = share_id(&token);
+        assert_eq!(id, share_id(&format!(" {}\n", token)));
+        assert!(!token.contains(&id) && !id.contains(&token[..16]));
+        assert_ne!(id, share_id(&auth::random_hex()));
+    }
+
+    #[test]
+    fn reads_peers_from_resolved_services() {
+        let txt = [("v", "1"), ("name", "Ada's Mac"), ("boards", "2")];
+        let info = ServiceInfo::new(
+            SERVICE,
+            "abc123",
+            "abc123.local.",
+            "fe80::1,192.168.1.20,10.0.0.5",
+            4870,
+            &txt[..],
+        )
+        .unwrap();
+        let peer = peer(&info.as_resolved_service()).expect("a peer");
+        assert_eq!(peer.id, "abc123");
+        assert_eq!(peer.name, "Ada's Mac");
+        assert_eq!(peer.address, "10.0.0.5");
+        assert_eq!(peer.port, 4870);
+        assert_eq!(peer.shared_boards, 2);
+
+        let ours = ServiceInfo::new(
+            SERVICE,
+            instance_id(),
+            "me.local.",
+            "192.168.1.21",
+            SHARE_PORT,
+            &txt[..],
+        )
+        .unwrap();
+        assert!(super::peer(&ours.as_resolved_service()).is_none());
+    }
+}
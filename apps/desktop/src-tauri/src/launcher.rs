@@ -0,0 +1,312 @@
+//! Unix socket protocol for launcher extensions (Raycast, Alfred)
+//!
+//! The app listens on `launcher.sock` in the data directory and writes a token to
+//! `launcher-token` next to it, both readable by the user only. A client sends one JSON
+//! request per line and gets one JSON response per line back, on a connection it can
+//! keep open between keystrokes:
+//!
+//! ```text
+//! {"id":1,"token":"…","method":"listOpenTasks","params":{"project":"…"}}
+//! {"id":1,"ok":true,"result":[…]}
+//! ```
+//!
+//! - `listOpenTasks` — tasks not yet done, with their project name, optionally for one
+//!   project (`project` is a name or id)
+//! - `addTask` — `{ project, title, description?, state? }`
+//! - `listSessions` — running sessions
+//! - `jumpToSession` — `{ sessionId }`, or `{ taskId }` for that task's latest session;
+//!   brings its window (or the main window) to the front
+//!
+//! Errors come back as `{"id":…,"ok":false,"error":{code,message,details}}`. Unix only;
+//! on Windows launchers can use the `claudepm://` URLs (see `automation`) instead.
+
+// Only the socket listener drives the protocol
+#![cfg_attr(not(unix), allow(dead_code))]
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::AppHandle;
+
+use crate::app_lock;
+use crate::error::Error;
+use crate::store::{self, NewTask, Project};
+
+const SOCKET_FILE: &str = "launcher.sock";
+const TOKEN_FILE: &str = "launcher-token";
+/// Longest request line read; a longer one ends the connection
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+
+/// Whether this instance owns the socket and token, so only it removes them
+static LISTENING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LauncherSocketInfo {
+    pub supported: bool,
+    pub socket_path: String,
+    pub token_path: String,
+}
+
+#[derive(Deserialize)]
+struct LauncherRequest {
+    #[serde(default)]
+    id: Value,
+    #[serde(default)]
+    token: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskFilter {
+    project: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddTask {
+    project: String,
+    title: String,
+    description: Option<String>,
+    state: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Jump {
+    session_id: Option<String>,
+    task_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OpenTask {
+    id: String,
+    title: String,
+    state: String,
+    project_id: String,
+    project_name: String,
+    updated_at: i64,
+}
+
+fn params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, Error> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params)
+        .map_err(|e| Error::InvalidInput(format!("Invalid params: {}", e)))
+}
+
+fn find_project(projects: &[Project], name_or_id: &str) -> Result<Project, Error> {
+    projects
+        .iter()
+        .find(|p| p.id == name_or_id || p.name.eq_ignore_ascii_case(name_or_id))
+        .cloned()
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", name_or_id)))
+}
+
+fn list_open_tasks(filter: TaskFilter) -> Result<Value, Error> {
+    let projects = store::list_projects()?;
+    let project_id = match filter.project.filter(|p| !p.trim().is_empty()) {
+        Some(project) => Some(find_project(&projects, &project)?.id),
+        None => None,
+    };
+    let mut tasks: Vec<OpenTask> = store::list_tasks(project_id, None)?
+        .into_iter()
+        .filter(|task| task.state != "done")
+        .map(|task| OpenTask {
+            project_name: projects
+                .iter()
+                .find(|p| p.id == task.project_id)
+                .map(|p| p.name.clone())
+                .unwrap_or_default(),
+            id: task.id,
+            title: task.title,
+            state: task.state,
+            project_id: task.project_id,
+            updated_at: task.updated_at,
+        })
+        .collect();
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.updated_at));
+    serde_json::to_value(tasks).map_err(|e| Error::Internal(e.to_string()))
+}
+
+fn add_task(add: AddTask) -> Result<Value, Error> {
+    let project = find_project(&store::list_projects()?, &add.project)?;
+    let task = store::create_task(NewTask {
+        project_id: project.id,
+        title: add.title,
+        description: add.description,
+        state: add.state,
+    })?;
+    serde_json::to_value(task).map_err(|e| Error::Internal(e.to_string()))
+}
+
+fn jump_to_session(app: &AppHandle, jump: Jump) -> Result<Value, Error> {
+    let session_id = match (jump.session_id, jump.task_id) {
+        (Some(id), _) => id,
+        (None, Some(task_id)) => store::list_sessions(None, None)?
+            .into_iter()
+            .find(|s| s.task_id.as_deref() == Some(task_id.as_str()))
+            .map(|s| s.id)
+            .ok_or_else(|| Error::NotFound(format!("No session for task {}", task_id)))?,
+        (None, None) => {
+            return Err(Error::InvalidInput(
+                "Expected \"sessionId\" or \"taskId\"".to_string(),
+            ))
+        }
+    };
+    crate::session_windows::route_navigation(app, &format!("/sessions/{}", session_id));
+    Ok(json!({ "sessionId": session_id }))
+}
+
+fn dispatch(app: &AppHandle, request: LauncherRequest) -> Result<Value, Error> {
+    match request.method.as_str() {
+        "listOpenTasks" => list_open_tasks(params(request.params)?),
+        "addTask" => add_task(params(request.params)?),
+        "listSessions" => {
+            serde_json::to_value(store::list_sessions(None, Some("running".to_string()))?)
+                .map_err(|e| Error::Internal(e.to_string()))
+        }
+        "jumpToSession" => jump_to_session(app, params(request.params)?),
+        other => Err(Error::NotFound(format!("Unknown method: {}", other))),
+    }
+}
+
+/// Answer one request line
+fn respond(app: &AppHandle, token: &str, line: &str) -> Value {
+    let request: LauncherRequest = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = Error::InvalidInput(format!("Invalid request: {}", e));
+            return json!({ "id": null, "ok": false, "error": error });
+        }
+    };
+    let id = request.id.clone();
+    if !crate::auth::same_token(request.token.trim(), token) {
+        let error = Error::Unauthorized("Invalid token".to_string());
+        return json!({ "id": id, "ok": false, "error": error });
+    }
+    match dispatch(app, request) {
+        Ok(result) => json!({ "id": id, "ok": true, "result": result }),
+        Err(e) => json!({ "id": id, "ok": false, "error": e }),
+    }
+}
+
+#[cfg(unix)]
+pub fn start(app: AppHandle) {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::{fs, thread};
+
+    let Some(dir) = crate::config::data_dir() else {
+        return;
+    };
+    let socket = dir.join(SOCKET_FILE);
+    let token = crate::auth::random_hex();
+    let listener = (|| -> Result<UnixListener, String> {
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        // Left behind by a previous run unless another instance is still answering on it,
+        // in which case its socket and token are left alone
+        if socket.exists() {
+            if UnixStream::connect(&socket).is_ok() {
+                return Err("another instance is already listening".to_string());
+            }
+            let _ = fs::remove_file(&socket);
+        }
+        let listener = UnixListener::bind(&socket).map_err(|e| e.to_string())?;
+        fs::set_permissions(&socket, fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+        let token_path = dir.join(TOKEN_FILE);
+        // Created owner-only so the token is never readable by anyone else, even briefly
+        let _ = fs::remove_file(&token_path);
+        let written = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&token_path)
+            .and_then(|mut file| file.write_all(token.as_bytes()));
+        if let Err(e) = written {
+            let _ = fs::remove_file(&socket);
+            return Err(e.to_string());
+        }
+        Ok(listener)
+    })();
+    let listener = match listener {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("[Claude PM] Failed to start launcher socket: {}", e);
+            return;
+        }
+    };
+    LISTENING.store(true, Ordering::SeqCst);
+    println!(
+        "[Claude PM] Launcher socket listening on {}",
+        socket.display()
+    );
+
+    thread::spawn(move || {
+        for stream in listener.incoming().map_while(Result::ok) {
+            let app = app.clone();
+            let token = token.clone();
+            thread::spawn(move || {
+                let Ok(mut writer) = stream.try_clone() else {
+                    return;
+                };
+                let mut reader = BufReader::new(stream);
+                loop {
+                    let mut line = String::new();
+                    match (&mut reader).take(MAX_LINE_BYTES + 1).read_line(&mut line) {
+                        Ok(0) | Err(_) => break,
+                        Ok(_) => {}
+                    }
+                    if line.len() as u64 > MAX_LINE_BYTES {
+                        let error = Error::InvalidInput("Request is too long".to_string());
+                        let _ = writeln!(
+                            writer,
+                            "{}",
+                            json!({ "id": null, "ok": false, "error": error })
+                        );
+                        break;
+                    }
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let response = respond(&app, &token, &line);
+                    if writeln!(writer, "{}", response).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+}
+
+#[cfg(not(unix))]
+pub fn start(_app: AppHandle) {}
+
+/// Remove the socket and token so launchers see the app as not running
+pub fn stop() {
+    if !LISTENING.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    if let Some(dir) = crate::config::data_dir() {
+        let _ = std::fs::remove_file(dir.join(SOCKET_FILE));
+        let _ = std::fs::remove_file(dir.join(TOKEN_FILE));
+    }
+}
+
+/// Where launcher extensions find the socket and token
+#[tauri::command]
+pub fn get_launcher_socket_info() -> Result<LauncherSocketInfo, Error> {
+    app_lock::ensure_unlocked()?;
+    let dir = crate::config::data_dir()
+        .ok_or_else(|| Error::NotFound("Could not determine data directory".to_string()))?;
+    Ok(LauncherSocketInfo {
+        supported: cfg!(unix),
+        socket_path: dir.join(SOCKET_FILE).display().to_string(),
+        token_path: dir.join(TOKEN_FILE).display().to_string(),
+    })
+}
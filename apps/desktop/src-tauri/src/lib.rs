@@ -1,45 +1,181 @@
 use std::process::{Command, Child, Stdio};
 use std::sync::Mutex;
-use std::net::TcpStream;
-use std::path::PathBuf;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::path::{Path, PathBuf};
 use std::env;
 use std::fs;
+use std::collections::BTreeMap;
+use tauri::Manager;
+use error::Error;
+
+mod activity;
+mod agent_monitor;
+mod api_cache;
+mod app_activation;
+mod app_lock;
+mod app_menu;
+mod applescript;
+mod approval_policy;
+mod attachments;
+mod audit;
+mod attention;
+mod auth;
+mod automation;
+mod backup;
+mod bootstrap;
+mod calendar_sync;
+mod ci_status;
+mod claude_md;
+mod claude_profiles;
+mod claude_settings;
+pub mod cli;
+mod clipboard;
+mod config;
+mod config_watch;
+mod connectivity;
+mod cost_estimate;
+mod crash;
+mod data_location;
+mod digest;
+mod dnd;
+mod doctor;
+mod dock;
+#[cfg(any(target_os = "macos", windows))]
+mod dock_menu;
+mod docker;
+mod due_date;
+mod editor;
+mod email;
+mod emergency_stop;
+mod env_editor;
+mod error;
+mod event_bus;
+mod exec_policy;
+mod file_manager;
+mod focus_session;
+mod github;
+mod github_auth;
+mod github_projects;
+mod handshake;
+mod heartbeat;
+mod hook_receiver;
+mod http_proxy;
+mod ics;
+mod idle;
+mod importer;
+mod integrations;
+mod jira;
+mod json_file;
+mod lan_share;
+mod launcher;
+mod lifecycle;
+mod linear;
+mod local_api;
+mod mcp;
+mod mcp_config;
+mod menubar;
+mod mock_server;
+mod multiplexer;
+mod notifications;
+mod onboarding;
+mod orchestrator;
+mod outbox;
+mod pane_watch;
+mod pdf;
+mod permissions;
+mod plugins;
+mod pomodoro;
+mod power;
+mod preview;
+mod process;
+mod profiles;
+mod project_archive;
+mod project_file;
+mod project_template;
+mod proxy;
+mod quick_capture;
+mod quick_switcher;
+mod queue_badge;
+mod rate_limits;
+mod recording;
+mod recurrence;
+mod report;
+mod run_diff;
+mod run_history;
+mod runner;
+mod scheduler;
+mod screenshot;
+mod scrollback;
+mod scripting;
+mod search;
+mod secrets;
+mod server_api;
+mod server_logs;
+mod server_output;
+mod server_paths;
+mod server_probe;
+mod server_update;
+mod service;
+mod session_bundle;
+mod session_windows;
+#[cfg(desktop)]
+mod shortcuts;
+mod snapshots;
+mod sounds;
+mod speech;
+mod startup_timing;
+mod static_site;
+mod storage;
+mod store;
+mod sync;
+mod sync_folder;
+mod sync_remote;
+mod system_log;
+mod task_branch;
+mod task_graph;
+mod terminal;
+mod theme;
+mod timetracking;
+mod tmux;
+mod tmux_layout;
+mod transcript_retention;
+mod transcript_tail;
+mod usage;
+mod vault;
+mod vcs;
+mod voice;
+mod warm_restart;
+mod watchdog;
+mod webhooks;
+mod window_state;
+mod windows;
+mod working_hours;
+mod ws_bridge;
+
+/// Default port the Node server listens on
+pub const SERVER_PORT: u16 = 4847;
+
+/// Port of the active profile, set whenever the server is started
+static ACTIVE_PORT: AtomicU16 = AtomicU16::new(SERVER_PORT);
 
 // Global state for the server process
 static SERVER_PROCESS: Mutex<Option<Child>> = Mutex::new(None);
 
-#[tauri::command]
-fn activate_app(app_name: String) -> Result<(), String> {
-    let script = format!("tell application \"{}\" to activate", app_name);
-
-    let output = Command::new("osascript")
-        .args(["-e", &script])
-        .output()
-        .map_err(|e| format!("Failed to run osascript: {}", e))?;
-
-    if output.status.success() {
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("osascript failed: {}", stderr))
-    }
+/// Port the server is (or will be) listening on
+pub fn server_port() -> u16 {
+    ACTIVE_PORT.load(Ordering::SeqCst)
 }
 
 /// Check if the server is already running by attempting to connect to the port
 fn is_server_running(port: u16) -> bool {
-    TcpStream::connect(format!("127.0.0.1:{}", port)).is_ok()
+    server_probe::is_listening(port)
 }
 
 /// Find npm executable - checks common locations
 fn find_npm() -> Option<PathBuf> {
     // Check if npm is in PATH
-    if let Ok(output) = Command::new("which").arg("npm").output() {
-        if output.status.success() {
-            let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !path.is_empty() {
-                return Some(PathBuf::from(path));
-            }
-        }
+    if let Some(path) = process::which("npm") {
+        return Some(path);
     }
 
     // Common npm locations on macOS
@@ -78,95 +214,152 @@ fn find_npm() -> Option<PathBuf> {
     None
 }
 
-/// Get the path to the server directory
+/// Get the path to the server directory (see `server_paths`)
 fn get_server_path() -> Option<PathBuf> {
-    // 1. Check environment variable first
-    if let Ok(path) = env::var("CLAUDE_PM_SERVER_PATH") {
-        let p = PathBuf::from(&path);
-        if p.exists() {
-            return Some(p);
-        }
-    }
+    server_paths::resolve()
+}
 
-    // 2. Check the hardcoded project path (most reliable for this project)
-    if let Ok(home) = env::var("HOME") {
-        let project_path = PathBuf::from(&home)
-            .join("Desktop/projects/claudePM/server");
-        if project_path.exists() {
-            return Some(project_path);
-        }
+/// PATH for Node processes: GUI apps on macOS don't inherit the shell's PATH
+fn node_path_env(npm_path: &Path) -> String {
+    // Get the bin directory for PATH (needed for tsx and other npm binaries)
+    let npm_bin_dir = npm_path.parent().unwrap_or(npm_path);
+
+    // Build PATH with all necessary directories
+    // Include: npm bin, /usr/local/bin (tmux), /opt/homebrew/bin, standard paths
+    let home = env::var("HOME").unwrap_or_default();
+    let current_path = env::var("PATH").unwrap_or_default();
+    format!(
+        "{}:/usr/local/bin:/opt/homebrew/bin:/usr/bin:/bin:/usr/sbin:/sbin:{}/.nvm/versions/node/v20.18.0/bin:{}",
+        npm_bin_dir.display(),
+        home,
+        current_path
+    )
+}
+
+/// Environment the server is started with, as a child process or in a container
+fn server_env(profile: &profiles::ServerProfile) -> Result<BTreeMap<String, String>, Error> {
+    let mut env = http_proxy::env();
+    env.extend(profile.env.clone());
+
+    // Secrets from the selected vault env set
+    if let Some(project) = config::load().server_env_set {
+        env.extend(vault::env_for(&project)?);
     }
 
-    // 3. Try relative to executable (for development)
-    if let Ok(exe_path) = env::current_exe() {
-        if let Some(parent) = exe_path.parent() {
-            // Development: executable is in src-tauri/target/debug or release
-            let paths_to_try = [
-                parent.join("../../../../server"),
-                parent.join("../../server"),
-                parent.join("../../../server"),
-            ];
-
-            for path in paths_to_try {
-                if let Ok(canonical) = path.canonicalize() {
-                    if canonical.join("package.json").exists() {
-                        return Some(canonical);
-                    }
-                }
-            }
-        }
+    env.insert("PORT".to_string(), profile.port.to_string());
+    env.insert("LOG_LEVEL".to_string(), profile.log_level.clone());
+    if let Some(key) = auth::api_key() {
+        env.insert(auth::API_KEY_ENV.to_string(), key);
     }
+    env.insert(auth::TOKEN_ENV.to_string(), auth::token().to_string());
+    Ok(env)
+}
 
-    None
+/// Start the server with the active profile, queued behind any other lifecycle operation
+fn start_server() -> Result<(), Error> {
+    lifecycle::start(spawn_server)
 }
 
-/// Start the server subprocess with hot reload
-fn start_server() -> Result<(), String> {
-    let port: u16 = 4847;
+/// Start a server whose launch was deferred (see `lifecycle`); true if this started it
+fn start_deferred_server() -> bool {
+    lifecycle::start_deferred(spawn_server)
+}
+
+/// Start the server now, e.g. from the UI's start button while the start is deferred
+#[tauri::command]
+async fn start_server_now() -> Result<(), Error> {
+    tauri::async_runtime::spawn_blocking(start_server)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Stop the server, queued behind any other lifecycle operation
+fn stop_server() {
+    lifecycle::stop(kill_server)
+}
+
+fn spawn_server() -> Result<(), Error> {
+    let profile = profiles::active();
+    let port = profile.port;
+    ACTIVE_PORT.store(port, Ordering::SeqCst);
+    proxy::set_upstream_port(port);
+
+    if mock_server::is_enabled() {
+        return mock_server::start(port);
+    }
 
     // Check if server is already running
     if is_server_running(port) {
+        // Another program on the port would otherwise pass for our server
+        if !server_probe::is_claude_pm(port) {
+            return Err(Error::PortInUse { port });
+        }
         println!("[Claude PM] Server already running on port {}", port);
         return Ok(());
     }
 
+    if service::is_installed() {
+        println!("[Claude PM] Server is managed by a background service");
+        return service::ensure_started();
+    }
+
+    if config::load().docker.enabled {
+        let env = server_env(&profile)?;
+        env_editor::mark_started(&env);
+        return docker::start(&profile, &env);
+    }
+
     // Find npm executable
-    let npm_path = find_npm().ok_or_else(|| {
-        "Could not find npm. Please ensure Node.js is installed.".to_string()
-    })?;
+    let npm_path = startup_timing::server_phase("npmDiscovery", find_npm).ok_or(Error::NpmNotFound)?;
     println!("[Claude PM] Found npm at: {:?}", npm_path);
 
     // Find server directory
-    let server_path = get_server_path().ok_or_else(|| {
-        "Could not find server directory. Set CLAUDE_PM_SERVER_PATH environment variable.".to_string()
-    })?;
+    let server_path = startup_timing::server_phase("serverPath", get_server_path)
+        .ok_or(Error::ServerPathMissing)?;
     println!("[Claude PM] Starting server from: {:?}", server_path);
 
-    // Get the bin directory for PATH (needed for tsx and other npm binaries)
-    let npm_bin_dir = npm_path.parent().unwrap_or(&npm_path);
+    // A fresh clone has no dependencies yet; `bootstrap::install` (the
+    // `install_server_dependencies` command) fixes that
+    if bootstrap::needs_install(&server_path) {
+        return Err(Error::DependenciesMissing);
+    }
 
-    // Build PATH with all necessary directories
-    // Include: npm bin, /usr/local/bin (tmux), /opt/homebrew/bin, standard paths
-    let home = env::var("HOME").unwrap_or_default();
-    let current_path = env::var("PATH").unwrap_or_default();
-    let new_path = format!(
-        "{}:/usr/local/bin:/opt/homebrew/bin:/usr/bin:/bin:/usr/sbin:/sbin:{}/.nvm/versions/node/v20.18.0/bin:{}",
-        npm_bin_dir.display(),
-        home,
-        current_path
-    );
-
-    // Start the server with npm run dev (uses tsx watch for hot reload)
-    let child = Command::new(&npm_path)
-        .args(["run", "dev"])
-        .current_dir(&server_path)
-        .env("PATH", &new_path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to start server: {}", e))?;
+    let new_path = node_path_env(&npm_path);
+    let env = server_env(&profile)?;
+    env_editor::mark_started(&env);
+
+    // Start the server with the profile's npm script (`dev` uses tsx watch for hot reload)
+    println!("[Claude PM] Using server profile: {}", profile.name);
+    let mut cmd = Command::new(&npm_path);
+    // Own process group so an orphaned server (and its node child) can be stopped after a crash
+    process::new_process_group(&mut cmd);
+    let mut child = startup_timing::server_phase("spawn", || {
+        cmd.args(["run", &profile.script])
+            .current_dir(&server_path)
+            .envs(&env)
+            .env("PATH", &new_path)
+            // Piped so a watch-mode server can be rerun warm (see `warm_restart`)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+    })
+    .map_err(|e| format!("Failed to start server: {}", e))?;
 
     println!("[Claude PM] Server started with PID: {}", child.id());
+    server_output::reset("process");
+    if let Some(stdout) = child.stdout.take() {
+        crash::capture_output(stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        crash::capture_output(stderr);
+    }
+    if let Some(stdin) = child.stdin.take() {
+        let key = warm_restart::key(&npm_path, &server_path, &profile, &env);
+        warm_restart::attach(key, stdin, &server_path, &profile.script);
+    }
+    crash::set_server_pid(Some(child.id()));
+    config_watch::mark_server_started();
 
     // Store the child process
     let mut server = SERVER_PROCESS.lock().map_err(|e| e.to_string())?;
@@ -175,62 +368,641 @@ fn start_server() -> Result<(), String> {
     Ok(())
 }
 
-/// Stop the server subprocess
-fn stop_server() {
+/// Stop the server subprocess (or container)
+fn kill_server() {
+    mock_server::stop();
+    if config::load().docker.enabled {
+        docker::stop();
+    }
     if let Ok(mut server) = SERVER_PROCESS.lock() {
         if let Some(ref mut child) = *server {
             println!("Stopping server (PID: {})", child.id());
 
-            // Try graceful shutdown first
-            let _ = child.kill();
+            // Take node down with npm now that the server has its own process group
+            process::kill_tree(child);
             let _ = child.wait();
 
             println!("Server stopped");
         }
         *server = None;
+        crash::set_server_pid(None);
     }
+    warm_restart::detach();
+    server_output::stopped();
 }
 
-#[tauri::command]
-fn restart_server() -> Result<(), String> {
-    stop_server();
-    std::thread::sleep(std::time::Duration::from_millis(500));
-    start_server()
+// Waits for the health check, so off the main thread
+#[tauri::command(async)]
+fn restart_server() -> Result<(), Error> {
+    if service::is_installed() {
+        return service::restart();
+    }
+    let warm = warm_restart::can_rerun().then_some(warm_restart::rerun);
+    lifecycle::restart(warm, kill_server, spawn_server)?;
+    if !warm_restart::wait_until_healthy() {
+        eprintln!("[Claude PM] Server did not pass its health check after restarting");
+    }
+    Ok(())
+}
+
+/// Our server child's pid and exit status if it died without being stopped
+fn server_exit_status() -> Option<(u32, String)> {
+    let mut server = SERVER_PROCESS.lock().ok()?;
+    let child = server.as_mut()?;
+    let status = child.try_wait().ok()??;
+    Some((child.id(), status.to_string()))
+}
+
+/// Restart the server if we started it and it has died or stopped answering; returns true if restarted
+fn recover_server() -> bool {
+    let dead = match SERVER_PROCESS.lock() {
+        Ok(mut server) => match server.as_mut() {
+            Some(child) => !matches!(child.try_wait(), Ok(None)),
+            None => return false,
+        },
+        Err(_) => return false,
+    };
+
+    // A live child gets a few seconds to start answering again before we give up on it
+    let unreachable = !dead
+        && (0..3).all(|_| {
+            std::thread::sleep(std::time::Duration::from_secs(2));
+            !is_server_running(server_port())
+        });
+
+    if !dead && !unreachable {
+        return false;
+    }
+    println!("[Claude PM] Server did not survive sleep, restarting");
+    match restart_server() {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("[Claude PM] Failed to restart server after wake: {}", e);
+            false
+        }
+    }
 }
 
 #[tauri::command]
-fn get_server_status() -> Result<String, String> {
-    let port: u16 = 4847;
-    if is_server_running(port) {
-        Ok("running".to_string())
-    } else {
-        Ok("stopped".to_string())
+fn get_server_status() -> Result<String, Error> {
+    if is_server_running(server_port()) {
+        return Ok("running".to_string());
+    }
+    // Lazy start: nothing has needed the server yet
+    if lifecycle::is_deferred() {
+        return Ok("idle".to_string());
+    }
+    // A server that hasn't bound its port yet, or printed a known failure
+    match server_output::last() {
+        Some(status) if status.status == "starting" || status.status == "failed" => {
+            Ok(status.status)
+        }
+        _ => Ok("stopped".to_string()),
     }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Start the server before the app
-    if let Err(e) = start_server() {
-        eprintln!("Warning: Failed to start server: {}", e);
+    startup_timing::mark_launch();
+    system_log::init();
+    mock_server::init();
+    crash::init();
+
+    // Start the server before the app, unless it should wait for first use
+    if config::load().lazy_server_start {
+        println!("[Claude PM] Lazy start: the server starts on first use");
+        lifecycle::defer();
+        startup_timing::server_started(false);
+    } else {
+        let started = start_server();
+        startup_timing::server_started(started.is_ok());
+        if let Err(e) = started {
+            eprintln!("Warning: Failed to start server: {}", e);
+        }
     }
+    let window_start = std::time::Instant::now();
+
+    let builder = tauri::Builder::default();
+    #[cfg(desktop)]
+    let builder = builder.plugin(shortcuts::plugin());
 
-    tauri::Builder::default()
+    builder
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_clipboard_manager::init())
-        .invoke_handler(tauri::generate_handler![
-            activate_app,
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(tauri_plugin_dialog::init())
+        .register_asynchronous_uri_scheme_protocol(preview::SCHEME, |_ctx, request, responder| {
+            preview::handle(request, responder)
+        })
+        .setup(move |app| {
+            startup_timing::init(app.handle().clone());
+            #[cfg(desktop)]
+            shortcuts::register_all(app.handle());
+            menubar::init(app.handle())?;
+            app_menu::init(app.handle())?;
+            #[cfg(any(target_os = "macos", windows))]
+            dock_menu::init(app.handle());
+            api_cache::init(app.handle().clone());
+            proxy::start();
+            ws_bridge::start(app.handle().clone());
+            mcp::start(app.handle().clone());
+            hook_receiver::start(app.handle().clone());
+            scheduler::start(app.handle().clone());
+            automation::init(app.handle());
+            project_file::open_from_args(app.handle());
+            power::start(app.handle().clone());
+            event_bus::start(app.handle().clone());
+            connectivity::start(app.handle().clone());
+            outbox::start(app.handle().clone());
+            approval_policy::start(app.handle().clone());
+            rate_limits::start(app.handle().clone());
+            usage::start(app.handle().clone());
+            orchestrator::start(app.handle().clone());
+            integrations::start(app.handle().clone());
+            plugins::start(app.handle().clone());
+            speech::start();
+            theme::start(app.handle().clone());
+            storage::start(app.handle().clone());
+            transcript_retention::start(app.handle().clone());
+            data_location::start(app.handle());
+            app_lock::start(app.handle().clone());
+            sync::start(app.handle().clone());
+            heartbeat::start(app.handle().clone());
+            working_hours::start(app.handle().clone());
+            lan_share::start(app.handle().clone());
+            local_api::start(app.handle().clone());
+            launcher::start(app.handle().clone());
+            pane_watch::start(app.handle().clone());
+            ci_status::start(app.handle().clone());
+            github_projects::start(app.handle().clone());
+            jira::start(app.handle().clone());
+            linear::start(app.handle().clone());
+            recurrence::start(app.handle().clone());
+            task_graph::start(app.handle().clone());
+            watchdog::start(app.handle().clone());
+            exec_policy::init(app.handle().clone());
+            webhooks::start(app.handle().clone());
+            docker::watch();
+            activity::start(app.handle().clone());
+            idle::start(app.handle().clone());
+            clipboard::start(app.handle().clone());
+            search::start();
+            ics::start();
+            handshake::start(app.handle().clone());
+            config_watch::start(app.handle().clone());
+            bootstrap::install_if_needed(app.handle().clone());
+            // The main window starts hidden so restoring its geometry doesn't flicker
+            if let Some(window) = app.get_webview_window(windows::MAIN_WINDOW) {
+                window_state::restore(&window.as_ref().window());
+                let _ = window.show();
+            }
+            startup_timing::record("windowCreation", window_start);
+            Ok(())
+        })
+        .invoke_handler({
+            let handler: Box<dyn Fn(tauri::ipc::Invoke) -> bool + Send + Sync> =
+                Box::new(tauri::generate_handler![
+            app_activation::activate_app,
             restart_server,
-            get_server_status
-        ])
-        .on_window_event(|_window, event| {
-            // Stop server when app is closed
-            if let tauri::WindowEvent::Destroyed = event {
-                stop_server();
+            get_server_status,
+            lifecycle::get_server_lifecycle,
+            handshake::get_server_handshake,
+            server_update::update_server,
+            server_output::get_server_output_status,
+            server_logs::search_server_logs,
+            server_logs::export_server_logs,
+            auth::get_auth_token,
+            proxy::get_proxy_url,
+            editor::list_editors,
+            editor::set_default_editor,
+            editor::open_in_editor,
+            terminal::list_terminals,
+            terminal::set_default_terminal,
+            terminal::open_terminal_at,
+            file_manager::reveal_in_file_manager,
+            runner::run_command,
+            runner::cancel_command,
+            runner::list_running_commands,
+            app_menu::list_recent_projects,
+            app_menu::mark_project_opened,
+            app_menu::clear_recent_projects,
+            app_menu::get_menu_accelerators,
+            app_menu::set_menu_accelerator,
+            dock::set_badge_count,
+            dock::set_dock_progress,
+            attention::request_attention,
+            agent_monitor::watch_tmux_pane,
+            agent_monitor::unwatch_tmux_pane,
+            agent_monitor::respond_to_agent,
+            approval_policy::list_approval_rules,
+            approval_policy::save_approval_rule,
+            approval_policy::delete_approval_rule,
+            approval_policy::test_approval_rules,
+            approval_policy::list_approval_decisions,
+            notifications::show_notification,
+            dnd::get_focus_state,
+            dnd::set_focus_policy,
+            working_hours::get_working_hours,
+            working_hours::set_working_hours,
+            working_hours::override_working_hours,
+            sounds::get_sound_settings,
+            sounds::list_system_sounds,
+            sounds::set_event_sound,
+            sounds::set_sounds_muted,
+            sounds::preview_sound,
+            theme::get_system_theme,
+            speech::speak,
+            speech::stop_speaking,
+            speech::list_voices,
+            speech::get_speech_settings,
+            speech::set_speech_settings,
+            speech::set_speech_event_enabled,
+            #[cfg(desktop)]
+            shortcuts::get_shortcuts,
+            #[cfg(desktop)]
+            shortcuts::set_shortcut,
+            quick_switcher::show_quick_switcher,
+            quick_switcher::hide_quick_switcher,
+            quick_switcher::toggle_quick_switcher,
+            window_state::reset_window_state,
+            session_windows::open_session_window,
+            session_windows::close_session_window,
+            session_windows::list_session_windows,
+            menubar::get_menubar_summary,
+            permissions::get_permission_status,
+            permissions::open_permission_settings,
+            power::keep_awake,
+            power::release_keep_awake,
+            power::list_keep_awake,
+            power::get_power_state,
+            storage::get_storage_report,
+            storage::set_storage_cap,
+            storage::set_min_free_space,
+            storage::clean_caches,
+            crash::get_last_crash,
+            crash::dismiss_crash,
+            app_lock::get_app_lock_status,
+            app_lock::set_app_lock_policy,
+            app_lock::lock_now,
+            app_lock::unlock_app,
+            data_location::get_data_dir_status,
+            data_location::move_data_dir,
+            data_location::reset_data_dir,
+            docker::detect_docker,
+            docker::get_docker_settings,
+            docker::set_docker_settings,
+            docker::get_docker_status,
+            docker::build_server_image,
+            docker::get_docker_logs,
+            config_watch::apply_config,
+            bootstrap::install_server_dependencies,
+            bootstrap::cancel_server_install,
+            doctor::run_doctor,
+            onboarding::get_onboarding_state,
+            onboarding::advance_onboarding,
+            onboarding::skip_onboarding_step,
+            onboarding::reset_onboarding,
+            activity::set_activity_tracking,
+            activity::get_activity_status,
+            activity::get_recent_activity,
+            idle::get_idle_seconds,
+            idle::set_idle_threshold,
+            timetracking::start_timer,
+            timetracking::stop_timer,
+            timetracking::switch_timer,
+            timetracking::resume_timer,
+            timetracking::get_current_timer,
+            timetracking::list_time_entries,
+            timetracking::get_time_report,
+            pomodoro::start_pomodoro,
+            pomodoro::pause_pomodoro,
+            pomodoro::resume_pomodoro,
+            pomodoro::skip_pomodoro_phase,
+            pomodoro::stop_pomodoro,
+            pomodoro::get_pomodoro_state,
+            pomodoro::set_pomodoro_settings,
+            report::render_report_markdown,
+            email::list_email_presets,
+            email::get_email_settings,
+            email::set_email_settings,
+            email::send_test_email,
+            email::send_weekly_report_email,
+            email::send_alert_email,
+            ics::set_deadline,
+            ics::clear_deadline,
+            ics::list_deadlines,
+            ics::export_ics,
+            ics::set_ics_feed_enabled,
+            ics::get_ics_feed_url,
+            integrations::list_integrations,
+            integrations::upsert_integration,
+            integrations::delete_integration,
+            integrations::send_test_webhook,
+            scripting::list_scripts,
+            scripting::save_script,
+            scripting::delete_script,
+            scripting::set_script_enabled,
+            scripting::dry_run_script,
+            webhooks::list_webhooks,
+            webhooks::upsert_webhook,
+            webhooks::delete_webhook,
+            webhooks::list_webhook_deliveries,
+            webhooks::redeliver_webhook,
+            webhooks::ping_webhook,
+            report::export_report_pdf,
+            report::generate_weekly_summary,
+            search::search,
+            search::reindex_transcripts,
+            transcript_tail::follow_transcript,
+            transcript_tail::unfollow_transcript,
+            project_file::take_opened_project,
+            project_file::open_project_file,
+            attachments::import_attachments,
+            attachments::list_attachments,
+            attachments::set_attachment_task,
+            attachments::delete_attachment,
+            screenshot::capture_screenshot,
+            recording::start_recording,
+            recording::stop_recording,
+            recording::list_recordings,
+            clipboard::set_clipboard_history_enabled,
+            clipboard::get_clipboard_history,
+            clipboard::clear_clipboard_history,
+            clipboard::paste_clipboard_into_task,
+            clipboard::copy_image,
+            clipboard::copy_html,
+            clipboard::copy_rtf,
+            profiles::list_profiles,
+            profiles::save_profile,
+            profiles::set_active_profile,
+            plugins::list_plugins,
+            plugins::set_plugin_enabled,
+            plugins::grant_plugin_capabilities,
+            plugins::list_plugin_commands,
+            plugins::run_plugin_command,
+            event_bus::list_recent_events,
+            connectivity::get_connectivity_status,
+            heartbeat::get_heartbeat,
+            connectivity::list_outbound_queue,
+            connectivity::queue_outbound_request,
+            connectivity::discard_queued_job,
+            orchestrator::enqueue_agent_run,
+            cost_estimate::estimate_run_cost,
+            orchestrator::list_agent_runs,
+            orchestrator::cancel_agent_run,
+            orchestrator::prioritize_agent_run,
+            orchestrator::set_max_parallel_agents,
+            orchestrator::clear_finished_agent_runs,
+            rate_limits::get_rate_limit_status,
+            rate_limits::clear_rate_limit,
+            usage::get_budget_status,
+            usage::set_budget,
+            usage::set_budget_thresholds,
+            voice::get_voice_status,
+            voice::start_voice_capture,
+            voice::stop_voice_capture,
+            voice::cancel_voice_capture,
+            outbox::list_pending_deliveries,
+            outbox::discard_pending_delivery,
+            http_proxy::get_proxy_settings,
+            http_proxy::set_proxy_settings,
+            vault::list_env_sets,
+            vault::get_env_set,
+            vault::set_env_set,
+            vault::delete_env_set,
+            vault::select_server_env_set,
+            ws_bridge::get_server_events_since,
+            ws_bridge::get_server_bridge_status,
+            ws_bridge::bridge_subscribe,
+            ws_bridge::bridge_unsubscribe,
+            mcp_config::list_mcp_servers,
+            mcp_config::add_mcp_server,
+            mcp::get_mcp_server_entry,
+            mcp_config::update_mcp_server,
+            mcp_config::remove_mcp_server,
+            claude_settings::read_claude_settings,
+            claude_settings::preview_claude_settings,
+            claude_settings::apply_claude_settings,
+            claude_settings::list_claude_settings_backups,
+            claude_settings::restore_claude_settings_backup,
+            claude_settings::get_model_config,
+            claude_settings::set_default_model,
+            claude_settings::set_project_model,
+            claude_md::list_claude_md_files,
+            claude_md::read_claude_md,
+            claude_md::list_claude_md_templates,
+            claude_md::create_claude_md_from_template,
+            claude_md::diff_claude_md,
+            claude_md::update_claude_md,
+            claude_md::lint_claude_md,
+            scheduler::list_schedules,
+            scheduler::upsert_schedule,
+            scheduler::delete_schedule,
+            scheduler::run_schedule_now,
+            service::install_server_service,
+            service::uninstall_server_service,
+            service::get_server_service_status,
+            store::get_encryption_status,
+            store::set_encryption_enabled,
+            store::list_projects,
+            store::create_project,
+            store::update_project,
+            store::list_tasks,
+            store::create_task,
+            store::update_task,
+            store::list_task_comments,
+            store::add_task_comment,
+            store::delete_task_comment,
+            store::list_sessions,
+            store::create_session,
+            store::update_session,
+            backup::export_backup,
+            backup::import_backup,
+            session_bundle::export_session_bundle,
+            session_bundle::import_session_bundle,
+            sync::get_sync_status,
+            sync::set_sync_settings,
+            sync::get_sync_key,
+            sync::set_sync_key,
+            sync::sync_now,
+            sync::list_sync_conflicts,
+            sync::clear_sync_conflicts,
+            lan_share::get_lan_status,
+            lan_share::set_lan_discovery,
+            lan_share::share_board,
+            lan_share::stop_sharing_board,
+            lan_share::fetch_shared_board,
+            local_api::get_local_api_status,
+            local_api::set_local_api_enabled,
+            launcher::get_launcher_socket_info,
+            multiplexer::get_multiplexer_status,
+            multiplexer::set_multiplexer,
+            tmux_layout::list_tmux_layouts,
+            tmux_layout::save_tmux_layout,
+            tmux_layout::delete_tmux_layout,
+            tmux_layout::apply_tmux_layout,
+            scrollback::capture_pane_scrollback,
+            pane_watch::list_pane_watches,
+            pane_watch::set_pane_watch,
+            pane_watch::remove_pane_watch,
+            emergency_stop::kill_all_managed_processes,
+            exec_policy::get_exec_policy,
+            exec_policy::set_exec_policy,
+            exec_policy::list_exec_violations,
+            audit::query_audit_log,
+            audit::export_audit_log,
+            audit::verify_audit_log,
+            snapshots::list_task_snapshots,
+            snapshots::rollback_run,
+            run_diff::get_run_diff,
+            run_diff::apply_hunks,
+            task_branch::start_task_branch,
+            task_branch::get_task_branch,
+            task_branch::remove_task_branch,
+            task_branch::create_pr,
+            vcs::get_project_vcs,
+            ci_status::list_pr_ci_statuses,
+            ci_status::refresh_pr_ci_statuses,
+            ci_status::set_ci_watch_settings,
+            server_probe::probe_server,
+            startup_timing::get_startup_timings,
+            start_server_now,
+            watchdog::get_watchdog_status,
+            watchdog::set_watchdog_settings,
+            env_editor::get_server_env,
+            env_editor::set_server_env_var,
+            server_paths::list_server_paths,
+            server_paths::add_server_path,
+            server_paths::remove_server_path,
+            server_paths::select_server_path,
+            server_paths::set_server_path,
+            server_paths::relocate_server_path,
+            run_history::query_run_history,
+            run_history::get_run_record,
+            run_history::set_run_history_retention,
+            quick_capture::quick_capture,
+            quick_capture::add_inbox_item,
+            quick_capture::list_inbox_items,
+            quick_capture::remove_inbox_item,
+            focus_session::start_focus_session,
+            focus_session::stop_focus_session,
+            focus_session::get_focus_session,
+            focus_session::set_focus_session_settings,
+            transcript_retention::preview_transcript_gc,
+            transcript_retention::run_transcript_gc,
+            transcript_retention::list_transcript_gc_batches,
+            transcript_retention::undo_transcript_gc,
+            transcript_retention::set_transcript_retention,
+            transcript_retention::set_transcript_gc_schedule,
+            transcript_retention::get_transcript_retention,
+            github_projects::link_github_board,
+            github_projects::unlink_github_board,
+            github_projects::list_github_boards,
+            github_projects::sync_github_board,
+            jira::start_jira_sign_in,
+            jira::disconnect_jira,
+            jira::get_jira_status,
+            jira::list_jira_fields,
+            jira::set_jira_settings,
+            jira::sync_jira,
+            linear::set_linear_api_key,
+            linear::get_linear_status,
+            linear::list_linear_teams,
+            linear::import_linear_team,
+            linear::unlink_linear_team,
+            linear::set_linear_settings,
+            linear::sync_linear,
+            project_template::list_project_templates,
+            project_template::create_project_from_template,
+            recurrence::list_recurrences,
+            recurrence::create_recurrence,
+            recurrence::update_recurrence,
+            recurrence::delete_recurrence,
+            recurrence::preview_recurrence,
+            task_graph::add_task_dependency,
+            task_graph::remove_task_dependency,
+            task_graph::get_task_graph,
+            task_graph::list_ready_tasks,
+            due_date::parse_due_date,
+            queue_badge::set_tray_badge,
+            queue_badge::set_overlay_badge,
+            queue_badge::set_queue_badges_enabled,
+            digest::get_digest_state,
+            digest::flush_digest_now,
+            digest::set_digest_settings,
+            api_cache::get_api_cache_status,
+            api_cache::clear_api_cache,
+            claude_profiles::list_claude_profiles,
+            claude_profiles::save_claude_profile,
+            claude_profiles::delete_claude_profile,
+            claude_profiles::switch_claude_profile,
+            static_site::export_static_site,
+            project_archive::archive_project,
+            project_archive::unarchive_project,
+            project_archive::list_project_archives,
+            importer::preview_import,
+            importer::run_import,
+            github::set_github_token,
+            github::disconnect_github,
+            github::list_github_issues,
+            github::list_github_prs,
+            github::link_github_item,
+            github::unlink_github_item,
+            github::list_task_github_links,
+            github::get_task_github_statuses,
+            github::create_github_issue_from_task,
+            github_auth::start_github_device_flow,
+            github_auth::cancel_github_device_flow,
+            calendar_sync::export_deadline,
+            calendar_sync::export_task_due_date,
+            calendar_sync::remove_deadline,
+            calendar_sync::list_exported_deadlines
+            ]);
+            // Every command the webview runs goes through the audit log first, then the
+            // app lock
+            move |invoke| {
+                audit::command(&invoke);
+                if !app_lock::allows(invoke.message.command()) {
+                    invoke.resolver.reject(Error::Locked);
+                    return true;
+                }
+                handler(invoke)
             }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::CloseRequested { .. } => window_state::save(window),
+            tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_) => {
+                window_state::save_soon(window)
+            }
+            tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. })
+                if window.label() == windows::MAIN_WINDOW =>
+            {
+                attachments::on_drop(window.app_handle(), paths.clone())
+            }
+            tauri::WindowEvent::Destroyed => {
+                // Stop server when the app is closed, not when a secondary window closes
+                if window.label() == windows::MAIN_WINDOW {
+                    process::cancel_all();
+                    lifecycle::shutdown(kill_server);
+                    launcher::stop();
+                    crash::clean_exit();
+                } else {
+                    session_windows::on_destroyed(window.label());
+                }
+            }
+            _ => {}
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { .. } = &event {
+                window_state::save_open(app);
+            }
+            #[cfg(target_os = "macos")]
+            if let tauri::RunEvent::Opened { urls } = &event {
+                project_file::open_urls(app, urls);
+            }
+        });
 }
@@ -0,0 +1,179 @@
+//! Server lifecycle as an explicit state machine
+//!
+//! `Stopped → Starting → Running → Stopping → Stopped`, with `Crashed` when a running
+//! server exits on its own. Start, stop and restart hold one operation lock for their
+//! whole transition, so concurrent requests (startup in `run()`, a restart from the UI,
+//! a wake-from-sleep recovery, window-close shutdown) queue behind each other instead of
+//! double-spawning. Once queued they resolve deterministically: starting a running
+//! server and stopping a stopped one are no-ops, and anything that would start the
+//! server after shutdown has begun is rejected.
+//!
+//! A restart first tries a warm rerun of a running server (see `warm_restart`), passing
+//! through `Starting` without stopping it; otherwise it stops, waits for the port to be
+//! released and starts again.
+//!
+//! With `lazy_server_start` set, `run()` defers the start instead: the server stays
+//! down until first use, i.e. a request through the proxy or the UI's start button,
+//! and any start or restart ends the deferral.
+//!
+//! Events:
+//! - `server-lifecycle` on every state change (via `event_bus`)
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::error::Error;
+use crate::event_bus::{self, AppEvent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum State {
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+    Crashed,
+}
+
+static STATE: Mutex<State> = Mutex::new(State::Stopped);
+/// Held for the duration of a transition; later operations wait their turn
+static OPERATION: Mutex<()> = Mutex::new(());
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+/// The launch-time start was skipped and nothing has started the server since
+static DEFERRED: AtomicBool = AtomicBool::new(false);
+
+fn set(state: State) {
+    let changed = match STATE.lock() {
+        Ok(mut current) if *current != state => {
+            *current = state;
+            true
+        }
+        _ => false,
+    };
+    if changed {
+        println!("[Claude PM] Server lifecycle: {:?}", state);
+        event_bus::publish(AppEvent::ServerLifecycle { state });
+    }
+}
+
+pub fn state() -> State {
+    STATE.lock().map(|state| *state).unwrap_or(State::Stopped)
+}
+
+/// A running server exited without being stopped
+pub fn mark_crashed() {
+    if state() == State::Running {
+        set(State::Crashed);
+    }
+}
+
+fn check_shutdown() -> Result<(), Error> {
+    if SHUTTING_DOWN.load(Ordering::SeqCst) {
+        return Err(Error::Cancelled("The app is shutting down".to_string()));
+    }
+    Ok(())
+}
+
+fn run_start(start: impl FnOnce() -> Result<(), Error>) -> Result<(), Error> {
+    DEFERRED.store(false, Ordering::SeqCst);
+    set(State::Starting);
+    let result = start();
+    set(if result.is_ok() {
+        State::Running
+    } else {
+        State::Stopped
+    });
+    result
+}
+
+fn run_stop(stop: impl FnOnce()) {
+    if state() == State::Stopped {
+        return;
+    }
+    set(State::Stopping);
+    stop();
+    set(State::Stopped);
+}
+
+/// Start the server with `start` unless it's already running
+pub fn start(start: impl FnOnce() -> Result<(), Error>) -> Result<(), Error> {
+    check_shutdown()?;
+    let _operation = OPERATION.lock().map_err(|e| e.to_string())?;
+    // Shutdown may have begun while this was queued
+    check_shutdown()?;
+    if state() == State::Running {
+        return Ok(());
+    }
+    run_start(start)
+}
+
+/// Leave the server down until first use
+pub fn defer() {
+    DEFERRED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_deferred() -> bool {
+    DEFERRED.load(Ordering::SeqCst)
+}
+
+/// Start a deferred server in the background; true if this call kicked it off
+pub fn start_deferred(start: fn() -> Result<(), Error>) -> bool {
+    if !DEFERRED.swap(false, Ordering::SeqCst) {
+        return false;
+    }
+    println!("[Claude PM] Starting the server on first use");
+    thread::spawn(move || {
+        if let Err(e) = self::start(start) {
+            eprintln!("[Claude PM] Failed to start server on demand: {}", e);
+            // Let the next request try again
+            DEFERRED.store(true, Ordering::SeqCst);
+        }
+    });
+    true
+}
+
+/// Stop the server with `stop` unless it's already stopped
+pub fn stop(stop: impl FnOnce()) {
+    let Ok(_operation) = OPERATION.lock() else {
+        return;
+    };
+    run_stop(stop);
+}
+
+/// Restart in place with `warm` if given, otherwise (or if it fails) stop then start; one
+/// transition either way
+pub fn restart(
+    warm: Option<impl FnOnce() -> bool>,
+    stop: impl FnOnce(),
+    start: impl FnOnce() -> Result<(), Error>,
+) -> Result<(), Error> {
+    check_shutdown()?;
+    let _operation = OPERATION.lock().map_err(|e| e.to_string())?;
+    check_shutdown()?;
+    if let Some(warm) = warm.filter(|_| state() == State::Running) {
+        set(State::Starting);
+        if warm() {
+            set(State::Running);
+            return Ok(());
+        }
+    }
+    if state() != State::Stopped {
+        let port = crate::server_port();
+        run_stop(stop);
+        crate::warm_restart::wait_for_port_release(port);
+    }
+    run_start(start)
+}
+
+/// Stop for good: queued and later starts or restarts are rejected
+pub fn shutdown(stop: impl FnOnce()) {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+    self::stop(stop);
+}
+
+#[tauri::command]
+pub fn get_server_lifecycle() -> State {
+    state()
+}
@@ -0,0 +1,614 @@
+//! Linear connector: a team's issues become tasks, and their statuses stay in step
+//!
+//! Linear is reached through its GraphQL API with a personal API key kept in the
+//! keychain. `import_linear_team` links a team to a project and imports its open
+//! issues; after that, linked teams are synced every ten minutes while online. A sync
+//! pushes tasks whose state changed here since the last sync to the issue's workflow
+//! state (`status_map` by name, else the first state of the matching type), pulls
+//! workflow states changed in Linear into their tasks, and imports new issues.
+//!
+//! When an agent run on a linked task finishes, a summary of it (duration, tokens,
+//! files changed) is posted to the issue as a comment, unless `comment_runs` is off.
+//!
+//! Events:
+//! - `linear-synced` with the [`LinearSyncReport`] of a scheduled sync that changed something
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::orchestrator::RunState;
+use crate::run_history::RunRecord;
+use crate::store::{self, NewTask, TaskUpdate};
+use crate::{config, connectivity, power, secrets};
+
+const API_URL: &str = "https://api.linear.app/graphql";
+const KEYCHAIN_ACCOUNT: &str = "linear-api-key";
+const SYNC_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const PAGE_SIZE: usize = 100;
+/// Files listed in a run comment before the rest are counted
+const COMMENT_FILES: usize = 20;
+const ISSUE_FIELDS: &str = "id identifier title description url team { id } state { id name type }";
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static SYNCING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct LinearSettings {
+    /// Sync linked teams on a schedule
+    pub enabled: bool,
+    /// Linear team id to the project its issues are imported into
+    pub teams: BTreeMap<String, String>,
+    /// Task state to workflow state name; unmapped states go by workflow state type
+    pub status_map: BTreeMap<String, String>,
+    /// Post a summary of each finished agent run on a linked task to its issue
+    pub comment_runs: bool,
+}
+
+impl Default for LinearSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            teams: BTreeMap::new(),
+            status_map: BTreeMap::new(),
+            comment_runs: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinearStatus {
+    pub connected: bool,
+    /// Name of the workspace the key belongs to
+    pub workspace: Option<String>,
+    pub user: Option<String>,
+    pub linked_teams: usize,
+    pub linked_issues: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinearTeam {
+    pub id: String,
+    pub key: String,
+    pub name: String,
+    /// Project its issues are imported into, if linked
+    pub project_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinearSyncReport {
+    /// Identifiers of issues that became tasks
+    pub imported: Vec<String>,
+    /// Identifiers of issues whose task took their new state
+    pub pulled: Vec<String>,
+    /// Identifiers of issues moved to follow their task
+    pub pushed: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl LinearSyncReport {
+    fn is_empty(&self) -> bool {
+        self.imported.is_empty()
+            && self.pulled.is_empty()
+            && self.pushed.is_empty()
+            && self.errors.is_empty()
+    }
+}
+
+/// A task linked to an issue, as of the last sync
+struct Link {
+    task_id: String,
+    issue_id: String,
+    identifier: String,
+    team_id: String,
+    state_id: String,
+    task_state: String,
+}
+
+fn api_key() -> Result<Option<String>, String> {
+    secrets::get(KEYCHAIN_ACCOUNT)
+}
+
+fn request(key: &str, query: &str, variables: Value) -> Result<Value, String> {
+    let response = ureq::post(API_URL)
+        .timeout(REQUEST_TIMEOUT)
+        .set("Authorization", key)
+        .send_json(json!({ "query": query, "variables": variables }));
+    let mut body: Value = match response {
+        Ok(response) => response.into_json().map_err(|e| e.to_string())?,
+        // GraphQL errors come back as 400 with the messages in the body
+        Err(ureq::Error::Status(_, response)) => response.into_json().map_err(|e| e.to_string())?,
+        Err(e) => return Err(format!("Linear request failed: {}", e)),
+    };
+    if let Some(errors) = body["errors"].as_array().filter(|e| !e.is_empty()) {
+        let messages: Vec<&str> = errors
+            .iter()
+            .filter_map(|e| e["message"].as_str())
+            .collect();
+        return Err(format!("Linear request failed: {}", messages.join("; ")));
+    }
+    Ok(body["data"].take())
+}
+
+/// Run a GraphQL query with the stored key, returning its `data`
+fn graphql(query: &str, variables: Value) -> Result<Value, String> {
+    let key = api_key()?.ok_or("Linear is not connected")?;
+    request(&key, query, variables)
+}
+
+/// Every issue matching `filter`, following pages
+fn issues(filter: Value) -> Result<Vec<Value>, String> {
+    let query = format!(
+        "query($filter: IssueFilter, $after: String) {{ issues(first: {}, after: $after, filter: $filter) {{ nodes {{ {} }} pageInfo {{ hasNextPage endCursor }} }} }}",
+        PAGE_SIZE, ISSUE_FIELDS
+    );
+    let mut nodes = Vec::new();
+    let mut after: Option<String> = None;
+    loop {
+        let data = graphql(&query, json!({ "filter": filter, "after": after }))?;
+        let page = &data["issues"];
+        nodes.extend(page["nodes"].as_array().cloned().unwrap_or_default());
+        match page["pageInfo"]["endCursor"].as_str() {
+            Some(cursor) if page["pageInfo"]["hasNextPage"] == true => {
+                after = Some(cursor.to_string())
+            }
+            _ => return Ok(nodes),
+        }
+    }
+}
+
+/// The task state for a workflow state: mapped by name, else by its type
+fn state_for(state: &Value, settings: &LinearSettings) -> String {
+    let name = state["name"].as_str().unwrap_or_default();
+    if let Some((task_state, _)) = settings
+        .status_map
+        .iter()
+        .find(|(_, mapped)| mapped.eq_ignore_ascii_case(name))
+    {
+        return task_state.clone();
+    }
+    match state["type"].as_str() {
+        Some("started") => "in_progress",
+        Some("completed" | "canceled") => "done",
+        _ => "backlog",
+    }
+    .to_string()
+}
+
+fn type_for(task_state: &str) -> &'static str {
+    match task_state {
+        "in_progress" | "review" => "started",
+        "done" => "completed",
+        _ => "unstarted",
+    }
+}
+
+/// The team's workflow state a task in `task_state` belongs in
+fn workflow_state(
+    team_id: &str,
+    task_state: &str,
+    settings: &LinearSettings,
+) -> Result<Value, String> {
+    let data = graphql(
+        "query($team: String!) { team(id: $team) { states { nodes { id name type position } } } }",
+        json!({ "team": team_id }),
+    )?;
+    let mut states = data["team"]["states"]["nodes"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    states.sort_by(|a, b| {
+        let position = |s: &Value| s["position"].as_f64().unwrap_or_default();
+        position(a).total_cmp(&position(b))
+    });
+    let target = settings.status_map.get(task_state);
+    states
+        .into_iter()
+        .find(|s| match target {
+            Some(name) => s["name"]
+                .as_str()
+                .is_some_and(|n| n.eq_ignore_ascii_case(name)),
+            None => s["type"] == type_for(task_state),
+        })
+        .ok_or_else(|| {
+            format!(
+                "The team has no workflow state for {}",
+                target.map(String::as_str).unwrap_or(task_state)
+            )
+        })
+}
+
+fn task_fields(issue: &Value) -> (String, String) {
+    let identifier = issue["identifier"].as_str().unwrap_or_default();
+    let title = format!(
+        "[{}] {}",
+        identifier,
+        issue["title"].as_str().unwrap_or_default()
+    );
+    let description = format!(
+        "{}\n\nLinear: {}",
+        issue["description"].as_str().unwrap_or_default(),
+        issue["url"].as_str().unwrap_or_default()
+    );
+    (title, description.trim().to_string())
+}
+
+fn links() -> Result<Vec<Link>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT task_id, issue_id, identifier, team_id, state_id, task_state FROM linear_issues",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Link {
+                task_id: row.get(0)?,
+                issue_id: row.get(1)?,
+                identifier: row.get(2)?,
+                team_id: row.get(3)?,
+                state_id: row.get(4)?,
+                task_state: row.get(5)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+fn save_link(task_id: &str, issue: &Value, task_state: &str) -> Result<(), String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO linear_issues (task_id, issue_id, identifier, team_id, state_id, task_state, synced_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                task_id,
+                issue["id"].as_str(),
+                issue["identifier"].as_str(),
+                issue["team"]["id"].as_str(),
+                issue["state"]["id"].as_str(),
+                task_state,
+                store::now_ms()
+            ],
+        )
+        .map(|_| ())
+    })
+}
+
+/// Make tasks for `issues` that aren't linked yet
+fn import(issues: &[Value], project_id: &str, report: &mut LinearSyncReport) -> Result<(), String> {
+    let settings = config::load().linear;
+    let linked: Vec<String> = links()?.into_iter().map(|link| link.issue_id).collect();
+    for issue in issues {
+        if linked
+            .iter()
+            .any(|id| Some(id.as_str()) == issue["id"].as_str())
+        {
+            continue;
+        }
+        let identifier = issue["identifier"].as_str().unwrap_or_default().to_string();
+        let (title, description) = task_fields(issue);
+        let state = state_for(&issue["state"], &settings);
+        let result = store::create_task(NewTask {
+            project_id: project_id.to_string(),
+            title,
+            description: Some(description),
+            state: Some(state.clone()),
+        })
+        .map_err(String::from)
+        .and_then(|task| save_link(&task.id, issue, &state));
+        match result {
+            Ok(()) => report.imported.push(identifier),
+            Err(e) => report.errors.push(format!("{}: {}", identifier, e)),
+        }
+    }
+    Ok(())
+}
+
+/// Open issues of `team_id`
+fn team_issues(team_id: &str) -> Result<Vec<Value>, String> {
+    issues(json!({
+        "team": { "id": { "eq": team_id } },
+        "state": { "type": { "nin": ["completed", "canceled"] } },
+    }))
+}
+
+fn sync() -> Result<LinearSyncReport, String> {
+    let settings = config::load().linear;
+    let mut report = LinearSyncReport::default();
+    let links = links()?;
+
+    let mut current: BTreeMap<String, Value> = BTreeMap::new();
+    let ids: Vec<&str> = links.iter().map(|link| link.issue_id.as_str()).collect();
+    for chunk in ids.chunks(PAGE_SIZE) {
+        for issue in issues(json!({ "id": { "in": chunk } }))? {
+            let id = issue["id"].as_str().unwrap_or_default().to_string();
+            current.insert(id, issue);
+        }
+    }
+
+    for link in links {
+        let Some(task) = store::with_conn(|conn| store::get_task(conn, &link.task_id))? else {
+            continue;
+        };
+        let Some(issue) = current.get(&link.issue_id) else {
+            continue;
+        };
+        let issue_state = issue["state"]["id"].as_str().unwrap_or_default();
+        // A state changed here wins over the issue keeping its old one
+        let result = if task.state != link.task_state {
+            workflow_state(&link.team_id, &task.state, &settings).and_then(|state| {
+                graphql(
+                    "mutation($id: String!, $state: String!) { issueUpdate(id: $id, input: { stateId: $state }) { success } }",
+                    json!({ "id": link.issue_id, "state": state["id"] }),
+                )?;
+                let mut issue = issue.clone();
+                issue["state"] = state;
+                save_link(&task.id, &issue, &task.state)?;
+                report.pushed.push(link.identifier.clone());
+                Ok(())
+            })
+        } else if issue_state != link.state_id {
+            let state = state_for(&issue["state"], &settings);
+            let pull = if state == task.state {
+                Ok(())
+            } else {
+                store::update_task(
+                    task.id.clone(),
+                    TaskUpdate {
+                        title: None,
+                        description: None,
+                        state: Some(state.clone()),
+                    },
+                )
+                .map(|_| report.pulled.push(link.identifier.clone()))
+                .map_err(String::from)
+            };
+            pull.and_then(|_| save_link(&task.id, issue, &state))
+        } else {
+            Ok(())
+        };
+        if let Err(e) = result {
+            report.errors.push(format!("{}: {}", link.identifier, e));
+        }
+    }
+
+    for (team_id, project_id) in &settings.teams {
+        match team_issues(team_id) {
+            Ok(issues) => import(&issues, project_id, &mut report)?,
+            Err(e) => report.errors.push(e),
+        }
+    }
+    Ok(report)
+}
+
+fn exclusive<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    if SYNCING.swap(true, Ordering::SeqCst) {
+        return Err("A Linear sync is already running".to_string());
+    }
+    let result = f();
+    SYNCING.store(false, Ordering::SeqCst);
+    result
+}
+
+fn format_duration(ms: i64) -> String {
+    let secs = ms / 1000;
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {}s", m, s),
+        (h, m, _) => format!("{}h {}m", h, m),
+    }
+}
+
+fn run_comment(record: &RunRecord) -> String {
+    let mut body = format!("**Agent run finished:** {}\n\n", record.title);
+    if let Some(duration) = record.duration_ms {
+        body.push_str(&format!("- Duration: {}\n", format_duration(duration)));
+    }
+    if let Some(model) = &record.model {
+        body.push_str(&format!("- Model: {}\n", model));
+    }
+    body.push_str(&format!(
+        "- Tokens: {} in / {} out (${:.2})\n",
+        record.input_tokens, record.output_tokens, record.cost_usd
+    ));
+    if !record.files_changed.is_empty() {
+        body.push_str(&format!(
+            "- Files changed: {} (+{} −{})\n",
+            record.files_changed.len(),
+            record.additions,
+            record.deletions
+        ));
+        for file in record.files_changed.iter().take(COMMENT_FILES) {
+            body.push_str(&format!("  - `{}`\n", file));
+        }
+        if record.files_changed.len() > COMMENT_FILES {
+            body.push_str(&format!(
+                "  - and {} more\n",
+                record.files_changed.len() - COMMENT_FILES
+            ));
+        }
+    }
+    body
+}
+
+/// Comment on the issue of a run's task, if it's linked; called once the run is recorded
+pub fn run_recorded(record: &RunRecord) {
+    if record.state != RunState::Done || !config::load().linear.comment_runs {
+        return;
+    }
+    let issue_id: Option<String> = store::with_conn(|conn| {
+        conn.query_row(
+            "SELECT issue_id FROM linear_issues WHERE task_id = ?1",
+            params![record.task_id],
+            |row| row.get(0),
+        )
+        .optional()
+    })
+    .ok()
+    .flatten();
+    let Some(issue_id) = issue_id else {
+        return;
+    };
+    let result = graphql(
+        "mutation($issue: String!, $body: String!) { commentCreate(input: { issueId: $issue, body: $body }) { success } }",
+        json!({ "issue": issue_id, "body": run_comment(record) }),
+    );
+    if let Err(e) = result {
+        eprintln!(
+            "[Claude PM] Failed to comment on Linear issue for run {}: {}",
+            record.id, e
+        );
+    }
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        power::wait(SYNC_INTERVAL);
+        let settings = config::load().linear;
+        let due = settings.enabled
+            && !settings.teams.is_empty()
+            && connectivity::is_online()
+            && api_key().ok().flatten().is_some();
+        if !due {
+            continue;
+        }
+        match exclusive(sync) {
+            Ok(report) if !report.is_empty() => {
+                let _ = app.emit("linear-synced", &report);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("[Claude PM] Linear sync failed: {}", e),
+        }
+    });
+}
+
+/// Store the API key after checking it works; an empty key disconnects
+#[tauri::command]
+pub async fn set_linear_api_key(key: String) -> Result<LinearStatus, Error> {
+    let key = key.trim().to_string();
+    if key.is_empty() {
+        secrets::delete(KEYCHAIN_ACCOUNT)?;
+        return get_linear_status().await;
+    }
+    let check = key.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        request(&check, "query { viewer { id } }", json!({}))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| Error::InvalidInput(format!("Linear rejected the API key: {}", e)))?;
+    secrets::set(KEYCHAIN_ACCOUNT, &key)?;
+    get_linear_status().await
+}
+
+#[tauri::command]
+pub async fn get_linear_status() -> Result<LinearStatus, Error> {
+    let linked_issues: i64 = store::with_conn(|conn| {
+        conn.query_row("SELECT COUNT(*) FROM linear_issues", [], |row| row.get(0))
+    })?;
+    let linked_teams = config::load().linear.teams.len();
+    let connected = api_key()?.is_some();
+    let viewer = if connected {
+        tauri::async_runtime::spawn_blocking(|| {
+            graphql("query { viewer { name } organization { name } }", json!({}))
+        })
+        .await
+        .map_err(|e| e.to_string())?
+        .ok()
+    } else {
+        None
+    };
+    let text = |value: &Value| value.as_str().map(str::to_string);
+    Ok(LinearStatus {
+        connected,
+        workspace: viewer
+            .as_ref()
+            .and_then(|v| text(&v["organization"]["name"])),
+        user: viewer.as_ref().and_then(|v| text(&v["viewer"]["name"])),
+        linked_teams,
+        linked_issues: linked_issues as usize,
+    })
+}
+
+#[tauri::command]
+pub async fn list_linear_teams() -> Result<Vec<LinearTeam>, Error> {
+    let data = tauri::async_runtime::spawn_blocking(|| {
+        graphql("query { teams { nodes { id key name } } }", json!({}))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let linked = config::load().linear.teams;
+    Ok(data["teams"]["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|team| {
+            let id = team["id"].as_str().unwrap_or_default().to_string();
+            LinearTeam {
+                key: team["key"].as_str().unwrap_or_default().to_string(),
+                name: team["name"].as_str().unwrap_or_default().to_string(),
+                project_id: linked.get(&id).cloned(),
+                id,
+            }
+        })
+        .collect())
+}
+
+/// Link a team to a project and import its open issues into it
+#[tauri::command]
+pub async fn import_linear_team(
+    team_id: String,
+    project_id: String,
+) -> Result<LinearSyncReport, Error> {
+    store::with_conn(|conn| store::get_project(conn, &project_id))?
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project_id)))?;
+    Ok(tauri::async_runtime::spawn_blocking(move || {
+        exclusive(|| {
+            let issues = team_issues(&team_id)?;
+            config::update(|c| {
+                c.linear.teams.insert(team_id.clone(), project_id.clone());
+            })?;
+            let mut report = LinearSyncReport::default();
+            import(&issues, &project_id, &mut report)?;
+            Ok(report)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??)
+}
+
+/// Stop importing a team's issues; imported tasks stay linked
+#[tauri::command]
+pub fn unlink_linear_team(team_id: String) -> Result<(), Error> {
+    config::update(|c| {
+        c.linear.teams.remove(&team_id);
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_linear_settings(settings: LinearSettings) -> Result<(), Error> {
+    for state in settings.status_map.keys() {
+        store::validate_state(state)?;
+    }
+    config::update(|c| c.linear = settings)?;
+    Ok(())
+}
+
+/// Push and pull statuses and import new issues now
+#[tauri::command]
+pub async fn sync_linear() -> Result<LinearSyncReport, Error> {
+    Ok(tauri::async_runtime::spawn_blocking(|| exclusive(sync))
+        .await
+        .map_err(|e| e.to_string())??)
+}
@@ -0,0 +1,283 @@
+//! Local REST API for scripts, git hooks and other tools on this machine
+//!
+//! Served by the app itself on `http://127.0.0.1:4855`, so it works whether or not the
+//! Node server is up. Every request needs `Authorization: Bearer <token>`; the API only
+//! runs while a token is configured. Bodies and responses are JSON, and errors are the
+//! same `{ code, message, details }` the commands return.
+//!
+//! - `GET /status` — app version, server status and the agent run queue summary
+//! - `GET /projects`
+//! - `GET /tasks?project=<id>&state=<state>`
+//! - `POST /tasks` with `{ projectId, title, description?, state? }`
+//! - `PATCH /tasks/<id>` with any of `{ title, description, state }`
+//! - `GET /runs`
+//! - `POST /tasks/<id>/runs` with `{ prompt?, model? }` to queue an agent run
+//!
+//! e.g. `curl -s -H "Authorization: Bearer $TOKEN" -d '{"projectId":"…","title":"Fix CI"}'
+//! http://127.0.0.1:4855/tasks`
+//!
+//! Events:
+//! - `local-api-task` with the [`Task`] created or updated through the API
+
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use tauri::{AppHandle, Emitter};
+use tiny_http::{Header, Method, Request, Response, Server};
+use url::form_urlencoded;
+
+use crate::error::Error;
+use crate::orchestrator::{self, RunState};
+use crate::store::{self, NewTask, Task, TaskUpdate};
+use crate::{app_lock, auth, config};
+
+pub const API_PORT: u16 = 4855;
+/// Request bodies past this are refused
+const MAX_BODY_BYTES: u64 = 1024 * 1024;
+
+static SERVER: Mutex<Option<Arc<Server>>> = Mutex::new(None);
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalApiStatus {
+    pub enabled: bool,
+    pub url: String,
+    pub token: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppStatus {
+    version: &'static str,
+    server: String,
+    queued_runs: usize,
+    active_runs: usize,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RunRequest {
+    prompt: Option<String>,
+    model: Option<String>,
+}
+
+fn status_code(error: &Error) -> u16 {
+    match error {
+        Error::NotFound(_) => 404,
+        Error::InvalidInput(_) => 400,
+        Error::Unauthorized(_) => 401,
+        Error::Locked => 423,
+        Error::Unsupported(_) => 501,
+        _ => 500,
+    }
+}
+
+fn json_response<T: Serialize>(status: u16, body: &T) -> Response<Cursor<Vec<u8>>> {
+    let header = Header::from_bytes("Content-Type", "application/json").expect("static header");
+    Response::from_string(serde_json::to_string(body).unwrap_or_default())
+        .with_status_code(status)
+        .with_header(header)
+}
+
+fn body<T: serde::de::DeserializeOwned>(request: &mut Request) -> Result<T, Error> {
+    if request
+        .body_length()
+        .is_some_and(|len| len as u64 > MAX_BODY_BYTES)
+    {
+        return Err(Error::InvalidInput("Request body is too large".to_string()));
+    }
+    let mut body = String::new();
+    request
+        .as_reader()
+        .take(MAX_BODY_BYTES)
+        .read_to_string(&mut body)
+        .map_err(|e| Error::InvalidInput(format!("Unreadable body: {}", e)))?;
+    serde_json::from_str(if body.trim().is_empty() { "{}" } else { &body })
+        .map_err(|e| Error::InvalidInput(format!("Invalid JSON body: {}", e)))
+}
+
+fn query(url: &str, key: &str) -> Option<String> {
+    form_urlencoded::parse(url.split_once('?')?.1.as_bytes())
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.into_owned())
+        .filter(|v| !v.is_empty())
+}
+
+fn app_status() -> Result<AppStatus, Error> {
+    let queue = orchestrator::list_agent_runs()?;
+    Ok(AppStatus {
+        version: env!("CARGO_PKG_VERSION"),
+        server: crate::get_server_status()?,
+        queued_runs: queue
+            .runs
+            .iter()
+            .filter(|r| r.state == RunState::Queued)
+            .count(),
+        active_runs: queue.runs.iter().filter(|r| r.state.is_active()).count(),
+    })
+}
+
+fn changed(task: &Task) {
+    if let Some(app) = APP.get() {
+        let _ = app.emit("local-api-task", task);
+    }
+}
+
+/// Route an authenticated request; `Ok` carries the status code and JSON body
+fn route(request: &mut Request) -> Result<(u16, serde_json::Value), Error> {
+    let url = request.url().to_string();
+    let path = url.split('?').next().unwrap_or("");
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let to_json = |value: Result<serde_json::Value, serde_json::Error>| {
+        value.map_err(|e| Error::Internal(e.to_string()))
+    };
+    match (request.method().clone(), segments.as_slice()) {
+        (Method::Get, ["status"]) => Ok((200, to_json(serde_json::to_value(app_status()?))?)),
+        (Method::Get, ["projects"]) => {
+            Ok((200, to_json(serde_json::to_value(store::list_projects()?))?))
+        }
+        (Method::Get, ["tasks"]) => {
+            let tasks = store::list_tasks(query(&url, "project"), query(&url, "state"))?;
+            Ok((200, to_json(serde_json::to_value(tasks))?))
+        }
+        (Method::Post, ["tasks"]) => {
+            let task = store::create_task(body::<NewTask>(request)?)?;
+            changed(&task);
+            Ok((201, to_json(serde_json::to_value(task))?))
+        }
+        (Method::Patch, ["tasks", id]) => {
+            let task = store::update_task(id.to_string(), body::<TaskUpdate>(request)?)?;
+            changed(&task);
+            Ok((200, to_json(serde_json::to_value(task))?))
+        }
+        (Method::Get, ["runs"]) => Ok((
+            200,
+            to_json(serde_json::to_value(orchestrator::list_agent_runs()?))?,
+        )),
+        (Method::Post, ["tasks", id, "runs"]) => {
+            let run = body::<RunRequest>(request)?;
+            let run = orchestrator::enqueue_agent_run(id.to_string(), run.prompt, run.model)?;
+            Ok((202, to_json(serde_json::to_value(run))?))
+        }
+        _ => Err(Error::NotFound(format!(
+            "No route for {} {}",
+            request.method(),
+            path
+        ))),
+    }
+}
+
+fn handle(token: &str, mut request: Request) {
+    let authorized = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|given| auth::same_token(given.trim(), token));
+    if !authorized {
+        let error = Error::Unauthorized("Missing or invalid API token".to_string());
+        let _ = request.respond(json_response(status_code(&error), &error));
+        return;
+    }
+    let response = match route(&mut request) {
+        Ok((status, body)) => json_response(status, &body),
+        Err(e) => {
+            if status_code(&e) == 500 {
+                eprintln!("[Claude PM] Local API request failed: {}", e);
+            }
+            json_response(status_code(&e), &e)
+        }
+    };
+    let _ = request.respond(response);
+}
+
+fn url() -> String {
+    format!("http://127.0.0.1:{}", API_PORT)
+}
+
+/// Serve the API if a token is configured; safe to call more than once
+pub fn start(app: AppHandle) {
+    let _ = APP.set(app);
+    let Some(token) = config::load().local_api_token else {
+        return;
+    };
+    let Ok(mut running) = SERVER.lock() else {
+        return;
+    };
+    if running.is_some() {
+        return;
+    }
+    let server = match Server::http(("127.0.0.1", API_PORT)) {
+        Ok(server) => Arc::new(server),
+        Err(e) => {
+            eprintln!(
+                "[Claude PM] Failed to start local API on port {}: {}",
+                API_PORT, e
+            );
+            return;
+        }
+    };
+    println!("[Claude PM] Local API listening on 127.0.0.1:{}", API_PORT);
+    *running = Some(server.clone());
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            handle(&token, request);
+        }
+    });
+}
+
+fn stop() {
+    if let Some(server) = SERVER.lock().ok().and_then(|mut server| server.take()) {
+        server.unblock();
+    }
+}
+
+#[tauri::command]
+pub fn get_local_api_status() -> Result<LocalApiStatus, Error> {
+    app_lock::ensure_unlocked()?;
+    let token = config::load().local_api_token;
+    Ok(LocalApiStatus {
+        enabled: token.is_some(),
+        url: url(),
+        token,
+    })
+}
+
+/// Turn the API on or off; turning it on again keeps the same token unless `rotate` is set
+#[tauri::command]
+pub fn set_local_api_enabled(
+    app: AppHandle,
+    enabled: bool,
+    rotate: Option<bool>,
+) -> Result<LocalApiStatus, Error> {
+    app_lock::ensure_unlocked()?;
+    stop();
+    if !enabled {
+        config::update(|c| c.local_api_token = None)?;
+        return get_local_api_status();
+    }
+    config::update(|c| {
+        if rotate.unwrap_or(false) {
+            c.local_api_token = None;
+        }
+        c.local_api_token.get_or_insert_with(auth::random_hex);
+    })?;
+    start(app);
+    get_local_api_status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_query_values() {
+        let url = "/v1/tasks?project=my%20app&state=in+progress&empty=";
+        assert_eq!(query(url, "project").as_deref(), Some("my app"));
+        assert_eq!(query(url, "state").as_deref(), Some("in progress"));
+        assert_eq!(query(url, "empty"), None);
+        assert_eq!(query("/v1/tasks", "project"), None);
+    }
+}
@@ -0,0 +1,305 @@
+//! Embedded MCP (Model Context Protocol) server for agents launched by Claude PM
+//!
+//! Speaks JSON-RPC over the streamable HTTP transport at `http://127.0.0.1:4851/mcp`.
+//! Only the tools listed in `tools()` are exposed; each maps onto an existing desktop
+//! capability so agents get a narrow, auditable interface.
+//!
+//! Every request needs `Authorization: Bearer <token>`, since the tools reach the
+//! clipboard and window focus; `get_mcp_server_entry` gives the entry to add to Claude's
+//! config with the token in its headers.
+
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde_json::{json, Value};
+use std::thread;
+use tauri::AppHandle;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tiny_http::{Header, Method, Request, Response, Server};
+use url::form_urlencoded;
+
+use crate::app_activation::{self, ActivateTarget};
+use crate::error::Error;
+use crate::notifications::{self, NotificationRequest};
+use crate::{app_lock, auth, config, server_api};
+
+pub const MCP_PORT: u16 = 4851;
+const PROTOCOL_VERSION: &str = "2025-03-26";
+
+/// JSON-RPC error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const PARSE_ERROR: i64 = -32700;
+
+/// The configured token, generated on first use so agent configs keep working across launches
+fn token() -> Result<String, String> {
+    if let Some(token) = config::load().mcp_token {
+        return Ok(token);
+    }
+    let token = auth::random_hex();
+    config::update(|c| c.mcp_token = Some(token.clone()))?;
+    Ok(token)
+}
+
+pub fn start(app: AppHandle) {
+    let token = match token() {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("[Claude PM] MCP server not started: {}", e);
+            return;
+        }
+    };
+    let server = match Server::http(("127.0.0.1", MCP_PORT)) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!(
+                "[Claude PM] Failed to start MCP server on port {}: {}",
+                MCP_PORT, e
+            );
+            return;
+        }
+    };
+    println!("[Claude PM] MCP server listening on 127.0.0.1:{}", MCP_PORT);
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let app = app.clone();
+            let token = token.clone();
+            thread::spawn(move || handle_http(&app, &token, request));
+        }
+    });
+}
+
+/// Reject browser-originated requests (DNS rebinding); agents don't send an Origin
+fn is_allowed_origin(request: &Request) -> bool {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Origin"))
+        .is_none_or(|h| {
+            let origin = h.value.as_str();
+            origin.starts_with("http://localhost") || origin.starts_with("http://127.0.0.1")
+        })
+}
+
+fn json_response(body: &Value) -> Response<std::io::Cursor<Vec<u8>>> {
+    let header = Header::from_bytes("Content-Type", "application/json").expect("static header");
+    Response::from_string(body.to_string()).with_header(header)
+}
+
+fn is_authorized(request: &Request, token: &str) -> bool {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .is_some_and(|given| auth::same_token(given.trim(), token))
+}
+
+fn handle_http(app: &AppHandle, token: &str, mut request: Request) {
+    if request.url().split('?').next() != Some("/mcp") {
+        let _ = request.respond(Response::empty(404));
+        return;
+    }
+    if *request.method() != Method::Post {
+        let _ = request.respond(Response::empty(405));
+        return;
+    }
+    if !is_allowed_origin(&request) {
+        let _ = request.respond(Response::empty(403));
+        return;
+    }
+    if !is_authorized(&request, token) {
+        let _ = request.respond(Response::empty(401));
+        return;
+    }
+
+    let mut body = String::new();
+    if request.as_reader().read_to_string(&mut body).is_err() {
+        let _ = request.respond(Response::empty(400));
+        return;
+    }
+
+    let message: Value = match serde_json::from_str(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            let _ = request.respond(json_response(&error(
+                Value::Null,
+                PARSE_ERROR,
+                &e.to_string(),
+            )));
+            return;
+        }
+    };
+
+    let reply = match message {
+        Value::Array(batch) => {
+            let replies: Vec<Value> = batch.iter().filter_map(|m| dispatch(app, m)).collect();
+            (!replies.is_empty()).then_some(Value::Array(replies))
+        }
+        message => dispatch(app, &message),
+    };
+
+    let _ = match reply {
+        Some(reply) => request.respond(json_response(&reply)),
+        // Notifications and responses get 202 with no body
+        None => request.respond(Response::empty(202)),
+    };
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Handle one JSON-RPC message; `None` for notifications
+fn dispatch(app: &AppHandle, message: &Value) -> Option<Value> {
+    let id = message.get("id").cloned()?;
+    let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "claude-pm-desktop", "version": env!("CARGO_PKG_VERSION") },
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => {
+            let Some(name) = params.get("name").and_then(Value::as_str) else {
+                return Some(error(id, INVALID_PARAMS, "Missing tool name"));
+            };
+            let args = params.get("arguments").cloned().unwrap_or(json!({}));
+            match call_tool(app, name, &args) {
+                Ok(text) => json!({ "content": [{ "type": "text", "text": text }] }),
+                Err(e) => json!({ "content": [{ "type": "text", "text": e }], "isError": true }),
+            }
+        }
+        _ => {
+            return Some(error(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("Unknown method: {}", method),
+            ))
+        }
+    };
+
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "notify_user",
+            "description": "Show a desktop notification to the user",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "title": { "type": "string" },
+                    "body": { "type": "string" }
+                },
+                "required": ["title", "body"]
+            }
+        },
+        {
+            "name": "focus_app",
+            "description": "Bring an application, or one of its windows by title, to the front",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "appName": { "type": "string" },
+                    "bundleId": { "type": "string" },
+                    "windowTitle": { "type": "string" }
+                }
+            }
+        },
+        {
+            "name": "read_clipboard",
+            "description": "Read the current text contents of the clipboard",
+            "inputSchema": { "type": "object", "properties": {} }
+        },
+        {
+            "name": "list_tasks",
+            "description": "List tickets for a Claude PM project",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "projectId": { "type": "string" },
+                    "state": { "type": "string", "enum": ["backlog", "in_progress", "review", "done"] }
+                },
+                "required": ["projectId"]
+            }
+        }
+    ])
+}
+
+fn string_arg<'a>(args: &'a Value, name: &str) -> Result<&'a str, String> {
+    args.get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("Missing argument: {}", name))
+}
+
+fn call_tool(app: &AppHandle, name: &str, args: &Value) -> Result<String, String> {
+    match name {
+        "notify_user" => {
+            notifications::notify(
+                app,
+                NotificationRequest {
+                    title: string_arg(args, "title")?.to_string(),
+                    body: string_arg(args, "body")?.to_string(),
+                    category: Some("agent".to_string()),
+                    ..Default::default()
+                },
+            );
+            Ok("Notification sent".to_string())
+        }
+        "focus_app" => {
+            let app_name = args.get("appName").and_then(Value::as_str);
+            let target = ActivateTarget {
+                bundle_id: args
+                    .get("bundleId")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                window_title: args
+                    .get("windowTitle")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            };
+            let label = target
+                .bundle_id
+                .as_deref()
+                .or(app_name)
+                .unwrap_or_default()
+                .to_string();
+            app_activation::activate_app(app_name.map(str::to_string), Some(target))?;
+            Ok(format!("Focused {}", label))
+        }
+        "read_clipboard" => app
+            .clipboard()
+            .read_text()
+            .map_err(|e| format!("Failed to read clipboard: {}", e)),
+        "list_tasks" => {
+            let project_id = string_arg(args, "projectId")?;
+            let mut path = format!(
+                "/api/projects/{}/tickets?sync=false&limit=100",
+                utf8_percent_encode(project_id, NON_ALPHANUMERIC)
+            );
+            if let Some(state) = args.get("state").and_then(Value::as_str) {
+                path.push_str("&state=");
+                path.extend(form_urlencoded::byte_serialize(state.as_bytes()));
+            }
+            let tickets: Value = server_api::get_json(&path)?;
+            serde_json::to_string_pretty(&tickets["data"]).map_err(|e| e.to_string())
+        }
+        _ => Err(format!("Unknown tool: {}", name)),
+    }
+}
+
+/// The `mcpServers` entry for Claude's config, e.g. for `add_mcp_server`
+#[tauri::command]
+pub fn get_mcp_server_entry() -> Result<Value, Error> {
+    app_lock::ensure_unlocked()?;
+    Ok(json!({
+        "type": "http",
+        "url": format!("http://127.0.0.1:{}/mcp", MCP_PORT),
+        "headers": { "Authorization": format!("Bearer {}", token()?) },
+    }))
+}
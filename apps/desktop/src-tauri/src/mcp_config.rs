@@ -0,0 +1,163 @@
+//! Managing MCP server entries in the user's Claude config (`~/.claude.json`)
+//!
+//! Global servers live under `mcpServers`; per-project ones under
+//! `projects.<absolute path>.mcpServers`. Everything else in the file is preserved.
+
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+
+use crate::error::Error;
+use crate::json_file;
+
+const BACKUP_KIND: &str = "claude-json";
+
+/// `~/.claude.json`, or the one in the active Claude profile's config directory
+pub fn claude_json_path() -> Result<PathBuf, String> {
+    if let Some(dir) = crate::claude_profiles::active_config_dir() {
+        return Ok(dir.join(".claude.json"));
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".claude.json"))
+        .ok_or_else(|| "Could not determine home directory".to_string())
+}
+
+/// Check an entry has the shape Claude expects for its transport
+fn validate(server: &Value) -> Result<(), String> {
+    let entry = server
+        .as_object()
+        .ok_or("MCP server entry must be an object")?;
+    let transport = entry.get("type").and_then(Value::as_str).unwrap_or("stdio");
+
+    match transport {
+        "stdio" => {
+            entry
+                .get("command")
+                .and_then(Value::as_str)
+                .filter(|c| !c.is_empty())
+                .ok_or("stdio servers need a \"command\"")?;
+            if let Some(args) = entry.get("args") {
+                let valid = args
+                    .as_array()
+                    .is_some_and(|a| a.iter().all(Value::is_string));
+                if !valid {
+                    return Err("\"args\" must be an array of strings".to_string());
+                }
+            }
+        }
+        "http" | "sse" => {
+            entry
+                .get("url")
+                .and_then(Value::as_str)
+                .filter(|u| u.starts_with("http://") || u.starts_with("https://"))
+                .ok_or("http/sse servers need an http(s) \"url\"")?;
+        }
+        other => return Err(format!("Unknown MCP transport: {}", other)),
+    }
+
+    for field in ["env", "headers"] {
+        if let Some(map) = entry.get(field) {
+            let valid = map
+                .as_object()
+                .is_some_and(|m| m.values().all(Value::is_string));
+            if !valid {
+                return Err(format!("\"{}\" must map names to strings", field));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The `mcpServers` object for the given scope, created if missing
+fn servers_mut<'a>(
+    config: &'a mut Value,
+    project: Option<&str>,
+) -> Result<&'a mut Map<String, Value>, String> {
+    let root = config
+        .as_object_mut()
+        .ok_or("~/.claude.json is not a JSON object")?;
+    let scope = match project {
+        Some(project) => root
+            .entry("projects")
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or("\"projects\" is not an object")?
+            .entry(project)
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or("Project entry is not an object")?,
+        None => root,
+    };
+    scope
+        .entry("mcpServers")
+        .or_insert_with(|| Value::Object(Map::new()))
+        .as_object_mut()
+        .ok_or_else(|| "\"mcpServers\" is not an object".to_string())
+}
+
+fn modify(
+    project: Option<&str>,
+    f: impl FnOnce(&mut Map<String, Value>) -> Result<(), String>,
+) -> Result<(), String> {
+    let path = claude_json_path()?;
+    let mut config = json_file::read(&path)?;
+    f(servers_mut(&mut config, project)?)?;
+    json_file::write(&path, BACKUP_KIND, &config)
+}
+
+/// MCP servers for a project path, or the global ones when `project` is omitted
+#[tauri::command]
+pub fn list_mcp_servers(project: Option<String>) -> Result<Map<String, Value>, Error> {
+    let config = json_file::read(&claude_json_path()?)?;
+    let servers = match project {
+        Some(project) => config
+            .get("projects")
+            .and_then(|projects| projects.get(&project))
+            .and_then(|entry| entry.get("mcpServers")),
+        None => config.get("mcpServers"),
+    };
+    Ok(servers
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn add_mcp_server(name: String, server: Value, project: Option<String>) -> Result<(), Error> {
+    validate(&server)?;
+    modify(project.as_deref(), |servers| {
+        if servers.contains_key(&name) {
+            return Err(format!("MCP server already exists: {}", name));
+        }
+        servers.insert(name, server);
+        Ok(())
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn update_mcp_server(
+    name: String,
+    server: Value,
+    project: Option<String>,
+) -> Result<(), Error> {
+    validate(&server)?;
+    modify(project.as_deref(), |servers| {
+        let entry = servers
+            .get_mut(&name)
+            .ok_or_else(|| format!("MCP server not found: {}", name))?;
+        *entry = server;
+        Ok(())
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn remove_mcp_server(name: String, project: Option<String>) -> Result<(), Error> {
+    modify(project.as_deref(), |servers| {
+        servers
+            .shift_remove(&name)
+            .map(|_| ())
+            .ok_or_else(|| format!("MCP server not found: {}", name))
+    })
+    .map_err(Error::from)
+}
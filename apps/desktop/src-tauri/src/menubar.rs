@@ -0,0 +1,251 @@
+//! Menu bar extra: a tray icon with a small popover summarising agent activity
+//!
+//! A background thread polls the server and emits `menubar-summary` every few seconds,
+//! independent of whether the main window is open.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{
+    AppHandle, Emitter, Manager, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+    WindowEvent,
+};
+
+use crate::theme::Mode;
+use crate::{is_server_running, power, server_api, server_port};
+
+pub const MENUBAR_WINDOW: &str = "menubar";
+pub const TRAY_ID: &str = "menubar";
+const POPOVER_SIZE: (f64, f64) = (320.0, 380.0);
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub id: String,
+    pub title: String,
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MenubarSummary {
+    pub server_running: bool,
+    pub running_agents: usize,
+    pub waiting_for_approval: usize,
+    pub sessions: Vec<SessionSummary>,
+}
+
+#[derive(Deserialize)]
+struct ApiNamed {
+    name: Option<String>,
+    title: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiSession {
+    id: String,
+    pane_name: Option<String>,
+    project: Option<ApiNamed>,
+    ticket: Option<ApiNamed>,
+}
+
+#[derive(Deserialize)]
+struct ApiNotification {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Deserialize)]
+struct ApiNotificationList {
+    data: Vec<ApiNotification>,
+}
+
+static LATEST: Mutex<Option<MenubarSummary>> = Mutex::new(None);
+
+fn fetch_summary() -> MenubarSummary {
+    if !is_server_running(server_port()) {
+        return MenubarSummary::default();
+    }
+
+    let sessions: Vec<ApiSession> =
+        server_api::get_json("/api/sessions?status=running").unwrap_or_default();
+    let waiting = server_api::get_json::<ApiNotificationList>("/api/notifications")
+        .map(|list| {
+            list.data
+                .iter()
+                .filter(|n| n.kind == "waiting_input")
+                .count()
+        })
+        .unwrap_or(0);
+
+    MenubarSummary {
+        server_running: true,
+        running_agents: sessions.len(),
+        waiting_for_approval: waiting,
+        sessions: sessions
+            .into_iter()
+            .map(|s| SessionSummary {
+                title: s
+                    .ticket
+                    .and_then(|t| t.title)
+                    .or(s.pane_name)
+                    .unwrap_or_else(|| s.id.clone()),
+                project: s.project.and_then(|p| p.name),
+                id: s.id,
+            })
+            .collect(),
+    }
+}
+
+fn tooltip(summary: &MenubarSummary) -> String {
+    if !summary.server_running {
+        return "Claude PM — server stopped".to_string();
+    }
+    format!(
+        "Claude PM — {} running, {} waiting",
+        summary.running_agents, summary.waiting_for_approval
+    )
+}
+
+fn get_or_create_popover(app: &AppHandle) -> Result<WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(MENUBAR_WINDOW) {
+        return Ok(window);
+    }
+
+    let window = WebviewWindowBuilder::new(
+        app,
+        MENUBAR_WINDOW,
+        WebviewUrl::App("index.html#/menubar".into()),
+    )
+    .title("Claude PM")
+    .inner_size(POPOVER_SIZE.0, POPOVER_SIZE.1)
+    .decorations(false)
+    .always_on_top(true)
+    .resizable(false)
+    .skip_taskbar(true)
+    .visible(false)
+    .build()
+    .map_err(|e| format!("Failed to create menu bar popover: {}", e))?;
+
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Focused(false) = event {
+            let _ = handle.hide();
+        }
+    });
+    Ok(window)
+}
+
+/// Show the popover centered under the tray icon, or hide it if already visible
+fn toggle_popover(app: &AppHandle, anchor: PhysicalPosition<f64>, anchor_width: f64) {
+    let window = match get_or_create_popover(app) {
+        Ok(window) => window,
+        Err(e) => {
+            eprintln!("[Claude PM] {}", e);
+            return;
+        }
+    };
+
+    if window.is_visible().unwrap_or(false) {
+        let _ = window.hide();
+        return;
+    }
+
+    let scale = window.scale_factor().unwrap_or(1.0);
+    let x = anchor.x + anchor_width / 2.0 - POPOVER_SIZE.0 * scale / 2.0;
+    let _ = window.set_position(PhysicalPosition::new(x.max(0.0), anchor.y));
+    let _ = window.show();
+    let _ = window.set_focus();
+}
+
+fn start_refresh_loop(app: AppHandle) {
+    thread::spawn(move || loop {
+        // Don't flash "server stopped" while it's being recovered after wake
+        if power::is_settling() {
+            thread::sleep(power::throttled(REFRESH_INTERVAL));
+            continue;
+        }
+        let summary = fetch_summary();
+        if let Some(tray) = app.tray_by_id(TRAY_ID) {
+            let _ = tray.set_tooltip(Some(tooltip(&summary)));
+        }
+        let _ = app.emit("menubar-summary", summary.clone());
+        if let Ok(mut latest) = LATEST.lock() {
+            *latest = Some(summary);
+        }
+        thread::sleep(power::throttled(REFRESH_INTERVAL));
+    });
+}
+
+/// Monochrome glyph that contrasts with a menu bar in `mode`
+fn tray_icon(mode: Mode) -> tauri::Result<Image<'static>> {
+    Image::from_bytes(match mode {
+        Mode::Light => include_bytes!("../icons/tray-light.png"),
+        Mode::Dark => include_bytes!("../icons/tray-dark.png"),
+    })
+}
+
+/// Swap the tray icon for the variant matching the system theme
+pub fn apply_theme(app: &AppHandle, mode: Mode) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Err(e) = tray_icon(mode).and_then(|icon| tray.set_icon(Some(icon))) {
+        eprintln!("[Claude PM] Failed to update tray icon: {}", e);
+    }
+}
+
+/// Create the tray icon and start refreshing the summary
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Claude PM")
+        .show_menu_on_left_click(false)
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                rect,
+                ..
+            } = event
+            {
+                let scale = tray
+                    .app_handle()
+                    .primary_monitor()
+                    .ok()
+                    .flatten()
+                    .map(|m| m.scale_factor())
+                    .unwrap_or(1.0);
+                let position = rect.position.to_physical::<f64>(scale);
+                let size = rect.size.to_physical::<f64>(scale);
+                let anchor = PhysicalPosition::new(position.x, position.y + size.height);
+                toggle_popover(tray.app_handle(), anchor, size.width);
+            }
+        });
+    match tray_icon(Mode::Light) {
+        Ok(icon) => builder = builder.icon(icon),
+        Err(e) => {
+            eprintln!("[Claude PM] Failed to load tray icon: {}", e);
+            if let Some(icon) = app.default_window_icon() {
+                builder = builder.icon(icon.clone());
+            }
+        }
+    }
+    builder.build(app)?;
+
+    start_refresh_loop(app.clone());
+    Ok(())
+}
+
+/// Most recent summary, for the popover's first render before the next event arrives
+#[tauri::command]
+pub fn get_menubar_summary() -> MenubarSummary {
+    LATEST
+        .lock()
+        .ok()
+        .and_then(|latest| latest.clone())
+        .unwrap_or_default()
+}
@@ -0,0 +1,202 @@
+//! Mock server for frontend development and E2E tests
+//!
+//! Launched with `--mock-server` (or `CLAUDEPM_MOCK_SERVER=1`), the app serves fixture
+//! responses on the server's port itself instead of spawning Node, so neither the real
+//! backend nor an API key is needed. Everything else (the proxy, the lifecycle, the
+//! health checks) sees an ordinary server.
+//!
+//! Fixtures come from `mock/fixtures.json`, or the file `CLAUDEPM_MOCK_FIXTURES` points
+//! to: `routes` maps `"METHOD /path"` to the JSON body to answer with, where a `:name`
+//! segment matches anything and the most literal match wins; a `null` body answers 204.
+//! A request without a fixture gets a 404. WebSocket clients get the fixture `events`
+//! in turn, one every few seconds, each stamped with the current time.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Request, Response, Server, StatusCode};
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+use crate::error::Error;
+use crate::server_output;
+
+const FLAG: &str = "--mock-server";
+const ENABLE_ENV: &str = "CLAUDEPM_MOCK_SERVER";
+const FIXTURES_ENV: &str = "CLAUDEPM_MOCK_FIXTURES";
+const BUILTIN_FIXTURES: &str = include_str!("../mock/fixtures.json");
+const EVENT_INTERVAL: Duration = Duration::from_secs(5);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SERVER: Mutex<Option<Arc<Server>>> = Mutex::new(None);
+/// Bumped on every stop so WebSocket threads from an earlier start wind down
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Fixtures {
+    routes: BTreeMap<String, Value>,
+    events: Vec<Value>,
+}
+
+/// Check the command line and environment; call once at launch
+pub fn init() {
+    let requested = std::env::args().any(|arg| arg == FLAG)
+        || std::env::var(ENABLE_ENV).is_ok_and(|v| v == "1" || v == "true");
+    if requested {
+        println!("[Claude PM] Mock server mode: serving fixtures instead of starting Node");
+        ENABLED.store(true, Ordering::SeqCst);
+    }
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+fn load_fixtures() -> Result<Fixtures, String> {
+    let contents = match std::env::var(FIXTURES_ENV) {
+        Ok(path) => fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read mock fixtures {}: {}", path, e))?,
+        Err(_) => BUILTIN_FIXTURES.to_string(),
+    };
+    serde_json::from_str(&contents).map_err(|e| format!("Invalid mock fixtures: {}", e))
+}
+
+/// The fixture for `method` and `path`, preferring routes with more literal segments
+fn find<'a>(fixtures: &'a Fixtures, method: &str, path: &str) -> Option<&'a Value> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    fixtures
+        .routes
+        .iter()
+        .filter_map(|(route, body)| {
+            let (route_method, route_path) = route.split_once(' ')?;
+            if !route_method.eq_ignore_ascii_case(method) {
+                return None;
+            }
+            let pattern: Vec<&str> = route_path.trim_matches('/').split('/').collect();
+            let matches = pattern.len() == segments.len()
+                && pattern
+                    .iter()
+                    .zip(&segments)
+                    .all(|(p, s)| p.starts_with(':') || p == s);
+            let literal = pattern.iter().filter(|p| !p.starts_with(':')).count();
+            matches.then_some((literal, body))
+        })
+        .max_by_key(|(literal, _)| *literal)
+        .map(|(_, body)| body)
+}
+
+fn json_response(status: u16, body: &Value) -> Response<Cursor<Vec<u8>>> {
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    Response::from_data(body.to_string().into_bytes())
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+fn header<'a>(request: &'a Request, name: &str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_str())
+}
+
+/// Send the fixture events in turn until the client goes away or the mock stops
+fn serve_socket(request: Request, events: Arc<Vec<Value>>) {
+    let Some(key) = header(&request, "Sec-WebSocket-Key") else {
+        let _ = request.respond(json_response(
+            400,
+            &serde_json::json!({"error": "Bad Request"}),
+        ));
+        return;
+    };
+    let accept = tungstenite::handshake::derive_accept_key(key.as_bytes());
+    let response = Response::empty(StatusCode(101))
+        .with_header(Header::from_bytes("Upgrade", "websocket").unwrap())
+        .with_header(Header::from_bytes("Connection", "Upgrade").unwrap())
+        .with_header(Header::from_bytes("Sec-WebSocket-Accept", accept).unwrap());
+    let stream = request.upgrade("websocket", response);
+    let generation = GENERATION.load(Ordering::SeqCst);
+    thread::spawn(move || {
+        let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+        for event in events.iter().cycle() {
+            thread::sleep(EVENT_INTERVAL);
+            if GENERATION.load(Ordering::SeqCst) != generation {
+                break;
+            }
+            let mut event = event.clone();
+            if let Some(payload) = event.get_mut("payload").and_then(Value::as_object_mut) {
+                payload
+                    .entry("timestamp")
+                    .or_insert_with(|| chrono::Utc::now().to_rfc3339().into());
+            }
+            if socket.send(Message::text(event.to_string())).is_err() {
+                break;
+            }
+        }
+        let _ = socket.close(None);
+    });
+}
+
+fn handle(request: Request, fixtures: &Fixtures, events: &Arc<Vec<Value>>) {
+    let is_upgrade =
+        header(&request, "Upgrade").is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+    if is_upgrade {
+        return serve_socket(request, events.clone());
+    }
+    let method = request.method().as_str().to_string();
+    let path = request.url().split('?').next().unwrap_or("/").to_string();
+    let response = match find(fixtures, &method, &path) {
+        Some(Value::Null) => Response::from_data(Vec::new()).with_status_code(204),
+        Some(body) => json_response(200, body),
+        None => json_response(
+            404,
+            &serde_json::json!({
+                "error": format!("No mock fixture for {} {}", method, path),
+            }),
+        ),
+    };
+    let _ = request.respond(response);
+}
+
+/// Serve the fixtures on `port` in place of the Node server
+pub fn start(port: u16) -> Result<(), Error> {
+    let fixtures = load_fixtures()?;
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| format!("Failed to start mock server on port {}: {}", port, e))?;
+    let server = Arc::new(server);
+    if let Ok(mut current) = SERVER.lock() {
+        *current = Some(server.clone());
+    }
+    server_output::reset("mock");
+    server_output::scan(&format!("Mock server listening on port {}", port));
+    println!(
+        "[Claude PM] Mock server listening on 127.0.0.1:{} ({} routes, {} events)",
+        port,
+        fixtures.routes.len(),
+        fixtures.events.len()
+    );
+
+    let events = Arc::new(fixtures.events.clone());
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            handle(request, &fixtures, &events);
+        }
+    });
+    Ok(())
+}
+
+pub fn stop() {
+    let server = SERVER.lock().ok().and_then(|mut current| current.take());
+    if let Some(server) = server {
+        GENERATION.fetch_add(1, Ordering::SeqCst);
+        server.unblock();
+        println!("[Claude PM] Mock server stopped");
+    }
+}
@@ -0,0 +1,422 @@
+//! Terminal multiplexer backends: tmux, zellij and GNU screen
+//!
+//! Agent runs, prompt monitoring and answers go through [`Multiplexer`], so they work
+//! with whichever of the three is installed (tmux first), or with the one named in
+//! `multiplexer` in the config. Pane targets are backend specific: `session:0.0` for
+//! tmux, the session name for zellij (its focused pane) and screen (window 0).
+//! Recording (`pipe-pane`) and window renames stay tmux only.
+//!
+//! zellij sessions are started in the background from a one-pane layout, which needs
+//! zellij 0.40 or later.
+
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::Error;
+use crate::process::{self, first_existing, which};
+use crate::{config, http_proxy, tmux};
+
+pub const BACKENDS: &[&str] = &["tmux", "zellij", "screen"];
+
+pub trait Multiplexer: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn path(&self) -> Option<PathBuf>;
+    /// Flag that prints the version, for the doctor
+    fn version_flag(&self) -> &'static str;
+    fn list_sessions(&self) -> Result<Vec<String>, String>;
+    /// Start a detached session running `command` in `cwd` with `env` set; returns the
+    /// pane target
+    fn new_session(
+        &self,
+        name: &str,
+        cwd: &str,
+        command: &[&str],
+        env: &[(String, String)],
+    ) -> Result<String, String>;
+    fn kill_session(&self, name: &str) -> Result<(), String>;
+    /// Type `text` into the pane as-is
+    fn send_text(&self, target: &str, text: &str) -> Result<(), String>;
+    fn send_enter(&self, target: &str) -> Result<(), String>;
+    /// Visible contents of the pane; fails once the session is gone
+    fn capture(&self, target: &str) -> Result<String, String>;
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MultiplexerStatus {
+    /// Backend in use; `None` when none is installed
+    pub active: Option<&'static str>,
+    /// Override from the config; auto-detected when unset
+    pub configured: Option<String>,
+    pub installed: Vec<&'static str>,
+    /// Sessions of the active backend
+    pub sessions: Vec<String>,
+}
+
+fn locate(program: &str) -> Option<PathBuf> {
+    // GUI apps on macOS don't see Homebrew's bin directories on PATH
+    which(program).or_else(|| {
+        first_existing(&[
+            PathBuf::from("/opt/homebrew/bin").join(program),
+            PathBuf::from("/usr/local/bin").join(program),
+            PathBuf::from("/usr/bin").join(program),
+        ])
+    })
+}
+
+/// Run `program` with `args`, returning stdout
+fn run(program: &str, args: &[&str], cwd: Option<&str>) -> Result<String, String> {
+    run_with_env(program, args, cwd, &[])
+}
+
+fn run_with_env(
+    program: &str,
+    args: &[&str],
+    cwd: Option<&str>,
+    env: &[(String, String)],
+) -> Result<String, String> {
+    let path = locate(program).ok_or_else(|| format!("{} not found", program))?;
+    let mut cmd = Command::new(path);
+    cmd.args(args)
+        .envs(http_proxy::env())
+        .envs(env.iter().map(|(k, v)| (k, v)));
+    if let Some(cwd) = cwd {
+        cmd.current_dir(cwd);
+    }
+    let output = process::output(&mut cmd)?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.first().unwrap_or(&""),
+            if stderr.trim().is_empty() {
+                stdout.trim()
+            } else {
+                stderr.trim()
+            }
+        ))
+    }
+}
+
+/// Scratch file for screen/zellij dumps, removed after reading
+fn read_dump(dump: impl FnOnce(&Path) -> Result<(), String>) -> Result<String, String> {
+    let path =
+        std::env::temp_dir().join(format!("claudepm-capture-{}.txt", crate::store::new_id()));
+    let result = dump(&path).and_then(|_| {
+        fs::read(&path)
+            .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+            .map_err(|e| format!("Failed to read pane capture: {}", e))
+    });
+    let _ = fs::remove_file(&path);
+    result
+}
+
+pub struct Tmux;
+
+impl Multiplexer for Tmux {
+    fn name(&self) -> &'static str {
+        "tmux"
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        tmux::tmux_path()
+    }
+
+    fn version_flag(&self) -> &'static str {
+        "-V"
+    }
+
+    fn list_sessions(&self) -> Result<Vec<String>, String> {
+        match tmux::run(&["list-sessions", "-F", "#{session_name}"]) {
+            Ok(out) => Ok(out.lines().map(str::to_string).collect()),
+            // No server running means no sessions
+            Err(e) if e.contains("no server running") => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn new_session(
+        &self,
+        name: &str,
+        cwd: &str,
+        command: &[&str],
+        env: &[(String, String)],
+    ) -> Result<String, String> {
+        // Panes get the server's environment, so per-session vars go in with `-e`
+        let vars: Vec<String> = env.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        let mut args = vec!["new-session", "-d", "-s", name, "-c", cwd];
+        for var in &vars {
+            args.extend(["-e", var.as_str()]);
+        }
+        args.extend_from_slice(command);
+        tmux::run(&args)?;
+        Ok(format!("{}:0.0", name))
+    }
+
+    fn kill_session(&self, name: &str) -> Result<(), String> {
+        tmux::run(&["kill-session", "-t", name]).map(|_| ())
+    }
+
+    fn send_text(&self, target: &str, text: &str) -> Result<(), String> {
+        tmux::run(&["send-keys", "-t", target, "-l", text]).map(|_| ())
+    }
+
+    fn send_enter(&self, target: &str) -> Result<(), String> {
+        tmux::run(&["send-keys", "-t", target, "Enter"]).map(|_| ())
+    }
+
+    fn capture(&self, target: &str) -> Result<String, String> {
+        tmux::capture_pane(target)
+    }
+}
+
+pub struct Zellij;
+
+/// KDL string literal
+fn kdl_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Multiplexer for Zellij {
+    fn name(&self) -> &'static str {
+        "zellij"
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        locate("zellij")
+    }
+
+    fn version_flag(&self) -> &'static str {
+        "--version"
+    }
+
+    fn list_sessions(&self) -> Result<Vec<String>, String> {
+        match run(
+            "zellij",
+            &["list-sessions", "--short", "--no-formatting"],
+            None,
+        ) {
+            Ok(out) => Ok(out.lines().map(|l| l.trim().to_string()).collect()),
+            Err(e) if e.contains("No active zellij sessions") => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn new_session(
+        &self,
+        name: &str,
+        cwd: &str,
+        command: &[&str],
+        env: &[(String, String)],
+    ) -> Result<String, String> {
+        let (program, args) = command.split_first().ok_or("No command to run")?;
+        // A single pane that closes with the command, so the session ends with it
+        let layout = format!(
+            "layout {{\n    pane command={} cwd={} close_on_exit=true {{\n        args {}\n    }}\n}}\n",
+            kdl_string(program),
+            kdl_string(cwd),
+            args.iter().map(|a| kdl_string(a)).collect::<Vec<_>>().join(" ")
+        );
+        let path = std::env::temp_dir().join(format!("claudepm-layout-{}.kdl", name));
+        fs::write(&path, layout).map_err(|e| format!("Failed to write zellij layout: {}", e))?;
+        let path_arg = path.display().to_string();
+        // The session's server is started here, so its pane inherits `env`
+        let result = run_with_env(
+            "zellij",
+            &[
+                "attach",
+                "--create-background",
+                name,
+                "options",
+                "--default-layout",
+                &path_arg,
+            ],
+            Some(cwd),
+            env,
+        );
+        let _ = fs::remove_file(&path);
+        result.map(|_| name.to_string())
+    }
+
+    fn kill_session(&self, name: &str) -> Result<(), String> {
+        run("zellij", &["kill-session", name], None)?;
+        // Killed sessions linger as resurrectable otherwise
+        let _ = run("zellij", &["delete-session", name], None);
+        Ok(())
+    }
+
+    fn send_text(&self, target: &str, text: &str) -> Result<(), String> {
+        run(
+            "zellij",
+            &["-s", target, "action", "write-chars", text],
+            None,
+        )
+        .map(|_| ())
+    }
+
+    fn send_enter(&self, target: &str) -> Result<(), String> {
+        run("zellij", &["-s", target, "action", "write", "13"], None).map(|_| ())
+    }
+
+    fn capture(&self, target: &str) -> Result<String, String> {
+        read_dump(|path| {
+            let path = path.display().to_string();
+            run(
+                "zellij",
+                &["-s", target, "action", "dump-screen", &path],
+                None,
+            )
+            .map(|_| ())
+        })
+    }
+}
+
+pub struct Screen;
+
+/// `stuff` expands `^X` and backslash escapes, so both are escaped for literal input
+fn screen_literal(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('^', "\\^")
+}
+
+impl Multiplexer for Screen {
+    fn name(&self) -> &'static str {
+        "screen"
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        locate("screen")
+    }
+
+    fn version_flag(&self) -> &'static str {
+        "-v"
+    }
+
+    fn list_sessions(&self) -> Result<Vec<String>, String> {
+        // `screen -ls` exits non-zero when there are no sessions, so read it either way
+        let path = self.path().ok_or("screen not found")?;
+        let output = process::output(Command::new(path).arg("-ls"))?;
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| line.starts_with('\t'))
+            .filter_map(|line| line.split_whitespace().next())
+            .filter_map(|id| id.split_once('.').map(|(_, name)| name.to_string()))
+            .collect())
+    }
+
+    fn new_session(
+        &self,
+        name: &str,
+        cwd: &str,
+        command: &[&str],
+        env: &[(String, String)],
+    ) -> Result<String, String> {
+        let mut args = vec!["-dmS", name];
+        args.extend_from_slice(command);
+        run_with_env("screen", &args, Some(cwd), env)?;
+        Ok(name.to_string())
+    }
+
+    fn kill_session(&self, name: &str) -> Result<(), String> {
+        run("screen", &["-S", name, "-X", "quit"], None).map(|_| ())
+    }
+
+    fn send_text(&self, target: &str, text: &str) -> Result<(), String> {
+        let text = screen_literal(text);
+        run(
+            "screen",
+            &["-S", target, "-p", "0", "-X", "stuff", &text],
+            None,
+        )
+        .map(|_| ())
+    }
+
+    fn send_enter(&self, target: &str) -> Result<(), String> {
+        run(
+            "screen",
+            &["-S", target, "-p", "0", "-X", "stuff", "^M"],
+            None,
+        )
+        .map(|_| ())
+    }
+
+    fn capture(&self, target: &str) -> Result<String, String> {
+        read_dump(|path| {
+            let path = path.display().to_string();
+            run(
+                "screen",
+                &["-S", target, "-p", "0", "-X", "hardcopy", &path],
+                None,
+            )
+            .map(|_| ())
+        })
+    }
+}
+
+static TMUX: Tmux = Tmux;
+static ZELLIJ: Zellij = Zellij;
+static SCREEN: Screen = Screen;
+
+fn backend(name: &str) -> Option<&'static dyn Multiplexer> {
+    match name {
+        "tmux" => Some(&TMUX),
+        "zellij" => Some(&ZELLIJ),
+        "screen" => Some(&SCREEN),
+        _ => None,
+    }
+}
+
+fn installed() -> Vec<&'static dyn Multiplexer> {
+    BACKENDS
+        .iter()
+        .filter_map(|name| backend(name))
+        .filter(|mux| mux.path().is_some())
+        .collect()
+}
+
+/// The configured backend if it's installed, otherwise the first one found
+pub fn active() -> Result<&'static dyn Multiplexer, String> {
+    let configured = config::load().multiplexer.and_then(|name| backend(&name));
+    match configured {
+        Some(mux) if mux.path().is_some() => Ok(mux),
+        Some(mux) => Err(format!("{} is selected but not installed", mux.name())),
+        None => installed()
+            .into_iter()
+            .next()
+            .ok_or_else(|| "No terminal multiplexer found (tmux, zellij or screen)".to_string()),
+    }
+}
+
+#[tauri::command]
+pub fn get_multiplexer_status() -> MultiplexerStatus {
+    let active = active().ok();
+    MultiplexerStatus {
+        active: active.map(|mux| mux.name()),
+        configured: config::load().multiplexer,
+        installed: installed().iter().map(|mux| mux.name()).collect(),
+        sessions: active
+            .and_then(|mux| mux.list_sessions().ok())
+            .unwrap_or_default(),
+    }
+}
+
+/// Pick the backend to use; `None` goes back to auto-detection
+#[tauri::command]
+pub fn set_multiplexer(name: Option<String>) -> Result<MultiplexerStatus, Error> {
+    if let Some(name) = &name {
+        if backend(name).is_none() {
+            return Err(Error::InvalidInput(format!(
+                "Unknown multiplexer {} (expected one of {})",
+                name,
+                BACKENDS.join(", ")
+            )));
+        }
+    }
+    config::update(|c| c.multiplexer = name.clone())?;
+    Ok(get_multiplexer_status())
+}
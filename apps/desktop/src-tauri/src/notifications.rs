@@ -0,0 +1,265 @@
+//! Native notifications with action buttons, click-through navigation and burst control
+//!
+//! Unlike the notification plugin, clicks and action buttons are reported back:
+//! - a click routes the notification's target like a deep link (see `session_windows`)
+//! - an action button emits `notification-action` with the action id
+//!
+//! Identical notifications (same `key`) within a short window are dropped, and bursts
+//! from many agents at once are collapsed into a single summary. With digests on,
+//! non-critical ones are batched into a periodic summary instead (see `digest`).
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::digest;
+use crate::dnd::{self, Gate};
+use crate::session_windows::route_navigation;
+use crate::sounds;
+use crate::windows::focus_main_window;
+use crate::working_hours;
+
+/// Notifications with the same key inside this window are treated as duplicates
+const DEDUPE_WINDOW: Duration = Duration::from_secs(10);
+/// More than BURST_LIMIT notifications inside BURST_WINDOW get aggregated
+const BURST_WINDOW: Duration = Duration::from_secs(5);
+const BURST_LIMIT: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationAction {
+    pub id: String,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationRequest {
+    pub title: String,
+    pub body: String,
+    /// Dedupe key, e.g. `approval:<session-id>`
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub actions: Vec<NotificationAction>,
+    /// Frontend route to open when the notification is clicked, e.g. `/sessions/<id>`
+    #[serde(default)]
+    pub target: Option<String>,
+    /// Category used for Focus policies, e.g. `approval`, `completed`, `failed`
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ActionEvent {
+    action: String,
+    key: Option<String>,
+    target: Option<String>,
+}
+
+/// What the user did with a delivered notification
+enum Response {
+    Clicked,
+    Action(String),
+    Dismissed,
+}
+
+struct Router {
+    recent_keys: BTreeMap<String, Instant>,
+    sent: VecDeque<Instant>,
+    held: Vec<NotificationRequest>,
+    flush_scheduled: bool,
+}
+
+static ROUTER: Mutex<Router> = Mutex::new(Router {
+    recent_keys: BTreeMap::new(),
+    sent: VecDeque::new(),
+    held: Vec::new(),
+    flush_scheduled: false,
+});
+
+#[cfg(target_os = "macos")]
+fn deliver(request: &NotificationRequest) -> Result<Response, String> {
+    use mac_notification_sys::{MainButton, Notification, NotificationResponse};
+
+    let labels: Vec<&str> = request.actions.iter().map(|a| a.label.as_str()).collect();
+    let mut notification = Notification::new();
+    notification
+        .title(&request.title)
+        .message(&request.body)
+        .wait_for_click(true);
+    match labels.as_slice() {
+        [] => {}
+        [single] => {
+            notification.main_button(MainButton::SingleAction(single));
+        }
+        many => {
+            notification.main_button(MainButton::DropdownActions("Actions", many));
+        }
+    }
+
+    let response = notification.send().map_err(|e| e.to_string())?;
+    Ok(match response {
+        NotificationResponse::ActionButton(label) => request
+            .actions
+            .iter()
+            .find(|a| a.label == label)
+            .map(|a| Response::Action(a.id.clone()))
+            .unwrap_or(Response::Clicked),
+        NotificationResponse::Click => Response::Clicked,
+        _ => Response::Dismissed,
+    })
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn deliver(request: &NotificationRequest) -> Result<Response, String> {
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .appname("Claude PM")
+        .summary(&request.title)
+        .body(&request.body)
+        .action("default", "Open");
+    for action in &request.actions {
+        notification.action(&action.id, &action.label);
+    }
+
+    let handle = notification.show().map_err(|e| e.to_string())?;
+    let mut response = Response::Dismissed;
+    handle.wait_for_action(|action| {
+        response = match action {
+            "default" => Response::Clicked,
+            "__closed" => Response::Dismissed,
+            id => Response::Action(id.to_string()),
+        }
+    });
+    Ok(response)
+}
+
+#[cfg(windows)]
+fn deliver(request: &NotificationRequest) -> Result<Response, String> {
+    // Toast activation isn't reported back through notify-rust on Windows
+    notify_rust::Notification::new()
+        .summary(&request.title)
+        .body(&request.body)
+        .show()
+        .map_err(|e| e.to_string())?;
+    Ok(Response::Dismissed)
+}
+
+/// Show the notification on a background thread and route the user's response
+fn dispatch(app: AppHandle, request: NotificationRequest) {
+    thread::spawn(move || match deliver(&request) {
+        Ok(Response::Clicked) => match request.target {
+            Some(ref path) => route_navigation(&app, path),
+            None => focus_main_window(&app),
+        },
+        Ok(Response::Action(action)) => {
+            crate::agent_monitor::handle_notification_action(request.key.as_deref(), &action);
+            crate::task_graph::handle_notification_action(request.key.as_deref(), &action);
+            let _ = app.emit(
+                "notification-action",
+                ActionEvent {
+                    action,
+                    key: request.key.clone(),
+                    target: request.target.clone(),
+                },
+            );
+        }
+        Ok(Response::Dismissed) => {}
+        Err(e) => eprintln!("[Claude PM] Failed to show notification: {}", e),
+    });
+}
+
+/// Collapse held notifications into one summary
+fn summarize(held: Vec<NotificationRequest>) -> NotificationRequest {
+    let titles: Vec<&str> = held.iter().map(|n| n.title.as_str()).collect();
+    NotificationRequest {
+        title: format!("{} new notifications", held.len()),
+        body: titles.join(" · "),
+        ..Default::default()
+    }
+}
+
+fn flush_held(app: AppHandle) {
+    thread::sleep(BURST_WINDOW);
+    let held = match ROUTER.lock() {
+        Ok(mut router) => {
+            router.flush_scheduled = false;
+            std::mem::take(&mut router.held)
+        }
+        Err(_) => return,
+    };
+
+    match held.len() {
+        0 => {}
+        1 => dispatch(app, held.into_iter().next().unwrap_or_default()),
+        _ => dispatch(app, summarize(held)),
+    }
+}
+
+/// Route a notification through Focus policy, working hours, the digest, dedupe and
+/// burst aggregation, then show it
+pub fn notify(app: &AppHandle, request: NotificationRequest) {
+    if let Gate::Held = dnd::gate(app, &request) {
+        return;
+    }
+    if let Gate::Held = working_hours::gate(&request) {
+        return;
+    }
+    if let Gate::Held = digest::gate(app, &request) {
+        return;
+    }
+    deliver_now(app, request);
+}
+
+/// Dedupe and burst handling only; used for notifications released from the Focus queue
+pub fn deliver_now(app: &AppHandle, request: NotificationRequest) {
+    let Ok(mut router) = ROUTER.lock() else {
+        return;
+    };
+    let now = Instant::now();
+
+    router
+        .recent_keys
+        .retain(|_, at| now.duration_since(*at) < DEDUPE_WINDOW);
+    if let Some(ref key) = request.key {
+        if router.recent_keys.contains_key(key) {
+            return;
+        }
+        router.recent_keys.insert(key.clone(), now);
+    }
+
+    while router
+        .sent
+        .front()
+        .is_some_and(|at| now.duration_since(*at) >= BURST_WINDOW)
+    {
+        router.sent.pop_front();
+    }
+
+    if router.sent.len() >= BURST_LIMIT {
+        router.held.push(request);
+        if !router.flush_scheduled {
+            router.flush_scheduled = true;
+            let app = app.clone();
+            thread::spawn(move || flush_held(app));
+        }
+        return;
+    }
+
+    router.sent.push_back(now);
+    drop(router);
+    if let Some(ref category) = request.category {
+        sounds::play_for_event(category);
+    }
+    dispatch(app.clone(), request);
+}
+
+#[tauri::command]
+pub fn show_notification(app: AppHandle, notification: NotificationRequest) {
+    notify(&app, notification);
+}
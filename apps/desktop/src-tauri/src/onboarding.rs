@@ -0,0 +1,225 @@
+//! First-run setup progress
+//!
+//! The wizard's steps are persisted to onboarding.json in the config directory so a
+//! restart mid-setup resumes where the user left off. Advancing a step verifies it
+//! (and applies any value it takes) before marking it done.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+use crate::doctor::{self, CheckStatus};
+use crate::error::Error;
+use crate::permissions::{self, PermissionStatus};
+use crate::{auth, config, server_paths};
+
+pub const ONBOARDING_FILE: &str = "onboarding.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Step {
+    ServerPath,
+    Node,
+    ApiKey,
+    Permissions,
+}
+
+/// Wizard order
+const STEPS: &[Step] = &[
+    Step::ServerPath,
+    Step::Node,
+    Step::ApiKey,
+    Step::Permissions,
+];
+
+impl Step {
+    /// Steps the app can't work without
+    fn required(self) -> bool {
+        matches!(self, Step::ServerPath | Step::Node)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StepStatus {
+    Pending,
+    Done,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StepRecord {
+    status: StepStatus,
+    updated_at: i64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepState {
+    pub step: Step,
+    pub status: StepStatus,
+    pub required: bool,
+    pub updated_at: Option<i64>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingState {
+    /// First step that is still pending; `None` once setup is complete
+    pub current_step: Option<Step>,
+    pub complete: bool,
+    pub steps: Vec<StepState>,
+}
+
+fn onboarding_path() -> Result<PathBuf, String> {
+    config::config_dir()
+        .map(|dir| dir.join(ONBOARDING_FILE))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+fn load() -> BTreeMap<Step, StepRecord> {
+    onboarding_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save(records: &BTreeMap<Step, StepRecord>) -> Result<(), String> {
+    let path = onboarding_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let contents = serde_json::to_string_pretty(records).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write onboarding state: {}", e))
+}
+
+fn record(step: Step, status: StepStatus) -> Result<OnboardingState, Error> {
+    let mut records = load();
+    records.insert(
+        step,
+        StepRecord {
+            status,
+            updated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or_default(),
+        },
+    );
+    save(&records)?;
+    Ok(state())
+}
+
+fn state() -> OnboardingState {
+    let records = load();
+    let steps: Vec<StepState> = STEPS
+        .iter()
+        .map(|&step| {
+            let record = records.get(&step);
+            StepState {
+                step,
+                status: record.map_or(StepStatus::Pending, |r| r.status),
+                required: step.required(),
+                updated_at: record.map(|r| r.updated_at),
+            }
+        })
+        .collect();
+    let current_step = steps
+        .iter()
+        .find(|s| s.status == StepStatus::Pending)
+        .map(|s| s.step);
+    OnboardingState {
+        current_step,
+        complete: current_step.is_none(),
+        steps,
+    }
+}
+
+fn verify_server_path(value: Option<String>) -> Result<(), Error> {
+    let path = match value {
+        Some(value) => PathBuf::from(value),
+        None => crate::get_server_path().ok_or(Error::ServerPathMissing)?,
+    };
+    server_paths::add(&path.display().to_string(), None, None, true).map(|_| ())
+}
+
+fn verify_node() -> Result<(), Error> {
+    let check = doctor::check_node();
+    if check.status == CheckStatus::Error {
+        return Err(Error::InvalidInput(format!(
+            "Node.js: {}. {}",
+            check.detail,
+            check.fix.unwrap_or_default()
+        )));
+    }
+    Ok(())
+}
+
+fn verify_permissions(app: AppHandle) -> Result<(), Error> {
+    let report = permissions::get_permission_status(app);
+    let missing: Vec<&'static str> = [
+        ("automation", report.automation),
+        ("notifications", report.notifications),
+    ]
+    .into_iter()
+    .filter(|(_, status)| {
+        !matches!(
+            status,
+            PermissionStatus::Granted | PermissionStatus::Unsupported
+        )
+    })
+    .map(|(name, _)| name)
+    .collect();
+    if let Some(&permission) = missing.first() {
+        return Err(Error::PermissionDenied {
+            permission,
+            message: format!("Still missing: {}", missing.join(", ")),
+        });
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_onboarding_state() -> OnboardingState {
+    state()
+}
+
+/// Verify and complete a step. `value` is the server path for `serverPath` and the API
+/// key for `apiKey` (omit it to generate one)
+#[tauri::command]
+pub fn advance_onboarding(
+    app: AppHandle,
+    step: Step,
+    value: Option<String>,
+) -> Result<OnboardingState, Error> {
+    match step {
+        Step::ServerPath => verify_server_path(value)?,
+        Step::Node => verify_node()?,
+        Step::ApiKey => auth::store_api_key(value).map_err(Error::InvalidInput)?,
+        Step::Permissions => verify_permissions(app)?,
+    }
+    record(step, StepStatus::Done)
+}
+
+#[tauri::command]
+pub fn skip_onboarding_step(step: Step) -> Result<OnboardingState, Error> {
+    if step.required() {
+        return Err(Error::InvalidInput(format!(
+            "{:?} is required and can't be skipped",
+            step
+        )));
+    }
+    record(step, StepStatus::Skipped)
+}
+
+/// Start the wizard over, e.g. from a "run setup again" menu item
+#[tauri::command]
+pub fn reset_onboarding() -> Result<OnboardingState, Error> {
+    save(&BTreeMap::new())?;
+    Ok(state())
+}
@@ -0,0 +1,493 @@
+//! Agent run queue with a concurrency limit
+//!
+//! Runs are queued per task and started oldest first (or as reprioritized) while fewer
+//! than `max_parallel_agents` are active, so firing ten tasks doesn't start ten Claude
+//! processes at once. Each run gets its own multiplexer session (see `multiplexer`) in the project's repository and
+//! a session row in the store, and is started with the project's model (see
+//! `claude_settings`) unless the run asks for another. A run is `blocked` while its pane
+//! shows a prompt (it still holds its slot) and `done` once its tmux session ends.
+//! The repository is snapshotted before Claude starts (see `snapshots`).
+//! Nothing new starts while the account is rate limited (see `rate_limits`).
+//! Finished runs stay here for the queue view only; each is recorded in `run_history`.
+//! With `start_unblocked_agents` on, a task is queued as soon as its dependencies are
+//! done (see `task_graph`).
+//!
+//! Events:
+//! - `agent-run` with the [`AgentRun`] on every state change
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::audit::{self, AuditKind};
+use crate::error::Error;
+use crate::event_bus::{self, AppEvent, Filter, Topic};
+use crate::store::{self, NewSession, SessionUpdate, Task};
+use crate::{
+    agent_monitor, claude_profiles, claude_settings, config, doctor, linear, multiplexer,
+    queue_badge, rate_limits, run_history, snapshots,
+};
+
+const DEFAULT_MAX_PARALLEL: usize = 3;
+const TICK: Duration = Duration::from_secs(2);
+const SESSION_PREFIX: &str = "claudepm-run-";
+/// Finished runs kept for the queue view
+const HISTORY_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RunState {
+    Queued,
+    Running,
+    Blocked,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl RunState {
+    pub fn is_active(self) -> bool {
+        matches!(self, Self::Running | Self::Blocked)
+    }
+
+    fn is_finished(self) -> bool {
+        matches!(self, Self::Done | Self::Failed | Self::Cancelled)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Blocked => "blocked",
+            Self::Done => "done",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "queued" => Self::Queued,
+            "running" => Self::Running,
+            "blocked" => Self::Blocked,
+            "done" => Self::Done,
+            "cancelled" => Self::Cancelled,
+            _ => Self::Failed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentRun {
+    pub id: String,
+    pub task_id: String,
+    pub project_id: String,
+    pub title: String,
+    pub prompt: String,
+    /// Model requested for this run; the project's configured model when unset
+    pub model: Option<String>,
+    /// Directory Claude works in, e.g. a task worktree; the project's repository when unset
+    pub cwd: Option<String>,
+    /// Claude profile it runs as: the active one when it was queued
+    pub claude_profile: Option<String>,
+    pub state: RunState,
+    /// Store session, once started
+    pub session_id: Option<String>,
+    /// Multiplexer pane target, once started
+    pub target: Option<String>,
+    pub queued_at: i64,
+    pub started_at: Option<i64>,
+    pub ended_at: Option<i64>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunQueue {
+    pub max_parallel: usize,
+    /// Queued runs are held while the account is rate limited
+    pub paused: bool,
+    pub resumes_at: Option<i64>,
+    pub runs: Vec<AgentRun>,
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static RUNS: Mutex<VecDeque<AgentRun>> = Mutex::new(VecDeque::new());
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+fn max_parallel() -> usize {
+    config::load()
+        .max_parallel_agents
+        .unwrap_or(DEFAULT_MAX_PARALLEL)
+        .max(1)
+}
+
+fn emit(run: &AgentRun) {
+    if let Some(app) = APP.get() {
+        let _ = app.emit("agent-run", run);
+        queue_badge::refresh(app);
+    }
+}
+
+/// Apply `f` to run `id`, emitting the result if it changed state
+fn update(id: &str, f: impl FnOnce(&mut AgentRun)) -> Option<AgentRun> {
+    let mut runs = RUNS.lock().ok()?;
+    let run = runs.iter_mut().find(|r| r.id == id)?;
+    let before = run.state;
+    f(run);
+    let run = run.clone();
+    drop(runs);
+    if run.state != before {
+        emit(&run);
+    }
+    Some(run)
+}
+
+fn finish(id: &str, state: RunState, error: Option<String>) {
+    let Some(run) = update(id, |run| {
+        run.state = state;
+        run.ended_at = Some(store::now_ms());
+        run.error = error;
+    }) else {
+        return;
+    };
+    if state == RunState::Failed {
+        event_bus::publish(AppEvent::AgentFailed { run: run.clone() });
+    }
+    if let Some(session_id) = run.session_id.clone() {
+        let status = if state == RunState::Done {
+            "completed"
+        } else {
+            "failed"
+        };
+        let _ = store::update_session(
+            session_id,
+            SessionUpdate {
+                status: Some(status.to_string()),
+                pane_id: None,
+                ended_at: run.ended_at,
+            },
+        );
+    }
+    if let Some(target) = &run.target {
+        let _ = agent_monitor::unwatch_tmux_pane(target.clone());
+    }
+    // Diffing and reading the transcript can take a while; don't hold up the queue
+    thread::spawn(move || {
+        if let Some(record) = run_history::record(&run) {
+            linear::run_recorded(&record);
+        }
+    });
+}
+
+fn session_name(run: &AgentRun) -> String {
+    format!("{}{}", SESSION_PREFIX, run.id)
+}
+
+/// Open the run's multiplexer session with Claude and record it; returns the pane target
+fn launch(app: &AppHandle, run: &AgentRun) -> Result<(String, String), String> {
+    let claude = doctor::claude_path().ok_or("Claude CLI not found")?;
+    let repo = match &run.cwd {
+        Some(cwd) => cwd.clone(),
+        None => store::list_projects()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .find(|p| p.id == run.project_id)
+            .and_then(|p| p.repo_path)
+            .ok_or("The task's project has no repository path")?,
+    };
+    let name = session_name(run);
+    let claude = claude.display().to_string();
+    let mut args = vec![claude.as_str()];
+    let model = run
+        .model
+        .clone()
+        .or_else(|| claude_settings::effective_model(Some(&repo)));
+    if let Some(model) = &model {
+        args.extend(["--model", model]);
+    }
+    args.push(&run.prompt);
+    if let Err(e) = snapshots::take(&run.id, &run.task_id, &run.project_id, &repo) {
+        eprintln!("[Claude PM] No pre-run snapshot for run {}: {}", run.id, e);
+    }
+    let profile = run
+        .claude_profile
+        .as_deref()
+        .unwrap_or(claude_profiles::DEFAULT_PROFILE);
+    let env = claude_profiles::agent_env(profile);
+    let target = multiplexer::active()?.new_session(&name, &repo, &args, &env)?;
+    let session = store::create_session(NewSession {
+        project_id: run.project_id.clone(),
+        task_id: Some(run.task_id.clone()),
+        pane_id: Some(target.clone()),
+    })?;
+    // Prompt notifications and attention requests, as for any watched pane
+    let _ = agent_monitor::watch_tmux_pane(app.clone(), target.clone());
+    audit::record(
+        AuditKind::AgentSpawn,
+        "launch",
+        Some(&run.task_id),
+        serde_json::json!({ "runId": run.id, "target": target, "model": model, "repo": repo, "claudeProfile": profile }),
+    );
+    Ok((session.id, target))
+}
+
+/// Track active runs, then start queued ones while there are free slots
+fn tick(app: &AppHandle) {
+    let active: Vec<AgentRun> = RUNS
+        .lock()
+        .map(|runs| {
+            runs.iter()
+                .filter(|r| r.state.is_active())
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    for run in &active {
+        let Some(target) = &run.target else {
+            continue;
+        };
+        // The session closes when Claude exits
+        match multiplexer::active().and_then(|mux| mux.capture(target)) {
+            Err(_) => finish(&run.id, RunState::Done, None),
+            Ok(output) => {
+                let blocked = agent_monitor::detect_prompt(&output).is_some();
+                update(&run.id, |run| {
+                    run.state = if blocked {
+                        RunState::Blocked
+                    } else {
+                        RunState::Running
+                    };
+                });
+            }
+        }
+    }
+
+    // Starting more agents would only hit the limit again
+    if rate_limits::limited_until().is_some() {
+        return;
+    }
+    loop {
+        let next = {
+            let Ok(runs) = RUNS.lock() else {
+                return;
+            };
+            if runs.iter().filter(|r| r.state.is_active()).count() >= max_parallel() {
+                return;
+            }
+            match runs.iter().find(|r| r.state == RunState::Queued) {
+                Some(run) => run.clone(),
+                None => return,
+            }
+        };
+        match launch(app, &next) {
+            Ok((session_id, target)) => {
+                println!("[Claude PM] Started agent run {} in {}", next.id, target);
+                update(&next.id, |run| {
+                    run.state = RunState::Running;
+                    run.started_at = Some(store::now_ms());
+                    run.session_id = Some(session_id);
+                    run.target = Some(target);
+                });
+            }
+            Err(e) => {
+                eprintln!("[Claude PM] Failed to start agent run {}: {}", next.id, e);
+                finish(&next.id, RunState::Failed, Some(e));
+            }
+        }
+    }
+}
+
+fn prune(runs: &mut VecDeque<AgentRun>) {
+    let finished = runs.iter().filter(|r| r.state.is_finished()).count();
+    let mut excess = finished.saturating_sub(HISTORY_LIMIT);
+    runs.retain(|r| {
+        if excess > 0 && r.state.is_finished() {
+            excess -= 1;
+            return false;
+        }
+        true
+    });
+}
+
+/// Run `id`, while it's still in the queue or history
+pub fn get(id: &str) -> Option<AgentRun> {
+    RUNS.lock()
+        .ok()
+        .and_then(|runs| runs.iter().find(|r| r.id == id).cloned())
+}
+
+/// Whether run `id` holds a slot (running or blocked on a prompt)
+pub fn is_active(id: &str) -> bool {
+    RUNS.lock()
+        .map(|runs| runs.iter().any(|r| r.id == id && r.state.is_active()))
+        .unwrap_or(false)
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let _ = APP.set(app.clone());
+    event_bus::subscribe(Filter::topics(&[Topic::TaskUnblocked]), |envelope| {
+        let AppEvent::TaskUnblocked { task, .. } = &envelope.event else {
+            return;
+        };
+        if !config::load().start_unblocked_agents {
+            return;
+        }
+        match enqueue(task.id.clone(), None, None, None) {
+            Ok(run) => println!(
+                "[Claude PM] Queued agent run {} for ready task {}",
+                run.id, task.id
+            ),
+            Err(e) => eprintln!("[Claude PM] Failed to queue agent for {}: {}", task.id, e),
+        }
+    });
+    thread::spawn(move || loop {
+        tick(&app);
+        thread::sleep(TICK);
+    });
+}
+
+/// Queue an agent run for a task; `prompt` defaults to the task's title and description
+#[tauri::command]
+pub fn enqueue_agent_run(
+    task_id: String,
+    prompt: Option<String>,
+    model: Option<String>,
+) -> Result<AgentRun, Error> {
+    enqueue(task_id, prompt, model, None)
+}
+
+/// [`enqueue_agent_run`] in `cwd` instead of the project's repository
+/// The task's title and description, what a run is prompted with unless told otherwise
+pub fn default_prompt(task: &Task) -> String {
+    match task.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        Some(description) => format!("{}\n\n{}", task.title, description),
+        None => task.title.clone(),
+    }
+}
+
+pub fn enqueue(
+    task_id: String,
+    prompt: Option<String>,
+    model: Option<String>,
+    cwd: Option<String>,
+) -> Result<AgentRun, Error> {
+    if let Some(model) = &model {
+        claude_settings::validate_model(model).map_err(Error::InvalidInput)?;
+    }
+    let task = store::with_conn(|conn| store::get_task(conn, &task_id))?
+        .ok_or_else(|| Error::NotFound(format!("Task not found: {}", task_id)))?;
+    let prompt = prompt
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| default_prompt(&task));
+    let run = AgentRun {
+        id: store::new_id(),
+        task_id: task.id,
+        project_id: task.project_id,
+        title: task.title,
+        prompt,
+        model,
+        cwd,
+        claude_profile: Some(claude_profiles::active_name()),
+        state: RunState::Queued,
+        session_id: None,
+        target: None,
+        queued_at: store::now_ms(),
+        started_at: None,
+        ended_at: None,
+        error: None,
+    };
+    {
+        let mut runs = RUNS.lock().map_err(|e| e.to_string())?;
+        if runs
+            .iter()
+            .any(|r| r.task_id == run.task_id && !r.state.is_finished())
+        {
+            return Err(Error::InvalidInput(
+                "This task already has a queued or running agent".to_string(),
+            ));
+        }
+        runs.push_back(run.clone());
+        prune(&mut runs);
+    }
+    emit(&run);
+    Ok(run)
+}
+
+#[tauri::command]
+pub fn list_agent_runs() -> Result<RunQueue, Error> {
+    let limit = rate_limits::limited_until();
+    let runs = RUNS.lock().map_err(|e| e.to_string())?;
+    Ok(RunQueue {
+        max_parallel: max_parallel(),
+        paused: limit.is_some(),
+        resumes_at: limit.flatten(),
+        runs: runs.iter().cloned().collect(),
+    })
+}
+
+/// Drop a queued run, or stop a running one by closing its session
+#[tauri::command]
+pub fn cancel_agent_run(id: String) -> Result<(), Error> {
+    let run = RUNS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .find(|r| r.id == id)
+        .cloned()
+        .ok_or_else(|| Error::NotFound(format!("Agent run not found: {}", id)))?;
+    if run.state.is_finished() {
+        return Err(Error::InvalidInput(
+            "The run has already finished".to_string(),
+        ));
+    }
+    if run.state.is_active() {
+        multiplexer::active()?.kill_session(&session_name(&run))?;
+    }
+    finish(&id, RunState::Cancelled, None);
+    Ok(())
+}
+
+/// Move a queued run to the front of the queue
+#[tauri::command]
+pub fn prioritize_agent_run(id: String) -> Result<(), Error> {
+    let mut runs = RUNS.lock().map_err(|e| e.to_string())?;
+    let index = runs
+        .iter()
+        .position(|r| r.id == id && r.state == RunState::Queued)
+        .ok_or_else(|| Error::NotFound(format!("No queued agent run with id {}", id)))?;
+    if let Some(run) = runs.remove(index) {
+        runs.push_front(run);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_max_parallel_agents(max: usize) -> Result<(), Error> {
+    if max == 0 {
+        return Err(Error::InvalidInput(
+            "Allow at least one agent at a time".to_string(),
+        ));
+    }
+    config::update(|c| c.max_parallel_agents = Some(max))?;
+    if let Some(app) = APP.get() {
+        tick(app);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_finished_agent_runs() -> Result<(), Error> {
+    RUNS.lock()
+        .map_err(|e| e.to_string())?
+        .retain(|r| !r.state.is_finished());
+    Ok(())
+}
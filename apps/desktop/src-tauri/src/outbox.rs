@@ -0,0 +1,273 @@
+//! Durable outbox for outbound side effects (notifications, chat messages, emails)
+//!
+//! Effects are written to the `outbox` table before anything is sent, then delivered by
+//! a worker that survives restarts: effects queued while offline, or left behind by a
+//! crash, go out on the next run. A row is claimed (`pending` → `sending`) before it is
+//! sent and marked `delivered` afterwards, so two workers never send it twice, and an
+//! optional dedupe key makes enqueueing the same effect again a no-op. A send cut off by
+//! a crash is retried on the next launch, which is the one case a receiver may see twice.
+//!
+//! Events:
+//! - `outbox-delivery` after each delivery attempt
+
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::notifications::{self, NotificationRequest};
+use crate::{connectivity, email, integrations, store};
+
+const TICK: Duration = Duration::from_secs(5);
+const MAX_ATTEMPTS: u32 = 8;
+const BASE_BACKOFF_MS: i64 = 30 * 1000;
+const MAX_BACKOFF_MS: i64 = 60 * 60 * 1000;
+/// Delivered and failed rows kept for inspection
+const HISTORY_LIMIT: i64 = 1000;
+const BATCH: i64 = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Effect {
+    Notification {
+        request: NotificationRequest,
+    },
+    /// A Slack/Discord message body for one integration
+    #[serde(rename_all = "camelCase")]
+    Integration {
+        integration_id: String,
+        body: Value,
+    },
+    Email {
+        subject: String,
+        body: String,
+    },
+}
+
+impl Effect {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Notification { .. } => "notification",
+            Self::Integration { .. } => "integration",
+            Self::Email { .. } => "email",
+        }
+    }
+
+    fn needs_network(&self) -> bool {
+        !matches!(self, Self::Notification { .. })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxEntry {
+    pub id: String,
+    pub effect: Effect,
+    pub dedupe_key: Option<String>,
+    /// `pending`, `sending`, `delivered` or `failed`
+    pub status: String,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub delivered_at: Option<i64>,
+}
+
+impl OutboxEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let payload: String = row.get("payload")?;
+        let effect = serde_json::from_str(&payload).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+        })?;
+        Ok(Self {
+            id: row.get("id")?,
+            effect,
+            dedupe_key: row.get("dedupe_key")?,
+            status: row.get("status")?,
+            attempts: row.get("attempts")?,
+            next_attempt_at: row.get("next_attempt_at")?,
+            last_error: row.get("last_error")?,
+            created_at: row.get("created_at")?,
+            delivered_at: row.get("delivered_at")?,
+        })
+    }
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static FLUSHING: AtomicBool = AtomicBool::new(false);
+
+fn backoff_ms(attempts: u32) -> i64 {
+    (BASE_BACKOFF_MS << attempts.saturating_sub(1).min(16)).min(MAX_BACKOFF_MS)
+}
+
+/// Queue `effect` for delivery; a `dedupe_key` that was already queued is ignored
+pub fn enqueue(effect: Effect, dedupe_key: Option<String>) -> Result<(), String> {
+    let payload = serde_json::to_string(&effect).map_err(|e| e.to_string())?;
+    let now = store::now_ms();
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT OR IGNORE INTO outbox (id, kind, dedupe_key, payload, next_attempt_at, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            params![store::new_id(), effect.kind(), dedupe_key, payload, now],
+        )
+        .map(|_| ())
+    })?;
+    if let Some(app) = APP.get() {
+        flush(app.clone());
+    }
+    Ok(())
+}
+
+fn due() -> Result<Vec<OutboxEntry>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT * FROM outbox WHERE status = 'pending' AND next_attempt_at <= ?1 ORDER BY created_at LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![store::now_ms(), BATCH], OutboxEntry::from_row)?;
+        rows.collect()
+    })
+}
+
+/// Take ownership of a pending entry; false if another worker got it first
+fn claim(id: &str) -> Result<bool, String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "UPDATE outbox SET status = 'sending' WHERE id = ?1 AND status = 'pending'",
+            [id],
+        )
+        .map(|changed| changed == 1)
+    })
+}
+
+fn record(entry: &OutboxEntry, error: Option<String>) -> Result<OutboxEntry, String> {
+    let now = store::now_ms();
+    let attempts = entry.attempts + 1;
+    let (status, next, delivered) = match error {
+        None => ("delivered", entry.next_attempt_at, Some(now)),
+        Some(_) if attempts >= MAX_ATTEMPTS => ("failed", entry.next_attempt_at, None),
+        Some(_) => ("pending", now + backoff_ms(attempts), None),
+    };
+    store::with_conn(|conn| {
+        conn.execute(
+            "UPDATE outbox SET status = ?2, attempts = ?3, next_attempt_at = ?4, last_error = ?5, delivered_at = ?6 WHERE id = ?1",
+            params![entry.id, status, attempts, next, error, delivered],
+        )?;
+        conn.query_row(
+            "SELECT * FROM outbox WHERE id = ?1",
+            [&entry.id],
+            OutboxEntry::from_row,
+        )
+    })
+}
+
+fn send(app: &AppHandle, effect: &Effect) -> Result<(), String> {
+    match effect {
+        Effect::Notification { request } => {
+            notifications::notify(app, request.clone());
+            Ok(())
+        }
+        Effect::Integration {
+            integration_id,
+            body,
+        } => integrations::deliver(integration_id, body),
+        Effect::Email { subject, body } => email::send(subject, body).map_err(|e| e.to_string()),
+    }
+}
+
+fn prune() -> Result<(), String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM outbox WHERE status IN ('delivered', 'failed') AND id NOT IN (SELECT id FROM outbox WHERE status IN ('delivered', 'failed') ORDER BY created_at DESC LIMIT ?1)",
+            [HISTORY_LIMIT],
+        )
+        .map(|_| ())
+    })
+}
+
+/// Deliver everything that's due, in the background
+fn flush(app: AppHandle) {
+    if FLUSHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || {
+        let online = connectivity::is_online();
+        for entry in due().unwrap_or_default() {
+            // Failures while offline would only burn attempts
+            if entry.effect.needs_network() && !online {
+                continue;
+            }
+            if !claim(&entry.id).unwrap_or(false) {
+                continue;
+            }
+            let result = send(&app, &entry.effect);
+            if let Err(ref e) = result {
+                eprintln!(
+                    "[Claude PM] Outbox {} {} failed: {}",
+                    entry.effect.kind(),
+                    entry.id,
+                    e
+                );
+            }
+            match record(&entry, result.err()) {
+                Ok(updated) => {
+                    let _ = app.emit("outbox-delivery", updated);
+                }
+                Err(e) => eprintln!("[Claude PM] Failed to record outbox delivery: {}", e),
+            }
+        }
+        let _ = prune();
+        FLUSHING.store(false, Ordering::SeqCst);
+    });
+}
+
+pub fn start(app: AppHandle) {
+    if APP.set(app.clone()).is_err() {
+        return;
+    }
+    // Sends cut off by a crash or quit are retried
+    if let Err(e) = store::with_conn(|conn| {
+        conn.execute(
+            "UPDATE outbox SET status = 'pending' WHERE status = 'sending'",
+            [],
+        )
+        .map(|_| ())
+    }) {
+        eprintln!("[Claude PM] Failed to recover outbox: {}", e);
+    }
+    thread::spawn(move || loop {
+        flush(app.clone());
+        thread::sleep(TICK);
+    });
+}
+
+/// Effects waiting to be delivered (or being delivered right now), oldest first
+#[tauri::command]
+pub fn list_pending_deliveries() -> Result<Vec<OutboxEntry>, Error> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT * FROM outbox WHERE status IN ('pending', 'sending') ORDER BY created_at",
+        )?;
+        let rows = stmt.query_map([], OutboxEntry::from_row)?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn discard_pending_delivery(id: String) -> Result<(), Error> {
+    let deleted = store::with_conn(|conn| {
+        conn.query_row(
+            "DELETE FROM outbox WHERE id = ?1 AND status = 'pending' RETURNING id",
+            [&id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+    })?;
+    deleted
+        .map(|_| ())
+        .ok_or_else(|| Error::NotFound(format!("No pending delivery with id {}", id)))
+}
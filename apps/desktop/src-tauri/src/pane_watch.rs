@@ -0,0 +1,218 @@
+//! Watching multiplexer panes for activity, silence and bells
+//!
+//! Configured panes are captured every few seconds and compared with the last capture.
+//! Output after at least a minute of quiet counts as activity, no output for
+//! `silence_mins` as a (possibly stuck) silent agent, and a bell flagged on a tmux window
+//! as a bell. Each is emitted and turned into a notification, e.g. "The agent in dev:2
+//! has been silent for 10 minutes". Watches live in the config under `pane_watches`.
+//!
+//! Events:
+//! - `pane-activity`, `pane-silent` and `pane-bell` with a [`PaneEvent`]
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::notifications::{self, NotificationRequest};
+use crate::{config, multiplexer, power, tmux};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Output only counts as activity after this long without any
+const QUIET: Duration = Duration::from_secs(60);
+const DEFAULT_SILENCE_MINS: u64 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaneWatch {
+    /// Pane target, e.g. `dev:2` (see `multiplexer`)
+    pub target: String,
+    /// Shown in notifications instead of the target
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Minutes without output before the pane counts as silent; 10 when unset, 0 to
+    /// not watch for silence
+    #[serde(default)]
+    pub silence_mins: Option<u64>,
+    #[serde(default = "default_true")]
+    pub activity: bool,
+    #[serde(default = "default_true")]
+    pub bell: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaneEvent {
+    pub target: String,
+    pub label: String,
+    /// How long the pane had been quiet, for activity and silence
+    pub quiet_secs: u64,
+}
+
+struct PaneState {
+    hash: [u8; 32],
+    changed_at: Instant,
+    silent: bool,
+    bell: bool,
+}
+
+static STATES: Mutex<BTreeMap<String, PaneState>> = Mutex::new(BTreeMap::new());
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+fn label(watch: &PaneWatch) -> String {
+    watch
+        .label
+        .clone()
+        .filter(|label| !label.trim().is_empty())
+        .unwrap_or_else(|| format!("pane {}", watch.target))
+}
+
+fn minutes(duration: Duration) -> String {
+    match duration.as_secs() / 60 {
+        1 => "1 minute".to_string(),
+        n => format!("{} minutes", n),
+    }
+}
+
+/// tmux flags a bell on the window until someone looks at it
+fn bell_flag(target: &str) -> bool {
+    multiplexer::active().is_ok_and(|mux| mux.name() == "tmux")
+        && tmux::run(&["display-message", "-p", "-t", target, "#{window_bell_flag}"])
+            .is_ok_and(|out| out.trim() == "1")
+}
+
+fn report(app: &AppHandle, event: &str, watch: &PaneWatch, quiet: Duration, body: String) {
+    let payload = PaneEvent {
+        target: watch.target.clone(),
+        label: label(watch),
+        quiet_secs: quiet.as_secs(),
+    };
+    let _ = app.emit(event, payload);
+    notifications::notify(
+        app,
+        NotificationRequest {
+            title: "Claude PM".to_string(),
+            body,
+            key: Some(format!("{}:{}", event, watch.target)),
+            category: Some("pane-monitor".to_string()),
+            ..Default::default()
+        },
+    );
+}
+
+fn poll(app: &AppHandle, watch: &PaneWatch) {
+    let Ok(output) = multiplexer::active().and_then(|mux| mux.capture(&watch.target)) else {
+        // Gone for now; start over if it comes back
+        if let Ok(mut states) = STATES.lock() {
+            states.remove(&watch.target);
+        }
+        return;
+    };
+    let hash: [u8; 32] = Sha256::digest(output.as_bytes()).into();
+    let bell = watch.bell && bell_flag(&watch.target);
+    let silence = Duration::from_secs(60 * watch.silence_mins.unwrap_or(DEFAULT_SILENCE_MINS));
+
+    let Ok(mut states) = STATES.lock() else {
+        return;
+    };
+    let now = Instant::now();
+    let state = states
+        .entry(watch.target.clone())
+        .or_insert_with(|| PaneState {
+            hash,
+            changed_at: now,
+            silent: false,
+            bell,
+        });
+    let quiet = now.duration_since(state.changed_at);
+    let mut pending = Vec::new();
+
+    if state.hash != hash {
+        if watch.activity && quiet >= QUIET {
+            pending.push((
+                "pane-activity",
+                quiet,
+                format!("{} is active again after {}", label(watch), minutes(quiet)),
+            ));
+        }
+        state.hash = hash;
+        state.changed_at = now;
+        state.silent = false;
+    } else if !silence.is_zero() && quiet >= silence && !state.silent {
+        state.silent = true;
+        pending.push((
+            "pane-silent",
+            quiet,
+            format!(
+                "The agent in {} has been silent for {}",
+                label(watch),
+                minutes(quiet)
+            ),
+        ));
+    }
+    if bell && !state.bell {
+        pending.push((
+            "pane-bell",
+            quiet,
+            format!("{} rang the bell", label(watch)),
+        ));
+    }
+    state.bell = bell;
+    drop(states);
+
+    for (event, quiet, body) in pending {
+        report(app, event, watch, quiet, body);
+    }
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        let watches = config::load().pane_watches;
+        if let Ok(mut states) = STATES.lock() {
+            states.retain(|target, _| watches.iter().any(|w| &w.target == target));
+        }
+        for watch in &watches {
+            poll(&app, watch);
+        }
+        power::wait(POLL_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn list_pane_watches() -> Vec<PaneWatch> {
+    config::load().pane_watches
+}
+
+/// Add a watch, or replace the one for the same target
+#[tauri::command]
+pub fn set_pane_watch(watch: PaneWatch) -> Result<Vec<PaneWatch>, Error> {
+    if watch.target.trim().is_empty() {
+        return Err(Error::InvalidInput("A pane target is required".to_string()));
+    }
+    let config =
+        config::update(
+            |c| match c.pane_watches.iter_mut().find(|w| w.target == watch.target) {
+                Some(existing) => *existing = watch.clone(),
+                None => c.pane_watches.push(watch.clone()),
+            },
+        )?;
+    Ok(config.pane_watches)
+}
+
+#[tauri::command]
+pub fn remove_pane_watch(target: String) -> Result<Vec<PaneWatch>, Error> {
+    let config = config::update(|c| c.pane_watches.retain(|w| w.target != target))?;
+    Ok(config.pane_watches)
+}
@@ -0,0 +1,621 @@
+//! Minimal Markdown to PDF renderer
+//!
+//! Covers what generated reports use: headings, paragraphs, bullet lists, pipe tables,
+//! rules, code blocks and ```` ```chart ```` blocks of `Label: value` lines, drawn as
+//! horizontal bar charts. Pages are laid out here and written with `printpdf`. Text is
+//! set in the bundled DejaVu Sans (Latin, Greek, Cyrillic and more), embedded and
+//! subset; characters it lacks, such as CJK or emoji, come from the first system font
+//! in `FALLBACK_FONTS` that has them. The parsed blocks are also rendered as HTML by
+//! `static_site`.
+
+use printpdf::{
+    Color, FontId, Greyscale, Line, LinePoint, Op, PaintMode, ParsedFont, PdfDocument,
+    PdfFontHandle, PdfPage, PdfSaveOptions, Point, Pt, Rect, Rgb, TextItem,
+};
+use std::collections::BTreeSet;
+use std::fs;
+
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 56.0;
+const CONTENT_WIDTH: f64 = PAGE_WIDTH - 2.0 * MARGIN;
+const BODY_SIZE: f64 = 10.5;
+const LINE_HEIGHT: f64 = 1.4;
+const TABLE_ROW: f64 = 16.0;
+const CHART_BAR: f64 = 12.0;
+const CHART_LABEL_WIDTH: f64 = 130.0;
+
+const REGULAR_TTF: &[u8] = include_bytes!("../fonts/DejaVuSans.ttf");
+const BOLD_TTF: &[u8] = include_bytes!("../fonts/DejaVuSans-Bold.ttf");
+const MONO_TTF: &[u8] = include_bytes!("../fonts/DejaVuSansMono.ttf");
+
+/// Fonts with CJK, symbol and emoji outlines, tried in order for what DejaVu lacks
+#[cfg(target_os = "macos")]
+const FALLBACK_FONTS: &[&str] = &[
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/System/Library/Fonts/AppleSDGothicNeo.ttc",
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/Library/Fonts/Arial Unicode.ttf",
+    "/System/Library/Fonts/Apple Symbols.ttf",
+];
+#[cfg(windows)]
+const FALLBACK_FONTS: &[&str] = &[
+    r"C:\Windows\Fonts\msyh.ttc",
+    r"C:\Windows\Fonts\YuGothR.ttc",
+    r"C:\Windows\Fonts\malgun.ttf",
+    r"C:\Windows\Fonts\Nirmala.ttf",
+    r"C:\Windows\Fonts\seguisym.ttf",
+    r"C:\Windows\Fonts\seguiemj.ttf",
+];
+#[cfg(not(any(target_os = "macos", windows)))]
+const FALLBACK_FONTS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/noto/NotoSansSymbols2-Regular.ttf",
+    "/usr/share/fonts/truetype/noto/NotoEmoji-Regular.ttf",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Font {
+    Regular,
+    Bold,
+    Mono,
+}
+
+pub enum Block {
+    Heading(usize, String),
+    Paragraph(String),
+    Bullet(String),
+    Table(Vec<Vec<String>>),
+    Chart(Vec<(String, f64)>),
+    Code(Vec<String>),
+    Rule,
+}
+
+/// Drop inline markup: `**bold**`, `_em_`, `` `code` `` and `[text](url)`
+fn plain(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('[') {
+        let (before, after) = rest.split_at(start);
+        out.push_str(before);
+        match after
+            .find("](")
+            .and_then(|mid| after[mid..].find(')').map(|end| (mid, mid + end)))
+        {
+            Some((mid, end)) => {
+                out.push_str(&after[1..mid]);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('[');
+                rest = &after[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out.replace("**", "").replace('`', "")
+}
+
+fn table_cells(line: &str) -> Vec<String> {
+    line.trim()
+        .trim_matches('|')
+        .split('|')
+        .map(|cell| plain(cell.trim()))
+        .collect()
+}
+
+fn is_separator_row(line: &str) -> bool {
+    line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ')) && line.contains('-')
+}
+
+pub fn parse(markdown: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut lines = markdown.lines().peekable();
+    let mut paragraph: Vec<String> = Vec::new();
+
+    fn flush(paragraph: &mut Vec<String>, blocks: &mut Vec<Block>) {
+        if !paragraph.is_empty() {
+            blocks.push(Block::Paragraph(plain(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    }
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            flush(&mut paragraph, &mut blocks);
+        } else if let Some(lang) = trimmed.strip_prefix("```") {
+            flush(&mut paragraph, &mut blocks);
+            let mut body = Vec::new();
+            for line in lines.by_ref() {
+                if line.trim().starts_with("```") {
+                    break;
+                }
+                body.push(line.to_string());
+            }
+            if lang.trim() == "chart" {
+                let bars = body
+                    .iter()
+                    .filter_map(|line| line.rsplit_once(':'))
+                    .filter_map(|(label, value)| {
+                        Some((plain(label.trim()), value.trim().parse::<f64>().ok()?))
+                    })
+                    .collect();
+                blocks.push(Block::Chart(bars));
+            } else {
+                blocks.push(Block::Code(body));
+            }
+        } else if trimmed.starts_with('#') {
+            flush(&mut paragraph, &mut blocks);
+            let level = trimmed.chars().take_while(|&c| c == '#').count();
+            blocks.push(Block::Heading(level.min(3), plain(trimmed[level..].trim())));
+        } else if trimmed == "---" || trimmed == "***" {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Rule);
+        } else if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            flush(&mut paragraph, &mut blocks);
+            blocks.push(Block::Bullet(plain(item)));
+        } else if trimmed.starts_with('|') {
+            flush(&mut paragraph, &mut blocks);
+            let mut rows = vec![table_cells(trimmed)];
+            while let Some(next) = lines.peek().map(|l| l.trim()) {
+                if !next.starts_with('|') {
+                    break;
+                }
+                if !is_separator_row(next) {
+                    rows.push(table_cells(next));
+                }
+                lines.next();
+            }
+            blocks.push(Block::Table(rows));
+        } else {
+            paragraph.push(trimmed.to_string());
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+/// The bundled faces plus whichever fallbacks the document needs, as added to `doc`
+struct Fonts {
+    faces: Vec<(ParsedFont, FontId)>,
+}
+
+impl Fonts {
+    const FALLBACK_START: usize = 3;
+
+    fn load(doc: &mut PdfDocument, text: &BTreeSet<char>) -> Self {
+        let mut faces = Vec::new();
+        for bytes in [REGULAR_TTF, BOLD_TTF, MONO_TTF] {
+            let font =
+                ParsedFont::from_bytes(bytes, 0, &mut Vec::new()).expect("bundled font parses");
+            let id = doc.add_font(&font);
+            faces.push((font, id));
+        }
+        let mut fonts = Self { faces };
+        let mut missing: BTreeSet<char> = text
+            .iter()
+            .copied()
+            .filter(|&c| !c.is_whitespace() && fonts.glyph(0, c).is_none())
+            .collect();
+        for path in FALLBACK_FONTS {
+            if missing.is_empty() {
+                break;
+            }
+            let Some(font) = fs::read(path)
+                .ok()
+                .and_then(|bytes| ParsedFont::from_bytes(&bytes, 0, &mut Vec::new()))
+            else {
+                continue;
+            };
+            let before = missing.len();
+            missing.retain(|&c| font.lookup_glyph_index(c as u32).is_none());
+            if missing.len() < before {
+                let id = doc.add_font(&font);
+                fonts.faces.push((font, id));
+            }
+        }
+        fonts
+    }
+
+    fn glyph(&self, face: usize, c: char) -> Option<u16> {
+        self.faces[face]
+            .0
+            .lookup_glyph_index(c as u32)
+            .filter(|&gid| gid != 0)
+    }
+
+    /// The style's own face if it has `c`, else the first fallback that does
+    fn face_for(&self, c: char, font: Font) -> usize {
+        let own = font as usize;
+        if c.is_whitespace() || self.glyph(own, c).is_some() {
+            return own;
+        }
+        (Self::FALLBACK_START..self.faces.len())
+            .find(|&face| self.glyph(face, c).is_some())
+            .unwrap_or(own)
+    }
+
+    fn char_width(&self, c: char, font: Font) -> f64 {
+        let face = self.face_for(c, font);
+        let (parsed, _) = &self.faces[face];
+        let units_per_em = parsed.font_metrics.units_per_em.max(1);
+        let width = match self.glyph(face, c) {
+            Some(gid) => parsed.get_horizontal_advance(gid),
+            None => units_per_em / 2,
+        };
+        width as f64 * 1000.0 / units_per_em as f64
+    }
+
+    fn text_width(&self, text: &str, font: Font, size: f64) -> f64 {
+        text.chars().map(|c| self.char_width(c, font)).sum::<f64>() * size / 1000.0
+    }
+
+    /// `text` split into runs that each come from one face
+    fn runs(&self, text: &str, font: Font) -> Vec<(usize, String)> {
+        let mut runs: Vec<(usize, String)> = Vec::new();
+        for c in text.chars() {
+            let face = self.face_for(c, font);
+            match runs.last_mut() {
+                Some((last, run)) if *last == face => run.push(c),
+                _ => runs.push((face, c.to_string())),
+            }
+        }
+        runs
+    }
+
+    fn wrap(&self, text: &str, font: Font, size: f64, width: f64) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut line = String::new();
+        for word in text.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if self.text_width(&candidate, font, size) > width && !line.is_empty() {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+            } else {
+                line = candidate;
+            }
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Shorten `text` with an ellipsis so it fits in `width`
+    fn fit(&self, text: &str, font: Font, size: f64, width: f64) -> String {
+        if self.text_width(text, font, size) <= width {
+            return text.to_string();
+        }
+        let mut out = String::new();
+        for c in text.chars() {
+            if self.text_width(&format!("{}{}…", out, c), font, size) > width {
+                break;
+            }
+            out.push(c);
+        }
+        out.push('…');
+        out
+    }
+}
+
+fn point(x: f64, y: f64) -> Point {
+    Point {
+        x: Pt(x as f32),
+        y: Pt(y as f32),
+    }
+}
+
+struct Writer<'a> {
+    fonts: &'a Fonts,
+    pages: Vec<Vec<Op>>,
+    current: Vec<Op>,
+    y: f64,
+}
+
+impl<'a> Writer<'a> {
+    fn new(fonts: &'a Fonts) -> Self {
+        Self {
+            fonts,
+            pages: Vec::new(),
+            current: Vec::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    /// Start a new page unless `height` still fits on this one
+    fn ensure(&mut self, height: f64) {
+        if self.y - height < MARGIN {
+            self.pages.push(std::mem::take(&mut self.current));
+            self.y = PAGE_HEIGHT - MARGIN;
+        }
+    }
+
+    fn text(&mut self, x: f64, y: f64, text: &str, font: Font, size: f64) {
+        self.current.push(Op::StartTextSection);
+        self.current.push(Op::SetTextCursor { pos: point(x, y) });
+        for (face, run) in self.fonts.runs(text, font) {
+            self.current.push(Op::SetFont {
+                font: PdfFontHandle::External(self.fonts.faces[face].1.clone()),
+                size: Pt(size as f32),
+            });
+            self.current.push(Op::ShowText {
+                items: vec![TextItem::Text(run)],
+            });
+        }
+        self.current.push(Op::EndTextSection);
+    }
+
+    fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) {
+        self.current.extend([
+            Op::SaveGraphicsState,
+            Op::SetOutlineColor {
+                col: Color::Greyscale(Greyscale::new(0.8, None)),
+            },
+            Op::SetOutlineThickness { pt: Pt(0.5) },
+            Op::DrawLine {
+                line: Line {
+                    points: [point(x1, y1), point(x2, y2)]
+                        .map(|p| LinePoint { p, bezier: false })
+                        .to_vec(),
+                    is_closed: false,
+                },
+            },
+            Op::RestoreGraphicsState,
+        ]);
+    }
+
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64, fill: Color) {
+        self.current.extend([
+            Op::SaveGraphicsState,
+            Op::SetFillColor { col: fill },
+            Op::DrawRectangle {
+                rectangle: Rect {
+                    mode: Some(PaintMode::Fill),
+                    ..Rect::from_xywh(Pt(x as f32), Pt(y as f32), Pt(w as f32), Pt(h as f32))
+                },
+            },
+            Op::RestoreGraphicsState,
+        ]);
+    }
+    fn lines(&mut self, lines: &[String], x: f64, font: Font, size: f64) {
+        let step = size * LINE_HEIGHT;
+        for line in lines {
+            self.ensure(step);
+            self.y -= size;
+            self.text(x, self.y, line, font, size);
+            self.y -= step - size;
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        match block {
+            Block::Heading(level, text) => {
+                let size = match level {
+                    1 => 20.0,
+                    2 => 15.0,
+                    _ => 12.0,
+                };
+                self.ensure(size * 3.0);
+                self.y -= size * 0.6;
+                let lines = self.fonts.wrap(text, Font::Bold, size, CONTENT_WIDTH);
+                self.lines(&lines, MARGIN, Font::Bold, size);
+                self.y -= size * 0.2;
+            }
+            Block::Paragraph(text) => {
+                let lines = self
+                    .fonts
+                    .wrap(text, Font::Regular, BODY_SIZE, CONTENT_WIDTH);
+                self.lines(&lines, MARGIN, Font::Regular, BODY_SIZE);
+                self.y -= BODY_SIZE * 0.5;
+            }
+            Block::Bullet(text) => {
+                let indent = 14.0;
+                let lines = self
+                    .fonts
+                    .wrap(text, Font::Regular, BODY_SIZE, CONTENT_WIDTH - indent);
+                self.ensure(BODY_SIZE * LINE_HEIGHT);
+                self.text(
+                    MARGIN + 3.0,
+                    self.y - BODY_SIZE,
+                    "•",
+                    Font::Regular,
+                    BODY_SIZE,
+                );
+                self.lines(&lines, MARGIN + indent, Font::Regular, BODY_SIZE);
+            }
+            Block::Rule => {
+                self.ensure(12.0);
+                self.y -= 6.0;
+                self.line(MARGIN, self.y, PAGE_WIDTH - MARGIN, self.y);
+                self.y -= 6.0;
+            }
+            Block::Code(lines) => {
+                let size = 9.0;
+                let fitted: Vec<String> = lines
+                    .iter()
+                    .map(|l| self.fonts.fit(l, Font::Mono, size, CONTENT_WIDTH - 8.0))
+                    .collect();
+                self.lines(&fitted, MARGIN + 8.0, Font::Mono, size);
+                self.y -= BODY_SIZE * 0.5;
+            }
+            Block::Table(rows) => self.table(rows),
+            Block::Chart(bars) => self.chart(bars),
+        }
+    }
+
+    fn table(&mut self, rows: &[Vec<String>]) {
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        let size = 9.5;
+        let padding = 4.0;
+        // Natural column widths, scaled down proportionally if the table is too wide
+        let mut widths: Vec<f64> = (0..columns)
+            .map(|col| {
+                rows.iter()
+                    .filter_map(|row| row.get(col))
+                    .map(|cell| self.fonts.text_width(cell, Font::Bold, size) + 2.0 * padding)
+                    .fold(30.0, f64::max)
+            })
+            .collect();
+        let total: f64 = widths.iter().sum();
+        if total > CONTENT_WIDTH {
+            widths.iter_mut().for_each(|w| *w *= CONTENT_WIDTH / total);
+        }
+        let table_width: f64 = widths.iter().sum();
+
+        for (index, row) in rows.iter().enumerate() {
+            self.ensure(TABLE_ROW);
+            let top = self.y;
+            let font = if index == 0 {
+                Font::Bold
+            } else {
+                Font::Regular
+            };
+            if index == 0 {
+                self.rect(
+                    MARGIN,
+                    top - TABLE_ROW,
+                    table_width,
+                    TABLE_ROW,
+                    Color::Greyscale(Greyscale::new(0.93, None)),
+                );
+            }
+            let mut x = MARGIN;
+            for (col, width) in widths.iter().enumerate() {
+                let cell = row.get(col).map(String::as_str).unwrap_or("");
+                let text = self.fonts.fit(cell, font, size, width - 2.0 * padding);
+                self.text(x + padding, top - TABLE_ROW + 4.5, &text, font, size);
+                x += width;
+            }
+            self.y -= TABLE_ROW;
+            self.line(MARGIN, self.y, MARGIN + table_width, self.y);
+        }
+        self.y -= BODY_SIZE;
+    }
+
+    fn chart(&mut self, bars: &[(String, f64)]) {
+        let max = bars.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+        let size = 9.0;
+        let bar_area = CONTENT_WIDTH - CHART_LABEL_WIDTH - 50.0;
+        for (label, value) in bars {
+            let row = CHART_BAR + 5.0;
+            self.ensure(row);
+            let base = self.y - CHART_BAR;
+            let label = self
+                .fonts
+                .fit(label, Font::Regular, size, CHART_LABEL_WIDTH - 8.0);
+            self.text(MARGIN, base + 2.5, &label, Font::Regular, size);
+            let width = if max > 0.0 {
+                bar_area * value / max
+            } else {
+                0.0
+            };
+            self.rect(
+                MARGIN + CHART_LABEL_WIDTH,
+                base,
+                width.max(1.0),
+                CHART_BAR,
+                Color::Rgb(Rgb::new(0.29, 0.47, 0.86, None)),
+            );
+            let shown = if value.fract() == 0.0 {
+                format!("{}", value)
+            } else {
+                format!("{:.1}", value)
+            };
+            self.text(
+                MARGIN + CHART_LABEL_WIDTH + width + 4.0,
+                base + 2.5,
+                &shown,
+                Font::Regular,
+                size,
+            );
+            self.y -= row;
+        }
+        self.y -= BODY_SIZE;
+    }
+
+    fn finish(mut self) -> Vec<Vec<Op>> {
+        if !self.current.is_empty() || self.pages.is_empty() {
+            self.pages.push(self.current);
+        }
+        self.pages
+    }
+}
+
+/// Render Markdown to a complete PDF document
+pub fn render(markdown: &str, title: &str) -> Vec<u8> {
+    let blocks = parse(markdown);
+    let chars: BTreeSet<char> = markdown.chars().chain("•…".chars()).collect();
+    let mut doc = PdfDocument::new(title);
+    let fonts = Fonts::load(&mut doc, &chars);
+    let mut writer = Writer::new(&fonts);
+    for block in &blocks {
+        writer.block(block);
+    }
+    let pages = writer
+        .finish()
+        .into_iter()
+        .map(|ops| {
+            PdfPage::new(
+                Pt(PAGE_WIDTH as f32).into(),
+                Pt(PAGE_HEIGHT as f32).into(),
+                ops,
+            )
+        })
+        .collect();
+    let options = PdfSaveOptions {
+        subset_fonts: true,
+        ..Default::default()
+    };
+    doc.with_pages(pages).save(&options, &mut Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_fonts_cover_latin_greek_and_cyrillic() {
+        let mut doc = PdfDocument::new("test");
+        let fonts = Fonts::load(&mut doc, &BTreeSet::new());
+        for text in ["Résumé – naïve", "Ελληνικά", "Привет"] {
+            assert!(fonts
+                .runs(text, Font::Regular)
+                .iter()
+                .all(|(face, _)| *face == 0));
+            assert!(text
+                .chars()
+                .all(|c| fonts.glyph(0, c).is_some() || c == ' '));
+        }
+        assert!(
+            fonts.text_width("WWW", Font::Regular, 10.0)
+                > fonts.text_width("iii", Font::Regular, 10.0)
+        );
+        assert_eq!(
+            fonts.text_width("iii", Font::Mono, 10.0),
+            fonts.text_width("WWW", Font::Mono, 10.0)
+        );
+    }
+
+    #[test]
+    fn renders_text_the_bundled_fonts_lack() {
+        let pdf = render(
+            "# 週報\n\n- 完了したタスク 🎉\n\n| 名前 | 値 |\n|---|---|\n| a | 1 |",
+            "週報",
+        );
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert!(pdf.windows(9).any(|w| w == b"/FontFile"));
+    }
+}
@@ -0,0 +1,145 @@
+//! macOS privacy permission checks (Automation, Notifications, Full Disk Access, Screen
+//! Recording)
+//!
+//! None of these have a public query API for unsigned helpers, so each check probes
+//! the behaviour the permission gates and reports what it observed.
+
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::Command;
+use tauri::AppHandle;
+use tauri_plugin_notification::{NotificationExt, PermissionState};
+
+use crate::applescript;
+use crate::error::Error;
+use crate::process;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionStatus {
+    Granted,
+    Denied,
+    /// The user hasn't been asked yet
+    NotDetermined,
+    /// The permission doesn't exist on this platform
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionReport {
+    pub automation: PermissionStatus,
+    pub notifications: PermissionStatus,
+    pub full_disk_access: PermissionStatus,
+    pub screen_recording: PermissionStatus,
+}
+
+/// Send a harmless AppleEvent to System Events; the first call triggers the consent prompt
+fn check_automation() -> PermissionStatus {
+    if !cfg!(target_os = "macos") {
+        return PermissionStatus::Unsupported;
+    }
+    match applescript::run("tell application \"System Events\" to return name of first process") {
+        Ok(_) => PermissionStatus::Granted,
+        Err(Error::PermissionDenied { .. }) => PermissionStatus::Denied,
+        Err(_) => PermissionStatus::NotDetermined,
+    }
+}
+
+fn check_notifications(app: &AppHandle) -> PermissionStatus {
+    match app.notification().permission_state() {
+        Ok(PermissionState::Granted) => PermissionStatus::Granted,
+        Ok(PermissionState::Denied) => PermissionStatus::Denied,
+        Ok(_) => PermissionStatus::NotDetermined,
+        Err(_) => PermissionStatus::Unsupported,
+    }
+}
+
+/// The TCC database is only readable by processes with Full Disk Access
+fn check_full_disk_access() -> PermissionStatus {
+    if !cfg!(target_os = "macos") {
+        return PermissionStatus::Unsupported;
+    }
+    let Some(home) = dirs::home_dir() else {
+        return PermissionStatus::NotDetermined;
+    };
+    let tcc: PathBuf = home.join("Library/Application Support/com.apple.TCC/TCC.db");
+    match std::fs::File::open(tcc) {
+        Ok(_) => PermissionStatus::Granted,
+        Err(_) => PermissionStatus::Denied,
+    }
+}
+
+/// Screen Recording is one of the few with a query API; JXA can call CoreGraphics directly
+fn screen_capture_access(function: &str) -> Option<bool> {
+    let script = format!("ObjC.import('CoreGraphics'); $.{}()", function);
+    let output =
+        process::output(Command::new("osascript").args(["-l", "JavaScript", "-e", &script]))
+            .ok()?;
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+pub fn check_screen_recording() -> PermissionStatus {
+    if !cfg!(target_os = "macos") {
+        return PermissionStatus::Unsupported;
+    }
+    match screen_capture_access("CGPreflightScreenCaptureAccess") {
+        Some(true) => PermissionStatus::Granted,
+        Some(false) => PermissionStatus::Denied,
+        None => PermissionStatus::NotDetermined,
+    }
+}
+
+/// Show the system's Screen Recording prompt (only the first time it is asked)
+pub fn request_screen_recording() {
+    if cfg!(target_os = "macos") {
+        let _ = screen_capture_access("CGRequestScreenCaptureAccess");
+    }
+}
+
+fn settings_url(permission: &str) -> Option<&'static str> {
+    match permission {
+        "automation" => {
+            Some("x-apple.systempreferences:com.apple.preference.security?Privacy_Automation")
+        }
+        "accessibility" => {
+            Some("x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility")
+        }
+        "notifications" => Some("x-apple.systempreferences:com.apple.preference.notifications"),
+        "fullDiskAccess" => {
+            Some("x-apple.systempreferences:com.apple.preference.security?Privacy_AllFiles")
+        }
+        "screenRecording" => {
+            Some("x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture")
+        }
+        _ => None,
+    }
+}
+
+#[tauri::command]
+pub fn get_permission_status(app: AppHandle) -> PermissionReport {
+    PermissionReport {
+        automation: check_automation(),
+        notifications: check_notifications(&app),
+        full_disk_access: check_full_disk_access(),
+        screen_recording: check_screen_recording(),
+    }
+}
+
+/// Open the System Settings pane for `permission` (`automation`, `accessibility`,
+/// `notifications`, `fullDiskAccess` or `screenRecording`)
+#[tauri::command]
+pub fn open_permission_settings(permission: String) -> Result<(), Error> {
+    if !cfg!(target_os = "macos") {
+        return Err(Error::Unsupported(
+            "Permission settings are only available on macOS".to_string(),
+        ));
+    }
+    let url = settings_url(&permission)
+        .ok_or_else(|| Error::InvalidInput(format!("Unknown permission: {}", permission)))?;
+    process::output(Command::new("open").arg(url)).map(|_| ())
+}
@@ -0,0 +1,804 @@
+//! WASM plugins loaded from the `plugins` directory in the config directory
+//!
+//! Each plugin is a folder holding a `plugin.json` manifest and a WASM module. The
+//! manifest lists the capabilities the plugin wants (`readTasks`, `notify`,
+//! `registerCommands`) and the commands and events it handles; nothing is granted until
+//! the user approves it, and a plugin only loads while enabled. Grants and enable
+//! toggles are kept in plugins.json.
+//!
+//! Modules run in wasmi, an interpreter with no access to the host beyond the functions
+//! imported from `claudepm`, each of which fails with `-1` unless its capability was
+//! granted:
+//! - `read_tasks(ptr, cap) -> len` writes the tasks as JSON if they fit in `cap` bytes
+//! - `notify(title_ptr, title_len, body_ptr, body_len) -> 0` shows a notification
+//! - `register_command(name_ptr, name_len, title_ptr, title_len) -> 0` adds a command
+//!   backed by the module's `command_<name>` export
+//!
+//! A loaded plugin is instantiated once and kept running: its optional `init` export is
+//! called on load and `shutdown` when it's disabled. Events go to `on_event(ptr, len)`
+//! as JSON written into memory from the module's `alloc(len) -> ptr`. Every call gets a
+//! fuel budget and memory is capped, so a runaway plugin traps instead of hanging.
+//!
+//! Events:
+//! - `plugins-changed` after a plugin is enabled, disabled or granted capabilities
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use tauri::{AppHandle, Emitter};
+use wasmi::{
+    Caller, Config, Engine, Extern, ExternType, Instance, Linker, Module, Store, StoreLimits,
+    StoreLimitsBuilder,
+};
+
+use crate::config;
+use crate::error::Error;
+use crate::integrations::{Event, EventKind};
+use crate::notifications::{self, NotificationRequest};
+use crate::store;
+
+pub const PLUGINS_DIR: &str = "plugins";
+pub const STATE_FILE: &str = "plugins.json";
+const MANIFEST_FILE: &str = "plugin.json";
+const HOST_MODULE: &str = "claudepm";
+/// Instructions (roughly) a single call into a plugin may run
+const FUEL_PER_CALL: u64 = 50_000_000;
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+/// Returned by host functions the plugin wasn't granted
+const DENIED: i32 = -1;
+const FAILED: i32 = -2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Capability {
+    ReadTasks,
+    Notify,
+    RegisterCommands,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginCommand {
+    /// Exported by the module as `command_<name>`
+    pub name: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// Module path relative to the plugin folder
+    #[serde(default = "default_module")]
+    pub module: String,
+    #[serde(default)]
+    pub capabilities: BTreeSet<Capability>,
+    #[serde(default)]
+    pub commands: Vec<PluginCommand>,
+    /// Delivered to the module's `on_event` export
+    #[serde(default)]
+    pub events: BTreeSet<EventKind>,
+}
+
+fn default_module() -> String {
+    "plugin.wasm".to_string()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginState {
+    enabled: bool,
+    granted: BTreeSet<Capability>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PluginStatus {
+    Disabled,
+    /// Enabled but waiting for the user to grant requested capabilities
+    NeedsApproval,
+    Loaded,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    /// Folder name, which identifies the plugin
+    pub id: String,
+    pub path: String,
+    pub manifest: Option<Manifest>,
+    pub enabled: bool,
+    pub granted: BTreeSet<Capability>,
+    pub exports: Vec<String>,
+    /// Commands the running module added with `register_command`
+    pub registered: Vec<PluginCommand>,
+    pub status: PluginStatus,
+    pub error: Option<String>,
+}
+
+static STATE: Mutex<Option<BTreeMap<String, PluginState>>> = Mutex::new(None);
+
+fn plugins_dir() -> Result<PathBuf, String> {
+    config::config_dir()
+        .map(|dir| dir.join(PLUGINS_DIR))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+fn state_path() -> Result<PathBuf, String> {
+    config::config_dir()
+        .map(|dir| dir.join(STATE_FILE))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+fn load_state() -> BTreeMap<String, PluginState> {
+    state_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(state: &BTreeMap<String, PluginState>) -> Result<(), String> {
+    let path = state_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let contents = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write plugin state: {}", e))
+}
+
+/// Run `f` against the plugin grants and toggles, loading them on first use; saves if `f` returns true
+fn with_state<T>(
+    f: impl FnOnce(&mut BTreeMap<String, PluginState>) -> (T, bool),
+) -> Result<T, String> {
+    let mut guard = STATE.lock().map_err(|e| e.to_string())?;
+    let state = guard.get_or_insert_with(load_state);
+    let (result, changed) = f(state);
+    if changed {
+        save_state(state)?;
+    }
+    Ok(result)
+}
+
+/// What a running plugin may do, and the commands it registered
+struct Host {
+    granted: BTreeSet<Capability>,
+    registered: Vec<PluginCommand>,
+    limits: StoreLimits,
+}
+
+struct Running {
+    store: Store<Host>,
+    instance: Instance,
+    events: BTreeSet<EventKind>,
+}
+
+static ENGINE: OnceLock<Engine> = OnceLock::new();
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static RUNNING: Mutex<BTreeMap<String, Running>> = Mutex::new(BTreeMap::new());
+/// Why a plugin that should be loaded failed to start
+static LOAD_ERRORS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+fn engine() -> &'static Engine {
+    ENGINE.get_or_init(|| {
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        Engine::new(&config)
+    })
+}
+
+fn compile(bytes: &[u8]) -> Result<Module, String> {
+    Module::new(engine(), bytes).map_err(|e| format!("Invalid WASM module: {}", e))
+}
+
+/// Names of the functions a module exports
+fn exported_functions(module: &Module) -> Vec<String> {
+    module
+        .exports()
+        .filter(|export| matches!(export.ty(), ExternType::Func(_)))
+        .map(|export| export.name().to_string())
+        .collect()
+}
+
+fn read_guest(caller: &Caller<'_, Host>, ptr: i32, len: i32) -> Option<Vec<u8>> {
+    let memory = caller.get_export("memory").and_then(Extern::into_memory)?;
+    let start = usize::try_from(ptr).ok()?;
+    let end = start.checked_add(usize::try_from(len).ok()?)?;
+    memory.data(caller).get(start..end).map(<[u8]>::to_vec)
+}
+
+fn read_guest_str(caller: &Caller<'_, Host>, ptr: i32, len: i32) -> Option<String> {
+    read_guest(caller, ptr, len).and_then(|bytes| String::from_utf8(bytes).ok())
+}
+
+fn write_guest(caller: &mut Caller<'_, Host>, ptr: i32, bytes: &[u8]) -> Option<()> {
+    let memory = caller.get_export("memory").and_then(Extern::into_memory)?;
+    let start = usize::try_from(ptr).ok()?;
+    memory.write(caller, start, bytes).ok()
+}
+
+fn granted(caller: &Caller<'_, Host>, capability: Capability) -> bool {
+    caller.data().granted.contains(&capability)
+}
+
+fn linker() -> Result<Linker<Host>, String> {
+    let mut linker = Linker::new(engine());
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "read_tasks",
+            |mut caller: Caller<'_, Host>, ptr: i32, cap: i32| -> i32 {
+                if !granted(&caller, Capability::ReadTasks) {
+                    return DENIED;
+                }
+                let Some(json) = store::list_tasks(None, None)
+                    .ok()
+                    .and_then(|tasks| serde_json::to_vec(&tasks).ok())
+                else {
+                    return FAILED;
+                };
+                let Ok(len) = i32::try_from(json.len()) else {
+                    return FAILED;
+                };
+                if len <= cap && write_guest(&mut caller, ptr, &json).is_none() {
+                    return FAILED;
+                }
+                len
+            },
+        )
+        .and_then(|linker| {
+            linker.func_wrap(
+                HOST_MODULE,
+                "notify",
+                |caller: Caller<'_, Host>,
+                 title_ptr: i32,
+                 title_len: i32,
+                 body_ptr: i32,
+                 body_len: i32|
+                 -> i32 {
+                    if !granted(&caller, Capability::Notify) {
+                        return DENIED;
+                    }
+                    let (Some(title), Some(body)) = (
+                        read_guest_str(&caller, title_ptr, title_len),
+                        read_guest_str(&caller, body_ptr, body_len),
+                    ) else {
+                        return FAILED;
+                    };
+                    if let Some(app) = APP.get() {
+                        notifications::notify(
+                            app,
+                            NotificationRequest {
+                                title,
+                                body,
+                                category: Some("plugin".to_string()),
+                                ..Default::default()
+                            },
+                        );
+                    }
+                    0
+                },
+            )
+        })
+        .and_then(|linker| {
+            linker.func_wrap(
+                HOST_MODULE,
+                "register_command",
+                |mut caller: Caller<'_, Host>,
+                 name_ptr: i32,
+                 name_len: i32,
+                 title_ptr: i32,
+                 title_len: i32|
+                 -> i32 {
+                    if !granted(&caller, Capability::RegisterCommands) {
+                        return DENIED;
+                    }
+                    let (Some(name), Some(title)) = (
+                        read_guest_str(&caller, name_ptr, name_len),
+                        read_guest_str(&caller, title_ptr, title_len),
+                    ) else {
+                        return FAILED;
+                    };
+                    let export = format!("command_{}", name);
+                    if caller
+                        .get_export(&export)
+                        .and_then(Extern::into_func)
+                        .is_none()
+                    {
+                        return FAILED;
+                    }
+                    let registered = &mut caller.data_mut().registered;
+                    registered.retain(|c| c.name != name);
+                    registered.push(PluginCommand { name, title });
+                    0
+                },
+            )
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(linker)
+}
+
+/// Run an export taking `params` and returning nothing, with a fresh fuel budget
+fn call<Params: wasmi::WasmParams>(
+    running: &mut Running,
+    name: &str,
+    params: Params,
+) -> Result<(), String> {
+    let func = running
+        .instance
+        .get_typed_func::<Params, ()>(&running.store, name)
+        .map_err(|e| format!("Bad {} export: {}", name, e))?;
+    running
+        .store
+        .set_fuel(FUEL_PER_CALL)
+        .map_err(|e| e.to_string())?;
+    func.call(&mut running.store, params)
+        .map_err(|e| format!("{} failed: {}", name, e))
+}
+
+fn has_export(running: &Running, name: &str) -> bool {
+    running.instance.get_func(&running.store, name).is_some()
+}
+
+/// Instantiate a module with `granted` capabilities and run its `init`
+fn instantiate(
+    bytes: &[u8],
+    granted: BTreeSet<Capability>,
+    events: BTreeSet<EventKind>,
+) -> Result<Running, String> {
+    let module = compile(bytes)?;
+    let host = Host {
+        granted,
+        registered: Vec::new(),
+        limits: StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY_BYTES)
+            .build(),
+    };
+    let mut store = Store::new(engine(), host);
+    store.limiter(|host| &mut host.limits);
+    store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+    let instance = linker()?
+        .instantiate_and_start(&mut store, &module)
+        .map_err(|e| format!("Failed to start plugin: {}", e))?;
+    let mut running = Running {
+        store,
+        instance,
+        events,
+    };
+    if has_export(&running, "init") {
+        call(&mut running, "init", ())?;
+    }
+    Ok(running)
+}
+
+/// Copy `bytes` into the plugin's memory via its `alloc` export
+fn pass_bytes(running: &mut Running, bytes: &[u8]) -> Result<(i32, i32), String> {
+    let len = i32::try_from(bytes.len()).map_err(|_| "Payload too large".to_string())?;
+    let alloc = running
+        .instance
+        .get_typed_func::<i32, i32>(&running.store, "alloc")
+        .map_err(|e| format!("Bad alloc export: {}", e))?;
+    running
+        .store
+        .set_fuel(FUEL_PER_CALL)
+        .map_err(|e| e.to_string())?;
+    let ptr = alloc
+        .call(&mut running.store, len)
+        .map_err(|e| format!("alloc failed: {}", e))?;
+    let memory = running
+        .instance
+        .get_memory(&running.store, "memory")
+        .ok_or("Module doesn't export its memory")?;
+    let start = usize::try_from(ptr).map_err(|_| "alloc returned a bad pointer".to_string())?;
+    memory
+        .write(&mut running.store, start, bytes)
+        .map_err(|e| format!("Failed to write to plugin memory: {}", e))?;
+    Ok((ptr, len))
+}
+
+fn read_manifest(dir: &Path) -> Result<Manifest, String> {
+    let path = dir.join(MANIFEST_FILE);
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let manifest: Manifest =
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid {}: {}", MANIFEST_FILE, e))?;
+    if !manifest.commands.is_empty()
+        && !manifest
+            .capabilities
+            .contains(&Capability::RegisterCommands)
+    {
+        return Err("Commands require the registerCommands capability".to_string());
+    }
+    Ok(manifest)
+}
+
+fn module_path(dir: &Path, manifest: &Manifest) -> Result<PathBuf, String> {
+    let path = dir.join(&manifest.module);
+    if !path.starts_with(dir) || manifest.module.contains("..") {
+        return Err("Module must be inside the plugin folder".to_string());
+    }
+    Ok(path)
+}
+
+/// Check that the module compiles and exports everything the manifest promises
+fn load_module(dir: &Path, manifest: &Manifest) -> Result<Vec<String>, String> {
+    let path = module_path(dir, manifest)?;
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let exports = exported_functions(&compile(&bytes)?);
+    let mut required: Vec<String> = manifest
+        .commands
+        .iter()
+        .map(|c| format!("command_{}", c.name))
+        .collect();
+    if !manifest.events.is_empty() {
+        required.push("on_event".to_string());
+        required.push("alloc".to_string());
+    }
+    if let Some(missing) = required.iter().find(|name| !exports.contains(name)) {
+        return Err(format!("Module doesn't export {}", missing));
+    }
+    Ok(exports)
+}
+
+fn inspect(dir: &Path, state: &PluginState) -> PluginInfo {
+    let id = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut info = PluginInfo {
+        id,
+        path: dir.display().to_string(),
+        manifest: None,
+        enabled: state.enabled,
+        granted: state.granted.clone(),
+        exports: Vec::new(),
+        registered: Vec::new(),
+        status: PluginStatus::Disabled,
+        error: None,
+    };
+    let result = read_manifest(dir).and_then(|manifest| {
+        let exports = load_module(dir, &manifest);
+        info.manifest = Some(manifest);
+        exports
+    });
+    match result {
+        Ok(exports) => info.exports = exports,
+        Err(e) => {
+            info.status = PluginStatus::Error;
+            info.error = Some(e);
+            return info;
+        }
+    }
+    let requested = info
+        .manifest
+        .as_ref()
+        .map(|m| &m.capabilities)
+        .cloned()
+        .unwrap_or_default();
+    info.status = if !state.enabled {
+        PluginStatus::Disabled
+    } else if !requested.is_subset(&state.granted) {
+        PluginStatus::NeedsApproval
+    } else {
+        PluginStatus::Loaded
+    };
+    info
+}
+
+/// Plugins as found on disk, with `Loaded` meaning they should be running
+fn scan_files() -> Result<Vec<PluginInfo>, String> {
+    let dir = plugins_dir()?;
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+    with_state(|state| {
+        let plugins = dirs
+            .iter()
+            .map(|dir| {
+                let id = dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                inspect(dir, &state.get(&id).cloned().unwrap_or_default())
+            })
+            .collect();
+        (plugins, false)
+    })
+}
+
+/// Plugins with their runtime state: start failures and registered commands
+fn scan() -> Result<Vec<PluginInfo>, String> {
+    let mut plugins = scan_files()?;
+    let running = RUNNING.lock().map_err(|e| e.to_string())?;
+    let errors = LOAD_ERRORS.lock().map_err(|e| e.to_string())?;
+    for plugin in plugins
+        .iter_mut()
+        .filter(|p| p.status == PluginStatus::Loaded)
+    {
+        if let Some(error) = errors.get(&plugin.id) {
+            plugin.status = PluginStatus::Error;
+            plugin.error = Some(error.clone());
+        }
+        if let Some(running) = running.get(&plugin.id) {
+            plugin.registered = running.store.data().registered.clone();
+        }
+    }
+    Ok(plugins)
+}
+
+fn start_plugin(plugin: &PluginInfo) -> Result<Running, String> {
+    let manifest = plugin.manifest.as_ref().ok_or("Missing manifest")?;
+    let path = module_path(Path::new(&plugin.path), manifest)?;
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    instantiate(&bytes, plugin.granted.clone(), manifest.events.clone())
+}
+
+/// Start plugins that should be loaded, and stop those disabled, removed or whose grants
+/// changed (they restart with the new ones)
+fn sync() -> Result<(), String> {
+    let plugins = scan_files()?;
+    let wanted: BTreeMap<&str, &PluginInfo> = plugins
+        .iter()
+        .filter(|p| p.status == PluginStatus::Loaded)
+        .map(|p| (p.id.as_str(), p))
+        .collect();
+    let mut running = RUNNING.lock().map_err(|e| e.to_string())?;
+    let mut errors = LOAD_ERRORS.lock().map_err(|e| e.to_string())?;
+
+    let stale: Vec<String> = running
+        .iter()
+        .filter(|(id, plugin)| {
+            wanted
+                .get(id.as_str())
+                .is_none_or(|p| p.granted != plugin.store.data().granted)
+        })
+        .map(|(id, _)| id.clone())
+        .collect();
+    for id in stale {
+        if let Some(mut plugin) = running.remove(&id) {
+            if has_export(&plugin, "shutdown") {
+                if let Err(e) = call(&mut plugin, "shutdown", ()) {
+                    eprintln!("[Claude PM] Plugin {}: {}", id, e);
+                }
+            }
+        }
+    }
+
+    errors.clear();
+    for (id, plugin) in wanted {
+        if running.contains_key(id) {
+            continue;
+        }
+        match start_plugin(plugin) {
+            Ok(started) => {
+                running.insert(id.to_string(), started);
+            }
+            Err(e) => {
+                errors.insert(id.to_string(), e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Keep the handle for notifications and start the enabled plugins
+pub fn start(app: AppHandle) {
+    if APP.set(app).is_err() {
+        return;
+    }
+    thread::spawn(|| {
+        if let Err(e) = sync() {
+            eprintln!("[Claude PM] Failed to load plugins: {}", e);
+        }
+    });
+}
+
+/// Deliver `event` to every running plugin subscribed to it
+pub fn run_for_event(event: &Event) {
+    let payload = serde_json::json!({
+        "kind": event.kind,
+        "title": event.title,
+        "detail": event.detail,
+        "data": event.data,
+    })
+    .to_string();
+    let kind = event.kind;
+    thread::spawn(move || {
+        let Ok(mut running) = RUNNING.lock() else {
+            return;
+        };
+        for (id, plugin) in running.iter_mut() {
+            if !plugin.events.contains(&kind) {
+                continue;
+            }
+            let result = pass_bytes(plugin, payload.as_bytes())
+                .and_then(|(ptr, len)| call(plugin, "on_event", (ptr, len)));
+            if let Err(e) = result {
+                eprintln!("[Claude PM] Plugin {}: {}", id, e);
+            }
+        }
+    });
+}
+
+fn find(id: &str) -> Result<PluginInfo, Error> {
+    scan()?
+        .into_iter()
+        .find(|p| p.id == id)
+        .ok_or_else(|| Error::NotFound(format!("Plugin not found: {}", id)))
+}
+
+#[tauri::command]
+pub fn list_plugins() -> Result<Vec<PluginInfo>, Error> {
+    scan().map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn set_plugin_enabled(app: AppHandle, id: String, enabled: bool) -> Result<PluginInfo, Error> {
+    find(&id)?;
+    with_state(|state| {
+        state.entry(id.clone()).or_default().enabled = enabled;
+        ((), true)
+    })?;
+    sync()?;
+    let info = find(&id)?;
+    let _ = app.emit("plugins-changed", &info);
+    Ok(info)
+}
+
+/// Replace the capabilities granted to a plugin; only ones its manifest requests are kept
+#[tauri::command]
+pub fn grant_plugin_capabilities(
+    app: AppHandle,
+    id: String,
+    capabilities: BTreeSet<Capability>,
+) -> Result<PluginInfo, Error> {
+    let requested = find(&id)?
+        .manifest
+        .map(|m| m.capabilities)
+        .unwrap_or_default();
+    with_state(|state| {
+        state.entry(id.clone()).or_default().granted =
+            capabilities.intersection(&requested).copied().collect();
+        ((), true)
+    })?;
+    sync()?;
+    let info = find(&id)?;
+    let _ = app.emit("plugins-changed", &info);
+    Ok(info)
+}
+
+/// Commands of loaded plugins, from their manifests and `register_command`, as
+/// `(plugin id, command)`
+#[tauri::command]
+pub fn list_plugin_commands() -> Result<Vec<(String, PluginCommand)>, Error> {
+    Ok(scan()?
+        .into_iter()
+        .filter(|p| p.status == PluginStatus::Loaded)
+        .flat_map(|p| {
+            let id = p.id;
+            let mut commands = p.manifest.map(|m| m.commands).unwrap_or_default();
+            for command in p.registered {
+                if !commands.iter().any(|c| c.name == command.name) {
+                    commands.push(command);
+                }
+            }
+            commands.into_iter().map(move |c| (id.clone(), c))
+        })
+        .collect())
+}
+
+fn run_command(id: &str, command: &str) -> Result<(), Error> {
+    let registered = list_plugin_commands()?
+        .iter()
+        .any(|(plugin, c)| plugin == id && c.name == command);
+    if !registered {
+        return Err(Error::NotFound(format!("Command not found: {}", command)));
+    }
+    let mut running = RUNNING.lock().map_err(|e| e.to_string())?;
+    let plugin = running
+        .get_mut(id)
+        .ok_or_else(|| Error::InvalidInput(format!("Plugin {} is not loaded", id)))?;
+    call(plugin, &format!("command_{}", command), ()).map_err(Error::from)
+}
+
+#[tauri::command]
+pub async fn run_plugin_command(id: String, command: String) -> Result<(), Error> {
+    tauri::async_runtime::spawn_blocking(move || run_command(&id, &command))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULE: &str = r#"
+        (module
+          (import "claudepm" "register_command" (func $register (param i32 i32 i32 i32) (result i32)))
+          (import "claudepm" "notify" (func $notify (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "greet")
+          (data (i32.const 16) "Say hello")
+          (global $notified (export "notified") (mut i32) (i32.const 0))
+          (global $events (export "events") (mut i32) (i32.const 0))
+          (func (export "init")
+            (drop (call $register (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 9))))
+          (func (export "command_greet")
+            (global.set $notified (call $notify (i32.const 0) (i32.const 5) (i32.const 0) (i32.const 0))))
+          (func (export "command_spin") (loop $forever (br $forever)))
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "on_event") (param i32 i32)
+            (global.set $events (i32.add (global.get $events) (i32.const 1)))))
+    "#;
+
+    fn run(granted: &[Capability]) -> Running {
+        let bytes = wat::parse_str(MODULE).unwrap();
+        instantiate(
+            &bytes,
+            granted.iter().copied().collect(),
+            BTreeSet::from([EventKind::TaskCompleted]),
+        )
+        .unwrap()
+    }
+
+    fn global(running: &Running, name: &str) -> i32 {
+        running
+            .instance
+            .get_global(&running.store, name)
+            .unwrap()
+            .get(&running.store)
+            .i32()
+            .unwrap()
+    }
+
+    #[test]
+    fn lists_exported_functions() {
+        let module = compile(&wat::parse_str(MODULE).unwrap()).unwrap();
+        let exports = exported_functions(&module);
+        assert!(exports.contains(&"command_greet".to_string()));
+        assert!(!exports.contains(&"memory".to_string()));
+        assert!(compile(b"not wasm").is_err());
+    }
+
+    #[test]
+    fn host_functions_follow_grants() {
+        let mut granted = run(&[Capability::RegisterCommands, Capability::Notify]);
+        assert_eq!(granted.store.data().registered[0].name, "greet");
+        assert_eq!(granted.store.data().registered[0].title, "Say hello");
+        call(&mut granted, "command_greet", ()).unwrap();
+        assert_eq!(global(&granted, "notified"), 0);
+
+        let mut denied = run(&[]);
+        assert!(denied.store.data().registered.is_empty());
+        call(&mut denied, "command_greet", ()).unwrap();
+        assert_eq!(global(&denied, "notified"), DENIED);
+    }
+
+    #[test]
+    fn delivers_events_through_alloc() {
+        let mut running = run(&[]);
+        let (ptr, len) = pass_bytes(&mut running, br#"{"kind":"taskCompleted"}"#).unwrap();
+        assert_eq!((ptr, len), (1024, 24));
+        call(&mut running, "on_event", (ptr, len)).unwrap();
+        assert_eq!(global(&running, "events"), 1);
+    }
+
+    #[test]
+    fn runaway_calls_run_out_of_fuel() {
+        let mut running = run(&[]);
+        assert!(call(&mut running, "command_spin", ()).is_err());
+    }
+}
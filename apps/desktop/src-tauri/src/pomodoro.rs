@@ -0,0 +1,295 @@
+//! Pomodoro timer that runs in the app process
+//!
+//! Phases are timed against the wall clock, so a countdown stays correct across sleep
+//! and doesn't depend on the webview being open. The remaining time is shown as the
+//! tray title and each phase change raises a notification.
+//!
+//! Events:
+//! - `pomodoro-tick`: the current state, once a second while running
+//! - `pomodoro-phase`: a phase ended and the next one started
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::menubar::TRAY_ID;
+use crate::notifications::{self, NotificationRequest};
+use crate::{config, queue_badge, store};
+
+const TICK: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PomodoroSettings {
+    pub work_mins: u64,
+    pub short_break_mins: u64,
+    pub long_break_mins: u64,
+    /// Work phases before a long break
+    pub long_break_every: u32,
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        Self {
+            work_mins: 25,
+            short_break_mins: 5,
+            long_break_mins: 15,
+            long_break_every: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PomodoroState {
+    pub phase: Phase,
+    /// Unix millis; `None` while paused
+    pub ends_at: Option<i64>,
+    pub remaining_ms: i64,
+    pub paused: bool,
+    /// Work phases finished in this run
+    pub completed_work: u32,
+    pub task_id: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhaseChange {
+    finished: Phase,
+    phase: Phase,
+    completed_work: u32,
+}
+
+static STATE: Mutex<Option<PomodoroState>> = Mutex::new(None);
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+fn duration_ms(phase: Phase, settings: &PomodoroSettings) -> i64 {
+    let mins = match phase {
+        Phase::Work => settings.work_mins,
+        Phase::ShortBreak => settings.short_break_mins,
+        Phase::LongBreak => settings.long_break_mins,
+    };
+    (mins.max(1) * 60_000) as i64
+}
+
+fn next_phase(state: &PomodoroState, settings: &PomodoroSettings) -> Phase {
+    match state.phase {
+        Phase::Work
+            if state
+                .completed_work
+                .is_multiple_of(settings.long_break_every.max(1)) =>
+        {
+            Phase::LongBreak
+        }
+        Phase::Work => Phase::ShortBreak,
+        _ => Phase::Work,
+    }
+}
+
+/// Move to the next phase, counting a finished work phase
+fn advance(state: &mut PomodoroState) -> PhaseChange {
+    let settings = config::load().pomodoro;
+    let finished = state.phase;
+    if finished == Phase::Work {
+        state.completed_work += 1;
+    }
+    state.phase = next_phase(state, &settings);
+    state.remaining_ms = duration_ms(state.phase, &settings);
+    state.ends_at = (!state.paused).then(|| store::now_ms() + state.remaining_ms);
+    PhaseChange {
+        finished,
+        phase: state.phase,
+        completed_work: state.completed_work,
+    }
+}
+
+fn phase_label(phase: Phase) -> &'static str {
+    match phase {
+        Phase::Work => "Focus",
+        Phase::ShortBreak => "Short break",
+        Phase::LongBreak => "Long break",
+    }
+}
+
+fn tray_title(state: Option<&PomodoroState>) -> Option<String> {
+    let state = state?;
+    let secs = (state.remaining_ms.max(0) + 999) / 1000;
+    let icon = if state.phase == Phase::Work {
+        "🍅"
+    } else {
+        "☕"
+    };
+    let pause = if state.paused { " ⏸" } else { "" };
+    Some(format!(
+        "{} {:02}:{:02}{}",
+        icon,
+        secs / 60,
+        secs % 60,
+        pause
+    ))
+}
+
+fn update_tray(app: &AppHandle, state: Option<&PomodoroState>) {
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        // Back to the agent queue once the countdown stops
+        let _ = tray.set_title(tray_title(state).or_else(queue_badge::title));
+    }
+}
+
+fn announce(app: &AppHandle, change: PhaseChange) {
+    let minutes = duration_ms(change.phase, &config::load().pomodoro) / 60_000;
+    let (title, body) = match change.phase {
+        Phase::Work => (
+            "Break's over".to_string(),
+            format!("Back to work for {} minutes", minutes),
+        ),
+        _ => (
+            format!("Time for a {}", phase_label(change.phase).to_lowercase()),
+            format!(
+                "{} pomodoro(s) done — take {} minutes",
+                change.completed_work, minutes
+            ),
+        ),
+    };
+    notifications::notify(
+        app,
+        NotificationRequest {
+            title,
+            body,
+            key: Some(format!("pomodoro:{}", change.completed_work)),
+            category: Some("pomodoro".to_string()),
+            ..Default::default()
+        },
+    );
+    let _ = app.emit("pomodoro-phase", change);
+}
+
+fn ensure_ticker(app: &AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || loop {
+        thread::sleep(TICK);
+        let (state, change) = {
+            let Ok(mut guard) = STATE.lock() else {
+                continue;
+            };
+            let Some(state) = guard.as_mut() else {
+                continue;
+            };
+            let mut change = None;
+            if let Some(ends_at) = state.ends_at {
+                state.remaining_ms = ends_at - store::now_ms();
+                if state.remaining_ms <= 0 {
+                    change = Some(advance(state));
+                }
+            }
+            (state.clone(), change)
+        };
+        if let Some(change) = change {
+            announce(&app, change);
+        }
+        update_tray(&app, Some(&state));
+        let _ = app.emit("pomodoro-tick", state);
+    });
+}
+
+/// Apply `f` to the running pomodoro and refresh the tray
+fn modify(app: &AppHandle, f: impl FnOnce(&mut PomodoroState)) -> Result<PomodoroState, Error> {
+    let state = {
+        let mut guard = STATE.lock().map_err(|e| e.to_string())?;
+        let state = guard
+            .as_mut()
+            .ok_or_else(|| Error::NotFound("No pomodoro is running".to_string()))?;
+        f(state);
+        state.clone()
+    };
+    update_tray(app, Some(&state));
+    Ok(state)
+}
+
+/// Start a new run with a work phase, replacing any current one
+#[tauri::command]
+pub fn start_pomodoro(app: AppHandle, task_id: Option<String>) -> Result<PomodoroState, Error> {
+    let remaining_ms = duration_ms(Phase::Work, &config::load().pomodoro);
+    let state = PomodoroState {
+        phase: Phase::Work,
+        ends_at: Some(store::now_ms() + remaining_ms),
+        remaining_ms,
+        paused: false,
+        completed_work: 0,
+        task_id,
+    };
+    *STATE.lock().map_err(|e| e.to_string())? = Some(state.clone());
+    ensure_ticker(&app);
+    update_tray(&app, Some(&state));
+    Ok(state)
+}
+
+#[tauri::command]
+pub fn pause_pomodoro(app: AppHandle) -> Result<PomodoroState, Error> {
+    modify(&app, |state| {
+        if let Some(ends_at) = state.ends_at.take() {
+            state.remaining_ms = ends_at - store::now_ms();
+            state.paused = true;
+        }
+    })
+}
+
+#[tauri::command]
+pub fn resume_pomodoro(app: AppHandle) -> Result<PomodoroState, Error> {
+    modify(&app, |state| {
+        if state.paused {
+            state.paused = false;
+            state.ends_at = Some(store::now_ms() + state.remaining_ms);
+        }
+    })
+}
+
+/// End the current phase early and start the next
+#[tauri::command]
+pub fn skip_pomodoro_phase(app: AppHandle) -> Result<PomodoroState, Error> {
+    let mut change = None;
+    let state = modify(&app, |state| change = Some(advance(state)))?;
+    if let Some(change) = change {
+        let _ = app.emit("pomodoro-phase", change);
+    }
+    Ok(state)
+}
+
+#[tauri::command]
+pub fn stop_pomodoro(app: AppHandle) -> Result<(), Error> {
+    *STATE.lock().map_err(|e| e.to_string())? = None;
+    update_tray(&app, None);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_pomodoro_state() -> Option<PomodoroState> {
+    STATE.lock().ok().and_then(|state| state.clone())
+}
+
+/// New durations apply from the next phase
+#[tauri::command]
+pub fn set_pomodoro_settings(settings: PomodoroSettings) -> Result<(), Error> {
+    if settings.work_mins == 0 || settings.short_break_mins == 0 || settings.long_break_mins == 0 {
+        return Err(Error::InvalidInput(
+            "Pomodoro durations must be at least one minute".to_string(),
+        ));
+    }
+    config::update(|c| c.pomodoro = settings)?;
+    Ok(())
+}
@@ -0,0 +1,580 @@
+//! Sleep/wake detection and keep-awake assertions
+//!
+//! Sleep and wake come from the OS: NSWorkspace's will-sleep/did-wake notifications on
+//! macOS, logind's `PrepareForSleep` signal on Linux (read through `gdbus monitor`), and
+//! suspend/resume power broadcasts on Windows. From will-sleep until shortly after wake,
+//! pollers that wait through [`wait`] hold off and watchers skip their rounds
+//! ([`is_settling`]), so nothing reports the half-asleep state. On wake we re-verify the
+//! server (the npm child often dies across sleep) and emit `resumed` so the frontend can
+//! resync. Where no notification source is available, wake is inferred from the wall
+//! clock jumping further than our tick interval.
+//!
+//! Keep-awake assertions are held by a helper process (`caffeinate` on macOS, which takes
+//! an IOKit assertion; `systemd-inhibit` on Linux) that exits with the app, so an
+//! assertion can never outlive us.
+//!
+//! The power source is checked every minute (`pmset`, `/sys/class/power_supply` and
+//! `powerprofilesctl`, or `Win32_Battery`). On battery or in low-power mode, health checks
+//! and metrics polling stretch their intervals via `throttled` and transcript indexing
+//! pauses.
+//!
+//! Events:
+//! - `suspending` when the OS announces sleep
+//! - `resumed` after wake
+//! - `power-state` when the power source or low-power mode changes
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{process, store};
+
+const TICK: Duration = Duration::from_secs(5);
+/// Wall-clock gap beyond the tick that counts as having slept
+const SLEEP_THRESHOLD: Duration = Duration::from_secs(30);
+/// Watchers hold off this long after wake while the network and server settle
+const SETTLE: Duration = Duration::from_secs(15);
+/// Sleep announced this long ago without the clock jumping was called off
+const SLEEP_CALLED_OFF: Duration = Duration::from_secs(120);
+
+/// How often keep-awake holders are checked for a linked process having exited
+const ASSERTION_POLL: Duration = Duration::from_secs(2);
+
+/// How often the power source is re-read
+const POWER_POLL: Duration = Duration::from_secs(60);
+/// Factor polling intervals grow by while throttled
+const BATTERY_SLOWDOWN: u32 = 3;
+
+static RESUMED_AT: Mutex<Option<SystemTime>> = Mutex::new(None);
+/// Set from the OS announcing sleep until the matching wake
+static SLEEPING_SINCE: Mutex<Option<SystemTime>> = Mutex::new(None);
+/// An OS notification source is subscribed, so the wall-clock fallback stays quiet
+static OS_NOTIFICATIONS: AtomicBool = AtomicBool::new(false);
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static STARTED: AtomicBool = AtomicBool::new(false);
+static ASSERTIONS: Mutex<BTreeMap<String, Assertion>> = Mutex::new(BTreeMap::new());
+static POWER_STATE: Mutex<Option<PowerState>> = Mutex::new(None);
+
+struct Assertion {
+    info: KeepAwake,
+    holder: Child,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeepAwake {
+    pub id: String,
+    pub reason: String,
+    /// Process whose exit releases the assertion automatically
+    pub pid: Option<u32>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerState {
+    pub on_battery: bool,
+    pub low_power_mode: bool,
+    pub battery_percent: Option<u8>,
+    /// Background polling is slowed and indexing paused
+    pub throttled: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Resumed {
+    slept_secs: u64,
+    server_restarted: bool,
+}
+
+/// True while going to sleep and shortly after wake; watchers should skip a round rather
+/// than report stale state
+pub fn is_settling() -> bool {
+    let sleeping = SLEEPING_SINCE.lock().is_ok_and(|since| since.is_some());
+    sleeping
+        || RESUMED_AT
+            .lock()
+            .ok()
+            .and_then(|at| *at)
+            .and_then(|at| at.elapsed().ok())
+            .is_some_and(|elapsed| elapsed < SETTLE)
+}
+
+/// True on battery or in low-power mode; non-essential background work should wait
+pub fn is_throttled() -> bool {
+    POWER_STATE
+        .lock()
+        .ok()
+        .and_then(|state| state.as_ref().map(|s| s.throttled))
+        .unwrap_or(false)
+}
+
+/// `interval`, stretched while throttled
+pub fn throttled(interval: Duration) -> Duration {
+    if is_throttled() {
+        interval * BATTERY_SLOWDOWN
+    } else {
+        interval
+    }
+}
+
+/// Block while the machine is going to sleep or settling after wake
+pub fn wait_awake() {
+    while is_settling() {
+        thread::sleep(TICK);
+    }
+}
+
+/// A poller's pause between rounds: `interval` (stretched while throttled), then however
+/// long sleep and wake take
+pub fn wait(interval: Duration) {
+    thread::sleep(throttled(interval));
+    wait_awake();
+}
+
+fn command_stdout(cmd: &mut Command) -> Option<String> {
+    let output = process::output(cmd).ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "macos")]
+fn read_power_state() -> PowerState {
+    // "Now drawing from 'Battery Power'" then " -InternalBattery-0 (id=…)\t85%; discharging; …"
+    let batt = command_stdout(Command::new("pmset").args(["-g", "batt"])).unwrap_or_default();
+    let battery_percent = batt
+        .lines()
+        .filter_map(|line| line.split('%').next()?.rsplit(char::is_whitespace).next())
+        .find_map(|value| value.parse().ok());
+    let low_power_mode = command_stdout(Command::new("pmset").arg("-g"))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .any(|(key, value)| matches!(key, "lowpowermode" | "powermode") && value.trim() == "1");
+    PowerState {
+        on_battery: batt.contains("'Battery Power'"),
+        low_power_mode,
+        battery_percent,
+        throttled: false,
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_power_state() -> PowerState {
+    let read = |path: std::path::PathBuf| {
+        std::fs::read_to_string(path)
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default()
+    };
+    let mut mains_online = false;
+    let mut battery = None;
+    let supplies = std::fs::read_dir("/sys/class/power_supply")
+        .into_iter()
+        .flatten();
+    for supply in supplies.filter_map(Result::ok).map(|e| e.path()) {
+        match read(supply.join("type")).as_str() {
+            "Mains" => mains_online |= read(supply.join("online")) == "1",
+            "Battery" if battery.is_none() => {
+                battery = Some((read(supply.join("status")), read(supply.join("capacity"))))
+            }
+            _ => {}
+        }
+    }
+    let low_power_mode = process::which("powerprofilesctl").is_some()
+        && command_stdout(Command::new("powerprofilesctl").arg("get"))
+            .is_some_and(|profile| profile.trim() == "power-saver");
+    PowerState {
+        on_battery: battery
+            .as_ref()
+            .is_some_and(|(status, _)| !mains_online && status != "Full"),
+        low_power_mode,
+        battery_percent: battery.and_then(|(_, capacity)| capacity.parse().ok()),
+        throttled: false,
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn read_power_state() -> PowerState {
+    // BatteryStatus 1 is "discharging"; desktops have no Win32_Battery at all
+    let status = command_stdout(Command::new("powershell").args([
+        "-NoProfile",
+        "-Command",
+        "Get-CimInstance Win32_Battery | Select-Object -First 1 | ForEach-Object { \"$($_.BatteryStatus) $($_.EstimatedChargeRemaining)\" }",
+    ]))
+    .unwrap_or_default();
+    let mut fields = status.split_whitespace();
+    PowerState {
+        on_battery: fields.next() == Some("1"),
+        low_power_mode: false,
+        battery_percent: fields.next().and_then(|v| v.parse().ok()),
+        throttled: false,
+    }
+}
+
+fn refresh_power_state(app: &AppHandle) {
+    let mut state = read_power_state();
+    state.throttled = state.on_battery || state.low_power_mode;
+    let Ok(mut current) = POWER_STATE.lock() else {
+        return;
+    };
+    let previous = current.replace(state.clone());
+    if previous.as_ref().map(|p| (p.on_battery, p.low_power_mode))
+        == Some((state.on_battery, state.low_power_mode))
+    {
+        return;
+    }
+    drop(current);
+    println!(
+        "[Claude PM] Power: {}{}",
+        if state.on_battery { "battery" } else { "AC" },
+        if state.low_power_mode {
+            ", low-power mode"
+        } else {
+            ""
+        }
+    );
+    let _ = app.emit("power-state", state);
+}
+
+fn on_will_sleep() {
+    let Ok(mut since) = SLEEPING_SINCE.lock() else {
+        return;
+    };
+    if since.is_some() {
+        return;
+    }
+    *since = Some(SystemTime::now());
+    drop(since);
+    println!("[Claude PM] System is going to sleep, pausing watchers");
+    if let Some(app) = APP.get() {
+        let _ = app.emit("suspending", ());
+    }
+}
+
+fn on_did_wake() {
+    let since = SLEEPING_SINCE
+        .lock()
+        .ok()
+        .and_then(|mut since| since.take());
+    let slept = since
+        .and_then(|since| since.elapsed().ok())
+        .unwrap_or_default();
+    if let Some(app) = APP.get() {
+        on_wake(app, slept);
+        // Unplugging is common around sleep
+        refresh_power_state(app);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn watch_os_sleep() {
+    use block2::RcBlock;
+    use objc2::msg_send;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyClass, AnyObject};
+    use objc2_foundation::NSString;
+
+    let Some(class) = AnyClass::get(c"NSWorkspace") else {
+        return;
+    };
+    let workspace: Retained<AnyObject> = unsafe { msg_send![class, sharedWorkspace] };
+    let center: Retained<AnyObject> = unsafe { msg_send![&workspace, notificationCenter] };
+    let observe = |name: &str, handler: fn()| {
+        let name = NSString::from_str(name);
+        let block = RcBlock::new(move |_notification: *mut AnyObject| handler());
+        let observer: Retained<AnyObject> = unsafe {
+            msg_send![
+                &center,
+                addObserverForName: &*name,
+                object: std::ptr::null_mut::<AnyObject>(),
+                queue: std::ptr::null_mut::<AnyObject>(),
+                usingBlock: &*block
+            ]
+        };
+        // Observers stay registered for the life of the app
+        std::mem::forget(observer);
+    };
+    observe("NSWorkspaceWillSleepNotification", on_will_sleep);
+    observe("NSWorkspaceDidWakeNotification", on_did_wake);
+    OS_NOTIFICATIONS.store(true, Ordering::SeqCst);
+}
+
+#[cfg(target_os = "linux")]
+fn watch_os_sleep() {
+    use std::io::{BufRead, BufReader};
+
+    if process::which("gdbus").is_none() {
+        return;
+    }
+    let child = Command::new("gdbus")
+        .args([
+            "monitor",
+            "--system",
+            "--dest",
+            "org.freedesktop.login1",
+            "--object-path",
+            "/org/freedesktop/login1",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
+    let Ok(mut child) = child else {
+        return;
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return;
+    };
+    OS_NOTIFICATIONS.store(true, Ordering::SeqCst);
+    thread::spawn(move || {
+        // "/org/freedesktop/login1: org.freedesktop.login1.Manager.PrepareForSleep (true,)"
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Some((_, args)) = line.split_once(".PrepareForSleep ") {
+                if args.starts_with("(true") {
+                    on_will_sleep();
+                } else {
+                    on_did_wake();
+                }
+            }
+        }
+        // Without logind on the bus there's nothing to listen to; fall back to the clock
+        let _ = child.wait();
+        OS_NOTIFICATIONS.store(false, Ordering::SeqCst);
+    });
+}
+
+#[cfg(windows)]
+fn watch_os_sleep() {
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::{ERROR_SUCCESS, HANDLE};
+    use windows::Win32::System::Power::{
+        PowerRegisterSuspendResumeNotification, DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        DEVICE_NOTIFY_CALLBACK, PBT_APMRESUMEAUTOMATIC, PBT_APMSUSPEND,
+    };
+
+    unsafe extern "system" fn callback(
+        _context: *const c_void,
+        kind: u32,
+        _setting: *const c_void,
+    ) -> u32 {
+        match kind {
+            PBT_APMSUSPEND => on_will_sleep(),
+            // Sent on every resume, whether or not a user is there yet
+            PBT_APMRESUMEAUTOMATIC => on_did_wake(),
+            _ => {}
+        }
+        ERROR_SUCCESS.0
+    }
+
+    // Windows keeps a pointer to the parameters for as long as the registration lives
+    let parameters: &'static mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS =
+        Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+            Callback: Some(callback),
+            Context: std::ptr::null_mut(),
+        }));
+    let mut registration = std::ptr::null_mut();
+    let result = unsafe {
+        PowerRegisterSuspendResumeNotification(
+            DEVICE_NOTIFY_CALLBACK,
+            HANDLE(parameters as *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS as *mut c_void),
+            &mut registration,
+        )
+    };
+    if result == ERROR_SUCCESS {
+        OS_NOTIFICATIONS.store(true, Ordering::SeqCst);
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+fn watch_os_sleep() {}
+
+fn on_wake(app: &AppHandle, slept: Duration) {
+    println!(
+        "[Claude PM] Woke after ~{}s asleep, re-verifying server",
+        slept.as_secs()
+    );
+    if let Ok(mut at) = RESUMED_AT.lock() {
+        *at = Some(SystemTime::now());
+    }
+    let server_restarted = crate::recover_server();
+    let _ = app.emit(
+        "resumed",
+        Resumed {
+            slept_secs: slept.as_secs(),
+            server_restarted,
+        },
+    );
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let _ = APP.set(app.clone());
+    watch_os_sleep();
+    thread::spawn(move || {
+        refresh_power_state(&app);
+        let mut last = SystemTime::now();
+        let mut last_power_check = last;
+        loop {
+            thread::sleep(TICK);
+            let now = SystemTime::now();
+            let elapsed = now.duration_since(last).unwrap_or_default();
+            last = now;
+            let slept = elapsed > TICK + SLEEP_THRESHOLD;
+            if slept && !OS_NOTIFICATIONS.load(Ordering::SeqCst) {
+                on_wake(&app, elapsed - TICK);
+            } else if !slept {
+                if let Ok(mut since) = SLEEPING_SINCE.lock() {
+                    let called_off = since
+                        .and_then(|since| since.elapsed().ok())
+                        .is_some_and(|waited| waited > SLEEP_CALLED_OFF);
+                    if called_off {
+                        *since = None;
+                    }
+                }
+            }
+            // Unplugging is common around sleep, so wake also triggers a re-read
+            if now.duration_since(last_power_check).unwrap_or_default() >= POWER_POLL || slept {
+                last_power_check = now;
+                refresh_power_state(&app);
+            }
+        }
+    });
+}
+
+/// Spawn the platform helper that holds the assertion until it is killed or the app exits
+fn spawn_holder(reason: &str) -> Result<Child, Error> {
+    let app_pid = std::process::id().to_string();
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("caffeinate");
+        // -i: prevent idle sleep; -w: exit when the app exits
+        cmd.args(["-i", "-w", &app_pid]);
+        cmd
+    } else if cfg!(target_os = "linux") {
+        let mut cmd = Command::new("systemd-inhibit");
+        cmd.args([
+            "--what=idle:sleep",
+            "--who=Claude PM",
+            &format!("--why={}", reason),
+            "--mode=block",
+            "tail",
+            &format!("--pid={}", app_pid),
+            "-f",
+            "/dev/null",
+        ]);
+        cmd
+    } else {
+        return Err(Error::Unsupported(
+            "Keep-awake is not supported on this platform".to_string(),
+        ));
+    };
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to take keep-awake assertion: {}", e).into())
+}
+
+fn is_alive(pid: u32) -> bool {
+    if cfg!(unix) {
+        process::output(Command::new("kill").args(["-0", &pid.to_string()]))
+            .is_ok_and(|output| output.status.success())
+    } else {
+        true
+    }
+}
+
+/// Drop assertions whose holder died or whose linked process has exited
+fn watch_assertion(id: String) {
+    thread::spawn(move || loop {
+        thread::sleep(ASSERTION_POLL);
+        let Ok(mut assertions) = ASSERTIONS.lock() else {
+            return;
+        };
+        let Some(assertion) = assertions.get_mut(&id) else {
+            return;
+        };
+        let holder_exited = !matches!(assertion.holder.try_wait(), Ok(None));
+        let linked_exited = assertion.info.pid.is_some_and(|pid| !is_alive(pid));
+        if holder_exited || linked_exited {
+            if let Some(mut assertion) = assertions.remove(&id) {
+                let _ = assertion.holder.kill();
+                let _ = assertion.holder.wait();
+                println!(
+                    "[Claude PM] Released keep-awake \"{}\"",
+                    assertion.info.reason
+                );
+            }
+            return;
+        }
+    });
+}
+
+/// Keep the machine from idle-sleeping; released by id, when `pid` exits, or when the app quits
+#[tauri::command]
+pub fn keep_awake(reason: String, pid: Option<u32>) -> Result<KeepAwake, Error> {
+    if pid.is_some_and(|pid| !is_alive(pid)) {
+        return Err(Error::NotFound(format!(
+            "Process {} is not running",
+            pid.unwrap_or_default()
+        )));
+    }
+    let holder = spawn_holder(&reason)?;
+    let info = KeepAwake {
+        id: store::new_id(),
+        reason,
+        pid,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default(),
+    };
+    ASSERTIONS.lock().map_err(|e| e.to_string())?.insert(
+        info.id.clone(),
+        Assertion {
+            info: info.clone(),
+            holder,
+        },
+    );
+    watch_assertion(info.id.clone());
+    println!("[Claude PM] Keeping awake: {}", info.reason);
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn get_power_state() -> PowerState {
+    POWER_STATE
+        .lock()
+        .ok()
+        .and_then(|state| state.clone())
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn release_keep_awake(id: String) -> Result<(), Error> {
+    let mut assertion = ASSERTIONS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&id)
+        .ok_or_else(|| Error::NotFound(format!("No keep-awake assertion with id {}", id)))?;
+    let _ = assertion.holder.kill();
+    let _ = assertion.holder.wait();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_keep_awake() -> Result<Vec<KeepAwake>, Error> {
+    let assertions = ASSERTIONS.lock().map_err(|e| e.to_string())?;
+    Ok(assertions.values().map(|a| a.info.clone()).collect())
+}
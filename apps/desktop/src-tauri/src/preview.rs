@@ -0,0 +1,202 @@
+//! Attachment thumbnails, served over the `claudepm-preview` protocol
+//!
+//! Thumbnails are generated in the background when an attachment is added and cached as
+//! PNGs under `previews/` in the data directory. The webview loads them from
+//! `claudepm-preview://localhost/<attachment id>` (`http://claudepm-preview.localhost/…`
+//! on Windows); a thumbnail that isn't cached yet is generated on request.
+//!
+//! PNG and JPEG are scaled in-process. Everything else (PDF first pages, HEIC, GIF...)
+//! goes through Quick Look (`qlmanage`) on macOS, and `pdftoppm` for PDFs on Linux.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::thread;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::UriSchemeResponder;
+
+use crate::attachments::{self, Attachment};
+use crate::process::{self, which};
+use crate::{config, store};
+
+pub const SCHEME: &str = "claudepm-preview";
+const PREVIEWS_DIR: &str = "previews";
+/// Longest edge of a thumbnail, in pixels
+const THUMBNAIL_SIZE: u32 = 256;
+
+static QUEUE: OnceLock<Mutex<mpsc::Sender<Attachment>>> = OnceLock::new();
+
+fn preview_path(id: &str) -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join(PREVIEWS_DIR).join(format!("{}.png", id)))
+}
+
+fn scale_image(source: &Path, target: &Path) -> Result<(), String> {
+    let image = image::open(source).map_err(|e| format!("Failed to decode image: {}", e))?;
+    image
+        .thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+        .save_with_format(target, image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to write thumbnail: {}", e))
+}
+
+/// Quick Look writes `<source file name>.png` into the output directory
+fn quick_look(source: &Path, target: &Path) -> Result<(), String> {
+    let out_dir = target.with_extension("ql");
+    fs::create_dir_all(&out_dir).map_err(|e| e.to_string())?;
+    let result = process::output(
+        Command::new("qlmanage")
+            .args(["-t", "-s", &THUMBNAIL_SIZE.to_string(), "-o"])
+            .arg(&out_dir)
+            .arg(source),
+    );
+    let rendered = source
+        .file_name()
+        .map(|name| out_dir.join(format!("{}.png", name.to_string_lossy())));
+    let moved = match rendered {
+        Some(rendered) if rendered.exists() => {
+            fs::rename(rendered, target).map_err(|e| e.to_string())
+        }
+        _ => Err(match result {
+            Ok(_) => "Quick Look produced no thumbnail".to_string(),
+            Err(e) => e.to_string(),
+        }),
+    };
+    let _ = fs::remove_dir_all(&out_dir);
+    moved
+}
+
+fn pdf_first_page(source: &Path, target: &Path) -> Result<(), String> {
+    let pdftoppm = which("pdftoppm").ok_or("pdftoppm is required for PDF previews")?;
+    // pdftoppm appends `.png` to the output prefix
+    let prefix = target.with_extension("");
+    let output = process::output(
+        Command::new(pdftoppm)
+            .args([
+                "-png",
+                "-singlefile",
+                "-scale-to",
+                &THUMBNAIL_SIZE.to_string(),
+            ])
+            .arg(source)
+            .arg(&prefix),
+    )?;
+    if !output.status.success() {
+        return Err(format!(
+            "pdftoppm failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Render and cache the thumbnail for an attachment, returning its path
+fn generate(attachment: &Attachment) -> Result<PathBuf, String> {
+    let target = preview_path(&attachment.id).ok_or("Could not determine data directory")?;
+    if target.exists() {
+        return Ok(target);
+    }
+    if let Some(dir) = target.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create previews directory: {}", e))?;
+    }
+    let source = Path::new(&attachment.path);
+    match attachment.mime_type.as_str() {
+        "image/png" | "image/jpeg" => scale_image(source, &target),
+        _ if cfg!(target_os = "macos") => quick_look(source, &target),
+        "application/pdf" => pdf_first_page(source, &target),
+        other => Err(format!("No preview available for {}", other)),
+    }?;
+    Ok(target)
+}
+
+fn has_preview(attachment: &Attachment) -> bool {
+    attachment.mime_type.starts_with("image/") || attachment.mime_type == "application/pdf"
+}
+
+/// Generate the thumbnail in the background; no-op for types without previews
+pub fn queue(attachment: &Attachment) {
+    if !has_preview(attachment) {
+        return;
+    }
+    let sender = QUEUE.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<Attachment>();
+        thread::spawn(move || {
+            for attachment in rx {
+                if let Err(e) = generate(&attachment) {
+                    eprintln!(
+                        "[Claude PM] Preview for {} failed: {}",
+                        attachment.file_name, e
+                    );
+                }
+            }
+        });
+        Mutex::new(tx)
+    });
+    if let Ok(sender) = sender.lock() {
+        let _ = sender.send(attachment.clone());
+    }
+}
+
+/// Cached thumbnails whose attachment no longer exists
+pub fn stale_previews() -> Result<Vec<PathBuf>, String> {
+    let Some(dir) = config::data_dir().map(|dir| dir.join(PREVIEWS_DIR)) else {
+        return Ok(Vec::new());
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let ids: HashSet<String> = store::with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT id FROM attachments")?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    })?;
+    Ok(entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_stem()
+                .is_some_and(|id| !ids.contains(id.to_string_lossy().as_ref()))
+        })
+        .collect())
+}
+
+/// Drop a cached thumbnail, e.g. when its attachment is deleted
+pub fn remove(id: &str) {
+    if let Some(path) = preview_path(id) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+fn respond(status: StatusCode, content_type: &str, body: Vec<u8>) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CACHE_CONTROL, "max-age=31536000, immutable")
+        .body(body)
+        .unwrap_or_default()
+}
+
+fn serve(request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    let id = request.uri().path().trim_start_matches('/');
+    let attachment = match attachments::get(id) {
+        Ok(Some(attachment)) if has_preview(&attachment) => attachment,
+        Ok(_) => return respond(StatusCode::NOT_FOUND, "text/plain", b"No preview".to_vec()),
+        Err(e) => {
+            return respond(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "text/plain",
+                e.into_bytes(),
+            )
+        }
+    };
+    match generate(&attachment).and_then(|path| fs::read(path).map_err(|e| e.to_string())) {
+        Ok(bytes) => respond(StatusCode::OK, "image/png", bytes),
+        Err(e) => respond(StatusCode::NOT_FOUND, "text/plain", e.into_bytes()),
+    }
+}
+
+/// Protocol handler; rendering can take a moment, so it runs off the webview thread
+pub fn handle(request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    thread::spawn(move || responder.respond(serve(&request)));
+}
@@ -0,0 +1,170 @@
+//! Helpers for locating and launching external programs
+//!
+//! Short-lived helpers (osascript, which, tmux, scutil...) go through [`output`] so a
+//! hung process is killed after a timeout instead of blocking a command forever.
+
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::error::Error;
+
+/// Used when `processTimeoutSecs` isn't set in the config
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// How long to wait for a pipe to close after the process exits (a grandchild may hold it)
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Set on shutdown so in-flight helpers are killed rather than delaying exit
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Shared cancellation flag for a running process
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// True once cancelled, or when the app is shutting down
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst) || SHUTTING_DOWN.load(Ordering::SeqCst)
+    }
+}
+
+/// Cancel every helper still running; called when the app exits
+pub fn cancel_all() {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+}
+
+/// Timeout for external helpers, from the config or the default
+pub fn default_timeout() -> Duration {
+    config::load()
+        .process_timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_TIMEOUT)
+}
+
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> mpsc::Receiver<Vec<u8>> {
+    let (tx, rx) = mpsc::channel();
+    if let Some(mut pipe) = pipe {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            let _ = pipe.read_to_end(&mut buf);
+            let _ = tx.send(buf);
+        });
+    }
+    rx
+}
+
+/// Run `cmd` to completion capturing its output; killed on timeout (`timeout` error) or
+/// cancellation (`cancelled` error)
+pub fn run(cmd: &mut Command, timeout: Duration, cancel: &CancelToken) -> Result<Output, Error> {
+    let program = cmd.get_program().to_string_lossy().to_string();
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    new_process_group(cmd);
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|e| format!("Failed to wait for {}: {}", program, e))?
+        {
+            break status;
+        }
+        let failure = if cancel.is_cancelled() {
+            Error::Cancelled(format!("{} was cancelled", program))
+        } else if started.elapsed() >= timeout {
+            Error::Timeout(format!(
+                "{} did not finish within {}s",
+                program,
+                timeout.as_secs()
+            ))
+        } else {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        };
+        kill_tree(&mut child);
+        let _ = child.wait();
+        return Err(failure);
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.recv_timeout(DRAIN_TIMEOUT).unwrap_or_default(),
+        stderr: stderr.recv_timeout(DRAIN_TIMEOUT).unwrap_or_default(),
+    })
+}
+
+/// [`run`] with the configured timeout and no cancellation other than shutdown
+pub fn output(cmd: &mut Command) -> Result<Output, Error> {
+    run(cmd, default_timeout(), &CancelToken::default())
+}
+
+/// Resolve a program on PATH using `which`
+pub fn which(program: &str) -> Option<PathBuf> {
+    let output = output(Command::new("which").arg(program)).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if path.is_empty() {
+        None
+    } else {
+        Some(PathBuf::from(path))
+    }
+}
+
+/// Return the first of `paths` that exists on disk
+pub fn first_existing(paths: &[PathBuf]) -> Option<PathBuf> {
+    paths.iter().find(|p| p.exists()).cloned()
+}
+
+/// Quote a string for safe interpolation into a POSIX shell command line
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Put the child in its own process group (Unix) so the whole tree can be signalled later
+pub fn new_process_group(cmd: &mut Command) -> &mut Command {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    cmd
+}
+
+/// Signal every process in the group led by `pid` (Unix only)
+pub fn kill_group(pid: u32) {
+    // `kill` can't hang, and going through `run` here would recurse on timeout
+    #[cfg(unix)]
+    {
+        let _ = Command::new("kill")
+            .args(["-TERM", &format!("-{}", pid)])
+            .status();
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+/// Kill a child and, on Unix, every process in its group (e.g. node under npm)
+pub fn kill_tree(child: &mut Child) {
+    kill_group(child.id());
+    let _ = child.kill();
+}
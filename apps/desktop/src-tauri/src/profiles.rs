@@ -0,0 +1,130 @@
+//! Server environment profiles (dev/staging/prod)
+//!
+//! A profile picks the npm script, port, log level and extra env vars the Node server
+//! is started with. Profiles live in the config; the built-in ones are used until the
+//! user saves their own.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+
+use crate::error::Error;
+use crate::SERVER_PORT;
+use crate::{config, config_watch};
+
+const DEFAULT_PROFILE: &str = "dev";
+const LOG_LEVELS: &[&str] = &["debug", "info", "warn", "error"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerProfile {
+    pub name: String,
+    /// npm script to run, e.g. `dev` (tsx watch) or `start` (built output)
+    pub script: String,
+    pub port: u16,
+    pub log_level: String,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileList {
+    pub profiles: Vec<ServerProfile>,
+    pub active: String,
+}
+
+fn builtin(name: &str, script: &str, log_level: &str, node_env: &str) -> ServerProfile {
+    ServerProfile {
+        name: name.to_string(),
+        script: script.to_string(),
+        port: SERVER_PORT,
+        log_level: log_level.to_string(),
+        env: BTreeMap::from([("NODE_ENV".to_string(), node_env.to_string())]),
+    }
+}
+
+fn profiles(config: &config::AppConfig) -> Vec<ServerProfile> {
+    if !config.server_profiles.is_empty() {
+        return config.server_profiles.clone();
+    }
+    vec![
+        builtin("dev", "dev", "debug", "development"),
+        // The server only accepts development/production/test as NODE_ENV
+        builtin("staging", "start", "info", "production"),
+        builtin("prod", "start", "warn", "production"),
+    ]
+}
+
+/// The profile the server should be started with
+pub fn active() -> ServerProfile {
+    let config = config::load();
+    let name = config.active_profile.as_deref().unwrap_or(DEFAULT_PROFILE);
+    let all = profiles(&config);
+    all.iter()
+        .find(|p| p.name == name)
+        .or_else(|| all.first())
+        .cloned()
+        .unwrap_or_else(|| builtin("dev", "dev", "debug", "development"))
+}
+
+/// Change the active profile in place; saving materializes the built-ins like `save_profile`
+pub fn update_active(f: impl FnOnce(&mut ServerProfile)) -> Result<ServerProfile, String> {
+    let name = active().name;
+    let mut updated = None;
+    config::update(|c| {
+        let mut all = profiles(c);
+        if let Some(profile) = all.iter_mut().find(|p| p.name == name) {
+            f(profile);
+            updated = Some(profile.clone());
+        }
+        c.server_profiles = all;
+    })?;
+    updated.ok_or_else(|| format!("Profile not found: {}", name))
+}
+
+#[tauri::command]
+pub fn list_profiles() -> ProfileList {
+    ProfileList {
+        active: active().name,
+        profiles: profiles(&config::load()),
+    }
+}
+
+/// Add or replace a profile; saving the first one replaces the built-ins
+#[tauri::command]
+pub fn save_profile(profile: ServerProfile) -> Result<(), Error> {
+    if profile.name.trim().is_empty() || profile.script.trim().is_empty() {
+        return Err(Error::InvalidInput(
+            "Profile name and script are required".to_string(),
+        ));
+    }
+    if !LOG_LEVELS.contains(&profile.log_level.as_str()) {
+        return Err(Error::InvalidInput(format!(
+            "Log level must be one of {}",
+            LOG_LEVELS.join(", ")
+        )));
+    }
+    if profile.port == 0 {
+        return Err(Error::InvalidInput("Port must be non-zero".to_string()));
+    }
+    config::update(|c| {
+        let mut all = profiles(c);
+        match all.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => all.push(profile),
+        }
+        c.server_profiles = all;
+    })?;
+    Ok(())
+}
+
+/// Switch profiles; the server restarts if the new profile starts it differently
+#[tauri::command]
+pub fn set_active_profile(app: AppHandle, name: String) -> Result<(), Error> {
+    if !profiles(&config::load()).iter().any(|p| p.name == name) {
+        return Err(Error::NotFound(format!("Profile not found: {}", name)));
+    }
+    config::update(|c| c.active_profile = Some(name))?;
+    config_watch::apply(&app).map(|_| ())
+}
@@ -0,0 +1,839 @@
+//! Cold storage for finished projects: one archive file out, the live database lighter
+//!
+//! `archive_project` writes a project's rows (tasks, sessions, time entries, run history,
+//! links to GitHub, Jira and Linear, and the rest listed in [`TABLES`]), its attachment
+//! files and its Claude transcripts into a zip under `project-archives/` in the data
+//! directory (or a path the caller picks). The archive is read back and checked before
+//! anything is removed; then the rows go in one transaction, which also drops them from
+//! the search index, and the files are deleted. `unarchive_project` puts every row back
+//! with the values it had, and the files into the attachment store and Claude's projects
+//! directory; the paths in the manifest only supply file names, so an archive can't write
+//! anywhere else.
+//!
+//! Layout inside the zip:
+//! - `manifest.json`: the project, schema version, row counts and the archived files
+//! - `tables/<table>.json`: the rows, column by column; blobs as `{"base64": …}`
+//! - `files/<n>`: attachment and transcript files, named in the manifest
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::Error;
+use crate::orchestrator::{self, RunState};
+use crate::{attachments, claude_profiles, config, preview, search, store, task_branch};
+
+/// Bump when the archive layout changes incompatibly
+const FORMAT_VERSION: u32 = 1;
+const ARCHIVE_DIR: &str = "project-archives";
+const MANIFEST: &str = "manifest.json";
+
+/// Archived tables, parents first, with which of their rows belong to project `?1`
+const TABLES: &[(&str, &str)] = &[
+    ("projects", "id = ?1"),
+    ("tasks", "project_id = ?1"),
+    ("sessions", "project_id = ?1"),
+    ("deadlines", "project_id = ?1"),
+    ("recent_projects", "project_id = ?1"),
+    ("task_recurrences", "project_id = ?1"),
+    ("github_boards", "project_id = ?1"),
+    ("github_board_items", "project_id = ?1"),
+    ("run_history", "project_id = ?1"),
+    ("run_snapshots", "project_id = ?1"),
+    ("task_branches", "project_id = ?1"),
+    ("github_links", "task_id IN (SELECT id FROM tasks WHERE project_id = ?1)"),
+    ("jira_issues", "task_id IN (SELECT id FROM tasks WHERE project_id = ?1)"),
+    ("linear_issues", "task_id IN (SELECT id FROM tasks WHERE project_id = ?1)"),
+    ("time_entries", "task_id IN (SELECT id FROM tasks WHERE project_id = ?1)"),
+    ("attachments", "task_id IN (SELECT id FROM tasks WHERE project_id = ?1)"),
+    ("task_comments", "task_id IN (SELECT id FROM tasks WHERE project_id = ?1)"),
+    (
+        "calendar_items",
+        "source_id IN (SELECT id FROM tasks WHERE project_id = ?1)",
+    ),
+    (
+        "task_dependencies",
+        "task_id IN (SELECT id FROM tasks WHERE project_id = ?1) OR depends_on IN (SELECT id FROM tasks WHERE project_id = ?1)",
+    ),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedFile {
+    /// Entry in the zip
+    pub entry: String,
+    /// Where it lived; its file name is restored into the store it came from
+    pub path: String,
+    /// `attachment` or `transcript`
+    pub kind: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    /// Database `user_version` the rows were written with
+    pub schema_version: usize,
+    pub created_at: i64,
+    pub project_id: String,
+    pub project_name: String,
+    /// Rows per table
+    pub rows: BTreeMap<String, usize>,
+    pub files: Vec<ArchivedFile>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectArchive {
+    pub path: String,
+    pub project_id: String,
+    pub project_name: String,
+    pub created_at: i64,
+    pub tasks: usize,
+    pub runs: usize,
+    pub attachments: usize,
+    pub transcripts: usize,
+    /// Size of the archive file
+    pub bytes: u64,
+    /// What couldn't be removed after archiving, or restored when unarchiving
+    pub errors: Vec<String>,
+}
+
+impl ProjectArchive {
+    fn new(path: &Path, manifest: &ArchiveManifest, errors: Vec<String>) -> Self {
+        let rows = |table: &str| manifest.rows.get(table).copied().unwrap_or_default();
+        let files = |kind: &str| manifest.files.iter().filter(|f| f.kind == kind).count();
+        Self {
+            path: path.display().to_string(),
+            project_id: manifest.project_id.clone(),
+            project_name: manifest.project_name.clone(),
+            created_at: manifest.created_at,
+            tasks: rows("tasks"),
+            runs: rows("run_history"),
+            attachments: files("attachment"),
+            transcripts: files("transcript"),
+            bytes: fs::metadata(path).map(|m| m.len()).unwrap_or_default(),
+            errors,
+        }
+    }
+}
+
+fn archive_dir() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join(ARCHIVE_DIR))
+}
+
+fn to_json(value: SqlValue) -> Value {
+    match value {
+        SqlValue::Null => Value::Null,
+        SqlValue::Integer(i) => Value::from(i),
+        SqlValue::Real(f) => Value::from(f),
+        SqlValue::Text(s) => Value::from(s),
+        SqlValue::Blob(b) => serde_json::json!({ "base64": BASE64.encode(b) }),
+    }
+}
+
+fn to_sql(value: &Value) -> Result<SqlValue, String> {
+    Ok(match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(b) => SqlValue::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => SqlValue::Text(s.clone()),
+        Value::Object(blob) => SqlValue::Blob(
+            blob.get("base64")
+                .and_then(Value::as_str)
+                .and_then(|b| BASE64.decode(b).ok())
+                .ok_or("Malformed blob in archive")?,
+        ),
+        Value::Array(_) => return Err("Unexpected array in archive".to_string()),
+    })
+}
+
+fn rows(
+    conn: &Connection,
+    table: &str,
+    filter: &str,
+    project_id: &str,
+) -> rusqlite::Result<Vec<Map<String, Value>>> {
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {} WHERE {}", table, filter))?;
+    let columns: Vec<String> = stmt
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let rows = stmt.query_map([project_id], |row| {
+        let mut object = Map::new();
+        for (index, column) in columns.iter().enumerate() {
+            object.insert(column.clone(), to_json(row.get::<_, SqlValue>(index)?));
+        }
+        Ok(object)
+    })?;
+    rows.collect()
+}
+
+fn columns(conn: &Connection, table: &str) -> rusqlite::Result<BTreeSet<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    names.collect()
+}
+
+fn schema_version(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+/// Each Claude profile's transcripts directory, the default one first
+fn transcript_roots() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = claude_profiles::transcripts_dir(None).into_iter().collect();
+    for profile in config::load().claude_profiles {
+        if let Some(root) = claude_profiles::transcripts_dir(Some(&profile.name)) {
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+        }
+    }
+    roots
+}
+
+/// Directories Claude writes the project's transcripts to: one per directory it ran in,
+/// under each Claude profile's transcripts directory
+fn transcript_files(cwds: &BTreeSet<String>) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for root in transcript_roots() {
+        for cwd in cwds {
+            let Ok(entries) = fs::read_dir(root.join(cwd.replace(['/', '.'], "-"))) else {
+                continue;
+            };
+            files.extend(
+                entries
+                    .flatten()
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl")),
+            );
+        }
+    }
+    files.sort();
+    files
+}
+
+fn write_archive(
+    path: &Path,
+    manifest: &ArchiveManifest,
+    tables: &BTreeMap<String, Vec<Map<String, Value>>>,
+) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let file =
+        File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (table, rows) in tables {
+        zip.start_file(format!("tables/{}.json", table), options)
+            .map_err(|e| e.to_string())?;
+        serde_json::to_writer(&mut zip, rows).map_err(|e| e.to_string())?;
+    }
+    for archived in &manifest.files {
+        let mut reader = File::open(&archived.path)
+            .map_err(|e| format!("Failed to read {}: {}", archived.path, e))?;
+        zip.start_file(&archived.entry, options)
+            .map_err(|e| e.to_string())?;
+        io::copy(&mut reader, &mut zip).map_err(|e| e.to_string())?;
+    }
+    zip.start_file(MANIFEST, options)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&serde_json::to_vec_pretty(manifest).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn read_manifest(zip: &mut ZipArchive<File>) -> Result<ArchiveManifest, String> {
+    let entry = zip
+        .by_name(MANIFEST)
+        .map_err(|_| "Not a project archive: no manifest".to_string())?;
+    serde_json::from_reader(entry).map_err(|e| format!("Invalid archive manifest: {}", e))
+}
+
+/// Every table and file the manifest lists is in the archive, with the right sizes
+fn verify(path: &Path, expected: &ArchiveManifest) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let manifest = read_manifest(&mut zip)?;
+    for table in manifest.rows.keys() {
+        let rows: Vec<Value> = serde_json::from_reader(
+            zip.by_name(&format!("tables/{}.json", table))
+                .map_err(|e| e.to_string())?,
+        )
+        .map_err(|e| e.to_string())?;
+        if rows.len() != expected.rows.get(table).copied().unwrap_or_default() {
+            return Err(format!("Archived {} rows don't match", table));
+        }
+    }
+    for archived in &manifest.files {
+        let size = zip
+            .by_name(&archived.entry)
+            .map_err(|e| e.to_string())?
+            .size();
+        if size != archived.bytes {
+            return Err(format!("Archived copy of {} is incomplete", archived.path));
+        }
+    }
+    Ok(())
+}
+
+/// A single path component that stays where it's joined: no separators, `.` or `..`
+fn plain_name(name: Option<&std::ffi::OsStr>) -> Result<&str, String> {
+    match name.and_then(|name| name.to_str()) {
+        Some(name)
+            if !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\']) =>
+        {
+            Ok(name)
+        }
+        _ => Err("not a plain file name".to_string()),
+    }
+}
+
+/// Where an archived file goes back to: its file name in the attachment store, or its
+/// project directory and file name under one of Claude's transcript roots (the one it came
+/// from, if it's still configured)
+fn restore_target(
+    archived: &ArchivedFile,
+    attachments: &Path,
+    transcripts: &[PathBuf],
+) -> Result<PathBuf, String> {
+    let original = Path::new(&archived.path);
+    let name = plain_name(original.file_name())?;
+    match archived.kind.as_str() {
+        "attachment" => Ok(attachments.join(name)),
+        "transcript" => {
+            if !name.ends_with(".jsonl") {
+                return Err("not a transcript".to_string());
+            }
+            let project = plain_name(original.parent().and_then(Path::file_name))?;
+            let root = transcripts
+                .iter()
+                .find(|root| original.starts_with(root))
+                .or(transcripts.first())
+                .ok_or("Could not determine Claude's projects directory")?;
+            Ok(root.join(project).join(name))
+        }
+        other => Err(format!("unknown kind of file: {}", other)),
+    }
+}
+
+fn archive(project_id: &str, path: Option<PathBuf>) -> Result<ProjectArchive, Error> {
+    let project = store::with_conn(|conn| store::get_project(conn, project_id))?
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project_id)))?;
+    let busy = orchestrator::list_agent_runs()?.runs.iter().any(|run| {
+        run.project_id == project_id
+            && matches!(
+                run.state,
+                RunState::Queued | RunState::Running | RunState::Blocked
+            )
+    });
+    if busy {
+        return Err(Error::InvalidInput(
+            "The project has agent runs queued or running; cancel them before archiving"
+                .to_string(),
+        ));
+    }
+
+    let (tables, schema_version) = store::with_conn(|conn| {
+        let mut tables = BTreeMap::new();
+        for (table, filter) in TABLES {
+            tables.insert(table.to_string(), rows(conn, table, filter, project_id)?);
+        }
+        Ok((tables, schema_version(conn)?))
+    })?;
+
+    // Attachment files, and transcripts from wherever the project's agents worked
+    let mut sources: Vec<(&str, PathBuf)> = tables["attachments"]
+        .iter()
+        .filter_map(|row| row.get("path")?.as_str().map(PathBuf::from))
+        .map(|path| ("attachment", path))
+        .collect();
+    let mut cwds: BTreeSet<String> = project.repo_path.iter().cloned().collect();
+    for (table, column) in [("run_history", "cwd"), ("task_branches", "worktree")] {
+        cwds.extend(
+            tables[table]
+                .iter()
+                .filter_map(|row| row.get(column)?.as_str().map(str::to_string)),
+        );
+    }
+    sources.extend(
+        transcript_files(&cwds)
+            .into_iter()
+            .map(|path| ("transcript", path)),
+    );
+    // Attachments are content-addressed, so tasks can share a file
+    let mut seen = BTreeSet::new();
+    let files: Vec<ArchivedFile> = sources
+        .into_iter()
+        .filter(|(_, path)| seen.insert(path.clone()))
+        .filter_map(|(kind, path)| Some((kind, fs::metadata(&path).ok()?.len(), path)))
+        .enumerate()
+        .map(|(index, (kind, bytes, path))| ArchivedFile {
+            entry: format!("files/{}", index),
+            path: path.display().to_string(),
+            kind: kind.to_string(),
+            bytes,
+        })
+        .collect();
+
+    let manifest = ArchiveManifest {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version,
+        created_at: store::now_ms(),
+        project_id: project.id.clone(),
+        project_name: project.name.clone(),
+        rows: tables
+            .iter()
+            .map(|(t, rows)| (t.clone(), rows.len()))
+            .collect(),
+        files,
+    };
+    let path = match path {
+        Some(path) => path,
+        None => archive_dir()
+            .ok_or("Could not determine data directory")?
+            .join(format!(
+                "{}-{}.zip",
+                task_branch::slug(&project.name),
+                project.id
+            )),
+    };
+    if path.exists() {
+        return Err(Error::InvalidInput(format!(
+            "{} already exists",
+            path.display()
+        )));
+    }
+    // Nothing is removed until the archive is known to be complete
+    if let Err(e) = write_archive(&path, &manifest, &tables).and_then(|_| verify(&path, &manifest))
+    {
+        let _ = fs::remove_file(&path);
+        return Err(Error::Internal(format!(
+            "Failed to write the archive: {}",
+            e
+        )));
+    }
+
+    let shared = store::with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        // Children first; the rows of tables keyed by task go before the tasks
+        for (table, filter) in TABLES.iter().rev() {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE {}", table, filter),
+                [project_id],
+            )?;
+        }
+        for archived in manifest.files.iter().filter(|f| f.kind == "transcript") {
+            tx.execute(
+                "DELETE FROM search_sources WHERE path = ?1",
+                [&archived.path],
+            )?;
+        }
+        tx.commit()?;
+        // Files other projects' attachments still point at stay where they are
+        let mut stmt = conn.prepare("SELECT path FROM attachments")?;
+        let paths = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        paths.collect::<rusqlite::Result<BTreeSet<String>>>()
+    })?;
+    // Transcript entries; tasks and attachments leave the index through triggers
+    if let Err(e) = search::forget_project(project_id) {
+        eprintln!(
+            "[Claude PM] Failed to drop the archived project from search: {}",
+            e
+        );
+    }
+    for row in &tables["attachments"] {
+        if let Some(id) = row.get("id").and_then(Value::as_str) {
+            preview::remove(id);
+        }
+    }
+    let errors: Vec<String> = manifest
+        .files
+        .iter()
+        .filter(|archived| !shared.contains(&archived.path))
+        .filter_map(|archived| {
+            fs::remove_file(&archived.path)
+                .err()
+                .map(|e| format!("Failed to remove {}: {}", archived.path, e))
+        })
+        .collect();
+    println!(
+        "[Claude PM] Archived project {} to {}",
+        project.name,
+        path.display()
+    );
+    Ok(ProjectArchive::new(&path, &manifest, errors))
+}
+
+/// A table's archived rows, column by column, ready to insert
+type Rows = Vec<Vec<(String, SqlValue)>>;
+
+/// The rows of every table the manifest lists, parents first; attachment rows point at
+/// `moved`, where their files are restored to
+fn read_tables(
+    zip: &mut ZipArchive<File>,
+    manifest: &ArchiveManifest,
+    moved: &HashMap<&str, String>,
+) -> Result<Vec<(&'static str, Rows)>, Error> {
+    let mut tables = Vec::new();
+    for (table, _) in TABLES {
+        if !manifest.rows.contains_key(*table) {
+            continue;
+        }
+        let mut entry = zip
+            .by_name(&format!("tables/{}.json", table))
+            .map_err(|e| e.to_string())?;
+        let mut contents = String::new();
+        entry
+            .read_to_string(&mut contents)
+            .map_err(|e| e.to_string())?;
+        let rows: Vec<Map<String, Value>> =
+            serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+        let rows = rows
+            .into_iter()
+            .map(|mut row| {
+                if *table == "attachments" {
+                    let target = row
+                        .get("path")
+                        .and_then(Value::as_str)
+                        .and_then(|path| moved.get(path));
+                    if let Some(target) = target {
+                        row.insert("path".to_string(), Value::from(target.as_str()));
+                    }
+                }
+                row.into_iter()
+                    .map(|(column, value)| Ok((column, to_sql(&value)?)))
+                    .collect::<Result<Vec<_>, String>>()
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(Error::InvalidInput)?;
+        tables.push((*table, rows));
+    }
+    Ok(tables)
+}
+
+/// Insert archived rows in one transaction; dependencies on tasks in other projects that
+/// are gone by now are left out, and counted
+fn insert_rows(conn: &Connection, tables: &[(&str, Rows)]) -> rusqlite::Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    let mut skipped = 0;
+    for (table, rows) in tables {
+        let known = columns(&tx, table)?;
+        for row in rows {
+            let (names, values): (Vec<&String>, Vec<SqlValue>) = row
+                .iter()
+                .filter(|(column, _)| known.contains(column))
+                .map(|(column, value)| (column, value.clone()))
+                .unzip();
+            let sql = format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                table,
+                names
+                    .iter()
+                    .map(|n| n.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                vec!["?"; names.len()].join(", ")
+            );
+            match tx.execute(&sql, params_from_iter(values)) {
+                Ok(_) => {}
+                Err(_) if *table == "task_dependencies" => skipped += 1,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(skipped)
+}
+
+fn unarchive(path: &Path) -> Result<ProjectArchive, Error> {
+    let file = File::open(path)
+        .map_err(|e| Error::NotFound(format!("Failed to open {}: {}", path.display(), e)))?;
+    let mut zip = ZipArchive::new(file)
+        .map_err(|e| Error::InvalidInput(format!("Not a project archive: {}", e)))?;
+    let manifest = read_manifest(&mut zip).map_err(Error::InvalidInput)?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(Error::Unsupported(format!(
+            "Archive format v{} is newer than this app supports (v{})",
+            manifest.format_version, FORMAT_VERSION
+        )));
+    }
+    let attachments_dir = attachments::attachments_dir()?;
+    let transcripts = transcript_roots();
+    let targets: Vec<Result<PathBuf, String>> = manifest
+        .files
+        .iter()
+        .map(|archived| restore_target(archived, &attachments_dir, &transcripts))
+        .collect();
+    // Attachment rows point at wherever their file is restored to
+    let moved: HashMap<&str, String> = manifest
+        .files
+        .iter()
+        .zip(&targets)
+        .filter(|(archived, _)| archived.kind == "attachment")
+        .filter_map(|(archived, target)| {
+            Some((
+                archived.path.as_str(),
+                target.as_ref().ok()?.display().to_string(),
+            ))
+        })
+        .collect();
+
+    let tables = read_tables(&mut zip, &manifest, &moved)?;
+
+    let (current, exists) = store::with_conn(|conn| {
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM projects WHERE id = ?1)",
+            [&manifest.project_id],
+            |row| row.get(0),
+        )?;
+        Ok((schema_version(conn)?, exists))
+    })?;
+    if current < manifest.schema_version {
+        return Err(Error::Unsupported(format!(
+            "The archive was written by a newer app (schema v{})",
+            manifest.schema_version
+        )));
+    }
+    if exists {
+        return Err(Error::InvalidInput(format!(
+            "Project {} already exists",
+            manifest.project_id
+        )));
+    }
+
+    let skipped = store::with_conn(|conn| insert_rows(conn, &tables))
+        .map_err(|e| Error::Internal(format!("Can't restore the archive: {}", e)))?;
+
+    let mut errors = Vec::new();
+    if skipped > 0 {
+        errors.push(format!(
+            "{} dependencies on tasks that no longer exist were left out",
+            skipped
+        ));
+    }
+    for (archived, target) in manifest.files.iter().zip(targets) {
+        let target = match target {
+            Ok(target) => target,
+            Err(e) => {
+                errors.push(format!("Skipped {}: {}", archived.path, e));
+                continue;
+            }
+        };
+        // A shared attachment that was never removed is already in place
+        if target.exists() {
+            if archived.kind != "attachment" {
+                errors.push(format!("{} already exists; kept it", target.display()));
+            }
+            continue;
+        }
+        let restored = (|| -> Result<(), String> {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let mut entry = zip.by_name(&archived.entry).map_err(|e| e.to_string())?;
+            let mut out = File::create(&target).map_err(|e| e.to_string())?;
+            io::copy(&mut entry, &mut out).map_err(|e| e.to_string())?;
+            Ok(())
+        })();
+        if let Err(e) = restored {
+            errors.push(format!("Failed to restore {}: {}", target.display(), e));
+        }
+    }
+    println!(
+        "[Claude PM] Restored project {} from {}",
+        manifest.project_name,
+        path.display()
+    );
+    Ok(ProjectArchive::new(path, &manifest, errors))
+}
+
+/// Move a project into an archive file, by default under `project-archives/`
+#[tauri::command]
+pub async fn archive_project(id: String, path: Option<String>) -> Result<ProjectArchive, Error> {
+    tauri::async_runtime::spawn_blocking(move || archive(&id, path.map(PathBuf::from)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Bring an archived project back, exactly as it was archived
+#[tauri::command]
+pub async fn unarchive_project(path: String) -> Result<ProjectArchive, Error> {
+    tauri::async_runtime::spawn_blocking(move || unarchive(Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Archives in `project-archives/`, newest first
+#[tauri::command]
+pub fn list_project_archives() -> Vec<ProjectArchive> {
+    let Some(entries) = archive_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut archives: Vec<ProjectArchive> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "zip"))
+        .filter_map(|path| {
+            let mut zip = ZipArchive::new(File::open(&path).ok()?).ok()?;
+            let manifest = read_manifest(&mut zip).ok()?;
+            Some(ProjectArchive::new(&path, &manifest, Vec::new()))
+        })
+        .collect();
+    archives.sort_by_key(|archive| std::cmp::Reverse(archive.created_at));
+    archives
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn database() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        store::migrate(&mut conn).unwrap();
+        conn
+    }
+
+    fn archived(path: &str, kind: &str) -> ArchivedFile {
+        ArchivedFile {
+            entry: "files/0".to_string(),
+            path: path.to_string(),
+            kind: kind.to_string(),
+            bytes: 0,
+        }
+    }
+
+    #[test]
+    fn round_trips_a_project() {
+        let dir = std::env::temp_dir().join(format!("claudepm-archive-{}", store::new_id()));
+        fs::create_dir_all(&dir).unwrap();
+        let attachment = dir.join("a1-notes.txt");
+        fs::write(&attachment, "hello").unwrap();
+
+        let source = database();
+        source
+            .execute_batch(
+                "INSERT INTO projects (id, name, created_at, updated_at)
+                     VALUES ('p1', 'Archived', 1, 2), ('p2', 'Other', 1, 2);
+                 INSERT INTO tasks (id, project_id, title, state, created_at, updated_at)
+                     VALUES ('t1', 'p1', 'Ship it', 'done', 3, 4), ('t2', 'p2', 'Stay', 'todo', 3, 4);
+                 INSERT INTO task_comments (id, task_id, body, created_at)
+                     VALUES ('c1', 't1', 'Shipped', 5);",
+            )
+            .unwrap();
+        source
+            .execute(
+                "INSERT INTO attachments (id, task_id, file_name, path, mime_type, size, created_at, hash)
+                 VALUES ('a1', 't1', 'notes.txt', ?1, 'text/plain', 5, 6, X'00ff')",
+                [attachment.display().to_string()],
+            )
+            .unwrap();
+
+        // Every table's filter runs against the real schema
+        let tables: BTreeMap<String, Vec<Map<String, Value>>> = TABLES
+            .iter()
+            .map(|(table, filter)| {
+                (
+                    table.to_string(),
+                    rows(&source, table, filter, "p1").unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(tables["tasks"].len(), 1);
+        assert_eq!(tables["task_comments"].len(), 1);
+        let manifest = ArchiveManifest {
+            format_version: FORMAT_VERSION,
+            app_version: "test".to_string(),
+            schema_version: schema_version(&source).unwrap(),
+            created_at: 7,
+            project_id: "p1".to_string(),
+            project_name: "Archived".to_string(),
+            rows: tables
+                .iter()
+                .map(|(t, rows)| (t.clone(), rows.len()))
+                .collect(),
+            files: vec![ArchivedFile {
+                bytes: 5,
+                ..archived(&attachment.display().to_string(), "attachment")
+            }],
+        };
+        let path = dir.join("archive.zip");
+        write_archive(&path, &manifest, &tables).unwrap();
+        verify(&path, &manifest).unwrap();
+
+        let mut zip = ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let read = read_manifest(&mut zip).unwrap();
+        let store_dir = dir.join("store");
+        let target = restore_target(&read.files[0], &store_dir, &[]).unwrap();
+        assert_eq!(target, store_dir.join("a1-notes.txt"));
+        let moved = HashMap::from([(read.files[0].path.as_str(), target.display().to_string())]);
+        let restored = read_tables(&mut zip, &read, &moved).unwrap();
+
+        let dest = database();
+        assert_eq!(insert_rows(&dest, &restored).unwrap(), 0);
+        for (table, filter) in TABLES {
+            let mut expected = tables[*table].clone();
+            if *table == "attachments" {
+                expected[0].insert(
+                    "path".to_string(),
+                    Value::from(target.display().to_string()),
+                );
+            }
+            assert_eq!(
+                rows(&dest, table, filter, "p1").unwrap(),
+                expected,
+                "{}",
+                table
+            );
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn restores_only_into_the_stores() {
+        let attachments = Path::new("/data/attachments");
+        let roots = [
+            PathBuf::from("/home/me/.claude/projects"),
+            PathBuf::from("/profiles/work/projects"),
+        ];
+        let target =
+            |path: &str, kind: &str| restore_target(&archived(path, kind), attachments, &roots);
+
+        assert_eq!(
+            target("/elsewhere/etc/../a1-notes.txt", "attachment").unwrap(),
+            attachments.join("a1-notes.txt")
+        );
+        assert_eq!(
+            target("/old/home/.claude/projects/-repo/s1.jsonl", "transcript").unwrap(),
+            roots[0].join("-repo/s1.jsonl")
+        );
+        assert_eq!(
+            target("/profiles/work/projects/-repo/s1.jsonl", "transcript").unwrap(),
+            roots[1].join("-repo/s1.jsonl")
+        );
+        assert!(target("/data/attachments/..", "attachment").is_err());
+        assert!(target("..\\..\\evil.txt", "attachment").is_err());
+        assert!(target("../../s1.jsonl", "transcript").is_err());
+        assert!(target("/home/me/.bashrc", "transcript").is_err());
+        assert!(target("/tmp/run.sh", "script").is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! `.claudepm` project bundles opened from Finder / Explorer
+//!
+//! A bundle is a small JSON file describing a project (and optionally seed tasks). Opening
+//! one registers the project in the local store, or finds it again by repository path,
+//! and navigates to it. macOS delivers opened files as `RunEvent::Opened`, including on
+//! cold start; Windows and Linux pass the path as a launch argument. Since the webview
+//! may not be listening yet on cold start, the last opened project is also kept for the
+//! frontend to pick up with `take_opened_project`.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+use crate::app_menu;
+use crate::error::Error;
+use crate::session_windows::route_navigation;
+use crate::store::{self, NewProject, NewTask, Project};
+
+pub const EXTENSION: &str = "claudepm";
+const BUNDLE_VERSION: u32 = 1;
+
+static OPENED: Mutex<Option<OpenedProject>> = Mutex::new(None);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleTask {
+    title: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    state: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Bundle {
+    version: u32,
+    name: String,
+    /// Relative paths are resolved against the bundle's directory
+    #[serde(default)]
+    repo_path: Option<String>,
+    /// Only created when the project is new
+    #[serde(default)]
+    tasks: Vec<BundleTask>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenedProject {
+    pub project: Project,
+    /// False when the bundle matched a project that already existed
+    pub created: bool,
+    pub route: String,
+}
+
+fn find_by_repo(repo_path: &str) -> Result<Option<Project>, Error> {
+    Ok(store::list_projects()?
+        .into_iter()
+        .find(|p| p.repo_path.as_deref() == Some(repo_path)))
+}
+
+fn register(path: &Path) -> Result<OpenedProject, Error> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| Error::NotFound(format!("Failed to read {}: {}", path.display(), e)))?;
+    let bundle: Bundle = serde_json::from_str(&contents)
+        .map_err(|e| Error::InvalidInput(format!("Invalid project file: {}", e)))?;
+    if bundle.version > BUNDLE_VERSION {
+        return Err(Error::Unsupported(format!(
+            "Project file version {} is newer than this app supports",
+            bundle.version
+        )));
+    }
+
+    let repo_path = bundle.repo_path.map(|repo| {
+        let repo = PathBuf::from(repo);
+        let resolved = if repo.is_relative() {
+            path.parent().map(|dir| dir.join(&repo)).unwrap_or(repo)
+        } else {
+            repo
+        };
+        fs::canonicalize(&resolved)
+            .unwrap_or(resolved)
+            .display()
+            .to_string()
+    });
+
+    let existing = match repo_path {
+        Some(ref repo) => find_by_repo(repo)?,
+        None => None,
+    };
+    let (project, created) = match existing {
+        Some(project) => (project, false),
+        None => {
+            let project = store::create_project(NewProject {
+                name: bundle.name,
+                repo_path,
+            })?;
+            for task in bundle.tasks {
+                store::create_task(NewTask {
+                    project_id: project.id.clone(),
+                    title: task.title,
+                    description: task.description,
+                    state: task.state,
+                })?;
+            }
+            (project, true)
+        }
+    };
+    Ok(OpenedProject {
+        route: format!("/projects/{}", project.id),
+        project,
+        created,
+    })
+}
+
+/// Register and navigate to the project in a bundle
+pub fn open(app: &AppHandle, path: &Path) {
+    match register(path) {
+        Ok(opened) => {
+            println!(
+                "[Claude PM] Opened project file {} ({})",
+                path.display(),
+                opened.project.name
+            );
+            route_navigation(app, &opened.route);
+            app_menu::project_opened(&opened.project.id);
+            if let Ok(mut last) = OPENED.lock() {
+                *last = Some(opened);
+            }
+        }
+        Err(e) => eprintln!(
+            "[Claude PM] Failed to open project file {}: {}",
+            path.display(),
+            e
+        ),
+    }
+}
+
+fn is_bundle(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == EXTENSION)
+}
+
+/// Bundles passed on the command line (Windows/Linux file association launch)
+pub fn open_from_args(app: &AppHandle) {
+    for arg in std::env::args_os().skip(1) {
+        let path = PathBuf::from(arg);
+        if is_bundle(&path) {
+            open(app, &path);
+        }
+    }
+}
+
+/// Files macOS asked us to open
+#[cfg(target_os = "macos")]
+pub fn open_urls(app: &AppHandle, urls: &[tauri::Url]) {
+    for path in urls.iter().filter_map(|url| url.to_file_path().ok()) {
+        if is_bundle(&path) {
+            open(app, &path);
+        }
+    }
+}
+
+/// The most recently opened bundle, cleared once read; for the cold-start case where
+/// `navigate` fired before the frontend subscribed
+#[tauri::command]
+pub fn take_opened_project() -> Option<OpenedProject> {
+    OPENED.lock().ok().and_then(|mut last| last.take())
+}
+
+/// Open a bundle by path, e.g. from a file picker
+#[tauri::command]
+pub fn open_project_file(path: String) -> Result<OpenedProject, Error> {
+    let opened = register(Path::new(&path))?;
+    app_menu::project_opened(&opened.project.id);
+    Ok(opened)
+}
@@ -0,0 +1,427 @@
+//! Scaffolding new projects from templates
+//!
+//! A template is a directory tree with a `claudepm-template.json` manifest at its root:
+//! the variables it takes, the cards each board column starts with, a CLAUDE.md (a
+//! built-in `claude_md` template, used when the tree has none of its own), a tmux layout
+//! and default schedules. A template is named by a git URL (cloned shallowly), a
+//! directory, or the name of one saved under `project-templates/` in the config
+//! directory.
+//!
+//! `{{variable}}` is replaced in file contents, file names, task text, layout commands
+//! and schedules. Besides the manifest's own variables there are `project` (the name),
+//! `path`, `year` and `date`, and in schedules also `projectId`. Files that aren't UTF-8
+//! text are copied as they are.
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::Error;
+use crate::scheduler::{self, Schedule};
+use crate::store::{self, NewProject, NewTask, Project};
+use crate::tmux_layout::{self, LayoutTemplate, LayoutWindow};
+use crate::{auth, claude_md, config, process};
+
+pub const MANIFEST_FILE: &str = "claudepm-template.json";
+pub const TEMPLATES_DIR: &str = "project-templates";
+/// Larger files are copied without substitution
+const MAX_TEXT_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateVariable {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Required when unset
+    #[serde(default)]
+    pub default: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateTask {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateColumn {
+    /// Task state, e.g. `backlog`
+    pub state: String,
+    pub tasks: Vec<TemplateTask>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TemplateManifest {
+    pub name: String,
+    pub description: Option<String>,
+    pub variables: Vec<TemplateVariable>,
+    pub board: Vec<TemplateColumn>,
+    /// Id of a built-in CLAUDE.md template, e.g. `rust`
+    pub claude_md: Option<String>,
+    /// Saved as a tmux layout named after the project
+    pub tmux_windows: Vec<LayoutWindow>,
+    /// Schedules as `upsert_schedule` takes them
+    pub schedules: Vec<Value>,
+    /// Whether to `git init` when the caller doesn't say
+    pub git_init: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTemplate {
+    pub name: String,
+    pub description: Option<String>,
+    pub variables: Vec<TemplateVariable>,
+    /// Directory to pass to `create_project_from_template`
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScaffoldedProject {
+    pub project: Project,
+    pub files: usize,
+    pub tasks: usize,
+    pub git_initialized: bool,
+    /// Name of the tmux layout saved for the project
+    pub layout: Option<String>,
+    /// Ids of the schedules created
+    pub schedules: Vec<String>,
+}
+
+fn templates_dir() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join(TEMPLATES_DIR))
+}
+
+fn is_git_url(template: &str) -> bool {
+    template.starts_with("https://")
+        || template.starts_with("ssh://")
+        || template.starts_with("git@")
+        || template.ends_with(".git")
+}
+
+/// A shallow clone of `url` in a temporary directory
+fn clone(url: &str) -> Result<PathBuf, Error> {
+    let dir = std::env::temp_dir().join(format!("claudepm-template-{}", auth::random_hex()));
+    let output = process::output(
+        Command::new("git")
+            .args(["clone", "--depth", "1", "--quiet", url])
+            .arg(&dir),
+    )?;
+    if !output.status.success() {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(Error::InvalidInput(format!(
+            "Failed to clone {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(dir)
+}
+
+fn read_manifest(dir: &Path) -> Result<TemplateManifest, Error> {
+    let path = dir.join(MANIFEST_FILE);
+    let contents = fs::read_to_string(&path)
+        .map_err(|_| Error::InvalidInput(format!("{} has no {}", dir.display(), MANIFEST_FILE)))?;
+    serde_json::from_str(&contents)
+        .map_err(|e| Error::InvalidInput(format!("Invalid {}: {}", MANIFEST_FILE, e)))
+}
+
+fn substitute(text: &str, variables: &BTreeMap<String, String>) -> String {
+    variables
+        .iter()
+        .fold(text.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{{{}}}}}", name), value)
+        })
+}
+
+/// Every string in `value`, substituted
+fn substitute_value(value: &mut Value, variables: &BTreeMap<String, String>) {
+    match value {
+        Value::String(s) => *s = substitute(s, variables),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| substitute_value(item, variables)),
+        Value::Object(map) => map
+            .values_mut()
+            .for_each(|item| substitute_value(item, variables)),
+        _ => {}
+    }
+}
+
+/// Copy the template tree into `dest`, substituting as it goes; returns the files copied
+fn copy_tree(
+    src: &Path,
+    dest: &Path,
+    variables: &BTreeMap<String, String>,
+) -> Result<usize, String> {
+    let mut files = 0;
+    let entries =
+        fs::read_dir(src).map_err(|e| format!("Failed to read {}: {}", src.display(), e))?;
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".git" || name == MANIFEST_FILE {
+            continue;
+        }
+        let target = dest.join(substitute(&name, variables));
+        let file_type = entry.file_type().map_err(|e| e.to_string())?;
+        if file_type.is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+            files += copy_tree(&entry.path(), &target, variables)?;
+        } else if file_type.is_file() {
+            let text = match entry.metadata() {
+                Ok(metadata) if metadata.len() <= MAX_TEXT_BYTES => {
+                    fs::read_to_string(entry.path()).ok()
+                }
+                _ => None,
+            };
+            match text {
+                Some(text) => fs::write(&target, substitute(&text, variables)),
+                None => fs::copy(entry.path(), &target).map(|_| ()),
+            }
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+            // Keep scripts executable
+            if let Ok(metadata) = entry.metadata() {
+                let _ = fs::set_permissions(&target, metadata.permissions());
+            }
+            files += 1;
+        }
+        // Symlinks are left out: they could point anywhere outside the template
+    }
+    Ok(files)
+}
+
+/// The template's variables filled in from `values`, defaults and the built-ins
+fn resolve_variables(
+    manifest: &TemplateManifest,
+    mut values: BTreeMap<String, String>,
+    name: &str,
+    path: &Path,
+) -> Result<BTreeMap<String, String>, Error> {
+    for variable in &manifest.variables {
+        if values.contains_key(&variable.name) {
+            continue;
+        }
+        match &variable.default {
+            Some(default) => {
+                values.insert(variable.name.clone(), default.clone());
+            }
+            None => {
+                return Err(Error::InvalidInput(format!(
+                    "The template needs a value for {}",
+                    variable.name
+                )))
+            }
+        }
+    }
+    let now = Local::now();
+    values.insert("project".to_string(), name.to_string());
+    values.insert("path".to_string(), path.display().to_string());
+    values.insert("year".to_string(), now.format("%Y").to_string());
+    values.insert("date".to_string(), now.format("%Y-%m-%d").to_string());
+    Ok(values)
+}
+
+fn scaffold(
+    source: &Path,
+    manifest: TemplateManifest,
+    path: &Path,
+    name: String,
+    variables: BTreeMap<String, String>,
+    git_init: bool,
+) -> Result<ScaffoldedProject, Error> {
+    let mut variables = resolve_variables(&manifest, variables, &name, path)?;
+    for column in &manifest.board {
+        store::validate_state(&column.state)?;
+    }
+    let claude_md =
+        match &manifest.claude_md {
+            Some(id) => Some(claude_md::template(id).ok_or_else(|| {
+                Error::InvalidInput(format!("Unknown CLAUDE.md template: {}", id))
+            })?),
+            None => None,
+        };
+
+    fs::create_dir_all(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut files = copy_tree(source, path, &variables)?;
+    if let Some(contents) = claude_md.filter(|_| !path.join("CLAUDE.md").exists()) {
+        // Built-in templates use `{{project}}` like the manifest does
+        fs::write(path.join("CLAUDE.md"), substitute(contents, &variables))
+            .map_err(|e| format!("Failed to write CLAUDE.md: {}", e))?;
+        files += 1;
+    }
+    if git_init && !path.join(".git").exists() {
+        git2::Repository::init(path)
+            .map_err(|e| format!("Failed to initialize a git repository: {}", e))?;
+    }
+
+    let project = store::create_project(NewProject {
+        name: name.clone(),
+        repo_path: Some(path.display().to_string()),
+    })?;
+    let mut tasks = 0;
+    for column in &manifest.board {
+        for task in &column.tasks {
+            store::create_task(NewTask {
+                project_id: project.id.clone(),
+                title: substitute(&task.title, &variables),
+                description: task
+                    .description
+                    .as_deref()
+                    .map(|d| substitute(d, &variables)),
+                state: Some(column.state.clone()),
+            })?;
+            tasks += 1;
+        }
+    }
+
+    // From here on the project exists; a bad layout or schedule is logged, not fatal
+    let layout = if manifest.tmux_windows.is_empty() {
+        None
+    } else {
+        let mut windows = manifest.tmux_windows.clone();
+        for pane in windows.iter_mut().flat_map(|w| w.panes.iter_mut()) {
+            pane.command = pane.command.as_deref().map(|c| substitute(c, &variables));
+        }
+        match tmux_layout::save_tmux_layout(LayoutTemplate {
+            name: name.clone(),
+            windows,
+        }) {
+            Ok(()) => Some(name.clone()),
+            Err(e) => {
+                eprintln!("[Claude PM] Skipped the template's tmux layout: {}", e);
+                None
+            }
+        }
+    };
+    variables.insert("projectId".to_string(), project.id.clone());
+    let mut schedules = Vec::new();
+    for schedule in &manifest.schedules {
+        let mut schedule = schedule.clone();
+        substitute_value(&mut schedule, &variables);
+        let result = serde_json::from_value::<Schedule>(schedule)
+            .map_err(|e| Error::InvalidInput(e.to_string()))
+            .and_then(|mut schedule| {
+                schedule.id = String::new();
+                scheduler::upsert_schedule(schedule)
+            });
+        match result {
+            Ok(schedule) => schedules.push(schedule.id),
+            Err(e) => eprintln!("[Claude PM] Skipped a template schedule: {}", e),
+        }
+    }
+
+    println!(
+        "[Claude PM] Created project {} from template {} ({} files)",
+        name, manifest.name, files
+    );
+    Ok(ScaffoldedProject {
+        project,
+        files,
+        tasks,
+        git_initialized: path.join(".git").exists(),
+        layout,
+        schedules,
+    })
+}
+
+/// Templates saved in the config directory
+#[tauri::command]
+pub fn list_project_templates() -> Vec<ProjectTemplate> {
+    let Some(entries) = templates_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut templates: Vec<ProjectTemplate> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let manifest = read_manifest(&entry.path()).ok()?;
+            let name = if manifest.name.is_empty() {
+                entry.file_name().to_string_lossy().to_string()
+            } else {
+                manifest.name
+            };
+            Some(ProjectTemplate {
+                name,
+                description: manifest.description,
+                variables: manifest.variables,
+                path: entry.path().display().to_string(),
+            })
+        })
+        .collect();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+/// Create a project at `path` from `template` (git URL, directory or saved template
+/// name) and register it; `path` must not exist yet or be empty
+#[tauri::command]
+pub async fn create_project_from_template(
+    template: String,
+    path: String,
+    name: Option<String>,
+    variables: Option<BTreeMap<String, String>>,
+    git_init: Option<bool>,
+) -> Result<ScaffoldedProject, Error> {
+    let path = PathBuf::from(&path);
+    if !path.is_absolute() {
+        return Err(Error::InvalidInput(
+            "The project path must be absolute".to_string(),
+        ));
+    }
+    let existed = path.exists();
+    if fs::read_dir(&path).is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(Error::InvalidInput(format!(
+            "{} already exists and isn't empty",
+            path.display()
+        )));
+    }
+    let name = name
+        .filter(|n| !n.trim().is_empty())
+        .or_else(|| path.file_name().map(|n| n.to_string_lossy().to_string()))
+        .ok_or_else(|| Error::InvalidInput("A project name is required".to_string()))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let (source, cloned) = if is_git_url(&template) {
+            (clone(&template)?, true)
+        } else if Path::new(&template).is_dir() {
+            (PathBuf::from(&template), false)
+        } else {
+            let saved = templates_dir()
+                .map(|dir| dir.join(&template))
+                .filter(|dir| dir.is_dir() && !template.contains(['/', '\\']))
+                .ok_or_else(|| Error::NotFound(format!("Template not found: {}", template)))?;
+            (saved, false)
+        };
+        let result = read_manifest(&source).and_then(|manifest| {
+            let git_init = git_init.or(manifest.git_init).unwrap_or(true);
+            scaffold(
+                &source,
+                manifest,
+                &path,
+                name,
+                variables.unwrap_or_default(),
+                git_init,
+            )
+        });
+        if cloned {
+            let _ = fs::remove_dir_all(&source);
+        }
+        // Don't leave a half-written project behind
+        if result.is_err() && !existed {
+            let _ = fs::remove_dir_all(&path);
+        }
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,154 @@
+//! Local reverse proxy giving the frontend a stable origin in front of the Node server
+//!
+//! Connections are piped byte-for-byte to the current upstream port, so HTTP keep-alive
+//! and WebSocket traffic pass through untouched. While the server is down, plain requests
+//! get a 503 with `Retry-After`; WebSocket upgrades are held until it comes back. With a
+//! lazy start (see `lifecycle`) the first request starts the server and is answered
+//! with `"status": "starting"`. API reads the server answered before are replayed from
+//! the cache (see `api_cache`) instead of the 503.
+
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{api_cache, lifecycle, server_probe, SERVER_PORT};
+
+/// Port the frontend talks to; never changes
+pub const PROXY_PORT: u16 = 4850;
+/// Largest request head we'll buffer while deciding how to route
+const MAX_HEAD: usize = 64 * 1024;
+/// How long a WebSocket upgrade waits for a restarting server
+const UPGRADE_WAIT: Duration = Duration::from_secs(15);
+const RETRY_AFTER_SECS: u64 = 2;
+
+static UPSTREAM_PORT: AtomicU16 = AtomicU16::new(SERVER_PORT);
+
+/// Point the proxy at the port the server is (re)starting on
+pub fn set_upstream_port(port: u16) {
+    UPSTREAM_PORT.store(port, Ordering::SeqCst);
+}
+
+/// Bind the proxy and serve connections on background threads
+pub fn start() {
+    let listener = match TcpListener::bind(("127.0.0.1", PROXY_PORT)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!(
+                "[Claude PM] Failed to bind proxy on port {}: {}",
+                PROXY_PORT, e
+            );
+            return;
+        }
+    };
+    println!("[Claude PM] Proxy listening on 127.0.0.1:{}", PROXY_PORT);
+
+    thread::spawn(move || {
+        for client in listener.incoming().flatten() {
+            thread::spawn(move || {
+                if let Err(e) = handle(client) {
+                    eprintln!("[Claude PM] Proxy connection error: {}", e);
+                }
+            });
+        }
+    });
+}
+
+fn handle(mut client: TcpStream) -> io::Result<()> {
+    client.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let head = read_head(&mut client)?;
+    if head.is_empty() {
+        return Ok(());
+    }
+
+    // With a lazy start, the first request is what brings the server up
+    let starting =
+        crate::start_deferred_server() || lifecycle::state() == lifecycle::State::Starting;
+    let cacheable = api_cache::cacheable(&head);
+    let Some(mut upstream) = connect_upstream(is_upgrade(&head)) else {
+        if let Some(ref request) = cacheable {
+            if api_cache::serve_stale(&mut client, request)? {
+                return Ok(());
+            }
+        }
+        return write_unavailable(&mut client, starting);
+    };
+    client.set_read_timeout(None)?;
+    upstream.write_all(cacheable.as_ref().map_or(&head, |request| &request.head))?;
+
+    let mut client_reader = client.try_clone()?;
+    let mut upstream_writer = upstream.try_clone()?;
+    let forward = thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut upstream_writer);
+        let _ = upstream_writer.shutdown(Shutdown::Write);
+    });
+    match cacheable {
+        Some(request) => api_cache::record(request, &mut upstream, &mut client),
+        None => {
+            let _ = io::copy(&mut upstream, &mut client);
+        }
+    }
+    let _ = client.shutdown(Shutdown::Both);
+    let _ = forward.join();
+    Ok(())
+}
+
+/// Read until the end of the request head; anything after it (body bytes) is kept too
+fn read_head(client: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    let mut chunk = [0u8; 4096];
+    while head.len() < MAX_HEAD {
+        let n = client.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&chunk[..n]);
+        if head.windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+    }
+    Ok(head)
+}
+
+fn is_upgrade(head: &[u8]) -> bool {
+    String::from_utf8_lossy(head)
+        .to_ascii_lowercase()
+        .contains("\r\nupgrade:")
+}
+
+/// Connect to the server, waiting out a restart if `wait`
+pub fn connect_upstream(wait: bool) -> Option<TcpStream> {
+    let started = Instant::now();
+    loop {
+        let port = UPSTREAM_PORT.load(Ordering::SeqCst);
+        if let Some(stream) = server_probe::connect(port) {
+            return Some(stream);
+        }
+        if !wait || started.elapsed() >= UPGRADE_WAIT {
+            return None;
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+fn write_unavailable(client: &mut TcpStream, starting: bool) -> io::Result<()> {
+    let body = if starting {
+        r#"{"error":"Service Unavailable","status":"starting","message":"Server is starting"}"#
+    } else {
+        r#"{"error":"Service Unavailable","message":"Server is restarting"}"#
+    };
+    let response = format!(
+        "HTTP/1.1 503 Service Unavailable\r\nRetry-After: {}\r\nContent-Type: application/json\r\nAccess-Control-Allow-Origin: *\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        RETRY_AFTER_SECS,
+        body.len(),
+        body
+    );
+    client.write_all(response.as_bytes())
+}
+
+/// Stable base URL the frontend should use for the local server
+#[tauri::command]
+pub fn get_proxy_url() -> String {
+    format!("http://localhost:{}", PROXY_PORT)
+}
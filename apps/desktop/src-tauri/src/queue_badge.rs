@@ -0,0 +1,284 @@
+//! Glanceable agent queue: the tray title and, on Windows, taskbar overlay icons
+//!
+//! The tray title shows the queue as `3⏳ 1❗`: runs queued or running, and runs waiting
+//! for input. The main window's taskbar button gets an overlay with the number waiting
+//! (red) or, when none are, running (blue); each session window gets its own run's
+//! state. Both follow the orchestrator on every run change unless `queue_badges` is off.
+//!
+//! The frontend can set its own tray text or overlay instead; that stays until it's
+//! cleared. A running pomodoro or focus session owns the tray title, which goes back to
+//! the queue when it ends.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::error::Error;
+use crate::menubar::TRAY_ID;
+use crate::orchestrator::{self, AgentRun, RunState};
+use crate::windows::MAIN_WINDOW;
+use crate::{config, focus_session, pomodoro, session_windows};
+
+/// Run changes come in bursts (a queue draining); badges are redrawn once per burst
+const SETTLE: Duration = Duration::from_millis(250);
+/// Overlay icons are drawn at this size; Windows scales them to the taskbar
+const ICON_SIZE: u32 = 16;
+
+static PENDING: AtomicBool = AtomicBool::new(false);
+static TRAY_TEXT: Mutex<Option<String>> = Mutex::new(None);
+/// Window label to the overlay the frontend set for it
+static OVERLAYS: Mutex<BTreeMap<String, Option<OverlayBadge>>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OverlayKind {
+    /// Blue
+    Running,
+    /// Red
+    Attention,
+    /// Green
+    Done,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayBadge {
+    pub kind: OverlayKind,
+    /// Drawn on the dot, as `9+` past nine; a plain dot when unset
+    #[serde(default)]
+    pub count: Option<u32>,
+}
+
+fn enabled() -> bool {
+    config::load().queue_badges.unwrap_or(true)
+}
+
+/// The queue's tray title, `None` when nothing is queued
+fn queue_title(runs: &[AgentRun]) -> Option<String> {
+    let waiting = runs.iter().filter(|r| r.state == RunState::Blocked).count();
+    let active = runs
+        .iter()
+        .filter(|r| matches!(r.state, RunState::Queued | RunState::Running))
+        .count();
+    let mut parts = Vec::new();
+    if active > 0 {
+        parts.push(format!("{}⏳", active));
+    }
+    if waiting > 0 {
+        parts.push(format!("{}❗", waiting));
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// What the tray title shows when no pomodoro or focus session holds it
+pub fn title() -> Option<String> {
+    if let Some(text) = TRAY_TEXT.lock().ok().and_then(|t| t.clone()) {
+        return Some(text);
+    }
+    if !enabled() {
+        return None;
+    }
+    orchestrator::list_agent_runs()
+        .ok()
+        .and_then(|queue| queue_title(&queue.runs))
+}
+
+fn main_overlay(runs: &[AgentRun]) -> Option<OverlayBadge> {
+    let count = |state: RunState| runs.iter().filter(|r| r.state == state).count() as u32;
+    match (count(RunState::Blocked), count(RunState::Running)) {
+        (0, 0) => None,
+        (0, running) => Some(OverlayBadge {
+            kind: OverlayKind::Running,
+            count: Some(running),
+        }),
+        (waiting, _) => Some(OverlayBadge {
+            kind: OverlayKind::Attention,
+            count: Some(waiting),
+        }),
+    }
+}
+
+fn session_overlay(run: &AgentRun) -> Option<OverlayBadge> {
+    let kind = match run.state {
+        RunState::Queued | RunState::Cancelled => return None,
+        RunState::Running => OverlayKind::Running,
+        RunState::Blocked | RunState::Failed => OverlayKind::Attention,
+        RunState::Done => OverlayKind::Done,
+    };
+    Some(OverlayBadge { kind, count: None })
+}
+
+/// 3×5 glyphs for the digits and `+`, one row per element, high bit on the left
+const GLYPHS: [(char, [u8; 5]); 11] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b010, 0b010, 0b010]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('+', [0b000, 0b010, 0b111, 0b010, 0b000]),
+];
+
+/// A filled dot in the badge's colour with its count in white, as RGBA
+fn draw(badge: OverlayBadge) -> Vec<u8> {
+    let color: [u8; 3] = match badge.kind {
+        OverlayKind::Running => [0x25, 0x63, 0xeb],
+        OverlayKind::Attention => [0xdc, 0x26, 0x26],
+        OverlayKind::Done => [0x16, 0xa3, 0x4a],
+    };
+    let size = ICON_SIZE as usize;
+    let mut pixels = vec![0u8; size * size * 4];
+    let center = (size as f64 - 1.0) / 2.0;
+    for y in 0..size {
+        for x in 0..size {
+            let distance = ((x as f64 - center).powi(2) + (y as f64 - center).powi(2)).sqrt();
+            // A pixel of antialiasing at the rim
+            let alpha = (size as f64 / 2.0 - distance).clamp(0.0, 1.0);
+            let i = (y * size + x) * 4;
+            pixels[i..i + 3].copy_from_slice(&color);
+            pixels[i + 3] = (alpha * 255.0) as u8;
+        }
+    }
+    let Some(count) = badge.count else {
+        return pixels;
+    };
+    let text = if count > 9 {
+        "9+".to_string()
+    } else {
+        count.to_string()
+    };
+    // One digit at 2×, or two at 1× a pixel apart, centred
+    let scale = if text.len() == 1 { 2 } else { 1 };
+    let width = text.len() * (3 * scale + 1) - 1;
+    let left = (size - width) / 2;
+    let top = (size - 5 * scale) / 2;
+    for (n, c) in text.chars().enumerate() {
+        let Some((_, rows)) = GLYPHS.iter().find(|(g, _)| *g == c) else {
+            continue;
+        };
+        for (row, bits) in rows.iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let x = left + n * (3 * scale + 1) + col * scale + dx;
+                        let y = top + row * scale + dy;
+                        let i = (y * size + x) * 4;
+                        pixels[i..i + 4].copy_from_slice(&[0xff, 0xff, 0xff, 0xff]);
+                    }
+                }
+            }
+        }
+    }
+    pixels
+}
+
+fn set_overlay(app: &AppHandle, label: &str, badge: Option<OverlayBadge>) {
+    let icon = badge.map(|badge| tauri::image::Image::new_owned(draw(badge), ICON_SIZE, ICON_SIZE));
+    #[cfg(windows)]
+    if let Some(window) = app.get_webview_window(label) {
+        let _ = window.set_overlay_icon(icon);
+    }
+    // Only Windows has taskbar overlays
+    #[cfg(not(windows))]
+    let _ = (app, label, icon);
+}
+
+fn redraw(app: &AppHandle) {
+    let runs = orchestrator::list_agent_runs()
+        .map(|queue| queue.runs)
+        .unwrap_or_default();
+    let holding = pomodoro::get_pomodoro_state().is_some() || focus_session::is_active();
+    if !holding {
+        if let Some(tray) = app.tray_by_id(TRAY_ID) {
+            let _ = tray.set_title(title());
+        }
+    }
+    if !cfg!(windows) {
+        return;
+    }
+    let manual = OVERLAYS.lock().map(|o| o.clone()).unwrap_or_default();
+    let auto = enabled();
+    let mut windows: Vec<(String, Option<OverlayBadge>)> = vec![(
+        MAIN_WINDOW.to_string(),
+        auto.then(|| main_overlay(&runs)).flatten(),
+    )];
+    for window in session_windows::list_session_windows().unwrap_or_default() {
+        let run = runs
+            .iter()
+            .rev()
+            .find(|r| r.session_id.as_deref() == Some(window.session_id.as_str()));
+        windows.push((
+            window.label,
+            auto.then(|| run.and_then(session_overlay)).flatten(),
+        ));
+    }
+    for (label, badge) in windows {
+        let badge = manual.get(&label).copied().unwrap_or(badge);
+        set_overlay(app, &label, badge);
+    }
+}
+
+/// Redraw the badges shortly; called on every run change
+pub fn refresh(app: &AppHandle) {
+    if PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        thread::sleep(SETTLE);
+        PENDING.store(false, Ordering::SeqCst);
+        redraw(&app);
+    });
+}
+
+/// Show `text` as the tray title instead of the queue; `None` or empty goes back to it
+#[tauri::command]
+pub fn set_tray_badge(app: AppHandle, text: Option<String>) -> Result<(), Error> {
+    *TRAY_TEXT.lock().map_err(|e| e.to_string())? =
+        text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    refresh(&app);
+    Ok(())
+}
+
+/// Set a window's taskbar overlay (Windows only): `badge: None` clears it, and
+/// `automatic: true` hands the window back to the queue
+#[tauri::command]
+pub fn set_overlay_badge(
+    app: AppHandle,
+    window: Option<String>,
+    badge: Option<OverlayBadge>,
+    automatic: Option<bool>,
+) -> Result<(), Error> {
+    let label = window.unwrap_or_else(|| MAIN_WINDOW.to_string());
+    if app.get_webview_window(&label).is_none() {
+        return Err(Error::NotFound(format!("No window {}", label)));
+    }
+    let mut overlays = OVERLAYS.lock().map_err(|e| e.to_string())?;
+    if automatic == Some(true) {
+        overlays.remove(&label);
+    } else {
+        overlays.insert(label, badge);
+    }
+    drop(overlays);
+    refresh(&app);
+    Ok(())
+}
+
+/// Turn the automatic queue badges on or off
+#[tauri::command]
+pub fn set_queue_badges_enabled(app: AppHandle, enabled: bool) -> Result<(), Error> {
+    config::update(|c| c.queue_badges = Some(enabled))?;
+    refresh(&app);
+    Ok(())
+}
@@ -0,0 +1,229 @@
+//! Desktop-wide quick capture into the inbox
+//!
+//! The `quickCapture` shortcut (see `shortcuts`) grabs the selected text in whatever app
+//! is frontmost, or the clipboard when nothing is selected, and appends it to the inbox
+//! in the store. The selection is read by sending the app a copy keystroke and restoring
+//! the clipboard afterwards (macOS, which needs Accessibility access) or from the primary
+//! selection (Linux, with `wl-paste` or `xclip`). Unless `capture_context` is off, the
+//! frontmost app, window title and, for known browsers, the page URL are kept with it.
+//!
+//! Events:
+//! - `inbox-item-added` with the new [`InboxItem`]
+//! - `inbox-item-removed` with the item's id
+
+use rusqlite::{params, Row};
+use serde::Serialize;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::error::Error;
+use crate::notifications::{self, NotificationRequest};
+use crate::{activity, applescript, config, process, store};
+
+/// Time for the frontmost app to answer the copy keystroke
+const COPY_SETTLE: Duration = Duration::from_millis(250);
+/// Browsers whose front tab URL can be read, and the AppleScript that reads it
+const BROWSERS: &[(&str, &str)] = &[
+    ("Safari", "URL of front document"),
+    ("Google Chrome", "URL of active tab of front window"),
+    ("Arc", "URL of active tab of front window"),
+    ("Brave Browser", "URL of active tab of front window"),
+    ("Microsoft Edge", "URL of active tab of front window"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InboxItem {
+    pub id: String,
+    pub text: String,
+    /// `selection`, `clipboard` or `manual`
+    pub source: String,
+    pub source_app: Option<String>,
+    pub source_title: Option<String>,
+    pub source_url: Option<String>,
+    pub created_at: i64,
+}
+
+impl InboxItem {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            text: row.get("text")?,
+            source: row.get("source")?,
+            source_app: row.get("source_app")?,
+            source_title: row.get("source_title")?,
+            source_url: row.get("source_url")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+/// Frontmost app, window title and page URL
+type Context = (Option<String>, Option<String>, Option<String>);
+
+fn context() -> Context {
+    if !config::load().capture_context.unwrap_or(true) {
+        return (None, None, None);
+    }
+    let Ok((app, title)) = activity::frontmost() else {
+        return (None, None, None);
+    };
+    let url = BROWSERS
+        .iter()
+        .find(|(browser, _)| *browser == app)
+        .filter(|_| cfg!(target_os = "macos"))
+        .and_then(|(browser, property)| {
+            applescript::run(&format!(
+                "tell application \"{}\" to get {}",
+                browser, property
+            ))
+            .ok()
+        })
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    let title = Some(title).filter(|t| !t.is_empty());
+    (Some(app).filter(|a| !a.is_empty()), title, url)
+}
+
+fn command_text(program: &str, args: &[&str]) -> Option<String> {
+    let output = process::output(Command::new(program).args(args)).ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// The frontmost app's selected text, if any
+fn selection(app: &AppHandle) -> Option<String> {
+    if cfg!(target_os = "macos") {
+        let clipboard = app.clipboard();
+        let before = clipboard.read_text().ok();
+        applescript::run(
+            "tell application \"System Events\" to keystroke \"c\" using command down",
+        )
+        .ok()?;
+        thread::sleep(COPY_SETTLE);
+        let copied = clipboard.read_text().ok();
+        // Put back what the user had copied; the selection isn't theirs to keep there
+        match &before {
+            Some(text) => {
+                let _ = clipboard.write_text(text.clone());
+            }
+            None => {
+                let _ = clipboard.clear();
+            }
+        }
+        copied.filter(|text| Some(text) != before.as_ref())
+    } else if cfg!(target_os = "linux") {
+        command_text("wl-paste", &["--primary", "--no-newline"])
+            .or_else(|| command_text("xclip", &["-o", "-selection", "primary"]))
+    } else {
+        None
+    }
+    .filter(|text| !text.trim().is_empty())
+}
+
+fn insert(text: String, source: &str, (app, title, url): Context) -> Result<InboxItem, String> {
+    let item = InboxItem {
+        id: store::new_id(),
+        text,
+        source: source.to_string(),
+        source_app: app,
+        source_title: title,
+        source_url: url,
+        created_at: store::now_ms(),
+    };
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO inbox_items (id, text, source, source_app, source_title, source_url, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                item.id,
+                item.text,
+                item.source,
+                item.source_app,
+                item.source_title,
+                item.source_url,
+                item.created_at
+            ],
+        )
+        .map(|_| ())
+    })?;
+    Ok(item)
+}
+
+/// Capture the selection or clipboard into the inbox
+pub fn capture(app: &AppHandle) -> Result<InboxItem, Error> {
+    // Read before anything else could take focus
+    let context = context();
+    let (text, source) = match selection(app) {
+        Some(text) => (text, "selection"),
+        None => (
+            app.clipboard()
+                .read_text()
+                .ok()
+                .filter(|text| !text.trim().is_empty())
+                .ok_or_else(|| {
+                    Error::InvalidInput("Nothing is selected or on the clipboard".to_string())
+                })?,
+            "clipboard",
+        ),
+    };
+    let item = insert(text.trim().to_string(), source, context)?;
+    println!("[Claude PM] Captured {} to the inbox", source);
+    let _ = app.emit("inbox-item-added", &item);
+    let preview: String = item.text.chars().take(80).collect();
+    notifications::notify(
+        app,
+        NotificationRequest {
+            title: "Captured to inbox".to_string(),
+            body: preview,
+            key: Some(format!("inbox:{}", item.id)),
+            target: Some("/inbox".to_string()),
+            category: Some("capture".to_string()),
+            ..Default::default()
+        },
+    );
+    Ok(item)
+}
+
+/// Run the capture flow, as the shortcut does
+#[tauri::command(async)]
+pub fn quick_capture(app: AppHandle) -> Result<InboxItem, Error> {
+    capture(&app)
+}
+
+/// Add text to the inbox from the UI
+#[tauri::command]
+pub fn add_inbox_item(app: AppHandle, text: String) -> Result<InboxItem, Error> {
+    if text.trim().is_empty() {
+        return Err(Error::InvalidInput("Nothing to capture".to_string()));
+    }
+    let item = insert(text.trim().to_string(), "manual", (None, None, None))?;
+    let _ = app.emit("inbox-item-added", &item);
+    Ok(item)
+}
+
+/// Inbox items, newest first
+#[tauri::command]
+pub fn list_inbox_items() -> Result<Vec<InboxItem>, Error> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT * FROM inbox_items ORDER BY created_at DESC")?;
+        let rows = stmt.query_map([], InboxItem::from_row)?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn remove_inbox_item(app: AppHandle, id: String) -> Result<(), Error> {
+    let removed =
+        store::with_conn(|conn| conn.execute("DELETE FROM inbox_items WHERE id = ?1", [&id]))?;
+    if removed == 0 {
+        return Err(Error::NotFound(format!("No inbox item with id {}", id)));
+    }
+    let _ = app.emit("inbox-item-removed", &id);
+    Ok(())
+}
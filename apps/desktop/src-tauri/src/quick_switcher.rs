@@ -0,0 +1,89 @@
+//! Raycast-style quick switcher: a frameless, always-on-top palette window
+//!
+//! The window is created lazily, shown on the monitor under the cursor and hidden again
+//! as soon as it loses focus, so it stays warm between uses.
+
+use tauri::{
+    AppHandle, Manager, PhysicalPosition, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+    WindowEvent,
+};
+
+use crate::error::Error;
+use crate::windows::active_monitor;
+
+pub const QUICK_SWITCHER_WINDOW: &str = "quick-switcher";
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 420.0;
+
+fn get_or_create(app: &AppHandle) -> Result<WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(QUICK_SWITCHER_WINDOW) {
+        return Ok(window);
+    }
+
+    let window = WebviewWindowBuilder::new(
+        app,
+        QUICK_SWITCHER_WINDOW,
+        WebviewUrl::App("index.html#/quick-switcher".into()),
+    )
+    .title("Quick Switcher")
+    .inner_size(WIDTH, HEIGHT)
+    .decorations(false)
+    .always_on_top(true)
+    .resizable(false)
+    .skip_taskbar(true)
+    .visible(false)
+    .build()
+    .map_err(|e| format!("Failed to create quick switcher: {}", e))?;
+
+    let handle = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Focused(false) = event {
+            let _ = handle.hide();
+        }
+    });
+
+    Ok(window)
+}
+
+/// Center horizontally on the active monitor, a quarter of the way down
+fn position_on_active_monitor(app: &AppHandle, window: &WebviewWindow) {
+    let Some(monitor) = active_monitor(app) else {
+        return;
+    };
+    let scale = monitor.scale_factor();
+    let origin = monitor.position();
+    let size = monitor.size();
+
+    let x = origin.x + ((size.width as f64 - WIDTH * scale) / 2.0) as i32;
+    let y = origin.y + (size.height as f64 / 4.0) as i32;
+    let _ = window.set_position(PhysicalPosition::new(x, y));
+}
+
+#[tauri::command]
+pub fn show_quick_switcher(app: AppHandle) -> Result<(), Error> {
+    let window = get_or_create(&app)?;
+    position_on_active_monitor(&app, &window);
+    window.show().map_err(|e| e.to_string())?;
+    window.set_focus().map_err(|e| e.to_string().into())
+}
+
+#[tauri::command]
+pub fn hide_quick_switcher(app: AppHandle) -> Result<(), Error> {
+    match app.get_webview_window(QUICK_SWITCHER_WINDOW) {
+        Some(window) => Ok(window.hide().map_err(|e| e.to_string())?),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn toggle_quick_switcher(app: AppHandle) -> Result<(), Error> {
+    let visible = app
+        .get_webview_window(QUICK_SWITCHER_WINDOW)
+        .and_then(|w| w.is_visible().ok())
+        .unwrap_or(false);
+    if visible {
+        hide_quick_switcher(app)
+    } else {
+        show_quick_switcher(app)
+    }
+}
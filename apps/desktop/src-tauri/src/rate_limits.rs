@@ -0,0 +1,330 @@
+//! Rate-limit and usage-limit tracking per Claude account
+//!
+//! Agent output (watched tmux panes and followed transcripts) is scanned for Claude
+//! Code's usage-limit messages and for API rate-limit errors. A limit is kept per account
+//! (the signed-in Claude account, or `api-key` when an API key is in use) until its
+//! reset time, parsed from the message when it gives one. While any account is limited
+//! the orchestrator holds queued runs.
+//!
+//! Events:
+//! - `rate-limit-status` with the [`LimitStatus`] when a limit is hit, then every minute
+//!   with the countdown (e.g. "limit resets in 42 min")
+//! - `rate-limit-reset` with the account once its limit has passed
+
+use chrono::{Duration as ChronoDuration, Local, NaiveTime};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{claude_profiles, json_file, mcp_config, store};
+
+const TICK: Duration = Duration::from_secs(60);
+/// Assumed wait for a rate-limit error that doesn't say when to retry
+const DEFAULT_RETRY_MS: i64 = 60_000;
+/// Lines already acted on, so a limit message still on screen doesn't re-trigger
+const SEEN_LIMIT: usize = 200;
+const ACCOUNT_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LimitKind {
+    /// The plan's usage window is exhausted (hours until reset)
+    UsageLimit,
+    /// Too many requests (usually seconds to minutes)
+    RateLimit,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitStatus {
+    pub account: String,
+    pub kind: LimitKind,
+    pub message: String,
+    /// Where it was seen, e.g. a tmux target or `transcript:<session>`
+    pub source: String,
+    pub detected_at: i64,
+    pub resets_at: Option<i64>,
+    /// Human-readable countdown, e.g. "limit resets in 42 min"
+    pub summary: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitStatus {
+    pub account: String,
+    pub limited: bool,
+    pub limits: Vec<LimitStatus>,
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static LIMITS: Mutex<BTreeMap<String, LimitStatus>> = Mutex::new(BTreeMap::new());
+static SEEN: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static ACCOUNT: Mutex<Option<(Instant, String)>> = Mutex::new(None);
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// The account agents run as: the signed-in Claude account, else an API key
+pub fn current_account() -> String {
+    // ~/.claude.json can be large; re-read it at most once per ACCOUNT_TTL
+    if let Ok(cached) = ACCOUNT.lock() {
+        if let Some((_, account)) = cached.as_ref().filter(|(at, _)| at.elapsed() < ACCOUNT_TTL) {
+            return account.clone();
+        }
+    }
+    let account = if env::var("ANTHROPIC_API_KEY").is_ok_and(|key| !key.is_empty())
+        || claude_profiles::uses_api_key()
+    {
+        "api-key".to_string()
+    } else {
+        mcp_config::claude_json_path()
+            .and_then(|path| json_file::read(&path))
+            .ok()
+            .and_then(|config| {
+                config["oauthAccount"]["emailAddress"]
+                    .as_str()
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| "default".to_string())
+    };
+    if let Ok(mut cached) = ACCOUNT.lock() {
+        *cached = Some((Instant::now(), account.clone()));
+    }
+    account
+}
+
+fn regex(cell: &'static OnceLock<Regex>, pattern: &str) -> &'static Regex {
+    cell.get_or_init(|| Regex::new(pattern).expect("valid pattern"))
+}
+
+/// `Claude AI usage limit reached|1748000000`
+fn epoch_reset(line: &str) -> Option<i64> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let caps = regex(&RE, r"\|(\d{10})\b").captures(line)?;
+    caps[1].parse::<i64>().ok().map(|secs| secs * 1000)
+}
+
+/// `resets 3pm`, `reset at 3:30 pm`: the next time it's that o'clock locally
+fn clock_reset(line: &str) -> Option<i64> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let caps = regex(
+        &RE,
+        r"(?i)resets?(?:\s+at)?\s+(\d{1,2})(?::(\d{2}))?\s*(am|pm)",
+    )
+    .captures(line)?;
+    let mut hour: u32 = caps[1].parse().ok()?;
+    let minute: u32 = caps.get(2).map_or(Some(0), |m| m.as_str().parse().ok())?;
+    match (hour, caps[3].eq_ignore_ascii_case("pm")) {
+        (12, false) => hour = 0,
+        (12, true) => {}
+        (_, true) => hour += 12,
+        _ => {}
+    }
+    let now = Local::now();
+    let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+    let mut at = now
+        .date_naive()
+        .and_time(time)
+        .and_local_timezone(Local)
+        .earliest()?;
+    if at <= now {
+        at += ChronoDuration::days(1);
+    }
+    Some(at.timestamp_millis())
+}
+
+/// `try again in 30 seconds`, `"retry-after": 20`
+fn retry_reset(line: &str) -> Option<i64> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    static AFTER: OnceLock<Regex> = OnceLock::new();
+    let (amount, unit) =
+        match regex(&RE, r"(?i)(?:try again|retry) in (\d+)\s*([a-z]+)").captures(line) {
+            Some(caps) => (caps[1].parse::<i64>().ok()?, caps[2].to_lowercase()),
+            None => {
+                let caps = regex(&AFTER, r#"(?i)retry-after"?\s*[:=]\s*"?(\d+)"#).captures(line)?;
+                (caps[1].parse::<i64>().ok()?, "s".to_string())
+            }
+        };
+    let unit_ms = match unit.chars().next()? {
+        'h' => 3_600_000,
+        'm' if !unit.starts_with("ms") => 60_000,
+        's' => 1000,
+        _ => return None,
+    };
+    Some(store::now_ms() + amount * unit_ms)
+}
+
+/// The limit a line of agent output reports, if any
+fn parse_line(line: &str) -> Option<(LimitKind, Option<i64>)> {
+    let lower = line.to_lowercase();
+    let kind = if lower.contains("usage limit") || lower.contains("limit reached") {
+        LimitKind::UsageLimit
+    } else if lower.contains("rate_limit_error")
+        || lower.contains("api error: 429")
+        || lower.contains("rate limit exceeded")
+    {
+        LimitKind::RateLimit
+    } else {
+        return None;
+    };
+    let resets_at = epoch_reset(line)
+        .or_else(|| clock_reset(line))
+        .or_else(|| retry_reset(line))
+        .or_else(|| (kind == LimitKind::RateLimit).then(|| store::now_ms() + DEFAULT_RETRY_MS));
+    Some((kind, resets_at))
+}
+
+fn summary(resets_at: Option<i64>) -> String {
+    let Some(resets_at) = resets_at else {
+        return "limit reached; reset time unknown".to_string();
+    };
+    let minutes = (resets_at - store::now_ms()).max(0) / 60_000;
+    match minutes {
+        0 => "limit resets in under a minute".to_string(),
+        m if m < 60 => format!("limit resets in {} min", m),
+        m => format!("limit resets in {}h {:02}m", m / 60, m % 60),
+    }
+}
+
+fn emit_status(status: &LimitStatus) {
+    if let Some(app) = APP.get() {
+        let _ = app.emit("rate-limit-status", status);
+    }
+}
+
+/// Record a limit found in `text` (agent output from `source`) for the current account
+pub fn scan(text: &str, source: &str) {
+    let Some((line, kind, resets_at)) = text.lines().rev().find_map(|line| {
+        parse_line(line).map(|(kind, resets_at)| (line.trim().to_string(), kind, resets_at))
+    }) else {
+        return;
+    };
+    // A reset time already past means the message is stale
+    if resets_at.is_some_and(|at| at <= store::now_ms()) {
+        return;
+    }
+    {
+        let Ok(mut seen) = SEEN.lock() else {
+            return;
+        };
+        let key = format!("{}\t{}", source, line);
+        if seen.contains(&key) {
+            return;
+        }
+        if seen.len() == SEEN_LIMIT {
+            seen.pop_front();
+        }
+        seen.push_back(key);
+    }
+    let account = current_account();
+    let status = LimitStatus {
+        account: account.clone(),
+        kind,
+        message: line,
+        source: source.to_string(),
+        detected_at: store::now_ms(),
+        resets_at,
+        summary: summary(resets_at),
+    };
+    println!(
+        "[Claude PM] {} for {}: {}",
+        match kind {
+            LimitKind::UsageLimit => "Usage limit",
+            LimitKind::RateLimit => "Rate limit",
+        },
+        account,
+        status.summary
+    );
+    if let Ok(mut limits) = LIMITS.lock() {
+        limits.insert(account, status.clone());
+    }
+    emit_status(&status);
+}
+
+/// Drop limits whose reset time has passed; refresh countdowns of the rest
+fn tick() {
+    let now = store::now_ms();
+    let (expired, active): (Vec<String>, Vec<LimitStatus>) = match LIMITS.lock() {
+        Ok(mut limits) => {
+            let expired: Vec<String> = limits
+                .iter()
+                .filter(|(_, s)| s.resets_at.is_some_and(|at| at <= now))
+                .map(|(account, _)| account.clone())
+                .collect();
+            for account in &expired {
+                limits.remove(account);
+            }
+            for status in limits.values_mut() {
+                status.summary = summary(status.resets_at);
+            }
+            (expired, limits.values().cloned().collect())
+        }
+        Err(_) => return,
+    };
+    for account in expired {
+        println!("[Claude PM] Limit for {} has reset", account);
+        if let Some(app) = APP.get() {
+            let _ = app.emit("rate-limit-reset", &account);
+        }
+    }
+    for status in &active {
+        emit_status(status);
+    }
+}
+
+/// When queued work may resume: `Some` while the current account is limited
+pub fn limited_until() -> Option<Option<i64>> {
+    let account = current_account();
+    let limits = LIMITS.lock().ok()?;
+    let status = limits.get(&account)?;
+    match status.resets_at {
+        Some(at) if at <= store::now_ms() => None,
+        resets_at => Some(resets_at),
+    }
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let _ = APP.set(app);
+    thread::spawn(|| loop {
+        thread::sleep(TICK);
+        tick();
+    });
+}
+
+#[tauri::command]
+pub fn get_rate_limit_status() -> Result<RateLimitStatus, Error> {
+    let account = current_account();
+    let mut limits: Vec<LimitStatus> = LIMITS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .values()
+        .cloned()
+        .collect();
+    for status in &mut limits {
+        status.summary = summary(status.resets_at);
+    }
+    Ok(RateLimitStatus {
+        limited: limits.iter().any(|s| s.account == account),
+        account,
+        limits,
+    })
+}
+
+/// Forget a limit, e.g. after switching plans; queued runs resume on the next check
+#[tauri::command]
+pub fn clear_rate_limit(account: Option<String>) -> Result<(), Error> {
+    let account = account.unwrap_or_else(current_account);
+    LIMITS.lock().map_err(|e| e.to_string())?.remove(&account);
+    if let Some(app) = APP.get() {
+        let _ = app.emit("rate-limit-reset", &account);
+    }
+    Ok(())
+}
@@ -0,0 +1,253 @@
+//! Recording tmux panes to asciinema v2 `.cast` files
+//!
+//! tmux `pipe-pane` appends the pane's raw output to a spool file; a thread tails it,
+//! timestamps each chunk and writes the cast. When the recording stops (or the pane goes
+//! away) the cast is stored as an attachment on the pane's task so the run can be
+//! replayed later with any asciinema player.
+//!
+//! Events:
+//! - `recording-stopped`: a recording was finalised, with its attachment
+
+use rusqlite::OptionalExtension;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::attachments::{self, Attachment};
+use crate::error::Error;
+use crate::process::shell_quote;
+use crate::{store, tmux};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often to check that the recorded pane still exists
+const PANE_CHECK_EVERY: u32 = 20;
+const CAST_MIME: &str = "application/x-asciicast";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recording {
+    pub id: String,
+    /// tmux target (`session:window.pane` or a pane id)
+    pub target: String,
+    pub task_id: Option<String>,
+    pub started_at: i64,
+}
+
+struct Active {
+    info: Recording,
+    stop: Arc<AtomicBool>,
+    finished: mpsc::Receiver<Result<Attachment, Error>>,
+}
+
+static RECORDINGS: Mutex<BTreeMap<String, Active>> = Mutex::new(BTreeMap::new());
+
+fn pane_size(target: &str) -> Result<(u32, u32), String> {
+    let out = tmux::run(&[
+        "display-message",
+        "-p",
+        "-t",
+        target,
+        "#{pane_width} #{pane_height}",
+    ])?;
+    let mut parts = out.split_whitespace().map(str::parse::<u32>);
+    match (parts.next(), parts.next()) {
+        (Some(Ok(width)), Some(Ok(height))) => Ok((width, height)),
+        _ => Err(format!("Unexpected pane size from tmux: {}", out.trim())),
+    }
+}
+
+fn pane_exists(target: &str) -> bool {
+    tmux::run(&["display-message", "-p", "-t", target, "#{pane_id}"]).is_ok()
+}
+
+/// The task of the running session in this pane, if it was started from a task
+pub fn task_for_pane(target: &str) -> Option<String> {
+    store::with_conn(|conn| {
+        conn.query_row(
+            "SELECT task_id FROM sessions WHERE pane_id = ?1 AND status = 'running' AND task_id IS NOT NULL ORDER BY started_at DESC LIMIT 1",
+            [target],
+            |row| row.get(0),
+        )
+        .optional()
+    })
+    .ok()
+    .flatten()
+}
+
+/// Split off any incomplete UTF-8 sequence at the end so it's emitted with the next chunk
+fn take_text(pending: &mut Vec<u8>) -> String {
+    let valid = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    let rest = pending.split_off(valid);
+    let text = String::from_utf8_lossy(pending).to_string();
+    *pending = rest;
+    text
+}
+
+/// Copy new spool output into the cast until stopped or the pane disappears
+fn pump(
+    target: &str,
+    spool: &Path,
+    cast: &mut File,
+    stop: &AtomicBool,
+    started: Instant,
+) -> Result<(), String> {
+    let mut reader = File::open(spool).map_err(|e| format!("Failed to open spool: {}", e))?;
+    let mut pending = Vec::new();
+    let mut ticks = 0u32;
+    loop {
+        let stopping = stop.load(Ordering::SeqCst)
+            || (ticks > 0 && ticks.is_multiple_of(PANE_CHECK_EVERY) && !pane_exists(target));
+        if stopping {
+            // Close the pipe first so everything tmux had buffered is in the spool
+            let _ = tmux::run(&["pipe-pane", "-t", target]);
+        }
+        reader
+            .read_to_end(&mut pending)
+            .map_err(|e| format!("Failed to read spool: {}", e))?;
+        let text = take_text(&mut pending);
+        if !text.is_empty() {
+            let event = json!([started.elapsed().as_secs_f64(), "o", text]);
+            writeln!(cast, "{}", event).map_err(|e| format!("Failed to write cast: {}", e))?;
+        }
+        if stopping {
+            return Ok(());
+        }
+        ticks += 1;
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn record(
+    recording: &Recording,
+    cast_path: &Path,
+    spool: &Path,
+    stop: &AtomicBool,
+    started: Instant,
+) -> Result<Attachment, Error> {
+    let mut cast = fs::OpenOptions::new()
+        .append(true)
+        .open(cast_path)
+        .map_err(|e| format!("Failed to open cast: {}", e))?;
+    let result = pump(&recording.target, spool, &mut cast, stop, started);
+    let _ = fs::remove_file(spool);
+    result?;
+    attachments::add(recording.task_id.as_deref(), cast_path, CAST_MIME)
+}
+
+/// Start recording a pane; linked to the pane's session task when `task_id` is omitted
+#[tauri::command]
+pub fn start_recording(
+    app: AppHandle,
+    target: String,
+    task_id: Option<String>,
+) -> Result<Recording, Error> {
+    if let Ok(recordings) = RECORDINGS.lock() {
+        if recordings.values().any(|r| r.info.target == target) {
+            return Err(Error::InvalidInput(format!(
+                "{} is already being recorded",
+                target
+            )));
+        }
+    }
+    let (width, height) = pane_size(&target)?;
+    let recording = Recording {
+        id: store::new_id(),
+        task_id: task_id.or_else(|| task_for_pane(&target)),
+        target,
+        started_at: store::now_ms(),
+    };
+
+    let cast_path = attachments::new_path(&format!(
+        "session-{}.cast",
+        chrono::Local::now().format("%Y-%m-%d-%H%M%S")
+    ))?;
+    let spool: PathBuf = cast_path.with_extension("spool");
+    let header = json!({
+        "version": 2,
+        "width": width,
+        "height": height,
+        "timestamp": recording.started_at / 1000,
+        "title": recording.target,
+    });
+    fs::write(&cast_path, format!("{}\n", header))
+        .map_err(|e| format!("Failed to create cast: {}", e))?;
+    File::create(&spool).map_err(|e| format!("Failed to create spool: {}", e))?;
+
+    let pipe = format!("cat >> {}", shell_quote(&spool.display().to_string()));
+    if let Err(e) = tmux::run(&["pipe-pane", "-t", &recording.target, &pipe]) {
+        let _ = fs::remove_file(&cast_path);
+        let _ = fs::remove_file(&spool);
+        return Err(e.into());
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let (tx, finished) = mpsc::channel();
+    let started = Instant::now();
+    {
+        let recording = recording.clone();
+        let stop = stop.clone();
+        thread::spawn(move || {
+            let result = record(&recording, &cast_path, &spool, &stop, started);
+            if let Ok(ref attachment) = result {
+                let _ = app.emit("recording-stopped", attachment.clone());
+            }
+            // The pane closed on its own; nobody is waiting in stop_recording
+            if !stop.load(Ordering::SeqCst) {
+                if let Ok(mut recordings) = RECORDINGS.lock() {
+                    recordings.remove(&recording.id);
+                }
+            }
+            let _ = tx.send(result);
+        });
+    }
+
+    RECORDINGS.lock().map_err(|e| e.to_string())?.insert(
+        recording.id.clone(),
+        Active {
+            info: recording.clone(),
+            stop,
+            finished,
+        },
+    );
+    println!("[Claude PM] Recording {}", recording.target);
+    Ok(recording)
+}
+
+/// Stop a recording and return the stored cast
+#[tauri::command]
+pub async fn stop_recording(id: String) -> Result<Attachment, Error> {
+    let active = RECORDINGS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&id)
+        .ok_or_else(|| Error::NotFound(format!("Recording not found: {}", id)))?;
+    active.stop.store(true, Ordering::SeqCst);
+    tauri::async_runtime::spawn_blocking(move || {
+        active
+            .finished
+            .recv()
+            .unwrap_or_else(|_| Err(Error::Internal("Recording thread exited".to_string())))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn list_recordings() -> Vec<Recording> {
+    RECORDINGS
+        .lock()
+        .map(|recordings| recordings.values().map(|r| r.info.clone()).collect())
+        .unwrap_or_default()
+}
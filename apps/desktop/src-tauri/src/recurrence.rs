@@ -0,0 +1,651 @@
+//! Recurring tasks: rules that create a fresh task each time they come due
+//!
+//! A recurrence holds the task to create and a rule: every N days, on given weekdays
+//! every N weeks, on a day of the month or the Nth weekday of the month every N months,
+//! or N days after the previous instance was completed. Instances are created at the
+//! rule's time of day, local time.
+//!
+//! Each recurrence stores when it's next due, so the materializer (checking every
+//! minute, and straight away on launch) notices instances that came due while the app
+//! was closed. It creates one instance for a missed stretch, not one per missed
+//! occurrence, then moves on to the next occurrence after now. An after-completion rule
+//! only gets its next due time once the previous instance is done (or deleted).
+//!
+//! Events:
+//! - `recurring-task-created` with the [`RecurringTaskCreated`] for each new instance
+
+use chrono::{
+    DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, NaiveTime, TimeZone,
+};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::power;
+use crate::store::{self, NewTask, Task};
+
+const TICK: Duration = Duration::from_secs(60);
+/// How far ahead to look for a rule's next occurrence before giving up on it
+const SEARCH_DAYS: u32 = 3 * 366;
+const DEFAULT_TIME: &str = "09:00";
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl Weekday {
+    fn to_chrono(self) -> chrono::Weekday {
+        match self {
+            Self::Mon => chrono::Weekday::Mon,
+            Self::Tue => chrono::Weekday::Tue,
+            Self::Wed => chrono::Weekday::Wed,
+            Self::Thu => chrono::Weekday::Thu,
+            Self::Fri => chrono::Weekday::Fri,
+            Self::Sat => chrono::Weekday::Sat,
+            Self::Sun => chrono::Weekday::Sun,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum RecurrenceRule {
+    Daily {
+        every: u32,
+    },
+    /// e.g. every weekday: `weekdays: [mon, tue, wed, thu, fri]`, `every: 1`
+    Weekly {
+        every: u32,
+        weekdays: Vec<Weekday>,
+    },
+    /// Day of the month; past the end of a short month it falls on the last day
+    MonthlyDay {
+        every: u32,
+        day: u32,
+    },
+    /// e.g. the 2nd Tuesday: `week: 2, weekday: tue`; `week: -1` is the last one
+    MonthlyWeekday {
+        every: u32,
+        week: i32,
+        weekday: Weekday,
+    },
+    AfterCompletion {
+        days: u32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recurrence {
+    pub id: String,
+    pub project_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// State new instances start in
+    pub state: String,
+    pub rule: RecurrenceRule,
+    /// Local time of day instances are created, `HH:MM`
+    pub time: String,
+    /// Occurrences are counted from this day (Unix millis)
+    pub starts_at: i64,
+    pub ends_at: Option<i64>,
+    pub enabled: bool,
+    /// `None` once the rule has run out, or while an after-completion instance is open
+    pub next_due: Option<i64>,
+    pub last_task_id: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Recurrence {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let rule: String = row.get("rule")?;
+        Ok(Self {
+            id: row.get("id")?,
+            project_id: row.get("project_id")?,
+            title: row.get("title")?,
+            description: row.get("description")?,
+            state: row.get("state")?,
+            rule: serde_json::from_str(&rule).map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, e.into())
+            })?,
+            time: row.get("time")?,
+            starts_at: row.get("starts_at")?,
+            ends_at: row.get("ends_at")?,
+            enabled: row.get("enabled")?,
+            next_due: row.get("next_due")?,
+            last_task_id: row.get("last_task_id")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewRecurrence {
+    pub project_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub state: Option<String>,
+    pub rule: RecurrenceRule,
+    /// `09:00` when unset
+    pub time: Option<String>,
+    /// Today when unset
+    pub starts_at: Option<i64>,
+    pub ends_at: Option<i64>,
+}
+
+/// Fields left as `None` are unchanged
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecurrenceUpdate {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub state: Option<String>,
+    pub rule: Option<RecurrenceRule>,
+    pub time: Option<String>,
+    pub starts_at: Option<i64>,
+    pub ends_at: Option<i64>,
+    pub enabled: Option<bool>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecurringTaskCreated {
+    pub recurrence_id: String,
+    pub task: Task,
+    /// When the instance was due; earlier than now if it came due while the app was closed
+    pub due_at: i64,
+}
+
+fn validate(rule: &RecurrenceRule, time: &str) -> Result<NaiveTime, Error> {
+    let invalid = |message: &str| Err(Error::InvalidInput(message.to_string()));
+    match rule {
+        RecurrenceRule::Daily { every }
+        | RecurrenceRule::Weekly { every, .. }
+        | RecurrenceRule::MonthlyDay { every, .. }
+        | RecurrenceRule::MonthlyWeekday { every, .. }
+            if *every == 0 =>
+        {
+            return invalid("A recurrence must repeat at least every 1");
+        }
+        RecurrenceRule::Weekly { weekdays, .. } if weekdays.is_empty() => {
+            return invalid("A weekly recurrence needs at least one weekday");
+        }
+        RecurrenceRule::MonthlyDay { day, .. } if !(1..=31).contains(day) => {
+            return invalid("The day of the month must be between 1 and 31");
+        }
+        RecurrenceRule::MonthlyWeekday { week, .. } if !matches!(week, 1..=5 | -1) => {
+            return invalid("The week of the month must be 1 to 5, or -1 for the last");
+        }
+        RecurrenceRule::AfterCompletion { days } if *days == 0 => {
+            return invalid("A recurrence after completion needs at least 1 day");
+        }
+        _ => {}
+    }
+    NaiveTime::parse_from_str(time, "%H:%M")
+        .map_err(|_| Error::InvalidInput(format!("Invalid time of day: {}", time)))
+}
+
+fn local_date(ms: i64) -> NaiveDate {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .map(|dt| dt.date_naive())
+        .unwrap_or_else(|| Local::now().date_naive())
+}
+
+fn at(date: NaiveDate, time: NaiveTime) -> Option<DateTime<Local>> {
+    // `earliest` settles the hour a DST switch repeats; a skipped hour has no answer
+    Local.from_local_datetime(&date.and_time(time)).earliest()
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = match date.month() {
+        12 => (date.year() + 1, 1),
+        month => (date.year(), month + 1),
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|first| first.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(28)
+}
+
+fn months_between(from: NaiveDate, to: NaiveDate) -> i32 {
+    (to.year() - from.year()) * 12 + to.month() as i32 - from.month() as i32
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - ChronoDuration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// Whether a calendar rule anchored at `anchor` falls on `date` (never for after-completion)
+fn falls_on(rule: &RecurrenceRule, anchor: NaiveDate, date: NaiveDate) -> bool {
+    if date < anchor {
+        return false;
+    }
+    match rule {
+        RecurrenceRule::Daily { every } => (date - anchor).num_days() % *every as i64 == 0,
+        RecurrenceRule::Weekly { every, weekdays } => {
+            let weeks = (week_start(date) - week_start(anchor)).num_days() / 7;
+            weeks % *every as i64 == 0 && weekdays.iter().any(|d| d.to_chrono() == date.weekday())
+        }
+        RecurrenceRule::MonthlyDay { every, day } => {
+            months_between(anchor, date) % *every as i32 == 0
+                && date.day() == (*day).min(days_in_month(date))
+        }
+        RecurrenceRule::MonthlyWeekday {
+            every,
+            week,
+            weekday,
+        } => {
+            let in_week = if *week == -1 {
+                date.day() + 7 > days_in_month(date)
+            } else {
+                (date.day() as i32 - 1) / 7 + 1 == *week
+            };
+            months_between(anchor, date) % *every as i32 == 0
+                && date.weekday() == weekday.to_chrono()
+                && in_week
+        }
+        RecurrenceRule::AfterCompletion { .. } => false,
+    }
+}
+
+/// The first occurrence of a calendar rule strictly after `after`
+fn next_occurrence(
+    rule: &RecurrenceRule,
+    anchor: NaiveDate,
+    time: NaiveTime,
+    after: DateTime<Local>,
+) -> Option<DateTime<Local>> {
+    let mut date = after.date_naive().max(anchor);
+    for _ in 0..SEARCH_DAYS {
+        if falls_on(rule, anchor, date) {
+            if let Some(due) = at(date, time).filter(|due| *due > after) {
+                return Some(due);
+            }
+        }
+        date = date.succ_opt()?;
+    }
+    None
+}
+
+/// When the first instance of a new or edited recurrence is due
+fn first_due(rule: &RecurrenceRule, starts_at: i64, time: NaiveTime) -> Option<i64> {
+    let anchor = local_date(starts_at);
+    match rule {
+        // The first instance doesn't wait for anything to be completed
+        RecurrenceRule::AfterCompletion { .. } => {
+            at(anchor, time).map(|due| due.timestamp_millis())
+        }
+        _ => {
+            // Include an occurrence at exactly the start
+            let after = at(anchor, time)
+                .map(|start| start - ChronoDuration::milliseconds(1))
+                .unwrap_or_else(Local::now)
+                .max(Local::now() - ChronoDuration::milliseconds(1));
+            next_occurrence(rule, anchor, time, after).map(|due| due.timestamp_millis())
+        }
+    }
+}
+
+fn get(conn: &rusqlite::Connection, id: &str) -> rusqlite::Result<Option<Recurrence>> {
+    conn.query_row(
+        "SELECT * FROM task_recurrences WHERE id = ?1",
+        params![id],
+        Recurrence::from_row,
+    )
+    .optional()
+}
+
+fn list(project_id: Option<&str>) -> Result<Vec<Recurrence>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT * FROM task_recurrences WHERE (?1 IS NULL OR project_id = ?1) ORDER BY created_at",
+        )?;
+        let rows = stmt.query_map(params![project_id], Recurrence::from_row)?;
+        rows.collect()
+    })
+}
+
+fn set_next(id: &str, next_due: Option<i64>, last_task_id: Option<&str>) -> Result<(), String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "UPDATE task_recurrences SET next_due = ?2, last_task_id = COALESCE(?3, last_task_id), updated_at = ?4 WHERE id = ?1",
+            params![id, next_due, last_task_id, store::now_ms()],
+        )
+        .map(|_| ())
+    })
+}
+
+/// Bring one recurrence up to date, creating its instance if it's due
+fn materialize(
+    recurrence: &Recurrence,
+    now: DateTime<Local>,
+) -> Result<Option<RecurringTaskCreated>, String> {
+    let time = NaiveTime::parse_from_str(&recurrence.time, "%H:%M").map_err(|e| e.to_string())?;
+    let mut next_due = recurrence.next_due;
+
+    if let RecurrenceRule::AfterCompletion { days } = recurrence.rule {
+        if next_due.is_none() {
+            let last = match &recurrence.last_task_id {
+                Some(id) => store::with_conn(|conn| store::get_task(conn, id))?,
+                None => None,
+            };
+            // A deleted instance counts as dealt with, from now
+            let completed_at = match last {
+                Some(task) => task.completed_at,
+                None => Some(now.timestamp_millis()),
+            };
+            let Some(completed_at) = completed_at else {
+                return Ok(None);
+            };
+            next_due = at(
+                local_date(completed_at) + ChronoDuration::days(days as i64),
+                time,
+            )
+            .map(|due| due.timestamp_millis());
+            set_next(&recurrence.id, next_due, None)?;
+        }
+    }
+
+    let Some(due) = next_due.filter(|due| *due <= now.timestamp_millis()) else {
+        return Ok(None);
+    };
+    if recurrence.ends_at.is_some_and(|ends| due > ends) {
+        set_next(&recurrence.id, None, None)?;
+        return Ok(None);
+    }
+    let task = store::create_task(NewTask {
+        project_id: recurrence.project_id.clone(),
+        title: recurrence.title.clone(),
+        description: recurrence.description.clone(),
+        state: Some(recurrence.state.clone()),
+    })
+    .map_err(String::from)?;
+    // Skip the rest of a missed stretch rather than creating a backlog of copies
+    let following = match recurrence.rule {
+        RecurrenceRule::AfterCompletion { .. } => None,
+        ref rule => next_occurrence(rule, local_date(recurrence.starts_at), time, now)
+            .map(|due| due.timestamp_millis())
+            .filter(|next| recurrence.ends_at.is_none_or(|ends| *next <= ends)),
+    };
+    set_next(&recurrence.id, following, Some(&task.id))?;
+    Ok(Some(RecurringTaskCreated {
+        recurrence_id: recurrence.id.clone(),
+        task,
+        due_at: due,
+    }))
+}
+
+fn materialize_all(app: &AppHandle) {
+    let recurrences = match list(None) {
+        Ok(recurrences) => recurrences,
+        Err(e) => {
+            eprintln!("[Claude PM] Failed to load recurring tasks: {}", e);
+            return;
+        }
+    };
+    let now = Local::now();
+    for recurrence in recurrences.iter().filter(|r| r.enabled) {
+        match materialize(recurrence, now) {
+            Ok(Some(created)) => {
+                println!(
+                    "[Claude PM] Created recurring task {} ({})",
+                    created.task.id, recurrence.title
+                );
+                let _ = app.emit("recurring-task-created", &created);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!(
+                "[Claude PM] Failed to create recurring task {}: {}",
+                recurrence.id, e
+            ),
+        }
+    }
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        // First pass straight away: instances may have come due while the app was closed
+        materialize_all(&app);
+        power::wait(TICK);
+    });
+}
+
+#[tauri::command]
+pub fn list_recurrences(project_id: Option<String>) -> Result<Vec<Recurrence>, Error> {
+    list(project_id.as_deref()).map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn create_recurrence(recurrence: NewRecurrence) -> Result<Recurrence, Error> {
+    let time = recurrence.time.unwrap_or_else(|| DEFAULT_TIME.to_string());
+    let parsed = validate(&recurrence.rule, &time)?;
+    let state = recurrence.state.unwrap_or_else(|| "backlog".to_string());
+    store::validate_state(&state)?;
+    if recurrence.title.trim().is_empty() {
+        return Err(Error::InvalidInput(
+            "A recurring task needs a title".to_string(),
+        ));
+    }
+    store::with_conn(|conn| store::get_project(conn, &recurrence.project_id))?
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", recurrence.project_id)))?;
+    let starts_at = recurrence.starts_at.unwrap_or_else(store::now_ms);
+    let next_due = first_due(&recurrence.rule, starts_at, parsed);
+    let rule = serde_json::to_string(&recurrence.rule).map_err(|e| e.to_string())?;
+    let id = store::new_id();
+    let now = store::now_ms();
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO task_recurrences (id, project_id, title, description, state, rule, time, starts_at, ends_at, enabled, next_due, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 1, ?10, ?11, ?11)",
+            params![
+                id,
+                recurrence.project_id,
+                recurrence.title,
+                recurrence.description,
+                state,
+                rule,
+                time,
+                starts_at,
+                recurrence.ends_at,
+                next_due,
+                now
+            ],
+        )?;
+        get(conn, &id)
+    })?
+    .ok_or_else(|| Error::Internal("Recurring task vanished after insert".to_string()))
+}
+
+/// Edit a recurrence; changing when it runs recomputes when it's next due
+#[tauri::command]
+pub fn update_recurrence(id: String, update: RecurrenceUpdate) -> Result<Recurrence, Error> {
+    let current = store::with_conn(|conn| get(conn, &id))?
+        .ok_or_else(|| Error::NotFound(format!("Recurring task not found: {}", id)))?;
+    if let Some(state) = &update.state {
+        store::validate_state(state)?;
+    }
+    let reschedule = update.rule.is_some()
+        || update.time.is_some()
+        || update.starts_at.is_some()
+        || (update.enabled == Some(true) && !current.enabled);
+    let rule = update.rule.unwrap_or(current.rule);
+    let time = update.time.unwrap_or(current.time);
+    let parsed = validate(&rule, &time)?;
+    let starts_at = update.starts_at.unwrap_or(current.starts_at);
+    let next_due = if reschedule {
+        first_due(&rule, starts_at, parsed)
+    } else {
+        current.next_due
+    };
+    let rule_json = serde_json::to_string(&rule).map_err(|e| e.to_string())?;
+    store::with_conn(|conn| {
+        conn.execute(
+            "UPDATE task_recurrences SET title = COALESCE(?2, title), description = COALESCE(?3, description), state = COALESCE(?4, state), rule = ?5, time = ?6, starts_at = ?7, ends_at = COALESCE(?8, ends_at), enabled = COALESCE(?9, enabled), next_due = ?10, updated_at = ?11 WHERE id = ?1",
+            params![
+                id,
+                update.title,
+                update.description,
+                update.state,
+                rule_json,
+                time,
+                starts_at,
+                update.ends_at,
+                update.enabled,
+                next_due,
+                store::now_ms()
+            ],
+        )?;
+        get(conn, &id)
+    })?
+    .ok_or_else(|| Error::NotFound(format!("Recurring task not found: {}", id)))
+}
+
+/// Stop recurring; instances already created are kept
+#[tauri::command]
+pub fn delete_recurrence(id: String) -> Result<(), Error> {
+    store::with_conn(|conn| {
+        conn.execute("DELETE FROM task_recurrences WHERE id = ?1", params![id])
+    })?;
+    Ok(())
+}
+
+/// The next `count` times a calendar rule would create a task, for previewing a rule
+#[tauri::command]
+pub fn preview_recurrence(
+    rule: RecurrenceRule,
+    time: Option<String>,
+    starts_at: Option<i64>,
+    count: Option<usize>,
+) -> Result<Vec<i64>, Error> {
+    let time = validate(&rule, time.as_deref().unwrap_or(DEFAULT_TIME))?;
+    if let RecurrenceRule::AfterCompletion { .. } = rule {
+        return Err(Error::InvalidInput(
+            "After-completion rules depend on when each instance is done".to_string(),
+        ));
+    }
+    let starts_at = starts_at.unwrap_or_else(store::now_ms);
+    let anchor = local_date(starts_at);
+    let mut times = Vec::new();
+    let mut next = first_due(&rule, starts_at, time);
+    while let Some(due) = next.filter(|_| times.len() < count.unwrap_or(5)) {
+        times.push(due);
+        next = Local
+            .timestamp_millis_opt(due)
+            .single()
+            .and_then(|after| next_occurrence(&rule, anchor, time, after))
+            .map(|due| due.timestamp_millis());
+    }
+    Ok(times)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn daily_and_weekly_rules() {
+        let daily = RecurrenceRule::Daily { every: 2 };
+        let anchor = date(2026, 3, 1);
+        assert!(falls_on(&daily, anchor, date(2026, 3, 3)));
+        assert!(!falls_on(&daily, anchor, date(2026, 3, 2)));
+        assert!(!falls_on(&daily, anchor, date(2026, 2, 27)));
+
+        // 2026-01-01 is a Thursday
+        let fortnightly = RecurrenceRule::Weekly {
+            every: 2,
+            weekdays: vec![Weekday::Mon, Weekday::Thu],
+        };
+        let anchor = date(2026, 1, 1);
+        assert!(falls_on(&fortnightly, anchor, date(2026, 1, 1)));
+        assert!(!falls_on(&fortnightly, anchor, date(2026, 1, 5)));
+        assert!(falls_on(&fortnightly, anchor, date(2026, 1, 12)));
+        assert!(falls_on(&fortnightly, anchor, date(2026, 1, 15)));
+        assert!(!falls_on(&fortnightly, anchor, date(2026, 1, 13)));
+    }
+
+    #[test]
+    fn monthly_rules() {
+        let last_day = RecurrenceRule::MonthlyDay { every: 1, day: 31 };
+        let anchor = date(2026, 1, 1);
+        assert!(falls_on(&last_day, anchor, date(2026, 2, 28)));
+        assert!(falls_on(&last_day, anchor, date(2026, 3, 31)));
+        assert!(falls_on(&last_day, anchor, date(2026, 4, 30)));
+        assert!(!falls_on(&last_day, anchor, date(2026, 4, 29)));
+
+        let quarterly = RecurrenceRule::MonthlyDay { every: 3, day: 15 };
+        assert!(falls_on(&quarterly, date(2026, 1, 15), date(2026, 4, 15)));
+        assert!(!falls_on(&quarterly, date(2026, 1, 15), date(2026, 2, 15)));
+
+        let second_tuesday = RecurrenceRule::MonthlyWeekday {
+            every: 1,
+            week: 2,
+            weekday: Weekday::Tue,
+        };
+        assert!(falls_on(&second_tuesday, anchor, date(2026, 1, 13)));
+        assert!(!falls_on(&second_tuesday, anchor, date(2026, 1, 6)));
+        assert!(!falls_on(&second_tuesday, anchor, date(2026, 1, 20)));
+
+        let last_friday = RecurrenceRule::MonthlyWeekday {
+            every: 1,
+            week: -1,
+            weekday: Weekday::Fri,
+        };
+        assert!(falls_on(&last_friday, anchor, date(2026, 1, 30)));
+        assert!(!falls_on(&last_friday, anchor, date(2026, 1, 23)));
+
+        assert_eq!(days_in_month(date(2024, 2, 10)), 29);
+        assert_eq!(days_in_month(date(2026, 12, 1)), 31);
+    }
+
+    #[test]
+    fn next_occurrence_is_strictly_later() {
+        let daily = RecurrenceRule::Daily { every: 1 };
+        let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        let after = at(date(2026, 6, 10), nine).unwrap();
+        let next = next_occurrence(&daily, date(2026, 6, 1), nine, after).unwrap();
+        assert_eq!(next, at(date(2026, 6, 11), nine).unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        assert!(validate(&RecurrenceRule::Daily { every: 0 }, "09:00").is_err());
+        let weekly = RecurrenceRule::Weekly {
+            every: 1,
+            weekdays: Vec::new(),
+        };
+        assert!(validate(&weekly, "09:00").is_err());
+        let fifth = RecurrenceRule::MonthlyWeekday {
+            every: 1,
+            week: 6,
+            weekday: Weekday::Mon,
+        };
+        assert!(validate(&fifth, "09:00").is_err());
+        assert!(validate(&RecurrenceRule::AfterCompletion { days: 3 }, "25:00").is_err());
+        assert!(validate(&RecurrenceRule::AfterCompletion { days: 3 }, "09:30").is_ok());
+    }
+}
@@ -0,0 +1,499 @@
+//! Project status reports, as Markdown or PDF
+//!
+//! A report is a Markdown template with `{{placeholders}}` filled from the local store.
+//! The built-in `status` and `timesheet` templates cover the common cases; any other
+//! template name is read as a path to a Markdown file using the same placeholders:
+//!
+//! `project`, `from`, `to`, `generatedAt`, `taskSummary`, `completedTasks`, `openTasks`,
+//! `timeTotal`, `timeChart`, `timeTable`
+//!
+//! The weekly summary rolls up every project: completed tasks, agent runs, token spend
+//! from Claude Code transcripts, and time tracked. It is saved under `reports/` in the
+//! data directory and can be run by the scheduler, which notifies when it's ready and
+//! optionally emails it (see `email`).
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use rusqlite::params;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::notifications::NotificationRequest;
+use crate::outbox::{self, Effect};
+use crate::store::{self, Project, Task};
+use crate::{config, pdf, search, timetracking};
+
+const REPORTS_DIR: &str = "reports";
+
+const STATUS_TEMPLATE: &str = "# {{project}} status report
+
+{{from}} to {{to}}
+
+## Summary
+
+{{taskSummary}}
+
+## Completed
+
+{{completedTasks}}
+
+## In progress
+
+{{openTasks}}
+
+## Time tracked
+
+Total: {{timeTotal}}
+
+{{timeChart}}
+
+---
+
+Generated by Claude PM on {{generatedAt}}
+";
+
+const TIMESHEET_TEMPLATE: &str = "# {{project}} timesheet
+
+{{from}} to {{to}}
+
+Total: {{timeTotal}}
+
+{{timeTable}}
+
+{{timeChart}}
+
+---
+
+Generated by Claude PM on {{generatedAt}}
+";
+
+fn date(ms: i64) -> String {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn hours(ms: i64) -> String {
+    format!("{:.1} h", ms as f64 / 3_600_000.0)
+}
+
+/// Table cells can't contain pipes or newlines
+fn cell(text: &str) -> String {
+    text.replace('|', "/").replace('\n', " ")
+}
+
+fn state_label(state: &str) -> &str {
+    match state {
+        "in_progress" => "In progress",
+        "review" => "Review",
+        "done" => "Done",
+        _ => "Backlog",
+    }
+}
+
+fn load_template(template: Option<&str>) -> Result<String, Error> {
+    match template.unwrap_or("status") {
+        "status" => Ok(STATUS_TEMPLATE.to_string()),
+        "timesheet" => Ok(TIMESHEET_TEMPLATE.to_string()),
+        path => fs::read_to_string(path)
+            .map_err(|e| Error::NotFound(format!("Failed to read template {}: {}", path, e))),
+    }
+}
+
+fn project_and_tasks(project_id: &str) -> Result<(Project, Vec<Task>), Error> {
+    let project = store::list_projects()?
+        .into_iter()
+        .find(|p| p.id == project_id)
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project_id)))?;
+    let tasks = store::list_tasks(Some(project_id.to_string()), None)?;
+    Ok((project, tasks))
+}
+
+/// Fill a report template for one project over `from..to` (unix millis)
+pub fn project_markdown(
+    project_id: &str,
+    from: i64,
+    to: i64,
+    template: Option<&str>,
+) -> Result<(Project, String), Error> {
+    let template = load_template(template)?;
+    let (project, tasks) = project_and_tasks(project_id)?;
+    let time = timetracking::report(from, to, Some(project_id))?;
+
+    // A task counts as completed in the range if it (last) reached `done` during it
+    let completed: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| t.completed_at.is_some_and(|at| at >= from && at < to))
+        .collect();
+    let open: Vec<&Task> = tasks.iter().filter(|t| t.state != "done").collect();
+
+    let mut summary = vec![format!("- {} task(s) completed", completed.len())];
+    for state in ["in_progress", "review", "backlog"] {
+        let count = tasks.iter().filter(|t| t.state == state).count();
+        if count > 0 {
+            summary.push(format!("- {} {}", count, state_label(state).to_lowercase()));
+        }
+    }
+    summary.push(format!("- {} tracked", hours(time.total_ms)));
+
+    let completed_table = if completed.is_empty() {
+        "No tasks completed in this period.".to_string()
+    } else {
+        let mut table = String::from("| Task | Completed |\n|---|---|\n");
+        for task in &completed {
+            table.push_str(&format!(
+                "| {} | {} |\n",
+                cell(&task.title),
+                date(task.completed_at.unwrap_or(task.updated_at))
+            ));
+        }
+        table
+    };
+    let open_table = if open.is_empty() {
+        "Nothing open.".to_string()
+    } else {
+        let mut table = String::from("| Task | State | Updated |\n|---|---|---|\n");
+        for task in &open {
+            table.push_str(&format!(
+                "| {} | {} | {} |\n",
+                cell(&task.title),
+                state_label(&task.state),
+                date(task.updated_at)
+            ));
+        }
+        table
+    };
+    let time_chart = if time.by_day.is_empty() {
+        String::new()
+    } else {
+        let bars: Vec<String> = time
+            .by_day
+            .iter()
+            .map(|d| format!("{}: {:.1}", d.day, d.total_ms as f64 / 3_600_000.0))
+            .collect();
+        format!("```chart\n{}\n```", bars.join("\n"))
+    };
+    let time_table = if time.by_day.is_empty() {
+        "No time tracked in this period.".to_string()
+    } else {
+        let mut table = String::from("| Day | Hours |\n|---|---|\n");
+        for day in &time.by_day {
+            table.push_str(&format!("| {} | {} |\n", day.day, hours(day.total_ms)));
+        }
+        table
+    };
+
+    let markdown = [
+        ("project", project.name.clone()),
+        ("from", date(from)),
+        ("to", date(to)),
+        (
+            "generatedAt",
+            Local::now().format("%Y-%m-%d %H:%M").to_string(),
+        ),
+        ("taskSummary", summary.join("\n")),
+        ("completedTasks", completed_table),
+        ("openTasks", open_table),
+        ("timeTotal", hours(time.total_ms)),
+        ("timeChart", time_chart),
+        ("timeTable", time_table),
+    ]
+    .iter()
+    .fold(template, |text, (key, value)| {
+        text.replace(&format!("{{{{{}}}}}", key), value)
+    });
+    Ok((project, markdown))
+}
+
+fn default_output(project: &Project, to: i64) -> Result<PathBuf, Error> {
+    let dir = dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .ok_or_else(|| Error::NotFound("Could not determine Downloads directory".to_string()))?;
+    let slug: String = project
+        .name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    Ok(dir.join(format!(
+        "{}-report-{}.pdf",
+        slug.trim_matches('-'),
+        date(to)
+    )))
+}
+
+/// The filled-in Markdown, for previewing a report before exporting it
+#[tauri::command]
+pub fn render_report_markdown(
+    project_id: String,
+    from: i64,
+    to: i64,
+    template: Option<String>,
+) -> Result<String, Error> {
+    project_markdown(&project_id, from, to, template.as_deref()).map(|(_, markdown)| markdown)
+}
+
+/// Render a project report to PDF, by default into Downloads; returns the file path
+#[tauri::command]
+pub fn export_report_pdf(
+    project_id: String,
+    from: i64,
+    to: i64,
+    template: Option<String>,
+    output_path: Option<String>,
+) -> Result<String, Error> {
+    let (project, markdown) = project_markdown(&project_id, from, to, template.as_deref())?;
+    let path = match output_path {
+        Some(path) => PathBuf::from(path),
+        None => default_output(&project, to)?,
+    };
+    let title = format!("{} report", project.name);
+    fs::write(&path, pdf::render(&markdown, &title))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path.display().to_string())
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub input: u64,
+    pub output: u64,
+    pub cache_creation: u64,
+    pub cache_read: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WeeklySummary {
+    /// Local Monday midnight, unix millis
+    pub from: i64,
+    pub to: i64,
+    pub completed_tasks: usize,
+    /// Sessions started during the week, by status
+    pub agent_runs: BTreeMap<String, usize>,
+    pub tokens: TokenUsage,
+    pub tracked_ms: i64,
+    pub markdown: String,
+    /// Where the Markdown was saved
+    pub path: String,
+}
+
+fn local_midnight(day: NaiveDate) -> Option<i64> {
+    Local
+        .from_local_datetime(&day.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|d| d.timestamp_millis())
+}
+
+/// Monday of the week containing `ms`
+fn week_of(ms: i64) -> Option<NaiveDate> {
+    let day = Local.timestamp_millis_opt(ms).single()?.date_naive();
+    Some(day - Duration::days(day.weekday().num_days_from_monday() as i64))
+}
+
+/// Token usage from assistant messages in Claude Code transcripts between `from` and `to`
+fn token_usage(from: i64, to: i64) -> TokenUsage {
+    let mut usage = TokenUsage::default();
+    let Some(entries) = search::transcripts_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return usage;
+    };
+    let files = entries
+        .flatten()
+        .filter_map(|project| fs::read_dir(project.path()).ok())
+        .flat_map(|files| files.flatten().map(|f| f.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"));
+    for path in files {
+        // Transcripts last written before the week can't contain any of its messages
+        let modified = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .map(|t| DateTime::<Local>::from(t).timestamp_millis())
+            .unwrap_or(i64::MAX);
+        if modified < from {
+            continue;
+        }
+        add_transcript_usage(&path, from, to, &mut usage);
+    }
+    usage
+}
+
+fn add_transcript_usage(path: &Path, from: i64, to: i64, usage: &mut TokenUsage) {
+    let Ok(file) = fs::File::open(path) else {
+        return;
+    };
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let Ok(entry) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let at = entry["timestamp"]
+            .as_str()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.timestamp_millis());
+        if !at.is_some_and(|at| at >= from && at < to) {
+            continue;
+        }
+        let counts = &entry["message"]["usage"];
+        let count = |key: &str| counts[key].as_u64().unwrap_or(0);
+        usage.input += count("input_tokens");
+        usage.output += count("output_tokens");
+        usage.cache_creation += count("cache_creation_input_tokens");
+        usage.cache_read += count("cache_read_input_tokens");
+    }
+}
+
+fn agent_runs(from: i64, to: i64) -> Result<BTreeMap<String, usize>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT status, COUNT(*) FROM sessions WHERE started_at >= ?1 AND started_at < ?2 GROUP BY status",
+        )?;
+        let rows = stmt.query_map(params![from, to], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+        })?;
+        rows.collect()
+    })
+}
+
+fn tokens_label(count: u64) -> String {
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=999_999 => format!("{:.1}k", count as f64 / 1_000.0),
+        _ => format!("{:.2}M", count as f64 / 1_000_000.0),
+    }
+}
+
+/// Build and save the summary for the week starting on `monday`
+fn weekly_summary(monday: NaiveDate) -> Result<WeeklySummary, Error> {
+    let from = local_midnight(monday).ok_or("Invalid week start")?;
+    let to = local_midnight(monday + Duration::days(7)).ok_or("Invalid week start")?;
+
+    let projects = store::list_projects()?;
+    let tasks = store::list_tasks(None, None)?;
+    let completed: Vec<&Task> = tasks
+        .iter()
+        .filter(|t| t.completed_at.is_some_and(|at| at >= from && at < to))
+        .collect();
+    let runs = agent_runs(from, to)?;
+    let tokens = token_usage(from, to);
+    let time = timetracking::report(from, to, None)?;
+
+    let mut md = format!(
+        "# Weekly review\n\n{} to {}\n\n## At a glance\n\n- {} task(s) completed\n- {} agent run(s)\n- {} input / {} output tokens ({} cached)\n- {} tracked\n",
+        date(from),
+        date(to - 1),
+        completed.len(),
+        runs.values().sum::<usize>(),
+        tokens_label(tokens.input + tokens.cache_creation),
+        tokens_label(tokens.output),
+        tokens_label(tokens.cache_read),
+        hours(time.total_ms)
+    );
+
+    if !completed.is_empty() {
+        md.push_str("\n## Completed\n\n| Task | Project | Completed |\n|---|---|---|\n");
+        for task in &completed {
+            let project = projects
+                .iter()
+                .find(|p| p.id == task.project_id)
+                .map(|p| p.name.as_str())
+                .unwrap_or("");
+            md.push_str(&format!(
+                "| {} | {} | {} |\n",
+                cell(&task.title),
+                cell(project),
+                date(task.completed_at.unwrap_or(task.updated_at))
+            ));
+        }
+    }
+    if !runs.is_empty() {
+        md.push_str("\n## Agent runs\n\n");
+        for (status, count) in &runs {
+            md.push_str(&format!("- {} {}\n", count, status));
+        }
+    }
+    if !time.by_project.is_empty() {
+        md.push_str("\n## Time by project\n\n```chart\n");
+        for project in &time.by_project {
+            md.push_str(&format!(
+                "{}: {:.1}\n",
+                project.project_name,
+                project.total_ms as f64 / 3_600_000.0
+            ));
+        }
+        md.push_str("```\n");
+    }
+
+    let dir = config::data_dir()
+        .map(|dir| dir.join(REPORTS_DIR))
+        .ok_or("Could not determine data directory")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create reports directory: {}", e))?;
+    let path = dir.join(format!("weekly-{}.md", monday.format("%Y-%m-%d")));
+    fs::write(&path, &md).map_err(|e| format!("Failed to write weekly summary: {}", e))?;
+
+    Ok(WeeklySummary {
+        from,
+        to,
+        completed_tasks: completed.len(),
+        agent_runs: runs,
+        tokens,
+        tracked_ms: time.total_ms,
+        markdown: md,
+        path: path.display().to_string(),
+    })
+}
+
+/// Summary for the week containing `ms`
+pub fn summary_for_week_of(ms: i64) -> Result<WeeklySummary, Error> {
+    let monday = week_of(ms).ok_or_else(|| Error::InvalidInput("Invalid date".to_string()))?;
+    weekly_summary(monday)
+}
+
+/// e.g. `2026-10-05 to 2026-10-11`
+pub fn week_label(summary: &WeeklySummary) -> String {
+    format!("{} to {}", date(summary.from), date(summary.to - 1))
+}
+
+/// Summarise last week and notify (and email, if enabled); used by the scheduler's
+/// `weeklySummary` action
+pub fn publish_weekly() -> Result<(), Error> {
+    let this_week = week_of(store::now_ms()).ok_or("Invalid current date")?;
+    let summary = weekly_summary(this_week - Duration::days(7))?;
+    let notification = NotificationRequest {
+        title: "Your weekly review is ready".to_string(),
+        body: format!(
+            "{} task(s) completed, {} tracked",
+            summary.completed_tasks,
+            hours(summary.tracked_ms)
+        ),
+        key: Some(format!("weekly:{}", summary.from)),
+        target: Some(format!("/reports/weekly?from={}", summary.from)),
+        category: Some("report".to_string()),
+        ..Default::default()
+    };
+    // Keyed by week, so a rerun after a crash doesn't notify or email twice
+    outbox::enqueue(
+        Effect::Notification {
+            request: notification,
+        },
+        Some(format!("weekly-notification:{}", summary.from)),
+    )?;
+    if config::load().email.weekly_report {
+        outbox::enqueue(
+            Effect::Email {
+                subject: format!("Claude PM weekly review: {}", week_label(&summary)),
+                body: summary.markdown.clone(),
+            },
+            Some(format!("weekly-email:{}", summary.from)),
+        )?;
+    }
+    Ok(())
+}
+
+/// Summary for the week containing `week_of` (unix millis); the current week when unset
+#[tauri::command]
+pub async fn generate_weekly_summary(week_of: Option<i64>) -> Result<WeeklySummary, Error> {
+    summary_for_week_of(week_of.unwrap_or_else(store::now_ms))
+}
@@ -0,0 +1,444 @@
+//! Structured diffs of what an agent run changed
+//!
+//! Compares the run's pre-run snapshot (see `snapshots`), or the merge base with a branch
+//! the caller names, against the repository's working tree as it is now — untracked files
+//! included, ignored ones not — using libgit2, and returns files, hunks and lines for the
+//! UI to render. Very long files are cut off after `MAX_LINES_PER_FILE` lines.
+//!
+//! When the run worked in a linked worktree, `apply_hunks` carries the hunks the user
+//! picked over to the main checkout, one file at a time so a file whose hunks don't fit
+//! is reported as a conflict without holding back the others. Files the run created are
+//! copied whole, unless the main checkout already has one by that name.
+
+use git2::{
+    ApplyLocation, ApplyOptions, Delta, Diff, DiffFindOptions, DiffLineType, DiffOptions, Oid,
+    Patch, Repository,
+};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::fs;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::{orchestrator, snapshots, store, vcs};
+
+const MAX_LINES_PER_FILE: usize = 5000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunDiff {
+    pub run_id: String,
+    pub repo: String,
+    /// Commit the working tree was compared against
+    pub base: String,
+    /// The branch name, or `pre-run snapshot`
+    pub base_label: String,
+    pub additions: usize,
+    pub deletions: usize,
+    pub files: Vec<FileDiff>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    pub path: String,
+    /// Previous path of a renamed file
+    pub old_path: Option<String>,
+    /// `added`, `deleted`, `modified`, `renamed` or `typechange`
+    pub status: &'static str,
+    pub binary: bool,
+    pub additions: usize,
+    pub deletions: usize,
+    /// Lines past `MAX_LINES_PER_FILE` were left out
+    pub truncated: bool,
+    pub hunks: Vec<Hunk>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Hunk {
+    /// `@@ -a,b +c,d @@ …` as git prints it
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    /// `context`, `add` or `delete`
+    pub kind: &'static str,
+    pub old_lineno: Option<u32>,
+    pub new_lineno: Option<u32>,
+    pub content: String,
+}
+
+/// Hunks of one file to apply, by their index in the file's [`FileDiff::hunks`]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkSelection {
+    pub path: String,
+    /// Every hunk when unset
+    pub hunks: Option<Vec<usize>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileApplyResult {
+    pub path: String,
+    /// `applied`, `conflict`, or `missing` when the run didn't change the file
+    pub status: &'static str,
+    pub hunks: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplyReport {
+    pub run_id: String,
+    /// The main checkout the hunks were applied to
+    pub target: String,
+    pub applied: usize,
+    pub conflicts: usize,
+    pub files: Vec<FileApplyResult>,
+}
+
+fn status(delta: Delta) -> &'static str {
+    match delta {
+        Delta::Added | Delta::Untracked => "added",
+        Delta::Deleted => "deleted",
+        Delta::Renamed => "renamed",
+        Delta::Typechange => "typechange",
+        _ => "modified",
+    }
+}
+
+fn file_diff(patch: &Patch) -> Result<FileDiff, git2::Error> {
+    let delta = patch.delta();
+    let path_of = |file: git2::DiffFile| file.path().map(|p| p.display().to_string());
+    let new_path = path_of(delta.new_file());
+    let old_path = path_of(delta.old_file());
+    let mut file = FileDiff {
+        path: new_path
+            .clone()
+            .or_else(|| old_path.clone())
+            .unwrap_or_default(),
+        old_path: old_path
+            .filter(|old| delta.status() == Delta::Renamed && Some(old) != new_path.as_ref()),
+        status: status(delta.status()),
+        binary: delta.flags().is_binary(),
+        additions: 0,
+        deletions: 0,
+        truncated: false,
+        hunks: Vec::new(),
+    };
+    let mut kept = 0;
+    for h in 0..patch.num_hunks() {
+        let (hunk, count) = patch.hunk(h)?;
+        let mut lines = Vec::new();
+        for l in 0..count {
+            let line = patch.line_in_hunk(h, l)?;
+            let kind = match line.origin_value() {
+                DiffLineType::Addition => "add",
+                DiffLineType::Deletion => "delete",
+                DiffLineType::Context => "context",
+                // "No newline at end of file" markers
+                _ => continue,
+            };
+            match kind {
+                "add" => file.additions += 1,
+                "delete" => file.deletions += 1,
+                _ => {}
+            }
+            if kept >= MAX_LINES_PER_FILE {
+                file.truncated = true;
+                continue;
+            }
+            kept += 1;
+            lines.push(DiffLine {
+                kind,
+                old_lineno: line.old_lineno(),
+                new_lineno: line.new_lineno(),
+                content: String::from_utf8_lossy(line.content())
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string(),
+            });
+        }
+        file.hunks.push(Hunk {
+            header: String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_string(),
+            old_start: hunk.old_start(),
+            old_lines: hunk.old_lines(),
+            new_start: hunk.new_start(),
+            new_lines: hunk.new_lines(),
+            lines,
+        });
+    }
+    Ok(file)
+}
+
+/// `base` against the working tree, untracked files included
+fn workdir_diff(repo: &Repository, base: Oid) -> Result<Diff<'_>, git2::Error> {
+    let tree = repo.find_commit(base)?.tree()?;
+    let mut options = DiffOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .show_untracked_content(true);
+    let mut diff = repo.diff_tree_to_workdir_with_index(Some(&tree), Some(&mut options))?;
+    diff.find_similar(Some(
+        DiffFindOptions::new().renames(true).for_untracked(true),
+    ))?;
+    Ok(diff)
+}
+
+fn diff(repo_path: &str, base: Oid) -> Result<Vec<FileDiff>, git2::Error> {
+    let repo = Repository::open(repo_path)?;
+    let diff = workdir_diff(&repo, base)?;
+    let mut files = Vec::new();
+    for index in 0..diff.deltas().len() {
+        match Patch::from_diff(&diff, index)? {
+            Some(patch) => files.push(file_diff(&patch)?),
+            // Binary files have no patch
+            None => {
+                if let Some(delta) = diff.get_delta(index) {
+                    files.push(FileDiff {
+                        path: delta
+                            .new_file()
+                            .path()
+                            .or_else(|| delta.old_file().path())
+                            .map(|p| p.display().to_string())
+                            .unwrap_or_default(),
+                        old_path: None,
+                        status: status(delta.status()),
+                        binary: true,
+                        additions: 0,
+                        deletions: 0,
+                        truncated: false,
+                        hunks: Vec::new(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// The merge base of `branch` and `HEAD`, so changes made on the branch since aren't shown
+fn branch_base(repo_path: &str, branch: &str) -> Result<Oid, git2::Error> {
+    let repo = Repository::open(repo_path)?;
+    let branch = repo.revparse_single(branch)?.peel_to_commit()?.id();
+    let head = repo.head()?.peel_to_commit()?.id();
+    repo.merge_base(branch, head)
+}
+
+/// The run's repository and the commit to compare it against, with its label
+fn resolve(run_id: &str, base_branch: Option<&str>) -> Result<(String, Oid, String), Error> {
+    let snapshot = snapshots::pre_run(run_id)?;
+    let repo = match &snapshot {
+        Some(snapshot) => snapshot.repo.clone(),
+        None => {
+            let run = orchestrator::get(run_id)
+                .ok_or_else(|| Error::NotFound(format!("No agent run with id {}", run_id)))?;
+            match run.cwd {
+                Some(cwd) => cwd,
+                None => store::list_projects()?
+                    .into_iter()
+                    .find(|p| p.id == run.project_id)
+                    .and_then(|p| p.repo_path)
+                    .ok_or_else(|| {
+                        Error::InvalidInput("The run's project has no repository path".to_string())
+                    })?,
+            }
+        }
+    };
+    if !Path::new(&repo).exists() {
+        return Err(Error::NotFound(format!(
+            "Repository {} no longer exists",
+            repo
+        )));
+    }
+    vcs::require_git(Path::new(&repo), "Run diffs")?;
+    let (base, base_label) = match (base_branch, &snapshot) {
+        (Some(branch), _) => (
+            branch_base(&repo, branch).map_err(|e| {
+                Error::InvalidInput(format!("Can't compare against {}: {}", branch, e.message()))
+            })?,
+            branch.to_string(),
+        ),
+        (None, Some(snapshot)) => (
+            Oid::from_str(&snapshot.commit).map_err(|e| e.to_string())?,
+            "pre-run snapshot".to_string(),
+        ),
+        (None, None) => {
+            return Err(Error::NotFound(format!(
+                "Run {} has no pre-run snapshot; name a base branch to compare against",
+                run_id
+            )))
+        }
+    };
+    Ok((repo, base, base_label))
+}
+
+pub fn run_diff(run_id: &str, base_branch: Option<&str>) -> Result<RunDiff, Error> {
+    let (repo, base, base_label) = resolve(run_id, base_branch)?;
+    let files =
+        diff(&repo, base).map_err(|e| format!("Failed to diff {}: {}", repo, e.message()))?;
+    Ok(RunDiff {
+        run_id: run_id.to_string(),
+        repo,
+        base: base.to_string(),
+        base_label,
+        additions: files.iter().map(|f| f.additions).sum(),
+        deletions: files.iter().map(|f| f.deletions).sum(),
+        files,
+    })
+}
+
+/// What run `run_id` changed, compared with its pre-run snapshot or with `base_branch`
+#[tauri::command]
+pub async fn get_run_diff(run_id: String, base_branch: Option<String>) -> Result<RunDiff, Error> {
+    tauri::async_runtime::spawn_blocking(move || run_diff(&run_id, base_branch.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn delta_path(delta: &git2::DiffDelta) -> Option<String> {
+    delta
+        .new_file()
+        .path()
+        .or_else(|| delta.old_file().path())
+        .map(|p| p.display().to_string())
+}
+
+/// Copy a file the run created into `target`, refusing to overwrite one already there
+fn copy_new_file(source: &Path, target: &Path, path: &str) -> Result<(), String> {
+    let to = target.join(path);
+    if to.exists() {
+        return Err("The main checkout already has a file at this path".to_string());
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::copy(source.join(path), &to)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn apply(
+    run_id: &str,
+    base_branch: Option<&str>,
+    selections: &[HunkSelection],
+) -> Result<ApplyReport, Error> {
+    let (repo_path, base, _) = resolve(run_id, base_branch)?;
+    let source = Repository::open(&repo_path).map_err(|e| e.message().to_string())?;
+    if !source.is_worktree() {
+        return Err(Error::InvalidInput(format!(
+            "Run {} worked in the main checkout, so its changes are already there",
+            run_id
+        )));
+    }
+    // A linked worktree's common dir is the main checkout's .git
+    let main = Repository::open(source.commondir()).map_err(|e| e.message().to_string())?;
+    let target = main
+        .workdir()
+        .ok_or("The main repository is bare")?
+        .to_path_buf();
+    let diff = workdir_diff(&source, base).map_err(|e| e.message().to_string())?;
+
+    let mut files = Vec::new();
+    for selection in selections {
+        let delta = diff
+            .deltas()
+            .find(|delta| delta_path(delta).as_deref() == Some(&selection.path));
+        let Some(delta) = delta else {
+            files.push(FileApplyResult {
+                path: selection.path.clone(),
+                status: "missing",
+                hunks: 0,
+                error: None,
+            });
+            continue;
+        };
+        let wanted = |index: usize| {
+            selection
+                .hunks
+                .as_ref()
+                .is_none_or(|hunks| hunks.contains(&index))
+        };
+
+        // libgit2 can't apply untracked files onto another checkout
+        let result = if delta.status() == Delta::Untracked {
+            if wanted(0) {
+                copy_new_file(Path::new(&repo_path), &target, &selection.path).map(|_| 1)
+            } else {
+                Ok(0)
+            }
+        } else {
+            let index = Cell::new(0);
+            let applied = Cell::new(0);
+            let mut options = ApplyOptions::new();
+            options.delta_callback(|delta| {
+                index.set(0);
+                delta.and_then(|d| delta_path(&d)).as_deref() == Some(&selection.path)
+            });
+            options.hunk_callback(|_| {
+                let take = wanted(index.get());
+                index.set(index.get() + 1);
+                if take {
+                    applied.set(applied.get() + 1);
+                }
+                take
+            });
+            main.apply(&diff, ApplyLocation::WorkDir, Some(&mut options))
+                .map(|_| applied.get())
+                .map_err(|e| e.message().to_string())
+        };
+        files.push(match result {
+            Ok(hunks) => FileApplyResult {
+                path: selection.path.clone(),
+                status: "applied",
+                hunks,
+                error: None,
+            },
+            Err(e) => FileApplyResult {
+                path: selection.path.clone(),
+                status: "conflict",
+                hunks: 0,
+                error: Some(e),
+            },
+        });
+    }
+
+    let report = ApplyReport {
+        run_id: run_id.to_string(),
+        target: target.display().to_string(),
+        applied: files.iter().filter(|f| f.status == "applied").count(),
+        conflicts: files.iter().filter(|f| f.status == "conflict").count(),
+        files,
+    };
+    println!(
+        "[Claude PM] Applied {} files from run {} to {} ({} conflicts)",
+        report.applied, run_id, report.target, report.conflicts
+    );
+    Ok(report)
+}
+
+/// Apply the selected hunks of run `run_id` (as listed by `get_run_diff` with the same
+/// `base_branch`) from its worktree to the main checkout
+#[tauri::command]
+pub async fn apply_hunks(
+    run_id: String,
+    selections: Vec<HunkSelection>,
+    base_branch: Option<String>,
+) -> Result<ApplyReport, Error> {
+    tauri::async_runtime::spawn_blocking(move || {
+        apply(&run_id, base_branch.as_deref(), &selections)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
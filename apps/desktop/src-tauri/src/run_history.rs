@@ -0,0 +1,377 @@
+//! Persisted history of agent runs and what came of them
+//!
+//! Every run the orchestrator finishes — done, failed or cancelled — is recorded in the
+//! `run_history` table with its prompt, timing, token use and cost (from the Claude
+//! transcript the run wrote), the Claude profile it ran as, and the files it changed (from its diff against the
+//! pre-run snapshot, see `run_diff`). The transcript is found among those in the run's
+//! project directory written since it started, by its first prompt. Records older than
+//! `run_history_days` (90 unless set) are dropped, as are all but the newest
+//! `run_history_limit` (5000 unless set).
+
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::claude_profiles::{self, ProfileUsage};
+use crate::error::Error;
+use crate::orchestrator::{AgentRun, RunState};
+use crate::{config, run_diff, search, snapshots, store, usage};
+
+const DEFAULT_DAYS: u32 = 90;
+const DEFAULT_MAX_RUNS: usize = 5000;
+const DEFAULT_LIMIT: usize = 200;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRecord {
+    pub id: String,
+    pub task_id: String,
+    pub project_id: String,
+    pub title: String,
+    pub prompt: String,
+    pub model: Option<String>,
+    pub cwd: Option<String>,
+    pub state: RunState,
+    pub error: Option<String>,
+    /// Store session the run was tracked under
+    pub session_id: Option<String>,
+    pub queued_at: i64,
+    pub started_at: Option<i64>,
+    pub ended_at: i64,
+    /// From start to end; `None` for runs that never started
+    pub duration_ms: Option<i64>,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    pub files_changed: Vec<String>,
+    pub additions: usize,
+    pub deletions: usize,
+    /// The Claude transcript, for `follow_transcript` or opening the file
+    pub transcript_path: Option<String>,
+    pub claude_session_id: Option<String>,
+    /// Pre-run snapshot, for `get_run_diff` and `rollback_run`
+    pub snapshot_id: Option<String>,
+    /// Claude profile the run ran as (see `claude_profiles`); `None` for runs recorded
+    /// before there were profiles
+    pub claude_profile: Option<String>,
+}
+
+impl RunRecord {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            project_id: row.get("project_id")?,
+            title: row.get("title")?,
+            prompt: row.get("prompt")?,
+            model: row.get("model")?,
+            cwd: row.get("cwd")?,
+            state: RunState::parse(&row.get::<_, String>("state")?),
+            error: row.get("error")?,
+            session_id: row.get("session_id")?,
+            queued_at: row.get("queued_at")?,
+            started_at: row.get("started_at")?,
+            ended_at: row.get("ended_at")?,
+            duration_ms: row.get("duration_ms")?,
+            input_tokens: row.get::<_, i64>("input_tokens")? as u64,
+            output_tokens: row.get::<_, i64>("output_tokens")? as u64,
+            cost_usd: row.get("cost_usd")?,
+            files_changed: serde_json::from_str(&row.get::<_, String>("files_changed")?)
+                .unwrap_or_default(),
+            additions: row.get::<_, i64>("additions")? as usize,
+            deletions: row.get::<_, i64>("deletions")? as usize,
+            transcript_path: row.get("transcript_path")?,
+            claude_session_id: row.get("claude_session_id")?,
+            snapshot_id: row.get("snapshot_id")?,
+            claude_profile: row.get("claude_profile")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RunHistoryFilter {
+    pub task_id: Option<String>,
+    pub project_id: Option<String>,
+    pub state: Option<String>,
+    /// Runs that ended at or after this time (ms)
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    /// Substring of the title or prompt
+    pub text: Option<String>,
+    /// Runs that changed a file whose path contains this
+    pub file: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// Token use and cost summed over a transcript
+#[derive(Default)]
+struct TranscriptUsage {
+    input_tokens: u64,
+    output_tokens: u64,
+    cost_usd: f64,
+}
+
+fn modified_ms(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64)
+}
+
+/// The first user message's text, and the token use of all entries
+fn read_transcript(path: &Path) -> Option<(String, TranscriptUsage)> {
+    let file = fs::File::open(path).ok()?;
+    let mut first_prompt = None;
+    let mut usage = TranscriptUsage::default();
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let Ok(entry) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if first_prompt.is_none() && entry["type"] == "user" {
+            first_prompt = Some(search::text_of(&entry["message"]["content"]));
+        }
+        let tokens = &entry["message"]["usage"];
+        let count = |key: &str| tokens[key].as_u64().unwrap_or(0);
+        usage.input_tokens += count("input_tokens")
+            + count("cache_creation_input_tokens")
+            + count("cache_read_input_tokens");
+        usage.output_tokens += count("output_tokens");
+        usage.cost_usd += usage::entry_cost(&entry);
+    }
+    Some((first_prompt?, usage))
+}
+
+/// The transcript in `repo`'s project directory that `run` wrote
+fn find_transcript(run: &AgentRun, repo: &str) -> Option<(PathBuf, TranscriptUsage)> {
+    let started = run.started_at?;
+    let dir = claude_profiles::transcripts_dir(run.claude_profile.as_deref())?
+        .join(repo.replace(['/', '.'], "-"));
+    let prompt = run.prompt.trim();
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .filter(|path| modified_ms(path).is_some_and(|m| m >= started))
+        .find_map(|path| {
+            let (first, usage) = read_transcript(&path)?;
+            (first.trim() == prompt).then_some((path, usage))
+        })
+}
+
+fn repo_of(run: &AgentRun) -> Option<String> {
+    run.cwd.clone().or_else(|| {
+        store::list_projects()
+            .ok()?
+            .into_iter()
+            .find(|p| p.id == run.project_id)
+            .and_then(|p| p.repo_path)
+    })
+}
+
+fn save(record: &RunRecord) -> Result<(), String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO run_history (id, task_id, project_id, title, prompt, model, cwd, state, error, session_id, queued_at, started_at, ended_at, duration_ms, input_tokens, output_tokens, cost_usd, files_changed, additions, deletions, transcript_path, claude_session_id, snapshot_id, claude_profile)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)",
+            params![
+                record.id,
+                record.task_id,
+                record.project_id,
+                record.title,
+                record.prompt,
+                record.model,
+                record.cwd,
+                record.state.as_str(),
+                record.error,
+                record.session_id,
+                record.queued_at,
+                record.started_at,
+                record.ended_at,
+                record.duration_ms,
+                record.input_tokens as i64,
+                record.output_tokens as i64,
+                record.cost_usd,
+                serde_json::to_string(&record.files_changed).unwrap_or_else(|_| "[]".to_string()),
+                record.additions as i64,
+                record.deletions as i64,
+                record.transcript_path,
+                record.claude_session_id,
+                record.snapshot_id,
+                record.claude_profile,
+            ],
+        )
+        .map(|_| ())
+    })
+}
+
+/// Drop records past the configured age and count
+fn prune() {
+    let config = config::load();
+    let days = config.run_history_days.unwrap_or(DEFAULT_DAYS).max(1);
+    let max = config.run_history_limit.unwrap_or(DEFAULT_MAX_RUNS).max(1);
+    let cutoff = store::now_ms() - days as i64 * DAY_MS;
+    let result = store::with_conn(|conn| {
+        conn.execute("DELETE FROM run_history WHERE ended_at < ?1", [cutoff])?;
+        conn.execute(
+            "DELETE FROM run_history WHERE id NOT IN (SELECT id FROM run_history ORDER BY ended_at DESC LIMIT ?1)",
+            [max as i64],
+        )
+        .map(|_| ())
+    });
+    if let Err(e) = result {
+        eprintln!("[Claude PM] Failed to prune run history: {}", e);
+    }
+}
+
+/// Record a finished run, returning the record once saved; failures are logged, the
+/// run is over either way
+pub fn record(run: &AgentRun) -> Option<RunRecord> {
+    let ended_at = run.ended_at.unwrap_or_else(store::now_ms);
+    let snapshot = snapshots::pre_run(&run.id).ok().flatten();
+    // Nothing to diff or read for a run that never started
+    let diff = run
+        .started_at
+        .and_then(|_| run_diff::run_diff(&run.id, None).ok());
+    let repo = snapshot
+        .as_ref()
+        .map(|s| s.repo.clone())
+        .or_else(|| repo_of(run));
+    let transcript = repo.and_then(|repo| find_transcript(run, &repo));
+    let (transcript_path, usage) = match transcript {
+        Some((path, usage)) => (Some(path), usage),
+        None => (None, TranscriptUsage::default()),
+    };
+    let record = RunRecord {
+        id: run.id.clone(),
+        task_id: run.task_id.clone(),
+        project_id: run.project_id.clone(),
+        title: run.title.clone(),
+        prompt: run.prompt.clone(),
+        model: run.model.clone(),
+        cwd: run.cwd.clone(),
+        state: run.state,
+        error: run.error.clone(),
+        session_id: run.session_id.clone(),
+        queued_at: run.queued_at,
+        started_at: run.started_at,
+        ended_at,
+        duration_ms: run.started_at.map(|started| ended_at - started),
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        cost_usd: usage.cost_usd,
+        files_changed: diff
+            .as_ref()
+            .map(|d| d.files.iter().map(|f| f.path.clone()).collect())
+            .unwrap_or_default(),
+        additions: diff.as_ref().map(|d| d.additions).unwrap_or(0),
+        deletions: diff.as_ref().map(|d| d.deletions).unwrap_or(0),
+        claude_session_id: transcript_path
+            .as_ref()
+            .and_then(|p| p.file_stem())
+            .map(|stem| stem.to_string_lossy().to_string()),
+        transcript_path: transcript_path.map(|p| p.display().to_string()),
+        snapshot_id: snapshot.map(|s| s.id),
+        claude_profile: run.claude_profile.clone(),
+    };
+    match save(&record) {
+        Ok(()) => {
+            prune();
+            Some(record)
+        }
+        Err(e) => {
+            eprintln!("[Claude PM] Failed to record run {}: {}", run.id, e);
+            None
+        }
+    }
+}
+
+pub fn query(filter: &RunHistoryFilter) -> Result<Vec<RunRecord>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT * FROM run_history
+             WHERE (?1 IS NULL OR task_id = ?1) AND (?2 IS NULL OR project_id = ?2)
+               AND (?3 IS NULL OR state = ?3)
+               AND (?4 IS NULL OR ended_at >= ?4) AND (?5 IS NULL OR ended_at <= ?5)
+               AND (?6 IS NULL OR instr(lower(title), lower(?6)) > 0 OR instr(lower(prompt), lower(?6)) > 0)
+               AND (?7 IS NULL OR EXISTS (SELECT 1 FROM json_each(files_changed) WHERE instr(value, ?7) > 0))
+             ORDER BY ended_at DESC LIMIT ?8",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                filter.task_id,
+                filter.project_id,
+                filter.state,
+                filter.since,
+                filter.until,
+                filter.text,
+                filter.file,
+                filter.limit.unwrap_or(DEFAULT_LIMIT) as i64
+            ],
+            RunRecord::from_row,
+        )?;
+        rows.collect()
+    })
+}
+
+/// Runs, tokens and cost per Claude profile for runs that ended since `since`
+pub fn usage_by_profile(since: i64) -> Result<BTreeMap<String, ProfileUsage>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT COALESCE(claude_profile, ?2), COUNT(*), SUM(input_tokens), SUM(output_tokens), SUM(cost_usd)
+             FROM run_history WHERE ended_at >= ?1 GROUP BY 1",
+        )?;
+        let rows = stmt.query_map(params![since, claude_profiles::DEFAULT_PROFILE], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                ProfileUsage {
+                    runs: row.get::<_, i64>(1)? as usize,
+                    input_tokens: row.get::<_, i64>(2)? as u64,
+                    output_tokens: row.get::<_, i64>(3)? as u64,
+                    cost_usd: row.get(4)?,
+                },
+            ))
+        })?;
+        rows.collect()
+    })
+}
+
+/// Recorded runs matching `filter`, most recently ended first
+#[tauri::command]
+pub fn query_run_history(filter: Option<RunHistoryFilter>) -> Result<Vec<RunRecord>, Error> {
+    query(&filter.unwrap_or_default()).map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn get_run_record(id: String) -> Result<RunRecord, Error> {
+    store::with_conn(|conn| {
+        conn.query_row(
+            "SELECT * FROM run_history WHERE id = ?1",
+            [&id],
+            RunRecord::from_row,
+        )
+        .optional()
+    })?
+    .ok_or_else(|| Error::NotFound(format!("No recorded run with id {}", id)))
+}
+
+/// Keep `days` of history and at most `max_runs` records (the defaults when unset)
+#[tauri::command]
+pub fn set_run_history_retention(days: Option<u32>, max_runs: Option<usize>) -> Result<(), Error> {
+    if days == Some(0) || max_runs == Some(0) {
+        return Err(Error::InvalidInput(
+            "Keep at least a day and one run of history".to_string(),
+        ));
+    }
+    config::update(|c| {
+        c.run_history_days = days;
+        c.run_history_limit = max_runs;
+    })?;
+    prune();
+    Ok(())
+}
@@ -0,0 +1,189 @@
+//! Generic command runner that streams output to the frontend as events
+//!
+//! Events:
+//! - `command-output` — one per line of stdout/stderr
+//! - `command-exit` — once, when the process exits, is cancelled or times out
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Read};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::exec_policy;
+use crate::process::{kill_tree, new_process_group, CancelToken};
+
+/// How often the waiter thread checks for exit, cancellation and timeout
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Cancellation flags for running commands, keyed by caller-supplied id
+static RUNNING: Mutex<BTreeMap<String, CancelToken>> = Mutex::new(BTreeMap::new());
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OutputEvent<'a> {
+    id: &'a str,
+    stream: &'static str,
+    line: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExitEvent {
+    id: String,
+    /// None when killed by a signal (including cancellation/timeout)
+    code: Option<i32>,
+    success: bool,
+    cancelled: bool,
+    timed_out: bool,
+    duration_ms: u128,
+}
+
+fn stream_lines(
+    app: AppHandle,
+    id: String,
+    stream: &'static str,
+    reader: impl Read + Send + 'static,
+) {
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            let _ = app.emit(
+                "command-output",
+                OutputEvent {
+                    id: &id,
+                    stream,
+                    line,
+                },
+            );
+        }
+    });
+}
+
+/// Spawn `program` and stream its output; returns as soon as the process has started
+#[tauri::command]
+pub fn run_command(
+    app: AppHandle,
+    id: String,
+    program: String,
+    args: Vec<String>,
+    cwd: Option<String>,
+    env: Option<HashMap<String, String>>,
+    timeout_secs: Option<u64>,
+) -> Result<(), Error> {
+    let cancel = CancelToken::default();
+    {
+        let mut running = RUNNING.lock().map_err(|e| e.to_string())?;
+        if running.contains_key(&id) {
+            return Err(Error::InvalidInput(format!(
+                "A command with id {} is already running",
+                id
+            )));
+        }
+        running.insert(id.clone(), cancel.clone());
+    }
+
+    // Checked against the execution policy (see `exec_policy`)
+    let mut cmd =
+        match exec_policy::command(&program, &args, cwd.as_deref(), env.unwrap_or_default()) {
+            Ok(cmd) => cmd,
+            Err(e) => {
+                if let Ok(mut running) = RUNNING.lock() {
+                    running.remove(&id);
+                }
+                return Err(e);
+            }
+        };
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    new_process_group(&mut cmd);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            if let Ok(mut running) = RUNNING.lock() {
+                running.remove(&id);
+            }
+            return Err(format!("Failed to start {}: {}", program, e).into());
+        }
+    };
+
+    if let Some(stdout) = child.stdout.take() {
+        stream_lines(app.clone(), id.clone(), "stdout", stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        stream_lines(app.clone(), id.clone(), "stderr", stderr);
+    }
+
+    let timeout = timeout_secs.map(Duration::from_secs);
+    thread::spawn(move || {
+        let started = Instant::now();
+        let mut timed_out = false;
+
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break Some(status),
+                Ok(None) => {}
+                Err(_) => break None,
+            }
+
+            timed_out = timeout.is_some_and(|t| started.elapsed() >= t);
+            if timed_out || cancel.is_cancelled() {
+                kill_tree(&mut child);
+                break child.wait().ok();
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        if let Ok(mut running) = RUNNING.lock() {
+            running.remove(&id);
+        }
+
+        let _ = app.emit(
+            "command-exit",
+            ExitEvent {
+                id,
+                code: status.and_then(|s| s.code()),
+                success: status.is_some_and(|s| s.success()),
+                cancelled: cancel.is_cancelled(),
+                timed_out,
+                duration_ms: started.elapsed().as_millis(),
+            },
+        );
+    });
+
+    Ok(())
+}
+
+/// Request cancellation of a running command; the `command-exit` event follows shortly
+#[tauri::command]
+pub fn cancel_command(id: String) -> Result<(), Error> {
+    let running = RUNNING.lock().map_err(|e| e.to_string())?;
+    let cancel = running
+        .get(&id)
+        .ok_or_else(|| Error::NotFound(format!("No running command with id {}", id)))?;
+    cancel.cancel();
+    Ok(())
+}
+
+/// Cancel every running command; returns their ids
+pub fn cancel_all() -> Vec<String> {
+    let Ok(running) = RUNNING.lock() else {
+        return Vec::new();
+    };
+    for cancel in running.values() {
+        cancel.cancel();
+    }
+    running.keys().cloned().collect()
+}
+
+/// Ids of commands that are still running
+#[tauri::command]
+pub fn list_running_commands() -> Result<Vec<String>, Error> {
+    let running = RUNNING.lock().map_err(|e| e.to_string())?;
+    Ok(running.keys().cloned().collect())
+}
@@ -0,0 +1,312 @@
+//! Persisted cron/interval jobs evaluated while the app is running
+//!
+//! Schedules live in schedules.json in the config directory. The evaluator compares
+//! wall-clock time against each job's last run, so runs missed while the machine slept
+//! are noticed on wake and either caught up (once) or skipped, per `catch_up`. Outside
+//! working hours (see `working_hours`) due runs are held the same way, unless the
+//! schedule is marked `after_hours`.
+
+use chrono::{DateTime, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{config, report, runner, server_api, store, working_hours};
+
+pub const SCHEDULES_FILE: &str = "schedules.json";
+const TICK: Duration = Duration::from_secs(30);
+/// A run this late counts as missed rather than merely delayed by the tick
+const MISSED_AFTER_MS: i64 = 2 * 60 * 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Trigger {
+    /// Standard 5-field cron expression in local time, e.g. `0 9 * * Mon`
+    Cron {
+        expression: String,
+    },
+    Interval {
+        every_secs: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ScheduleAction {
+    /// Call the local server, e.g. `POST /api/projects/<id>/tickets/sync`
+    Api {
+        method: String,
+        path: String,
+        #[serde(default)]
+        body: Option<Value>,
+    },
+    /// Spawn a process (typically an agent) with output streamed like `run_command`
+    Spawn {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        cwd: Option<String>,
+    },
+    /// Write last week's summary and notify that it's ready
+    WeeklySummary,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+    pub trigger: Trigger,
+    pub action: ScheduleAction,
+    /// Run once on wake if a run was missed while asleep, or held outside working hours
+    #[serde(default = "enabled_default")]
+    pub catch_up: bool,
+    /// Fire outside working hours too (see `working_hours`)
+    #[serde(default)]
+    pub after_hours: bool,
+    /// Unix millis of the last run (or of creation, before the first run)
+    #[serde(default)]
+    pub last_run: Option<i64>,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScheduleFired {
+    id: String,
+    name: String,
+    catch_up: bool,
+    error: Option<String>,
+}
+
+static SCHEDULES: Mutex<Option<Vec<Schedule>>> = Mutex::new(None);
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+fn schedules_path() -> Result<PathBuf, String> {
+    config::config_dir()
+        .map(|dir| dir.join(SCHEDULES_FILE))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+fn load() -> Vec<Schedule> {
+    schedules_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save(schedules: &[Schedule]) -> Result<(), String> {
+    let path = schedules_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let contents = serde_json::to_string_pretty(schedules).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write schedules: {}", e))
+}
+
+/// Run `f` against the in-memory schedules, loading them on first use; saves if `f` returns true
+fn with_schedules<T>(f: impl FnOnce(&mut Vec<Schedule>) -> (T, bool)) -> Result<T, String> {
+    let mut guard = SCHEDULES.lock().map_err(|e| e.to_string())?;
+    let schedules = guard.get_or_insert_with(load);
+    let (result, changed) = f(schedules);
+    if changed {
+        save(schedules)?;
+    }
+    Ok(result)
+}
+
+fn parse_cron(expression: &str) -> Result<cron::Schedule, String> {
+    // The cron crate wants a leading seconds field
+    let expression = match expression.split_whitespace().count() {
+        5 => format!("0 {}", expression),
+        _ => expression.to_string(),
+    };
+    cron::Schedule::from_str(&expression).map_err(|e| format!("Invalid cron expression: {}", e))
+}
+
+/// First run time strictly after `after_ms`
+fn next_run(trigger: &Trigger, after_ms: i64) -> Option<i64> {
+    match trigger {
+        Trigger::Interval { every_secs } => Some(after_ms + (*every_secs as i64) * 1000),
+        Trigger::Cron { expression } => {
+            let after: DateTime<Local> = Local.timestamp_millis_opt(after_ms).single()?;
+            parse_cron(expression)
+                .ok()?
+                .after(&after)
+                .next()
+                .map(|next| next.timestamp_millis())
+        }
+    }
+}
+
+fn validate(schedule: &Schedule) -> Result<(), String> {
+    if schedule.name.trim().is_empty() {
+        return Err("Schedule name is required".to_string());
+    }
+    match &schedule.trigger {
+        Trigger::Cron { expression } => parse_cron(expression).map(|_| ()),
+        Trigger::Interval { every_secs } if *every_secs < 60 => {
+            Err("Interval must be at least 60 seconds".to_string())
+        }
+        Trigger::Interval { .. } => Ok(()),
+    }
+}
+
+fn execute(app: &AppHandle, schedule: &Schedule) -> Result<(), Error> {
+    match &schedule.action {
+        ScheduleAction::Api { method, path, body } => {
+            server_api::send_json(method, path, body.as_ref())?;
+            Ok(())
+        }
+        ScheduleAction::Spawn { program, args, cwd } => runner::run_command(
+            app.clone(),
+            format!("schedule:{}:{}", schedule.id, store::now_ms()),
+            program.clone(),
+            args.clone(),
+            cwd.clone(),
+            None,
+            None,
+        ),
+        ScheduleAction::WeeklySummary => report::publish_weekly(),
+    }
+}
+
+fn fire(app: &AppHandle, schedule: Schedule, catch_up: bool) {
+    let error = execute(app, &schedule).err().map(|e| e.to_string());
+    if let Some(ref e) = error {
+        eprintln!("[Claude PM] Schedule {} failed: {}", schedule.name, e);
+    }
+    let _ = app.emit(
+        "schedule-fired",
+        ScheduleFired {
+            id: schedule.id,
+            name: schedule.name,
+            catch_up,
+            error,
+        },
+    );
+}
+
+/// Work out which schedules are due, recording their run time before firing
+fn due(now: i64) -> Vec<(Schedule, bool)> {
+    let after_hours = working_hours::is_after_hours();
+    with_schedules(|schedules| {
+        let mut due = Vec::new();
+        let mut changed = false;
+        for schedule in schedules.iter_mut().filter(|s| s.enabled) {
+            if schedule.last_run.is_none() {
+                schedule.last_run = Some(now);
+                changed = true;
+            }
+            let last = schedule.last_run.unwrap_or(now);
+            let Some(next) = next_run(&schedule.trigger, last) else {
+                continue;
+            };
+            // Left due, so it counts as missed once working hours start
+            if next > now || (after_hours && !schedule.after_hours) {
+                continue;
+            }
+            schedule.last_run = Some(now);
+            changed = true;
+            let missed = now - next > MISSED_AFTER_MS;
+            if !missed || schedule.catch_up {
+                due.push((schedule.clone(), missed));
+            }
+        }
+        (due, changed)
+    })
+    .unwrap_or_default()
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        for (schedule, catch_up) in due(store::now_ms()) {
+            fire(&app, schedule, catch_up);
+        }
+        thread::sleep(TICK);
+    });
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleInfo {
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    pub next_run: Option<i64>,
+}
+
+#[tauri::command]
+pub fn list_schedules() -> Result<Vec<ScheduleInfo>, Error> {
+    let now = store::now_ms();
+    with_schedules(|schedules| {
+        let infos = schedules
+            .iter()
+            .map(|schedule| ScheduleInfo {
+                next_run: schedule
+                    .enabled
+                    .then(|| next_run(&schedule.trigger, schedule.last_run.unwrap_or(now)))
+                    .flatten(),
+                schedule: schedule.clone(),
+            })
+            .collect();
+        (infos, false)
+    })
+    .map_err(Error::from)
+}
+
+/// Create a schedule (empty `id`) or replace an existing one
+#[tauri::command]
+pub fn upsert_schedule(mut schedule: Schedule) -> Result<Schedule, Error> {
+    validate(&schedule)?;
+    if schedule.id.is_empty() {
+        schedule.id = format!("sched-{}", store::now_ms());
+    }
+    with_schedules(|schedules| {
+        match schedules.iter_mut().find(|s| s.id == schedule.id) {
+            Some(existing) => {
+                schedule.last_run = schedule.last_run.or(existing.last_run);
+                *existing = schedule.clone();
+            }
+            None => {
+                schedule.last_run.get_or_insert_with(store::now_ms);
+                schedules.push(schedule.clone());
+            }
+        }
+        (schedule, true)
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn delete_schedule(id: String) -> Result<(), Error> {
+    with_schedules(|schedules| (schedules.retain(|s| s.id != id), true)).map_err(Error::from)
+}
+
+/// Fire a schedule immediately without changing its timing
+#[tauri::command]
+pub fn run_schedule_now(app: AppHandle, id: String) -> Result<(), Error> {
+    let schedule =
+        with_schedules(|schedules| (schedules.iter().find(|s| s.id == id).cloned(), false))?
+            .ok_or_else(|| Error::NotFound(format!("Schedule not found: {}", id)))?;
+    execute(&app, &schedule)
+}
@@ -0,0 +1,114 @@
+//! Screenshot capture straight into a task's attachments
+//!
+//! Uses `screencapture` on macOS, which needs the Screen Recording permission (without
+//! it captures silently contain only the wallpaper, so we check first), and
+//! `gnome-screenshot` on Linux.
+
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+use crate::attachments::{self, Attachment};
+use crate::error::Error;
+use crate::permissions::{self, PermissionStatus};
+use crate::process::{self, which, CancelToken};
+
+/// Interactive modes wait for the user to pick a window or drag a region
+const INTERACTIVE_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptureMode {
+    /// Every display (macOS writes the main one)
+    Full,
+    /// Click a window to capture it
+    Window,
+    /// Drag out a region
+    Region,
+}
+
+fn capture_command(mode: CaptureMode, path: &Path) -> Result<Command, Error> {
+    if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("screencapture");
+        // -x: no shutter sound
+        cmd.arg("-x");
+        match mode {
+            CaptureMode::Full => {}
+            CaptureMode::Window => {
+                cmd.args(["-i", "-w"]);
+            }
+            CaptureMode::Region => {
+                cmd.args(["-i", "-s"]);
+            }
+        }
+        cmd.arg(path);
+        Ok(cmd)
+    } else if cfg!(target_os = "linux") {
+        let cli = which("gnome-screenshot")
+            .ok_or_else(|| Error::NotFound("gnome-screenshot is required on Linux".to_string()))?;
+        let mut cmd = Command::new(cli);
+        match mode {
+            CaptureMode::Full => {}
+            CaptureMode::Window => {
+                cmd.arg("-w");
+            }
+            CaptureMode::Region => {
+                cmd.arg("-a");
+            }
+        }
+        cmd.arg("-f").arg(path);
+        Ok(cmd)
+    } else {
+        Err(Error::Unsupported(
+            "Screenshots are not supported on this platform".to_string(),
+        ))
+    }
+}
+
+/// Take a screenshot and store it as an attachment, linked to `task_id` when given.
+/// Pressing Escape during an interactive capture returns a `cancelled` error
+#[tauri::command]
+pub async fn capture_screenshot(
+    mode: CaptureMode,
+    task_id: Option<String>,
+) -> Result<Attachment, Error> {
+    if permissions::check_screen_recording() == PermissionStatus::Denied {
+        permissions::request_screen_recording();
+        return Err(Error::PermissionDenied {
+            permission: "screenRecording",
+            message: "Screen Recording permission denied. Allow Claude PM under System Settings → Privacy & Security → Screen Recording".to_string(),
+        });
+    }
+
+    let file_name = format!(
+        "screenshot-{}.png",
+        chrono::Local::now().format("%Y-%m-%d-%H%M%S")
+    );
+    let path = attachments::new_path(&file_name)?;
+    let mut cmd = capture_command(mode, &path)?;
+    let timeout = match mode {
+        CaptureMode::Full => process::default_timeout(),
+        _ => INTERACTIVE_TIMEOUT,
+    };
+
+    let output = tauri::async_runtime::spawn_blocking(move || {
+        process::run(&mut cmd, timeout, &CancelToken::default())
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    // Both tools exit successfully without writing a file when the user cancels
+    if !path.exists() {
+        return if output.status.success() {
+            Err(Error::Cancelled("Screenshot cancelled".to_string()))
+        } else {
+            Err(format!(
+                "Screenshot failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into())
+        };
+    }
+    attachments::add(task_id.as_deref(), &path, "image/png")
+}
@@ -0,0 +1,446 @@
+//! User automation scripts run when an event fires
+//!
+//! Scripts are Rhai, for example on `taskCompleted`:
+//!
+//! ```text
+//! rename_window(`done: ${task.title}`);
+//! if task.projectId == "abc123" {
+//!     post("Team Slack", `Finished ${task.title}`);
+//! }
+//! ```
+//!
+//! Functions: `notify(title, body)`, `post(integration, text)`, `rename_window(name)`
+//! (the tmux window of the event's agent, or of the task's latest session) and
+//! `log(message)` (`print` also logs). Constants come from the event: `event.kind`,
+//! `event.title`, `event.detail`, plus its data (`task`, `run`, `target`, `prompt`,
+//! `status`).
+//!
+//! Each run gets a sandboxed engine: no modules or `eval`, and limits on operations,
+//! call depth and string and collection sizes. The registered functions only record
+//! actions, so evaluating a script has no side effects; a dry run returns those actions
+//! and a real run performs them once the script finishes. Scripts live in scripts.json
+//! in the config directory.
+//!
+//! Events:
+//! - `script-run` after a script runs for an event
+
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, Scope};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::thread;
+use tauri::{AppHandle, Emitter};
+
+use crate::config;
+use crate::error::Error;
+use crate::integrations::{self, Event, EventKind};
+use crate::notifications::{self, NotificationRequest};
+use crate::store;
+use crate::tmux;
+
+pub const SCRIPTS_FILE: &str = "scripts.json";
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_STRING_SIZE: usize = 64 * 1024;
+const MAX_COLLECTION_SIZE: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Script {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub event: EventKind,
+    #[serde(default)]
+    pub enabled: bool,
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum Action {
+    Notify { title: String, body: String },
+    Post { integration: String, text: String },
+    RenameWindow { target: String, name: String },
+    Log { message: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptRun {
+    pub script_id: String,
+    pub ok: bool,
+    pub actions: Vec<Action>,
+    pub error: Option<String>,
+}
+
+static SCRIPTS: Mutex<Option<Vec<Script>>> = Mutex::new(None);
+
+fn scripts_path() -> Result<PathBuf, String> {
+    config::config_dir()
+        .map(|dir| dir.join(SCRIPTS_FILE))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+fn load() -> Vec<Script> {
+    scripts_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save(scripts: &[Script]) -> Result<(), String> {
+    let path = scripts_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let contents = serde_json::to_string_pretty(scripts).map_err(|e| e.to_string())?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write scripts: {}", e))
+}
+
+/// Run `f` against the in-memory scripts, loading them on first use; saves if `f` returns true
+fn with_scripts<T>(f: impl FnOnce(&mut Vec<Script>) -> (T, bool)) -> Result<T, String> {
+    let mut guard = SCRIPTS.lock().map_err(|e| e.to_string())?;
+    let scripts = guard.get_or_insert_with(load);
+    let (result, changed) = f(scripts);
+    if changed {
+        save(scripts)?;
+    }
+    Ok(result)
+}
+
+/// An engine without host functions, limited so a runaway script fails instead of hanging
+fn sandbox() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval");
+    engine.on_debug(|_, _, _| {});
+    engine
+}
+
+/// Check that `source` compiles
+fn check(source: &str) -> Result<(), String> {
+    sandbox()
+        .compile(source)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Constants visible to a script for `event`
+fn scope(event: &Event) -> Result<Scope<'static>, String> {
+    let mut scope = Scope::new();
+    if let Value::Object(data) = &event.data {
+        for (key, value) in data {
+            let value = rhai::serde::to_dynamic(value).map_err(|e| e.to_string())?;
+            scope.push_constant(key.as_str(), value);
+        }
+    }
+    let meta = json!({
+        "kind": event.kind,
+        "title": event.title,
+        "detail": event.detail,
+    });
+    let meta = rhai::serde::to_dynamic(meta).map_err(|e| e.to_string())?;
+    scope.push_constant("event", meta);
+    Ok(scope)
+}
+
+/// The tmux window an event is about: the blocked agent's, or the task's latest session
+fn window_target(event: &Event) -> Result<String, String> {
+    if let Some(target) = event.data.get("target").and_then(Value::as_str) {
+        return Ok(target.to_string());
+    }
+    let task_id = event
+        .data
+        .pointer("/task/id")
+        .and_then(Value::as_str)
+        .ok_or("This event has no tmux window")?;
+    store::list_sessions(None, None)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|s| s.task_id.as_deref() == Some(task_id))
+        .find_map(|s| s.pane_id)
+        .ok_or_else(|| "The task has no session with a tmux pane".to_string())
+}
+
+/// The sandbox with the script functions registered, each recording its action in `actions`
+fn engine(event: &Event, actions: &Rc<RefCell<Vec<Action>>>) -> Engine {
+    let mut engine = sandbox();
+    let record = |actions: &Rc<RefCell<Vec<Action>>>| {
+        let actions = Rc::clone(actions);
+        move |action: Action| actions.borrow_mut().push(action)
+    };
+
+    let push = record(actions);
+    engine.on_print(move |message| {
+        push(Action::Log {
+            message: message.to_string(),
+        })
+    });
+    let push = record(actions);
+    engine.register_fn("log", move |message: Dynamic| {
+        push(Action::Log {
+            message: message.to_string(),
+        })
+    });
+    let push = record(actions);
+    engine.register_fn("notify", move |title: Dynamic, body: Dynamic| {
+        push(Action::Notify {
+            title: title.to_string(),
+            body: body.to_string(),
+        })
+    });
+    let push = record(actions);
+    engine.register_fn("post", move |integration: Dynamic, text: Dynamic| {
+        push(Action::Post {
+            integration: integration.to_string(),
+            text: text.to_string(),
+        })
+    });
+    let push = record(actions);
+    let event = event.clone();
+    engine.register_fn(
+        "rename_window",
+        move |name: Dynamic| -> Result<(), Box<EvalAltResult>> {
+            push(Action::RenameWindow {
+                target: window_target(&event)?,
+                name: name.to_string(),
+            });
+            Ok(())
+        },
+    );
+    engine
+}
+
+/// The actions `source` would take for `event`, without running any of them
+fn plan(source: &str, event: &Event) -> Result<Vec<Action>, String> {
+    let actions = Rc::new(RefCell::new(Vec::new()));
+    let engine = engine(event, &actions);
+    let ast = engine.compile(source).map_err(|e| e.to_string())?;
+    let mut scope = scope(event)?;
+    engine
+        .run_ast_with_scope(&mut scope, &ast)
+        .map_err(|e| e.to_string())?;
+    drop(engine);
+    Ok(actions.take())
+}
+
+fn perform(app: &AppHandle, script: &Script, action: &Action) -> Result<(), String> {
+    match action {
+        Action::Notify { title, body } => {
+            notifications::notify(
+                app,
+                NotificationRequest {
+                    title: title.clone(),
+                    body: body.clone(),
+                    category: Some("script".to_string()),
+                    ..Default::default()
+                },
+            );
+            Ok(())
+        }
+        Action::Post { integration, text } => integrations::send_message(integration, text),
+        Action::RenameWindow { target, name } => {
+            tmux::run(&["rename-window", "-t", target, name]).map(|_| ())
+        }
+        Action::Log { message } => {
+            println!("[Claude PM] Script {}: {}", script.name, message);
+            Ok(())
+        }
+    }
+}
+
+fn run(app: &AppHandle, script: &Script, event: &Event) -> ScriptRun {
+    let mut run = ScriptRun {
+        script_id: script.id.clone(),
+        ok: true,
+        actions: Vec::new(),
+        error: None,
+    };
+    let result = plan(&script.source, event).and_then(|actions| {
+        for action in &actions {
+            perform(app, script, action)?;
+            run.actions.push(action.clone());
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("[Claude PM] Script {} failed: {}", script.name, e);
+        run.ok = false;
+        run.error = Some(e);
+    }
+    run
+}
+
+/// Run the enabled scripts for `event` in the background
+pub fn run_for_event(app: &AppHandle, event: &Event) {
+    let scripts: Vec<Script> = with_scripts(|scripts| {
+        let matching = scripts
+            .iter()
+            .filter(|s| s.enabled && s.event == event.kind)
+            .cloned()
+            .collect();
+        (matching, false)
+    })
+    .unwrap_or_default();
+    if scripts.is_empty() {
+        return;
+    }
+    let app = app.clone();
+    let event = event.clone();
+    thread::spawn(move || {
+        for script in scripts {
+            let _ = app.emit("script-run", run(&app, &script, &event));
+        }
+    });
+}
+
+/// A stand-in event for dry runs
+fn sample_event(kind: EventKind) -> Event {
+    match kind {
+        EventKind::TaskCompleted => {
+            let task = store::list_tasks(None, Some("done".to_string()))
+                .ok()
+                .and_then(|tasks| tasks.into_iter().next());
+            match task {
+                Some(task) => Event::task_completed(&task),
+                None => Event {
+                    kind,
+                    title: "Task completed: Example task".to_string(),
+                    detail: "Example project".to_string(),
+                    data: json!({ "task": {
+                        "id": "example",
+                        "projectId": "example",
+                        "title": "Example task",
+                        "state": "done",
+                    }}),
+                },
+            }
+        }
+        EventKind::AgentBlocked => {
+            Event::agent_blocked("claudepm:1.0", "Allow Bash(npm test)? (y/n)")
+        }
+        EventKind::AgentFailed => Event {
+            kind,
+            title: "Agent failed: Example task".to_string(),
+            detail: "Claude exited with status 1".to_string(),
+            data: json!({ "run": {
+                "id": "example",
+                "taskId": "example",
+                "title": "Example task",
+                "state": "failed",
+                "error": "Claude exited with status 1",
+            }}),
+        },
+        EventKind::ServerCrashed => Event::server_crashed("exit status: 1"),
+    }
+}
+
+#[tauri::command]
+pub fn list_scripts() -> Result<Vec<Script>, Error> {
+    with_scripts(|scripts| (scripts.clone(), false)).map_err(Error::from)
+}
+
+/// Create a script (empty `id`) or replace an existing one; rejects scripts that don't compile
+#[tauri::command]
+pub fn save_script(mut script: Script) -> Result<Script, Error> {
+    if script.name.trim().is_empty() {
+        return Err(Error::InvalidInput("Script name is required".to_string()));
+    }
+    check(&script.source).map_err(Error::InvalidInput)?;
+    if script.id.is_empty() {
+        script.id = store::new_id();
+    }
+    with_scripts(|scripts| {
+        match scripts.iter_mut().find(|s| s.id == script.id) {
+            Some(existing) => *existing = script.clone(),
+            None => scripts.push(script.clone()),
+        }
+        (script, true)
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn delete_script(id: String) -> Result<(), Error> {
+    with_scripts(|scripts| (scripts.retain(|s| s.id != id), true)).map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn set_script_enabled(id: String, enabled: bool) -> Result<(), Error> {
+    with_scripts(|scripts| match scripts.iter_mut().find(|s| s.id == id) {
+        Some(script) => {
+            script.enabled = enabled;
+            (Ok(()), true)
+        }
+        None => (
+            Err(Error::NotFound(format!("Script not found: {}", id))),
+            false,
+        ),
+    })?
+}
+
+/// Evaluate `source` against a sample `event` and return the actions it would take
+#[tauri::command]
+pub fn dry_run_script(source: String, event: EventKind) -> Result<Vec<Action>, Error> {
+    plan(&source, &sample_event(event)).map_err(Error::InvalidInput)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocked() -> Event {
+        Event::agent_blocked("claudepm:1.0", "Allow Bash(npm test)? (y/n)")
+    }
+
+    #[test]
+    fn records_actions_without_running_them() {
+        let source = r#"
+            rename_window(`blocked: ${event.kind}`);
+            if target == "claudepm:1.0" {
+                post("Team Slack", `Needs input: ${prompt}`);
+            } else {
+                notify("never", "sent");
+            }
+            print("done");
+        "#;
+        let actions = plan(source, &blocked()).unwrap();
+        let actions = serde_json::to_value(actions).unwrap();
+        assert_eq!(
+            actions,
+            json!([
+                { "type": "renameWindow", "target": "claudepm:1.0", "name": "blocked: agentBlocked" },
+                { "type": "post", "integration": "Team Slack", "text": "Needs input: Allow Bash(npm test)? (y/n)" },
+                { "type": "log", "message": "done" },
+            ])
+        );
+    }
+
+    #[test]
+    fn event_values_are_constants() {
+        assert!(plan(r#"target = "other";"#, &blocked()).is_err());
+        assert!(plan("log(missing);", &blocked()).is_err());
+    }
+
+    #[test]
+    fn sandbox_stops_runaway_and_escaping_scripts() {
+        assert!(plan("loop {}", &blocked()).is_err());
+        assert!(plan(r#"let s = "x"; loop { s += s; }"#, &blocked()).is_err());
+        assert!(check(r#"eval("1")"#).is_err());
+        assert!(plan(r#"import "std" as std;"#, &blocked()).is_err());
+    }
+}
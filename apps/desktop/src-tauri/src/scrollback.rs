@@ -0,0 +1,250 @@
+//! Saving a tmux pane's whole scrollback on its task
+//!
+//! The history is captured with its colours (`capture-pane -e -S -`) and stored twice in
+//! the attachment store: as plain text with the escape codes stripped, for search and
+//! previews, and as a standalone HTML page that keeps the colours, for reading an
+//! agent's final output the way it looked in the terminal.
+
+use std::fs;
+
+use crate::attachments::{self, Attachment};
+use crate::error::Error;
+use crate::{recording, tmux};
+
+/// The 16 basic terminal colours (normal then bright), xterm defaults
+const PALETTE: [&str; 16] = [
+    "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+    "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+];
+
+#[derive(Clone, Default, PartialEq)]
+struct Style {
+    fg: Option<String>,
+    bg: Option<String>,
+    bold: bool,
+    dim: bool,
+    italic: bool,
+    underline: bool,
+}
+
+impl Style {
+    fn css(&self) -> String {
+        let mut css = Vec::new();
+        if let Some(fg) = &self.fg {
+            css.push(format!("color:{}", fg));
+        }
+        if let Some(bg) = &self.bg {
+            css.push(format!("background:{}", bg));
+        }
+        if self.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        if self.dim {
+            css.push("opacity:0.6".to_string());
+        }
+        if self.italic {
+            css.push("font-style:italic".to_string());
+        }
+        if self.underline {
+            css.push("text-decoration:underline".to_string());
+        }
+        css.join(";")
+    }
+}
+
+/// xterm 256-colour index to a CSS colour
+fn color_256(n: u16) -> String {
+    match n {
+        0..=15 => PALETTE[n as usize].to_string(),
+        16..=231 => {
+            let n = n - 16;
+            let level = |v: u16| if v == 0 { 0 } else { 55 + v * 40 };
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(n / 36),
+                level((n / 6) % 6),
+                level(n % 6)
+            )
+        }
+        _ => {
+            let v = 8 + (n.min(255) - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", v, v, v)
+        }
+    }
+}
+
+/// Apply an SGR (`ESC [ … m`) parameter list
+fn apply_sgr(style: &mut Style, params: &str) {
+    let codes: Vec<u16> = params.split(';').map(|p| p.parse().unwrap_or(0)).collect();
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 => *style = Style::default(),
+            1 => style.bold = true,
+            2 => style.dim = true,
+            3 => style.italic = true,
+            4 => style.underline = true,
+            22 => {
+                style.bold = false;
+                style.dim = false;
+            }
+            23 => style.italic = false,
+            24 => style.underline = false,
+            c @ 30..=37 => style.fg = Some(PALETTE[(c - 30) as usize].to_string()),
+            c @ 90..=97 => style.fg = Some(PALETTE[(c - 90 + 8) as usize].to_string()),
+            39 => style.fg = None,
+            c @ 40..=47 => style.bg = Some(PALETTE[(c - 40) as usize].to_string()),
+            c @ 100..=107 => style.bg = Some(PALETTE[(c - 100 + 8) as usize].to_string()),
+            49 => style.bg = None,
+            c @ (38 | 48) => {
+                let color = match codes.get(i + 1) {
+                    Some(5) => {
+                        i += 2;
+                        codes.get(i).map(|&n| color_256(n))
+                    }
+                    Some(2) => {
+                        i += 4;
+                        match (codes.get(i - 2), codes.get(i - 1), codes.get(i)) {
+                            (Some(r), Some(g), Some(b)) => {
+                                Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                if c == 38 {
+                    style.fg = color;
+                } else {
+                    style.bg = color;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Split terminal output into plain text and coloured HTML
+fn render(raw: &str) -> (String, String) {
+    let mut plain = String::with_capacity(raw.len());
+    let mut html = String::with_capacity(raw.len() * 2);
+    let mut style = Style::default();
+    let mut open = false;
+    let mut run = String::new();
+    let flush = |run: &mut String, html: &mut String| {
+        html.push_str(&escape_html(run));
+        run.clear();
+    };
+
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            if c != '\r' {
+                plain.push(c);
+                run.push(c);
+            }
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters, then a final byte in @..~
+            Some('[') => {
+                let mut params = String::new();
+                let mut end = None;
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        end = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if end == Some('m') {
+                    let mut next = style.clone();
+                    apply_sgr(&mut next, &params);
+                    if next != style {
+                        flush(&mut run, &mut html);
+                        if open {
+                            html.push_str("</span>");
+                            open = false;
+                        }
+                        let css = next.css();
+                        if !css.is_empty() {
+                            html.push_str(&format!("<span style=\"{}\">", css));
+                            open = true;
+                        }
+                        style = next;
+                    }
+                }
+            }
+            // OSC (titles, hyperlinks): up to BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    flush(&mut run, &mut html);
+    if open {
+        html.push_str("</span>");
+    }
+    (plain, html)
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>body{{margin:0;background:#1e1e1e;color:#e5e5e5}}pre{{margin:0;padding:16px;font:13px/1.4 ui-monospace,Menlo,Consolas,monospace;white-space:pre-wrap}}</style>\n</head>\n<body><pre>{}</pre></body>\n</html>\n",
+        escape_html(title),
+        body
+    )
+}
+
+/// Capture pane `target`'s full scrollback onto `task_id` (the pane's task when unset);
+/// returns the text and HTML attachments
+#[tauri::command]
+pub async fn capture_pane_scrollback(
+    target: String,
+    task_id: Option<String>,
+) -> Result<Vec<Attachment>, Error> {
+    tauri::async_runtime::spawn_blocking(move || {
+        // -J joins wrapped lines so the text reflows
+        let raw = tmux::run(&["capture-pane", "-p", "-e", "-J", "-S", "-", "-t", &target])?;
+        let (plain, html) = render(raw.trim_end());
+        let task_id = task_id.or_else(|| recording::task_for_pane(&target));
+        let stamp = chrono::Local::now().format("%Y-%m-%d-%H%M%S");
+
+        let text_path = attachments::new_path(&format!("scrollback-{}.txt", stamp))?;
+        fs::write(&text_path, format!("{}\n", plain))
+            .map_err(|e| format!("Failed to write scrollback: {}", e))?;
+        let html_path = attachments::new_path(&format!("scrollback-{}.html", stamp))?;
+        fs::write(
+            &html_path,
+            page(&format!("Scrollback of {}", target), &html),
+        )
+        .map_err(|e| format!("Failed to write scrollback: {}", e))?;
+
+        let text = attachments::add(task_id.as_deref(), &text_path, "text/plain")?;
+        let html = attachments::add(task_id.as_deref(), &html_path, "text/html")?;
+        println!(
+            "[Claude PM] Saved scrollback of {} ({} lines)",
+            target,
+            plain.lines().count()
+        );
+        Ok(vec![text, html])
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,841 @@
+//! Full-text search over tasks, task comments, attachment text and Claude transcripts
+//!
+//! The index is a tantivy index in `search-index/` under the data directory, ranked with
+//! bm25 over stemmed English text. Store triggers queue every task, comment and
+//! attachment change in `search_pending`; a background thread (and every search, so
+//! results never lag a write) applies the queue and clears what it committed. A missing
+//! or unreadable index is recreated and everything is queued again.
+//!
+//! Claude Code transcripts (`~/.claude/projects/<project>/<session>.jsonl`) are
+//! re-indexed by the same thread whenever a file's mtime changes, and dropped from the
+//! index once their file is gone.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::{BooleanQuery, BoostQuery, FuzzyTermQuery, Occur, Query, TermQuery};
+use tantivy::schema::{
+    Field, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, Value as _, STORED, STRING,
+};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::tokenizer::{Language, LowerCaser, RemoveLongFilter, SimpleTokenizer, Stemmer};
+use tantivy::tokenizer::{TextAnalyzer, TokenStream};
+use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+use crate::error::Error;
+use crate::{config, power, store};
+
+pub const INDEX_DIR: &str = "search-index";
+const SCAN_INTERVAL: Duration = Duration::from_secs(60);
+/// How often the background thread applies queued store changes
+const PENDING_INTERVAL: Duration = Duration::from_secs(2);
+const PENDING_BATCH: i64 = 500;
+const WRITER_MEMORY: usize = 50_000_000;
+const DEFAULT_LIMIT: usize = 50;
+/// Very long transcripts are truncated; the start of a session says most about it
+const MAX_TRANSCRIPT_CHARS: usize = 200_000;
+const TITLE_CHARS: usize = 120;
+const SNIPPET_CHARS: usize = 160;
+const TITLE_BOOST: f32 = 4.0;
+/// Stemmed English, used for titles and bodies
+const TOKENIZER: &str = "en_stem";
+/// Lowercased but unstemmed, used for matching the term being typed as a prefix
+const PREFIX_TOKENIZER: &str = "default";
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static INDEX: Mutex<Option<SearchIndex>> = Mutex::new(None);
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SearchFilters {
+    /// `task`, `comment`, `attachment` and/or `transcript`; all when empty
+    pub kinds: Vec<String>,
+    pub project_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResult {
+    pub kind: String,
+    /// Task id, comment id, attachment id or transcript session id
+    pub ref_id: String,
+    pub project_id: Option<String>,
+    pub title: String,
+    /// Matching excerpt, HTML-escaped, with hits wrapped in `<mark>`
+    pub snippet: String,
+    /// bm25 score; higher is better
+    pub score: f64,
+}
+
+#[derive(Clone, Copy)]
+struct Fields {
+    /// `<kind>:<ref_id>`, so one term deletes an entry
+    key: Field,
+    kind: Field,
+    ref_id: Field,
+    project_id: Field,
+    title: Field,
+    body: Field,
+}
+
+struct SearchIndex {
+    /// Where it lives on disk; `None` for the in-memory index tests use
+    dir: Option<PathBuf>,
+    index: Index,
+    reader: IndexReader,
+    writer: IndexWriter,
+    fields: Fields,
+}
+
+/// One indexed item
+struct Entry {
+    kind: &'static str,
+    ref_id: String,
+    project_id: Option<String>,
+    title: String,
+    body: String,
+}
+
+fn schema() -> (Schema, Fields) {
+    let text = TextOptions::default()
+        .set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(TOKENIZER)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        )
+        .set_stored();
+    let mut builder = Schema::builder();
+    let fields = Fields {
+        key: builder.add_text_field("key", STRING),
+        kind: builder.add_text_field("kind", STRING | STORED),
+        ref_id: builder.add_text_field("ref_id", STRING | STORED),
+        project_id: builder.add_text_field("project_id", STRING | STORED),
+        title: builder.add_text_field("title", text.clone()),
+        body: builder.add_text_field("body", text),
+    };
+    (builder.build(), fields)
+}
+
+fn key(kind: &str, ref_id: &str) -> String {
+    format!("{}:{}", kind, ref_id)
+}
+
+impl SearchIndex {
+    fn new(dir: Option<PathBuf>, index: Index, fields: Fields) -> tantivy::Result<Self> {
+        index.tokenizers().register(
+            TOKENIZER,
+            TextAnalyzer::builder(SimpleTokenizer::default())
+                .filter(RemoveLongFilter::limit(40))
+                .filter(LowerCaser)
+                .filter(Stemmer::new(Language::English))
+                .build(),
+        );
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let writer = index.writer(WRITER_MEMORY)?;
+        Ok(Self {
+            dir,
+            index,
+            reader,
+            writer,
+            fields,
+        })
+    }
+
+    #[cfg(test)]
+    fn in_memory() -> Self {
+        let (schema, fields) = schema();
+        Self::new(None, Index::create_in_ram(schema), fields).unwrap()
+    }
+
+    /// Open the index in `dir`, recreating it if it's unreadable; the flag says whether
+    /// it started out empty and needs everything indexed again
+    fn open(dir: &Path) -> Result<(Self, bool), String> {
+        let (schema, fields) = schema();
+        let fresh = !dir.join("meta.json").exists();
+        let open = || -> tantivy::Result<Index> {
+            fs::create_dir_all(dir)?;
+            Index::open_or_create(MmapDirectory::open(dir)?, schema.clone())
+        };
+        let (index, fresh) = match open() {
+            Ok(index) => (index, fresh),
+            Err(e) => {
+                eprintln!("[Claude PM] Recreating the search index: {}", e);
+                let _ = fs::remove_dir_all(dir);
+                (open().map_err(|e| e.to_string())?, true)
+            }
+        };
+        let index = Self::new(Some(dir.to_path_buf()), index, fields).map_err(|e| e.to_string())?;
+        Ok((index, fresh))
+    }
+
+    /// Replace whatever is indexed under `kind`/`ref_id` with `entry`, or remove it
+    fn put(&mut self, kind: &str, ref_id: &str, entry: Option<Entry>) -> tantivy::Result<()> {
+        let fields = self.fields;
+        self.writer
+            .delete_term(Term::from_field_text(fields.key, &key(kind, ref_id)));
+        let Some(entry) = entry else {
+            return Ok(());
+        };
+        let mut doc = TantivyDocument::default();
+        doc.add_text(fields.key, key(entry.kind, &entry.ref_id));
+        doc.add_text(fields.kind, entry.kind);
+        doc.add_text(fields.ref_id, &entry.ref_id);
+        if let Some(ref project_id) = entry.project_id {
+            doc.add_text(fields.project_id, project_id);
+        }
+        doc.add_text(fields.title, &entry.title);
+        doc.add_text(fields.body, &entry.body);
+        self.writer.add_document(doc)?;
+        Ok(())
+    }
+
+    fn commit(&mut self) -> tantivy::Result<()> {
+        self.writer.commit()?;
+        self.reader.reload()
+    }
+
+    fn tokens(&self, tokenizer: &str, text: &str) -> Vec<String> {
+        let Some(mut analyzer) = self.index.tokenizers().get(tokenizer) else {
+            return Vec::new();
+        };
+        let mut tokens = Vec::new();
+        let mut stream = analyzer.token_stream(text);
+        while stream.advance() {
+            tokens.push(stream.token().text.clone());
+        }
+        tokens
+    }
+
+    /// `token` in the title (weighted up) or the body
+    fn either_field(&self, token: &str, prefix: bool) -> Box<dyn Query> {
+        let fields = self.fields;
+        let clause = |field: Field| -> Box<dyn Query> {
+            let term = Term::from_field_text(field, token);
+            if prefix {
+                Box::new(FuzzyTermQuery::new_prefix(term, 0, false))
+            } else {
+                Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs))
+            }
+        };
+        Box::new(BooleanQuery::new(vec![
+            (
+                Occur::Should,
+                Box::new(BoostQuery::new(clause(fields.title), TITLE_BOOST)),
+            ),
+            (Occur::Should, clause(fields.body)),
+        ]))
+    }
+
+    /// Every word must match; the last also matches as a prefix, for search-as-you-type
+    fn query(&self, text: &str, filters: &SearchFilters) -> Option<Box<dyn Query>> {
+        let fields = self.fields;
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        for (i, word) in words.iter().enumerate() {
+            let stemmed = self.tokens(TOKENIZER, word);
+            if i + 1 < words.len() {
+                for token in stemmed {
+                    clauses.push((Occur::Must, self.either_field(&token, false)));
+                }
+                continue;
+            }
+            let mut any: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+            if let Some(token) = self.tokens(PREFIX_TOKENIZER, word).last() {
+                any.push((Occur::Should, self.either_field(token, true)));
+            }
+            if !stemmed.is_empty() {
+                let all = stemmed
+                    .iter()
+                    .map(|token| (Occur::Must, self.either_field(token, false)))
+                    .collect();
+                any.push((Occur::Should, Box::new(BooleanQuery::new(all))));
+            }
+            if !any.is_empty() {
+                clauses.push((Occur::Must, Box::new(BooleanQuery::new(any))));
+            }
+        }
+        if clauses.is_empty() {
+            return None;
+        }
+        if !filters.kinds.is_empty() {
+            let kinds = filters
+                .kinds
+                .iter()
+                .map(|kind| -> (Occur, Box<dyn Query>) {
+                    let term = Term::from_field_text(fields.kind, kind);
+                    (
+                        Occur::Should,
+                        Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
+                    )
+                })
+                .collect();
+            clauses.push((Occur::Must, Box::new(BooleanQuery::new(kinds))));
+        }
+        if let Some(ref project_id) = filters.project_id {
+            let term = Term::from_field_text(fields.project_id, project_id);
+            clauses.push((
+                Occur::Must,
+                Box::new(TermQuery::new(term, IndexRecordOption::Basic)),
+            ));
+        }
+        Some(Box::new(BooleanQuery::new(clauses)))
+    }
+
+    fn search(&self, text: &str, filters: &SearchFilters) -> tantivy::Result<Vec<SearchResult>> {
+        let Some(query) = self.query(text, filters) else {
+            return Ok(Vec::new());
+        };
+        let limit = filters.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, 500);
+        let searcher = self.reader.searcher();
+        let hits = searcher.search(&query, &TopDocs::with_limit(limit).order_by_score())?;
+        let mut snippets = SnippetGenerator::create(&searcher, &query, self.fields.body)?;
+        snippets.set_max_num_chars(SNIPPET_CHARS);
+        let fields = self.fields;
+        hits.into_iter()
+            .map(|(score, address)| {
+                let doc: TantivyDocument = searcher.doc(address)?;
+                let text = |field: Field| {
+                    doc.get_first(field)
+                        .and_then(|value| value.as_str())
+                        .map(str::to_string)
+                };
+                let mut snippet = snippets.snippet_from_doc(&doc);
+                snippet.set_snippet_prefix_postfix("<mark>", "</mark>");
+                let snippet = if snippet.is_empty() {
+                    escape(&text(fields.body).unwrap_or_default())
+                        .chars()
+                        .take(SNIPPET_CHARS)
+                        .collect()
+                } else {
+                    snippet.to_html()
+                };
+                Ok(SearchResult {
+                    kind: text(fields.kind).unwrap_or_default(),
+                    ref_id: text(fields.ref_id).unwrap_or_default(),
+                    project_id: text(fields.project_id),
+                    title: text(fields.title).unwrap_or_default(),
+                    snippet,
+                    score: score as f64,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Escape text for the snippet field, which is otherwise HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn index_dir() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join(INDEX_DIR))
+}
+
+/// Queue every task, comment and attachment, and forget which transcripts are indexed
+fn queue_everything(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "INSERT OR REPLACE INTO search_pending (kind, ref_id) SELECT 'task', id FROM tasks;
+         INSERT OR REPLACE INTO search_pending (kind, ref_id) SELECT 'comment', id FROM task_comments;
+         INSERT OR REPLACE INTO search_pending (kind, ref_id) SELECT 'attachment', id FROM attachments;
+         DELETE FROM search_sources;",
+    )
+}
+
+/// Run `f` on the index, opening it on first use and again after the data directory moves
+fn with_index<T>(f: impl FnOnce(&mut SearchIndex) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = INDEX.lock().map_err(|e| e.to_string())?;
+    let dir = index_dir().ok_or("Could not determine data directory")?;
+    if guard
+        .as_ref()
+        .is_none_or(|index| index.dir.as_ref() != Some(&dir))
+    {
+        // The old writer has to let go of its lock before another opens
+        *guard = None;
+        let (index, fresh) = SearchIndex::open(&dir)?;
+        if fresh {
+            store::with_conn(queue_everything)?;
+        }
+        *guard = Some(index);
+    }
+    f(guard.as_mut().ok_or("Search index not open")?)
+}
+
+pub fn transcripts_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".claude").join("projects"))
+}
+
+/// Claude names project directories after the cwd with separators replaced by `-`
+pub fn project_for_transcript_dir(conn: &Connection, dir_name: &str) -> Option<String> {
+    let mut stmt = conn
+        .prepare("SELECT id, repo_path FROM projects WHERE repo_path IS NOT NULL")
+        .ok()?;
+    let projects: Vec<(String, String)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .ok()?
+        .flatten()
+        .collect();
+    projects
+        .into_iter()
+        .find(|(_, repo)| repo.replace(['/', '.'], "-") == dir_name)
+        .map(|(id, _)| id)
+}
+
+pub fn text_of(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter(|part| part["type"] == "text")
+            .filter_map(|part| part["text"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// (first user message, all user/assistant text)
+fn parse_transcript(path: &Path) -> Option<(String, String)> {
+    let contents = fs::read_to_string(path).ok()?;
+    let mut title = None;
+    let mut body = String::new();
+    for line in contents.lines() {
+        let Ok(entry) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let kind = entry["type"].as_str().unwrap_or_default();
+        if kind != "user" && kind != "assistant" {
+            continue;
+        }
+        let text = text_of(&entry["message"]["content"]);
+        if text.trim().is_empty() {
+            continue;
+        }
+        if kind == "user" && title.is_none() {
+            title = Some(text.chars().take(TITLE_CHARS).collect::<String>());
+        }
+        body.push_str(&text);
+        body.push('\n');
+        if body.len() > MAX_TRANSCRIPT_CHARS {
+            break;
+        }
+    }
+    Some((title.unwrap_or_default(), body))
+}
+
+fn mtime(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64)
+}
+
+fn session_id(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// The current row behind a queued change; `None` once it's gone
+fn load(conn: &Connection, kind: &str, ref_id: &str) -> rusqlite::Result<Option<Entry>> {
+    let (kind, sql) = match kind {
+        "task" => (
+            "task",
+            "SELECT id, project_id, title, COALESCE(description, '') FROM tasks WHERE id = ?1",
+        ),
+        "comment" => (
+            "comment",
+            "SELECT c.id, t.project_id, t.title, c.body
+             FROM task_comments c JOIN tasks t ON t.id = c.task_id WHERE c.id = ?1",
+        ),
+        "attachment" => (
+            "attachment",
+            "SELECT a.id, t.project_id, a.file_name, a.preview
+             FROM attachments a LEFT JOIN tasks t ON t.id = a.task_id
+             WHERE a.id = ?1 AND a.preview IS NOT NULL",
+        ),
+        _ => return Ok(None),
+    };
+    conn.query_row(sql, [ref_id], |row| {
+        Ok(Entry {
+            kind,
+            ref_id: row.get(0)?,
+            project_id: row.get(1)?,
+            title: row.get(2)?,
+            body: row.get(3)?,
+        })
+    })
+    .optional()
+}
+
+/// Index one batch of queued store changes; returns how many were applied
+fn apply_pending_batch(index: &mut SearchIndex, conn: &Connection) -> Result<usize, String> {
+    let db = |e: rusqlite::Error| format!("Database error: {}", e);
+    let mut stmt = conn
+        .prepare("SELECT rowid, kind, ref_id FROM search_pending ORDER BY rowid LIMIT ?1")
+        .map_err(db)?;
+    let pending: Vec<(i64, String, String)> = stmt
+        .query_map([PENDING_BATCH], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .and_then(|rows| rows.collect())
+        .map_err(db)?;
+    if pending.is_empty() {
+        return Ok(0);
+    }
+    for (_, kind, ref_id) in &pending {
+        let entry = load(conn, kind, ref_id).map_err(db)?;
+        index.put(kind, ref_id, entry).map_err(|e| e.to_string())?;
+    }
+    index.commit().map_err(|e| e.to_string())?;
+    // A row re-queued meanwhile got a new rowid and stays for the next batch
+    for (rowid, _, _) in &pending {
+        conn.execute("DELETE FROM search_pending WHERE rowid = ?1", [rowid])
+            .map_err(db)?;
+    }
+    Ok(pending.len())
+}
+
+/// Bring the index up to date with the store; returns how many changes were applied
+fn apply_pending() -> Result<usize, String> {
+    with_index(|index| {
+        let mut applied = 0;
+        loop {
+            let batch = store::with_conn(|conn| Ok(apply_pending_batch(index, conn)))??;
+            if batch == 0 {
+                return Ok(applied);
+            }
+            applied += batch;
+        }
+    })
+}
+
+fn index_transcript(
+    index: &mut SearchIndex,
+    conn: &Connection,
+    path: &Path,
+) -> tantivy::Result<()> {
+    let session_id = session_id(path);
+    let project_id = path
+        .parent()
+        .and_then(|dir| dir.file_name())
+        .and_then(|name| project_for_transcript_dir(conn, &name.to_string_lossy()));
+    let entry = parse_transcript(path).map(|(title, body)| Entry {
+        kind: "transcript",
+        ref_id: session_id.clone(),
+        project_id,
+        title,
+        body,
+    });
+    index.put("transcript", &session_id, entry)
+}
+
+/// Drop indexed transcripts whose files have been deleted; returns how many
+fn prune_transcripts(index: &mut SearchIndex) -> Result<usize, String> {
+    let gone: Vec<String> = store::with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT path FROM search_sources")?;
+        let paths = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(paths)
+    })?
+    .into_iter()
+    .filter(|path| !Path::new(path).exists())
+    .collect();
+    if gone.is_empty() {
+        return Ok(0);
+    }
+    for path in &gone {
+        index
+            .put("transcript", &session_id(Path::new(path)), None)
+            .map_err(|e| e.to_string())?;
+    }
+    index.commit().map_err(|e| e.to_string())?;
+    store::with_conn(|conn| {
+        for path in &gone {
+            conn.execute("DELETE FROM search_sources WHERE path = ?1", [path])?;
+        }
+        Ok(())
+    })?;
+    Ok(gone.len())
+}
+
+/// Index transcripts that are new or changed since the last scan and drop those that
+/// were deleted; returns how many changed
+fn scan_transcripts() -> Result<usize, String> {
+    let pruned = with_index(prune_transcripts)?;
+    let Some(root) = transcripts_dir().filter(|dir| dir.is_dir()) else {
+        return Ok(pruned);
+    };
+    let files: Vec<PathBuf> = fs::read_dir(&root)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .filter_map(|project| fs::read_dir(project.path()).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect();
+
+    let mut changed = Vec::new();
+    for path in files {
+        let Some(modified) = mtime(&path) else {
+            continue;
+        };
+        let known: Option<i64> = store::with_conn(|conn| {
+            conn.query_row(
+                "SELECT mtime FROM search_sources WHERE path = ?1",
+                [path.to_string_lossy()],
+                |row| row.get(0),
+            )
+            .optional()
+        })?;
+        if known != Some(modified) {
+            changed.push((path, modified));
+        }
+    }
+    // A few files per commit so searches aren't blocked behind a full re-index
+    for batch in changed.chunks(20) {
+        with_index(|index| {
+            for (path, _) in batch {
+                store::with_conn(|conn| Ok(index_transcript(index, conn, path)))?
+                    .map_err(|e| e.to_string())?;
+            }
+            index.commit().map_err(|e| e.to_string())
+        })?;
+        store::with_conn(|conn| {
+            for (path, modified) in batch {
+                conn.execute(
+                    "INSERT OR REPLACE INTO search_sources (path, mtime) VALUES (?1, ?2)",
+                    params![path.to_string_lossy(), modified],
+                )?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(pruned + changed.len())
+}
+
+/// Remove everything indexed for a project the store no longer has
+pub fn forget_project(project_id: &str) -> Result<(), String> {
+    with_index(|index| {
+        let term = Term::from_field_text(index.fields.project_id, project_id);
+        index.writer.delete_term(term);
+        index.commit().map_err(|e| e.to_string())
+    })
+}
+
+/// Start over from the store, e.g. after a backup replaced the database
+pub fn rebuild() -> Result<(), String> {
+    with_index(|index| {
+        index
+            .writer
+            .delete_all_documents()
+            .map_err(|e| e.to_string())?;
+        index.commit().map_err(|e| e.to_string())?;
+        store::with_conn(queue_everything)
+    })
+}
+
+pub fn start() {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(|| {
+        let mut last_scan: Option<Instant> = None;
+        loop {
+            if let Err(e) = apply_pending() {
+                eprintln!("[Claude PM] Search indexing failed: {}", e);
+            }
+            // Transcripts can catch up once we're back on AC
+            if !power::is_throttled() && last_scan.is_none_or(|at| at.elapsed() >= SCAN_INTERVAL) {
+                match scan_transcripts() {
+                    Ok(0) => {}
+                    Ok(count) => println!("[Claude PM] Re-indexed {} transcript(s)", count),
+                    Err(e) => eprintln!("[Claude PM] Transcript indexing failed: {}", e),
+                }
+                last_scan = Some(Instant::now());
+            }
+            power::wait(PENDING_INTERVAL);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn search(query: String, filters: Option<SearchFilters>) -> Result<Vec<SearchResult>, Error> {
+    let filters = filters.unwrap_or_default();
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    apply_pending()?;
+    with_index(|index| index.search(&query, &filters).map_err(|e| e.to_string()))
+        .map_err(Error::from)
+}
+
+/// Re-scan transcripts now instead of waiting for the next pass
+#[tauri::command]
+pub async fn reindex_transcripts() -> Result<usize, Error> {
+    tauri::async_runtime::spawn_blocking(scan_transcripts)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: &'static str, ref_id: &str, project_id: &str, title: &str, body: &str) -> Entry {
+        Entry {
+            kind,
+            ref_id: ref_id.to_string(),
+            project_id: Some(project_id.to_string()),
+            title: title.to_string(),
+            body: body.to_string(),
+        }
+    }
+
+    fn indexed(entries: Vec<Entry>) -> SearchIndex {
+        let mut index = SearchIndex::in_memory();
+        for entry in entries {
+            let (kind, ref_id) = (entry.kind, entry.ref_id.clone());
+            index.put(kind, &ref_id, Some(entry)).unwrap();
+        }
+        index.commit().unwrap();
+        index
+    }
+
+    fn ids(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.ref_id.as_str()).collect()
+    }
+
+    #[test]
+    fn matches_stems_and_the_word_being_typed() {
+        let index = indexed(vec![
+            entry(
+                "task",
+                "t1",
+                "p1",
+                "Deploy the API",
+                "Roll out <v2> to staging",
+            ),
+            entry(
+                "task",
+                "t2",
+                "p1",
+                "Write docs",
+                "Explain deployments & rollbacks",
+            ),
+            entry(
+                "comment",
+                "c1",
+                "p2",
+                "Fix login",
+                "Login fails after deploying",
+            ),
+        ]);
+        let all = SearchFilters::default();
+        let mut hits = ids(&index.search("deployed", &all).unwrap())
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        hits.sort();
+        assert_eq!(hits, ["c1", "t1", "t2"]);
+        // Title hits outrank body hits
+        assert_eq!(index.search("deploy", &all).unwrap()[0].ref_id, "t1");
+        assert_eq!(ids(&index.search("roll stag", &all).unwrap()), ["t1"]);
+        assert!(index.search("roll nothing", &all).unwrap().is_empty());
+        assert!(index.search("  ", &all).unwrap().is_empty());
+
+        let snippet = &index.search("rollbacks", &all).unwrap()[0].snippet;
+        assert!(snippet.contains("<mark>rollbacks</mark>"));
+        assert!(snippet.contains("&amp;"));
+    }
+
+    #[test]
+    fn filters_by_kind_and_project() {
+        let index = indexed(vec![
+            entry("task", "t1", "p1", "Cache warmup", ""),
+            entry("comment", "c1", "p1", "Cache warmup", "Warmup is slow"),
+            entry("transcript", "s1", "p2", "Why is warmup slow", "warmup"),
+        ]);
+        let kinds = SearchFilters {
+            kinds: vec!["comment".to_string(), "transcript".to_string()],
+            ..Default::default()
+        };
+        let mut hits = ids(&index.search("warmup", &kinds).unwrap())
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        hits.sort();
+        assert_eq!(hits, ["c1", "s1"]);
+        let project = SearchFilters {
+            project_id: Some("p2".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(&index.search("warmup", &project).unwrap()), ["s1"]);
+    }
+
+    #[test]
+    fn replacing_an_entry_drops_the_old_text() {
+        let mut index = indexed(vec![entry("task", "t1", "p1", "Old title", "")]);
+        index
+            .put(
+                "task",
+                "t1",
+                Some(entry("task", "t1", "p1", "New title", "")),
+            )
+            .unwrap();
+        index.commit().unwrap();
+        let all = SearchFilters::default();
+        assert!(index.search("old", &all).unwrap().is_empty());
+        assert_eq!(ids(&index.search("new", &all).unwrap()), ["t1"]);
+
+        index.put("task", "t1", None).unwrap();
+        index.commit().unwrap();
+        assert!(index.search("new", &all).unwrap().is_empty());
+    }
+
+    #[test]
+    fn store_changes_are_queued_and_applied() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        store::migrate(&mut conn).unwrap();
+        conn.execute_batch(
+            "PRAGMA foreign_keys = ON;
+             INSERT INTO projects (id, name, created_at, updated_at) VALUES ('p1', 'Apollo', 0, 0);
+             INSERT INTO tasks (id, project_id, title, state, created_at, updated_at)
+                 VALUES ('t1', 'p1', 'Launch checklist', 'backlog', 0, 0);
+             INSERT INTO task_comments (id, task_id, body, created_at)
+                 VALUES ('c1', 't1', 'Fuel the rocket', 0);",
+        )
+        .unwrap();
+        let mut index = SearchIndex::in_memory();
+        assert_eq!(apply_pending_batch(&mut index, &conn).unwrap(), 2);
+        let all = SearchFilters::default();
+        let hits = index.search("rocket", &all).unwrap();
+        assert_eq!(ids(&hits), ["c1"]);
+        assert_eq!(hits[0].title, "Launch checklist");
+
+        // Renaming the task re-indexes its comments under the new title
+        conn.execute("UPDATE tasks SET title = 'Liftoff' WHERE id = 't1'", [])
+            .unwrap();
+        assert_eq!(apply_pending_batch(&mut index, &conn).unwrap(), 2);
+        assert_eq!(index.search("rocket", &all).unwrap()[0].title, "Liftoff");
+
+        conn.execute("DELETE FROM tasks WHERE id = 't1'", [])
+            .unwrap();
+        apply_pending_batch(&mut index, &conn).unwrap();
+        assert!(index.search("liftoff", &all).unwrap().is_empty());
+        assert!(index.search("rocket", &all).unwrap().is_empty());
+        assert_eq!(apply_pending_batch(&mut index, &conn).unwrap(), 0);
+    }
+}
@@ -0,0 +1,128 @@
+//! Keychain entries, hex encoding and ChaCha20-Poly1305 sealing shared by every module
+//! that keeps a secret
+//!
+//! All entries live under one keychain service, one account per secret. Sealed blobs are
+//! the 12-byte nonce followed by the ciphertext; keys are stored base64-encoded.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+const KEYCHAIN_SERVICE: &str = "com.claudepm.desktop";
+pub const NONCE_LEN: usize = 12;
+
+fn entry(account: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, account)
+        .map_err(|e| format!("Keychain unavailable: {}", e))
+}
+
+/// The secret stored as `account`; `None` if there isn't one
+pub fn get(account: &str) -> Result<Option<String>, String> {
+    match entry(account)?.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!(
+            "Failed to read {} from the keychain: {}",
+            account, e
+        )),
+    }
+}
+
+pub fn set(account: &str, value: &str) -> Result<(), String> {
+    entry(account)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store {} in the keychain: {}", account, e))
+}
+
+/// Remove `account`; one that was never stored counts as removed
+pub fn delete(account: &str) -> Result<(), String> {
+    match entry(account)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!(
+            "Failed to remove {} from the keychain: {}",
+            account, e
+        )),
+    }
+}
+
+/// Lowercase hex, two digits per byte
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A fresh random key, base64-encoded for the keychain
+pub fn generate_key() -> String {
+    BASE64.encode(ChaCha20Poly1305::generate_key(&mut OsRng))
+}
+
+/// The cipher for a base64-encoded key
+pub fn cipher(encoded: &str) -> Result<ChaCha20Poly1305, String> {
+    let key = BASE64
+        .decode(encoded.trim())
+        .map_err(|e| format!("Invalid key: {}", e))?;
+    if key.len() != 32 {
+        return Err("Invalid key length".to_string());
+    }
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+/// Encrypt `plaintext` under a random nonce, binding `aad` into the tag
+pub fn seal(cipher: &ChaCha20Poly1305, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| "Encryption failed".to_string())?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+/// Reverse `seal`; fails on a different key, different `aad` or any tampering
+pub fn open(cipher: &ChaCha20Poly1305, aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN {
+        return Err("Sealed data is truncated".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| "Decryption failed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_pads_each_byte() {
+        assert_eq!(hex(&[0x00, 0x0f, 0xa0, 0xff]), "000fa0ff");
+    }
+
+    #[test]
+    fn sealed_data_opens_only_with_the_same_key_and_aad() {
+        let cipher = super::cipher(&generate_key()).unwrap();
+        let sealed = seal(&cipher, b"alpha", b"secret").unwrap();
+        assert_eq!(open(&cipher, b"alpha", &sealed).unwrap(), b"secret");
+        assert!(open(&cipher, b"beta", &sealed).is_err());
+
+        let other = super::cipher(&generate_key()).unwrap();
+        assert!(open(&other, b"alpha", &sealed).is_err());
+        assert!(open(&cipher, b"alpha", &sealed[..NONCE_LEN - 1]).is_err());
+    }
+
+    #[test]
+    fn rejects_keys_of_the_wrong_length() {
+        assert!(cipher(&BASE64.encode([0u8; 16])).is_err());
+        assert!(cipher("not base64!").is_err());
+    }
+}
@@ -0,0 +1,47 @@
+//! Minimal HTTP client for the local Node server's REST API
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::{auth, server_port, server_probe};
+
+/// Requests go to localhost, so anything slower than this means the server is unhealthy
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub fn base_url() -> String {
+    server_probe::base_url(server_port())
+}
+
+fn request(method: &str, path: &str) -> ureq::Request {
+    ureq::request(method, &format!("{}{}", base_url(), path))
+        .timeout(REQUEST_TIMEOUT)
+        .set(auth::TOKEN_HEADER, auth::token())
+}
+
+/// GET `path` (e.g. `/api/sessions`) and decode the JSON body
+pub fn get_json<T: DeserializeOwned>(path: &str) -> Result<T, String> {
+    request("GET", path)
+        .call()
+        .map_err(|e| format!("GET {} failed: {}", path, e))?
+        .into_json()
+        .map_err(|e| format!("Invalid response from {}: {}", path, e))
+}
+
+/// Send `method path` with an optional JSON body; an empty response decodes as `null`
+pub fn send_json(method: &str, path: &str, body: Option<&Value>) -> Result<Value, String> {
+    let request = request(method, path);
+    let response = match body {
+        Some(body) => request.send_json(body),
+        None => request.call(),
+    }
+    .map_err(|e| format!("{} {} failed: {}", method, path, e))?;
+
+    let text = response
+        .into_string()
+        .map_err(|e| format!("Invalid response from {}: {}", path, e))?;
+    if text.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(&text).map_err(|e| format!("Invalid response from {}: {}", path, e))
+}
@@ -0,0 +1,250 @@
+//! Persistent server log with search and level filtering
+//!
+//! Everything the server prints is appended to `logs/server.log` in the data directory
+//! with a timestamp, rotating to `server.log.1` at 5 MB. Levels are read from pino's JSON
+//! output (`"level":30`) and pino-pretty/console prefixes (`INFO`, `WARN`, `ERROR`); stack
+//! trace lines (`    at ...`) stay attached to the line that started them, so a search
+//! returns the whole trace.
+
+use chrono::{Local, TimeZone};
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::config;
+use crate::error::Error;
+use crate::store;
+
+pub const LOG_DIR: &str = "logs";
+pub const LOG_FILE: &str = "server.log";
+const ROTATED_FILE: &str = "server.log.1";
+const MAX_BYTES: u64 = 5 * 1024 * 1024;
+const DEFAULT_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    /// pino's numeric levels
+    fn from_pino(level: i64) -> Self {
+        match level {
+            i64::MIN..=10 => Self::Trace,
+            11..=20 => Self::Debug,
+            21..=30 => Self::Info,
+            31..=40 => Self::Warn,
+            41..=50 => Self::Error,
+            _ => Self::Fatal,
+        }
+    }
+}
+
+/// Millisecond bounds, either of which may be open
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRange {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    pub at: i64,
+    pub level: Level,
+    /// The line, plus any stack trace lines that followed it
+    pub message: String,
+}
+
+struct Writer {
+    file: File,
+    size: u64,
+}
+
+static WRITER: Mutex<Option<Writer>> = Mutex::new(None);
+
+pub fn log_dir() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join(LOG_DIR))
+}
+
+fn open() -> Option<Writer> {
+    let dir = log_dir()?;
+    fs::create_dir_all(&dir).ok()?;
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(LOG_FILE))
+        .ok()?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or_default();
+    Some(Writer { file, size })
+}
+
+/// Close the log file so the next line opens it in the current `log_dir`
+pub fn reopen() {
+    if let Ok(mut writer) = WRITER.lock() {
+        *writer = None;
+    }
+}
+
+/// Append one line of server output
+pub fn append(line: &str) {
+    let Ok(mut writer) = WRITER.lock() else {
+        return;
+    };
+    if writer.as_ref().is_some_and(|w| w.size >= MAX_BYTES) {
+        *writer = None;
+        if let Some(dir) = log_dir() {
+            let _ = fs::rename(dir.join(LOG_FILE), dir.join(ROTATED_FILE));
+        }
+    }
+    if writer.is_none() {
+        *writer = open();
+    }
+    let Some(w) = writer.as_mut() else {
+        return;
+    };
+    let record = format!("{}\t{}\n", store::now_ms(), line);
+    if w.file.write_all(record.as_bytes()).is_ok() {
+        w.size += record.len() as u64;
+    }
+}
+
+fn is_continuation(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    (line.starts_with(char::is_whitespace) && trimmed.starts_with("at ")) || trimmed == "^"
+}
+
+/// Level and display text for one line
+fn parse(line: &str) -> (Level, String) {
+    if line.starts_with('{') {
+        if let Ok(json) = serde_json::from_str::<Value>(line) {
+            if let Some(level) = json.get("level").and_then(Value::as_i64) {
+                let mut message = json
+                    .get("msg")
+                    .and_then(Value::as_str)
+                    .unwrap_or(line)
+                    .to_string();
+                if let Some(stack) = json.pointer("/err/stack").and_then(Value::as_str) {
+                    message = format!("{}\n{}", message, stack);
+                }
+                return (Level::from_pino(level), message);
+            }
+        }
+    }
+    let upper = line.to_uppercase();
+    let level = if upper.contains("FATAL") {
+        Level::Fatal
+    } else if upper.contains("ERROR") || upper.contains("ERR!") {
+        Level::Error
+    } else if upper.contains("WARN") {
+        Level::Warn
+    } else if upper.contains("DEBUG") {
+        Level::Debug
+    } else if upper.contains("TRACE") {
+        Level::Trace
+    } else {
+        Level::Info
+    };
+    (level, line.to_string())
+}
+
+/// Rotated-out logs, which only serve searches of older history
+pub fn rotated_files() -> Vec<PathBuf> {
+    log_dir()
+        .map(|dir| dir.join(ROTATED_FILE))
+        .filter(|path| path.exists())
+        .into_iter()
+        .collect()
+}
+
+/// Every logged entry, oldest first, with stack traces folded into their entry
+fn entries() -> Vec<LogEntry> {
+    let Some(dir) = log_dir() else {
+        return Vec::new();
+    };
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for name in [ROTATED_FILE, LOG_FILE] {
+        let Ok(file) = File::open(dir.join(name)) else {
+            continue;
+        };
+        for record in BufReader::new(file).lines().map_while(Result::ok) {
+            let Some((at, line)) = record.split_once('\t') else {
+                continue;
+            };
+            let at = at.parse().unwrap_or_default();
+            if let Some(last) = entries.last_mut().filter(|_| is_continuation(line)) {
+                last.message.push('\n');
+                last.message.push_str(line);
+                continue;
+            }
+            let (level, message) = parse(line);
+            entries.push(LogEntry { at, level, message });
+        }
+    }
+    entries
+}
+
+/// Log entries matching `query` (a case-insensitive regex) at `level` or above,
+/// newest last, up to `limit`
+#[tauri::command]
+pub async fn search_server_logs(
+    query: Option<String>,
+    level: Option<Level>,
+    range: Option<LogRange>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntry>, Error> {
+    let pattern = match query.as_deref().filter(|q| !q.is_empty()) {
+        Some(query) => Some(
+            RegexBuilder::new(query)
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| Error::InvalidInput(format!("Invalid search pattern: {}", e)))?,
+        ),
+        None => None,
+    };
+    let range = range.unwrap_or_default();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    let mut matches = tauri::async_runtime::spawn_blocking(move || {
+        entries()
+            .into_iter()
+            .rev()
+            .filter(|e| level.is_none_or(|level| e.level >= level))
+            .filter(|e| range.from.is_none_or(|from| e.at >= from))
+            .filter(|e| range.to.is_none_or(|to| e.at <= to))
+            .filter(|e| pattern.as_ref().is_none_or(|p| p.is_match(&e.message)))
+            .take(limit)
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    matches.reverse();
+    Ok(matches)
+}
+
+/// Write the whole log to `path` as plain text with local timestamps
+#[tauri::command]
+pub fn export_server_logs(path: String) -> Result<(), Error> {
+    let text: String = entries()
+        .iter()
+        .map(|e| {
+            let at = Local
+                .timestamp_millis_opt(e.at)
+                .single()
+                .map(|d| d.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+                .unwrap_or_default();
+            format!("{} {:?} {}\n", at, e.level, e.message)
+        })
+        .collect();
+    fs::write(&path, text).map_err(|e| Error::from(format!("Failed to write {}: {}", path, e)))
+}
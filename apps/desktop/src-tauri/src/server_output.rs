@@ -0,0 +1,146 @@
+//! Readiness and failure detection from the server's own output
+//!
+//! Every line the server (or its container) prints is checked against a few patterns:
+//! a "listening on" line marks it ready, and known failures (port already in use, a
+//! missing environment variable, a TypeScript compile error, a missing module) are
+//! mapped to an error code and a remediation hint. Changes are published on the event
+//! bus as `server-status`, alongside the Docker backend's health checks.
+
+use serde::Serialize;
+use std::sync::Mutex;
+
+use crate::event_bus::{self, AppEvent};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatus {
+    /// `running`, `starting`, `unhealthy`, `failed` or `stopped`
+    pub status: String,
+    pub backend: &'static str,
+    /// Raw container state, or the output line the status was read from
+    pub detail: Option<String>,
+    /// Known failure, e.g. `port_in_use`
+    pub code: Option<&'static str>,
+    pub hint: Option<&'static str>,
+}
+
+struct Failure {
+    code: &'static str,
+    /// Any of these (lowercased) marks the failure
+    needles: &'static [&'static str],
+    hint: &'static str,
+}
+
+const READY: &[&str] = &["listening on", "server running at", "server listening"];
+
+const FAILURES: &[Failure] = &[
+    Failure {
+        code: "port_in_use",
+        needles: &["eaddrinuse", "address already in use"],
+        hint: "Another process is using the server's port. Quit it, or pick another port in the server profile.",
+    },
+    Failure {
+        code: "missing_env",
+        needles: &[
+            "missing required environment variable",
+            "invalid environment variables",
+            "environment variable is not set",
+        ],
+        hint: "A required environment variable is missing. Add it to the server's .env file or the profile's env.",
+    },
+    Failure {
+        code: "compile_error",
+        needles: &["): error ts", "tserror", "transform failed"],
+        hint: "The server doesn't compile. Fix the TypeScript error in the log; `dev` reloads once it builds.",
+    },
+    Failure {
+        code: "module_not_found",
+        needles: &["cannot find module", "err_module_not_found"],
+        hint: "A dependency is missing. Reinstall the server's dependencies.",
+    },
+];
+
+static LAST: Mutex<Option<ServerStatus>> = Mutex::new(None);
+
+fn publish(status: ServerStatus) {
+    let Ok(mut last) = LAST.lock() else {
+        return;
+    };
+    if last.as_ref() == Some(&status) {
+        return;
+    }
+    *last = Some(status.clone());
+    drop(last);
+    event_bus::publish(AppEvent::ServerStatus(status));
+}
+
+/// A freshly spawned server counts as starting until it says otherwise
+pub fn reset(backend: &'static str) {
+    publish(ServerStatus {
+        status: "starting".to_string(),
+        backend,
+        detail: None,
+        code: None,
+        hint: None,
+    });
+}
+
+fn backend() -> &'static str {
+    LAST.lock()
+        .ok()
+        .and_then(|last| last.as_ref().map(|s| s.backend))
+        .unwrap_or("process")
+}
+
+/// Called once the server has been stopped on purpose
+pub fn stopped() {
+    publish(ServerStatus {
+        status: "stopped".to_string(),
+        backend: backend(),
+        detail: None,
+        code: None,
+        hint: None,
+    });
+}
+
+/// Check one line of server output
+pub fn scan(line: &str) {
+    let lower = line.to_lowercase();
+    let backend = backend();
+    if let Some(failure) = FAILURES
+        .iter()
+        .find(|f| f.needles.iter().any(|n| lower.contains(n)))
+    {
+        eprintln!(
+            "[Claude PM] Server failed ({}): {}",
+            failure.code,
+            line.trim()
+        );
+        publish(ServerStatus {
+            status: "failed".to_string(),
+            backend,
+            detail: Some(line.trim().to_string()),
+            code: Some(failure.code),
+            hint: Some(failure.hint),
+        });
+    } else if READY.iter().any(|r| lower.contains(r)) {
+        publish(ServerStatus {
+            status: "running".to_string(),
+            backend,
+            detail: Some(line.trim().to_string()),
+            code: None,
+            hint: None,
+        });
+    }
+}
+
+/// What the server's output last told us, if anything
+pub fn last() -> Option<ServerStatus> {
+    LAST.lock().ok().and_then(|last| last.clone())
+}
+
+/// The last status read from the server's output, with its failure code and hint
+#[tauri::command]
+pub fn get_server_output_status() -> Option<ServerStatus> {
+    last()
+}
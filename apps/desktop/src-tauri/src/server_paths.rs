@@ -0,0 +1,366 @@
+//! Registry of known server locations
+//!
+//! Every server directory the app has been pointed at is remembered in the config under
+//! `server_paths`, optionally tied to a project, with the selected one in `server_path`.
+//! An entry is valid while its directory has a `package.json`; its package name is kept
+//! so a moved checkout can be found again. When the selected directory disappears,
+//! directories near the old location are searched for the same package (once per
+//! launch), and the entry follows it if found; otherwise the next valid entry is used.
+//!
+//! The server directory is resolved from, in order: the selected entry,
+//! `CLAUDE_PM_SERVER_PATH`, the other entries, and in development the repo checkout
+//! next to the executable.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::error::Error;
+use crate::{config, store};
+
+/// How far above a moved directory to look for it, and how deep below that
+const SEARCH_UP: usize = 3;
+const SEARCH_DEPTH: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerPathEntry {
+    pub id: String,
+    pub path: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Project this server location belongs to, if any
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// `name` from its package.json, to recognize it after a move
+    #[serde(default)]
+    pub package_name: Option<String>,
+    pub added_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerPathStatus {
+    #[serde(flatten)]
+    pub entry: ServerPathEntry,
+    pub valid: bool,
+    pub selected: bool,
+}
+
+/// Old paths a relocation search already ran for
+static SEARCHED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+fn package_name(dir: &Path) -> Option<String> {
+    let contents = fs::read_to_string(dir.join("package.json")).ok()?;
+    let package: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    package["name"].as_str().map(str::to_string)
+}
+
+fn is_valid(dir: &Path) -> bool {
+    dir.join("package.json").is_file()
+}
+
+fn validate(path: &str) -> Result<PathBuf, Error> {
+    let dir = PathBuf::from(path.trim());
+    if !dir.is_dir() {
+        return Err(Error::NotFound(format!(
+            "{} is not a directory",
+            dir.display()
+        )));
+    }
+    if !is_valid(&dir) {
+        return Err(Error::InvalidInput(format!(
+            "{} doesn't look like the Claude PM server (no package.json)",
+            dir.display()
+        )));
+    }
+    Ok(dir.canonicalize().unwrap_or(dir))
+}
+
+/// A directory within `depth` levels of `dir` holding the package `name`
+fn find_package(dir: &Path, name: &str, depth: usize) -> Option<PathBuf> {
+    if package_name(dir).as_deref() == Some(name) {
+        return Some(dir.to_path_buf());
+    }
+    if depth == 0 {
+        return None;
+    }
+    let mut children: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_dir()
+                && !path
+                    .file_name()
+                    .is_some_and(|n| n == "node_modules" || n.to_string_lossy().starts_with('.'))
+        })
+        .collect();
+    children.sort();
+    children
+        .iter()
+        .find_map(|child| find_package(child, name, depth - 1))
+}
+
+/// Where a moved entry has gone, searching around its old location once per launch
+fn find_moved(entry: &ServerPathEntry) -> Option<PathBuf> {
+    let name = entry.package_name.as_deref()?;
+    if !SEARCHED.lock().ok()?.insert(entry.path.clone()) {
+        return None;
+    }
+    let old = Path::new(&entry.path);
+    let base = old
+        .ancestors()
+        .skip(1)
+        .take(SEARCH_UP)
+        .find(|dir| dir.is_dir())?;
+    let found = find_package(base, name, SEARCH_DEPTH)?;
+    println!(
+        "[Claude PM] Server directory moved: {} -> {}",
+        entry.path,
+        found.display()
+    );
+    Some(found)
+}
+
+/// Point an entry at a new location, keeping the selection on it
+fn move_entry(id: &str, dir: &Path) -> Result<ServerPathEntry, String> {
+    let path = dir.display().to_string();
+    let mut moved = None;
+    config::update(|c| {
+        if let Some(entry) = c.server_paths.iter_mut().find(|e| e.id == id) {
+            if c.server_path.as_deref() == Some(entry.path.as_str()) {
+                c.server_path = Some(path.clone());
+            }
+            entry.path = path.clone();
+            entry.package_name = package_name(dir).or(entry.package_name.take());
+            moved = Some(entry.clone());
+        }
+    })?;
+    moved.ok_or_else(|| format!("No server path with id {}", id))
+}
+
+/// Dev builds run from `src-tauri/target/<profile>`, next to the server checkout
+fn dev_checkout() -> Option<PathBuf> {
+    let exe = env::current_exe().ok()?;
+    let parent = exe.parent()?;
+    [
+        parent.join("../../../../server"),
+        parent.join("../../server"),
+        parent.join("../../../server"),
+    ]
+    .into_iter()
+    .filter_map(|path| path.canonicalize().ok())
+    .find(|path| is_valid(path))
+}
+
+/// The server directory to use (see the module docs for the order)
+pub fn resolve() -> Option<PathBuf> {
+    let config = config::load();
+    if let Some(selected) = config.server_path.as_deref() {
+        let dir = PathBuf::from(selected);
+        if dir.exists() {
+            return Some(dir);
+        }
+        let entry = config.server_paths.iter().find(|e| e.path == selected);
+        if let Some((entry, found)) = entry.and_then(|e| find_moved(e).map(|found| (e, found))) {
+            if let Err(e) = move_entry(&entry.id, &found) {
+                eprintln!("[Claude PM] Failed to record moved server directory: {}", e);
+            }
+            return Some(found);
+        }
+    }
+    if let Ok(path) = env::var("CLAUDE_PM_SERVER_PATH") {
+        let dir = PathBuf::from(&path);
+        if dir.exists() {
+            return Some(dir);
+        }
+    }
+    config
+        .server_paths
+        .iter()
+        .map(|entry| PathBuf::from(&entry.path))
+        .find(|dir| is_valid(dir))
+        .or_else(dev_checkout)
+}
+
+/// Register `path` (or find its existing entry) and optionally select it
+pub fn add(
+    path: &str,
+    label: Option<String>,
+    project_id: Option<String>,
+    select: bool,
+) -> Result<ServerPathEntry, Error> {
+    let dir = validate(path)?;
+    let path = dir.display().to_string();
+    let mut added = None;
+    config::update(|c| {
+        let entry = match c.server_paths.iter_mut().find(|e| e.path == path) {
+            Some(existing) => {
+                if label.is_some() {
+                    existing.label = label;
+                }
+                if project_id.is_some() {
+                    existing.project_id = project_id;
+                }
+                existing.clone()
+            }
+            None => {
+                let entry = ServerPathEntry {
+                    id: store::new_id(),
+                    path: path.clone(),
+                    label,
+                    project_id,
+                    package_name: package_name(&dir),
+                    added_at: store::now_ms(),
+                };
+                c.server_paths.push(entry.clone());
+                entry
+            }
+        };
+        if select {
+            c.server_path = Some(path.clone());
+        }
+        added = Some(entry);
+    })?;
+    added.ok_or_else(|| Error::Internal("Failed to add server path".to_string()))
+}
+
+fn pick_folder(
+    app: &AppHandle,
+    title: &str,
+    start: Option<&Path>,
+) -> Result<Option<PathBuf>, Error> {
+    let mut dialog = app.dialog().file().set_title(title);
+    if let Some(dir) = start.filter(|dir| dir.is_dir()) {
+        dialog = dialog.set_directory(dir);
+    }
+    match dialog.blocking_pick_folder() {
+        Some(path) => path
+            .into_path()
+            .map(Some)
+            .map_err(|e| Error::Internal(e.to_string())),
+        None => Ok(None),
+    }
+}
+
+#[tauri::command]
+pub fn list_server_paths() -> Vec<ServerPathStatus> {
+    let config = config::load();
+    config
+        .server_paths
+        .iter()
+        .map(|entry| ServerPathStatus {
+            valid: is_valid(Path::new(&entry.path)),
+            selected: config.server_path.as_deref() == Some(entry.path.as_str()),
+            entry: entry.clone(),
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn add_server_path(
+    path: String,
+    label: Option<String>,
+    project_id: Option<String>,
+) -> Result<ServerPathEntry, Error> {
+    add(&path, label, project_id, false)
+}
+
+/// Forget an entry; removing the selected one clears the selection
+#[tauri::command]
+pub fn remove_server_path(id: String) -> Result<(), Error> {
+    let mut found = false;
+    config::update(|c| {
+        if let Some(index) = c.server_paths.iter().position(|e| e.id == id) {
+            let entry = c.server_paths.remove(index);
+            if c.server_path.as_deref() == Some(entry.path.as_str()) {
+                c.server_path = None;
+            }
+            found = true;
+        }
+    })?;
+    if found {
+        Ok(())
+    } else {
+        Err(Error::NotFound(format!("No server path with id {}", id)))
+    }
+}
+
+/// Use an entry from the next (re)start on
+#[tauri::command]
+pub fn select_server_path(id: String) -> Result<ServerPathEntry, Error> {
+    let entry = config::load()
+        .server_paths
+        .into_iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| Error::NotFound(format!("No server path with id {}", id)))?;
+    validate(&entry.path)?;
+    config::update(|c| c.server_path = Some(entry.path.clone()))?;
+    Ok(entry)
+}
+
+/// Pick the server directory with the native folder picker, register and select it;
+/// `None` if the picker was cancelled
+#[tauri::command]
+pub async fn set_server_path(app: AppHandle) -> Result<Option<ServerPathEntry>, Error> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let current = resolve();
+        let Some(dir) = pick_folder(
+            &app,
+            "Choose the Claude PM server directory",
+            current.as_deref(),
+        )?
+        else {
+            return Ok(None);
+        };
+        add(&dir.display().to_string(), None, None, true).map(Some)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Point an entry at where its directory moved to, picking it when `path` is not given
+#[tauri::command]
+pub async fn relocate_server_path(
+    app: AppHandle,
+    id: String,
+    path: Option<String>,
+) -> Result<Option<ServerPathEntry>, Error> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let entry = config::load()
+            .server_paths
+            .into_iter()
+            .find(|e| e.id == id)
+            .ok_or_else(|| Error::NotFound(format!("No server path with id {}", id)))?;
+        let path = match path {
+            Some(path) => path,
+            None => {
+                let start = Path::new(&entry.path).ancestors().find(|dir| dir.is_dir());
+                match pick_folder(&app, "Where did the server directory move?", start)? {
+                    Some(dir) => dir.display().to_string(),
+                    None => return Ok(None),
+                }
+            }
+        };
+        let dir = validate(&path)?;
+        if let (Some(expected), Some(found)) = (entry.package_name.as_deref(), package_name(&dir)) {
+            if expected != found {
+                return Err(Error::InvalidInput(format!(
+                    "{} holds {}, not {}",
+                    dir.display(),
+                    found,
+                    expected
+                )));
+            }
+        }
+        move_entry(&id, &dir).map(Some).map_err(Error::from)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,139 @@
+//! Finding the local server on whichever address it bound
+//!
+//! Node binds `localhost` to `::1` on some systems, `127.0.0.1` on others, or every
+//! interface, so a server that only listens on IPv6 looked "stopped" and got a duplicate
+//! spawned. Probes now try IPv4 and IPv6 loopback and the optional `server_host` from
+//! the config (a container name, say), starting with whichever answered last time. The
+//! address found is what the REST client, the WebSocket bridge and the proxy connect to,
+//! and the health check asks `/api/health` on the same address.
+
+use serde::Serialize;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::{config, server_api, server_port};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+const HEALTH_PATH: &str = "/api/health";
+/// How long a listener gets to answer the health check before it counts as foreign
+const IDENTIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The address that last accepted a connection
+static LAST: Mutex<Option<SocketAddr>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerProbe {
+    pub port: u16,
+    /// Where the server answered, e.g. `[::1]:3000`
+    pub address: Option<String>,
+    pub listening: bool,
+    /// `/api/health` answered (only checked for the active server's port)
+    pub healthy: bool,
+    /// Addresses that were tried, in order
+    pub tried: Vec<String>,
+}
+
+fn candidates(port: u16) -> Vec<SocketAddr> {
+    let mut addrs = vec![
+        SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port),
+        SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), port),
+    ];
+    if let Some(host) = config::load().server_host.filter(|h| !h.trim().is_empty()) {
+        match (host.trim(), port).to_socket_addrs() {
+            Ok(resolved) => addrs.extend(resolved),
+            Err(e) => eprintln!("[Claude PM] Can't resolve server host {}: {}", host, e),
+        }
+    }
+    if let Some(last) = LAST
+        .lock()
+        .ok()
+        .and_then(|last| *last)
+        .filter(|a| a.port() == port)
+    {
+        addrs.retain(|addr| *addr != last);
+        addrs.insert(0, last);
+    }
+    addrs.dedup();
+    addrs
+}
+
+/// A connection to the server on the first address that accepts one
+pub fn connect(port: u16) -> Option<TcpStream> {
+    for addr in candidates(port) {
+        if let Ok(stream) = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            if let Ok(mut last) = LAST.lock() {
+                *last = Some(addr);
+            }
+            return Some(stream);
+        }
+    }
+    None
+}
+
+pub fn is_listening(port: u16) -> bool {
+    connect(port).is_some()
+}
+
+/// `host:port` to reach the server at, IPv6 bracketed; IPv4 loopback until one is found
+fn authority(port: u16) -> String {
+    let addr = LAST
+        .lock()
+        .ok()
+        .and_then(|last| *last)
+        .filter(|addr| addr.port() == port)
+        .unwrap_or(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port));
+    addr.to_string()
+}
+
+/// Base URL for the server's REST API, e.g. `http://[::1]:3000`
+pub fn base_url(port: u16) -> String {
+    format!("http://{}", authority(port))
+}
+
+/// WebSocket URL for the server, e.g. `ws://127.0.0.1:3000`
+pub fn ws_url(port: u16) -> String {
+    format!("ws://{}", authority(port))
+}
+
+/// The active server is listening and its health endpoint answers
+pub fn is_healthy() -> bool {
+    is_listening(server_port()) && server_api::send_json("GET", HEALTH_PATH, None).is_ok()
+}
+
+/// Whatever listens on `port` answers `/api/health` like a Claude PM server
+pub fn is_claude_pm(port: u16) -> bool {
+    ureq::get(&format!("{}{}", base_url(port), HEALTH_PATH))
+        .timeout(IDENTIFY_TIMEOUT)
+        .call()
+        .ok()
+        .and_then(|response| response.into_json::<serde_json::Value>().ok())
+        .is_some_and(|health| health.get("apiVersion").is_some())
+}
+
+pub fn probe(port: u16) -> ServerProbe {
+    let tried = candidates(port).iter().map(SocketAddr::to_string).collect();
+    let listening = is_listening(port);
+    ServerProbe {
+        port,
+        address: listening.then(|| authority(port)),
+        listening,
+        healthy: listening && port == server_port() && is_healthy(),
+        tried,
+    }
+}
+
+/// Where (and whether) the active server answers
+#[tauri::command]
+pub async fn probe_server() -> ServerProbe {
+    tauri::async_runtime::spawn_blocking(|| probe(server_port()))
+        .await
+        .unwrap_or_else(|_| ServerProbe {
+            port: server_port(),
+            address: None,
+            listening: false,
+            healthy: false,
+            tried: Vec::new(),
+        })
+}
@@ -0,0 +1,200 @@
+//! One-click update for servers run from a git checkout
+//!
+//! `update_server` fast-forwards the checkout to its upstream, reinstalls dependencies
+//! and restarts the server, waiting until it answers again. If the install or restart
+//! fails, the checkout is reset to the commit that was running before, dependencies are
+//! reinstalled and the server restarted, so a bad upstream commit doesn't leave the app
+//! without a server. Updates refuse to run over uncommitted changes.
+//!
+//! Events:
+//! - `server-update` — `fetch`, `pull`, `install`, `restart`, `rollback`, `output` (one
+//!   per line), then `done` or `failed`
+
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::bootstrap;
+use crate::error::Error;
+use crate::process::{self, CancelToken};
+use crate::server_output;
+
+const GIT_TIMEOUT: Duration = Duration::from_secs(120);
+const READY_TIMEOUT: Duration = Duration::from_secs(90);
+const READY_POLL: Duration = Duration::from_secs(1);
+const CHANGELOG_LIMIT: &str = "50";
+
+static UPDATING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateEvent {
+    stage: &'static str,
+    line: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateResult {
+    pub previous: String,
+    pub current: String,
+    /// False when the checkout was already up to date
+    pub updated: bool,
+    /// `git log --oneline` of the server commits pulled in
+    pub changelog: Vec<String>,
+}
+
+fn emit(app: &AppHandle, stage: &'static str, line: Option<String>) {
+    let _ = app.emit("server-update", UpdateEvent { stage, line });
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, Error> {
+    let output = process::run(
+        Command::new("git").args(args).current_dir(dir),
+        GIT_TIMEOUT,
+        &CancelToken::default(),
+    )?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn install(app: &AppHandle) -> Result<(), Error> {
+    let app = app.clone();
+    bootstrap::run_package_install(&CancelToken::default(), move |line| {
+        emit(&app, "output", Some(line))
+    })
+}
+
+/// Restart and wait for the server to answer, or to report a known failure
+fn restart() -> Result<(), Error> {
+    crate::restart_server()?;
+    let deadline = Instant::now() + READY_TIMEOUT;
+    while Instant::now() < deadline {
+        if crate::is_server_running(crate::server_port()) {
+            return Ok(());
+        }
+        if let Some(status) = server_output::last().filter(|s| s.status == "failed") {
+            return Err(format!(
+                "Server failed to start: {}",
+                status.hint.unwrap_or("see the server log")
+            )
+            .into());
+        }
+        thread::sleep(READY_POLL);
+    }
+    Err(Error::Timeout(
+        "Server didn't come back after the update".to_string(),
+    ))
+}
+
+fn rollback(app: &AppHandle, dir: &Path, previous: &str) -> Result<(), Error> {
+    emit(app, "rollback", Some(previous.to_string()));
+    git(dir, &["reset", "--hard", previous])?;
+    install(app)?;
+    restart()
+}
+
+fn run_update(app: &AppHandle) -> Result<UpdateResult, Error> {
+    let dir = crate::get_server_path().ok_or(Error::ServerPathMissing)?;
+    if git(&dir, &["rev-parse", "--is-inside-work-tree"]).is_err() {
+        return Err(Error::Unsupported(
+            "The server isn't a git checkout, so it can't be updated from here".to_string(),
+        ));
+    }
+    if !git(&dir, &["status", "--porcelain"])?.is_empty() {
+        return Err(Error::InvalidInput(
+            "The server checkout has uncommitted changes; commit or stash them first".to_string(),
+        ));
+    }
+    let previous = git(&dir, &["rev-parse", "HEAD"])?;
+
+    emit(app, "fetch", None);
+    git(&dir, &["fetch", "--prune"])?;
+    let changelog: Vec<String> = git(
+        &dir,
+        &[
+            "log",
+            "--oneline",
+            "--no-merges",
+            "-n",
+            CHANGELOG_LIMIT,
+            "HEAD..@{u}",
+            "--",
+            ".",
+        ],
+    )?
+    .lines()
+    .map(str::to_string)
+    .collect();
+    if git(&dir, &["rev-list", "--count", "HEAD..@{u}"])? == "0" {
+        return Ok(UpdateResult {
+            current: previous.clone(),
+            previous,
+            updated: false,
+            changelog,
+        });
+    }
+
+    emit(app, "pull", None);
+    git(&dir, &["merge", "--ff-only", "@{u}"])?;
+    let current = git(&dir, &["rev-parse", "HEAD"])?;
+
+    emit(app, "install", None);
+    let result = install(app).and_then(|_| {
+        emit(app, "restart", None);
+        restart()
+    });
+    if let Err(e) = result {
+        eprintln!(
+            "[Claude PM] Server update failed, rolling back to {}: {}",
+            previous, e
+        );
+        return Err(match rollback(app, &dir, &previous) {
+            Ok(()) => format!("Update failed and was rolled back: {}", e).into(),
+            Err(rollback_error) => format!(
+                "Update failed ({}) and so did rolling back ({})",
+                e, rollback_error
+            )
+            .into(),
+        });
+    }
+    println!("[Claude PM] Server updated {} → {}", previous, current);
+    Ok(UpdateResult {
+        previous,
+        current,
+        updated: true,
+        changelog,
+    })
+}
+
+/// Pull, install and restart; errors if an update or dependency install is already running
+#[tauri::command]
+pub async fn update_server(app: AppHandle) -> Result<UpdateResult, Error> {
+    if bootstrap::is_installing() || UPDATING.swap(true, Ordering::SeqCst) {
+        return Err(Error::InvalidInput(
+            "The server is already being updated or installed".to_string(),
+        ));
+    }
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let result = run_update(&app);
+        match &result {
+            Ok(_) => emit(&app, "done", None),
+            Err(e) => emit(&app, "failed", Some(e.to_string())),
+        }
+        result
+    })
+    .await;
+    UPDATING.store(false, Ordering::SeqCst);
+    result.map_err(|e| e.to_string())?
+}
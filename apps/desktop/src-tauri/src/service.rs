@@ -0,0 +1,308 @@
+//! Installing the server as a user-level launchd agent (macOS) or systemd unit (Linux)
+//!
+//! A service-managed server keeps running when the app is closed. While one is
+//! installed, `start_server` makes sure the service is up instead of spawning npm, and
+//! `restart_server` restarts the service. Because the service outlives the app, it gets
+//! a persistent desktop token (see `auth`) instead of the per-launch one.
+//!
+//! The unit embeds the server environment, including vault secrets, so it is written
+//! readable by the user only.
+
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::error::Error;
+use crate::{config, process, profiles};
+
+const LABEL: &str = "com.claudepm.server";
+const UNIT: &str = "claudepm-server.service";
+const LOG_FILE: &str = "server-service.log";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceStatus {
+    pub supported: bool,
+    pub installed: bool,
+    pub running: bool,
+    /// `launchd` or `systemd`
+    pub manager: Option<&'static str>,
+    pub path: Option<String>,
+}
+
+fn manager() -> Option<&'static str> {
+    if cfg!(target_os = "macos") {
+        Some("launchd")
+    } else if cfg!(target_os = "linux") {
+        Some("systemd")
+    } else {
+        None
+    }
+}
+
+fn unit_path() -> Option<PathBuf> {
+    match manager()? {
+        "launchd" => dirs::home_dir().map(|home| {
+            home.join("Library/LaunchAgents")
+                .join(format!("{}.plist", LABEL))
+        }),
+        _ => dirs::config_dir().map(|dir| dir.join("systemd/user").join(UNIT)),
+    }
+}
+
+pub fn is_installed() -> bool {
+    unit_path().is_some_and(|path| path.exists())
+}
+
+fn uid() -> Result<String, Error> {
+    let output = process::output(Command::new("id").arg("-u"))?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Run a launchctl/systemctl command, failing with its stderr
+fn control(program: &str, args: &[&str]) -> Result<(), Error> {
+    let output = process::output(Command::new(program).args(args))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::Internal(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// systemd wants `"` and `\` escaped inside quoted values, and `%` doubled
+fn systemd_quote(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+    )
+}
+
+fn plist(args: &[String], dir: &str, env: &[(String, String)], log: &str) -> String {
+    let args: String = args
+        .iter()
+        .map(|a| format!("        <string>{}</string>\n", xml_escape(a)))
+        .collect();
+    let env: String = env
+        .iter()
+        .map(|(k, v)| {
+            format!(
+                "        <key>{}</key>\n        <string>{}</string>\n",
+                xml_escape(k),
+                xml_escape(v)
+            )
+        })
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{args}    </array>
+    <key>WorkingDirectory</key>
+    <string>{dir}</string>
+    <key>EnvironmentVariables</key>
+    <dict>
+{env}    </dict>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = LABEL,
+        dir = xml_escape(dir),
+        log = xml_escape(log),
+    )
+}
+
+fn systemd_unit(args: &[String], dir: &str, env: &[(String, String)]) -> String {
+    let exec: Vec<String> = args.iter().map(|a| systemd_quote(a)).collect();
+    let env: String = env
+        .iter()
+        .map(|(k, v)| format!("Environment={}\n", systemd_quote(&format!("{}={}", k, v))))
+        .collect();
+    format!(
+        "[Unit]\nDescription=Claude PM server\nAfter=network.target\n\n[Service]\nType=simple\nWorkingDirectory={}\nExecStart={}\n{}Restart=on-failure\nRestartSec=5\n\n[Install]\nWantedBy=default.target\n",
+        systemd_quote(dir),
+        exec.join(" "),
+        env
+    )
+}
+
+fn write_private(path: &PathBuf, contents: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("Failed to restrict {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Start the installed service if it isn't already running
+pub fn ensure_started() -> Result<(), Error> {
+    match manager() {
+        Some("launchd") => {
+            let target = format!("gui/{}/{}", uid()?, LABEL);
+            control("launchctl", &["kickstart", &target])
+        }
+        Some(_) => control("systemctl", &["--user", "start", UNIT]),
+        None => Err(unsupported()),
+    }
+}
+
+pub fn restart() -> Result<(), Error> {
+    match manager() {
+        Some("launchd") => {
+            let target = format!("gui/{}/{}", uid()?, LABEL);
+            control("launchctl", &["kickstart", "-k", &target])
+        }
+        Some(_) => control("systemctl", &["--user", "restart", UNIT]),
+        None => Err(unsupported()),
+    }
+}
+
+fn unsupported() -> Error {
+    Error::Unsupported("Background services are only supported on macOS and Linux".to_string())
+}
+
+/// Install (or reinstall) the service for the active profile and start it
+#[tauri::command]
+pub fn install_server_service() -> Result<ServiceStatus, Error> {
+    let path = unit_path().ok_or_else(unsupported)?;
+    let npm = crate::find_npm().ok_or(Error::NpmNotFound)?;
+    let server_path = crate::get_server_path().ok_or(Error::ServerPathMissing)?;
+    if crate::bootstrap::needs_install(&server_path) {
+        return Err(Error::DependenciesMissing);
+    }
+    if config::load().docker.enabled {
+        return Err(Error::InvalidInput(
+            "Turn off the Docker backend before installing the server as a service".to_string(),
+        ));
+    }
+
+    // The service outlives this process, so it can't use the per-launch token
+    crate::auth::use_persistent_token()?;
+    let profile = profiles::active();
+    let mut env: Vec<(String, String)> = crate::server_env(&profile)?.into_iter().collect();
+    env.push(("PATH".to_string(), crate::node_path_env(&npm)));
+
+    let args = vec![
+        npm.display().to_string(),
+        "run".to_string(),
+        profile.script.clone(),
+    ];
+    let dir = server_path.display().to_string();
+
+    // Our own child would hold the port the service needs
+    crate::stop_server();
+
+    match manager() {
+        Some("launchd") => {
+            let log = config::data_dir()
+                .map(|dir| dir.join(LOG_FILE))
+                .ok_or("Could not determine data directory")?;
+            if let Some(parent) = log.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let domain = format!("gui/{}", uid()?);
+            // Reinstalling: unload the old definition first
+            let _ = control("launchctl", &["bootout", &format!("{}/{}", domain, LABEL)]);
+            write_private(&path, &plist(&args, &dir, &env, &log.display().to_string()))?;
+            control(
+                "launchctl",
+                &["bootstrap", &domain, &path.display().to_string()],
+            )?;
+        }
+        _ => {
+            write_private(&path, &systemd_unit(&args, &dir, &env))?;
+            control("systemctl", &["--user", "daemon-reload"])?;
+            control("systemctl", &["--user", "enable", UNIT])?;
+            control("systemctl", &["--user", "restart", UNIT])?;
+        }
+    }
+    println!("[Claude PM] Installed server service at {}", path.display());
+    Ok(get_server_service_status())
+}
+
+/// Stop and remove the service; the app goes back to running the server itself
+#[tauri::command]
+pub fn uninstall_server_service() -> Result<ServiceStatus, Error> {
+    let path = unit_path().ok_or_else(unsupported)?;
+    if path.exists() {
+        match manager() {
+            Some("launchd") => {
+                let target = format!("gui/{}/{}", uid()?, LABEL);
+                let _ = control("launchctl", &["bootout", &target]);
+            }
+            _ => {
+                let _ = control("systemctl", &["--user", "disable", "--now", UNIT]);
+            }
+        }
+        fs::remove_file(&path)
+            .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+        if manager() == Some("systemd") {
+            let _ = control("systemctl", &["--user", "daemon-reload"]);
+        }
+    }
+    crate::auth::clear_persistent_token()?;
+    println!("[Claude PM] Removed server service");
+    Ok(get_server_service_status())
+}
+
+#[tauri::command]
+pub fn get_server_service_status() -> ServiceStatus {
+    let running = match manager() {
+        Some("launchd") => {
+            uid()
+                .map(|uid| {
+                    control("launchctl", &["print", &format!("gui/{}/{}", uid, LABEL)]).is_ok()
+                })
+                .unwrap_or(false)
+                && crate::is_server_running(profiles::active().port)
+        }
+        Some(_) => control("systemctl", &["--user", "is-active", "--quiet", UNIT]).is_ok(),
+        None => false,
+    };
+    ServiceStatus {
+        supported: manager().is_some(),
+        installed: is_installed(),
+        running: is_installed() && running,
+        manager: manager(),
+        path: unit_path().map(|p| p.display().to_string()),
+    }
+}
@@ -0,0 +1,393 @@
+//! Portable bundles of a Claude session, for handing an investigation to someone else
+//!
+//! Layout inside the zip:
+//! - `manifest.json`: the session, its task and project, and the checkout it ran in
+//! - `transcript.jsonl`: the Claude transcript as written
+//! - `prompts.md`: the user's prompts in order, for reading without the app
+//! - `changes.diff`: uncommitted changes in the checkout (`git diff HEAD --binary`)
+//! - `untracked/…`: new files git doesn't track yet (and doesn't ignore)
+//!
+//! Importing creates the task in the chosen project and places the transcript where
+//! `claude --resume <session>` finds it for that project's repo. The changes are only
+//! applied when asked, with `git apply --3way` so conflicts surface as conflict markers.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use crate::error::Error;
+use crate::process::{self, CancelToken};
+use crate::store::{self, NewTask, Project, Task};
+use crate::{search, transcript_tail};
+
+/// Bump when the archive layout changes incompatibly
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST: &str = "manifest.json";
+const TRANSCRIPT: &str = "transcript.jsonl";
+const PROMPTS: &str = "prompts.md";
+const CHANGES: &str = "changes.diff";
+const UNTRACKED: &str = "untracked/";
+const GIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: i64,
+    pub session_id: String,
+    pub project: Option<Project>,
+    pub task: Option<Task>,
+    /// Checkout the session ran in, as recorded in the transcript
+    pub cwd: Option<String>,
+    pub branch: Option<String>,
+    /// Commit the diff applies on top of
+    pub head: Option<String>,
+    pub untracked: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleImport {
+    pub manifest: BundleManifest,
+    pub task_id: Option<String>,
+    /// Where the transcript was placed; `None` if one with the same id already existed
+    pub transcript_path: Option<String>,
+    /// The changes were applied to the project's repo
+    pub applied: bool,
+    /// Files left with conflict markers by the three-way apply
+    pub conflicts: Vec<String>,
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = process::run(
+        Command::new("git").args(args).current_dir(dir),
+        GIT_TIMEOUT,
+        &CancelToken::default(),
+    )
+    .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+fn git_text(dir: &Path, args: &[&str]) -> Option<String> {
+    git(dir, args)
+        .ok()
+        .map(|out| String::from_utf8_lossy(&out).trim().to_string())
+        .filter(|out| !out.is_empty())
+}
+
+/// A field of the first transcript line that has it (`cwd`, `gitBranch`)
+fn transcript_field(transcript: &str, field: &str) -> Option<String> {
+    transcript
+        .lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find_map(|line| line[field].as_str().map(str::to_string))
+}
+
+fn prompts(transcript: &str) -> String {
+    let mut out = String::from("# Prompts\n");
+    for entry in transcript.lines().flat_map(transcript_tail::parse_line) {
+        if let transcript_tail::EntryKind::Message { role, text } = entry.kind {
+            if role == "user" {
+                out.push_str(&format!(
+                    "\n## {}\n\n{}\n",
+                    entry.timestamp.unwrap_or_default(),
+                    text.trim()
+                ));
+            }
+        }
+    }
+    out
+}
+
+fn add_bytes(zip: &mut ZipWriter<File>, name: &str, contents: &[u8]) -> Result<(), String> {
+    zip.start_file(name, SimpleFileOptions::default())
+        .map_err(|e| e.to_string())?;
+    zip.write_all(contents).map_err(|e| e.to_string())
+}
+
+/// The stored session, or failing that its project from the transcript's directory
+fn session_context(session_id: &str, transcript: &Path) -> (Option<Project>, Option<Task>) {
+    store::with_conn(|conn| {
+        if let Some(session) = store::get_session(conn, session_id)? {
+            let task = match session.task_id {
+                Some(ref id) => store::get_task(conn, id)?,
+                None => None,
+            };
+            return Ok((store::get_project(conn, &session.project_id)?, task));
+        }
+        let project = transcript
+            .parent()
+            .and_then(|dir| dir.file_name())
+            .and_then(|name| search::project_for_transcript_dir(conn, &name.to_string_lossy()));
+        Ok((
+            match project {
+                Some(id) => store::get_project(conn, &id)?,
+                None => None,
+            },
+            None,
+        ))
+    })
+    .unwrap_or_default()
+}
+
+/// Relative path of an untracked file or archive entry; rejects anything escaping the repo
+fn safe_relative(name: &str) -> Result<&str, String> {
+    if name.is_empty()
+        || name.starts_with('/')
+        || name.contains('\\')
+        || name.split('/').any(|part| part == "..")
+    {
+        return Err(format!("Unsafe path in bundle: {}", name));
+    }
+    Ok(name)
+}
+
+fn export(session_id: &str, path: &Path) -> Result<BundleManifest, Error> {
+    let transcript_path = transcript_tail::find_transcript(session_id)
+        .ok_or_else(|| Error::NotFound(format!("No transcript for session {}", session_id)))?;
+    let transcript = fs::read_to_string(&transcript_path)
+        .map_err(|e| format!("Failed to read transcript: {}", e))?;
+    let (project, task) = session_context(session_id, &transcript_path);
+
+    let cwd = transcript_field(&transcript, "cwd")
+        .or_else(|| project.as_ref().and_then(|p| p.repo_path.clone()))
+        .filter(|dir| Path::new(dir).is_dir());
+    let repo = cwd.as_deref().map(Path::new);
+    let head = repo.and_then(|dir| git_text(dir, &["rev-parse", "HEAD"]));
+    let diff = match (repo, &head) {
+        (Some(dir), Some(_)) => git(dir, &["diff", "HEAD", "--binary"])?,
+        _ => Vec::new(),
+    };
+    let untracked: Vec<String> = repo
+        .and_then(|dir| git_text(dir, &["ls-files", "--others", "--exclude-standard"]))
+        .map(|out| out.lines().map(str::to_string).collect())
+        .unwrap_or_default();
+
+    let manifest = BundleManifest {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: store::now_ms(),
+        session_id: session_id.to_string(),
+        project,
+        task,
+        branch: transcript_field(&transcript, "gitBranch")
+            .or_else(|| repo.and_then(|dir| git_text(dir, &["branch", "--show-current"]))),
+        cwd: cwd.clone(),
+        head,
+        untracked: untracked.clone(),
+    };
+
+    let file =
+        File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    add_bytes(
+        &mut zip,
+        MANIFEST,
+        &serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?,
+    )?;
+    add_bytes(&mut zip, TRANSCRIPT, transcript.as_bytes())?;
+    add_bytes(&mut zip, PROMPTS, prompts(&transcript).as_bytes())?;
+    add_bytes(&mut zip, CHANGES, &diff)?;
+    if let Some(dir) = repo {
+        for name in &untracked {
+            let contents =
+                fs::read(dir.join(name)).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+            add_bytes(&mut zip, &format!("{}{}", UNTRACKED, name), &contents)?;
+        }
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to finish bundle: {}", e))?;
+    println!(
+        "[Claude PM] Exported session {} to {}",
+        session_id,
+        path.display()
+    );
+    Ok(manifest)
+}
+
+fn read_entry(zip: &mut ZipArchive<File>, name: &str) -> Result<Vec<u8>, String> {
+    let mut entry = zip
+        .by_name(name)
+        .map_err(|_| format!("Bundle is missing {}", name))?;
+    let mut contents = Vec::new();
+    entry
+        .read_to_end(&mut contents)
+        .map_err(|e| format!("Failed to read {} from bundle: {}", name, e))?;
+    Ok(contents)
+}
+
+/// Apply the bundle's changes to `repo`, returning files left with conflicts
+fn apply_changes(
+    zip: &mut ZipArchive<File>,
+    manifest: &BundleManifest,
+    repo: &Path,
+) -> Result<Vec<String>, Error> {
+    for name in &manifest.untracked {
+        if repo.join(safe_relative(name)?).exists() {
+            return Err(Error::InvalidInput(format!(
+                "{} already exists in {}",
+                name,
+                repo.display()
+            )));
+        }
+    }
+
+    let diff = read_entry(zip, CHANGES)?;
+    if !diff.is_empty() {
+        let patch = std::env::temp_dir().join(format!("claudepm-bundle-{}.diff", store::new_id()));
+        fs::write(&patch, &diff).map_err(|e| format!("Failed to write patch: {}", e))?;
+        let patch_arg = patch.to_string_lossy().to_string();
+        // --3way needs the blobs the diff was made against; without them fall back to a plain apply
+        let applied = git(
+            repo,
+            &["apply", "--3way", "--whitespace=nowarn", &patch_arg],
+        )
+        .or_else(|_| git(repo, &["apply", "--whitespace=nowarn", &patch_arg]));
+        let _ = fs::remove_file(&patch);
+        if let Err(e) = applied {
+            // A three-way apply that conflicted still wrote markers; anything else is a failure
+            if git_text(repo, &["diff", "--name-only", "--diff-filter=U"]).is_none() {
+                return Err(Error::Internal(e));
+            }
+        }
+    }
+
+    for name in &manifest.untracked {
+        let contents = read_entry(zip, &format!("{}{}", UNTRACKED, name))?;
+        let target = repo.join(safe_relative(name)?);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&target, contents)
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+    }
+    Ok(git_text(repo, &["diff", "--name-only", "--diff-filter=U"])
+        .map(|out| out.lines().map(str::to_string).collect())
+        .unwrap_or_default())
+}
+
+fn import(path: &Path, project_id: Option<String>, apply: bool) -> Result<BundleImport, Error> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| format!("Not a valid bundle: {}", e))?;
+    let manifest: BundleManifest = serde_json::from_slice(&read_entry(&mut zip, MANIFEST)?)
+        .map_err(|e| format!("Invalid bundle manifest: {}", e))?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(Error::InvalidInput(format!(
+            "Bundle was made by a newer version of Claude PM ({}); update before importing",
+            manifest.app_version
+        )));
+    }
+    safe_relative(&manifest.session_id)
+        .ok()
+        .filter(|id| !id.contains('/'))
+        .ok_or_else(|| Error::InvalidInput("Invalid session id in bundle".to_string()))?;
+
+    // The chosen project, else one with the same name as the sender's
+    let projects = store::list_projects()?;
+    let project = match project_id {
+        Some(id) => projects.into_iter().find(|p| p.id == id),
+        None => manifest
+            .project
+            .as_ref()
+            .and_then(|sent| projects.into_iter().find(|p| p.name == sent.name)),
+    }
+    .ok_or_else(|| Error::NotFound("Choose a project to import the session into".to_string()))?;
+    let repo = project
+        .repo_path
+        .as_deref()
+        .map(Path::new)
+        .filter(|dir| dir.is_dir());
+    if apply && repo.is_none() {
+        return Err(Error::InvalidInput(format!(
+            "{} has no repository to apply the changes to",
+            project.name
+        )));
+    }
+
+    // Claude looks transcripts up by the directory it's started in
+    let dir_name = match (repo, &manifest.cwd) {
+        (Some(dir), _) => dir.display().to_string().replace(['/', '.'], "-"),
+        (None, Some(cwd)) => cwd.replace(['/', '.'], "-"),
+        (None, None) => "imported".to_string(),
+    };
+    let transcript_dir: PathBuf = search::transcripts_dir()
+        .ok_or("Could not determine home directory")?
+        .join(dir_name);
+    let transcript_path = transcript_dir.join(format!("{}.jsonl", manifest.session_id));
+    let transcript_path = if transcript_path.exists() {
+        None
+    } else {
+        fs::create_dir_all(&transcript_dir).map_err(|e| e.to_string())?;
+        fs::write(&transcript_path, read_entry(&mut zip, TRANSCRIPT)?)
+            .map_err(|e| format!("Failed to write transcript: {}", e))?;
+        Some(transcript_path.display().to_string())
+    };
+
+    let conflicts = match repo.filter(|_| apply) {
+        Some(dir) => apply_changes(&mut zip, &manifest, dir)?,
+        None => Vec::new(),
+    };
+
+    let task_id = match manifest.task {
+        Some(ref task) => Some(
+            store::create_task(NewTask {
+                project_id: project.id.clone(),
+                title: task.title.clone(),
+                description: task.description.clone(),
+                state: Some(task.state.clone()),
+            })?
+            .id,
+        ),
+        None => None,
+    };
+    println!(
+        "[Claude PM] Imported session {} into {}",
+        manifest.session_id, project.name
+    );
+    Ok(BundleImport {
+        manifest,
+        task_id,
+        transcript_path,
+        applied: apply,
+        conflicts,
+    })
+}
+
+/// Write a handoff bundle for a Claude session to `path`
+#[tauri::command]
+pub async fn export_session_bundle(
+    session_id: String,
+    path: String,
+) -> Result<BundleManifest, Error> {
+    tauri::async_runtime::spawn_blocking(move || export(&session_id, Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Import a bundle into `project_id` (by default the project with the sender's project
+/// name); with `apply_changes` its diff and new files are applied to the project repo
+#[tauri::command]
+pub async fn import_session_bundle(
+    path: String,
+    project_id: Option<String>,
+    apply_changes: bool,
+) -> Result<BundleImport, Error> {
+    tauri::async_runtime::spawn_blocking(move || {
+        import(Path::new(&path), project_id, apply_changes)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
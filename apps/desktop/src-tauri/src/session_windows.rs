@@ -0,0 +1,128 @@
+//! Dedicated windows for individual agent sessions
+//!
+//! Rust keeps a registry of which session is open in which window so that closes are
+//! cleaned up and deep links to a session focus its window instead of the main one.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+
+use crate::error::Error;
+use crate::windows::{focus_main_window, MAIN_WINDOW};
+
+/// session id -> window label
+static SESSION_WINDOWS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NavigateEvent {
+    path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionWindow {
+    pub session_id: String,
+    pub label: String,
+}
+
+fn label_for(session_id: &str) -> String {
+    // Window labels only allow alphanumerics and `-/:_`
+    let safe: String = session_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("session-{}", safe)
+}
+
+/// Extract the session id from a route like `/sessions/<id>` or `/sessions/<id>/terminal`
+fn session_from_path(path: &str) -> Option<&str> {
+    let rest = path.strip_prefix("/sessions/")?;
+    rest.split(['/', '?']).next().filter(|id| !id.is_empty())
+}
+
+/// Route a deep link: session links go to that session's window if one is open,
+/// everything else focuses the main window and emits `navigate` to it
+pub fn route_navigation(app: &AppHandle, path: &str) {
+    let label = session_from_path(path).and_then(|id| {
+        SESSION_WINDOWS
+            .lock()
+            .ok()
+            .and_then(|windows| windows.get(id).cloned())
+    });
+
+    if let Some(window) = label.and_then(|l| app.get_webview_window(&l)) {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+        return;
+    }
+
+    focus_main_window(app);
+    let _ = app.emit_to(
+        MAIN_WINDOW,
+        "navigate",
+        NavigateEvent {
+            path: path.to_string(),
+        },
+    );
+}
+
+/// Drop a closed window from the registry
+pub fn on_destroyed(label: &str) {
+    if let Ok(mut windows) = SESSION_WINDOWS.lock() {
+        windows.retain(|_, l| l != label);
+    }
+}
+
+/// Open (or focus) a window showing a single session's terminal and activity
+#[tauri::command]
+pub fn open_session_window(app: AppHandle, session_id: String) -> Result<(), Error> {
+    let label = label_for(&session_id);
+    if let Some(window) = app.get_webview_window(&label) {
+        let _ = window.unminimize();
+        return Ok(window.set_focus().map_err(|e| e.to_string())?);
+    }
+
+    let url = format!("index.html#/sessions/{}?window=session", session_id);
+    WebviewWindowBuilder::new(&app, &label, WebviewUrl::App(url.into()))
+        .title(format!("Claude PM — Session {}", session_id))
+        .inner_size(900.0, 640.0)
+        .min_inner_size(480.0, 320.0)
+        .build()
+        .map_err(|e| format!("Failed to open session window: {}", e))?;
+
+    SESSION_WINDOWS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(session_id, label);
+    // Its taskbar button starts out with its run's overlay
+    crate::queue_badge::refresh(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn close_session_window(app: AppHandle, session_id: String) -> Result<(), Error> {
+    match app.get_webview_window(&label_for(&session_id)) {
+        Some(window) => Ok(window.close().map_err(|e| e.to_string())?),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn list_session_windows() -> Result<Vec<SessionWindow>, Error> {
+    let windows = SESSION_WINDOWS.lock().map_err(|e| e.to_string())?;
+    Ok(windows
+        .iter()
+        .map(|(session_id, label)| SessionWindow {
+            session_id: session_id.clone(),
+            label: label.clone(),
+        })
+        .collect())
+}
@@ -0,0 +1,204 @@
+//! Configurable global shortcuts
+//!
+//! Each action has a default accelerator that can be overridden (or disabled) in the
+//! config file. Triggering a shortcut emits `shortcut-triggered` with the action name.
+
+use serde::Serialize;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Emitter, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::error::Error;
+use crate::windows::focus_main_window;
+use crate::{config, emergency_stop};
+use crate::{quick_capture, quick_switcher};
+
+/// Actions that can be bound, with their default accelerators
+const ACTIONS: &[(&str, &str)] = &[
+    ("quickSwitcher", "Alt+Space"),
+    ("newTask", "Alt+Shift+N"),
+    ("emergencyStop", "CommandOrControl+Alt+Shift+Escape"),
+    ("quickCapture", "Alt+Shift+C"),
+];
+
+/// Currently registered shortcuts and the action each one triggers
+static BINDINGS: Mutex<Vec<(Shortcut, String)>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutBinding {
+    pub action: String,
+    /// Empty when the action is disabled
+    pub accelerator: String,
+    /// False if the OS refused the registration (usually another app owns it)
+    pub registered: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TriggeredEvent<'a> {
+    action: &'a str,
+}
+
+/// Accelerator per action after applying config overrides
+fn configured_accelerators() -> Vec<(String, String)> {
+    let overrides = config::load().shortcuts;
+    ACTIONS
+        .iter()
+        .map(|(action, default)| {
+            let accelerator = overrides
+                .get(*action)
+                .cloned()
+                .unwrap_or_else(|| default.to_string());
+            (action.to_string(), accelerator)
+        })
+        .collect()
+}
+
+fn parse(accelerator: &str) -> Result<Shortcut, String> {
+    Shortcut::from_str(accelerator).map_err(|e| format!("Invalid shortcut {}: {}", accelerator, e))
+}
+
+fn trigger(app: &AppHandle, action: &str) {
+    match action {
+        "quickSwitcher" => {
+            if let Err(e) = quick_switcher::toggle_quick_switcher(app.clone()) {
+                eprintln!("[Claude PM] {}", e);
+            }
+        }
+        "newTask" => focus_main_window(app),
+        "emergencyStop" => {
+            let app = app.clone();
+            std::thread::spawn(move || emergency_stop::stop_all(&app));
+        }
+        "quickCapture" => {
+            let app = app.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = quick_capture::capture(&app) {
+                    eprintln!("[Claude PM] Quick capture failed: {}", e);
+                }
+            });
+        }
+        _ => {}
+    }
+    let _ = app.emit("shortcut-triggered", TriggeredEvent { action });
+}
+
+fn handle(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let action = BINDINGS.lock().ok().and_then(|bindings| {
+        bindings
+            .iter()
+            .find(|(bound, _)| bound == shortcut)
+            .map(|(_, action)| action.clone())
+    });
+    if let Some(action) = action {
+        trigger(app, &action);
+    }
+}
+
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(handle)
+        .build()
+}
+
+/// Register every configured shortcut; failures are logged and reported by `get_shortcuts`
+pub fn register_all(app: &AppHandle) {
+    let manager = app.global_shortcut();
+    let _ = manager.unregister_all();
+
+    let mut bindings = Vec::new();
+    for (action, accelerator) in configured_accelerators() {
+        if accelerator.is_empty() {
+            continue;
+        }
+        match parse(&accelerator) {
+            Ok(shortcut) => {
+                if let Err(e) = manager.register(shortcut) {
+                    eprintln!(
+                        "[Claude PM] Could not register {} for {}: {}",
+                        accelerator, action, e
+                    );
+                }
+                bindings.push((shortcut, action));
+            }
+            Err(e) => eprintln!("[Claude PM] {}", e),
+        }
+    }
+
+    if let Ok(mut current) = BINDINGS.lock() {
+        *current = bindings;
+    }
+}
+
+#[tauri::command]
+pub fn get_shortcuts(app: AppHandle) -> Vec<ShortcutBinding> {
+    let manager = app.global_shortcut();
+    configured_accelerators()
+        .into_iter()
+        .map(|(action, accelerator)| {
+            let registered = parse(&accelerator)
+                .map(|shortcut| manager.is_registered(shortcut))
+                .unwrap_or(false);
+            ShortcutBinding {
+                action,
+                accelerator,
+                registered,
+            }
+        })
+        .collect()
+}
+
+/// Bind `action` to `accelerator` (e.g. `Alt+Space`); `None` disables the action
+#[tauri::command]
+pub fn set_shortcut(
+    app: AppHandle,
+    action: String,
+    accelerator: Option<String>,
+) -> Result<(), Error> {
+    if !ACTIONS.iter().any(|(known, _)| *known == action) {
+        return Err(Error::InvalidInput(format!(
+            "Unknown shortcut action: {}",
+            action
+        )));
+    }
+    let accelerator = accelerator.unwrap_or_default();
+
+    if !accelerator.is_empty() {
+        let shortcut = parse(&accelerator)?;
+        for (other, other_accelerator) in configured_accelerators() {
+            if other == action || other_accelerator.is_empty() {
+                continue;
+            }
+            if parse(&other_accelerator).is_ok_and(|bound| bound == shortcut) {
+                return Err(Error::InvalidInput(format!(
+                    "{} is already used by {}",
+                    accelerator, other
+                )));
+            }
+        }
+
+        // Try the new shortcut before persisting so OS-level conflicts are reported
+        let manager = app.global_shortcut();
+        if !manager.is_registered(shortcut) {
+            manager.register(shortcut).map_err(|e| {
+                format!(
+                    "{} is unavailable (in use by another app?): {}",
+                    accelerator, e
+                )
+            })?;
+            let _ = manager.unregister(shortcut);
+        }
+    }
+
+    config::update(|c| {
+        c.shortcuts.insert(action, accelerator);
+    })?;
+    register_all(&app);
+    Ok(())
+}
@@ -0,0 +1,292 @@
+//! Pre-run snapshots of a project's repository, and rolling a run back to one
+//!
+//! Before an agent run starts (see `orchestrator`), the working tree — staged, unstaged
+//! and untracked files, minus ignored ones — is written as a commit on top of `HEAD`
+//! through a scratch index, so the user's index and stashes are left alone. The commit is
+//! kept alive by a ref under `refs/claudepm/snapshots/`. `rollback_run` first snapshots
+//! the current state (so a rollback can itself be undone), then checks out the pre-run
+//! branch and commit and restores the snapshot's files; what was staged comes back
+//! unstaged. The newest snapshots per task are kept (10 unless `snapshots_per_task`),
+//! older ones are pruned along with their refs. Projects that aren't git repositories,
+//! including Mercurial and Jujutsu ones (see `vcs`), aren't snapshotted.
+//!
+//! Events:
+//! - `run-rolled-back` with the [`Snapshot`] that was restored
+
+use rusqlite::{params, OptionalExtension, Row};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::process::{self, CancelToken};
+use crate::{config, orchestrator, store, vcs};
+
+const GIT_TIMEOUT: Duration = Duration::from_secs(120);
+const DEFAULT_KEEP: usize = 10;
+const REF_PREFIX: &str = "refs/claudepm/snapshots/";
+/// Snapshot commits are made without touching the user's git identity
+const IDENTITY: &[(&str, &str)] = &[
+    ("GIT_AUTHOR_NAME", "Claude PM"),
+    ("GIT_AUTHOR_EMAIL", "claudepm@localhost"),
+    ("GIT_COMMITTER_NAME", "Claude PM"),
+    ("GIT_COMMITTER_EMAIL", "claudepm@localhost"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    pub id: String,
+    pub run_id: String,
+    pub task_id: String,
+    pub project_id: String,
+    pub repo: String,
+    /// `preRun`, or `preRollback` for the state a rollback replaced
+    pub kind: String,
+    /// Commit checked out when the snapshot was taken
+    pub head: String,
+    /// Branch checked out, `None` when detached
+    pub branch: Option<String>,
+    /// Snapshot commit holding the working tree
+    pub commit: String,
+    /// Files that differed from `head`
+    pub files: usize,
+    pub created_at: i64,
+}
+
+impl Snapshot {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            run_id: row.get("run_id")?,
+            task_id: row.get("task_id")?,
+            project_id: row.get("project_id")?,
+            repo: row.get("repo")?,
+            kind: row.get("kind")?,
+            head: row.get("head")?,
+            branch: row.get("branch")?,
+            commit: row.get("commit_sha")?,
+            files: row.get::<_, i64>("files")? as usize,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+fn git(dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Result<String, String> {
+    let mut cmd = Command::new("git");
+    cmd.args(args).current_dir(dir).envs(env.iter().copied());
+    let output =
+        process::run(&mut cmd, GIT_TIMEOUT, &CancelToken::default()).map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn save(snapshot: &Snapshot) -> Result<(), String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO run_snapshots (id, run_id, task_id, project_id, repo, kind, head, branch, commit_sha, files, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                snapshot.id,
+                snapshot.run_id,
+                snapshot.task_id,
+                snapshot.project_id,
+                snapshot.repo,
+                snapshot.kind,
+                snapshot.head,
+                snapshot.branch,
+                snapshot.commit,
+                snapshot.files as i64,
+                snapshot.created_at
+            ],
+        )
+        .map(|_| ())
+    })
+}
+
+/// Snapshots for a task, newest first
+fn for_task(task_id: &str) -> Result<Vec<Snapshot>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT * FROM run_snapshots WHERE task_id = ?1 ORDER BY created_at DESC, rowid DESC",
+        )?;
+        let rows = stmt.query_map([task_id], Snapshot::from_row)?;
+        rows.collect()
+    })
+}
+
+fn snapshot(
+    kind: &str,
+    run_id: &str,
+    task_id: &str,
+    project_id: &str,
+    repo: &Path,
+) -> Result<Snapshot, String> {
+    // Checking out and cleaning behind jj's back would confuse it
+    vcs::require_git(repo, "Pre-run snapshots").map_err(|e| e.to_string())?;
+    let head = git(repo, &["rev-parse", "--verify", "-q", "HEAD"], &[])
+        .map_err(|_| format!("{} is not a git repository with commits", repo.display()))?;
+    let branch = git(repo, &["symbolic-ref", "-q", "--short", "HEAD"], &[])
+        .ok()
+        .filter(|branch| !branch.is_empty());
+    let id = store::new_id();
+
+    // A scratch index so the user's staging area isn't disturbed
+    let index = std::env::temp_dir().join(format!("claudepm-snapshot-{}.index", id));
+    let index_path = index.display().to_string();
+    let env = [("GIT_INDEX_FILE", index_path.as_str())];
+    let tree = git(repo, &["read-tree", &head], &env)
+        .and_then(|_| git(repo, &["add", "-A"], &env))
+        .and_then(|_| git(repo, &["write-tree"], &env));
+    let _ = fs::remove_file(&index);
+    let tree = tree?;
+
+    let message = format!("Claude PM snapshot ({}) for run {}", kind, run_id);
+    let commit = git(
+        repo,
+        &["commit-tree", &tree, "-p", &head, "-m", &message],
+        IDENTITY,
+    )?;
+    git(
+        repo,
+        &["update-ref", &format!("{}{}", REF_PREFIX, id), &commit],
+        &[],
+    )?;
+    let files = git(repo, &["diff", "--name-only", &head, &commit], &[])?
+        .lines()
+        .count();
+
+    let snapshot = Snapshot {
+        id,
+        run_id: run_id.to_string(),
+        task_id: task_id.to_string(),
+        project_id: project_id.to_string(),
+        repo: repo.display().to_string(),
+        kind: kind.to_string(),
+        head,
+        branch,
+        commit,
+        files,
+        created_at: store::now_ms(),
+    };
+    save(&snapshot)?;
+    prune(task_id);
+    Ok(snapshot)
+}
+
+/// The snapshot taken before run `run_id` started
+pub fn pre_run(run_id: &str) -> Result<Option<Snapshot>, String> {
+    store::with_conn(|conn| {
+        conn.query_row(
+            "SELECT * FROM run_snapshots WHERE run_id = ?1 AND kind = 'preRun' ORDER BY created_at DESC LIMIT 1",
+            [run_id],
+            Snapshot::from_row,
+        )
+        .optional()
+    })
+}
+
+/// Snapshot `repo` before run `run_id` starts
+pub fn take(run_id: &str, task_id: &str, project_id: &str, repo: &str) -> Result<Snapshot, String> {
+    let snapshot = snapshot("preRun", run_id, task_id, project_id, Path::new(repo))?;
+    println!(
+        "[Claude PM] Snapshotted {} before run {} ({} changed files)",
+        repo, run_id, snapshot.files
+    );
+    Ok(snapshot)
+}
+
+/// Drop all but the newest snapshots for `task_id`
+fn prune(task_id: &str) {
+    let keep = config::load()
+        .snapshots_per_task
+        .unwrap_or(DEFAULT_KEEP)
+        .max(1);
+    let Ok(snapshots) = for_task(task_id) else {
+        return;
+    };
+    for old in snapshots.into_iter().skip(keep) {
+        let _ = git(
+            Path::new(&old.repo),
+            &["update-ref", "-d", &format!("{}{}", REF_PREFIX, old.id)],
+            &[],
+        );
+        let _ = store::with_conn(|conn| {
+            conn.execute("DELETE FROM run_snapshots WHERE id = ?1", [&old.id])
+        });
+    }
+}
+
+fn restore(snapshot: &Snapshot) -> Result<(), String> {
+    let repo = Path::new(&snapshot.repo);
+    match &snapshot.branch {
+        Some(branch) => git(repo, &["checkout", "-q", "-f", branch], &[])?,
+        None => git(
+            repo,
+            &["checkout", "-q", "-f", "--detach", &snapshot.head],
+            &[],
+        )?,
+    };
+    git(repo, &["reset", "-q", "--hard", &snapshot.head], &[])?;
+    // Files the run created; the ones that existed before come back from the snapshot
+    git(repo, &["clean", "-q", "-f", "-d"], &[])?;
+    git(repo, &["checkout", &snapshot.commit, "--", "."], &[])?;
+    git(repo, &["reset", "-q"], &[])?;
+    Ok(())
+}
+
+/// Restore the repository to how it was before run `run_id`
+fn rollback(app: &AppHandle, run_id: &str) -> Result<Snapshot, Error> {
+    if orchestrator::is_active(run_id) {
+        return Err(Error::InvalidInput(
+            "Cancel the run before rolling it back".to_string(),
+        ));
+    }
+    let pre_run = pre_run(run_id)?
+        .ok_or_else(|| Error::NotFound(format!("No pre-run snapshot for run {}", run_id)))?;
+
+    snapshot(
+        "preRollback",
+        run_id,
+        &pre_run.task_id,
+        &pre_run.project_id,
+        Path::new(&pre_run.repo),
+    )
+    .map_err(|e| {
+        format!(
+            "Failed to save the current state before rolling back: {}",
+            e
+        )
+    })?;
+    restore(&pre_run).map_err(|e| format!("Rollback failed: {}", e))?;
+    println!(
+        "[Claude PM] Rolled {} back to before run {}",
+        pre_run.repo, run_id
+    );
+    let _ = app.emit("run-rolled-back", &pre_run);
+    Ok(pre_run)
+}
+
+/// Snapshots taken for a task's runs, newest first
+#[tauri::command]
+pub fn list_task_snapshots(task_id: String) -> Result<Vec<Snapshot>, Error> {
+    for_task(&task_id).map_err(Error::from)
+}
+
+/// Put the run's repository back to its pre-run state; the replaced state is kept as a
+/// `preRollback` snapshot
+#[tauri::command]
+pub async fn rollback_run(app: AppHandle, run_id: String) -> Result<Snapshot, Error> {
+    tauri::async_runtime::spawn_blocking(move || rollback(&app, &run_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,188 @@
+//! Event sounds (task completed, agent failed, approval needed) with a global mute
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::config;
+use crate::error::Error;
+
+/// Event types that can have a sound; they match notification categories
+pub const SOUND_EVENTS: &[&str] = &["completed", "failed", "approval"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SoundConfig {
+    pub muted: bool,
+    /// Sound per event: a system sound name or a file path; empty string disables it.
+    /// Events missing from the map use the platform default.
+    pub events: BTreeMap<String, String>,
+}
+
+#[cfg(target_os = "macos")]
+const SYSTEM_SOUNDS_DIR: &str = "/System/Library/Sounds";
+#[cfg(not(target_os = "macos"))]
+const SYSTEM_SOUNDS_DIR: &str = "/usr/share/sounds/freedesktop/stereo";
+
+fn default_sound(event: &str) -> Option<&'static str> {
+    let (completed, failed, approval) = if cfg!(target_os = "macos") {
+        ("Glass", "Basso", "Ping")
+    } else if cfg!(target_os = "windows") {
+        (
+            "Windows Notify System Generic",
+            "Windows Critical Stop",
+            "Windows Exclamation",
+        )
+    } else {
+        ("complete", "dialog-error", "message-new-instant")
+    };
+
+    match event {
+        "completed" => Some(completed),
+        "failed" => Some(failed),
+        "approval" => Some(approval),
+        _ => None,
+    }
+}
+
+/// Resolve a sound name to a file: paths are used as-is, names are looked up as system sounds
+fn resolve(sound: &str) -> Option<PathBuf> {
+    if sound.contains('/') || sound.contains('\\') {
+        let path = PathBuf::from(sound);
+        return path.exists().then_some(path);
+    }
+
+    if cfg!(target_os = "windows") {
+        let path = PathBuf::from(format!("C:\\Windows\\Media\\{}.wav", sound));
+        return path.exists().then_some(path);
+    }
+
+    ["aiff", "oga", "wav"]
+        .iter()
+        .map(|ext| PathBuf::from(SYSTEM_SOUNDS_DIR).join(format!("{}.{}", sound, ext)))
+        .find(|path| path.exists())
+}
+
+fn play_file(path: PathBuf) -> Result<(), String> {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("afplay");
+        cmd.arg(&path);
+        cmd
+    } else if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("powershell");
+        cmd.args(["-NoProfile", "-Command"]).arg(format!(
+            "(New-Object Media.SoundPlayer '{}').PlaySync()",
+            path.display()
+        ));
+        cmd
+    } else {
+        let mut cmd = Command::new("paplay");
+        cmd.arg(&path);
+        cmd
+    };
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to play sound: {}", e))?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// Play the configured sound for an event, unless muted or disabled
+pub fn play_for_event(event: &str) {
+    let sounds = config::load().sounds;
+    if sounds.muted {
+        return;
+    }
+
+    let sound = match sounds.events.get(event) {
+        Some(sound) => sound.as_str(),
+        None => match default_sound(event) {
+            Some(sound) => sound,
+            None => return,
+        },
+    };
+    if sound.is_empty() {
+        return;
+    }
+
+    match resolve(sound) {
+        Some(path) => {
+            if let Err(e) = play_file(path) {
+                eprintln!("[Claude PM] {}", e);
+            }
+        }
+        None => eprintln!("[Claude PM] Sound not found: {}", sound),
+    }
+}
+
+#[tauri::command]
+pub fn get_sound_settings() -> SoundConfig {
+    let mut sounds = config::load().sounds;
+    for event in SOUND_EVENTS {
+        if let (false, Some(default)) = (sounds.events.contains_key(*event), default_sound(event)) {
+            sounds.events.insert(event.to_string(), default.to_string());
+        }
+    }
+    sounds
+}
+
+/// Names of the built-in system sounds that can be chosen per event
+#[tauri::command]
+pub fn list_system_sounds() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(SYSTEM_SOUNDS_DIR)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| {
+                    e.path()
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().to_string())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Set the sound for an event; `None` restores the default, an empty string silences it
+#[tauri::command]
+pub fn set_event_sound(event: String, sound: Option<String>) -> Result<(), Error> {
+    if !SOUND_EVENTS.contains(&event.as_str()) {
+        return Err(Error::InvalidInput(format!(
+            "Unknown sound event: {}",
+            event
+        )));
+    }
+    config::update(|c| match sound {
+        Some(sound) => {
+            c.sounds.events.insert(event, sound);
+        }
+        None => {
+            c.sounds.events.remove(&event);
+        }
+    })
+    .map(|_| ())
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn set_sounds_muted(muted: bool) -> Result<(), Error> {
+    config::update(|c| c.sounds.muted = muted)
+        .map(|_| ())
+        .map_err(Error::from)
+}
+
+/// Play a sound by name or path, ignoring mute; used by the settings UI to preview
+#[tauri::command]
+pub fn preview_sound(sound: String) -> Result<(), Error> {
+    let path =
+        resolve(&sound).ok_or_else(|| Error::NotFound(format!("Sound not found: {}", sound)))?;
+    play_file(path).map_err(Error::from)
+}
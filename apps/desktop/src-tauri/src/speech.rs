@@ -0,0 +1,306 @@
+//! Spoken announcements for agent events
+//!
+//! Text is spoken with the platform's speech tool: `say` on macOS, System.Speech via
+//! PowerShell on Windows, and `spd-say` or `espeak` on Linux. Utterances are queued so
+//! they don't talk over each other. Announcements are off until enabled, and each event
+//! topic can be turned off separately.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::{Child, Command};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::event_bus::{self, AppEvent, Filter, Topic};
+use crate::process;
+use crate::{config, store};
+
+/// Topics that can be announced
+const SPOKEN_TOPICS: &[Topic] = &[
+    Topic::TaskCompleted,
+    Topic::AgentBlocked,
+    Topic::ServerCrashed,
+];
+/// Longer prompts are cut so an announcement stays short
+const MAX_SPOKEN_CHARS: usize = 160;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SpeechSettings {
+    pub enabled: bool,
+    /// Voice name as listed by `list_voices`; the system voice when unset
+    pub voice: Option<String>,
+    /// Words per minute; the system rate when unset
+    pub rate: Option<u32>,
+    /// Per-topic toggles; topics missing from the map are announced
+    pub events: BTreeMap<Topic, bool>,
+}
+
+struct Utterance {
+    text: String,
+    voice: Option<String>,
+    rate: Option<u32>,
+}
+
+static QUEUE: OnceLock<Mutex<Sender<Utterance>>> = OnceLock::new();
+static SPEAKING: Mutex<Option<Child>> = Mutex::new(None);
+
+fn command(utterance: &Utterance) -> Option<Command> {
+    if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("say");
+        if let Some(voice) = &utterance.voice {
+            cmd.args(["-v", voice]);
+        }
+        if let Some(rate) = utterance.rate {
+            cmd.args(["-r", &rate.to_string()]);
+        }
+        cmd.arg(&utterance.text);
+        return Some(cmd);
+    }
+    if cfg!(target_os = "windows") {
+        let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+        let mut script = "Add-Type -AssemblyName System.Speech; $s = New-Object System.Speech.Synthesis.SpeechSynthesizer;".to_string();
+        if let Some(voice) = &utterance.voice {
+            script.push_str(&format!(" $s.SelectVoice({});", quote(voice)));
+        }
+        if let Some(rate) = utterance.rate {
+            // -10..10, with 0 at roughly 180 words per minute
+            let rate = ((rate as i32 - 180) / 20).clamp(-10, 10);
+            script.push_str(&format!(" $s.Rate = {};", rate));
+        }
+        script.push_str(&format!(" $s.Speak({})", quote(&utterance.text)));
+        let mut cmd = Command::new("powershell");
+        cmd.args(["-NoProfile", "-Command", &script]);
+        return Some(cmd);
+    }
+    if let Some(espeak) = process::which("espeak-ng").or_else(|| process::which("espeak")) {
+        let mut cmd = Command::new(espeak);
+        if let Some(voice) = &utterance.voice {
+            cmd.args(["-v", voice]);
+        }
+        if let Some(rate) = utterance.rate {
+            cmd.args(["-s", &rate.to_string()]);
+        }
+        cmd.arg(&utterance.text);
+        return Some(cmd);
+    }
+    let mut cmd = Command::new(process::which("spd-say")?);
+    // Waits until spoken so the queue stays in order
+    cmd.arg("-w");
+    if let Some(voice) = &utterance.voice {
+        cmd.args(["-y", voice]);
+    }
+    cmd.arg(&utterance.text);
+    Some(cmd)
+}
+
+fn say(utterance: Utterance) {
+    let Some(mut cmd) = command(&utterance) else {
+        eprintln!("[Claude PM] No speech synthesizer found");
+        return;
+    };
+    let child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("[Claude PM] Failed to speak: {}", e);
+            return;
+        }
+    };
+    let pid = child.id();
+    if let Ok(mut speaking) = SPEAKING.lock() {
+        *speaking = Some(child);
+    }
+    // Wait without holding the lock so `stop_speaking` can kill it
+    loop {
+        let done = match SPEAKING.lock() {
+            Ok(mut speaking) => match speaking.as_mut().filter(|c| c.id() == pid) {
+                Some(child) => child.try_wait().ok().flatten().is_some(),
+                None => true,
+            },
+            Err(_) => true,
+        };
+        if done {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    if let Ok(mut speaking) = SPEAKING.lock() {
+        if speaking.as_ref().is_some_and(|c| c.id() == pid) {
+            *speaking = None;
+        }
+    }
+}
+
+fn queue() -> &'static Mutex<Sender<Utterance>> {
+    QUEUE.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<Utterance>();
+        thread::spawn(move || {
+            for utterance in rx {
+                say(utterance);
+            }
+        });
+        Mutex::new(tx)
+    })
+}
+
+fn enqueue(text: String, voice: Option<String>, rate: Option<u32>) -> Result<(), String> {
+    queue()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .send(Utterance { text, voice, rate })
+        .map_err(|e| e.to_string())
+}
+
+fn short(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= MAX_SPOKEN_CHARS {
+        return text.to_string();
+    }
+    text.chars().take(MAX_SPOKEN_CHARS).collect::<String>() + "…"
+}
+
+/// What to say for an event, if it's one worth announcing
+fn phrase(event: &AppEvent) -> Option<String> {
+    match event {
+        AppEvent::TaskCompleted { task } => Some(format!("Task {} finished", short(&task.title))),
+        AppEvent::AgentBlocked { target, prompt } => {
+            // Name the task rather than reading out a tmux target
+            let task = store::with_conn(|conn| {
+                let Some(session) = store::open_session_for_pane(conn, target)? else {
+                    return Ok(None);
+                };
+                match session.task_id {
+                    Some(id) => store::get_task(conn, &id),
+                    None => Ok(None),
+                }
+            })
+            .ok()
+            .flatten();
+            Some(match task {
+                Some(task) => format!("{} needs input: {}", short(&task.title), short(prompt)),
+                None => format!("An agent needs input: {}", short(prompt)),
+            })
+        }
+        AppEvent::ServerCrashed { .. } => Some("The Claude PM server crashed".to_string()),
+        AppEvent::TaskUnblocked { .. }
+        | AppEvent::AgentFailed { .. }
+        | AppEvent::ServerStatus(_)
+        | AppEvent::ServerLifecycle { .. } => None,
+    }
+}
+
+/// Announce enabled events from the bus
+pub fn start() {
+    event_bus::subscribe(Filter::topics(SPOKEN_TOPICS), |envelope| {
+        let settings = config::load().speech;
+        let topic = envelope.event.topic();
+        if !settings.enabled || !settings.events.get(&topic).copied().unwrap_or(true) {
+            return;
+        }
+        if let Some(text) = phrase(&envelope.event) {
+            let _ = enqueue(text, settings.voice, settings.rate);
+        }
+    });
+}
+
+/// Speak `text` now (after anything already queued); voice and rate default to the settings
+#[tauri::command]
+pub fn speak(text: String, voice: Option<String>, rate: Option<u32>) -> Result<(), Error> {
+    if text.trim().is_empty() {
+        return Err(Error::InvalidInput("Nothing to say".to_string()));
+    }
+    let settings = config::load().speech;
+    enqueue(text, voice.or(settings.voice), rate.or(settings.rate)).map_err(Error::from)
+}
+
+/// Stop the current utterance
+#[tauri::command]
+pub fn stop_speaking() {
+    if let Ok(mut speaking) = SPEAKING.lock() {
+        if let Some(mut child) = speaking.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Voices the platform's synthesizer offers
+#[tauri::command]
+pub fn list_voices() -> Vec<String> {
+    let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("say", &["-v", "?"])
+    } else if cfg!(target_os = "windows") {
+        (
+            "powershell",
+            &["-NoProfile", "-Command", "Add-Type -AssemblyName System.Speech; (New-Object System.Speech.Synthesis.SpeechSynthesizer).GetInstalledVoices() | ForEach-Object { $_.VoiceInfo.Name }"],
+        )
+    } else {
+        ("espeak", &["--voices"])
+    };
+    let Ok(output) = process::output(Command::new(program).args(args)) else {
+        return Vec::new();
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines = stdout.lines().filter(|l| !l.trim().is_empty());
+    if cfg!(target_os = "macos") {
+        // `Samantha            en_US    # Hello...`: the name may contain spaces
+        lines
+            .filter_map(|l| l.split_once('#'))
+            .filter_map(|(name, _)| name.trim_end().rsplit_once(char::is_whitespace))
+            .map(|(name, _)| name.trim().to_string())
+            .collect()
+    } else if cfg!(target_os = "windows") {
+        lines.map(|l| l.trim().to_string()).collect()
+    } else {
+        // Columns: Pty Language Age/Gender VoiceName File Other
+        lines
+            .skip(1)
+            .filter_map(|l| l.split_whitespace().nth(3).map(str::to_string))
+            .collect()
+    }
+}
+
+#[tauri::command]
+pub fn get_speech_settings() -> SpeechSettings {
+    let mut settings = config::load().speech;
+    for topic in SPOKEN_TOPICS {
+        settings.events.entry(*topic).or_insert(true);
+    }
+    settings
+}
+
+#[tauri::command]
+pub fn set_speech_settings(
+    enabled: bool,
+    voice: Option<String>,
+    rate: Option<u32>,
+) -> Result<(), Error> {
+    if rate.is_some_and(|r| !(80..=500).contains(&r)) {
+        return Err(Error::InvalidInput(
+            "Rate must be between 80 and 500 words per minute".to_string(),
+        ));
+    }
+    config::update(|c| {
+        c.speech.enabled = enabled;
+        c.speech.voice = voice;
+        c.speech.rate = rate;
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_speech_event_enabled(topic: Topic, enabled: bool) -> Result<(), Error> {
+    if !SPOKEN_TOPICS.contains(&topic) {
+        return Err(Error::InvalidInput(format!(
+            "{:?} events can't be announced",
+            topic
+        )));
+    }
+    config::update(|c| {
+        c.speech.events.insert(topic, enabled);
+    })?;
+    Ok(())
+}
@@ -0,0 +1,148 @@
+//! Timings of the phases of app startup
+//!
+//! Launch is split into npm discovery, server path resolution, spawning the server,
+//! waiting for it to accept connections, and creating the main window; each phase is
+//! timed from the moment `run` starts. Only the first launch is recorded, so restarts
+//! later on don't overwrite it. A phase slower than `startup_warn_ms` (2s by default) is
+//! logged and reported to the UI once it is listening.
+//!
+//! Events:
+//! - `startup-phase-slow` with the slow [`Phase`]
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::config;
+
+const DEFAULT_WARN_MS: u64 = 2000;
+/// Give up on timing readiness after this long; the server may never come up
+const READINESS_LIMIT: Duration = Duration::from_secs(120);
+const READINESS_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Phase {
+    /// `npmDiscovery`, `serverPath`, `spawn`, `readiness` or `windowCreation`
+    pub name: &'static str,
+    /// Milliseconds after launch the phase started
+    pub start_ms: u64,
+    pub duration_ms: u64,
+    pub slow: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupTimings {
+    pub phases: Vec<Phase>,
+    /// Launch until the last phase ended
+    pub total_ms: u64,
+    pub warn_ms: u64,
+}
+
+static LAUNCH: OnceLock<Instant> = OnceLock::new();
+static PHASES: Mutex<Vec<Phase>> = Mutex::new(Vec::new());
+/// Set once the first server start has been through, so restarts aren't recorded
+static SERVER_DONE: AtomicBool = AtomicBool::new(false);
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+fn warn_ms() -> u64 {
+    config::load().startup_warn_ms.unwrap_or(DEFAULT_WARN_MS)
+}
+
+fn launch() -> Instant {
+    *LAUNCH.get_or_init(Instant::now)
+}
+
+/// Start the clock; call first thing in `run`
+pub fn mark_launch() {
+    launch();
+}
+
+fn warn(phase: &Phase) {
+    eprintln!(
+        "[Claude PM] Startup phase {} took {}ms",
+        phase.name, phase.duration_ms
+    );
+    if let Some(app) = APP.get() {
+        let _ = app.emit("startup-phase-slow", phase);
+    }
+}
+
+/// Record phase `name` as running from `start` until now
+pub fn record(name: &'static str, start: Instant) {
+    let phase = Phase {
+        name,
+        start_ms: start.saturating_duration_since(launch()).as_millis() as u64,
+        duration_ms: start.elapsed().as_millis() as u64,
+        slow: start.elapsed().as_millis() as u64 > warn_ms(),
+    };
+    if let Ok(mut phases) = PHASES.lock() {
+        if phases.iter().any(|p| p.name == name) {
+            return;
+        }
+        phases.push(phase.clone());
+    }
+    if phase.slow {
+        warn(&phase);
+    }
+}
+
+/// Time `f` as phase `name`, while the first server start is still in progress
+pub fn server_phase<T>(name: &'static str, f: impl FnOnce() -> T) -> T {
+    if SERVER_DONE.load(Ordering::SeqCst) {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    record(name, start);
+    result
+}
+
+/// The first server start has returned; `spawned` times how long until it accepts
+/// connections
+pub fn server_started(spawned: bool) {
+    if SERVER_DONE.swap(true, Ordering::SeqCst) || !spawned {
+        return;
+    }
+    let start = Instant::now();
+    thread::spawn(move || {
+        while start.elapsed() < READINESS_LIMIT {
+            if crate::is_server_running(crate::server_port()) {
+                record("readiness", start);
+                return;
+            }
+            thread::sleep(READINESS_POLL);
+        }
+    });
+}
+
+/// Report slow phases recorded before the app could emit events
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app.clone());
+    let slow: Vec<Phase> = PHASES
+        .lock()
+        .map(|phases| phases.iter().filter(|p| p.slow).cloned().collect())
+        .unwrap_or_default();
+    for phase in &slow {
+        let _ = app.emit("startup-phase-slow", phase);
+    }
+}
+
+#[tauri::command]
+pub fn get_startup_timings() -> StartupTimings {
+    let mut phases = PHASES.lock().map(|p| p.clone()).unwrap_or_default();
+    phases.sort_by_key(|p| p.start_ms);
+    StartupTimings {
+        total_ms: phases
+            .iter()
+            .map(|p| p.start_ms + p.duration_ms)
+            .max()
+            .unwrap_or_default(),
+        phases,
+        warn_ms: warn_ms(),
+    }
+}
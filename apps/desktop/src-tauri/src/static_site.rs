@@ -0,0 +1,443 @@
+//! Exporting a project as a static HTML site, for archiving it or sharing it with clients
+//!
+//! Layout of the exported directory:
+//! - `index.html`: the board, a column per state with a card per task
+//! - `tasks/<id>.html`: a task's description, dependencies, linked issues and PRs, and
+//!   agent runs
+//! - `reports.html`: the status report and timesheet over the project's lifetime (see
+//!   `report`)
+//! - `runs.html`: every recorded agent run on the project
+//!
+//! Pages link to each other relatively and carry their own styles, with no scripts or
+//! external assets, so the directory opens straight from disk or any file host.
+//! Exporting into a directory that already holds something other than a previous
+//! export is refused.
+
+use chrono::{Local, TimeZone};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::pdf::{self, Block};
+use crate::run_history::{self, RunHistoryFilter, RunRecord};
+use crate::store::{self, Project, Task, TASK_STATES};
+use crate::{github, report};
+
+const STYLE: &str = "*{box-sizing:border-box}body{margin:0;font:14px/1.5 -apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;color:#1f2328;background:#f6f8fa}header{background:#24292f;color:#fff;padding:16px 24px}header h1{margin:0;font-size:20px}header p{margin:4px 0 0;color:#afb8c1;font-size:12px}nav a{color:#fff;margin-right:16px;text-decoration:none;font-weight:600}main{padding:24px;max-width:1200px;margin:0 auto}a{color:#0969da}.board{display:flex;gap:16px;align-items:flex-start;overflow-x:auto}.column{flex:1;min-width:220px;background:#eaeef2;border-radius:8px;padding:12px}.column h2{margin:0 0 12px;font-size:14px;text-transform:uppercase;color:#57606a}.card{display:block;background:#fff;border:1px solid #d0d7de;border-radius:6px;padding:10px;margin-bottom:8px;color:inherit;text-decoration:none}.card:hover{border-color:#0969da}.card p{margin:4px 0 0;color:#57606a;font-size:12px}.badge{display:inline-block;font-size:11px;padding:0 6px;border-radius:10px;background:#ddf4ff;color:#0969da;margin-right:4px}.badge.blocked{background:#ffebe9;color:#cf222e}section{background:#fff;border:1px solid #d0d7de;border-radius:8px;padding:16px 20px;margin-bottom:16px}table{border-collapse:collapse;width:100%;font-size:13px}th,td{text-align:left;padding:6px 8px;border-bottom:1px solid #eaeef2;vertical-align:top}pre{background:#f6f8fa;padding:12px;border-radius:6px;overflow-x:auto}.bar{display:flex;align-items:center;gap:8px;font-size:12px}.bar span:first-child{width:130px}.bar div{background:#0969da;height:10px;border-radius:2px}.muted{color:#57606a}";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaticSiteExport {
+    /// The exported directory; open `index.html` in it
+    pub path: String,
+    pub pages: usize,
+    pub tasks: usize,
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn date(ms: i64) -> String {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+fn state_label(state: &str) -> &str {
+    match state {
+        "in_progress" => "In progress",
+        "review" => "Review",
+        "done" => "Done",
+        _ => "Backlog",
+    }
+}
+
+/// Markdown (task descriptions, reports) as HTML, via the PDF renderer's parser
+fn markdown_html(markdown: &str) -> String {
+    let mut html = String::new();
+    let mut in_list = false;
+    for block in pdf::parse(markdown) {
+        let bullet = matches!(block, Block::Bullet(_));
+        if in_list && !bullet {
+            html.push_str("</ul>\n");
+        } else if !in_list && bullet {
+            html.push_str("<ul>\n");
+        }
+        in_list = bullet;
+        match block {
+            Block::Heading(level, text) => {
+                let level = level.clamp(1, 6);
+                html.push_str(&format!("<h{0}>{1}</h{0}>\n", level, escape(&text)));
+            }
+            Block::Paragraph(text) => html.push_str(&format!("<p>{}</p>\n", escape(&text))),
+            Block::Bullet(text) => html.push_str(&format!("<li>{}</li>\n", escape(&text))),
+            Block::Table(rows) => {
+                html.push_str("<table>\n");
+                for (i, row) in rows.iter().enumerate() {
+                    let tag = if i == 0 { "th" } else { "td" };
+                    let cells: String = row
+                        .iter()
+                        .map(|cell| format!("<{0}>{1}</{0}>", tag, escape(cell)))
+                        .collect();
+                    html.push_str(&format!("<tr>{}</tr>\n", cells));
+                }
+                html.push_str("</table>\n");
+            }
+            Block::Chart(bars) => {
+                let max = bars.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+                for (label, value) in bars {
+                    let width = if max > 0.0 { value / max * 300.0 } else { 0.0 };
+                    html.push_str(&format!(
+                        "<div class=\"bar\"><span>{}</span><div style=\"width:{:.0}px\"></div><span>{}</span></div>\n",
+                        escape(&label),
+                        width,
+                        value
+                    ));
+                }
+            }
+            Block::Code(lines) => {
+                html.push_str(&format!("<pre>{}</pre>\n", escape(&lines.join("\n"))));
+            }
+            Block::Rule => html.push_str("<hr>\n"),
+        }
+    }
+    if in_list {
+        html.push_str("</ul>\n");
+    }
+    html
+}
+
+/// A page of the site; `root` is the relative path back to the export's top level
+fn page(project: &Project, title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{title} · {project}</title>\n<style>{style}</style>\n</head>\n<body>\n<header><h1>{project}</h1><p>Exported from Claude PM on {exported}</p><nav><a href=\"{root}index.html\">Board</a><a href=\"{root}reports.html\">Reports</a><a href=\"{root}runs.html\">Agent runs</a></nav></header>\n<main>\n{body}</main>\n</body>\n</html>\n",
+        title = escape(title),
+        project = escape(&project.name),
+        style = STYLE,
+        exported = Local::now().format("%Y-%m-%d %H:%M"),
+        root = root,
+        body = body,
+    )
+}
+
+fn task_href(task_id: &str, root: &str) -> String {
+    format!("{}tasks/{}.html", root, task_id)
+}
+
+fn runs_table(runs: &[RunRecord], tasks: &BTreeMap<&str, &Task>, root: &str) -> String {
+    if runs.is_empty() {
+        return "<p class=\"muted\">No agent runs recorded.</p>\n".to_string();
+    }
+    let mut html = String::from(
+        "<table>\n<tr><th>Ended</th><th>Task</th><th>State</th><th>Model</th><th>Duration</th><th>Cost</th><th>Files</th></tr>\n",
+    );
+    for run in runs {
+        let task = match tasks.get(run.task_id.as_str()) {
+            Some(task) => format!(
+                "<a href=\"{}\">{}</a>",
+                task_href(&task.id, root),
+                escape(&task.title)
+            ),
+            None => escape(&run.title),
+        };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>${:.2}</td><td>{} (+{} −{})</td></tr>\n",
+            date(run.ended_at),
+            task,
+            run.state.as_str(),
+            escape(run.model.as_deref().unwrap_or("default")),
+            run.duration_ms
+                .map(|ms| format!("{} min", (ms + 59_999) / 60_000))
+                .unwrap_or_else(|| "—".to_string()),
+            run.cost_usd,
+            run.files_changed.len(),
+            run.additions,
+            run.deletions
+        ));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn board(tasks: &[Task], blocked: &BTreeMap<&str, usize>, runs: &BTreeMap<&str, usize>) -> String {
+    let mut html = String::from("<div class=\"board\">\n");
+    for state in TASK_STATES {
+        let column: Vec<&Task> = tasks.iter().filter(|t| t.state == *state).collect();
+        html.push_str(&format!(
+            "<div class=\"column\"><h2>{} ({})</h2>\n",
+            state_label(state),
+            column.len()
+        ));
+        for task in column {
+            let mut badges = String::new();
+            if let Some(count) = blocked.get(task.id.as_str()) {
+                badges.push_str(&format!(
+                    "<span class=\"badge blocked\">blocked by {}</span>",
+                    count
+                ));
+            }
+            if let Some(count) = runs.get(task.id.as_str()) {
+                badges.push_str(&format!(
+                    "<span class=\"badge\">{} run{}</span>",
+                    count,
+                    if *count == 1 { "" } else { "s" }
+                ));
+            }
+            let excerpt: String = task
+                .description
+                .as_deref()
+                .unwrap_or("")
+                .chars()
+                .take(140)
+                .collect();
+            html.push_str(&format!(
+                "<a class=\"card\" href=\"{}\"><strong>{}</strong>{}{}</a>\n",
+                task_href(&task.id, ""),
+                escape(&task.title),
+                if badges.is_empty() {
+                    String::new()
+                } else {
+                    format!("<p>{}</p>", badges)
+                },
+                if excerpt.trim().is_empty() {
+                    String::new()
+                } else {
+                    format!("<p>{}</p>", escape(excerpt.trim()))
+                }
+            ));
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</div>\n");
+    html
+}
+
+fn task_links(ids: &[&str], tasks: &BTreeMap<&str, &Task>) -> String {
+    let links: Vec<String> = ids
+        .iter()
+        .filter_map(|id| tasks.get(id))
+        .map(|task| {
+            format!(
+                "<a href=\"{}.html\">{}</a> <span class=\"muted\">({})</span>",
+                task.id,
+                escape(&task.title),
+                state_label(&task.state)
+            )
+        })
+        .collect();
+    links.join("<br>")
+}
+
+/// Refuse to write over a directory that isn't empty or a previous export
+fn prepare(dir: &Path) -> Result<(), Error> {
+    if dir.exists() {
+        let entries = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?
+            .count();
+        if entries > 0 && !dir.join("index.html").is_file() {
+            return Err(Error::InvalidInput(format!(
+                "{} isn't empty; choose an empty directory or a previous export",
+                dir.display()
+            )));
+        }
+    }
+    fs::create_dir_all(dir.join("tasks"))
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(())
+}
+
+fn write(path: PathBuf, contents: String) -> Result<(), Error> {
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+/// Render the project into `dir`; usable without the app running (see `cli`)
+pub fn export(project_id: &str, dir: &Path) -> Result<StaticSiteExport, Error> {
+    let project = store::with_conn(|conn| store::get_project(conn, project_id))?
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project_id)))?;
+    let tasks = store::list_tasks(Some(project.id.clone()), None)?;
+    let dependencies =
+        store::with_conn(|conn| store::list_task_dependencies(conn, Some(&project.id)))?;
+    let runs = run_history::query_run_history(Some(RunHistoryFilter {
+        project_id: Some(project.id.clone()),
+        // All of them
+        limit: Some(i64::MAX as usize),
+        ..Default::default()
+    }))?;
+    prepare(dir)?;
+
+    let by_id: BTreeMap<&str, &Task> = tasks.iter().map(|t| (t.id.as_str(), t)).collect();
+    let mut depends_on: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    let mut dependents: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for dep in &dependencies {
+        depends_on
+            .entry(dep.task_id.as_str())
+            .or_default()
+            .push(dep.depends_on.as_str());
+        dependents
+            .entry(dep.depends_on.as_str())
+            .or_default()
+            .push(dep.task_id.as_str());
+    }
+    let blocked: BTreeMap<&str, usize> = depends_on
+        .iter()
+        .map(|(task, deps)| {
+            let open = deps
+                .iter()
+                .filter(|d| by_id.get(*d).is_some_and(|t| t.state != "done"))
+                .count();
+            (*task, open)
+        })
+        .filter(|(_, open)| *open > 0)
+        .collect();
+    let mut run_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for run in &runs {
+        *run_counts.entry(run.task_id.as_str()).or_insert(0) += 1;
+    }
+
+    let done = tasks.iter().filter(|t| t.state == "done").count();
+    let summary = format!(
+        "<p class=\"muted\">{} tasks, {} done{}</p>\n",
+        tasks.len(),
+        done,
+        project
+            .repo_path
+            .as_deref()
+            .map(|repo| format!(" · {}", escape(repo)))
+            .unwrap_or_default()
+    );
+    write(
+        dir.join("index.html"),
+        page(
+            &project,
+            "Board",
+            "",
+            &format!("{}{}", summary, board(&tasks, &blocked, &run_counts)),
+        ),
+    )?;
+
+    for task in &tasks {
+        let mut body = format!(
+            "<section><h2>{}</h2><p class=\"muted\">{} · created {} · updated {}</p>\n{}</section>\n",
+            escape(&task.title),
+            state_label(&task.state),
+            date(task.created_at),
+            date(task.updated_at),
+            task.description
+                .as_deref()
+                .filter(|d| !d.trim().is_empty())
+                .map(markdown_html)
+                .unwrap_or_else(|| "<p class=\"muted\">No description.</p>\n".to_string())
+        );
+        let before = depends_on
+            .get(task.id.as_str())
+            .cloned()
+            .unwrap_or_default();
+        let after = dependents
+            .get(task.id.as_str())
+            .cloned()
+            .unwrap_or_default();
+        if !before.is_empty() || !after.is_empty() {
+            body.push_str("<section><h3>Dependencies</h3>\n");
+            if !before.is_empty() {
+                body.push_str(&format!(
+                    "<p><strong>Depends on</strong><br>{}</p>\n",
+                    task_links(&before, &by_id)
+                ));
+            }
+            if !after.is_empty() {
+                body.push_str(&format!(
+                    "<p><strong>Needed by</strong><br>{}</p>\n",
+                    task_links(&after, &by_id)
+                ));
+            }
+            body.push_str("</section>\n");
+        }
+        let links = github::list_task_github_links(task.id.clone()).unwrap_or_default();
+        if !links.is_empty() {
+            let items: String = links
+                .iter()
+                .map(|link| {
+                    format!(
+                        "<li><a href=\"{}\">{}#{}</a></li>\n",
+                        escape(&link.url),
+                        escape(&link.repo),
+                        link.number
+                    )
+                })
+                .collect();
+            body.push_str(&format!(
+                "<section><h3>GitHub</h3><ul>\n{}</ul></section>\n",
+                items
+            ));
+        }
+        let task_runs: Vec<RunRecord> = runs
+            .iter()
+            .filter(|r| r.task_id == task.id)
+            .cloned()
+            .collect();
+        body.push_str(&format!(
+            "<section><h3>Agent runs</h3>\n{}</section>\n",
+            runs_table(&task_runs, &by_id, "../")
+        ));
+        write(
+            dir.join("tasks").join(format!("{}.html", task.id)),
+            page(&project, &task.title, "../", &body),
+        )?;
+    }
+
+    // Reports cover the whole project, through the end of today
+    let to = store::now_ms() + 1;
+    let mut reports = String::new();
+    for template in ["status", "timesheet"] {
+        let (_, markdown) =
+            report::project_markdown(&project.id, project.created_at, to, Some(template))?;
+        reports.push_str(&format!(
+            "<section>\n{}</section>\n",
+            markdown_html(&markdown)
+        ));
+    }
+    write(
+        dir.join("reports.html"),
+        page(&project, "Reports", "", &reports),
+    )?;
+    write(
+        dir.join("runs.html"),
+        page(
+            &project,
+            "Agent runs",
+            "",
+            &format!("<section>\n{}</section>\n", runs_table(&runs, &by_id, "")),
+        ),
+    )?;
+
+    println!(
+        "[Claude PM] Exported {} as a static site to {}",
+        project.name,
+        dir.display()
+    );
+    Ok(StaticSiteExport {
+        path: dir.display().to_string(),
+        pages: tasks.len() + 3,
+        tasks: tasks.len(),
+    })
+}
+
+/// Export a project's board, tasks and reports as a self-contained HTML site in `path`
+#[tauri::command]
+pub async fn export_static_site(
+    project_id: String,
+    path: String,
+) -> Result<StaticSiteExport, Error> {
+    tauri::async_runtime::spawn_blocking(move || export(&project_id, Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,479 @@
+//! Disk usage of the log directory, attachment store, database and Claude transcripts
+//!
+//! Every half hour each category is measured against its cap and the volume holding the
+//! data directory against the minimum free space (free space from `df`, or `Get-PSDrive`
+//! on Windows). A warning is emitted when a limit is first crossed, not on every check.
+//!
+//! `clean_caches` removes what can be regenerated or is no longer referenced: rotated
+//! server logs, attachment files without a row, thumbnails of deleted attachments, and
+//! git worktrees of project repos whose checkout directory is gone (via `git worktree
+//! prune`, so git's own bookkeeping stays consistent).
+//!
+//! Events:
+//! - `storage-warning` with the `StorageWarning` that became active
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{attachments, config, power, preview, process, search, server_logs, store};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+const MB: u64 = 1024 * 1024;
+/// Caps per category in MB when none is configured
+const DEFAULT_CAPS_MB: &[(Category, u64)] = &[
+    (Category::Logs, 500),
+    (Category::Attachments, 5 * 1024),
+    (Category::Database, 1024),
+    (Category::Transcripts, 10 * 1024),
+];
+const DEFAULT_MIN_FREE_MB: u64 = 2 * 1024;
+/// Unreferenced attachment files younger than this may still be about to be indexed
+const ORPHAN_GRACE: Duration = Duration::from_secs(60 * 60);
+
+static ACTIVE: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Category {
+    Logs,
+    Attachments,
+    Database,
+    Transcripts,
+}
+
+impl Category {
+    const ALL: [Category; 4] = [
+        Category::Logs,
+        Category::Attachments,
+        Category::Database,
+        Category::Transcripts,
+    ];
+
+    fn path(self) -> Option<PathBuf> {
+        match self {
+            Category::Logs => server_logs::log_dir(),
+            Category::Attachments => attachments::attachments_dir().ok(),
+            Category::Database => store::db_path(),
+            Category::Transcripts => search::transcripts_dir(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StorageSettings {
+    /// Cap per category in MB; see `DEFAULT_CAPS_MB` for the defaults
+    pub caps_mb: BTreeMap<Category, u64>,
+    /// Free space below which the disk counts as low (2 GB when unset)
+    pub min_free_mb: Option<u64>,
+}
+
+impl StorageSettings {
+    fn cap_bytes(&self, category: Category) -> u64 {
+        self.caps_mb
+            .get(&category)
+            .or_else(|| {
+                DEFAULT_CAPS_MB
+                    .iter()
+                    .find(|(c, _)| *c == category)
+                    .map(|(_, mb)| mb)
+            })
+            .copied()
+            .unwrap_or(u64::MAX / MB)
+            .saturating_mul(MB)
+    }
+
+    fn min_free_bytes(&self) -> u64 {
+        self.min_free_mb.unwrap_or(DEFAULT_MIN_FREE_MB) * MB
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryUsage {
+    pub category: Category,
+    pub path: Option<String>,
+    pub bytes: u64,
+    pub files: u64,
+    pub cap_bytes: u64,
+    pub over_cap: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageReport {
+    pub categories: Vec<CategoryUsage>,
+    /// Free space on the volume holding the data directory; unset if it couldn't be read
+    pub free_bytes: Option<u64>,
+    pub min_free_bytes: u64,
+    pub low_disk: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum StorageWarning {
+    #[serde(rename_all = "camelCase")]
+    CapExceeded {
+        category: Category,
+        bytes: u64,
+        cap_bytes: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    LowDisk {
+        free_bytes: u64,
+        min_free_bytes: u64,
+    },
+}
+
+impl StorageWarning {
+    fn key(&self) -> String {
+        match self {
+            StorageWarning::CapExceeded { category, .. } => format!("cap:{:?}", category),
+            StorageWarning::LowDisk { .. } => "lowDisk".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheCategory {
+    RotatedLogs,
+    OrphanedAttachments,
+    StaleThumbnails,
+    DeadWorktrees,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupResult {
+    pub category: CacheCategory,
+    /// Files (or worktrees) removed, or that would be on a dry run
+    pub items: u64,
+    pub bytes: u64,
+    pub dry_run: bool,
+    pub errors: Vec<String>,
+}
+
+/// Total size and file count under `path`, without following symlinks
+fn measure(path: &Path) -> (u64, u64) {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return (0, 0);
+    };
+    if !metadata.is_dir() {
+        return (metadata.len(), 1);
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return (0, 0);
+    };
+    entries
+        .filter_map(Result::ok)
+        .map(|entry| measure(&entry.path()))
+        .fold((0, 0), |(bytes, files), (b, f)| (bytes + b, files + f))
+}
+
+/// Free bytes on the volume holding `path`
+fn free_space(path: &Path) -> Option<u64> {
+    if cfg!(windows) {
+        let drive = path.to_str()?.chars().next()?;
+        let output = process::output(Command::new("powershell").args([
+            "-NoProfile",
+            "-Command",
+            &format!("(Get-PSDrive {}).Free", drive),
+        ]))
+        .ok()?;
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    } else {
+        // POSIX output: header, then "fs 1024-blocks used available capacity mount"
+        let output = process::output(Command::new("df").arg("-Pk").arg(path)).ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let available: u64 = stdout
+            .lines()
+            .nth(1)?
+            .split_whitespace()
+            .nth(3)?
+            .parse()
+            .ok()?;
+        Some(available * 1024)
+    }
+}
+
+/// Free bytes on the volume holding the data directory, and the configured minimum
+pub fn disk_space() -> (Option<u64>, u64) {
+    let free_bytes = config::data_dir().and_then(|dir| {
+        // `df` needs an existing path
+        let _ = fs::create_dir_all(&dir);
+        free_space(&dir)
+    });
+    (free_bytes, config::load().storage.min_free_bytes())
+}
+
+fn report() -> StorageReport {
+    let settings = config::load().storage;
+    let categories = Category::ALL
+        .iter()
+        .map(|&category| {
+            let path = category.path();
+            let (bytes, files) = path.as_deref().map(measure).unwrap_or_default();
+            let cap_bytes = settings.cap_bytes(category);
+            CategoryUsage {
+                category,
+                path: path.map(|p| p.display().to_string()),
+                bytes,
+                files,
+                cap_bytes,
+                over_cap: bytes > cap_bytes,
+            }
+        })
+        .collect();
+    let (free_bytes, min_free_bytes) = disk_space();
+    StorageReport {
+        categories,
+        free_bytes,
+        min_free_bytes,
+        low_disk: free_bytes.is_some_and(|free| free < min_free_bytes),
+    }
+}
+
+fn warnings(report: &StorageReport) -> Vec<StorageWarning> {
+    let mut warnings: Vec<StorageWarning> = report
+        .categories
+        .iter()
+        .filter(|usage| usage.over_cap)
+        .map(|usage| StorageWarning::CapExceeded {
+            category: usage.category,
+            bytes: usage.bytes,
+            cap_bytes: usage.cap_bytes,
+        })
+        .collect();
+    if let Some(free_bytes) = report.free_bytes.filter(|_| report.low_disk) {
+        warnings.push(StorageWarning::LowDisk {
+            free_bytes,
+            min_free_bytes: report.min_free_bytes,
+        });
+    }
+    warnings
+}
+
+/// Emit warnings that weren't active at the last check; cleared ones can fire again later
+fn check(app: &AppHandle) {
+    let warnings = warnings(&report());
+    let Ok(mut active) = ACTIVE.lock() else {
+        return;
+    };
+    let current: BTreeSet<String> = warnings.iter().map(StorageWarning::key).collect();
+    for warning in warnings.iter().filter(|w| !active.contains(&w.key())) {
+        eprintln!("[Claude PM] Storage warning: {:?}", warning);
+        let _ = app.emit("storage-warning", warning);
+    }
+    *active = current;
+}
+
+/// Git's admin directories (`.git/worktrees/<name>`) of worktrees whose checkout is gone,
+/// grouped by repo
+fn dead_worktrees() -> Vec<(PathBuf, Vec<PathBuf>)> {
+    let projects = store::list_projects().unwrap_or_default();
+    let mut repos: Vec<PathBuf> = projects
+        .into_iter()
+        .filter_map(|p| p.repo_path.map(PathBuf::from))
+        .collect();
+    repos.sort();
+    repos.dedup();
+    repos
+        .into_iter()
+        .filter_map(|repo| {
+            let output = process::output(
+                Command::new("git")
+                    .arg("-C")
+                    .arg(&repo)
+                    .args(["rev-parse", "--git-common-dir"]),
+            )
+            .ok()
+            .filter(|o| o.status.success())?;
+            let common = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+            let common = if common.is_absolute() {
+                common
+            } else {
+                repo.join(common)
+            };
+            // Each admin dir's `gitdir` names the checkout's `.git` file
+            let dead: Vec<PathBuf> = fs::read_dir(common.join("worktrees"))
+                .ok()?
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|admin| {
+                    fs::read_to_string(admin.join("gitdir"))
+                        .is_ok_and(|gitdir| !Path::new(gitdir.trim()).exists())
+                })
+                .collect();
+            (!dead.is_empty()).then_some((repo, dead))
+        })
+        .collect()
+}
+
+fn remove_files(category: CacheCategory, paths: Vec<PathBuf>, dry_run: bool) -> CleanupResult {
+    let mut result = CleanupResult {
+        category,
+        items: 0,
+        bytes: 0,
+        dry_run,
+        errors: Vec::new(),
+    };
+    for path in paths {
+        let (bytes, _) = measure(&path);
+        if !dry_run {
+            if let Err(e) = fs::remove_file(&path) {
+                result
+                    .errors
+                    .push(format!("Failed to remove {}: {}", path.display(), e));
+                continue;
+            }
+        }
+        result.items += 1;
+        result.bytes += bytes;
+    }
+    result
+}
+
+fn prune_worktrees(dry_run: bool) -> CleanupResult {
+    let mut result = CleanupResult {
+        category: CacheCategory::DeadWorktrees,
+        items: 0,
+        bytes: 0,
+        dry_run,
+        errors: Vec::new(),
+    };
+    for (repo, dead) in dead_worktrees() {
+        let sizes: Vec<u64> = dead.iter().map(|admin| measure(admin).0).collect();
+        if !dry_run {
+            let pruned = process::output(
+                Command::new("git")
+                    .arg("-C")
+                    .arg(&repo)
+                    .args(["worktree", "prune"]),
+            );
+            if let Err(e) = pruned.map_err(|e| e.to_string()).and_then(|o| {
+                o.status
+                    .success()
+                    .then_some(())
+                    .ok_or_else(|| String::from_utf8_lossy(&o.stderr).trim().to_string())
+            }) {
+                result.errors.push(format!(
+                    "git worktree prune failed in {}: {}",
+                    repo.display(),
+                    e
+                ));
+                continue;
+            }
+        }
+        for (admin, bytes) in dead.iter().zip(sizes) {
+            // A locked worktree survives `prune`
+            if dry_run || !admin.exists() {
+                result.items += 1;
+                result.bytes += bytes;
+            }
+        }
+    }
+    result
+}
+
+fn clean(category: CacheCategory, dry_run: bool) -> CleanupResult {
+    let candidates = match category {
+        CacheCategory::DeadWorktrees => return prune_worktrees(dry_run),
+        CacheCategory::RotatedLogs => Ok(server_logs::rotated_files()),
+        CacheCategory::OrphanedAttachments => attachments::orphaned_files(ORPHAN_GRACE),
+        CacheCategory::StaleThumbnails => preview::stale_previews(),
+    };
+    match candidates {
+        Ok(paths) => remove_files(category, paths, dry_run),
+        Err(e) => CleanupResult {
+            category,
+            items: 0,
+            bytes: 0,
+            dry_run,
+            errors: vec![e],
+        },
+    }
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        check(&app);
+        power::wait(CHECK_INTERVAL);
+    });
+}
+
+/// Bytes used per category and free disk space; walks the directories, so it can be slow
+#[tauri::command]
+pub async fn get_storage_report() -> Result<StorageReport, Error> {
+    Ok(tauri::async_runtime::spawn_blocking(report)
+        .await
+        .map_err(|e| e.to_string())?)
+}
+
+/// Remove (or with `dry_run`, just measure) each category's leftovers; all categories when empty
+#[tauri::command]
+pub async fn clean_caches(
+    categories: Vec<CacheCategory>,
+    dry_run: bool,
+) -> Result<Vec<CleanupResult>, Error> {
+    let categories = if categories.is_empty() {
+        vec![
+            CacheCategory::RotatedLogs,
+            CacheCategory::OrphanedAttachments,
+            CacheCategory::StaleThumbnails,
+            CacheCategory::DeadWorktrees,
+        ]
+    } else {
+        categories
+    };
+    let results = tauri::async_runtime::spawn_blocking(move || {
+        categories
+            .into_iter()
+            .map(|category| clean(category, dry_run))
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+    if !dry_run {
+        let freed: u64 = results.iter().map(|r| r.bytes).sum();
+        println!("[Claude PM] Cache cleanup freed {} bytes", freed);
+    }
+    Ok(results)
+}
+
+/// Cap `category` at `cap_mb`; `None` restores the default
+#[tauri::command]
+pub fn set_storage_cap(category: Category, cap_mb: Option<u64>) -> Result<(), Error> {
+    if cap_mb == Some(0) {
+        return Err(Error::InvalidInput("Cap must be at least 1 MB".to_string()));
+    }
+    config::update(|c| match cap_mb {
+        Some(mb) => {
+            c.storage.caps_mb.insert(category, mb);
+        }
+        None => {
+            c.storage.caps_mb.remove(&category);
+        }
+    })?;
+    Ok(())
+}
+
+/// Warn when free space drops below `min_free_mb`; `None` restores the default
+#[tauri::command]
+pub fn set_min_free_space(min_free_mb: Option<u64>) -> Result<(), Error> {
+    config::update(|c| c.storage.min_free_mb = min_free_mb)?;
+    Ok(())
+}
@@ -0,0 +1,1208 @@
+//! Local SQLite store for projects, tasks and sessions
+//!
+//! Lets core PM data survive (and be edited) while the Node server is down. The schema is
+//! versioned with `PRAGMA user_version`; add a new entry to `MIGRATIONS` rather than
+//! editing an applied one.
+//!
+//! The database can be encrypted with SQLCipher (not on Windows builds) using a random
+//! key kept in the OS keychain. Whether a file is encrypted is read from its header, so
+//! restored backups and relocated copies open either way. The key stays in the keychain
+//! when encryption is turned off, so older encrypted backups can still be restored.
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+use crate::event_bus::{self, AppEvent};
+use crate::{config, secrets};
+
+pub const DB_FILE: &str = "claudepm.db";
+const KEYCHAIN_ACCOUNT: &str = "database-key";
+/// First bytes of every unencrypted SQLite file
+const PLAIN_HEADER: &[u8; 16] = b"SQLite format 3\0";
+const ENCRYPTION_SUPPORTED: bool = cfg!(not(windows));
+
+/// Schema migrations, applied in order; index + 1 is the resulting `user_version`
+const MIGRATIONS: &[&str] = &[
+    r#"
+    CREATE TABLE projects (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        repo_path TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE tasks (
+        id TEXT PRIMARY KEY,
+        project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+        title TEXT NOT NULL,
+        description TEXT,
+        state TEXT NOT NULL DEFAULT 'backlog',
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX tasks_project ON tasks(project_id, state);
+    CREATE TABLE sessions (
+        id TEXT PRIMARY KEY,
+        project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+        task_id TEXT REFERENCES tasks(id) ON DELETE SET NULL,
+        status TEXT NOT NULL DEFAULT 'running',
+        pane_id TEXT,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER
+    );
+    CREATE INDEX sessions_project ON sessions(project_id, status);
+"#,
+    r#"
+    CREATE TABLE github_links (
+        task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        kind TEXT NOT NULL CHECK (kind IN ('issue', 'pr')),
+        repo TEXT NOT NULL,
+        number INTEGER NOT NULL,
+        url TEXT NOT NULL,
+        PRIMARY KEY (task_id, kind, repo, number)
+    );
+"#,
+    r#"
+    CREATE TABLE calendar_items (
+        source_id TEXT NOT NULL,
+        kind TEXT NOT NULL CHECK (kind IN ('event', 'reminder')),
+        external_id TEXT NOT NULL,
+        container TEXT,
+        title TEXT NOT NULL,
+        due_at INTEGER NOT NULL,
+        PRIMARY KEY (source_id, kind)
+    );
+"#,
+    r#"
+    CREATE TABLE time_entries (
+        id TEXT PRIMARY KEY,
+        task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER,
+        stop_reason TEXT CHECK (stop_reason IN ('manual', 'switch', 'idle'))
+    );
+    CREATE INDEX time_entries_started ON time_entries(started_at);
+"#,
+    r#"
+    CREATE TABLE attachments (
+        id TEXT PRIMARY KEY,
+        task_id TEXT REFERENCES tasks(id) ON DELETE CASCADE,
+        file_name TEXT NOT NULL,
+        path TEXT NOT NULL,
+        mime_type TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX attachments_task ON attachments(task_id);
+"#,
+    r#"
+    ALTER TABLE attachments ADD COLUMN hash TEXT;
+    ALTER TABLE attachments ADD COLUMN preview TEXT;
+    CREATE INDEX attachments_hash ON attachments(hash);
+"#,
+    r#"
+    CREATE VIRTUAL TABLE search_index USING fts5(
+        kind UNINDEXED,
+        ref_id UNINDEXED,
+        project_id UNINDEXED,
+        title,
+        body,
+        tokenize = 'porter unicode61'
+    );
+    CREATE TABLE search_sources (
+        path TEXT PRIMARY KEY,
+        mtime INTEGER NOT NULL
+    );
+    INSERT INTO search_index (kind, ref_id, project_id, title, body)
+        SELECT 'task', id, project_id, title, COALESCE(description, '') FROM tasks;
+    CREATE TRIGGER tasks_search_insert AFTER INSERT ON tasks BEGIN
+        INSERT INTO search_index (kind, ref_id, project_id, title, body)
+            VALUES ('task', new.id, new.project_id, new.title, COALESCE(new.description, ''));
+    END;
+    CREATE TRIGGER tasks_search_update AFTER UPDATE OF title, description, project_id ON tasks BEGIN
+        DELETE FROM search_index WHERE kind = 'task' AND ref_id = old.id;
+        INSERT INTO search_index (kind, ref_id, project_id, title, body)
+            VALUES ('task', new.id, new.project_id, new.title, COALESCE(new.description, ''));
+    END;
+    CREATE TRIGGER tasks_search_delete AFTER DELETE ON tasks BEGIN
+        DELETE FROM search_index WHERE kind = 'task' AND ref_id = old.id;
+    END;
+    INSERT INTO search_index (kind, ref_id, project_id, title, body)
+        SELECT 'attachment', a.id, t.project_id, a.file_name, a.preview
+        FROM attachments a LEFT JOIN tasks t ON t.id = a.task_id WHERE a.preview IS NOT NULL;
+    CREATE TRIGGER attachments_search_insert AFTER INSERT ON attachments WHEN new.preview IS NOT NULL BEGIN
+        INSERT INTO search_index (kind, ref_id, project_id, title, body)
+            VALUES ('attachment', new.id, (SELECT project_id FROM tasks WHERE id = new.task_id), new.file_name, new.preview);
+    END;
+    CREATE TRIGGER attachments_search_update AFTER UPDATE OF task_id ON attachments BEGIN
+        UPDATE search_index SET project_id = (SELECT project_id FROM tasks WHERE id = new.task_id)
+            WHERE kind = 'attachment' AND ref_id = new.id;
+    END;
+    CREATE TRIGGER attachments_search_delete AFTER DELETE ON attachments BEGIN
+        DELETE FROM search_index WHERE kind = 'attachment' AND ref_id = old.id;
+    END;
+"#,
+    r#"
+    CREATE TABLE deadlines (
+        source_id TEXT PRIMARY KEY,
+        project_id TEXT REFERENCES projects(id) ON DELETE CASCADE,
+        title TEXT NOT NULL,
+        notes TEXT NOT NULL DEFAULT '',
+        due_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX deadlines_project ON deadlines(project_id, due_at);
+    INSERT OR IGNORE INTO deadlines (source_id, project_id, title, due_at, updated_at)
+        SELECT c.source_id, t.project_id, c.title, c.due_at, c.due_at
+        FROM calendar_items c LEFT JOIN tasks t ON t.id = c.source_id;
+"#,
+    r#"
+    CREATE TABLE webhook_endpoints (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        url TEXT NOT NULL,
+        secret TEXT NOT NULL,
+        events TEXT NOT NULL,
+        enabled INTEGER NOT NULL DEFAULT 1,
+        created_at INTEGER NOT NULL
+    );
+    CREATE TABLE webhook_deliveries (
+        id TEXT PRIMARY KEY,
+        endpoint_id TEXT NOT NULL REFERENCES webhook_endpoints(id) ON DELETE CASCADE,
+        event TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'failed')),
+        attempts INTEGER NOT NULL DEFAULT 0,
+        next_attempt_at INTEGER NOT NULL,
+        response_status INTEGER,
+        last_error TEXT,
+        created_at INTEGER NOT NULL,
+        completed_at INTEGER
+    );
+    CREATE INDEX webhook_deliveries_pending ON webhook_deliveries(status, next_attempt_at);
+    CREATE INDEX webhook_deliveries_endpoint ON webhook_deliveries(endpoint_id, created_at);
+"#,
+    r#"
+    CREATE TABLE event_log (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        topic TEXT NOT NULL,
+        data TEXT NOT NULL,
+        at INTEGER NOT NULL
+    );
+"#,
+    r#"
+    CREATE TABLE outbox (
+        id TEXT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        dedupe_key TEXT UNIQUE,
+        payload TEXT NOT NULL,
+        status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sending', 'delivered', 'failed')),
+        attempts INTEGER NOT NULL DEFAULT 0,
+        next_attempt_at INTEGER NOT NULL,
+        last_error TEXT,
+        created_at INTEGER NOT NULL,
+        delivered_at INTEGER
+    );
+    CREATE INDEX outbox_pending ON outbox(status, next_attempt_at);
+"#,
+    r#"
+    CREATE TABLE approval_decisions (
+        id TEXT PRIMARY KEY,
+        target TEXT NOT NULL,
+        session_id TEXT,
+        question TEXT NOT NULL,
+        detail TEXT NOT NULL,
+        decision TEXT NOT NULL CHECK (decision IN ('allow', 'deny', 'ask')),
+        rule_id TEXT,
+        error TEXT,
+        at INTEGER NOT NULL
+    );
+    CREATE INDEX approval_decisions_at ON approval_decisions(at);
+"#,
+    r#"
+    CREATE TABLE recent_projects (
+        project_id TEXT PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+        opened_at INTEGER NOT NULL
+    );
+"#,
+    r#"
+    CREATE TABLE sync_state (
+        table_name TEXT NOT NULL,
+        row_id TEXT NOT NULL,
+        field TEXT NOT NULL,
+        value TEXT NOT NULL,
+        updated_at INTEGER NOT NULL,
+        device_id TEXT NOT NULL,
+        PRIMARY KEY (table_name, row_id, field)
+    );
+    CREATE TABLE sync_conflicts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        table_name TEXT NOT NULL,
+        row_id TEXT NOT NULL,
+        field TEXT NOT NULL,
+        local_value TEXT NOT NULL,
+        remote_value TEXT NOT NULL,
+        kept TEXT NOT NULL,
+        remote_device TEXT NOT NULL,
+        at INTEGER NOT NULL
+    );
+    CREATE INDEX sync_conflicts_at ON sync_conflicts(at);
+    CREATE TABLE sync_meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+"#,
+    r#"
+    CREATE TABLE sync_seen (
+        name TEXT PRIMARY KEY,
+        merged_at INTEGER NOT NULL
+    );
+"#,
+    r#"
+    CREATE TABLE audit_log (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        at INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        action TEXT NOT NULL,
+        subject TEXT,
+        detail TEXT NOT NULL,
+        hash TEXT NOT NULL
+    );
+    CREATE INDEX audit_log_kind ON audit_log(kind, at);
+    CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+    BEGIN
+        SELECT RAISE(ABORT, 'audit_log is append-only');
+    END;
+    CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+    BEGIN
+        SELECT RAISE(ABORT, 'audit_log is append-only');
+    END;
+"#,
+    r#"
+    CREATE TABLE run_snapshots (
+        id TEXT PRIMARY KEY,
+        run_id TEXT NOT NULL,
+        task_id TEXT NOT NULL,
+        project_id TEXT NOT NULL,
+        repo TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        head TEXT NOT NULL,
+        branch TEXT,
+        commit_sha TEXT NOT NULL,
+        files INTEGER NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX run_snapshots_task ON run_snapshots(task_id, created_at);
+    CREATE INDEX run_snapshots_run ON run_snapshots(run_id);
+"#,
+    r#"
+    CREATE TABLE task_branches (
+        task_id TEXT PRIMARY KEY,
+        project_id TEXT NOT NULL,
+        branch TEXT NOT NULL,
+        worktree TEXT NOT NULL,
+        base TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+"#,
+    r#"
+    CREATE TABLE run_history (
+        id TEXT PRIMARY KEY,
+        task_id TEXT NOT NULL,
+        project_id TEXT NOT NULL,
+        title TEXT NOT NULL,
+        prompt TEXT NOT NULL,
+        model TEXT,
+        cwd TEXT,
+        state TEXT NOT NULL,
+        error TEXT,
+        session_id TEXT,
+        queued_at INTEGER NOT NULL,
+        started_at INTEGER,
+        ended_at INTEGER NOT NULL,
+        duration_ms INTEGER,
+        input_tokens INTEGER NOT NULL DEFAULT 0,
+        output_tokens INTEGER NOT NULL DEFAULT 0,
+        cost_usd REAL NOT NULL DEFAULT 0,
+        files_changed TEXT NOT NULL DEFAULT '[]',
+        additions INTEGER NOT NULL DEFAULT 0,
+        deletions INTEGER NOT NULL DEFAULT 0,
+        transcript_path TEXT,
+        claude_session_id TEXT,
+        snapshot_id TEXT
+    );
+    CREATE INDEX run_history_ended ON run_history(ended_at);
+    CREATE INDEX run_history_task ON run_history(task_id, ended_at);
+"#,
+    r#"
+    CREATE TABLE inbox_items (
+        id TEXT PRIMARY KEY,
+        text TEXT NOT NULL,
+        source TEXT NOT NULL,
+        source_app TEXT,
+        source_title TEXT,
+        source_url TEXT,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX inbox_items_created ON inbox_items(created_at);
+"#,
+    // SQLite can't alter a CHECK constraint, so the table is rebuilt to allow `focus`
+    r#"
+    CREATE TABLE time_entries_new (
+        id TEXT PRIMARY KEY,
+        task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        started_at INTEGER NOT NULL,
+        ended_at INTEGER,
+        stop_reason TEXT CHECK (stop_reason IN ('manual', 'switch', 'idle', 'focus'))
+    );
+    INSERT INTO time_entries_new SELECT id, task_id, started_at, ended_at, stop_reason FROM time_entries;
+    DROP TABLE time_entries;
+    ALTER TABLE time_entries_new RENAME TO time_entries;
+    CREATE INDEX time_entries_started ON time_entries(started_at);
+"#,
+    r#"
+    CREATE TABLE github_boards (
+        project_id TEXT PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+        board_id TEXT NOT NULL,
+        owner TEXT NOT NULL,
+        number INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        status_field_id TEXT NOT NULL,
+        status_map TEXT NOT NULL,
+        prefer TEXT NOT NULL,
+        previewed_at INTEGER,
+        last_synced_at INTEGER
+    );
+    CREATE TABLE github_board_items (
+        task_id TEXT PRIMARY KEY,
+        project_id TEXT NOT NULL REFERENCES github_boards(project_id) ON DELETE CASCADE,
+        item_id TEXT NOT NULL UNIQUE,
+        content_id TEXT,
+        content_kind TEXT NOT NULL,
+        title TEXT NOT NULL,
+        body TEXT NOT NULL,
+        status TEXT,
+        synced_at INTEGER NOT NULL
+    );
+"#,
+    r#"
+    CREATE TABLE jira_issues (
+        task_id TEXT PRIMARY KEY REFERENCES tasks(id) ON DELETE CASCADE,
+        issue_key TEXT NOT NULL UNIQUE,
+        issue_id TEXT NOT NULL,
+        status TEXT NOT NULL,
+        task_state TEXT NOT NULL,
+        synced_at INTEGER NOT NULL
+    );
+"#,
+    r#"
+    CREATE TABLE linear_issues (
+        task_id TEXT PRIMARY KEY REFERENCES tasks(id) ON DELETE CASCADE,
+        issue_id TEXT NOT NULL UNIQUE,
+        identifier TEXT NOT NULL,
+        team_id TEXT NOT NULL,
+        state_id TEXT NOT NULL,
+        task_state TEXT NOT NULL,
+        synced_at INTEGER NOT NULL
+    );
+"#,
+    r#"
+    CREATE TABLE task_recurrences (
+        id TEXT PRIMARY KEY,
+        project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+        title TEXT NOT NULL,
+        description TEXT,
+        state TEXT NOT NULL,
+        rule TEXT NOT NULL,
+        time TEXT NOT NULL,
+        starts_at INTEGER NOT NULL,
+        ends_at INTEGER,
+        enabled INTEGER NOT NULL DEFAULT 1,
+        next_due INTEGER,
+        last_task_id TEXT,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE INDEX task_recurrences_due ON task_recurrences(enabled, next_due);
+"#,
+    r#"
+    CREATE TABLE task_dependencies (
+        task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        depends_on TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (task_id, depends_on),
+        CHECK (task_id != depends_on)
+    );
+    CREATE INDEX task_dependencies_depends_on ON task_dependencies(depends_on);
+"#,
+    r#"
+    CREATE TABLE api_cache (
+        key TEXT PRIMARY KEY,
+        path TEXT NOT NULL,
+        request BLOB NOT NULL,
+        response BLOB NOT NULL,
+        fetched_at INTEGER NOT NULL
+    );
+    CREATE INDEX api_cache_fetched_at ON api_cache(fetched_at);
+"#,
+    r#"
+    ALTER TABLE run_history ADD COLUMN claude_profile TEXT;
+"#,
+    r#"
+    CREATE TABLE task_comments (
+        id TEXT PRIMARY KEY,
+        task_id TEXT NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+        body TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX task_comments_task ON task_comments(task_id, created_at);
+    CREATE TRIGGER task_comments_search_insert AFTER INSERT ON task_comments BEGIN
+        INSERT INTO search_index (kind, ref_id, project_id, title, body)
+            SELECT 'comment', new.id, project_id, title, new.body FROM tasks WHERE id = new.task_id;
+    END;
+    CREATE TRIGGER task_comments_search_delete AFTER DELETE ON task_comments BEGIN
+        DELETE FROM search_index WHERE kind = 'comment' AND ref_id = old.id;
+    END;
+    CREATE TRIGGER tasks_comments_search_update AFTER UPDATE OF title, project_id ON tasks BEGIN
+        UPDATE search_index SET title = new.title, project_id = new.project_id
+            WHERE kind = 'comment' AND ref_id IN (SELECT id FROM task_comments WHERE task_id = new.id);
+    END;
+"#,
+    r#"
+    ALTER TABLE tasks ADD COLUMN completed_at INTEGER;
+    UPDATE tasks SET completed_at = updated_at WHERE state = 'done';
+    CREATE TRIGGER tasks_completed_insert AFTER INSERT ON tasks WHEN new.state = 'done' BEGIN
+        UPDATE tasks SET completed_at = new.updated_at WHERE id = new.id;
+    END;
+    CREATE TRIGGER tasks_completed_update AFTER UPDATE OF state ON tasks WHEN new.state IS NOT old.state BEGIN
+        UPDATE tasks SET completed_at = CASE WHEN new.state = 'done' THEN new.updated_at END WHERE id = new.id;
+    END;
+"#,
+    r#"
+    DROP TRIGGER tasks_search_insert;
+    DROP TRIGGER tasks_search_update;
+    DROP TRIGGER tasks_search_delete;
+    DROP TRIGGER attachments_search_insert;
+    DROP TRIGGER attachments_search_update;
+    DROP TRIGGER attachments_search_delete;
+    DROP TRIGGER task_comments_search_insert;
+    DROP TRIGGER task_comments_search_delete;
+    DROP TRIGGER tasks_comments_search_update;
+    DROP TABLE search_index;
+    CREATE TABLE search_pending (
+        kind TEXT NOT NULL,
+        ref_id TEXT NOT NULL,
+        PRIMARY KEY (kind, ref_id)
+    );
+    INSERT INTO search_pending (kind, ref_id) SELECT 'task', id FROM tasks;
+    INSERT INTO search_pending (kind, ref_id) SELECT 'comment', id FROM task_comments;
+    INSERT INTO search_pending (kind, ref_id) SELECT 'attachment', id FROM attachments;
+    DELETE FROM search_sources;
+    CREATE TRIGGER tasks_search_insert AFTER INSERT ON tasks BEGIN
+        INSERT OR REPLACE INTO search_pending (kind, ref_id) VALUES ('task', new.id);
+    END;
+    CREATE TRIGGER tasks_search_update AFTER UPDATE OF title, description, project_id ON tasks BEGIN
+        INSERT OR REPLACE INTO search_pending (kind, ref_id) VALUES ('task', new.id);
+        INSERT OR REPLACE INTO search_pending (kind, ref_id)
+            SELECT 'comment', id FROM task_comments WHERE task_id = new.id;
+        INSERT OR REPLACE INTO search_pending (kind, ref_id)
+            SELECT 'attachment', id FROM attachments WHERE task_id = new.id;
+    END;
+    CREATE TRIGGER tasks_search_delete AFTER DELETE ON tasks BEGIN
+        INSERT OR REPLACE INTO search_pending (kind, ref_id) VALUES ('task', old.id);
+    END;
+    CREATE TRIGGER task_comments_search_insert AFTER INSERT ON task_comments BEGIN
+        INSERT OR REPLACE INTO search_pending (kind, ref_id) VALUES ('comment', new.id);
+    END;
+    CREATE TRIGGER task_comments_search_update AFTER UPDATE ON task_comments BEGIN
+        INSERT OR REPLACE INTO search_pending (kind, ref_id) VALUES ('comment', new.id);
+    END;
+    CREATE TRIGGER task_comments_search_delete AFTER DELETE ON task_comments BEGIN
+        INSERT OR REPLACE INTO search_pending (kind, ref_id) VALUES ('comment', old.id);
+    END;
+    CREATE TRIGGER attachments_search_insert AFTER INSERT ON attachments BEGIN
+        INSERT OR REPLACE INTO search_pending (kind, ref_id) VALUES ('attachment', new.id);
+    END;
+    CREATE TRIGGER attachments_search_update AFTER UPDATE OF task_id, file_name, preview ON attachments BEGIN
+        INSERT OR REPLACE INTO search_pending (kind, ref_id) VALUES ('attachment', new.id);
+    END;
+    CREATE TRIGGER attachments_search_delete AFTER DELETE ON attachments BEGIN
+        INSERT OR REPLACE INTO search_pending (kind, ref_id) VALUES ('attachment', old.id);
+    END;
+"#,
+];
+
+pub const TASK_STATES: &[&str] = &["backlog", "in_progress", "review", "done"];
+
+static DB: Mutex<Option<Connection>> = Mutex::new(None);
+
+/// Open (and migrate) the database on first use
+pub fn connection() -> Result<MutexGuard<'static, Option<Connection>>, String> {
+    let mut guard = DB.lock().map_err(|e| e.to_string())?;
+    if guard.is_none() {
+        let path = db_path().ok_or("Could not determine data directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create data directory: {}", e))?;
+        }
+        let mut conn = open(&path)?;
+        conn.execute_batch("PRAGMA foreign_keys = ON; PRAGMA journal_mode = WAL;")
+            .map_err(|e| e.to_string())?;
+        migrate(&mut conn)?;
+        *guard = Some(conn);
+    }
+    Ok(guard)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionStatus {
+    pub supported: bool,
+    pub enabled: bool,
+}
+
+/// Whether the database file at `path` is encrypted; a missing or new file isn't
+fn is_encrypted(path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok()
+        && &header != PLAIN_HEADER
+}
+
+/// The database key as a SQLCipher raw-key literal, creating one if asked
+fn database_key(create: bool) -> Result<String, String> {
+    let hex = match secrets::get(KEYCHAIN_ACCOUNT)? {
+        Some(hex) => hex,
+        None if create => {
+            let mut key = [0u8; 32];
+            getrandom::getrandom(&mut key).map_err(|e| e.to_string())?;
+            let hex = secrets::hex(&key);
+            secrets::set(KEYCHAIN_ACCOUNT, &hex)?;
+            hex
+        }
+        None => {
+            return Err("The database is encrypted but its key isn't in the keychain".to_string())
+        }
+    };
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("Invalid database key in keychain".to_string());
+    }
+    Ok(format!("x'{}'", hex))
+}
+
+/// Open a database file, unlocking it with the keychain key if it's encrypted
+pub fn open(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| format!("Failed to open database: {}", e))?;
+    if is_encrypted(path) {
+        if !ENCRYPTION_SUPPORTED {
+            return Err("The database is encrypted, which this build can't read".to_string());
+        }
+        conn.execute_batch(&format!("PRAGMA key = \"{}\";", database_key(false)?))
+            .map_err(|e| e.to_string())?;
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))
+            .map_err(|_| "The keychain key doesn't unlock the database".to_string())?;
+    }
+    Ok(conn)
+}
+
+/// Path of the database file
+pub fn db_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join(DB_FILE))
+}
+
+/// Write a consistent copy of the database to `path` (safe while it's in use)
+pub fn snapshot_to(path: &Path) -> Result<(), String> {
+    with_conn(|conn| {
+        conn.execute("VACUUM INTO ?1", [path.to_string_lossy()])
+            .map(|_| ())
+    })
+}
+
+/// Snapshot the database into `dir` and run `then` with writers held off, then close the
+/// connection so the next call opens the database at the (by then updated) `db_path`
+pub fn relocate(dir: &Path, then: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+    let mut guard = connection()?;
+    let conn = guard.as_ref().ok_or("Database not open")?;
+    conn.execute("VACUUM INTO ?1", [dir.join(DB_FILE).to_string_lossy()])
+        .map_err(|e| format!("Failed to copy the database: {}", e))?;
+    then()?;
+    *guard = None;
+    Ok(())
+}
+
+/// Close the database and keep it closed while the guard is held, so nothing reopens it
+/// while its file is being replaced
+pub fn close() -> Result<MutexGuard<'static, Option<Connection>>, String> {
+    let mut guard = DB.lock().map_err(|e| e.to_string())?;
+    *guard = None;
+    Ok(guard)
+}
+
+pub fn with_conn<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let guard = connection()?;
+    let conn = guard.as_ref().ok_or("Database not open")?;
+    f(conn).map_err(|e| format!("Database error: {}", e))
+}
+
+/// Schema version a fully migrated database has
+pub fn latest_schema_version() -> usize {
+    MIGRATIONS.len()
+}
+
+/// Apply the migrations `conn` hasn't had yet
+pub fn migrate(conn: &mut Connection) -> Result<(), String> {
+    let version: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if version > MIGRATIONS.len() {
+        return Err(format!(
+            "Database schema v{} is newer than this app supports (v{})",
+            version,
+            MIGRATIONS.len()
+        ));
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute_batch(migration)
+            .and_then(|_| tx.pragma_update(None, "user_version", index + 1))
+            .and_then(|_| tx.commit())
+            .map_err(|e| format!("Migration {} failed: {}", index + 1, e))?;
+        println!("[Claude PM] Applied database migration {}", index + 1);
+    }
+    Ok(())
+}
+
+pub fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+pub fn new_id() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("OS random number generator unavailable");
+    secrets::hex(&bytes)
+}
+
+pub fn validate_state(state: &str) -> Result<(), String> {
+    if TASK_STATES.contains(&state) {
+        Ok(())
+    } else {
+        Err(format!("Unknown task state: {}", state))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub repo_path: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl Project {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            repo_path: row.get("repo_path")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Task {
+    pub id: String,
+    pub project_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub state: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// When the task last moved to `done`; `None` while it isn't done
+    pub completed_at: Option<i64>,
+}
+
+impl Task {
+    pub fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            project_id: row.get("project_id")?,
+            title: row.get("title")?,
+            description: row.get("description")?,
+            state: row.get("state")?,
+            created_at: row.get("created_at")?,
+            updated_at: row.get("updated_at")?,
+            completed_at: row.get("completed_at")?,
+        })
+    }
+}
+
+/// `task_id` can't be ready until `depends_on` is done (see `task_graph`)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskDependency {
+    pub task_id: String,
+    pub depends_on: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskComment {
+    pub id: String,
+    pub task_id: String,
+    pub body: String,
+    pub created_at: i64,
+}
+
+impl TaskComment {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            body: row.get("body")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub id: String,
+    pub project_id: String,
+    pub task_id: Option<String>,
+    pub status: String,
+    pub pane_id: Option<String>,
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+}
+
+impl Session {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            project_id: row.get("project_id")?,
+            task_id: row.get("task_id")?,
+            status: row.get("status")?,
+            pane_id: row.get("pane_id")?,
+            started_at: row.get("started_at")?,
+            ended_at: row.get("ended_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewProject {
+    pub name: String,
+    pub repo_path: Option<String>,
+}
+
+/// Fields left as `None` are unchanged
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectUpdate {
+    pub name: Option<String>,
+    pub repo_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewTask {
+    pub project_id: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskUpdate {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewSession {
+    pub project_id: String,
+    pub task_id: Option<String>,
+    pub pane_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionUpdate {
+    pub status: Option<String>,
+    pub pane_id: Option<String>,
+    pub ended_at: Option<i64>,
+}
+
+pub fn get_project(conn: &Connection, id: &str) -> rusqlite::Result<Option<Project>> {
+    conn.query_row(
+        "SELECT * FROM projects WHERE id = ?1",
+        [id],
+        Project::from_row,
+    )
+    .optional()
+}
+
+pub fn get_task(conn: &Connection, id: &str) -> rusqlite::Result<Option<Task>> {
+    conn.query_row("SELECT * FROM tasks WHERE id = ?1", [id], Task::from_row)
+        .optional()
+}
+
+/// Dependencies of the tasks in `project_id`, or of every task
+pub fn list_task_dependencies(
+    conn: &Connection,
+    project_id: Option<&str>,
+) -> rusqlite::Result<Vec<TaskDependency>> {
+    let mut stmt = conn.prepare(
+        "SELECT d.task_id, d.depends_on, d.created_at FROM task_dependencies d JOIN tasks t ON t.id = d.task_id WHERE ?1 IS NULL OR t.project_id = ?1 ORDER BY d.created_at",
+    )?;
+    let rows = stmt.query_map([project_id], |row| {
+        Ok(TaskDependency {
+            task_id: row.get(0)?,
+            depends_on: row.get(1)?,
+            created_at: row.get(2)?,
+        })
+    })?;
+    rows.collect()
+}
+
+pub fn insert_task_dependency(
+    conn: &Connection,
+    task_id: &str,
+    depends_on: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO task_dependencies (task_id, depends_on, created_at) VALUES (?1, ?2, ?3)",
+        params![task_id, depends_on, now_ms()],
+    )
+    .map(|_| ())
+}
+
+pub fn delete_task_dependency(
+    conn: &Connection,
+    task_id: &str,
+    depends_on: &str,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "DELETE FROM task_dependencies WHERE task_id = ?1 AND depends_on = ?2",
+        params![task_id, depends_on],
+    )
+    .map(|_| ())
+}
+
+pub fn get_session(conn: &Connection, id: &str) -> rusqlite::Result<Option<Session>> {
+    conn.query_row(
+        "SELECT * FROM sessions WHERE id = ?1",
+        [id],
+        Session::from_row,
+    )
+    .optional()
+}
+
+/// The newest unfinished session recorded for a tmux pane
+pub fn open_session_for_pane(
+    conn: &Connection,
+    pane_id: &str,
+) -> rusqlite::Result<Option<Session>> {
+    conn.query_row(
+        "SELECT * FROM sessions WHERE pane_id = ?1 AND ended_at IS NULL ORDER BY started_at DESC LIMIT 1",
+        [pane_id],
+        Session::from_row,
+    )
+    .optional()
+}
+
+#[tauri::command]
+pub fn get_encryption_status() -> EncryptionStatus {
+    EncryptionStatus {
+        supported: ENCRYPTION_SUPPORTED,
+        enabled: db_path().is_some_and(|path| is_encrypted(&path)),
+    }
+}
+
+/// Encrypt the database (or decrypt it back) by exporting it into a new file and
+/// swapping that in; writers are held off for the duration
+#[tauri::command]
+pub fn set_encryption_enabled(enabled: bool) -> Result<EncryptionStatus, Error> {
+    if !ENCRYPTION_SUPPORTED {
+        return Err(Error::Unsupported(
+            "Database encryption isn't available on Windows".to_string(),
+        ));
+    }
+    let path = db_path().ok_or("Could not determine data directory")?;
+    let mut guard = connection()?;
+    if is_encrypted(&path) == enabled {
+        drop(guard);
+        return Ok(get_encryption_status());
+    }
+    let conn = guard.as_ref().ok_or("Database not open")?;
+    let key = if enabled {
+        database_key(true)?
+    } else {
+        String::new()
+    };
+
+    let converted = path.with_extension("db.converting");
+    let _ = fs::remove_file(&converted);
+    let export = (|| {
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        conn.execute(
+            "ATTACH DATABASE ?1 AS converted KEY ?2",
+            params![converted.to_string_lossy(), key],
+        )?;
+        conn.query_row("SELECT sqlcipher_export('converted')", [], |_| Ok(()))?;
+        // sqlcipher_export copies the schema and rows but not the schema version
+        conn.execute_batch(&format!(
+            "PRAGMA converted.user_version = {}; DETACH DATABASE converted;",
+            version
+        ))
+    })();
+    if let Err(e) = export {
+        let _ = conn.execute_batch("DETACH DATABASE converted");
+        let _ = fs::remove_file(&converted);
+        return Err(Error::Internal(format!(
+            "Failed to convert the database: {}",
+            e
+        )));
+    }
+
+    // Closing checkpoints the WAL into the old file, which is then replaced
+    *guard = None;
+    for sidecar in ["db-wal", "db-shm"] {
+        let _ = fs::remove_file(path.with_extension(sidecar));
+    }
+    fs::rename(&converted, &path).map_err(|e| {
+        let _ = fs::remove_file(&converted);
+        format!("Failed to replace the database: {}", e)
+    })?;
+    drop(guard);
+    println!(
+        "[Claude PM] Database {}",
+        if enabled { "encrypted" } else { "decrypted" }
+    );
+    Ok(get_encryption_status())
+}
+
+#[tauri::command]
+pub fn list_projects() -> Result<Vec<Project>, Error> {
+    with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT * FROM projects ORDER BY name COLLATE NOCASE")?;
+        let rows = stmt.query_map([], Project::from_row)?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn create_project(project: NewProject) -> Result<Project, Error> {
+    if project.name.trim().is_empty() {
+        return Err(Error::InvalidInput("Project name is required".to_string()));
+    }
+    let id = new_id();
+    let now = now_ms();
+    with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO projects (id, name, repo_path, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?4)",
+            params![id, project.name, project.repo_path, now],
+        )?;
+        get_project(conn, &id).map(|p| p.expect("just inserted"))
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn update_project(id: String, update: ProjectUpdate) -> Result<Project, Error> {
+    with_conn(|conn| {
+        conn.execute(
+            "UPDATE projects SET name = COALESCE(?2, name), repo_path = COALESCE(?3, repo_path), updated_at = ?4 WHERE id = ?1",
+            params![id, update.name, update.repo_path, now_ms()],
+        )?;
+        get_project(conn, &id)
+    })?
+    .ok_or_else(|| Error::NotFound(format!("Project not found: {}", id)))
+}
+
+/// Recently opened projects, newest first
+pub fn recent_projects(limit: usize) -> Result<Vec<Project>, String> {
+    with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT p.* FROM recent_projects r JOIN projects p ON p.id = r.project_id ORDER BY r.opened_at DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map([limit], Project::from_row)?;
+        rows.collect()
+    })
+}
+
+/// Move a project to the top of the recent list, keeping only the newest `keep`
+pub fn touch_recent_project(id: &str, keep: usize) -> Result<(), String> {
+    with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO recent_projects (project_id, opened_at) VALUES (?1, ?2) ON CONFLICT(project_id) DO UPDATE SET opened_at = excluded.opened_at",
+            params![id, now_ms()],
+        )?;
+        conn.execute(
+            "DELETE FROM recent_projects WHERE project_id NOT IN (SELECT project_id FROM recent_projects ORDER BY opened_at DESC LIMIT ?1)",
+            [keep],
+        )?;
+        Ok(())
+    })
+}
+
+pub fn clear_recent_projects() -> Result<(), String> {
+    with_conn(|conn| conn.execute("DELETE FROM recent_projects", []).map(|_| ()))
+}
+
+#[tauri::command]
+pub fn list_tasks(project_id: Option<String>, state: Option<String>) -> Result<Vec<Task>, Error> {
+    with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT * FROM tasks WHERE (?1 IS NULL OR project_id = ?1) AND (?2 IS NULL OR state = ?2) ORDER BY updated_at DESC",
+        )?;
+        let rows = stmt.query_map(params![project_id, state], Task::from_row)?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn create_task(task: NewTask) -> Result<Task, Error> {
+    if task.title.trim().is_empty() {
+        return Err(Error::InvalidInput("Task title is required".to_string()));
+    }
+    let state = task.state.unwrap_or_else(|| "backlog".to_string());
+    validate_state(&state)?;
+    let id = new_id();
+    let now = now_ms();
+    with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO tasks (id, project_id, title, description, state, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+            params![id, task.project_id, task.title, task.description, state, now],
+        )?;
+        get_task(conn, &id).map(|t| t.expect("just inserted"))
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn update_task(id: String, update: TaskUpdate) -> Result<Task, Error> {
+    if let Some(ref state) = update.state {
+        validate_state(state)?;
+    }
+    let (before, task) = with_conn(|conn| {
+        let before = get_task(conn, &id)?.map(|t| t.state);
+        conn.execute(
+            "UPDATE tasks SET title = COALESCE(?2, title), description = COALESCE(?3, description), state = COALESCE(?4, state), updated_at = ?5 WHERE id = ?1",
+            params![id, update.title, update.description, update.state, now_ms()],
+        )?;
+        Ok((before, get_task(conn, &id)?))
+    })?;
+    let task = task.ok_or_else(|| Error::NotFound(format!("Task not found: {}", id)))?;
+    if task.state == "done" && before.as_deref() != Some("done") {
+        event_bus::publish(AppEvent::TaskCompleted { task: task.clone() });
+    }
+    Ok(task)
+}
+
+#[tauri::command]
+pub fn list_task_comments(task_id: String) -> Result<Vec<TaskComment>, Error> {
+    with_conn(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT * FROM task_comments WHERE task_id = ?1 ORDER BY created_at")?;
+        let rows = stmt.query_map([task_id], TaskComment::from_row)?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn add_task_comment(task_id: String, body: String) -> Result<TaskComment, Error> {
+    if body.trim().is_empty() {
+        return Err(Error::InvalidInput("Comment is empty".to_string()));
+    }
+    let comment = TaskComment {
+        id: new_id(),
+        task_id,
+        body,
+        created_at: now_ms(),
+    };
+    let inserted = with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO task_comments (id, task_id, body, created_at) SELECT ?1, id, ?3, ?4 FROM tasks WHERE id = ?2",
+            params![comment.id, comment.task_id, comment.body, comment.created_at],
+        )
+    })?;
+    if inserted == 0 {
+        return Err(Error::NotFound(format!(
+            "Task not found: {}",
+            comment.task_id
+        )));
+    }
+    Ok(comment)
+}
+
+#[tauri::command]
+pub fn delete_task_comment(id: String) -> Result<(), Error> {
+    with_conn(|conn| conn.execute("DELETE FROM task_comments WHERE id = ?1", [id]))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_sessions(
+    project_id: Option<String>,
+    status: Option<String>,
+) -> Result<Vec<Session>, Error> {
+    with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT * FROM sessions WHERE (?1 IS NULL OR project_id = ?1) AND (?2 IS NULL OR status = ?2) ORDER BY started_at DESC",
+        )?;
+        let rows = stmt.query_map(params![project_id, status], Session::from_row)?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn create_session(session: NewSession) -> Result<Session, Error> {
+    let id = new_id();
+    with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO sessions (id, project_id, task_id, pane_id, started_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![id, session.project_id, session.task_id, session.pane_id, now_ms()],
+        )?;
+        get_session(conn, &id).map(|s| s.expect("just inserted"))
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn update_session(id: String, update: SessionUpdate) -> Result<Session, Error> {
+    with_conn(|conn| {
+        conn.execute(
+            "UPDATE sessions SET status = COALESCE(?2, status), pane_id = COALESCE(?3, pane_id), ended_at = COALESCE(?4, ended_at) WHERE id = ?1",
+            params![id, update.status, update.pane_id, update.ended_at],
+        )?;
+        get_session(conn, &id)
+    })?
+    .ok_or_else(|| Error::NotFound(format!("Session not found: {}", id)))
+}
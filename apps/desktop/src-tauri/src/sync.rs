@@ -0,0 +1,720 @@
+//! End-to-end encrypted sync of projects and tasks through the user's own storage
+//!
+//! Every synced field is mirrored in `sync_state` with the time and device of its last
+//! change. A sync records the local edits made since the last one (by comparing the
+//! tables against the mirror, deletions included), then merges the change sets of the
+//! other devices: per field the later change wins, with the device id breaking ties.
+//! When both sides changed a field since the last sync, the losing value is kept in
+//! `sync_conflicts`. Change sets are encrypted with ChaCha20-Poly1305, so the storage
+//! only ever holds ciphertext. With S3 or WebDAV (see `sync_remote`) the merged set is
+//! uploaded as this device's single object; with a synced folder (see `sync_folder`)
+//! each sync appends a file with just this device's new changes, and the folder is
+//! watched for files from peers. Deleted rows stay as tombstones in the mirror so
+//! a deletion reaches devices that sync later.
+//!
+//! The key is random and kept in the keychain; `get_sync_key` reveals it so it can be
+//! entered on the other devices with `set_sync_key`. The S3 secret key or WebDAV
+//! password is in the keychain as well.
+//!
+//! Events:
+//! - `sync-completed` with a [`SyncReport`]
+//! - `sync-failed` with the error message
+
+use chacha20poly1305::ChaCha20Poly1305;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::sync_remote::{self, SyncBackend};
+use crate::{app_lock, config, power, secrets, store, sync_folder};
+
+const KEY_ACCOUNT: &str = "sync-key";
+const SECRET_ACCOUNT: &str = "sync-secret";
+/// Bump when the change set format changes incompatibly
+const FORMAT_VERSION: u32 = 1;
+const DEFAULT_INTERVAL_MINS: u64 = 15;
+/// How often a sync folder is checked for peers' new files
+const FOLDER_POLL: Duration = Duration::from_secs(30);
+/// Synced tables and fields, parents first; every row also tracks `DELETED`
+const TABLES: &[(&str, &[&str])] = &[
+    ("projects", &["name", "repo_path", "created_at"]),
+    (
+        "tasks",
+        &["project_id", "title", "description", "state", "created_at"],
+    ),
+];
+const DELETED: &str = "_deleted";
+const LAST_SYNCED: &str = "last_synced_at";
+const DEVICE_ID: &str = "device_id";
+
+static SYNCING: AtomicBool = AtomicBool::new(false);
+static STARTED: AtomicBool = AtomicBool::new(false);
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SyncSettings {
+    pub enabled: bool,
+    pub backend: Option<SyncBackend>,
+    /// Minutes between background syncs (15 when unset)
+    pub interval_mins: Option<u64>,
+}
+
+/// One field's latest value; `value` is JSON so NULL and text stay distinct
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FieldChange {
+    table: String,
+    row_id: String,
+    field: String,
+    value: String,
+    updated_at: i64,
+    device_id: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangeSet {
+    format_version: u32,
+    device_id: String,
+    created_at: i64,
+    changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub at: i64,
+    /// Local field changes recorded since the last sync
+    pub recorded: usize,
+    /// Fields updated from other devices
+    pub pulled: usize,
+    pub conflicts: usize,
+    /// Other devices whose change sets were merged
+    pub devices: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub settings: SyncSettings,
+    pub device_id: Option<String>,
+    pub last_synced_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub has_key: bool,
+    pub syncing: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflict {
+    pub id: i64,
+    pub table: String,
+    pub row_id: String,
+    pub field: String,
+    pub local_value: Value,
+    pub remote_value: Value,
+    /// `local` or `remote`
+    pub kept: String,
+    pub remote_device: String,
+    pub at: i64,
+}
+
+fn decode_key(encoded: &str) -> Result<ChaCha20Poly1305, String> {
+    secrets::cipher(encoded).map_err(|e| format!("Sync key: {}", e))
+}
+
+/// The sync key, base64-encoded, creating one if asked
+fn key(create: bool) -> Result<Option<String>, String> {
+    match secrets::get(KEY_ACCOUNT)? {
+        Some(key) => Ok(Some(key)),
+        None if create => {
+            let key = secrets::generate_key();
+            secrets::set(KEY_ACCOUNT, &key)?;
+            Ok(Some(key))
+        }
+        None => Ok(None),
+    }
+}
+
+fn seal(cipher: &ChaCha20Poly1305, set: &ChangeSet) -> Result<Vec<u8>, String> {
+    let plaintext = serde_json::to_vec(set).map_err(|e| e.to_string())?;
+    secrets::seal(cipher, b"", &plaintext)
+}
+
+fn open(cipher: &ChaCha20Poly1305, sealed: &[u8]) -> Result<ChangeSet, String> {
+    let plaintext = secrets::open(cipher, b"", sealed)
+        .map_err(|_| "Failed to decrypt change set (different sync key?)".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Invalid change set: {}", e))
+}
+
+fn meta(conn: &Connection, key: &str) -> rusqlite::Result<Option<String>> {
+    conn.query_row("SELECT value FROM sync_meta WHERE key = ?1", [key], |row| {
+        row.get(0)
+    })
+    .optional()
+}
+
+fn set_meta(conn: &Connection, key: &str, value: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO sync_meta (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        [key, value],
+    )
+    .map(|_| ())
+}
+
+fn device_id(conn: &Connection) -> rusqlite::Result<String> {
+    if let Some(id) = meta(conn, DEVICE_ID)? {
+        return Ok(id);
+    }
+    let id = store::new_id();
+    set_meta(conn, DEVICE_ID, &id)?;
+    Ok(id)
+}
+
+fn to_json(value: SqlValue) -> String {
+    match value {
+        SqlValue::Null | SqlValue::Blob(_) => Value::Null,
+        SqlValue::Integer(i) => Value::from(i),
+        SqlValue::Real(f) => Value::from(f),
+        SqlValue::Text(s) => Value::from(s),
+    }
+    .to_string()
+}
+
+fn to_sql(value: &str) -> SqlValue {
+    match serde_json::from_str(value).unwrap_or(Value::Null) {
+        Value::Bool(b) => SqlValue::Integer(b as i64),
+        Value::Number(n) => n
+            .as_i64()
+            .map(SqlValue::Integer)
+            .or_else(|| n.as_f64().map(SqlValue::Real))
+            .unwrap_or(SqlValue::Null),
+        Value::String(s) => SqlValue::Text(s),
+        _ => SqlValue::Null,
+    }
+}
+
+fn upsert_state(conn: &Connection, change: &FieldChange) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO sync_state (table_name, row_id, field, value, updated_at, device_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(table_name, row_id, field) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at, device_id = excluded.device_id",
+        params![change.table, change.row_id, change.field, change.value, change.updated_at, change.device_id],
+    )
+    .map(|_| ())
+}
+
+fn load_state(conn: &Connection) -> rusqlite::Result<Vec<FieldChange>> {
+    let mut stmt = conn.prepare(
+        "SELECT table_name, row_id, field, value, updated_at, device_id FROM sync_state",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(FieldChange {
+            table: row.get(0)?,
+            row_id: row.get(1)?,
+            field: row.get(2)?,
+            value: row.get(3)?,
+            updated_at: row.get(4)?,
+            device_id: row.get(5)?,
+        })
+    })?;
+    rows.collect()
+}
+
+type Mirror = HashMap<(String, String, String), String>;
+
+/// Mirror one field's current value if it differs from what was last recorded
+fn record(conn: &Connection, mirror: &mut Mirror, change: FieldChange) -> rusqlite::Result<bool> {
+    let known = mirror.remove(&(
+        change.table.clone(),
+        change.row_id.clone(),
+        change.field.clone(),
+    ));
+    if known.as_ref() == Some(&change.value) {
+        return Ok(false);
+    }
+    upsert_state(conn, &change)?;
+    Ok(true)
+}
+
+/// Mirror local edits (and deletions) made since the last sync; returns how many fields changed
+fn record_local(conn: &Connection, device: &str, now: i64) -> rusqlite::Result<usize> {
+    let mut mirror: Mirror = load_state(conn)?
+        .into_iter()
+        .map(|c| ((c.table, c.row_id, c.field), c.value))
+        .collect();
+    let change = |table: &str, row_id: &str, field: &str, value: &str| FieldChange {
+        table: table.to_string(),
+        row_id: row_id.to_string(),
+        field: field.to_string(),
+        value: value.to_string(),
+        updated_at: now,
+        device_id: device.to_string(),
+    };
+    let mut recorded = 0;
+
+    for (table, fields) in TABLES {
+        let mut stmt = conn.prepare(&format!("SELECT id, {} FROM {}", fields.join(", "), table))?;
+        let rows: Vec<(String, Vec<String>)> = stmt
+            .query_map([], |row| {
+                let values = (0..fields.len())
+                    .map(|i| row.get::<_, SqlValue>(i + 1).map(to_json))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                Ok((row.get(0)?, values))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        for (id, values) in rows {
+            for (field, value) in fields.iter().zip(values) {
+                recorded += record(conn, &mut mirror, change(table, &id, field, &value))? as usize;
+            }
+            recorded += record(conn, &mut mirror, change(table, &id, DELETED, "false"))? as usize;
+        }
+    }
+    // Rows still marked live in what's left of the mirror were deleted here
+    let gone: Vec<FieldChange> = mirror
+        .iter()
+        .filter(|((_, _, field), value)| field == DELETED && value.as_str() == "false")
+        .map(|((table, row_id, _), _)| change(table, row_id, DELETED, "true"))
+        .collect();
+    for deleted in gone {
+        recorded += record(conn, &mut mirror, deleted)? as usize;
+    }
+    Ok(recorded)
+}
+
+/// Merge another device's change set into the mirror, adding the rows it changed to
+/// `dirty`; returns the number of fields pulled and conflicts logged
+fn merge(
+    conn: &Connection,
+    set: ChangeSet,
+    last_synced: i64,
+    now: i64,
+    dirty: &mut BTreeSet<(String, String)>,
+) -> rusqlite::Result<(usize, usize)> {
+    let (mut pulled, mut conflicts) = (0, 0);
+    for change in set.changes {
+        let known = TABLES.iter().any(|(table, fields)| {
+            *table == change.table
+                && (change.field == DELETED || fields.contains(&change.field.as_str()))
+        });
+        if !known {
+            continue;
+        }
+        let local: Option<(String, i64, String)> = conn
+            .query_row(
+                "SELECT value, updated_at, device_id FROM sync_state WHERE table_name = ?1 AND row_id = ?2 AND field = ?3",
+                params![change.table, change.row_id, change.field],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        let remote_wins = match local {
+            None => true,
+            Some((ref value, _, _)) if *value == change.value => false,
+            Some((_, at, ref device)) => (change.updated_at, &change.device_id) > (at, device),
+        };
+        if let Some((ref value, at, _)) = local {
+            if *value != change.value && at > last_synced && change.updated_at > last_synced {
+                conflicts += 1;
+                conn.execute(
+                    "INSERT INTO sync_conflicts (table_name, row_id, field, local_value, remote_value, kept, remote_device, at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        change.table,
+                        change.row_id,
+                        change.field,
+                        value,
+                        change.value,
+                        if remote_wins { "remote" } else { "local" },
+                        set.device_id,
+                        now
+                    ],
+                )?;
+            }
+        }
+        if remote_wins {
+            pulled += 1;
+            upsert_state(conn, &change)?;
+            dirty.insert((change.table, change.row_id));
+        }
+    }
+    Ok((pulled, conflicts))
+}
+
+/// Write the mirror's state of each changed row back to its table
+fn apply(conn: &Connection, dirty: &BTreeSet<(String, String)>, now: i64) -> rusqlite::Result<()> {
+    // Parents are inserted first and deleted last
+    let inserts = TABLES.iter();
+    let deletes = TABLES.iter().rev();
+    for (table, fields) in inserts {
+        for (_, row_id) in dirty.iter().filter(|(t, _)| t == table) {
+            let mut stmt = conn.prepare(
+                "SELECT field, value FROM sync_state WHERE table_name = ?1 AND row_id = ?2",
+            )?;
+            let state: HashMap<String, String> = stmt
+                .query_map([table, row_id.as_str()], |row| {
+                    Ok((row.get(0)?, row.get(1)?))
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+            if state.get(DELETED).map(String::as_str) == Some("true") {
+                continue;
+            }
+            let mut values: Vec<SqlValue> = vec![SqlValue::Text(row_id.clone())];
+            values.extend(
+                fields
+                    .iter()
+                    .map(|f| state.get(*f).map_or(SqlValue::Null, |v| to_sql(v))),
+            );
+            values.push(SqlValue::Integer(now));
+            let columns = fields.join(", ");
+            let placeholders: Vec<String> =
+                (1..=fields.len() + 2).map(|i| format!("?{}", i)).collect();
+            let updates: Vec<String> = fields
+                .iter()
+                .chain(std::iter::once(&"updated_at"))
+                .map(|f| format!("{} = excluded.{}", f, f))
+                .collect();
+            let sql = format!(
+                "INSERT INTO {} (id, {}, updated_at) VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
+                table,
+                columns,
+                placeholders.join(", "),
+                updates.join(", ")
+            );
+            // A row whose required fields or parent haven't arrived yet waits for a later sync
+            if let Err(e) = conn.execute(&sql, rusqlite::params_from_iter(values)) {
+                eprintln!("[Claude PM] Sync skipped {} {}: {}", table, row_id, e);
+            }
+        }
+    }
+    for (table, _) in deletes {
+        for (_, row_id) in dirty.iter().filter(|(t, _)| t == table) {
+            let deleted: Option<String> = conn
+                .query_row(
+                    "SELECT value FROM sync_state WHERE table_name = ?1 AND row_id = ?2 AND field = ?3",
+                    params![table, row_id, DELETED],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if deleted.as_deref() == Some("true") {
+                conn.execute(&format!("DELETE FROM {} WHERE id = ?1", table), [row_id])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn load_seen(conn: &Connection) -> rusqlite::Result<HashSet<String>> {
+    let mut stmt = conn.prepare("SELECT name FROM sync_seen")?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// Other devices' sealed change sets not merged yet, by name
+fn fetch(
+    backend: &SyncBackend,
+    secret: &str,
+    device: &str,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    if let SyncBackend::Folder { path } = backend {
+        let seen = store::with_conn(load_seen)?;
+        return sync_folder::pending(Path::new(path), device, &seen);
+    }
+    let own = format!("{}.bin", device);
+    let mut fetched = Vec::new();
+    for name in sync_remote::list(backend, secret)? {
+        if name == own || !name.ends_with(".bin") {
+            continue;
+        }
+        if let Some(sealed) = sync_remote::get(backend, secret, &name)? {
+            fetched.push((name, sealed));
+        }
+    }
+    Ok(fetched)
+}
+
+fn run_sync() -> Result<SyncReport, String> {
+    let settings = config::load().sync;
+    let backend = settings.backend.ok_or("Sync storage is not set up")?;
+    let secret = secrets::get(SECRET_ACCOUNT)?.unwrap_or_default();
+    let cipher = decode_key(&key(false)?.ok_or("No sync key; create or enter one first")?)?;
+    let started = store::now_ms();
+    let folder = match backend {
+        SyncBackend::Folder { ref path } => Some(PathBuf::from(path)),
+        _ => None,
+    };
+
+    let device = store::with_conn(device_id)?;
+    let mut remote = Vec::new();
+    for (name, sealed) in fetch(&backend, &secret, &device)? {
+        let set = open(&cipher, &sealed).map_err(|e| format!("{}: {}", name, e))?;
+        if set.format_version > FORMAT_VERSION {
+            return Err(format!(
+                "{} was written by a newer version of Claude PM; update to sync",
+                name
+            ));
+        }
+        remote.push((name, set));
+    }
+    let devices = remote
+        .iter()
+        .map(|(_, set)| set.device_id.as_str())
+        .collect::<HashSet<_>>()
+        .len();
+
+    let (recorded, pulled, conflicts, last_synced, state) = store::with_conn(|conn| {
+        let tx = conn.unchecked_transaction()?;
+        let last_synced = meta(&tx, LAST_SYNCED)?
+            .and_then(|at| at.parse().ok())
+            .unwrap_or(0);
+        let recorded = record_local(&tx, &device, started)?;
+        let (mut pulled, mut conflicts) = (0, 0);
+        let mut dirty = BTreeSet::new();
+        for (name, set) in remote {
+            let (p, c) = merge(&tx, set, last_synced, started, &mut dirty)?;
+            pulled += p;
+            conflicts += c;
+            if folder.is_some() {
+                tx.execute(
+                    "INSERT OR IGNORE INTO sync_seen (name, merged_at) VALUES (?1, ?2)",
+                    params![name, started],
+                )?;
+            }
+        }
+        apply(&tx, &dirty, started)?;
+        let state = load_state(&tx)?;
+        tx.commit()?;
+        Ok((recorded, pulled, conflicts, last_synced, state))
+    })?;
+
+    match folder {
+        // Peers read every device's files, so only this device's new changes are written
+        Some(ref folder) => {
+            let changes: Vec<FieldChange> = state
+                .into_iter()
+                .filter(|c| c.device_id == device && c.updated_at > last_synced)
+                .collect();
+            if !changes.is_empty() {
+                let sealed = seal(
+                    &cipher,
+                    &ChangeSet {
+                        format_version: FORMAT_VERSION,
+                        device_id: device.clone(),
+                        created_at: started,
+                        changes,
+                    },
+                )?;
+                sync_folder::append(folder, &device, started, &sealed)?;
+            }
+        }
+        None => {
+            let sealed = seal(
+                &cipher,
+                &ChangeSet {
+                    format_version: FORMAT_VERSION,
+                    device_id: device.clone(),
+                    created_at: started,
+                    changes: state,
+                },
+            )?;
+            sync_remote::put(&backend, &secret, &format!("{}.bin", device), &sealed)?;
+        }
+    }
+    store::with_conn(|conn| set_meta(conn, LAST_SYNCED, &started.to_string()))?;
+    Ok(SyncReport {
+        at: started,
+        recorded,
+        pulled,
+        conflicts,
+        devices,
+    })
+}
+
+/// Sync once, reporting the outcome to the frontend; one sync at a time
+fn sync(app: &AppHandle) -> Result<SyncReport, Error> {
+    if SYNCING.swap(true, Ordering::SeqCst) {
+        return Err(Error::InvalidInput("A sync is already running".to_string()));
+    }
+    let result = run_sync();
+    SYNCING.store(false, Ordering::SeqCst);
+    if let Ok(mut last) = LAST_ERROR.lock() {
+        *last = result.as_ref().err().cloned();
+    }
+    match result {
+        Ok(report) => {
+            println!(
+                "[Claude PM] Synced: {} recorded, {} pulled, {} conflicts",
+                report.recorded, report.pulled, report.conflicts
+            );
+            let _ = app.emit("sync-completed", &report);
+            Ok(report)
+        }
+        Err(e) => {
+            eprintln!("[Claude PM] Sync failed: {}", e);
+            let _ = app.emit("sync-failed", &e);
+            Err(Error::Internal(e))
+        }
+    }
+}
+
+pub fn is_syncing() -> bool {
+    SYNCING.load(Ordering::SeqCst)
+}
+
+pub fn last_synced_at() -> Option<i64> {
+    store::with_conn(|conn| meta(conn, LAST_SYNCED))
+        .ok()
+        .flatten()
+        .and_then(|at| at.parse().ok())
+}
+
+/// Why the last sync failed; cleared by the next one that succeeds
+pub fn last_error() -> Option<String> {
+    LAST_ERROR.lock().ok().and_then(|e| e.clone())
+}
+
+fn status() -> SyncStatus {
+    SyncStatus {
+        settings: config::load().sync,
+        device_id: store::with_conn(|conn| meta(conn, DEVICE_ID))
+            .ok()
+            .flatten(),
+        last_synced_at: last_synced_at(),
+        last_error: last_error(),
+        has_key: secrets::get(KEY_ACCOUNT).ok().flatten().is_some(),
+        syncing: is_syncing(),
+    }
+}
+
+/// A sync folder holds files from peers that haven't been merged yet
+fn folder_has_pending(backend: &SyncBackend) -> bool {
+    let SyncBackend::Folder { path } = backend else {
+        return false;
+    };
+    store::with_conn(|conn| Ok((device_id(conn)?, load_seen(conn)?)))
+        .is_ok_and(|(device, seen)| sync_folder::has_pending(Path::new(path), &device, &seen))
+}
+
+/// Sync in the background while enabled, and as soon as a sync folder has peers' changes
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || {
+        let mut last_sync = Instant::now();
+        loop {
+            power::wait(FOLDER_POLL);
+            let settings = config::load().sync;
+            let Some(backend) = settings.backend.filter(|_| settings.enabled) else {
+                continue;
+            };
+            let interval = settings
+                .interval_mins
+                .unwrap_or(DEFAULT_INTERVAL_MINS)
+                .max(1);
+            let due = last_sync.elapsed() >= power::throttled(Duration::from_secs(interval * 60));
+            if due || folder_has_pending(&backend) {
+                last_sync = Instant::now();
+                let _ = sync(&app);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_sync_status() -> SyncStatus {
+    status()
+}
+
+/// Save sync settings; `secret` (S3 secret key or WebDAV password) replaces the stored
+/// one, an empty string removes it
+#[tauri::command]
+pub fn set_sync_settings(
+    settings: SyncSettings,
+    secret: Option<String>,
+) -> Result<SyncStatus, Error> {
+    if settings.interval_mins == Some(0) {
+        return Err(Error::InvalidInput(
+            "Sync interval must be at least one minute".to_string(),
+        ));
+    }
+    if let Some(SyncBackend::Folder { ref path }) = settings.backend {
+        if !Path::new(path).is_dir() {
+            return Err(Error::NotFound(format!("{} is not a folder", path)));
+        }
+    }
+    if settings.enabled && settings.backend.is_none() {
+        return Err(Error::InvalidInput(
+            "Choose where to sync first".to_string(),
+        ));
+    }
+    match secret.as_deref() {
+        Some("") => secrets::delete(SECRET_ACCOUNT)?,
+        Some(secret) => secrets::set(SECRET_ACCOUNT, secret)?,
+        None => {}
+    }
+    config::update(|c| c.sync = settings)?;
+    Ok(status())
+}
+
+/// The sync key, to enter on another device; created on first use
+#[tauri::command]
+pub fn get_sync_key() -> Result<String, Error> {
+    app_lock::ensure_unlocked()?;
+    Ok(key(true)?.unwrap_or_default())
+}
+
+/// Use the key from another device, so this one can read what it uploaded
+#[tauri::command]
+pub fn set_sync_key(key: String) -> Result<(), Error> {
+    app_lock::ensure_unlocked()?;
+    decode_key(&key).map_err(Error::InvalidInput)?;
+    secrets::set(KEY_ACCOUNT, key.trim())?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn sync_now(app: AppHandle) -> Result<SyncReport, Error> {
+    tauri::async_runtime::spawn_blocking(move || sync(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Conflicts resolved by the last syncs, newest first
+#[tauri::command]
+pub fn list_sync_conflicts(limit: Option<usize>) -> Result<Vec<SyncConflict>, Error> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, table_name, row_id, field, local_value, remote_value, kept, remote_device, at FROM sync_conflicts ORDER BY at DESC, id DESC LIMIT ?1",
+        )?;
+        let parse = |value: String| serde_json::from_str(&value).unwrap_or(Value::String(value));
+        let rows = stmt.query_map([limit.unwrap_or(100) as i64], |row| {
+            Ok(SyncConflict {
+                id: row.get(0)?,
+                table: row.get(1)?,
+                row_id: row.get(2)?,
+                field: row.get(3)?,
+                local_value: parse(row.get(4)?),
+                remote_value: parse(row.get(5)?),
+                kept: row.get(6)?,
+                remote_device: row.get(7)?,
+                at: row.get(8)?,
+            })
+        })?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn clear_sync_conflicts() -> Result<(), Error> {
+    store::with_conn(|conn| conn.execute("DELETE FROM sync_conflicts", []).map(|_| ()))
+        .map_err(Error::from)
+}
@@ -0,0 +1,78 @@
+//! Folder backend for `sync`: a directory kept in sync by iCloud Drive, Dropbox and the like
+//!
+//! Each device appends files to its own subdirectory (`<folder>/<device id>/<millis>.bin`)
+//! holding only the changes it made since its previous file, and never rewrites them, so
+//! the sync client never has two versions of a file to reconcile. Files are written under
+//! a temporary name and renamed once complete. Peers' files are read once; their names
+//! are remembered (see `sync_seen`), and re-reading one is harmless anyway because
+//! merging is idempotent. Files the sync client hasn't downloaded yet (iCloud's
+//! `.<name>.icloud` placeholders) are picked up on a later pass.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+const EXTENSION: &str = "bin";
+
+/// Peers' change set files not in `seen`, oldest first, as (`<device>/<file>`, contents)
+pub fn pending(
+    folder: &Path,
+    own_device: &str,
+    seen: &HashSet<String>,
+) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut names = pending_names(folder, own_device, seen)?;
+    names.sort_by(|a, b| a.rsplit('/').next().cmp(&b.rsplit('/').next()));
+    names
+        .into_iter()
+        .map(|name| {
+            fs::read(folder.join(&name))
+                .map(|contents| (name.clone(), contents))
+                .map_err(|e| format!("Failed to read {}: {}", name, e))
+        })
+        .collect()
+}
+
+fn pending_names(
+    folder: &Path,
+    own_device: &str,
+    seen: &HashSet<String>,
+) -> Result<Vec<String>, String> {
+    let entries = fs::read_dir(folder)
+        .map_err(|e| format!("Failed to read sync folder {}: {}", folder.display(), e))?;
+    let mut names = Vec::new();
+    for device in entries.filter_map(Result::ok) {
+        let device_name = device.file_name().to_string_lossy().to_string();
+        if device_name == own_device || device_name.starts_with('.') || !device.path().is_dir() {
+            continue;
+        }
+        let Ok(files) = fs::read_dir(device.path()) else {
+            continue;
+        };
+        for file in files.filter_map(Result::ok) {
+            let path = file.path();
+            if path.extension().is_some_and(|ext| ext == EXTENSION) && path.is_file() {
+                let name = format!("{}/{}", device_name, file.file_name().to_string_lossy());
+                if !seen.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Whether a peer has written anything we haven't merged yet; cheap enough to poll
+pub fn has_pending(folder: &Path, own_device: &str, seen: &HashSet<String>) -> bool {
+    pending_names(folder, own_device, seen).is_ok_and(|names| !names.is_empty())
+}
+
+/// Add a change set file to this device's directory
+pub fn append(folder: &Path, own_device: &str, at: i64, contents: &[u8]) -> Result<(), String> {
+    let dir = folder.join(own_device);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.{}", at, EXTENSION));
+    let partial = dir.join(format!(".{}.partial", at));
+    fs::write(&partial, contents)
+        .and_then(|_| fs::rename(&partial, &path))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
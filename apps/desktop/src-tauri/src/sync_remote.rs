@@ -0,0 +1,290 @@
+//! Storage backends for `sync`: an S3-compatible bucket or a WebDAV folder
+//!
+//! Both hold one opaque object per device under `devices/`; the local folder backend
+//! works differently and lives in `sync_folder`. S3 goes through `rust-s3` with
+//! path-style URLs, so MinIO, R2 and B2 work with a custom endpoint; WebDAV uses basic
+//! auth, creates its folders with `MKCOL` on first upload and lists them with `PROPFIND`.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use quick_xml::escape::unescape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use s3::creds::Credentials;
+use s3::error::S3Error;
+use s3::{Bucket, Region};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::time::Duration;
+
+const DEVICES: &str = "devices";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum SyncBackend {
+    S3 {
+        /// `https://s3.<region>.amazonaws.com` when unset
+        endpoint: Option<String>,
+        region: String,
+        bucket: String,
+        /// Key prefix inside the bucket, e.g. `claudepm`
+        prefix: Option<String>,
+        access_key_id: String,
+    },
+    #[serde(rename = "webdav")]
+    WebDav {
+        /// Folder URL, e.g. `https://cloud.example.com/remote.php/dav/files/me/claudepm`
+        url: String,
+        username: Option<String>,
+    },
+    /// A folder synced by iCloud Drive, Dropbox etc. (see `sync_folder`)
+    Folder { path: String },
+}
+
+fn read_body(response: ureq::Response) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    Ok(body)
+}
+
+struct S3 {
+    bucket: Box<Bucket>,
+    prefix: String,
+}
+
+impl S3 {
+    fn key(&self, name: &str) -> String {
+        format!("{}{}/{}", self.prefix, DEVICES, name)
+    }
+
+    fn list(&self) -> Result<Vec<String>, String> {
+        let prefix = self.key("");
+        let pages = self
+            .bucket
+            .list(prefix.clone(), None)
+            .map_err(|e| format!("Failed to list the bucket: {}", e))?;
+        Ok(pages
+            .into_iter()
+            .flat_map(|page| page.contents)
+            .filter_map(|object| object.key.strip_prefix(&prefix).map(str::to_string))
+            .filter(|name| !name.is_empty() && !name.contains('/'))
+            .collect())
+    }
+}
+
+/// `href`s in a PROPFIND multistatus body, whatever namespace prefix the server uses
+fn propfind_hrefs(body: &str) -> Result<Vec<String>, String> {
+    let invalid = |e: &dyn std::fmt::Display| format!("Invalid PROPFIND response: {}", e);
+    let mut reader = Reader::from_str(body);
+    let mut hrefs = Vec::new();
+    loop {
+        match reader.read_event().map_err(|e| invalid(&e))? {
+            Event::Start(start) if start.local_name().as_ref() == b"href" => {
+                let raw = reader.read_text(start.name()).map_err(|e| invalid(&e))?;
+                hrefs.push(unescape(&raw).map_err(|e| invalid(&e))?.into_owned());
+            }
+            Event::Eof => return Ok(hrefs),
+            _ => {}
+        }
+    }
+}
+
+struct WebDav {
+    url: String,
+    auth: Option<String>,
+}
+
+impl WebDav {
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let request = ureq::request(method, url).timeout(REQUEST_TIMEOUT);
+        match self.auth {
+            Some(ref auth) => request.set("Authorization", auth),
+            None => request,
+        }
+    }
+
+    fn devices_url(&self) -> String {
+        format!("{}/{}/", self.url, DEVICES)
+    }
+
+    fn list(&self) -> Result<Vec<String>, String> {
+        let response = match self
+            .request("PROPFIND", &self.devices_url())
+            .set("Depth", "1")
+            .call()
+        {
+            Ok(response) => response,
+            // Nothing uploaded yet
+            Err(ureq::Error::Status(404, _)) => return Ok(Vec::new()),
+            Err(e) => return Err(format!("Failed to list the WebDAV folder: {}", e)),
+        };
+        let body = String::from_utf8_lossy(&read_body(response)?).to_string();
+        Ok(propfind_hrefs(&body)?
+            .into_iter()
+            .filter_map(|href| {
+                let name = href.trim_end_matches('/').rsplit('/').next()?;
+                let name = percent_decode_str(name).decode_utf8().ok()?.into_owned();
+                (!href.ends_with('/') && !name.is_empty()).then_some(name)
+            })
+            .collect())
+    }
+
+    /// Create the folder and `devices/` below it; "already exists" answers are fine
+    fn ensure_folders(&self) -> Result<(), String> {
+        for url in [format!("{}/", self.url), self.devices_url()] {
+            match self.request("MKCOL", &url).call() {
+                Ok(_) | Err(ureq::Error::Status(405, _)) => {}
+                Err(e) => return Err(format!("Failed to create {}: {}", url, e)),
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Client {
+    S3(S3),
+    WebDav(WebDav),
+}
+
+fn client(backend: &SyncBackend, secret: &str) -> Result<Client, String> {
+    Ok(match backend {
+        SyncBackend::S3 {
+            endpoint,
+            region,
+            bucket,
+            prefix,
+            access_key_id,
+        } => {
+            let region = Region::Custom {
+                region: region.clone(),
+                endpoint: endpoint
+                    .clone()
+                    .filter(|e| !e.is_empty())
+                    .unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region))
+                    .trim_end_matches('/')
+                    .to_string(),
+            };
+            let credentials = Credentials::new(Some(access_key_id), Some(secret), None, None, None)
+                .map_err(|e| format!("Invalid S3 credentials: {}", e))?;
+            let bucket = Bucket::new(bucket, region, credentials)
+                .and_then(|bucket| {
+                    bucket
+                        .with_path_style()
+                        .with_request_timeout(REQUEST_TIMEOUT)
+                })
+                .map_err(|e| format!("Invalid S3 bucket: {}", e))?;
+            Client::S3(S3 {
+                bucket,
+                prefix: prefix
+                    .as_deref()
+                    .map(|p| p.trim_matches('/'))
+                    .filter(|p| !p.is_empty())
+                    .map(|p| format!("{}/", p))
+                    .unwrap_or_default(),
+            })
+        }
+        SyncBackend::WebDav { url, username } => Client::WebDav(WebDav {
+            url: url.trim_end_matches('/').to_string(),
+            auth: username
+                .as_ref()
+                .map(|user| format!("Basic {}", BASE64.encode(format!("{}:{}", user, secret)))),
+        }),
+        SyncBackend::Folder { .. } => {
+            return Err("Folder sync doesn't use an object store".to_string())
+        }
+    })
+}
+
+/// Names of the device objects stored remotely
+pub fn list(backend: &SyncBackend, secret: &str) -> Result<Vec<String>, String> {
+    match client(backend, secret)? {
+        Client::S3(s3) => s3.list(),
+        Client::WebDav(dav) => dav.list(),
+    }
+}
+
+/// Fetch one device object; `None` if it has gone away since it was listed
+pub fn get(backend: &SyncBackend, secret: &str, name: &str) -> Result<Option<Vec<u8>>, String> {
+    let dav = match client(backend, secret)? {
+        Client::S3(s3) => {
+            return match s3.bucket.get_object(s3.key(name)) {
+                Ok(response) => Ok(Some(response.to_vec())),
+                Err(S3Error::HttpFailWithBody(404, _)) => Ok(None),
+                Err(e) => Err(format!("Failed to download {}: {}", name, e)),
+            }
+        }
+        Client::WebDav(dav) => dav,
+    };
+    let url = format!(
+        "{}{}",
+        dav.devices_url(),
+        utf8_percent_encode(name, NON_ALPHANUMERIC)
+    );
+    match dav.request("GET", &url).call() {
+        Ok(response) => read_body(response).map(Some),
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(e) => Err(format!("Failed to download {}: {}", name, e)),
+    }
+}
+
+pub fn put(backend: &SyncBackend, secret: &str, name: &str, contents: &[u8]) -> Result<(), String> {
+    let failed = |e: &dyn std::fmt::Display| format!("Failed to upload {}: {}", name, e);
+    match client(backend, secret)? {
+        Client::S3(s3) => s3
+            .bucket
+            .put_object(s3.key(name), contents)
+            .map(|_| ())
+            .map_err(|e| failed(&e)),
+        Client::WebDav(dav) => {
+            let url = format!(
+                "{}{}",
+                dav.devices_url(),
+                utf8_percent_encode(name, NON_ALPHANUMERIC)
+            );
+            match dav.request("PUT", &url).send_bytes(contents) {
+                // Parent folders don't exist yet
+                Err(ureq::Error::Status(404 | 409, _)) => {
+                    dav.ensure_folders()?;
+                    dav.request("PUT", &url).send_bytes(contents).map(|_| ())
+                }
+                result => result.map(|_| ()),
+            }
+            .map_err(|e| failed(&e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_hrefs_under_any_prefix() {
+        let body = r#"<?xml version="1.0"?>
+<d:multistatus xmlns:d="DAV:">
+  <d:response><d:href>/dav/claudepm/devices/</d:href></d:response>
+  <d:response><d:href>/dav/claudepm/devices/a&amp;b</d:href></d:response>
+</d:multistatus>"#;
+        assert_eq!(
+            propfind_hrefs(body).unwrap(),
+            ["/dav/claudepm/devices/", "/dav/claudepm/devices/a&b"]
+        );
+
+        let body = r#"<multistatus xmlns="DAV:"><response><href>/x/devices/laptop</href></response></multistatus>"#;
+        assert_eq!(propfind_hrefs(body).unwrap(), ["/x/devices/laptop"]);
+    }
+
+    #[test]
+    fn rejects_malformed_propfind_bodies() {
+        assert!(propfind_hrefs("<d:multistatus><d:href>x</d:multistatus>").is_err());
+    }
+}
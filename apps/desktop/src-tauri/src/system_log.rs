@@ -0,0 +1,208 @@
+//! Forwarding logs to the OS logging system
+//!
+//! Besides the log files, everything the app prints (the `[Claude PM] ...` lines on
+//! stdout and stderr) and every line of server output goes to macOS unified logging
+//! under the subsystem `com.claudepm.desktop`, category `app` or `server`, and to the
+//! Windows Application event log under the source `Claude PM`. Support can then ask
+//! for a Console.app search for `com.claudepm` (or `log show --predicate 'subsystem ==
+//! "com.claudepm.desktop"'`) rather than for files. Lines on stderr are logged as errors.
+//!
+//! The app's own output is captured by pointing stdout and stderr at pipes; each line is
+//! still copied to the original stream. `os_logging: false` in the config turns this
+//! off. Nothing is forwarded on Linux.
+
+#[cfg(any(target_os = "macos", windows))]
+use std::fs::File;
+#[cfg(any(target_os = "macos", windows))]
+use std::io::{self, BufRead, BufReader, PipeReader, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(any(target_os = "macos", windows))]
+use std::thread;
+
+use crate::config;
+
+#[cfg(target_os = "macos")]
+const SUBSYSTEM: &str = "com.claudepm.desktop";
+#[cfg(windows)]
+const EVENT_SOURCE: &str = "Claude PM";
+const SUPPORTED: bool = cfg!(any(target_os = "macos", windows));
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Clone, Copy)]
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+enum Category {
+    App,
+    Server,
+}
+
+impl Category {
+    #[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+    fn name(self) -> &'static str {
+        match self {
+            Category::App => "app",
+            Category::Server => "server",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+#[cfg_attr(not(any(target_os = "macos", windows)), allow(dead_code))]
+enum Level {
+    Info,
+    Error,
+}
+
+#[cfg(target_os = "macos")]
+fn write(category: Category, level: Level, message: &str) {
+    use oslog::OsLog;
+    use std::sync::OnceLock;
+
+    static APP: OnceLock<OsLog> = OnceLock::new();
+    static SERVER: OnceLock<OsLog> = OnceLock::new();
+    let log = match category {
+        Category::App => &APP,
+        Category::Server => &SERVER,
+    }
+    .get_or_init(|| OsLog::new(SUBSYSTEM, category.name()));
+    // `Default` rather than `Info`, which isn't persisted unless enabled
+    let level = match level {
+        Level::Info => oslog::Level::Default,
+        Level::Error => oslog::Level::Error,
+    };
+    log.with_level(level, message);
+}
+
+#[cfg(windows)]
+fn write(category: Category, level: Level, message: &str) {
+    use std::sync::OnceLock;
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::EventLog::{
+        RegisterEventSourceW, ReportEventW, EVENTLOG_ERROR_TYPE, EVENTLOG_INFORMATION_TYPE,
+    };
+
+    // The handle as an address, since HANDLE itself isn't Sync
+    static SOURCE: OnceLock<Option<usize>> = OnceLock::new();
+    let source = *SOURCE.get_or_init(|| unsafe {
+        RegisterEventSourceW(PCWSTR::null(), &HSTRING::from(EVENT_SOURCE))
+            .ok()
+            .map(|handle| handle.0 as usize)
+    });
+    let Some(source) = source else {
+        return;
+    };
+    let kind = match level {
+        Level::Info => EVENTLOG_INFORMATION_TYPE,
+        Level::Error => EVENTLOG_ERROR_TYPE,
+    };
+    let text = HSTRING::from(format!("[{}] {}", category.name(), message));
+    let strings = [PCWSTR(text.as_ptr())];
+    let _ = unsafe {
+        ReportEventW(
+            HANDLE(source as *mut std::ffi::c_void),
+            kind,
+            category as u16 + 1,
+            0,
+            None,
+            0,
+            Some(&strings),
+            None,
+        )
+    };
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn write(_category: Category, _level: Level, _message: &str) {}
+
+/// Copy each captured line to the original stream and the OS log
+#[cfg(any(target_os = "macos", windows))]
+fn forward(reader: PipeReader, mut original: Option<File>, level: Level) {
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines().map_while(Result::ok) {
+            if let Some(out) = original.as_mut() {
+                let _ = writeln!(out, "{}", line);
+            }
+            let message = line.strip_prefix("[Claude PM] ").unwrap_or(&line);
+            write(Category::App, level, message);
+        }
+    });
+}
+
+/// Point file descriptor `fd` at a pipe
+#[cfg(target_os = "macos")]
+fn redirect(fd: i32, level: Level) -> io::Result<()> {
+    use std::os::fd::{AsRawFd, FromRawFd};
+
+    extern "C" {
+        fn dup(fd: i32) -> i32;
+        fn dup2(src: i32, dst: i32) -> i32;
+    }
+    let (reader, writer) = io::pipe()?;
+    let original = unsafe { dup(fd) };
+    if original < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if unsafe { dup2(writer.as_raw_fd(), fd) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    forward(reader, Some(unsafe { File::from_raw_fd(original) }), level);
+    Ok(())
+}
+
+/// Point a standard handle at a pipe; a release build has no console to copy to
+#[cfg(windows)]
+fn redirect(handle: windows::Win32::System::Console::STD_HANDLE, level: Level) -> io::Result<()> {
+    use std::os::windows::io::{FromRawHandle, IntoRawHandle};
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Console::{GetStdHandle, SetStdHandle};
+
+    let (reader, writer) = io::pipe()?;
+    let original = unsafe { GetStdHandle(handle) }
+        .ok()
+        .filter(|h| !h.is_invalid());
+    // The std handle now owns the write end
+    unsafe { SetStdHandle(handle, HANDLE(writer.into_raw_handle())) }.map_err(io::Error::other)?;
+    forward(
+        reader,
+        original.map(|h| unsafe { File::from_raw_handle(h.0) }),
+        level,
+    );
+    Ok(())
+}
+
+fn capture() -> std::io::Result<()> {
+    #[cfg(target_os = "macos")]
+    {
+        redirect(1, Level::Info)?;
+        redirect(2, Level::Error)?;
+    }
+    #[cfg(windows)]
+    {
+        use windows::Win32::System::Console::{STD_ERROR_HANDLE, STD_OUTPUT_HANDLE};
+        redirect(STD_OUTPUT_HANDLE, Level::Info)?;
+        redirect(STD_ERROR_HANDLE, Level::Error)?;
+    }
+    Ok(())
+}
+
+/// Start forwarding; call first thing so startup output is captured too
+pub fn init() {
+    if !SUPPORTED || !config::load().os_logging.unwrap_or(true) {
+        return;
+    }
+    ENABLED.store(true, Ordering::SeqCst);
+    if let Err(e) = capture() {
+        eprintln!(
+            "[Claude PM] Failed to forward output to the system log: {}",
+            e
+        );
+    }
+}
+
+/// Forward one line of server output
+pub fn server_line(line: &str) {
+    if ENABLED.load(Ordering::SeqCst) {
+        write(Category::Server, Level::Info, line);
+    }
+}
@@ -0,0 +1,632 @@
+//! Branch-per-task workflow: a branch and git worktree for a task, ready for an agent
+//!
+//! `start_task_branch` names a branch after the task (`task/<title>-<id>`), checks it out
+//! in a worktree under `worktrees/` in the data directory, and adds a section describing
+//! the task to the worktree's CLAUDE.md, marked so it can be replaced when the task
+//! changes. The section is kept out of commits: a tracked CLAUDE.md is flagged
+//! skip-worktree in the worktree's index, an untracked one is excluded. Optionally it then
+//! queues an agent run in the worktree (see `orchestrator`). Calling it again for the same
+//! task reuses the branch and worktree.
+//!
+//! In a Jujutsu repo (see `vcs`) the worktree is a jj workspace and the branch a bookmark
+//! on its working-copy commit. jj has no index to hide CLAUDE.md from, so there the task
+//! section is part of the workspace's changes. Mercurial repos get no worktrees.
+//!
+//! `create_pr` pushes the branch and opens a pull request (see `github`) whose body is the
+//! task description, the agent's last message from the worktree's newest transcript, and
+//! the branch's commits; the PR is linked to the task.
+
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::github::{self, GithubItem};
+use crate::orchestrator::{self, AgentRun};
+use crate::process::{self, CancelToken};
+use crate::store::{self, Task};
+use crate::transcript_tail::{self, EntryKind};
+use crate::vcs::{self, Vcs};
+use crate::{config, search};
+
+const GIT_TIMEOUT: Duration = Duration::from_secs(120);
+const WORKTREE_DIR: &str = "worktrees";
+const BRANCH_PREFIX: &str = "task/";
+const MAX_SLUG: usize = 40;
+const CONTEXT_START: &str = "<!-- claudepm:task -->";
+const CONTEXT_END: &str = "<!-- /claudepm:task -->";
+const DEFAULT_REMOTE: &str = "origin";
+/// Commits listed in a generated PR body
+const MAX_PR_COMMITS: usize = 30;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskBranch {
+    pub task_id: String,
+    pub project_id: String,
+    pub branch: String,
+    pub worktree: String,
+    /// What the branch was created from
+    pub base: String,
+    pub created_at: i64,
+}
+
+impl TaskBranch {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            task_id: row.get("task_id")?,
+            project_id: row.get("project_id")?,
+            branch: row.get("branch")?,
+            worktree: row.get("worktree")?,
+            base: row.get("base")?,
+            created_at: row.get("created_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StartBranchOptions {
+    /// Branch or commit to start from; the repository's current `HEAD` when unset
+    pub base: Option<String>,
+    /// Queue an agent run in the worktree
+    pub start_agent: bool,
+    pub prompt: Option<String>,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartedBranch {
+    #[serde(flatten)]
+    pub branch: TaskBranch,
+    pub run: Option<AgentRun>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PrOptions {
+    /// `owner/name`; parsed from the remote's URL when unset
+    pub repo: Option<String>,
+    /// Branch to merge into; the branch the task started from, else the repo's default
+    pub base: Option<String>,
+    /// The task title when unset
+    pub title: Option<String>,
+    pub remote: Option<String>,
+    pub draft: bool,
+}
+
+fn jj(dir: &Path, args: &[&str]) -> Result<String, String> {
+    vcs::run("jj", dir, args)
+}
+
+/// The repository's VCS, refusing the ones worktrees aren't supported for
+fn worktree_vcs(repo: &Path) -> Result<Vcs, Error> {
+    match vcs::detect(repo).map(|r| r.kind) {
+        Some(Vcs::Mercurial) => Err(Error::Unsupported(format!(
+            "Task worktrees need a git or Jujutsu repository; {} is a Mercurial repository",
+            repo.display()
+        ))),
+        Some(Vcs::Jujutsu) => Ok(Vcs::Jujutsu),
+        _ => Ok(Vcs::Git),
+    }
+}
+
+/// The jj workspace name for a worktree: its directory name, as given to `workspace add`
+fn workspace_name(worktree: &Path) -> String {
+    worktree
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = process::run(
+        Command::new("git").args(args).current_dir(dir),
+        GIT_TIMEOUT,
+        &CancelToken::default(),
+    )
+    .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Lowercase words joined by dashes, safe in a ref name
+pub fn slug(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= MAX_SLUG {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "task".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+fn branch_name(task: &Task) -> String {
+    let short: String = task
+        .id
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .take(8)
+        .collect();
+    format!("{}{}-{}", BRANCH_PREFIX, slug(&task.title), short)
+}
+
+/// The branch recorded for a task
+pub fn get(task_id: &str) -> Result<Option<TaskBranch>, String> {
+    store::with_conn(|conn| {
+        conn.query_row(
+            "SELECT * FROM task_branches WHERE task_id = ?1",
+            [task_id],
+            TaskBranch::from_row,
+        )
+        .optional()
+    })
+}
+
+fn context(task: &Task, branch: &str) -> String {
+    let mut section = format!(
+        "{}\n## Current task\n\nYou are working on **{}** on branch `{}`, in a worktree of its own.\n",
+        CONTEXT_START, task.title, branch
+    );
+    if let Some(description) = task.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        section.push_str(&format!("\n{}\n", description.trim()));
+    }
+    section.push_str(&format!("\nTask id: `{}`\n{}\n", task.id, CONTEXT_END));
+    section
+}
+
+/// Put the task section into the worktree's CLAUDE.md, replacing an earlier one
+fn write_context(worktree: &Path, task: &Task, branch: &str) -> Result<(), String> {
+    let path = worktree.join("CLAUDE.md");
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    let kept = match (existing.find(CONTEXT_START), existing.find(CONTEXT_END)) {
+        (Some(start), Some(end)) if end > start => format!(
+            "{}{}",
+            &existing[..start],
+            existing[end + CONTEXT_END.len()..].trim_start_matches('\n')
+        ),
+        _ => existing,
+    };
+    let separator = if kept.is_empty() || kept.ends_with("\n\n") {
+        ""
+    } else if kept.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    fs::write(
+        &path,
+        format!("{}{}{}", kept, separator, context(task, branch)),
+    )
+    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    if vcs::detect(worktree).is_some_and(|repo| repo.kind == Vcs::Jujutsu) {
+        return Ok(());
+    }
+
+    // Keep the section out of the task's commits
+    if git(worktree, &["ls-files", "--error-unmatch", "CLAUDE.md"]).is_ok() {
+        git(worktree, &["update-index", "--skip-worktree", "CLAUDE.md"])?;
+    } else {
+        let exclude = PathBuf::from(git(worktree, &["rev-parse", "--git-path", "info/exclude"])?);
+        let exclude = if exclude.is_absolute() {
+            exclude
+        } else {
+            worktree.join(exclude)
+        };
+        let current = fs::read_to_string(&exclude).unwrap_or_default();
+        if !current.lines().any(|line| line == "/CLAUDE.md") {
+            if let Some(parent) = exclude.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let separator = if current.is_empty() || current.ends_with('\n') {
+                ""
+            } else {
+                "\n"
+            };
+            fs::write(&exclude, format!("{}{}/CLAUDE.md\n", current, separator))
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+fn start(task_id: &str, options: StartBranchOptions) -> Result<StartedBranch, Error> {
+    let (task, project) = store::with_conn(|conn| {
+        let task = store::get_task(conn, task_id)?;
+        let project = match &task {
+            Some(task) => store::get_project(conn, &task.project_id)?,
+            None => None,
+        };
+        Ok((task, project))
+    })?;
+    let task = task.ok_or_else(|| Error::NotFound(format!("Task not found: {}", task_id)))?;
+    let repo = project
+        .and_then(|p| p.repo_path)
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            Error::InvalidInput("The task's project has no repository path".to_string())
+        })?;
+    let kind = worktree_vcs(&repo)?;
+
+    let recorded = get(task_id)?.filter(|b| Path::new(&b.worktree).exists());
+    let branch = match recorded {
+        Some(branch) => branch,
+        None => {
+            let name = branch_name(&task);
+            let worktree = config::data_dir()
+                .ok_or("Could not determine data directory")?
+                .join(WORKTREE_DIR)
+                .join(&task.project_id)
+                .join(slug(&name));
+            let worktree_arg = worktree.display().to_string();
+            let base = match kind {
+                Vcs::Jujutsu => add_workspace(&repo, &worktree, &name, options.base.as_deref())?,
+                _ => add_worktree(&repo, &worktree_arg, &name, options.base.as_deref())?,
+            };
+            let branch = TaskBranch {
+                task_id: task.id.clone(),
+                project_id: task.project_id.clone(),
+                branch: name,
+                worktree: worktree_arg,
+                base,
+                created_at: store::now_ms(),
+            };
+            store::with_conn(|conn| {
+                conn.execute(
+                    "INSERT OR REPLACE INTO task_branches (task_id, project_id, branch, worktree, base, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        branch.task_id,
+                        branch.project_id,
+                        branch.branch,
+                        branch.worktree,
+                        branch.base,
+                        branch.created_at
+                    ],
+                )
+            })?;
+            println!(
+                "[Claude PM] Created branch {} in {}",
+                branch.branch, branch.worktree
+            );
+            branch
+        }
+    };
+    write_context(Path::new(&branch.worktree), &task, &branch.branch)?;
+
+    let run = if options.start_agent {
+        Some(orchestrator::enqueue(
+            task.id,
+            options.prompt,
+            options.model,
+            Some(branch.worktree.clone()),
+        )?)
+    } else {
+        None
+    };
+    Ok(StartedBranch { branch, run })
+}
+
+/// Check out `name` in a new git worktree, creating the branch from `base` (the current
+/// branch, or commit when detached, if unset) unless it exists; returns the base
+fn add_worktree(
+    repo: &Path,
+    worktree: &str,
+    name: &str,
+    base: Option<&str>,
+) -> Result<String, Error> {
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => match git(repo, &["rev-parse", "--abbrev-ref", "HEAD"])? {
+            head if head == "HEAD" => git(repo, &["rev-parse", "HEAD"])?,
+            branch => branch,
+        },
+    };
+    let exists = git(
+        repo,
+        &[
+            "rev-parse",
+            "--verify",
+            "-q",
+            &format!("refs/heads/{}", name),
+        ],
+    )
+    .is_ok();
+    // Drop a registration whose directory is gone before adding it back
+    let _ = git(repo, &["worktree", "prune"]);
+    if exists {
+        git(repo, &["worktree", "add", worktree, name])?;
+    } else {
+        git(repo, &["worktree", "add", "-b", name, worktree, &base])?;
+    }
+    Ok(base)
+}
+
+/// Add a jj workspace on top of the bookmark `name` if it exists, else `base` (the parent
+/// of the working-copy commit, what git would call `HEAD`, if unset), and point the
+/// bookmark at the workspace's working-copy commit; returns the base's change id
+fn add_workspace(
+    repo: &Path,
+    worktree: &Path,
+    name: &str,
+    base: Option<&str>,
+) -> Result<String, Error> {
+    let exists = jj(repo, &["bookmark", "list", "-T", "name ++ \"\\n\"", name])
+        .is_ok_and(|out| out.lines().any(|line| line == name));
+    let revision = match (exists, base) {
+        (true, _) => name,
+        (false, Some(base)) => base,
+        (false, None) => "@-",
+    };
+    let base = jj(
+        repo,
+        &[
+            "log",
+            "--no-graph",
+            "-r",
+            revision,
+            "-T",
+            "change_id.short()",
+        ],
+    )?;
+    // Drop a workspace whose directory is gone before adding it back
+    let workspace = workspace_name(worktree);
+    let _ = jj(repo, &["workspace", "forget", &workspace]);
+    if let Some(parent) = worktree.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    jj(
+        repo,
+        &[
+            "workspace",
+            "add",
+            "--name",
+            &workspace,
+            "-r",
+            revision,
+            &worktree.display().to_string(),
+        ],
+    )?;
+    jj(
+        worktree,
+        &["bookmark", "set", name, "-r", "@", "--allow-backwards"],
+    )?;
+    Ok(base)
+}
+
+/// Create (or reuse) the task's branch and worktree, and optionally queue an agent in it
+#[tauri::command]
+pub async fn start_task_branch(
+    task_id: String,
+    options: Option<StartBranchOptions>,
+) -> Result<StartedBranch, Error> {
+    tauri::async_runtime::spawn_blocking(move || start(&task_id, options.unwrap_or_default()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn get_task_branch(task_id: String) -> Result<Option<TaskBranch>, Error> {
+    get(&task_id).map_err(Error::from)
+}
+
+/// Remove the task's worktree; the branch is kept unless `delete_branch`
+#[tauri::command]
+pub fn remove_task_branch(task_id: String, delete_branch: bool) -> Result<(), Error> {
+    let branch =
+        get(&task_id)?.ok_or_else(|| Error::NotFound(format!("Task {} has no branch", task_id)))?;
+    let repo = store::with_conn(|conn| store::get_project(conn, &branch.project_id))?
+        .and_then(|p| p.repo_path)
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            Error::InvalidInput("The task's project has no repository path".to_string())
+        })?;
+    match worktree_vcs(&repo)? {
+        Vcs::Jujutsu => {
+            let worktree = Path::new(&branch.worktree);
+            let _ = jj(&repo, &["workspace", "forget", &workspace_name(worktree)]);
+            if worktree.exists() {
+                fs::remove_dir_all(worktree)
+                    .map_err(|e| format!("Failed to remove {}: {}", worktree.display(), e))?;
+            }
+            if delete_branch {
+                jj(&repo, &["bookmark", "delete", &branch.branch])?;
+            }
+        }
+        _ => {
+            if Path::new(&branch.worktree).exists() {
+                git(&repo, &["worktree", "remove", "--force", &branch.worktree])?;
+            } else {
+                let _ = git(&repo, &["worktree", "prune"]);
+            }
+            if delete_branch {
+                git(&repo, &["branch", "-D", &branch.branch])?;
+            }
+        }
+    }
+    store::with_conn(|conn| {
+        conn.execute("DELETE FROM task_branches WHERE task_id = ?1", [&task_id])
+    })?;
+    println!("[Claude PM] Removed worktree for branch {}", branch.branch);
+    Ok(())
+}
+
+/// `owner/name` from a GitHub remote URL (`git@github.com:o/n.git`, `https://github.com/o/n`)
+fn github_repo(url: &str) -> Option<String> {
+    let path = url
+        .strip_prefix("git@github.com:")
+        .or_else(|| url.split_once("github.com/").map(|(_, path)| path))?;
+    let path = path.trim_end_matches('/').trim_end_matches(".git");
+    path.split_once('/')
+        .filter(|(owner, name)| !owner.is_empty() && !name.is_empty() && !name.contains('/'))
+        .map(|_| path.to_string())
+}
+
+/// The agent's last message in the newest transcript of a session run in `cwd`
+fn agent_summary(cwd: &str) -> Option<String> {
+    let dir = search::transcripts_dir()?.join(cwd.replace(['/', '.'], "-"));
+    let newest = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "jsonl"))
+        .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())?;
+    let contents = fs::read_to_string(newest.path()).ok()?;
+    contents
+        .lines()
+        .rev()
+        .flat_map(|line| transcript_tail::parse_line(line).into_iter().rev())
+        .find_map(|entry| match entry.kind {
+            EntryKind::Message { role, text } if role == "assistant" && !text.trim().is_empty() => {
+                Some(text.trim().to_string())
+            }
+            _ => None,
+        })
+}
+
+fn pr_body(task: &Task, summary: Option<&str>, commits: &[String]) -> String {
+    let mut body = String::new();
+    if let Some(description) = task.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        body.push_str(description.trim());
+        body.push_str("\n\n");
+    }
+    if let Some(summary) = summary {
+        body.push_str("## Agent summary\n\n");
+        body.push_str(summary);
+        body.push_str("\n\n");
+    }
+    if !commits.is_empty() {
+        body.push_str("## Commits\n\n");
+        for commit in commits.iter().take(MAX_PR_COMMITS) {
+            body.push_str(&format!("- {}\n", commit));
+        }
+        if commits.len() > MAX_PR_COMMITS {
+            body.push_str(&format!("- …and {} more\n", commits.len() - MAX_PR_COMMITS));
+        }
+        body.push('\n');
+    }
+    body.push_str(&format!("Task: {} (`{}`)\n", task.title, task.id));
+    body
+}
+
+/// What the PR needs from git: the task, repo, base, commits and the agent summary
+struct PushedBranch {
+    task: Task,
+    branch: TaskBranch,
+    repo: String,
+    base: Option<String>,
+    commits: Vec<String>,
+    summary: Option<String>,
+}
+
+fn push(task_id: &str, options: &PrOptions) -> Result<PushedBranch, Error> {
+    let task = store::with_conn(|conn| store::get_task(conn, task_id))?
+        .ok_or_else(|| Error::NotFound(format!("Task not found: {}", task_id)))?;
+    let branch = get(task_id)?.ok_or_else(|| {
+        Error::InvalidInput(format!("Task {} has no branch; start one first", task_id))
+    })?;
+    let dir = Path::new(&branch.worktree);
+    if !dir.exists() {
+        return Err(Error::NotFound(format!(
+            "The worktree {} no longer exists",
+            branch.worktree
+        )));
+    }
+    vcs::require_git(dir, "Pull requests")?;
+    let remote = options.remote.as_deref().unwrap_or(DEFAULT_REMOTE);
+    let repo = match &options.repo {
+        Some(repo) => repo.clone(),
+        None => github_repo(&git(dir, &["remote", "get-url", remote])?).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "The {} remote isn't on GitHub; pass the repository as owner/name",
+                remote
+            ))
+        })?,
+    };
+    // The branch the task started from, if it was one and the remote has it
+    let base = options.base.clone().or_else(|| {
+        Some(branch.base.clone()).filter(|base| {
+            git(
+                dir,
+                &[
+                    "rev-parse",
+                    "--verify",
+                    "-q",
+                    &format!("refs/remotes/{}/{}", remote, base),
+                ],
+            )
+            .is_ok()
+        })
+    });
+    let range = match &base {
+        Some(base) => format!("{}/{}..HEAD", remote, base),
+        None => format!("{}..HEAD", branch.base),
+    };
+    let commits: Vec<String> = git(dir, &["log", "--no-merges", "--format=%h %s", &range])
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect();
+    if commits.is_empty() {
+        return Err(Error::InvalidInput(format!(
+            "{} has no commits to open a pull request for",
+            branch.branch
+        )));
+    }
+    git(dir, &["push", "-u", remote, &branch.branch])?;
+    println!("[Claude PM] Pushed {} to {}", branch.branch, remote);
+    let summary = agent_summary(&branch.worktree);
+    Ok(PushedBranch {
+        task,
+        branch,
+        repo,
+        base,
+        commits,
+        summary,
+    })
+}
+
+/// Push the task's branch and open a pull request for it, linked to the task
+#[tauri::command]
+pub async fn create_pr(task_id: String, options: Option<PrOptions>) -> Result<GithubItem, Error> {
+    let options = options.unwrap_or_default();
+    let pushed = {
+        let options = options.clone();
+        tauri::async_runtime::spawn_blocking(move || push(&task_id, &options))
+            .await
+            .map_err(|e| e.to_string())??
+    };
+    let title = options.title.unwrap_or_else(|| pushed.task.title.clone());
+    let body = pr_body(&pushed.task, pushed.summary.as_deref(), &pushed.commits);
+    let pr = github::create_pull_request(
+        &pushed.task.id,
+        &pushed.repo,
+        &title,
+        &pushed.branch.branch,
+        pushed.base.as_deref(),
+        &body,
+        options.draft,
+    )
+    .await?;
+    println!("[Claude PM] Opened {}", pr.url);
+    Ok(pr)
+}
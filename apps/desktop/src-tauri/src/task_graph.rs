@@ -0,0 +1,298 @@
+//! Dependencies between tasks: which tasks are ready, and telling when one becomes ready
+//!
+//! A task is ready once every task it depends on is done (and it isn't done itself).
+//! Adding a dependency that would close a cycle is refused; cycles that still turn up
+//! (say, from an older sync) are reported by [`get_task_graph`], and tasks on them are
+//! never ready.
+//!
+//! When a task is completed, each dependent it was the last blocker of is announced as
+//! [`AppEvent::TaskUnblocked`] on the event bus (`task-unblocked` for the frontend). The
+//! orchestrator queues an agent for it when `start_unblocked_agents` is on; otherwise a
+//! notification offers to.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use tauri::AppHandle;
+
+use crate::error::Error;
+use crate::event_bus::{self, AppEvent, Filter, Topic};
+use crate::notifications::{self, NotificationAction, NotificationRequest};
+use crate::store::{self, Task, TaskDependency};
+use crate::{config, orchestrator};
+
+const UNBLOCKED_KEY_PREFIX: &str = "unblocked:";
+const START_AGENT_ACTION: &str = "start-agent";
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNode {
+    pub task: Task,
+    pub depends_on: Vec<String>,
+    /// Dependencies not done yet
+    pub blocked_by: Vec<String>,
+    pub dependents: Vec<String>,
+    pub ready: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<TaskDependency>,
+    /// Task ids around each cycle, in dependency order
+    pub cycles: Vec<Vec<String>>,
+}
+
+/// Task id to the ids it depends on
+fn adjacency(edges: &[TaskDependency]) -> BTreeMap<&str, Vec<&str>> {
+    let mut graph: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for edge in edges {
+        graph
+            .entry(edge.task_id.as_str())
+            .or_default()
+            .push(edge.depends_on.as_str());
+    }
+    graph
+}
+
+/// Whether `to` can be reached from `from` following dependencies
+fn reaches(graph: &BTreeMap<&str, Vec<&str>>, from: &str, to: &str) -> bool {
+    let mut seen = BTreeSet::new();
+    let mut stack = vec![from];
+    while let Some(id) = stack.pop() {
+        if id == to {
+            return true;
+        }
+        if seen.insert(id) {
+            stack.extend(graph.get(id).into_iter().flatten().copied());
+        }
+    }
+    false
+}
+
+/// Every cycle found by a depth-first walk, each listed once
+fn cycles(graph: &BTreeMap<&str, Vec<&str>>) -> Vec<Vec<String>> {
+    fn visit<'a>(
+        id: &'a str,
+        graph: &BTreeMap<&'a str, Vec<&'a str>>,
+        path: &mut Vec<&'a str>,
+        done: &mut BTreeSet<&'a str>,
+        found: &mut Vec<Vec<String>>,
+    ) {
+        if let Some(start) = path.iter().position(|p| *p == id) {
+            found.push(path[start..].iter().map(|p| p.to_string()).collect());
+            return;
+        }
+        if done.contains(id) {
+            return;
+        }
+        path.push(id);
+        for next in graph.get(id).into_iter().flatten() {
+            visit(next, graph, path, done, found);
+        }
+        path.pop();
+        done.insert(id);
+    }
+    let mut found = Vec::new();
+    let mut done = BTreeSet::new();
+    for id in graph.keys() {
+        visit(id, graph, &mut Vec::new(), &mut done, &mut found);
+    }
+    found
+}
+
+fn build(project_id: Option<&str>) -> Result<TaskGraph, Error> {
+    let tasks = store::list_tasks(project_id.map(str::to_string), None)?;
+    let edges = store::with_conn(|conn| store::list_task_dependencies(conn, project_id))?;
+    let graph = adjacency(&edges);
+    let cycles = cycles(&graph);
+    let on_cycle: BTreeSet<&str> = cycles.iter().flatten().map(String::as_str).collect();
+    let states: BTreeMap<&str, &str> = tasks
+        .iter()
+        .map(|t| (t.id.as_str(), t.state.as_str()))
+        .collect();
+    let nodes = tasks
+        .iter()
+        .map(|task| {
+            let depends_on: Vec<String> = graph
+                .get(task.id.as_str())
+                .into_iter()
+                .flatten()
+                .map(|id| id.to_string())
+                .collect();
+            // A dependency in another project counts as blocking until it's done there
+            let blocked_by: Vec<String> = depends_on
+                .iter()
+                .filter(|id| states.get(id.as_str()).copied() != Some("done"))
+                .cloned()
+                .collect();
+            let dependents = edges
+                .iter()
+                .filter(|e| e.depends_on == task.id)
+                .map(|e| e.task_id.clone())
+                .collect();
+            GraphNode {
+                ready: task.state != "done"
+                    && blocked_by.is_empty()
+                    && !on_cycle.contains(task.id.as_str()),
+                task: task.clone(),
+                depends_on,
+                blocked_by,
+                dependents,
+            }
+        })
+        .collect();
+    Ok(TaskGraph {
+        nodes,
+        edges,
+        cycles,
+    })
+}
+
+/// Tasks `completed` was the last unfinished dependency of
+fn newly_unblocked(completed: &Task) -> Result<Vec<Task>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT t.* FROM task_dependencies d JOIN tasks t ON t.id = d.task_id
+             WHERE d.depends_on = ?1 AND t.state != 'done'
+               AND NOT EXISTS (
+                 SELECT 1 FROM task_dependencies o JOIN tasks b ON b.id = o.depends_on
+                 WHERE o.task_id = t.id AND b.state != 'done')",
+        )?;
+        let rows = stmt.query_map([&completed.id], Task::from_row)?;
+        rows.collect()
+    })
+}
+
+fn announce(task: Task, unblocked_by: Task) {
+    println!(
+        "[Claude PM] Task {} is ready ({} is done)",
+        task.id, unblocked_by.id
+    );
+    event_bus::publish(AppEvent::TaskUnblocked { task, unblocked_by });
+}
+
+fn offer_agent(app: &AppHandle, task: &Task, unblocked_by: &Task) {
+    notifications::notify(
+        app,
+        NotificationRequest {
+            title: format!("{} is now ready", task.title),
+            body: format!("{} is done. Start the agent?", unblocked_by.title),
+            key: Some(format!("{}{}", UNBLOCKED_KEY_PREFIX, task.id)),
+            actions: vec![NotificationAction {
+                id: START_AGENT_ACTION.to_string(),
+                label: "Start agent".to_string(),
+            }],
+            target: Some(format!("/projects/{}", task.project_id)),
+            category: Some("ready".to_string()),
+        },
+    );
+}
+
+/// Start the agent for a task offered by a "now ready" notification
+pub fn handle_notification_action(key: Option<&str>, action: &str) {
+    let Some(task_id) = key.and_then(|k| k.strip_prefix(UNBLOCKED_KEY_PREFIX)) else {
+        return;
+    };
+    if action != START_AGENT_ACTION {
+        return;
+    }
+    if let Err(e) = orchestrator::enqueue(task_id.to_string(), None, None, None) {
+        eprintln!("[Claude PM] Failed to start agent for {}: {}", task_id, e);
+    }
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    event_bus::subscribe(Filter::topics(&[Topic::TaskCompleted]), |envelope| {
+        let AppEvent::TaskCompleted { task } = &envelope.event else {
+            return;
+        };
+        // Publishing from inside a handler would run the other handlers re-entrantly
+        let completed = task.clone();
+        thread::spawn(move || match newly_unblocked(&completed) {
+            Ok(tasks) => {
+                for task in tasks {
+                    announce(task, completed.clone());
+                }
+            }
+            Err(e) => eprintln!("[Claude PM] Failed to check dependents: {}", e),
+        });
+    });
+    event_bus::subscribe(Filter::topics(&[Topic::TaskUnblocked]), move |envelope| {
+        let AppEvent::TaskUnblocked { task, unblocked_by } = &envelope.event else {
+            return;
+        };
+        // The orchestrator takes it from here when it starts agents by itself
+        if !config::load().start_unblocked_agents {
+            offer_agent(&app, task, unblocked_by);
+        }
+    });
+}
+
+/// Make `task_id` wait for `depends_on`; refused if `depends_on` already waits on it
+#[tauri::command]
+pub fn add_task_dependency(task_id: String, depends_on: String) -> Result<TaskGraph, Error> {
+    if task_id == depends_on {
+        return Err(Error::InvalidInput(
+            "A task can't depend on itself".to_string(),
+        ));
+    }
+    let (task, dependency) = store::with_conn(|conn| {
+        Ok((
+            store::get_task(conn, &task_id)?,
+            store::get_task(conn, &depends_on)?,
+        ))
+    })?;
+    let task = task.ok_or_else(|| Error::NotFound(format!("Task not found: {}", task_id)))?;
+    dependency.ok_or_else(|| Error::NotFound(format!("Task not found: {}", depends_on)))?;
+    let edges = store::with_conn(|conn| store::list_task_dependencies(conn, None))?;
+    if reaches(&adjacency(&edges), &depends_on, &task_id) {
+        return Err(Error::InvalidInput(
+            "That dependency would create a cycle".to_string(),
+        ));
+    }
+    store::with_conn(|conn| store::insert_task_dependency(conn, &task_id, &depends_on))?;
+    build(Some(&task.project_id))
+}
+
+/// Stop `task_id` waiting for `depends_on`; announces the task if that was its last blocker
+#[tauri::command]
+pub fn remove_task_dependency(task_id: String, depends_on: String) -> Result<TaskGraph, Error> {
+    let task = store::with_conn(|conn| store::get_task(conn, &task_id))?
+        .ok_or_else(|| Error::NotFound(format!("Task not found: {}", task_id)))?;
+    let dependency = store::with_conn(|conn| {
+        let dependency = store::get_task(conn, &depends_on)?;
+        store::delete_task_dependency(conn, &task_id, &depends_on)?;
+        Ok(dependency)
+    })?;
+    let graph = build(Some(&task.project_id))?;
+    let ready = graph.nodes.iter().any(|n| n.task.id == task_id && n.ready);
+    if let Some(dependency) = dependency.filter(|d| ready && d.state != "done") {
+        announce(task, dependency);
+    }
+    Ok(graph)
+}
+
+/// Tasks with their dependencies and readiness, for one project or all of them
+#[tauri::command]
+pub fn get_task_graph(project_id: Option<String>) -> Result<TaskGraph, Error> {
+    build(project_id.as_deref())
+}
+
+/// Tasks that aren't done and aren't waiting on anything
+#[tauri::command]
+pub fn list_ready_tasks(project_id: Option<String>) -> Result<Vec<Task>, Error> {
+    Ok(build(project_id.as_deref())?
+        .nodes
+        .into_iter()
+        .filter(|node| node.ready)
+        .map(|node| node.task)
+        .collect())
+}
@@ -0,0 +1,201 @@
+//! Opening terminal windows at a directory, optionally running a command
+
+use serde::Serialize;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use url::form_urlencoded;
+
+use crate::applescript;
+use crate::config;
+use crate::error::Error;
+use crate::process::{first_existing, shell_quote, which};
+
+struct TerminalSpec {
+    id: &'static str,
+    name: &'static str,
+    /// macOS app bundle used for detection
+    bundle: Option<&'static str>,
+    /// CLI used for detection and launching on non-macOS platforms
+    cli: Option<&'static str>,
+}
+
+/// Supported terminals, in auto-detection priority order
+const TERMINALS: &[TerminalSpec] = &[
+    TerminalSpec {
+        id: "terminal",
+        name: "Terminal",
+        bundle: Some("/System/Applications/Utilities/Terminal.app"),
+        cli: None,
+    },
+    TerminalSpec {
+        id: "iterm2",
+        name: "iTerm2",
+        bundle: Some("/Applications/iTerm.app"),
+        cli: None,
+    },
+    TerminalSpec {
+        id: "warp",
+        name: "Warp",
+        bundle: Some("/Applications/Warp.app"),
+        cli: None,
+    },
+    TerminalSpec {
+        id: "kitty",
+        name: "kitty",
+        bundle: Some("/Applications/kitty.app"),
+        cli: Some("kitty"),
+    },
+    TerminalSpec {
+        id: "wezterm",
+        name: "WezTerm",
+        bundle: Some("/Applications/WezTerm.app"),
+        cli: Some("wezterm"),
+    },
+];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedTerminal {
+    pub id: String,
+    pub name: String,
+}
+
+fn is_installed(spec: &TerminalSpec) -> bool {
+    spec.bundle.is_some_and(|bundle| Path::new(bundle).exists())
+        || spec.cli.is_some_and(|cli| which(cli).is_some())
+}
+
+/// Locate the CLI binary for kitty/WezTerm, including inside their app bundles
+fn cli_path(spec: &TerminalSpec) -> Option<PathBuf> {
+    let cli = spec.cli?;
+    which(cli).or_else(|| {
+        let bundle = PathBuf::from(spec.bundle?);
+        first_existing(&[bundle.join("Contents/MacOS").join(cli)])
+    })
+}
+
+/// Command line to type into a new shell: cd into the directory, then run the command
+fn shell_line(dir: &Path, command: Option<&str>) -> String {
+    let cd = format!("cd {}", shell_quote(&dir.display().to_string()));
+    match command {
+        Some(command) => format!("{} && {}", cd, command),
+        None => cd,
+    }
+}
+
+fn open_terminal_app(dir: &Path, command: Option<&str>) -> Result<(), Error> {
+    let script = format!(
+        "tell application \"Terminal\"\n  activate\n  do script \"{}\"\nend tell",
+        applescript::escape(&shell_line(dir, command))
+    );
+    applescript::run(&script).map(|_| ())
+}
+
+fn open_iterm(dir: &Path, command: Option<&str>) -> Result<(), Error> {
+    let script = format!(
+        "tell application \"iTerm\"\n  activate\n  set newWindow to (create window with default profile)\n  tell current session of newWindow to write text \"{}\"\nend tell",
+        applescript::escape(&shell_line(dir, command))
+    );
+    applescript::run(&script).map(|_| ())
+}
+
+fn open_warp(dir: &Path, command: Option<&str>) -> Result<(), Error> {
+    if command.is_some() {
+        return Err(Error::Unsupported(
+            "Warp does not support running an initial command; choose another terminal".to_string(),
+        ));
+    }
+    let uri = format!(
+        "warp://action/new_window?path={}",
+        form_urlencoded::byte_serialize(dir.display().to_string().as_bytes()).collect::<String>()
+    );
+    Ok(spawn_detached(Command::new("open").arg(uri))?)
+}
+
+/// kitty and WezTerm take the directory and an optional program on the command line
+fn open_cli_terminal(spec: &TerminalSpec, dir: &Path, command: Option<&str>) -> Result<(), Error> {
+    let cli =
+        cli_path(spec).ok_or_else(|| Error::NotFound(format!("{} CLI not found", spec.name)))?;
+    let mut cmd = Command::new(cli);
+
+    match spec.id {
+        "kitty" => {
+            cmd.arg("--directory").arg(dir);
+        }
+        _ => {
+            cmd.args(["start", "--cwd"]).arg(dir);
+        }
+    }
+
+    if let Some(command) = command {
+        // Keep the shell open after the command finishes so output stays visible
+        let shell = env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+        if spec.id == "wezterm" {
+            cmd.arg("--");
+        }
+        cmd.arg(&shell)
+            .arg("-lc")
+            .arg(format!("{}; exec {}", command, shell));
+    }
+
+    Ok(spawn_detached(&mut cmd)?)
+}
+
+fn spawn_detached(cmd: &mut Command) -> Result<(), String> {
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to launch terminal: {}", e))?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_terminals() -> Vec<DetectedTerminal> {
+    TERMINALS
+        .iter()
+        .filter(|spec| is_installed(spec))
+        .map(|spec| DetectedTerminal {
+            id: spec.id.to_string(),
+            name: spec.name.to_string(),
+        })
+        .collect()
+}
+
+/// Set the preferred terminal; `None` goes back to auto-detection
+#[tauri::command]
+pub fn set_default_terminal(terminal: Option<String>) -> Result<(), Error> {
+    if let Some(ref id) = terminal {
+        if !TERMINALS.iter().any(|spec| spec.id == id) {
+            return Err(Error::InvalidInput(format!("Unknown terminal: {}", id)));
+        }
+    }
+    config::update(|c| c.terminal = terminal)
+        .map(|_| ())
+        .map_err(Error::from)
+}
+
+/// Open a new terminal window at `path`, optionally running `command` (e.g. `tmux attach -t task-42`)
+#[tauri::command]
+pub fn open_terminal_at(path: String, command: Option<String>) -> Result<(), Error> {
+    let dir = PathBuf::from(&path);
+    if !dir.is_dir() {
+        return Err(Error::NotFound(format!("Directory not found: {}", path)));
+    }
+
+    let preferred = config::load().terminal;
+    let spec = preferred
+        .and_then(|id| TERMINALS.iter().find(|spec| spec.id == id))
+        .or_else(|| TERMINALS.iter().find(|spec| is_installed(spec)))
+        .ok_or_else(|| Error::NotFound("No supported terminal found".to_string()))?;
+
+    let command = command.as_deref().filter(|c| !c.trim().is_empty());
+    match spec.id {
+        "terminal" => open_terminal_app(&dir, command),
+        "iterm2" => open_iterm(&dir, command),
+        "warp" => open_warp(&dir, command),
+        _ => open_cli_terminal(spec, &dir, command),
+    }
+}
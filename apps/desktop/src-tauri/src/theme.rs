@@ -0,0 +1,198 @@
+//! System light/dark mode and accent color
+//!
+//! Light/dark comes from the main window (the webview reports the OS appearance and a
+//! change arrives as a window event). The accent color has no change notification on any
+//! platform, so it is read from the OS settings on a timer: `AppleAccentColor` on macOS,
+//! the DWM accent on Windows and GNOME's `accent-color` on Linux. The tray icon follows
+//! the mode.
+//!
+//! Events:
+//! - `theme-changed` with the new `SystemTheme`
+
+use serde::Serialize;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Theme, WindowEvent};
+
+use crate::windows::main_window;
+use crate::{menubar, process};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+static LAST: Mutex<Option<SystemTheme>> = Mutex::new(None);
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemTheme {
+    pub mode: Mode,
+    /// `#rrggbb`; unset when the platform has no accent setting
+    pub accent_color: Option<String>,
+}
+
+fn mode_of(theme: Theme) -> Mode {
+    match theme {
+        Theme::Dark => Mode::Dark,
+        _ => Mode::Light,
+    }
+}
+
+fn current_mode(app: &AppHandle) -> Mode {
+    main_window(app)
+        .ok()
+        .and_then(|window| window.theme().ok())
+        .map(mode_of)
+        .unwrap_or(Mode::Light)
+}
+
+fn command_stdout(program: &str, args: &[&str]) -> Option<String> {
+    let output = process::output(Command::new(program).args(args)).ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// macOS stores the accent as an index; no key means the default (multicolor) blue
+#[cfg(target_os = "macos")]
+fn accent_color() -> Option<String> {
+    let index = command_stdout("defaults", &["read", "-g", "AppleAccentColor"])
+        .and_then(|value| value.parse::<i32>().ok());
+    let hex = match index {
+        Some(-1) => "#8c8c8c",
+        Some(0) => "#ff5257",
+        Some(1) => "#f7821b",
+        Some(2) => "#ffc600",
+        Some(3) => "#62ba46",
+        Some(5) => "#a550a7",
+        Some(6) => "#f74f9e",
+        _ => "#007aff",
+    };
+    Some(hex.to_string())
+}
+
+/// `AccentColor` is a DWORD in 0xAABBGGRR order
+#[cfg(windows)]
+fn accent_color() -> Option<String> {
+    let output = command_stdout(
+        "reg",
+        &[
+            "query",
+            r"HKCU\Software\Microsoft\Windows\DWM",
+            "/v",
+            "AccentColor",
+        ],
+    )?;
+    let value = output
+        .split_whitespace()
+        .last()
+        .and_then(|v| u32::from_str_radix(v.trim_start_matches("0x"), 16).ok())?;
+    let [r, g, b, _] = value.to_le_bytes();
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+/// GNOME 47+ named accents, as libadwaita renders them
+#[cfg(not(any(target_os = "macos", windows)))]
+fn accent_color() -> Option<String> {
+    let name = command_stdout(
+        "gsettings",
+        &["get", "org.gnome.desktop.interface", "accent-color"],
+    )?;
+    let hex = match name.trim_matches('\'') {
+        "blue" => "#3584e4",
+        "teal" => "#2190a4",
+        "green" => "#3a944a",
+        "yellow" => "#c88800",
+        "orange" => "#ed5b00",
+        "red" => "#e62d42",
+        "pink" => "#d56199",
+        "purple" => "#9141ac",
+        "slate" => "#6f8396",
+        _ => return None,
+    };
+    Some(hex.to_string())
+}
+
+/// Record `theme`; on a change, swap the tray icon and tell the frontend
+fn update(app: &AppHandle, theme: SystemTheme) {
+    let changed = LAST
+        .lock()
+        .map(|mut last| {
+            let changed = last.as_ref() != Some(&theme);
+            *last = Some(theme.clone());
+            changed
+        })
+        .unwrap_or(false);
+    if !changed {
+        return;
+    }
+    menubar::apply_theme(app, theme.mode);
+    let _ = app.emit("theme-changed", &theme);
+}
+
+fn accent() -> Option<String> {
+    LAST.lock()
+        .ok()
+        .and_then(|last| last.as_ref().and_then(|t| t.accent_color.clone()))
+}
+
+/// Follow mode changes from the main window and poll the accent color
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    update(
+        &app,
+        SystemTheme {
+            mode: current_mode(&app),
+            accent_color: accent_color(),
+        },
+    );
+
+    if let Ok(window) = main_window(&app) {
+        let handle = app.clone();
+        window.on_window_event(move |event| {
+            if let WindowEvent::ThemeChanged(theme) = event {
+                update(
+                    &handle,
+                    SystemTheme {
+                        mode: mode_of(*theme),
+                        accent_color: accent(),
+                    },
+                );
+            }
+        });
+    }
+
+    thread::spawn(move || loop {
+        thread::sleep(POLL_INTERVAL);
+        update(
+            &app,
+            SystemTheme {
+                mode: current_mode(&app),
+                accent_color: accent_color(),
+            },
+        );
+    });
+}
+
+#[tauri::command]
+pub fn get_system_theme(app: AppHandle) -> SystemTheme {
+    LAST.lock()
+        .ok()
+        .and_then(|last| last.clone())
+        .unwrap_or_else(|| SystemTheme {
+            mode: current_mode(&app),
+            accent_color: accent_color(),
+        })
+}
@@ -0,0 +1,359 @@
+//! Per-task timers, persisted in the local store
+//!
+//! At most one timer runs at a time; its entry has no `ended_at` until stopped, so a
+//! timer keeps running across frontend reloads, with the window closed and across app
+//! restarts. When the user goes idle the running entry is closed at the last input and
+//! marked `idle`, so the frontend can ask on return whether to keep or drop the gap.
+//!
+//! Events:
+//! - `timer-changed`: the current timer (or `null`) after any start/stop/switch/pause
+
+use chrono::{Duration as ChronoDuration, Local, NaiveDate, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::store;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeEntry {
+    pub id: String,
+    pub task_id: String,
+    /// Unix millis
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    /// `manual`, `switch`, `idle` or `focus`; `None` while running
+    pub stop_reason: Option<String>,
+}
+
+impl TimeEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            task_id: row.get("task_id")?,
+            started_at: row.get("started_at")?,
+            ended_at: row.get("ended_at")?,
+            stop_reason: row.get("stop_reason")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentTimer {
+    pub entry: TimeEntry,
+    pub task_title: String,
+    pub project_id: String,
+    /// True when the timer was paused by idle detection and is waiting for a decision
+    pub paused_for_idle: bool,
+    pub elapsed_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayTotal {
+    /// Local date, `YYYY-MM-DD`
+    pub day: String,
+    pub total_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectTotal {
+    pub project_id: String,
+    pub project_name: String,
+    pub total_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeReport {
+    pub from: i64,
+    pub to: i64,
+    pub total_ms: i64,
+    pub by_day: Vec<DayTotal>,
+    pub by_project: Vec<ProjectTotal>,
+}
+
+fn running(conn: &Connection) -> rusqlite::Result<Option<TimeEntry>> {
+    conn.query_row(
+        "SELECT * FROM time_entries WHERE ended_at IS NULL",
+        [],
+        TimeEntry::from_row,
+    )
+    .optional()
+}
+
+/// Latest entry, if it was closed by idle detection
+fn idle_paused(conn: &Connection) -> rusqlite::Result<Option<TimeEntry>> {
+    conn.query_row(
+        "SELECT * FROM time_entries ORDER BY started_at DESC LIMIT 1",
+        [],
+        TimeEntry::from_row,
+    )
+    .optional()
+    .map(|entry| entry.filter(|e| e.stop_reason.as_deref() == Some("idle")))
+}
+
+fn stop_running(conn: &Connection, at: i64, reason: &str) -> rusqlite::Result<Option<TimeEntry>> {
+    let Some(entry) = running(conn)? else {
+        return Ok(None);
+    };
+    // Never end before the start, e.g. when idle began before a timer was started
+    let ended_at = at.max(entry.started_at);
+    conn.execute(
+        "UPDATE time_entries SET ended_at = ?2, stop_reason = ?3 WHERE id = ?1",
+        params![entry.id, ended_at, reason],
+    )?;
+    Ok(Some(TimeEntry {
+        ended_at: Some(ended_at),
+        stop_reason: Some(reason.to_string()),
+        ..entry
+    }))
+}
+
+fn insert(conn: &Connection, task_id: &str) -> rusqlite::Result<TimeEntry> {
+    let entry = TimeEntry {
+        id: store::new_id(),
+        task_id: task_id.to_string(),
+        started_at: store::now_ms(),
+        ended_at: None,
+        stop_reason: None,
+    };
+    conn.execute(
+        "INSERT INTO time_entries (id, task_id, started_at) VALUES (?1, ?2, ?3)",
+        params![entry.id, entry.task_id, entry.started_at],
+    )?;
+    Ok(entry)
+}
+
+fn current(conn: &Connection) -> rusqlite::Result<Option<CurrentTimer>> {
+    let (entry, paused_for_idle) = match running(conn)? {
+        Some(entry) => (entry, false),
+        None => match idle_paused(conn)? {
+            Some(entry) => (entry, true),
+            None => return Ok(None),
+        },
+    };
+    let Some(task) = store::get_task(conn, &entry.task_id)? else {
+        return Ok(None);
+    };
+    let elapsed_ms = entry.ended_at.unwrap_or_else(store::now_ms) - entry.started_at;
+    Ok(Some(CurrentTimer {
+        entry,
+        task_title: task.title,
+        project_id: task.project_id,
+        paused_for_idle,
+        elapsed_ms,
+    }))
+}
+
+/// Run `f` against the store, then broadcast the resulting timer state
+fn change<T>(
+    app: &AppHandle,
+    f: impl FnOnce(&Connection) -> rusqlite::Result<T>,
+) -> Result<T, Error> {
+    let (result, timer) = store::with_conn(|conn| Ok((f(conn)?, current(conn)?)))?;
+    let _ = app.emit("timer-changed", timer);
+    Ok(result)
+}
+
+fn require_task(task_id: &str) -> Result<(), Error> {
+    store::with_conn(|conn| store::get_task(conn, task_id))?
+        .map(|_| ())
+        .ok_or_else(|| Error::NotFound(format!("Task not found: {}", task_id)))
+}
+
+/// Close the running entry at `since` (the last input) because the user went idle
+pub fn pause_for_idle(app: &AppHandle, since: i64) {
+    match change(app, |conn| stop_running(conn, since, "idle")) {
+        Ok(Some(entry)) => println!("[Claude PM] Paused timer for task {} (idle)", entry.task_id),
+        Ok(None) => {}
+        Err(e) => eprintln!("[Claude PM] Failed to pause timer: {}", e),
+    }
+}
+
+/// Record time already spent on a task, e.g. a finished focus session (see `focus_session`)
+pub fn log(
+    app: &AppHandle,
+    task_id: &str,
+    started_at: i64,
+    ended_at: i64,
+    reason: &str,
+) -> Result<TimeEntry, Error> {
+    let entry = TimeEntry {
+        id: store::new_id(),
+        task_id: task_id.to_string(),
+        started_at,
+        ended_at: Some(ended_at.max(started_at)),
+        stop_reason: Some(reason.to_string()),
+    };
+    change(app, |conn| {
+        conn.execute(
+            "INSERT INTO time_entries (id, task_id, started_at, ended_at, stop_reason) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                entry.id,
+                entry.task_id,
+                entry.started_at,
+                entry.ended_at,
+                entry.stop_reason
+            ],
+        )?;
+        Ok(entry)
+    })
+}
+
+/// Start timing a task; fails if another timer is running (use `switch_timer`)
+#[tauri::command]
+pub fn start_timer(app: AppHandle, task_id: String) -> Result<TimeEntry, Error> {
+    require_task(&task_id)?;
+    if let Some(entry) = store::with_conn(running)? {
+        return Err(Error::InvalidInput(format!(
+            "A timer is already running for task {}",
+            entry.task_id
+        )));
+    }
+    change(&app, |conn| insert(conn, &task_id))
+}
+
+#[tauri::command]
+pub fn stop_timer(app: AppHandle) -> Result<Option<TimeEntry>, Error> {
+    change(&app, |conn| stop_running(conn, store::now_ms(), "manual"))
+}
+
+/// Stop whatever is running and start timing `task_id`
+#[tauri::command]
+pub fn switch_timer(app: AppHandle, task_id: String) -> Result<TimeEntry, Error> {
+    require_task(&task_id)?;
+    change(&app, |conn| {
+        stop_running(conn, store::now_ms(), "switch")?;
+        insert(conn, &task_id)
+    })
+}
+
+/// Answer the return-from-idle prompt: `keep_idle_time` reopens the paused entry so the
+/// gap counts, otherwise a fresh entry starts now
+#[tauri::command]
+pub fn resume_timer(app: AppHandle, keep_idle_time: bool) -> Result<TimeEntry, Error> {
+    let paused = store::with_conn(idle_paused)?
+        .ok_or_else(|| Error::NotFound("No timer is paused for idle".to_string()))?;
+    change(&app, |conn| {
+        if keep_idle_time {
+            conn.execute(
+                "UPDATE time_entries SET ended_at = NULL, stop_reason = NULL WHERE id = ?1",
+                [&paused.id],
+            )?;
+            Ok(TimeEntry {
+                ended_at: None,
+                stop_reason: None,
+                ..paused
+            })
+        } else {
+            // Mark the pause as handled so it isn't offered again
+            conn.execute(
+                "UPDATE time_entries SET stop_reason = 'manual' WHERE id = ?1",
+                [&paused.id],
+            )?;
+            insert(conn, &paused.task_id)
+        }
+    })
+}
+
+#[tauri::command]
+pub fn get_current_timer() -> Result<Option<CurrentTimer>, Error> {
+    store::with_conn(current).map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn list_time_entries(task_id: String) -> Result<Vec<TimeEntry>, Error> {
+    store::with_conn(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT * FROM time_entries WHERE task_id = ?1 ORDER BY started_at DESC")?;
+        let rows = stmt.query_map([&task_id], TimeEntry::from_row)?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
+
+/// Local midnight after `ms`
+fn next_midnight(ms: i64) -> Option<(NaiveDate, i64)> {
+    let day = Local.timestamp_millis_opt(ms).single()?.date_naive();
+    let next = (day + ChronoDuration::days(1)).and_hms_opt(0, 0, 0)?;
+    let next_ms = Local
+        .from_local_datetime(&next)
+        .earliest()?
+        .timestamp_millis();
+    Some((day, next_ms))
+}
+
+/// Time tracked between `from` and `to` (unix millis), split by local day and by project,
+/// optionally for one project. Entries straddling the range or midnight are clipped; a
+/// running timer counts up to now
+pub fn report(from: i64, to: i64, project_id: Option<&str>) -> Result<TimeReport, Error> {
+    if to <= from {
+        return Err(Error::InvalidInput(
+            "Report range must end after it starts".to_string(),
+        ));
+    }
+    let now = store::now_ms();
+    let rows: Vec<(i64, Option<i64>, String, String)> = store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT e.started_at, e.ended_at, p.id, p.name FROM time_entries e JOIN tasks t ON t.id = e.task_id JOIN projects p ON p.id = t.project_id WHERE e.started_at < ?2 AND (e.ended_at IS NULL OR e.ended_at > ?1) AND (?3 IS NULL OR p.id = ?3)",
+        )?;
+        let rows = stmt.query_map(params![from, to, project_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?;
+        rows.collect()
+    })?;
+
+    let mut by_day: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+    let mut by_project: BTreeMap<String, (String, i64)> = BTreeMap::new();
+    for (started_at, ended_at, project_id, project_name) in rows {
+        let mut start = started_at.max(from);
+        let end = ended_at.unwrap_or(now).min(to);
+        if end <= start {
+            continue;
+        }
+        by_project.entry(project_id).or_insert((project_name, 0)).1 += end - start;
+        while start < end {
+            let Some((day, midnight)) = next_midnight(start) else {
+                break;
+            };
+            let segment_end = end.min(midnight);
+            *by_day.entry(day).or_default() += segment_end - start;
+            start = segment_end;
+        }
+    }
+
+    let mut by_project: Vec<ProjectTotal> = by_project
+        .into_iter()
+        .map(|(project_id, (project_name, total_ms))| ProjectTotal {
+            project_id,
+            project_name,
+            total_ms,
+        })
+        .collect();
+    by_project.sort_by_key(|p| std::cmp::Reverse(p.total_ms));
+
+    Ok(TimeReport {
+        from,
+        to,
+        total_ms: by_project.iter().map(|p| p.total_ms).sum(),
+        by_day: by_day
+            .into_iter()
+            .map(|(day, total_ms)| DayTotal {
+                day: day.format("%Y-%m-%d").to_string(),
+                total_ms,
+            })
+            .collect(),
+        by_project,
+    })
+}
+
+#[tauri::command]
+pub fn get_time_report(from: i64, to: i64) -> Result<TimeReport, Error> {
+    report(from, to, None)
+}
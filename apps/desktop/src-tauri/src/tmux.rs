@@ -0,0 +1,41 @@
+//! Thin wrappers around the tmux CLI
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::http_proxy;
+use crate::process::{self, first_existing, which};
+
+/// Locate tmux; GUI apps on macOS don't see Homebrew's bin directories on PATH
+pub fn tmux_path() -> Option<PathBuf> {
+    which("tmux").or_else(|| {
+        first_existing(&[
+            PathBuf::from("/opt/homebrew/bin/tmux"),
+            PathBuf::from("/usr/local/bin/tmux"),
+            PathBuf::from("/usr/bin/tmux"),
+        ])
+    })
+}
+
+/// Run tmux with `args`, returning stdout
+pub fn run(args: &[&str]) -> Result<String, String> {
+    let tmux = tmux_path().ok_or("tmux not found")?;
+    // A tmux server started here passes these on to every pane (and agent) it runs
+    let output = process::output(Command::new(tmux).args(args).envs(http_proxy::env()))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!(
+            "tmux {} failed: {}",
+            args.first().unwrap_or(&""),
+            stderr.trim()
+        ))
+    }
+}
+
+/// Capture the visible contents of a pane (`session:window.pane` target syntax)
+pub fn capture_pane(target: &str) -> Result<String, String> {
+    run(&["capture-pane", "-p", "-t", target])
+}
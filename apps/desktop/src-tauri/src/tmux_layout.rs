@@ -0,0 +1,291 @@
+//! tmux layout templates: a project's session with its windows and panes set up
+//!
+//! A template is a list of windows, each with panes and the commands typed into them;
+//! `{repo}`, `{project}` and `{serverLog}` in commands are filled in for the project.
+//! Templates live in the config; the built-in `default` one (editor, server logs, agent,
+//! tests) is used until the user saves their own. Applying a template to a project whose
+//! session already exists only adds the windows it's missing, so it can be re-run
+//! without disturbing what's open.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::{config, server_logs, store, tmux};
+
+const DEFAULT_TEMPLATE: &str = "default";
+const SESSION_PREFIX: &str = "claudepm-";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutPane {
+    /// Typed into the pane's shell; just a shell when unset
+    #[serde(default)]
+    pub command: Option<String>,
+    /// Split the previous pane side by side rather than top and bottom
+    #[serde(default)]
+    pub horizontal: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutWindow {
+    pub name: String,
+    pub panes: Vec<LayoutPane>,
+    /// tmux layout applied once the panes exist, e.g. `main-vertical` or `tiled`
+    #[serde(default)]
+    pub layout: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutTemplate {
+    pub name: String,
+    pub windows: Vec<LayoutWindow>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppliedLayout {
+    pub session: String,
+    /// Windows this call created
+    pub created: Vec<String>,
+    /// Windows that were already there and left alone
+    pub existing: Vec<String>,
+}
+
+fn pane(command: Option<&str>, horizontal: bool) -> LayoutPane {
+    LayoutPane {
+        command: command.map(str::to_string),
+        horizontal,
+    }
+}
+
+fn window(name: &str, panes: Vec<LayoutPane>, layout: Option<&str>) -> LayoutWindow {
+    LayoutWindow {
+        name: name.to_string(),
+        panes,
+        layout: layout.map(str::to_string),
+    }
+}
+
+fn templates(config: &config::AppConfig) -> Vec<LayoutTemplate> {
+    if !config.tmux_layouts.is_empty() {
+        return config.tmux_layouts.clone();
+    }
+    vec![LayoutTemplate {
+        name: DEFAULT_TEMPLATE.to_string(),
+        windows: vec![
+            window("editor", vec![pane(Some("${EDITOR:-vi} ."), false)], None),
+            window(
+                "server",
+                vec![pane(Some("tail -F {serverLog}"), false)],
+                None,
+            ),
+            window("agent", vec![pane(Some("claude"), false)], None),
+            window(
+                "tests",
+                vec![pane(None, false), pane(Some("git status"), true)],
+                Some("even-horizontal"),
+            ),
+        ],
+    }]
+}
+
+/// tmux session names can't contain `.` or `:`
+fn session_name(project: &store::Project) -> String {
+    let name: String = project
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("{}{}", SESSION_PREFIX, name.trim_matches('-'))
+}
+
+fn expand(command: &str, project: &store::Project, repo: &str) -> String {
+    let server_log = server_logs::log_dir()
+        .map(|dir| dir.join(server_logs::LOG_FILE).display().to_string())
+        .unwrap_or_default();
+    command
+        .replace("{repo}", repo)
+        .replace("{project}", &project.name)
+        .replace("{serverLog}", &crate::process::shell_quote(&server_log))
+}
+
+fn send(target: &str, command: &str) -> Result<(), String> {
+    tmux::run(&["send-keys", "-t", target, "-l", command])?;
+    tmux::run(&["send-keys", "-t", target, "Enter"]).map(|_| ())
+}
+
+fn session_exists(session: &str) -> bool {
+    tmux::run(&["has-session", "-t", &format!("={}", session)]).is_ok()
+}
+
+fn window_names(session: &str) -> Result<Vec<String>, String> {
+    tmux::run(&["list-windows", "-t", session, "-F", "#{window_name}"])
+        .map(|out| out.lines().map(str::to_string).collect())
+}
+
+/// Create `window` and its panes, starting `session` with it if `create_session`
+fn build_window(
+    session: &str,
+    window: &LayoutWindow,
+    project: &store::Project,
+    repo: &str,
+    create_session: bool,
+) -> Result<(), String> {
+    let target = format!("{}:{}", session, window.name);
+    if create_session {
+        tmux::run(&[
+            "new-session",
+            "-d",
+            "-s",
+            session,
+            "-n",
+            &window.name,
+            "-c",
+            repo,
+        ])?;
+    } else {
+        tmux::run(&[
+            "new-window",
+            "-d",
+            "-t",
+            session,
+            "-n",
+            &window.name,
+            "-c",
+            repo,
+        ])?;
+    }
+    for (i, pane) in window.panes.iter().enumerate() {
+        let pane_target = if i == 0 {
+            format!("{}.0", target)
+        } else {
+            let split = if pane.horizontal { "-h" } else { "-v" };
+            tmux::run(&[
+                "split-window",
+                split,
+                "-t",
+                &target,
+                "-c",
+                repo,
+                "-P",
+                "-F",
+                "#{pane_id}",
+            ])?
+            .trim()
+            .to_string()
+        };
+        if let Some(command) = pane.command.as_deref().filter(|c| !c.trim().is_empty()) {
+            send(&pane_target, &expand(command, project, repo))?;
+        }
+    }
+    if let Some(layout) = &window.layout {
+        tmux::run(&["select-layout", "-t", &target, layout])?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_tmux_layouts() -> Vec<LayoutTemplate> {
+    templates(&config::load())
+}
+
+/// Add or replace a template; saving the first one replaces the built-in
+#[tauri::command]
+pub fn save_tmux_layout(template: LayoutTemplate) -> Result<(), Error> {
+    if template.name.trim().is_empty() {
+        return Err(Error::InvalidInput("Template name is required".to_string()));
+    }
+    if template.windows.is_empty() || template.windows.iter().any(|w| w.panes.is_empty()) {
+        return Err(Error::InvalidInput(
+            "Every template needs at least one window, and every window a pane".to_string(),
+        ));
+    }
+    if template
+        .windows
+        .iter()
+        .any(|w| w.name.trim().is_empty() || w.name.contains([':', '.']))
+    {
+        return Err(Error::InvalidInput(
+            "Window names can't be empty or contain ':' or '.'".to_string(),
+        ));
+    }
+    config::update(|c| {
+        let mut all = templates(c);
+        match all.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => all.push(template),
+        }
+        c.tmux_layouts = all;
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_tmux_layout(name: String) -> Result<(), Error> {
+    config::update(|c| {
+        let mut all = templates(c);
+        all.retain(|t| t.name != name);
+        c.tmux_layouts = all;
+    })?;
+    Ok(())
+}
+
+/// Create the project's session from `template`, or add the windows it's missing
+#[tauri::command]
+pub fn apply_tmux_layout(
+    project: String,
+    template: Option<String>,
+) -> Result<AppliedLayout, Error> {
+    let name = template.unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
+    let template = templates(&config::load())
+        .into_iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| Error::NotFound(format!("Layout template not found: {}", name)))?;
+    let project = store::with_conn(|conn| store::get_project(conn, &project))?
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project)))?;
+    let repo = project
+        .repo_path
+        .clone()
+        .ok_or_else(|| Error::InvalidInput("The project has no repository path".to_string()))?;
+
+    let session = session_name(&project);
+    let mut existing = if session_exists(&session) {
+        window_names(&session)?
+    } else {
+        Vec::new()
+    };
+    let mut applied = AppliedLayout {
+        session: session.clone(),
+        created: Vec::new(),
+        existing: Vec::new(),
+    };
+    for window in &template.windows {
+        if existing.contains(&window.name) {
+            applied.existing.push(window.name.clone());
+            continue;
+        }
+        let create_session = existing.is_empty() && applied.created.is_empty();
+        build_window(&session, window, &project, &repo, create_session)?;
+        existing.push(window.name.clone());
+        applied.created.push(window.name.clone());
+    }
+    if applied.created.len() == template.windows.len() {
+        let first = format!("{}:{}", session, template.windows[0].name);
+        let _ = tmux::run(&["select-window", "-t", &first]);
+    }
+    println!(
+        "[Claude PM] Applied layout {} to {} ({} new windows)",
+        template.name,
+        session,
+        applied.created.len()
+    );
+    Ok(applied)
+}
@@ -0,0 +1,444 @@
+//! Retention of Claude transcripts, with archived deletions that can be undone
+//!
+//! Claude Code keeps every session's JSONL transcript under `~/.claude/projects/` forever.
+//! Each project directory (mapped to a project as in `search`) follows its project's
+//! policy, or the default one: files older than `max_age_days` go, then the oldest files
+//! until the directory fits in `max_total_mb`. Transcripts written in the last day are
+//! never touched, since their session may still be going.
+//!
+//! A collection first writes the files into a zip under `transcript-archive/` in the
+//! data directory, then deletes them; `undo_transcript_gc` puts them back while the
+//! archive is kept (`undo_hours`, 72 unless set). When `enabled`, collection runs every
+//! six hours; expired archives are removed on the same schedule either way.
+//!
+//! Events:
+//! - `transcripts-collected` with the [`GcBatch`] after a scheduled collection
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::error::Error;
+use crate::{config, power, search, store};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+const ARCHIVE_DIR: &str = "transcript-archive";
+const MANIFEST: &str = "manifest.json";
+const DEFAULT_UNDO_HOURS: u32 = 72;
+const MB: u64 = 1024 * 1024;
+const HOUR_MS: i64 = 60 * 60 * 1000;
+const DAY_MS: i64 = 24 * HOUR_MS;
+/// Transcripts this recent may belong to a running session
+const MIN_AGE_MS: i64 = DAY_MS;
+
+/// Serializes collections and undos, which both move files in and out of the archive
+static RUNNING: Mutex<()> = Mutex::new(());
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    /// Transcripts last written longer ago than this are removed; kept forever when unset
+    pub max_age_days: Option<u32>,
+    /// Oldest transcripts are removed until the project's fit; no cap when unset
+    pub max_total_mb: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TranscriptRetention {
+    /// Collect on a schedule; otherwise only when asked
+    pub enabled: bool,
+    /// Applies to projects without their own policy, and to unmapped directories
+    pub default_policy: RetentionPolicy,
+    /// Per project id
+    pub projects: BTreeMap<String, RetentionPolicy>,
+    /// How long archives are kept for undo (72 when unset)
+    pub undo_hours: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcCandidate {
+    pub path: String,
+    pub project_id: Option<String>,
+    pub bytes: u64,
+    pub modified_at: i64,
+    /// `age` or `size`
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcPreview {
+    pub files: Vec<GcCandidate>,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcBatch {
+    pub id: String,
+    pub created_at: i64,
+    /// When the archive is removed and the batch can no longer be undone
+    pub expires_at: i64,
+    pub files: Vec<GcCandidate>,
+    pub bytes: u64,
+    /// Files that couldn't be removed after archiving; they were left in place
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
+fn archive_dir() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join(ARCHIVE_DIR))
+}
+
+fn modified_ms(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64)
+}
+
+/// What `policy` would remove from the transcripts in `dir`
+fn dir_candidates(
+    dir: &Path,
+    project_id: Option<&str>,
+    policy: &RetentionPolicy,
+    now: i64,
+) -> Vec<GcCandidate> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    // Oldest first
+    let mut files: Vec<(PathBuf, u64, i64)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|path| {
+            let bytes = fs::metadata(&path).ok()?.len();
+            let modified = modified_ms(&path)?;
+            Some((path, bytes, modified))
+        })
+        .collect();
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    let mut total: u64 = files.iter().map(|(_, bytes, _)| bytes).sum();
+    let cap = policy.max_total_mb.map(|mb| mb.saturating_mul(MB));
+    let cutoff = policy.max_age_days.map(|days| now - days as i64 * DAY_MS);
+    let mut candidates = Vec::new();
+    for (path, bytes, modified) in files {
+        if now - modified < MIN_AGE_MS {
+            break;
+        }
+        let reason = if cutoff.is_some_and(|cutoff| modified < cutoff) {
+            "age"
+        } else if cap.is_some_and(|cap| total > cap) {
+            "size"
+        } else {
+            continue;
+        };
+        total -= bytes;
+        candidates.push(GcCandidate {
+            path: path.display().to_string(),
+            project_id: project_id.map(str::to_string),
+            bytes,
+            modified_at: modified,
+            reason: reason.to_string(),
+        });
+    }
+    candidates
+}
+
+fn candidates() -> Result<Vec<GcCandidate>, String> {
+    let settings = config::load().transcript_retention;
+    let Some(root) = search::transcripts_dir() else {
+        return Ok(Vec::new());
+    };
+    let Ok(dirs) = fs::read_dir(&root) else {
+        return Ok(Vec::new());
+    };
+    let now = store::now_ms();
+    let dirs: Vec<(PathBuf, Option<String>)> = store::with_conn(|conn| {
+        Ok(dirs
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                (
+                    entry.path(),
+                    search::project_for_transcript_dir(conn, &name),
+                )
+            })
+            .collect())
+    })?;
+    Ok(dirs
+        .into_iter()
+        .flat_map(|(dir, project_id)| {
+            let policy = project_id
+                .as_ref()
+                .and_then(|id| settings.projects.get(id))
+                .unwrap_or(&settings.default_policy);
+            dir_candidates(&dir, project_id.as_deref(), policy, now)
+        })
+        .collect())
+}
+
+fn undo_ms() -> i64 {
+    config::load()
+        .transcript_retention
+        .undo_hours
+        .unwrap_or(DEFAULT_UNDO_HOURS) as i64
+        * HOUR_MS
+}
+
+/// Path inside the archive: the project directory and file name
+fn entry_name(path: &Path) -> String {
+    let file = path.file_name().unwrap_or_default().to_string_lossy();
+    match path.parent().and_then(Path::file_name) {
+        Some(dir) => format!("{}/{}", dir.to_string_lossy(), file),
+        None => file.to_string(),
+    }
+}
+
+fn write_archive(path: &Path, batch: &GcBatch) -> Result<(), String> {
+    let file =
+        File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for candidate in &batch.files {
+        let source = Path::new(&candidate.path);
+        let mut reader = File::open(source)
+            .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        zip.start_file(entry_name(source), options)
+            .map_err(|e| e.to_string())?;
+        io::copy(&mut reader, &mut zip).map_err(|e| e.to_string())?;
+    }
+    zip.start_file(MANIFEST, options)
+        .map_err(|e| e.to_string())?;
+    let manifest = serde_json::to_vec_pretty(batch).map_err(|e| e.to_string())?;
+    zip.write_all(&manifest).map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Archive and delete everything the policies remove
+fn collect() -> Result<Option<GcBatch>, String> {
+    let _guard = RUNNING.lock().map_err(|e| e.to_string())?;
+    let files = candidates()?;
+    if files.is_empty() {
+        return Ok(None);
+    }
+    let dir = archive_dir().ok_or("Could not determine data directory")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let created_at = store::now_ms();
+    let mut batch = GcBatch {
+        id: store::new_id(),
+        created_at,
+        expires_at: created_at + undo_ms(),
+        bytes: files.iter().map(|f| f.bytes).sum(),
+        files,
+        errors: Vec::new(),
+    };
+    let archive = dir.join(format!("{}.zip", batch.id));
+    if let Err(e) = write_archive(&archive, &batch) {
+        let _ = fs::remove_file(&archive);
+        return Err(e);
+    }
+    // Only delete once the archive is complete
+    for file in &batch.files {
+        if let Err(e) = fs::remove_file(&file.path) {
+            batch
+                .errors
+                .push(format!("Failed to remove {}: {}", file.path, e));
+        }
+    }
+    println!(
+        "[Claude PM] Archived and removed {} transcripts ({} bytes)",
+        batch.files.len(),
+        batch.bytes
+    );
+    Ok(Some(batch))
+}
+
+fn read_manifest(path: &Path) -> Option<GcBatch> {
+    let mut zip = ZipArchive::new(File::open(path).ok()?).ok()?;
+    let entry = zip.by_name(MANIFEST).ok()?;
+    serde_json::from_reader(entry).ok()
+}
+
+/// Archived batches, newest first
+fn batches() -> Vec<GcBatch> {
+    let Some(entries) = archive_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut batches: Vec<GcBatch> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "zip"))
+        .filter_map(|path| read_manifest(&path))
+        .collect();
+    batches.sort_by_key(|batch| std::cmp::Reverse(batch.created_at));
+    batches
+}
+
+fn purge_expired() {
+    let Some(dir) = archive_dir() else {
+        return;
+    };
+    let now = store::now_ms();
+    for batch in batches().into_iter().filter(|b| b.expires_at <= now) {
+        let _ = fs::remove_file(dir.join(format!("{}.zip", batch.id)));
+    }
+}
+
+fn check(app: &AppHandle) {
+    purge_expired();
+    if !config::load().transcript_retention.enabled {
+        return;
+    }
+    match collect() {
+        Ok(Some(batch)) => {
+            let _ = app.emit("transcripts-collected", &batch);
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("[Claude PM] Transcript collection failed: {}", e),
+    }
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        check(&app);
+        power::wait(CHECK_INTERVAL);
+    });
+}
+
+fn validate(policy: &RetentionPolicy) -> Result<(), Error> {
+    if policy.max_age_days == Some(0) || policy.max_total_mb == Some(0) {
+        return Err(Error::InvalidInput(
+            "Keep at least a day and 1 MB of transcripts".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Transcripts the current policies would remove, without touching them
+#[tauri::command]
+pub async fn preview_transcript_gc() -> Result<GcPreview, Error> {
+    let files = tauri::async_runtime::spawn_blocking(candidates)
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok(GcPreview {
+        bytes: files.iter().map(|f| f.bytes).sum(),
+        files,
+    })
+}
+
+/// Collect now; `None` when there was nothing to remove
+#[tauri::command]
+pub async fn run_transcript_gc() -> Result<Option<GcBatch>, Error> {
+    Ok(tauri::async_runtime::spawn_blocking(collect)
+        .await
+        .map_err(|e| e.to_string())??)
+}
+
+/// Collections that can still be undone, newest first
+#[tauri::command]
+pub fn list_transcript_gc_batches() -> Vec<GcBatch> {
+    let now = store::now_ms();
+    batches()
+        .into_iter()
+        .filter(|batch| batch.expires_at > now)
+        .collect()
+}
+
+/// Put a collection's transcripts back; files that were recreated since are left alone.
+/// Returns the number restored.
+#[tauri::command]
+pub fn undo_transcript_gc(id: String) -> Result<usize, Error> {
+    let _guard = RUNNING.lock().map_err(|e| e.to_string())?;
+    let path = archive_dir()
+        .map(|dir| dir.join(format!("{}.zip", id)))
+        .filter(|path| path.is_file())
+        .ok_or_else(|| Error::NotFound(format!("No transcript archive with id {}", id)))?;
+    let batch = read_manifest(&path)
+        .ok_or_else(|| Error::Internal(format!("{} has no readable manifest", path.display())))?;
+    let file =
+        File::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let mut restored = 0;
+    for candidate in &batch.files {
+        let target = Path::new(&candidate.path);
+        if target.exists() {
+            continue;
+        }
+        let mut entry = zip
+            .by_name(&entry_name(target))
+            .map_err(|e| format!("{} is missing from the archive: {}", candidate.path, e))?;
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut out = File::create(target)
+            .map_err(|e| format!("Failed to restore {}: {}", candidate.path, e))?;
+        io::copy(&mut entry, &mut out).map_err(|e| e.to_string())?;
+        restored += 1;
+    }
+    drop(zip);
+    fs::remove_file(&path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    println!("[Claude PM] Restored {} transcripts", restored);
+    Ok(restored)
+}
+
+/// Set the default policy, or `project_id`'s (`None` policy drops the project's own)
+#[tauri::command]
+pub fn set_transcript_retention(
+    project_id: Option<String>,
+    policy: Option<RetentionPolicy>,
+) -> Result<(), Error> {
+    if let Some(policy) = &policy {
+        validate(policy)?;
+    }
+    config::update(|c| {
+        let settings = &mut c.transcript_retention;
+        match (project_id, policy) {
+            (Some(id), Some(policy)) => {
+                settings.projects.insert(id, policy);
+            }
+            (Some(id), None) => {
+                settings.projects.remove(&id);
+            }
+            (None, policy) => settings.default_policy = policy.unwrap_or_default(),
+        }
+    })?;
+    Ok(())
+}
+
+/// Turn scheduled collection on or off and set how long archives are kept
+#[tauri::command]
+pub fn set_transcript_gc_schedule(enabled: bool, undo_hours: Option<u32>) -> Result<(), Error> {
+    if undo_hours == Some(0) {
+        return Err(Error::InvalidInput(
+            "Keep archives for at least an hour".to_string(),
+        ));
+    }
+    config::update(|c| {
+        c.transcript_retention.enabled = enabled;
+        c.transcript_retention.undo_hours = undo_hours;
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_transcript_retention() -> TranscriptRetention {
+    config::load().transcript_retention
+}
@@ -0,0 +1,277 @@
+//! Live following of Claude session transcripts
+//!
+//! A followed transcript (`~/.claude/projects/<project>/<session>.jsonl`) is checked
+//! for growth twice a second; only the bytes appended since the last read are parsed,
+//! and a partially written last line waits for the next check. Each transcript line
+//! becomes one or more entries (text, tool calls and tool results), streamed to the
+//! frontend in batches.
+//!
+//! Events:
+//! - `transcript-entries` with a [`TranscriptBatch`] for each check that found new entries
+//! - `transcript-closed` with the session id once its transcript disappears
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::{rate_limits, search};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Entries returned from the existing transcript when following starts
+const BACKLOG_ENTRIES: usize = 200;
+/// Tool results are cut to this many characters; the transcript has the full output
+const MAX_RESULT_CHARS: usize = 4000;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum EntryKind {
+    Message {
+        role: String,
+        text: String,
+    },
+    ToolCall {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        text: String,
+        is_error: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptEntry {
+    /// The transcript line's `uuid`
+    pub uuid: Option<String>,
+    pub timestamp: Option<String>,
+    #[serde(flatten)]
+    pub kind: EntryKind,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptBatch {
+    pub session_id: String,
+    pub entries: Vec<TranscriptEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FollowedTranscript {
+    pub session_id: String,
+    pub path: String,
+    /// The latest entries already in the transcript
+    pub backlog: Vec<TranscriptEntry>,
+}
+
+struct Follower {
+    path: PathBuf,
+    offset: u64,
+    /// Bytes after the last newline, waiting for the rest of the line
+    partial: Vec<u8>,
+}
+
+static FOLLOWED: Mutex<BTreeMap<String, Follower>> = Mutex::new(BTreeMap::new());
+
+fn truncate(text: String) -> String {
+    if text.chars().count() <= MAX_RESULT_CHARS {
+        return text;
+    }
+    let mut cut: String = text.chars().take(MAX_RESULT_CHARS).collect();
+    cut.push('…');
+    cut
+}
+
+/// The entries in one transcript line; lines other than messages yield none
+pub fn parse_line(line: &str) -> Vec<TranscriptEntry> {
+    let Ok(line) = serde_json::from_str::<Value>(line) else {
+        return Vec::new();
+    };
+    let role = line["type"].as_str().unwrap_or_default();
+    if role != "user" && role != "assistant" {
+        return Vec::new();
+    }
+    let entry = |kind| TranscriptEntry {
+        uuid: line["uuid"].as_str().map(str::to_string),
+        timestamp: line["timestamp"].as_str().map(str::to_string),
+        kind,
+    };
+    let content = &line["message"]["content"];
+    let Value::Array(parts) = content else {
+        let text = search::text_of(content);
+        if text.trim().is_empty() {
+            return Vec::new();
+        }
+        return vec![entry(EntryKind::Message {
+            role: role.to_string(),
+            text,
+        })];
+    };
+    parts
+        .iter()
+        .filter_map(|part| match part["type"].as_str()? {
+            "text" => Some(entry(EntryKind::Message {
+                role: role.to_string(),
+                text: part["text"].as_str()?.to_string(),
+            })),
+            "tool_use" => Some(entry(EntryKind::ToolCall {
+                id: part["id"].as_str().unwrap_or_default().to_string(),
+                name: part["name"].as_str().unwrap_or_default().to_string(),
+                input: part["input"].clone(),
+            })),
+            "tool_result" => Some(entry(EntryKind::ToolResult {
+                tool_use_id: part["tool_use_id"].as_str().unwrap_or_default().to_string(),
+                text: truncate(search::text_of(&part["content"])),
+                is_error: part["is_error"].as_bool().unwrap_or(false),
+            })),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Find `<session_id>.jsonl` under the transcripts directory
+pub fn find_transcript(session_id: &str) -> Option<PathBuf> {
+    let file = format!("{}.jsonl", session_id);
+    fs::read_dir(search::transcripts_dir()?)
+        .ok()?
+        .flatten()
+        .map(|project| project.path().join(&file))
+        .find(|path| path.is_file())
+}
+
+/// Read what was appended since the last check; `None` once the file is gone
+fn read_appended(follower: &mut Follower) -> Option<Vec<TranscriptEntry>> {
+    let len = fs::metadata(&follower.path).ok()?.len();
+    if len < follower.offset {
+        // Rewritten from scratch
+        follower.offset = 0;
+        follower.partial.clear();
+    }
+    if len == follower.offset {
+        return Some(Vec::new());
+    }
+    let mut file = File::open(&follower.path).ok()?;
+    file.seek(SeekFrom::Start(follower.offset)).ok()?;
+    let mut appended = Vec::new();
+    file.take(len - follower.offset)
+        .read_to_end(&mut appended)
+        .ok()?;
+    follower.offset += appended.len() as u64;
+    follower.partial.extend_from_slice(&appended);
+
+    let complete = match follower.partial.iter().rposition(|b| *b == b'\n') {
+        Some(end) => follower.partial.drain(..=end).collect::<Vec<u8>>(),
+        None => return Some(Vec::new()),
+    };
+    Some(
+        String::from_utf8_lossy(&complete)
+            .lines()
+            .flat_map(parse_line)
+            .collect(),
+    )
+}
+
+fn poll(app: &AppHandle) -> bool {
+    let Ok(mut followed) = FOLLOWED.lock() else {
+        return false;
+    };
+    let mut closed = Vec::new();
+    for (session_id, follower) in followed.iter_mut() {
+        match read_appended(follower) {
+            Some(entries) if entries.is_empty() => {}
+            Some(entries) => {
+                let source = format!("transcript:{}", session_id);
+                for entry in &entries {
+                    if let EntryKind::Message { text, .. } = &entry.kind {
+                        rate_limits::scan(text, &source);
+                    }
+                }
+                let _ = app.emit(
+                    "transcript-entries",
+                    TranscriptBatch {
+                        session_id: session_id.clone(),
+                        entries,
+                    },
+                );
+            }
+            None => closed.push(session_id.clone()),
+        }
+    }
+    for session_id in closed {
+        followed.remove(&session_id);
+        let _ = app.emit("transcript-closed", &session_id);
+    }
+    !followed.is_empty()
+}
+
+/// Poll until nothing is followed any more
+fn spawn_poller(app: AppHandle) {
+    thread::spawn(move || {
+        while poll(&app) {
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+fn backlog(path: &Path) -> Result<(Vec<TranscriptEntry>, u64, Vec<u8>), String> {
+    let contents = fs::read(path).map_err(|e| format!("Failed to read transcript: {}", e))?;
+    let end = contents
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |i| i + 1);
+    let mut entries: Vec<TranscriptEntry> = String::from_utf8_lossy(&contents[..end])
+        .lines()
+        .flat_map(parse_line)
+        .collect();
+    entries.drain(..entries.len().saturating_sub(BACKLOG_ENTRIES));
+    Ok((entries, contents.len() as u64, contents[end..].to_vec()))
+}
+
+/// Start streaming a session's transcript, returning its latest entries
+#[tauri::command]
+pub fn follow_transcript(app: AppHandle, session_id: String) -> Result<FollowedTranscript, Error> {
+    let path = find_transcript(&session_id)
+        .ok_or_else(|| Error::NotFound(format!("No transcript for session {}", session_id)))?;
+    let (entries, offset, partial) = backlog(&path)?;
+    let first = {
+        let mut followed = FOLLOWED.lock().map_err(|e| e.to_string())?;
+        let first = followed.is_empty();
+        followed.insert(
+            session_id.clone(),
+            Follower {
+                path: path.clone(),
+                offset,
+                partial,
+            },
+        );
+        first
+    };
+    if first {
+        spawn_poller(app);
+    }
+    Ok(FollowedTranscript {
+        session_id,
+        path: path.display().to_string(),
+        backlog: entries,
+    })
+}
+
+#[tauri::command]
+pub fn unfollow_transcript(session_id: String) -> Result<(), Error> {
+    FOLLOWED
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&session_id);
+    Ok(())
+}
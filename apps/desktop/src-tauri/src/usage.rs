@@ -0,0 +1,405 @@
+//! Claude spend per project and monthly budgets
+//!
+//! Spend is read from Claude Code transcripts: the recorded `costUSD` when an entry has
+//! one, otherwise its token counts priced per model (see [`PRICING`]). Transcripts map to
+//! projects by their directory (see `search`). Files unchanged since the last check
+//! aren't re-read.
+//!
+//! Budgets are a monthly cap for the whole workspace and/or per project. Spend is
+//! checked every 15 minutes, and each threshold (50/80/100% unless configured) fires
+//! once per month and scope.
+//!
+//! Events:
+//! - `budget-threshold` with a [`BudgetAlert`] when spend crosses a threshold
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::notifications::{self, NotificationRequest};
+use crate::{config, power, search, store};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+const DEFAULT_THRESHOLDS: &[u8] = &[50, 80, 100];
+const ALERTS_FILE: &str = "budget_alerts.json";
+/// Scope key for the workspace-wide budget
+const WORKSPACE: &str = "workspace";
+
+/// USD per million tokens: model id fragment, input, output, cache write, cache read.
+/// The first fragment contained in the model id applies.
+pub const PRICING: &[(&str, f64, f64, f64, f64)] = &[
+    ("opus-4-5", 5.0, 25.0, 6.25, 0.5),
+    ("opus-4-6", 5.0, 25.0, 6.25, 0.5),
+    ("opus", 15.0, 75.0, 18.75, 1.5),
+    ("sonnet", 3.0, 15.0, 3.75, 0.3),
+    ("haiku-4-5", 1.0, 5.0, 1.25, 0.1),
+    ("haiku", 0.8, 4.0, 1.0, 0.08),
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BudgetSettings {
+    /// Monthly cap across all projects, in USD
+    pub monthly_cap_usd: Option<f64>,
+    /// Monthly caps per project id, in USD
+    pub project_caps: BTreeMap<String, f64>,
+    /// Percentages of a cap that alert; 50, 80 and 100 when empty
+    pub thresholds: Vec<u8>,
+}
+
+impl BudgetSettings {
+    fn thresholds(&self) -> Vec<u8> {
+        let mut thresholds = if self.thresholds.is_empty() {
+            DEFAULT_THRESHOLDS.to_vec()
+        } else {
+            self.thresholds.clone()
+        };
+        thresholds.sort_unstable();
+        thresholds.dedup();
+        thresholds
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetLine {
+    /// `workspace` or a project id
+    pub scope: String,
+    pub name: String,
+    pub spent_usd: f64,
+    pub cap_usd: Option<f64>,
+    pub remaining_usd: Option<f64>,
+    pub percent: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetStatus {
+    /// `YYYY-MM`
+    pub month: String,
+    pub workspace: BudgetLine,
+    pub projects: Vec<BudgetLine>,
+    /// Spend in transcripts that don't belong to a known project
+    pub unassigned_usd: f64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetAlert {
+    pub month: String,
+    pub threshold: u8,
+    #[serde(flatten)]
+    pub line: BudgetLine,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct FiredAlerts {
+    month: String,
+    /// Scope -> thresholds already alerted this month
+    fired: BTreeMap<String, Vec<u8>>,
+}
+
+/// A transcript's spend this month, as of its last modification
+struct CachedFile {
+    modified: i64,
+    month: String,
+    project_id: Option<String>,
+    cost: f64,
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static FILES: Mutex<BTreeMap<PathBuf, CachedFile>> = Mutex::new(BTreeMap::new());
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Cost in USD of one transcript entry
+pub fn entry_cost(entry: &Value) -> f64 {
+    if let Some(cost) = entry["costUSD"].as_f64() {
+        return cost;
+    }
+    let usage = &entry["message"]["usage"];
+    let model = entry["message"]["model"].as_str().unwrap_or_default();
+    let Some((_, input, output, cache_write, cache_read)) =
+        PRICING.iter().find(|(id, ..)| model.contains(id))
+    else {
+        return 0.0;
+    };
+    let tokens = |key: &str| usage[key].as_u64().unwrap_or(0) as f64 / 1_000_000.0;
+    tokens("input_tokens") * input
+        + tokens("output_tokens") * output
+        + tokens("cache_creation_input_tokens") * cache_write
+        + tokens("cache_read_input_tokens") * cache_read
+}
+
+fn month_key(day: NaiveDate) -> String {
+    format!("{:04}-{:02}", day.year(), day.month())
+}
+
+pub fn month_start() -> i64 {
+    let today = Local::now().date_naive();
+    today
+        .with_day(1)
+        .and_then(|first| first.and_hms_opt(0, 0, 0))
+        .and_then(|start| Local.from_local_datetime(&start).earliest())
+        .map(|start| start.timestamp_millis())
+        .unwrap_or_default()
+}
+
+fn modified_ms(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as i64)
+}
+
+fn file_cost(path: &Path, from: i64) -> f64 {
+    let Ok(file) = fs::File::open(path) else {
+        return 0.0;
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<Value>(&line).ok())
+        .filter(|entry| {
+            entry["timestamp"]
+                .as_str()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .is_some_and(|t| t.timestamp_millis() >= from)
+        })
+        .map(|entry| entry_cost(&entry))
+        .sum()
+}
+
+/// Spend this month per project id (`None` for transcripts outside known projects)
+fn monthly_spend() -> Result<BTreeMap<Option<String>, f64>, String> {
+    let month = month_key(Local::now().date_naive());
+    let from = month_start();
+    let mut spend = BTreeMap::new();
+    let Some(root) = search::transcripts_dir().filter(|dir| dir.is_dir()) else {
+        return Ok(spend);
+    };
+    let dirs = fs::read_dir(&root).map_err(|e| e.to_string())?;
+    let mut files = FILES.lock().map_err(|e| e.to_string())?;
+    for dir in dirs.flatten() {
+        let dir_name = dir.file_name().to_string_lossy().to_string();
+        let Ok(entries) = fs::read_dir(dir.path()) else {
+            continue;
+        };
+        let project_id =
+            store::with_conn(|conn| Ok(search::project_for_transcript_dir(conn, &dir_name)))?;
+        for path in entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        {
+            let Some(modified) = modified_ms(&path).filter(|m| *m >= from) else {
+                continue;
+            };
+            let fresh = files
+                .get(&path)
+                .is_some_and(|cached| cached.modified == modified && cached.month == month);
+            if !fresh {
+                files.insert(
+                    path.clone(),
+                    CachedFile {
+                        modified,
+                        month: month.clone(),
+                        project_id: project_id.clone(),
+                        cost: file_cost(&path, from),
+                    },
+                );
+            }
+        }
+    }
+    files.retain(|_, cached| cached.month == month);
+    for cached in files.values() {
+        *spend.entry(cached.project_id.clone()).or_insert(0.0) += cached.cost;
+    }
+    Ok(spend)
+}
+
+fn line(scope: &str, name: String, spent: f64, cap: Option<f64>) -> BudgetLine {
+    BudgetLine {
+        scope: scope.to_string(),
+        name,
+        spent_usd: spent,
+        cap_usd: cap,
+        remaining_usd: cap.map(|cap| (cap - spent).max(0.0)),
+        percent: cap.filter(|cap| *cap > 0.0).map(|cap| spent / cap * 100.0),
+    }
+}
+
+fn status() -> Result<BudgetStatus, Error> {
+    let settings = config::load().budget;
+    let spend = monthly_spend()?;
+    let projects = store::list_projects()?;
+    let total: f64 = spend.values().sum();
+    let mut lines: Vec<BudgetLine> = projects
+        .into_iter()
+        .filter_map(|project| {
+            let spent = spend.get(&Some(project.id.clone())).copied();
+            let cap = settings.project_caps.get(&project.id).copied();
+            (spent.is_some() || cap.is_some())
+                .then(|| line(&project.id, project.name, spent.unwrap_or(0.0), cap))
+        })
+        .collect();
+    lines.sort_by(|a, b| b.spent_usd.total_cmp(&a.spent_usd));
+    Ok(BudgetStatus {
+        month: month_key(Local::now().date_naive()),
+        workspace: line(
+            WORKSPACE,
+            "Workspace".to_string(),
+            total,
+            settings.monthly_cap_usd,
+        ),
+        projects: lines,
+        unassigned_usd: spend.get(&None).copied().unwrap_or(0.0),
+    })
+}
+
+fn alerts_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join(ALERTS_FILE))
+}
+
+fn load_alerts() -> FiredAlerts {
+    alerts_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_alerts(alerts: &FiredAlerts) -> Result<(), String> {
+    let path = alerts_path().ok_or("Could not determine data directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let contents = serde_json::to_string_pretty(alerts).map_err(|e| e.to_string())?;
+    fs::write(path, contents).map_err(|e| format!("Failed to write budget alerts: {}", e))
+}
+
+/// Alert for every threshold newly crossed this month
+fn check(app: &AppHandle) -> Result<(), Error> {
+    let status = status()?;
+    let thresholds = config::load().budget.thresholds();
+    let mut alerts = load_alerts();
+    if alerts.month != status.month {
+        alerts = FiredAlerts {
+            month: status.month.clone(),
+            ..Default::default()
+        };
+    }
+    let mut changed = false;
+    for budget in std::iter::once(&status.workspace).chain(&status.projects) {
+        let Some(percent) = budget.percent else {
+            continue;
+        };
+        let fired = alerts.fired.entry(budget.scope.clone()).or_default();
+        // Only the highest newly crossed threshold is announced
+        let Some(threshold) = thresholds
+            .iter()
+            .copied()
+            .filter(|t| percent >= *t as f64 && !fired.contains(t))
+            .max()
+        else {
+            continue;
+        };
+        fired.extend(thresholds.iter().copied().filter(|t| *t <= threshold));
+        changed = true;
+        println!(
+            "[Claude PM] {} spend at {:.0}% of its monthly budget",
+            budget.name, percent
+        );
+        notifications::notify(
+            app,
+            NotificationRequest {
+                title: format!("{} budget {}% used", budget.name, threshold),
+                body: format!(
+                    "${:.2} of ${:.2} spent this month",
+                    budget.spent_usd,
+                    budget.cap_usd.unwrap_or_default()
+                ),
+                key: Some(format!("budget:{}:{}", budget.scope, threshold)),
+                category: Some("budget".to_string()),
+                ..Default::default()
+            },
+        );
+        let _ = app.emit(
+            "budget-threshold",
+            BudgetAlert {
+                month: status.month.clone(),
+                threshold,
+                line: budget.clone(),
+            },
+        );
+    }
+    if changed {
+        save_alerts(&alerts)?;
+    }
+    Ok(())
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let _ = APP.set(app.clone());
+    thread::spawn(move || loop {
+        if let Err(e) = check(&app) {
+            eprintln!("[Claude PM] Budget check failed: {}", e);
+        }
+        power::wait(CHECK_INTERVAL);
+    });
+}
+
+/// This month's spend and remaining budget, workspace-wide and per project
+#[tauri::command]
+pub async fn get_budget_status() -> Result<BudgetStatus, Error> {
+    tauri::async_runtime::spawn_blocking(status)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Set or clear (`None`) the monthly cap for a project, or for the workspace without `project_id`
+#[tauri::command]
+pub fn set_budget(project_id: Option<String>, cap_usd: Option<f64>) -> Result<(), Error> {
+    if cap_usd.is_some_and(|cap| !cap.is_finite() || cap <= 0.0) {
+        return Err(Error::InvalidInput(
+            "A budget must be a positive amount".to_string(),
+        ));
+    }
+    config::update(|c| match (project_id, cap_usd) {
+        (None, cap) => c.budget.monthly_cap_usd = cap,
+        (Some(id), Some(cap)) => {
+            c.budget.project_caps.insert(id, cap);
+        }
+        (Some(id), None) => {
+            c.budget.project_caps.remove(&id);
+        }
+    })?;
+    // A lowered cap may already be crossed
+    if let Some(app) = APP.get().cloned() {
+        thread::spawn(move || {
+            let _ = check(&app);
+        });
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_budget_thresholds(thresholds: Vec<u8>) -> Result<(), Error> {
+    if thresholds.iter().any(|t| *t == 0 || *t > 200) {
+        return Err(Error::InvalidInput(
+            "Thresholds are percentages between 1 and 200".to_string(),
+        ));
+    }
+    config::update(|c| c.budget.thresholds = thresholds)?;
+    Ok(())
+}
@@ -0,0 +1,191 @@
+//! Encrypted per-project environment variable sets
+//!
+//! Sets are stored in vault.json, each encrypted with ChaCha20-Poly1305. The key lives
+//! in the OS keychain so the file is useless on its own. The project name is bound in as
+//! associated data, so a sealed set copied under another project fails to decrypt.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::ChaCha20Poly1305;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::Error;
+use crate::secrets::{self, NONCE_LEN};
+use crate::{app_lock, config};
+
+pub const VAULT_FILE: &str = "vault.json";
+const KEYCHAIN_ACCOUNT: &str = "vault-key";
+
+pub type EnvSet = BTreeMap<String, String>;
+
+#[derive(Serialize, Deserialize)]
+struct SealedSet {
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct VaultFile {
+    sets: BTreeMap<String, SealedSet>,
+}
+
+fn vault_path() -> Result<PathBuf, String> {
+    config::config_dir()
+        .map(|dir| dir.join(VAULT_FILE))
+        .ok_or_else(|| "Could not determine config directory".to_string())
+}
+
+fn load_file() -> Result<VaultFile, String> {
+    let path = vault_path()?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => {
+            serde_json::from_str(&contents).map_err(|e| format!("Corrupt vault file: {}", e))
+        }
+        Err(_) => Ok(VaultFile::default()),
+    }
+}
+
+fn save_file(vault: &VaultFile) -> Result<(), String> {
+    let path = vault_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+    let contents = serde_json::to_string_pretty(vault)
+        .map_err(|e| format!("Failed to serialize vault: {}", e))?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write vault: {}", e))
+}
+
+/// Fetch the vault key from the keychain, creating one on first use
+fn cipher() -> Result<ChaCha20Poly1305, String> {
+    let key = match secrets::get(KEYCHAIN_ACCOUNT)? {
+        Some(key) => key,
+        None => {
+            let key = secrets::generate_key();
+            secrets::set(KEYCHAIN_ACCOUNT, &key)?;
+            key
+        }
+    };
+    secrets::cipher(&key).map_err(|e| format!("Vault key: {}", e))
+}
+
+fn seal(cipher: &ChaCha20Poly1305, project: &str, vars: &EnvSet) -> Result<SealedSet, String> {
+    let plaintext = serde_json::to_vec(vars).map_err(|e| e.to_string())?;
+    let sealed = secrets::seal(cipher, project.as_bytes(), &plaintext)?;
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    Ok(SealedSet {
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+fn open(cipher: &ChaCha20Poly1305, project: &str, sealed: &SealedSet) -> Result<EnvSet, String> {
+    let nonce = BASE64.decode(&sealed.nonce).map_err(|e| e.to_string())?;
+    let ciphertext = BASE64
+        .decode(&sealed.ciphertext)
+        .map_err(|e| e.to_string())?;
+    if nonce.len() != NONCE_LEN {
+        return Err("Invalid vault entry".to_string());
+    }
+    let plaintext = secrets::open(cipher, project.as_bytes(), &[nonce, ciphertext].concat())
+        .map_err(|_| "Failed to decrypt vault entry (wrong key?)".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| e.to_string())
+}
+
+/// Decrypt the env set for `project`, for injecting into a spawned process
+pub fn env_for(project: &str) -> Result<EnvSet, String> {
+    let vault = load_file()?;
+    let sealed = vault
+        .sets
+        .get(project)
+        .ok_or_else(|| format!("No env set for project: {}", project))?;
+    open(&cipher()?, project, sealed)
+}
+
+#[tauri::command]
+pub fn list_env_sets() -> Result<Vec<String>, Error> {
+    Ok(load_file()?.sets.into_keys().collect())
+}
+
+#[tauri::command]
+pub fn get_env_set(project: String) -> Result<EnvSet, Error> {
+    app_lock::ensure_unlocked()?;
+    env_for(&project).map_err(Error::from)
+}
+
+/// Create or replace the env set for `project`
+#[tauri::command]
+pub fn set_env_set(project: String, vars: EnvSet) -> Result<(), Error> {
+    app_lock::ensure_unlocked()?;
+    if let Some(name) = vars.keys().find(|k| k.is_empty() || k.contains('=')) {
+        return Err(Error::InvalidInput(format!(
+            "Invalid variable name: {:?}",
+            name
+        )));
+    }
+    let mut vault = load_file()?;
+    let sealed = seal(&cipher()?, &project, &vars)?;
+    vault.sets.insert(project, sealed);
+    save_file(&vault).map_err(Error::from)
+}
+
+#[tauri::command]
+pub fn delete_env_set(project: String) -> Result<(), Error> {
+    app_lock::ensure_unlocked()?;
+    let mut vault = load_file()?;
+    vault.sets.remove(&project);
+    save_file(&vault)?;
+    config::update(|c| {
+        if c.server_env_set.as_deref() == Some(project.as_str()) {
+            c.server_env_set = None;
+        }
+    })
+    .map(|_| ())
+    .map_err(Error::from)
+}
+
+/// Choose which env set the server gets on its next (re)start; `None` clears it
+#[tauri::command]
+pub fn select_server_env_set(project: Option<String>) -> Result<(), Error> {
+    app_lock::ensure_unlocked()?;
+    if let Some(ref project) = project {
+        if !load_file()?.sets.contains_key(project) {
+            return Err(Error::NotFound(format!(
+                "No env set for project: {}",
+                project
+            )));
+        }
+    }
+    config::update(|c| c.server_env_set = project)
+        .map(|_| ())
+        .map_err(Error::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cipher() -> ChaCha20Poly1305 {
+        secrets::cipher(&secrets::generate_key()).unwrap()
+    }
+
+    #[test]
+    fn round_trips_under_the_same_project() {
+        let cipher = test_cipher();
+        let vars = EnvSet::from([("API_TOKEN".to_string(), "secret".to_string())]);
+        let sealed = seal(&cipher, "alpha", &vars).unwrap();
+        assert_eq!(open(&cipher, "alpha", &sealed).unwrap(), vars);
+    }
+
+    #[test]
+    fn rejects_a_set_moved_to_another_project() {
+        let cipher = test_cipher();
+        let vars = EnvSet::from([("API_TOKEN".to_string(), "secret".to_string())]);
+        let sealed = seal(&cipher, "alpha", &vars).unwrap();
+        assert!(open(&cipher, "beta", &sealed).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Which version control system a project's repository uses, and what it can do
+//!
+//! Detection walks up from the project path looking for `.jj`, `.hg` or `.git`; `.jj` is
+//! checked first since a colocated Jujutsu repo also has a `.git` next to it, and jj owns
+//! its working copy. Status (branch or bookmark, current revision, changed files) comes
+//! from the tool's own CLI. The features built on git plumbing — pre-run snapshots, run
+//! diffs and pull requests — are reported as unavailable for Mercurial and Jujutsu repos
+//! and refuse with an `unsupported` error rather than failing halfway. Task worktrees
+//! work in Jujutsu repos too, as jj workspaces (see `task_branch`).
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::process::{self, CancelToken};
+use crate::store;
+
+const VCS_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Vcs {
+    Git,
+    Mercurial,
+    Jujutsu,
+}
+
+impl Vcs {
+    pub fn name(self) -> &'static str {
+        match self {
+            Vcs::Git => "git",
+            Vcs::Mercurial => "Mercurial",
+            Vcs::Jujutsu => "Jujutsu",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Repo {
+    pub kind: Vcs,
+    pub root: PathBuf,
+    /// A Jujutsu repo with a git checkout alongside (`jj git init --colocate`)
+    pub colocated: bool,
+}
+
+/// What the app can do with a repository
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VcsFeatures {
+    pub worktrees: bool,
+    pub snapshots: bool,
+    pub run_diffs: bool,
+    pub pull_requests: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VcsStatus {
+    pub kind: Vcs,
+    pub root: String,
+    pub colocated: bool,
+    /// The git or Mercurial branch, or the nearest Jujutsu bookmark
+    pub branch: Option<String>,
+    /// Short commit hash (git, Mercurial) or change id (Jujutsu)
+    pub revision: Option<String>,
+    pub changed_files: usize,
+    pub features: VcsFeatures,
+}
+
+/// The repository `path` is in, if any
+pub fn detect(path: &Path) -> Option<Repo> {
+    path.ancestors().find_map(|dir| {
+        if dir.join(".jj").is_dir() {
+            Some(Repo {
+                kind: Vcs::Jujutsu,
+                root: dir.to_path_buf(),
+                colocated: dir.join(".git").exists(),
+            })
+        } else if dir.join(".hg").is_dir() {
+            Some(Repo {
+                kind: Vcs::Mercurial,
+                root: dir.to_path_buf(),
+                colocated: false,
+            })
+        } else if dir.join(".git").exists() {
+            // `.git` is a file in linked worktrees
+            Some(Repo {
+                kind: Vcs::Git,
+                root: dir.to_path_buf(),
+                colocated: false,
+            })
+        } else {
+            None
+        }
+    })
+}
+
+/// Fail with an `unsupported` error unless git owns the working copy at `path`; paths that
+/// aren't in any repository are left for git itself to complain about
+pub fn require_git(path: &Path, feature: &str) -> Result<(), Error> {
+    match detect(path) {
+        Some(repo) if repo.kind != Vcs::Git => Err(Error::Unsupported(format!(
+            "{} need a git repository; {} is a {} repository",
+            feature,
+            repo.root.display(),
+            repo.kind.name()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn features(kind: Vcs) -> VcsFeatures {
+    VcsFeatures {
+        worktrees: kind != Vcs::Mercurial,
+        snapshots: kind == Vcs::Git,
+        run_diffs: kind == Vcs::Git,
+        pull_requests: kind == Vcs::Git,
+    }
+}
+
+/// Run a VCS command in `dir`, returning its trimmed stdout
+pub fn run(program: &str, dir: &Path, args: &[&str]) -> Result<String, String> {
+    let mut command = Command::new(program);
+    command.args(args).current_dir(dir);
+    match program {
+        // Stable output regardless of the user's aliases and config
+        "hg" => {
+            command.env("HGPLAIN", "1");
+        }
+        "jj" => {
+            command.args(["--color", "never", "--no-pager"]);
+        }
+        _ => {}
+    }
+    let output = process::run(&mut command, VCS_TIMEOUT, &CancelToken::default())
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn non_empty(value: Result<String, String>) -> Option<String> {
+    value.ok().filter(|value| !value.is_empty())
+}
+
+pub fn status(path: &Path) -> Result<VcsStatus, Error> {
+    let repo = detect(path)
+        .ok_or_else(|| Error::NotFound(format!("{} is not in a repository", path.display())))?;
+    let dir = repo.root.as_path();
+    let (branch, revision, changed) = match repo.kind {
+        Vcs::Git => (
+            non_empty(run("git", dir, &["symbolic-ref", "-q", "--short", "HEAD"])),
+            non_empty(run("git", dir, &["rev-parse", "--short", "HEAD"])),
+            run("git", dir, &["status", "--porcelain"])?,
+        ),
+        Vcs::Mercurial => (
+            non_empty(run("hg", dir, &["branch"])),
+            non_empty(run("hg", dir, &["id", "-i"])),
+            run("hg", dir, &["status"])?,
+        ),
+        Vcs::Jujutsu => (
+            non_empty(run(
+                "jj",
+                dir,
+                &[
+                    "log",
+                    "--no-graph",
+                    "-r",
+                    "latest(::@ & bookmarks())",
+                    "-T",
+                    "bookmarks.join(\",\")",
+                ],
+            )),
+            non_empty(run(
+                "jj",
+                dir,
+                &["log", "--no-graph", "-r", "@", "-T", "change_id.short()"],
+            )),
+            run("jj", dir, &["diff", "--summary", "-r", "@"])?,
+        ),
+    };
+    Ok(VcsStatus {
+        kind: repo.kind,
+        root: repo.root.display().to_string(),
+        colocated: repo.colocated,
+        branch,
+        revision,
+        changed_files: changed.lines().filter(|line| !line.is_empty()).count(),
+        features: features(repo.kind),
+    })
+}
+
+/// The version control status of a project's repository; `None` when the project has no
+/// repository path or it isn't under version control
+#[tauri::command]
+pub async fn get_project_vcs(project_id: String) -> Result<Option<VcsStatus>, Error> {
+    let project = store::with_conn(|conn| store::get_project(conn, &project_id))?
+        .ok_or_else(|| Error::NotFound(format!("Project not found: {}", project_id)))?;
+    let Some(path) = project.repo_path.map(PathBuf::from) else {
+        return Ok(None);
+    };
+    if detect(&path).is_none() {
+        return Ok(None);
+    }
+    tauri::async_runtime::spawn_blocking(move || status(&path).map(Some))
+        .await
+        .map_err(|e| e.to_string())?
+}
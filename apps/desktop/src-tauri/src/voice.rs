@@ -0,0 +1,397 @@
+//! Dictation: recording the microphone and transcribing it locally
+//!
+//! The default input device is recorded through `cpal` into memory, downmixed to mono and
+//! resampled to 16 kHz with `rubato`, then transcribed in-process by whisper.cpp (through
+//! `whisper-rs`) with the configured ggml model, so no audio leaves the machine or touches
+//! the disk. The loaded model is kept for the next dictation. Recordings stop by
+//! themselves after five minutes.
+//!
+//! Events:
+//! - `voice-capture` with the state (`recording`, `transcribing`, `idle`)
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{Device, FromSample, Sample, SampleFormat, SizedSample, Stream, StreamConfig};
+use rubato::audioadapter_buffers::direct::InterleavedSlice;
+use rubato::{Fft, FixedSync, Resampler};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+use crate::config;
+use crate::error::Error;
+
+const MAX_SECONDS: u32 = 300;
+/// The only rate whisper.cpp accepts
+const WHISPER_RATE: u32 = 16_000;
+const RESAMPLE_CHUNK: usize = 1024;
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct VoiceSettings {
+    /// ggml model file, e.g. `ggml-base.en.bin`
+    pub model_path: Option<String>,
+    /// Spoken language code (`en`, `de`...); whisper detects it when unset
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceStatus {
+    /// The microphone that would be recorded
+    pub input_device: Option<String>,
+    pub model: Option<String>,
+    pub recording: bool,
+    /// What's missing before dictation can work
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transcription {
+    pub text: String,
+    pub duration_ms: u64,
+}
+
+/// Mono samples at the device's rate
+#[derive(Default)]
+struct Recording {
+    samples: Vec<f32>,
+    sample_rate: u32,
+}
+
+/// Where the input callback collects downmixed samples
+type Buffer = Arc<Mutex<Vec<f32>>>;
+
+struct Capture {
+    stop: Sender<()>,
+    recorder: JoinHandle<Recording>,
+    started: Instant,
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static CAPTURE: Mutex<Option<Capture>> = Mutex::new(None);
+static MODEL: Mutex<Option<(PathBuf, Arc<WhisperContext>)>> = Mutex::new(None);
+
+fn emit_state(state: &str) {
+    if let Some(app) = APP.get() {
+        let _ = app.emit("voice-capture", state);
+    }
+}
+
+fn input_device() -> Option<Device> {
+    cpal::default_host().default_input_device()
+}
+
+fn device_name(device: &Device) -> String {
+    device
+        .description()
+        .map(|description| description.name().to_string())
+        .unwrap_or_else(|_| "Default input".to_string())
+}
+
+fn find_model(settings: &VoiceSettings) -> Option<PathBuf> {
+    settings
+        .model_path
+        .as_ref()
+        .map(PathBuf::from)
+        .filter(|p| p.is_file())
+}
+
+/// Append interleaved `frames` to `out` as one channel, averaging across channels
+fn downmix<T>(out: &mut Vec<f32>, frames: &[T], channels: usize)
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    let channels = channels.max(1);
+    out.extend(
+        frames.chunks(channels).map(|frame| {
+            frame.iter().map(|s| f32::from_sample(*s)).sum::<f32>() / frame.len() as f32
+        }),
+    );
+}
+
+/// Convert mono audio to the rate whisper.cpp expects
+fn resample(samples: Vec<f32>, sample_rate: u32) -> Result<Vec<f32>, String> {
+    if sample_rate == WHISPER_RATE || samples.is_empty() {
+        return Ok(samples);
+    }
+    let failed = |e: &dyn std::fmt::Display| format!("Failed to resample the recording: {}", e);
+    let mut resampler = Fft::<f32>::new(
+        sample_rate as usize,
+        WHISPER_RATE as usize,
+        RESAMPLE_CHUNK,
+        1,
+        FixedSync::Both,
+    )
+    .map_err(|e| failed(&e))?;
+    let input = InterleavedSlice::new(&samples, 1, samples.len()).map_err(|e| failed(&e))?;
+    let output = resampler
+        .process_all(&input, samples.len(), None)
+        .map_err(|e| failed(&e))?;
+    Ok(output.take_data())
+}
+
+fn stream<T>(device: &Device, config: StreamConfig, samples: Buffer) -> Result<Stream, cpal::Error>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    let limit = MAX_SECONDS as usize * config.sample_rate as usize;
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &_| {
+            if let Ok(mut samples) = samples.lock() {
+                if samples.len() < limit {
+                    downmix(&mut samples, data, channels);
+                }
+            }
+        },
+        |e| eprintln!("[Claude PM] Microphone error: {}", e),
+        None,
+    )
+}
+
+/// Start recording the default microphone; the stream lives on its own thread because
+/// some platforms don't let it move between threads
+fn open_stream() -> Result<(Stream, Buffer, u32), String> {
+    let device = input_device().ok_or("No microphone found")?;
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("Can't record from {}: {}", device_name(&device), e))?;
+    let config = supported.config();
+    let sample_rate = config.sample_rate;
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let buffer = samples.clone();
+    let stream = match supported.sample_format() {
+        SampleFormat::F32 => stream::<f32>(&device, config, buffer),
+        SampleFormat::I16 => stream::<i16>(&device, config, buffer),
+        SampleFormat::U16 => stream::<u16>(&device, config, buffer),
+        SampleFormat::I32 => stream::<i32>(&device, config, buffer),
+        other => {
+            return Err(format!(
+                "{} records {} samples, which isn't supported",
+                device_name(&device),
+                other
+            ))
+        }
+    }
+    .map_err(|e| format!("Failed to start recording: {}", e))?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start recording: {}", e))?;
+    Ok((stream, samples, sample_rate))
+}
+
+/// Record until told to stop (or for `MAX_SECONDS`), reporting on `ready` whether the
+/// microphone opened
+fn record(stop: Receiver<()>, ready: Sender<Result<(), String>>) -> Recording {
+    let (stream, samples, sample_rate) = match open_stream() {
+        Ok(opened) => {
+            let _ = ready.send(Ok(()));
+            opened
+        }
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return Recording::default();
+        }
+    };
+    let _ = stop.recv_timeout(Duration::from_secs(MAX_SECONDS.into()));
+    drop(stream);
+    let samples = samples.lock().map(|mut s| std::mem::take(&mut *s));
+    Recording {
+        samples: samples.unwrap_or_default(),
+        sample_rate,
+    }
+}
+
+/// The whisper context for `path`, loading it the first time (or when it changes)
+fn model(path: PathBuf) -> Result<Arc<WhisperContext>, Error> {
+    let mut loaded = MODEL.lock().map_err(|e| e.to_string())?;
+    if let Some((ref current, ref context)) = *loaded {
+        if *current == path {
+            return Ok(context.clone());
+        }
+    }
+    // Route whisper.cpp's logging away from stderr
+    whisper_rs::install_logging_hooks();
+    let context = WhisperContext::new_with_params(&path, WhisperContextParameters::default())
+        .map_err(|e| {
+            Error::InvalidInput(format!(
+                "Failed to load the whisper model {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+    let context = Arc::new(context);
+    *loaded = Some((path, context.clone()));
+    Ok(context)
+}
+
+fn transcribe(recording: Recording) -> Result<String, Error> {
+    let settings = config::load().voice;
+    let path = find_model(&settings).ok_or_else(|| {
+        Error::InvalidInput("Choose a whisper model file in the voice settings".to_string())
+    })?;
+    let audio = resample(recording.samples, recording.sample_rate)?;
+    if audio.is_empty() {
+        return Err(Error::Internal(
+            "The microphone didn't produce any audio; check microphone access".to_string(),
+        ));
+    }
+    let context = model(path)?;
+    let mut state = context
+        .create_state()
+        .map_err(|e| format!("Failed to start transcription: {}", e))?;
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_language(settings.language.as_deref());
+    params.set_n_threads(thread::available_parallelism().map_or(4, |n| n.get().min(8)) as i32);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_special(false);
+    params.set_print_timestamps(false);
+    state
+        .full(params, &audio)
+        .map_err(|e| format!("Transcription failed: {}", e))?;
+    let text = state
+        .as_iter()
+        .filter_map(|segment| {
+            segment
+                .to_str_lossy()
+                .ok()
+                .map(|text| text.trim().to_string())
+        })
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Ok(text)
+}
+
+#[tauri::command]
+pub fn get_voice_status() -> VoiceStatus {
+    let settings = config::load().voice;
+    let input_device = input_device().map(|device| device_name(&device));
+    let model = find_model(&settings).map(|path| path.display().to_string());
+    let mut problems = Vec::new();
+    if input_device.is_none() {
+        problems.push("Connect a microphone to record audio".to_string());
+    }
+    if model.is_none() {
+        problems.push("Download a whisper ggml model and select it".to_string());
+    }
+    VoiceStatus {
+        input_device,
+        model,
+        recording: CAPTURE.lock().is_ok_and(|c| c.is_some()),
+        problems,
+    }
+}
+
+#[tauri::command]
+pub fn start_voice_capture(app: AppHandle) -> Result<(), Error> {
+    let _ = APP.set(app);
+    let mut capture = CAPTURE.lock().map_err(|e| e.to_string())?;
+    if capture.is_some() {
+        return Err(Error::InvalidInput("Already recording".to_string()));
+    }
+    let (stop, stopped) = mpsc::channel();
+    let (ready, opened) = mpsc::channel();
+    let recorder = thread::spawn(move || record(stopped, ready));
+    match opened.recv_timeout(START_TIMEOUT) {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => return Err(Error::NotFound(e)),
+        Err(_) => {
+            let _ = stop.send(());
+            return Err(Error::Internal(
+                "The microphone didn't start in time".to_string(),
+            ));
+        }
+    }
+    *capture = Some(Capture {
+        stop,
+        recorder,
+        started: Instant::now(),
+    });
+    println!("[Claude PM] Voice capture started");
+    emit_state("recording");
+    Ok(())
+}
+
+/// Stop recording and return the transcribed text
+#[tauri::command]
+pub async fn stop_voice_capture() -> Result<Transcription, Error> {
+    let capture = CAPTURE
+        .lock()
+        .map_err(|e| e.to_string())?
+        .take()
+        .ok_or_else(|| Error::InvalidInput("Not recording".to_string()))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let duration_ms = capture.started.elapsed().as_millis() as u64;
+        let _ = capture.stop.send(());
+        let result = capture
+            .recorder
+            .join()
+            .map_err(|_| Error::Internal("The recorder stopped unexpectedly".to_string()))
+            .and_then(|recording| {
+                emit_state("transcribing");
+                transcribe(recording).map(|text| Transcription { text, duration_ms })
+            });
+        emit_state("idle");
+        result
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Stop recording and throw the audio away
+#[tauri::command]
+pub fn cancel_voice_capture() -> Result<(), Error> {
+    let capture = CAPTURE.lock().map_err(|e| e.to_string())?.take();
+    if let Some(capture) = capture {
+        let _ = capture.stop.send(());
+        let _ = capture.recorder.join();
+        emit_state("idle");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downmix_averages_each_frame() {
+        let mut out = vec![0.5];
+        downmix(&mut out, &[0.25f32, 0.75, -1.0, 0.0], 2);
+        assert_eq!(out, [0.5, 0.5, -0.5]);
+
+        let mut out = Vec::new();
+        downmix(&mut out, &[i16::MAX, i16::MIN], 1);
+        assert!((out[0] - 1.0).abs() < 1e-3 && (out[1] + 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn resamples_to_whisper_rate() {
+        let tone: Vec<f32> = (0..48_000)
+            .map(|i| (i as f32 * 440.0 * std::f32::consts::TAU / 48_000.0).sin() * 0.5)
+            .collect();
+        let out = resample(tone, 48_000).unwrap();
+        assert!(
+            (out.len() as i64 - 16_000).abs() <= 16,
+            "{} samples",
+            out.len()
+        );
+        let peak = out.iter().fold(0f32, |peak, s| peak.max(s.abs()));
+        assert!((peak - 0.5).abs() < 0.05, "peak {}", peak);
+
+        let same = vec![0.1; 100];
+        assert_eq!(resample(same.clone(), WHISPER_RATE).unwrap(), same);
+    }
+}
@@ -0,0 +1,181 @@
+//! Warm restarts of a watch-mode server
+//!
+//! A cold restart stops npm and node and starts both again, so every restart pays for
+//! booting npm and tsx compiling from scratch. When the server runs under `tsx watch`
+//! (the `dev` profile) and nothing it was started with has changed, a restart instead
+//! asks the watcher to rerun by sending Return on its stdin, which keeps npm and tsx
+//! warm. Any change to npm, the server path, the profile or the environment needs a
+//! fresh process, so those restart cold.
+//!
+//! Either way the restart is confirmed through the health endpoint rather than a fixed
+//! pause; a warm rerun that doesn't come back healthy in time falls back to a cold one.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
+use std::process::ChildStdin;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{config, profiles, server_output, server_probe};
+
+/// How long the old process gets to go down after the rerun is requested
+const RERUN_LIMIT: Duration = Duration::from_secs(3);
+/// How long a warm rerun gets to pass its health check
+const WARM_READY_LIMIT: Duration = Duration::from_secs(15);
+/// How long a restarted server gets to pass its health check before we warn
+const READY_LIMIT: Duration = Duration::from_secs(30);
+const POLL: Duration = Duration::from_millis(100);
+
+struct Watcher {
+    /// What the server was started with (see `key`)
+    key: u64,
+    stdin: ChildStdin,
+}
+
+static WATCHER: Mutex<Option<Watcher>> = Mutex::new(None);
+
+/// Fingerprint of everything a running server can't pick up without a fresh process
+pub fn key(
+    npm: &Path,
+    server_path: &Path,
+    profile: &profiles::ServerProfile,
+    env: &BTreeMap<String, String>,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    npm.hash(&mut hasher);
+    server_path.hash(&mut hasher);
+    profile.name.hash(&mut hasher);
+    profile.script.hash(&mut hasher);
+    env.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The npm script runs `tsx watch`, which reruns on Return
+fn is_tsx_watch(server_path: &Path, script: &str) -> bool {
+    fs::read_to_string(server_path.join("package.json"))
+        .ok()
+        .and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok())
+        .and_then(|package| {
+            package["scripts"][script]
+                .as_str()
+                .map(|command| command.contains("tsx watch"))
+        })
+        .unwrap_or(false)
+}
+
+/// Keep a freshly spawned server's stdin if its script can be rerun warm
+pub fn attach(key: u64, stdin: ChildStdin, server_path: &Path, script: &str) {
+    let watcher = is_tsx_watch(server_path, script).then_some(Watcher { key, stdin });
+    if let Ok(mut current) = WATCHER.lock() {
+        *current = watcher;
+    }
+}
+
+/// The server process is gone
+pub fn detach() {
+    if let Ok(mut current) = WATCHER.lock() {
+        *current = None;
+    }
+}
+
+/// The key the server would be started with now
+fn current_key() -> Option<u64> {
+    let profile = profiles::active();
+    let npm = crate::find_npm()?;
+    let server_path = crate::get_server_path()?;
+    let env = crate::server_env(&profile).ok()?;
+    Some(key(&npm, &server_path, &profile, &env))
+}
+
+fn wait_until(limit: Duration, mut done: impl FnMut() -> bool) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < limit {
+        if done() {
+            return true;
+        }
+        thread::sleep(POLL);
+    }
+    false
+}
+
+/// The running server was started under a watcher with what it would be started with now
+pub fn can_rerun() -> bool {
+    let config = config::load();
+    if !config.warm_restart.unwrap_or(true) || config.docker.enabled {
+        return false;
+    }
+    let Some(started) = WATCHER
+        .lock()
+        .ok()
+        .and_then(|current| current.as_ref().map(|watcher| watcher.key))
+    else {
+        return false;
+    };
+    if current_key() != Some(started) {
+        println!("[Claude PM] Server settings changed, restarting cold");
+        return false;
+    }
+    true
+}
+
+/// Rerun the server inside its watcher; false if it needs a cold restart after all
+pub fn rerun() -> bool {
+    {
+        let Ok(mut current) = WATCHER.lock() else {
+            return false;
+        };
+        let Some(watcher) = current.as_mut() else {
+            return false;
+        };
+        server_output::reset("process");
+        if let Err(e) = watcher
+            .stdin
+            .write_all(b"\n")
+            .and_then(|_| watcher.stdin.flush())
+        {
+            eprintln!("[Claude PM] Failed to signal the server watcher: {}", e);
+            *current = None;
+            return false;
+        }
+    }
+    println!("[Claude PM] Rerunning the server in its watcher");
+
+    // The old process answering health checks doesn't count: wait until it has gone
+    // down, or the new one has already printed that it's listening
+    let restarted = || {
+        !server_probe::is_healthy()
+            || server_output::last().is_some_and(|status| status.status == "running")
+    };
+    if !wait_until(RERUN_LIMIT, restarted) {
+        eprintln!("[Claude PM] Server watcher did not rerun");
+        return false;
+    }
+    let ready = wait_until(WARM_READY_LIMIT, || {
+        server_output::last().is_some_and(|status| status.status == "failed")
+            || server_probe::is_healthy()
+    }) && server_probe::is_healthy();
+    if !ready {
+        eprintln!("[Claude PM] Warm restart did not come back healthy, restarting cold");
+    }
+    ready
+}
+
+/// After stopping the server: wait for the port to be released, not a fixed pause
+pub fn wait_for_port_release(port: u16) {
+    if !wait_until(RERUN_LIMIT, || !server_probe::is_listening(port)) {
+        eprintln!(
+            "[Claude PM] Port {} is still in use after stopping the server",
+            port
+        );
+    }
+}
+
+/// After a restart: the server answers its health endpoint
+pub fn wait_until_healthy() -> bool {
+    wait_until(READY_LIMIT, server_probe::is_healthy)
+}
@@ -0,0 +1,405 @@
+//! Watchdog for the server and the app's own subsystems
+//!
+//! Every `interval_secs` the server is checked while it is meant to be up (running, or
+//! crashed) and told apart as dead (the process exited, or nothing listens on its port)
+//! or alive but unresponsive (the process is there but its health endpoint doesn't
+//! answer). The database and the local listeners the agents rely on (proxy, hook
+//! receiver, MCP server) are checked too.
+//!
+//! Each failure type has its own [`Policy`]: restart (the server, or for a subsystem the
+//! whole app), notify only, or ignore. With `escalate_after` set, that many consecutive
+//! failures stop the policy's action and escalate instead: one urgent notification, and
+//! nothing more until the check passes again. Settings live in the config under
+//! `watchdog`.
+//!
+//! Events:
+//! - `watchdog-failure` with a [`Failure`] for every failed check
+//! - `watchdog-escalated` with the [`Failure`] that reached `escalate_after`
+//! - `watchdog-recovered` with the [`FailureKind`] once a failing check passes again
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::lifecycle::{self, State};
+use crate::notifications::{self, NotificationRequest};
+use crate::{config, hook_receiver, mcp, power, proxy, server_probe, store};
+
+const DEFAULT_INTERVAL_SECS: u64 = 30;
+const MIN_INTERVAL_SECS: u64 = 5;
+/// Health checks a live server gets within one check before it counts as unresponsive
+const HEALTH_ATTEMPTS: u32 = 3;
+const HEALTH_RETRY: Duration = Duration::from_secs(1);
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Action {
+    /// Restart the server; for a subsystem, restart the app
+    Restart,
+    Notify,
+    Ignore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Policy {
+    pub action: Action,
+    /// Consecutive failures after which the action stops and the failure is escalated
+    #[serde(default)]
+    pub escalate_after: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchdogSettings {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Seconds between checks (30 when unset)
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    /// The server process exited, or nothing listens on its port
+    #[serde(default = "default_restart")]
+    pub server_dead: Policy,
+    /// The server process is alive but doesn't answer its health endpoint
+    #[serde(default = "default_restart")]
+    pub server_unresponsive: Policy,
+    /// The database or one of the app's local listeners is down
+    #[serde(default = "default_notify")]
+    pub subsystem_down: Policy,
+}
+
+impl Default for WatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: None,
+            server_dead: default_restart(),
+            server_unresponsive: default_restart(),
+            subsystem_down: default_notify(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_restart() -> Policy {
+    Policy {
+        action: Action::Restart,
+        escalate_after: Some(3),
+    }
+}
+
+fn default_notify() -> Policy {
+    Policy {
+        action: Action::Notify,
+        escalate_after: None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FailureKind {
+    ServerDead,
+    ServerUnresponsive,
+    Database,
+    Proxy,
+    HookReceiver,
+    Mcp,
+}
+
+impl FailureKind {
+    fn label(self) -> &'static str {
+        match self {
+            FailureKind::ServerDead => "The server is down",
+            FailureKind::ServerUnresponsive => "The server is not responding",
+            FailureKind::Database => "The database is unavailable",
+            FailureKind::Proxy => "The server proxy is not listening",
+            FailureKind::HookReceiver => "The hook receiver is not listening",
+            FailureKind::Mcp => "The MCP server is not listening",
+        }
+    }
+
+    fn policy(self, settings: &WatchdogSettings) -> &Policy {
+        match self {
+            FailureKind::ServerDead => &settings.server_dead,
+            FailureKind::ServerUnresponsive => &settings.server_unresponsive,
+            _ => &settings.subsystem_down,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Failure {
+    pub kind: FailureKind,
+    pub detail: String,
+    /// Consecutive failed checks, this one included
+    pub count: u32,
+    /// What the watchdog did about it: `restart`, `notify`, `ignore` or `escalate`
+    pub action: &'static str,
+    pub at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchdogStatus {
+    pub enabled: bool,
+    pub last_check: Option<i64>,
+    /// Checks failing right now, with their latest failure
+    pub failing: Vec<Failure>,
+}
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static FAILING: Mutex<BTreeMap<FailureKind, Failure>> = Mutex::new(BTreeMap::new());
+static LAST_CHECK: Mutex<Option<i64>> = Mutex::new(None);
+
+fn interval() -> Duration {
+    let secs = config::load()
+        .watchdog
+        .interval_secs
+        .unwrap_or(DEFAULT_INTERVAL_SECS)
+        .max(MIN_INTERVAL_SECS);
+    Duration::from_secs(secs)
+}
+
+fn is_listening(port: u16) -> bool {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).is_ok()
+}
+
+/// Dead or unresponsive, if the server is meant to be up and isn't
+fn check_server() -> Option<(FailureKind, String)> {
+    if !matches!(lifecycle::state(), State::Running | State::Crashed) {
+        return None;
+    }
+    if let Some((pid, status)) = crate::server_exit_status() {
+        return Some((
+            FailureKind::ServerDead,
+            format!("Server process {} exited: {}", pid, status),
+        ));
+    }
+    let port = crate::server_port();
+    let ours = crate::SERVER_PROCESS
+        .lock()
+        .map(|server| server.is_some())
+        .unwrap_or(false);
+    if !ours && !server_probe::is_listening(port) {
+        return Some((
+            FailureKind::ServerDead,
+            format!("Nothing is listening on port {}", port),
+        ));
+    }
+    let healthy = (0..HEALTH_ATTEMPTS).any(|attempt| {
+        if attempt > 0 {
+            thread::sleep(HEALTH_RETRY);
+        }
+        server_probe::is_healthy()
+    });
+    (!healthy).then(|| {
+        (
+            FailureKind::ServerUnresponsive,
+            format!(
+                "The server on port {} failed {} health checks",
+                port, HEALTH_ATTEMPTS
+            ),
+        )
+    })
+}
+
+fn check_subsystems() -> Vec<(FailureKind, String)> {
+    let mut failures = Vec::new();
+    if let Err(e) = store::with_conn(|conn| conn.query_row("SELECT 1", [], |_| Ok(()))) {
+        failures.push((FailureKind::Database, e));
+    }
+    for (kind, port) in [
+        (FailureKind::Proxy, proxy::PROXY_PORT),
+        (FailureKind::HookReceiver, hook_receiver::HOOK_PORT),
+        (FailureKind::Mcp, mcp::MCP_PORT),
+    ] {
+        if !is_listening(port) {
+            failures.push((kind, format!("Nothing is listening on port {}", port)));
+        }
+    }
+    failures
+}
+
+fn emit(event: &str, payload: impl Serialize + Clone) {
+    if let Some(app) = APP.get() {
+        let _ = app.emit(event, payload);
+    }
+}
+
+fn notify(failure: &Failure, escalated: bool) {
+    let Some(app) = APP.get() else {
+        return;
+    };
+    let body = if escalated {
+        format!(
+            "{}. Failed {} checks in a row; automatic recovery is paused until it passes again.",
+            failure.detail, failure.count
+        )
+    } else {
+        failure.detail.clone()
+    };
+    notifications::notify(
+        app,
+        NotificationRequest {
+            title: failure.kind.label().to_string(),
+            body,
+            key: Some(format!("watchdog:{:?}", failure.kind)),
+            category: Some(if escalated { "failed" } else { "watchdog" }.to_string()),
+            ..Default::default()
+        },
+    );
+}
+
+/// Apply the failure type's policy to one failed check
+fn handle(kind: FailureKind, detail: String, settings: &WatchdogSettings) {
+    let policy = kind.policy(settings);
+    let count = FAILING
+        .lock()
+        .map(|failing| failing.get(&kind).map_or(0, |f| f.count))
+        .unwrap_or(0)
+        + 1;
+    let escalated = policy.escalate_after.is_some_and(|n| count >= n);
+    let action = if escalated {
+        "escalate"
+    } else {
+        match policy.action {
+            Action::Restart => "restart",
+            Action::Notify => "notify",
+            Action::Ignore => "ignore",
+        }
+    };
+    let failure = Failure {
+        kind,
+        detail,
+        count,
+        action,
+        at: store::now_ms(),
+    };
+    eprintln!(
+        "[Claude PM] Watchdog: {} ({}, failure {}, {})",
+        kind.label(),
+        failure.detail,
+        count,
+        action
+    );
+    if let Ok(mut failing) = FAILING.lock() {
+        failing.insert(kind, failure.clone());
+    }
+    emit("watchdog-failure", failure.clone());
+
+    if escalated {
+        // Escalate once per streak
+        if policy.escalate_after == Some(count) {
+            notify(&failure, true);
+            emit("watchdog-escalated", failure);
+        }
+        return;
+    }
+    match policy.action {
+        Action::Restart
+            if kind == FailureKind::ServerDead || kind == FailureKind::ServerUnresponsive =>
+        {
+            if let Err(e) = crate::restart_server() {
+                eprintln!("[Claude PM] Watchdog failed to restart the server: {}", e);
+            }
+        }
+        Action::Restart => {
+            if let Some(app) = APP.get() {
+                println!("[Claude PM] Watchdog restarting the app");
+                app.request_restart();
+            }
+        }
+        Action::Notify if count == 1 => notify(&failure, false),
+        Action::Notify | Action::Ignore => {}
+    }
+}
+
+fn check() {
+    let settings = config::load().watchdog;
+    let failures: Vec<(FailureKind, String)> = check_server()
+        .into_iter()
+        .chain(check_subsystems())
+        .collect();
+    if let Ok(mut last) = LAST_CHECK.lock() {
+        *last = Some(store::now_ms());
+    }
+
+    let recovered: Vec<FailureKind> = FAILING
+        .lock()
+        .map(|mut failing| {
+            let passed: Vec<FailureKind> = failing
+                .keys()
+                .filter(|kind| !failures.iter().any(|(k, _)| k == *kind))
+                .copied()
+                .collect();
+            for kind in &passed {
+                failing.remove(kind);
+            }
+            passed
+        })
+        .unwrap_or_default();
+    for kind in recovered {
+        println!("[Claude PM] Watchdog: {:?} recovered", kind);
+        emit("watchdog-recovered", kind);
+    }
+
+    for (kind, detail) in failures {
+        handle(kind, detail, &settings);
+    }
+}
+
+/// Start checking in the background; safe to call more than once
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let _ = APP.set(app);
+    thread::spawn(|| loop {
+        power::wait(interval());
+        if config::load().watchdog.enabled {
+            check();
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_watchdog_status() -> Result<WatchdogStatus, Error> {
+    let failing = FAILING.lock().map_err(|e| e.to_string())?;
+    Ok(WatchdogStatus {
+        enabled: config::load().watchdog.enabled,
+        last_check: LAST_CHECK.lock().map(|last| *last).unwrap_or(None),
+        failing: failing.values().cloned().collect(),
+    })
+}
+
+#[tauri::command]
+pub fn set_watchdog_settings(settings: WatchdogSettings) -> Result<WatchdogSettings, Error> {
+    let policies = [
+        &settings.server_dead,
+        &settings.server_unresponsive,
+        &settings.subsystem_down,
+    ];
+    if policies
+        .iter()
+        .any(|policy| policy.escalate_after == Some(0))
+    {
+        return Err(Error::InvalidInput(
+            "Escalate after must be at least one failure".to_string(),
+        ));
+    }
+    let config = config::update(|c| c.watchdog = settings)?;
+    Ok(config.watchdog)
+}
@@ -0,0 +1,481 @@
+//! Outbound webhooks for driving external automations
+//!
+//! Endpoints subscribe to event types (`task.completed`, `agent.blocked`,
+//! `agent.failed`, `server.crashed`, or `*`). Every event becomes one row per endpoint in
+//! `webhook_deliveries`, which doubles as the retry queue and the delivery history: a
+//! worker posts pending rows, backing off exponentially on failure, and gives up after
+//! `MAX_ATTEMPTS`.
+//!
+//! Requests carry `X-ClaudePM-Event`, `X-ClaudePM-Delivery`, `X-ClaudePM-Timestamp` and
+//! `X-ClaudePM-Signature: sha256=<hex>`, an HMAC-SHA256 with the endpoint secret over
+//! `<timestamp>.<body>`.
+//!
+//! Events:
+//! - `webhook-delivery` — after each attempt, with the updated delivery
+
+use hmac::{Hmac, Mac};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::Error;
+use crate::integrations::{Event, EventKind};
+use crate::{app_lock, auth, connectivity, secrets, store};
+
+pub const EVENT_TYPES: &[&str] = &[
+    "task.completed",
+    "agent.blocked",
+    "agent.failed",
+    "server.crashed",
+];
+const TICK: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_ATTEMPTS: u32 = 8;
+const BASE_BACKOFF_MS: i64 = 30 * 1000;
+const MAX_BACKOFF_MS: i64 = 60 * 60 * 1000;
+/// Finished deliveries kept for the history view
+const HISTORY_LIMIT: i64 = 1000;
+const BATCH: i64 = 20;
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookEndpoint {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    /// Signing secret; generated when empty
+    #[serde(default)]
+    pub secret: String,
+    pub events: Vec<String>,
+    #[serde(default = "enabled_default")]
+    pub enabled: bool,
+}
+
+fn enabled_default() -> bool {
+    true
+}
+
+impl WebhookEndpoint {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let events: String = row.get("events")?;
+        Ok(Self {
+            id: row.get("id")?,
+            name: row.get("name")?,
+            url: row.get("url")?,
+            secret: row.get("secret")?,
+            events: serde_json::from_str(&events).unwrap_or_default(),
+            enabled: row.get("enabled")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Delivery {
+    pub id: String,
+    pub endpoint_id: String,
+    pub event: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+    pub response_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub completed_at: Option<i64>,
+}
+
+impl Delivery {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let payload: String = row.get("payload")?;
+        Ok(Self {
+            id: row.get("id")?,
+            endpoint_id: row.get("endpoint_id")?,
+            event: row.get("event")?,
+            payload: serde_json::from_str(&payload).unwrap_or(Value::Null),
+            status: row.get("status")?,
+            attempts: row.get("attempts")?,
+            next_attempt_at: row.get("next_attempt_at")?,
+            response_status: row.get("response_status")?,
+            last_error: row.get("last_error")?,
+            created_at: row.get("created_at")?,
+            completed_at: row.get("completed_at")?,
+        })
+    }
+}
+
+pub fn event_type(kind: EventKind) -> &'static str {
+    match kind {
+        EventKind::TaskCompleted => "task.completed",
+        EventKind::AgentBlocked => "agent.blocked",
+        EventKind::AgentFailed => "agent.failed",
+        EventKind::ServerCrashed => "server.crashed",
+    }
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+/// `sha256=<hex>` over `<timestamp>.<body>`
+fn signature(secret: &str, timestamp: i64, body: &str) -> String {
+    let mac = hmac_sha256(
+        secret.as_bytes(),
+        format!("{}.{}", timestamp, body).as_bytes(),
+    );
+    format!("sha256={}", secrets::hex(&mac))
+}
+
+fn insert_delivery(
+    conn: &Connection,
+    endpoint_id: &str,
+    event: &str,
+    payload: &Value,
+) -> rusqlite::Result<String> {
+    let id = store::new_id();
+    let now = store::now_ms();
+    conn.execute(
+        "INSERT INTO webhook_deliveries (id, endpoint_id, event, payload, next_attempt_at, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+        params![id, endpoint_id, event, payload.to_string(), now],
+    )?;
+    Ok(id)
+}
+
+fn get_delivery(conn: &Connection, id: &str) -> rusqlite::Result<Option<Delivery>> {
+    conn.query_row(
+        "SELECT * FROM webhook_deliveries WHERE id = ?1",
+        [id],
+        Delivery::from_row,
+    )
+    .optional()
+}
+
+/// Queue `event` for every enabled endpoint subscribed to it
+pub fn enqueue(event: &Event) {
+    let event_type = event_type(event.kind);
+    let payload = json!({
+        "event": event_type,
+        "title": event.title,
+        "detail": event.detail,
+        "data": event.data,
+        "createdAt": store::now_ms(),
+    });
+    let result = store::with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT * FROM webhook_endpoints WHERE enabled = 1")?;
+        let endpoints = stmt
+            .query_map([], WebhookEndpoint::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for endpoint in endpoints
+            .iter()
+            .filter(|e| e.events.iter().any(|t| t == "*" || t == event_type))
+        {
+            insert_delivery(conn, &endpoint.id, event_type, &payload)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("[Claude PM] Failed to queue webhooks: {}", e);
+    }
+}
+
+/// POST one delivery; returns the response status (if any) and the error
+fn attempt(endpoint: &WebhookEndpoint, delivery: &Delivery) -> (Option<u16>, Option<String>) {
+    let body = delivery.payload.to_string();
+    let timestamp = store::now_ms() / 1000;
+    let result = ureq::post(&endpoint.url)
+        .timeout(REQUEST_TIMEOUT)
+        .set("Content-Type", "application/json")
+        .set("User-Agent", "ClaudePM-Webhooks")
+        .set("X-ClaudePM-Event", &delivery.event)
+        .set("X-ClaudePM-Delivery", &delivery.id)
+        .set("X-ClaudePM-Timestamp", &timestamp.to_string())
+        .set(
+            "X-ClaudePM-Signature",
+            &signature(&endpoint.secret, timestamp, &body),
+        )
+        .send_string(&body);
+    match result {
+        Ok(response) => (Some(response.status()), None),
+        Err(ureq::Error::Status(code, _)) => (Some(code), Some(format!("HTTP {}", code))),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+fn backoff_ms(attempts: u32) -> i64 {
+    (BASE_BACKOFF_MS << attempts.saturating_sub(1).min(16)).min(MAX_BACKOFF_MS)
+}
+
+fn record(
+    delivery: &Delivery,
+    status: Option<u16>,
+    error: Option<String>,
+) -> Result<Delivery, String> {
+    let now = store::now_ms();
+    let attempts = delivery.attempts + 1;
+    let (state, next, completed) = match error {
+        None => ("delivered", delivery.next_attempt_at, Some(now)),
+        Some(_) if attempts >= MAX_ATTEMPTS => ("failed", delivery.next_attempt_at, Some(now)),
+        Some(_) => ("pending", now + backoff_ms(attempts), None),
+    };
+    store::with_conn(|conn| {
+        conn.execute(
+            "UPDATE webhook_deliveries SET status = ?2, attempts = ?3, next_attempt_at = ?4, response_status = ?5, last_error = ?6, completed_at = ?7 WHERE id = ?1",
+            params![delivery.id, state, attempts, next, status, error, completed],
+        )?;
+        get_delivery(conn, &delivery.id).map(|d| d.expect("just updated"))
+    })
+}
+
+fn due() -> Result<Vec<(WebhookEndpoint, Delivery)>, String> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT d.* FROM webhook_deliveries d WHERE d.status = 'pending' AND d.next_attempt_at <= ?1 ORDER BY d.next_attempt_at LIMIT ?2",
+        )?;
+        let deliveries = stmt
+            .query_map(params![store::now_ms(), BATCH], Delivery::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut due = Vec::new();
+        for delivery in deliveries {
+            let endpoint = conn
+                .query_row(
+                    "SELECT * FROM webhook_endpoints WHERE id = ?1",
+                    [&delivery.endpoint_id],
+                    WebhookEndpoint::from_row,
+                )
+                .optional()?;
+            if let Some(endpoint) = endpoint {
+                due.push((endpoint, delivery));
+            }
+        }
+        Ok(due)
+    })
+}
+
+fn prune() -> Result<(), String> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "DELETE FROM webhook_deliveries WHERE status != 'pending' AND id NOT IN (SELECT id FROM webhook_deliveries WHERE status != 'pending' ORDER BY created_at DESC LIMIT ?1)",
+            [HISTORY_LIMIT],
+        )
+        .map(|_| ())
+    })
+}
+
+fn deliver(app: &AppHandle, endpoint: &WebhookEndpoint, delivery: &Delivery) -> Option<Delivery> {
+    let (status, error) = attempt(endpoint, delivery);
+    if let Some(ref e) = error {
+        eprintln!(
+            "[Claude PM] Webhook {} delivery {} failed: {}",
+            endpoint.name, delivery.id, e
+        );
+    }
+    match record(delivery, status, error) {
+        Ok(updated) => {
+            let _ = app.emit("webhook-delivery", updated.clone());
+            Some(updated)
+        }
+        Err(e) => {
+            eprintln!("[Claude PM] Failed to record webhook delivery: {}", e);
+            None
+        }
+    }
+}
+
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    thread::spawn(move || loop {
+        // Failures while offline would only burn attempts
+        if connectivity::is_online() {
+            for (endpoint, delivery) in due().unwrap_or_default() {
+                deliver(&app, &endpoint, &delivery);
+            }
+            let _ = prune();
+        }
+        thread::sleep(TICK);
+    });
+}
+
+fn validate(endpoint: &WebhookEndpoint) -> Result<(), Error> {
+    if endpoint.name.trim().is_empty() {
+        return Err(Error::InvalidInput("Webhook name is required".to_string()));
+    }
+    if !endpoint.url.starts_with("https://") && !endpoint.url.starts_with("http://") {
+        return Err(Error::InvalidInput(format!(
+            "Expected an http(s) URL, got: {}",
+            endpoint.url
+        )));
+    }
+    if endpoint.events.is_empty() {
+        return Err(Error::InvalidInput(
+            "Subscribe to at least one event".to_string(),
+        ));
+    }
+    match endpoint
+        .events
+        .iter()
+        .find(|t| *t != "*" && !EVENT_TYPES.contains(&t.as_str()))
+    {
+        Some(unknown) => Err(Error::InvalidInput(format!(
+            "Unknown event type: {}",
+            unknown
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn list_webhooks() -> Result<Vec<WebhookEndpoint>, Error> {
+    app_lock::ensure_unlocked()?;
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare("SELECT * FROM webhook_endpoints ORDER BY created_at")?;
+        let rows = stmt.query_map([], WebhookEndpoint::from_row)?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
+
+/// Create an endpoint (empty `id`) or replace an existing one
+#[tauri::command]
+pub fn upsert_webhook(mut endpoint: WebhookEndpoint) -> Result<WebhookEndpoint, Error> {
+    app_lock::ensure_unlocked()?;
+    validate(&endpoint)?;
+    if endpoint.id.is_empty() {
+        endpoint.id = store::new_id();
+    }
+    if endpoint.secret.is_empty() {
+        endpoint.secret = auth::random_hex();
+    }
+    let events = serde_json::to_string(&endpoint.events).map_err(|e| e.to_string())?;
+    store::with_conn(|conn| {
+        conn.execute(
+            "INSERT INTO webhook_endpoints (id, name, url, secret, events, enabled, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT(id) DO UPDATE SET name = ?2, url = ?3, secret = ?4, events = ?5, enabled = ?6",
+            params![
+                endpoint.id,
+                endpoint.name,
+                endpoint.url,
+                endpoint.secret,
+                events,
+                endpoint.enabled,
+                store::now_ms()
+            ],
+        )
+        .map(|_| ())
+    })?;
+    Ok(endpoint)
+}
+
+/// Remove an endpoint along with its queue and history
+#[tauri::command]
+pub fn delete_webhook(id: String) -> Result<(), Error> {
+    store::with_conn(|conn| {
+        conn.execute("DELETE FROM webhook_endpoints WHERE id = ?1", [id])
+            .map(|_| ())
+    })
+    .map_err(Error::from)
+}
+
+/// Delivery history, newest first, optionally for one endpoint or status
+#[tauri::command]
+pub fn list_webhook_deliveries(
+    endpoint_id: Option<String>,
+    status: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<Delivery>, Error> {
+    store::with_conn(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT * FROM webhook_deliveries WHERE (?1 IS NULL OR endpoint_id = ?1) AND (?2 IS NULL OR status = ?2) ORDER BY created_at DESC LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(
+            params![endpoint_id, status, limit.unwrap_or(100)],
+            Delivery::from_row,
+        )?;
+        rows.collect()
+    })
+    .map_err(Error::from)
+}
+
+/// Queue a delivery again right away, e.g. after fixing the receiving end
+#[tauri::command]
+pub fn redeliver_webhook(delivery_id: String) -> Result<Delivery, Error> {
+    store::with_conn(|conn| {
+        conn.execute(
+            "UPDATE webhook_deliveries SET status = 'pending', attempts = 0, next_attempt_at = ?2, completed_at = NULL WHERE id = ?1",
+            params![delivery_id, store::now_ms()],
+        )?;
+        get_delivery(conn, &delivery_id)
+    })?
+    .ok_or_else(|| Error::NotFound(format!("Delivery not found: {}", delivery_id)))
+}
+
+/// Send a `ping` event to one endpoint now and return the recorded delivery
+#[tauri::command]
+pub async fn ping_webhook(app: AppHandle, endpoint_id: String) -> Result<Delivery, Error> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (endpoint, delivery) = store::with_conn(|conn| {
+            let endpoint = conn
+                .query_row(
+                    "SELECT * FROM webhook_endpoints WHERE id = ?1",
+                    [&endpoint_id],
+                    WebhookEndpoint::from_row,
+                )
+                .optional()?;
+            let Some(endpoint) = endpoint else {
+                return Ok(None);
+            };
+            let payload = json!({ "event": "ping", "createdAt": store::now_ms() });
+            let id = insert_delivery(conn, &endpoint.id, "ping", &payload)?;
+            Ok(get_delivery(conn, &id)?.map(|d| (endpoint, d)))
+        })?
+        .ok_or_else(|| Error::NotFound(format!("Webhook not found: {}", endpoint_id)))?;
+        deliver(&app, &endpoint, &delivery)
+            .ok_or_else(|| Error::Internal("Failed to record webhook delivery".to_string()))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::secrets::hex;
+
+    // RFC 4231, test cases 1, 2 and 6 (a key longer than the block size)
+    #[test]
+    fn hmac_matches_rfc_4231() {
+        assert_eq!(
+            hex(&hmac_sha256(&[0x0b; 20], b"Hi There")),
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        let expected = format!("sha256={}", hex(&hmac_sha256(b"secret", b"1700000000.{}")));
+        assert_eq!(signature("secret", 1_700_000_000, "{}"), expected);
+    }
+}
@@ -0,0 +1,202 @@
+//! Persisting and restoring window geometry across launches
+//!
+//! Geometry is saved in physical pixels together with the monitor name. On restore,
+//! if that monitor is gone (or the saved rect no longer overlaps it), the window keeps
+//! its size, clamped to the current monitor, and is centered instead.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, Runtime, Window};
+
+use crate::config;
+use crate::error::Error;
+use crate::windows::{main_window, MAIN_WINDOW};
+
+pub const STATE_FILE: &str = "window-state.json";
+const DEFAULT_SIZE: (f64, f64) = (1200.0, 800.0);
+/// Quiet time after the last move/resize before geometry is written
+const SAVE_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// When the pending debounced save is due; set while a saver thread is waiting
+static SAVE_AT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Only long-lived windows are persisted; per-session windows are not
+const PERSISTED_WINDOWS: &[&str] = &[MAIN_WINDOW];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WindowState {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    monitor: Option<String>,
+    maximized: bool,
+}
+
+fn state_path() -> Option<PathBuf> {
+    config::config_dir().map(|dir| dir.join(STATE_FILE))
+}
+
+fn load_all() -> BTreeMap<String, WindowState> {
+    state_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_all(states: &BTreeMap<String, WindowState>) -> Result<(), String> {
+    let path = state_path().ok_or("Could not determine config directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let contents = serde_json::to_string_pretty(states).map_err(|e| e.to_string())?;
+    fs::write(path, contents).map_err(|e| format!("Failed to save window state: {}", e))
+}
+
+/// Record the window's geometry
+pub fn save<R: Runtime>(window: &Window<R>) {
+    if !PERSISTED_WINDOWS.contains(&window.label()) {
+        return;
+    }
+
+    let mut states = load_all();
+    let maximized = window.is_maximized().unwrap_or(false);
+    let previous = states.get(window.label()).cloned();
+
+    // A maximized window's geometry is the monitor's; keep the last normal geometry instead
+    let state = match (maximized, previous) {
+        (true, Some(previous)) => WindowState {
+            maximized: true,
+            ..previous
+        },
+        _ => {
+            let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+                return;
+            };
+            WindowState {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                monitor: window
+                    .current_monitor()
+                    .ok()
+                    .flatten()
+                    .and_then(|m| m.name().cloned()),
+                maximized,
+            }
+        }
+    };
+
+    states.insert(window.label().to_string(), state);
+    if let Err(e) = save_all(&states) {
+        eprintln!("[Claude PM] {}", e);
+    }
+}
+
+/// Save once the window has stopped moving or resizing for a moment, so a crash or a
+/// forced quit doesn't lose the geometry
+pub fn save_soon<R: Runtime>(window: &Window<R>) {
+    if !PERSISTED_WINDOWS.contains(&window.label()) {
+        return;
+    }
+    let Ok(mut save_at) = SAVE_AT.lock() else {
+        return;
+    };
+    let waiting = save_at.is_some();
+    *save_at = Some(Instant::now() + SAVE_DEBOUNCE);
+    if waiting {
+        return;
+    }
+    let window = window.clone();
+    thread::spawn(move || loop {
+        let due = SAVE_AT.lock().ok().and_then(|save_at| *save_at);
+        match due {
+            Some(due) if due > Instant::now() => thread::sleep(due - Instant::now()),
+            _ => {
+                if let Ok(mut save_at) = SAVE_AT.lock() {
+                    *save_at = None;
+                }
+                save(&window);
+                break;
+            }
+        }
+    });
+}
+
+/// Save every persisted window that is open; for quitting from the tray or menu, which
+/// exits without a close request
+pub fn save_open<R: Runtime>(app: &AppHandle<R>) {
+    for label in PERSISTED_WINDOWS {
+        if let Some(window) = app.get_webview_window(label) {
+            save(&window.as_ref().window());
+        }
+    }
+}
+
+fn overlaps(state: &WindowState, monitor: &Monitor) -> bool {
+    let pos = monitor.position();
+    let size = monitor.size();
+    let right = pos.x + size.width as i32;
+    let bottom = pos.y + size.height as i32;
+    state.x < right
+        && state.x + state.width as i32 > pos.x
+        && state.y < bottom
+        && state.y + state.height as i32 > pos.y
+}
+
+/// Restore saved geometry for a window, coping with monitors that have been disconnected
+pub fn restore<R: Runtime>(window: &Window<R>) {
+    let Some(state) = load_all().remove(window.label()) else {
+        return;
+    };
+
+    let monitors = window.available_monitors().unwrap_or_default();
+    let saved_monitor = monitors.iter().find(|m| {
+        state.monitor.is_some() && m.name() == state.monitor.as_ref() && overlaps(&state, m)
+    });
+
+    match saved_monitor {
+        Some(_) => {
+            let _ = window.set_size(PhysicalSize::new(state.width, state.height));
+            let _ = window.set_position(PhysicalPosition::new(state.x, state.y));
+        }
+        None => {
+            let target = window.current_monitor().ok().flatten();
+            let (width, height) = match target {
+                Some(ref monitor) => (
+                    state.width.min(monitor.size().width),
+                    state.height.min(monitor.size().height),
+                ),
+                None => (state.width, state.height),
+            };
+            let _ = window.set_size(PhysicalSize::new(width, height));
+            let _ = window.center();
+        }
+    }
+
+    if state.maximized {
+        let _ = window.maximize();
+    }
+}
+
+/// Forget saved geometry and put the main window back to its default size, centered
+#[tauri::command]
+pub fn reset_window_state(app: AppHandle) -> Result<(), Error> {
+    let mut states = load_all();
+    states.remove(MAIN_WINDOW);
+    save_all(&states)?;
+
+    let window = main_window(&app)?;
+    let _ = window.unmaximize();
+    window
+        .set_size(tauri::LogicalSize::new(DEFAULT_SIZE.0, DEFAULT_SIZE.1))
+        .map_err(|e| e.to_string())?;
+    window.center().map_err(|e| e.to_string().into())
+}
@@ -0,0 +1,27 @@
+//! Window lookup and placement helpers shared by the window-managing modules
+
+use tauri::{AppHandle, Manager, Monitor, WebviewWindow};
+
+pub const MAIN_WINDOW: &str = "main";
+
+pub fn main_window(app: &AppHandle) -> Result<WebviewWindow, String> {
+    app.get_webview_window(MAIN_WINDOW)
+        .ok_or_else(|| "Main window not found".to_string())
+}
+
+/// Bring the main window to the front, restoring it if minimized or hidden
+pub fn focus_main_window(app: &AppHandle) {
+    if let Ok(window) = main_window(app) {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// The monitor under the mouse cursor, falling back to the primary monitor
+pub fn active_monitor(app: &AppHandle) -> Option<Monitor> {
+    app.cursor_position()
+        .ok()
+        .and_then(|pos| app.monitor_from_point(pos.x, pos.y).ok().flatten())
+        .or_else(|| app.primary_monitor().ok().flatten())
+}
@@ -0,0 +1,223 @@
+//! Working hours: what the app holds back outside them
+//!
+//! While enabled, outside the configured days and hours non-critical notifications are
+//! dropped (critical is the same per-category setting the digest uses, see `digest`),
+//! schedules (see `scheduler`) don't fire unless marked `after_hours` and instead catch
+//! up once when working hours begin, per their `catch_up`, and events published on the
+//! bus (see `event_bus`) carry `afterHours`. A day whose end is before its start runs
+//! past midnight. An override forces working or after hours, until a time or until it
+//! is cleared; it isn't kept across restarts.
+//!
+//! Events:
+//! - `working-hours-changed` with the [`WorkingHoursState`] when the policy flips or
+//!   its settings or override change
+
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveTime, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::dnd::Gate;
+use crate::error::Error;
+use crate::notifications::NotificationRequest;
+use crate::{config, digest, store};
+
+const DEFAULT_START: &str = "09:00";
+const DEFAULT_END: &str = "18:00";
+const DEFAULT_DAYS: &[Weekday] = &[
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+const TICK: Duration = Duration::from_secs(30);
+/// How far ahead to look for the next change; a week covers every pattern of days
+const LOOKAHEAD_MINUTES: i64 = 8 * 24 * 60;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static STARTED: AtomicBool = AtomicBool::new(false);
+static OVERRIDE: Mutex<Option<PolicyOverride>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WorkingHoursSettings {
+    pub enabled: bool,
+    /// Working days, e.g. `mon`; Monday to Friday when empty
+    pub days: Vec<String>,
+    /// Start of the working day as `HH:MM` (09:00 when unset)
+    pub start: Option<String>,
+    /// End of the working day as `HH:MM` (18:00 when unset)
+    pub end: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyOverride {
+    pub after_hours: bool,
+    /// Unix millis it lapses at; until cleared when unset
+    pub until: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingHoursState {
+    pub settings: WorkingHoursSettings,
+    pub after_hours: bool,
+    #[serde(rename = "override")]
+    pub policy_override: Option<PolicyOverride>,
+    /// When the schedule next flips between working and after hours, ignoring overrides
+    pub next_change_at: Option<i64>,
+}
+
+fn parse_time(value: Option<&str>, default: &str) -> Result<NaiveTime, String> {
+    let value = value.unwrap_or(default);
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("Invalid time {:?}; use HH:MM", value))
+}
+
+fn parse_days(days: &[String]) -> Result<Vec<Weekday>, String> {
+    if days.is_empty() {
+        return Ok(DEFAULT_DAYS.to_vec());
+    }
+    days.iter()
+        .map(|day| Weekday::from_str(day.trim()).map_err(|_| format!("Invalid day {:?}", day)))
+        .collect()
+}
+
+/// Whether `at` falls inside the working hours `settings` describe
+fn is_working(settings: &WorkingHoursSettings, at: DateTime<Local>) -> Result<bool, String> {
+    let start = parse_time(settings.start.as_deref(), DEFAULT_START)?;
+    let end = parse_time(settings.end.as_deref(), DEFAULT_END)?;
+    let days = parse_days(&settings.days)?;
+    let time = at.time();
+    let today = days.contains(&at.weekday());
+    Ok(if start < end {
+        today && time >= start && time < end
+    } else {
+        // Past midnight: the evening of a working day, or the early hours after one
+        (today && time >= start) || (days.contains(&at.weekday().pred()) && time < end)
+    })
+}
+
+fn current_override() -> Option<PolicyOverride> {
+    let mut current = OVERRIDE.lock().ok()?;
+    if current
+        .as_ref()
+        .is_some_and(|o| o.until.is_some_and(|until| until <= store::now_ms()))
+    {
+        *current = None;
+    }
+    current.clone()
+}
+
+/// Outside working hours right now, taking an override into account; always false while
+/// working hours are off
+pub fn is_after_hours() -> bool {
+    if let Some(policy_override) = current_override() {
+        return policy_override.after_hours;
+    }
+    let settings = config::load().working_hours;
+    settings.enabled && is_working(&settings, Local::now()).is_ok_and(|working| !working)
+}
+
+fn next_change(settings: &WorkingHoursSettings) -> Option<i64> {
+    if !settings.enabled {
+        return None;
+    }
+    let now = Local::now();
+    let working = is_working(settings, now).ok()?;
+    let minute = now.with_second(0)?.with_nanosecond(0)?;
+    (1..=LOOKAHEAD_MINUTES)
+        .map(|m| minute + ChronoDuration::minutes(m))
+        .find(|at| is_working(settings, *at).is_ok_and(|w| w != working))
+        .map(|at| at.timestamp_millis())
+}
+
+fn state() -> WorkingHoursState {
+    let settings = config::load().working_hours;
+    WorkingHoursState {
+        after_hours: is_after_hours(),
+        policy_override: current_override(),
+        next_change_at: next_change(&settings),
+        settings,
+    }
+}
+
+fn emit_state() {
+    if let Some(app) = APP.get() {
+        let _ = app.emit("working-hours-changed", state());
+    }
+}
+
+/// Drop non-critical notifications after hours
+pub fn gate(request: &NotificationRequest) -> Gate {
+    if is_after_hours() && !digest::is_critical_category(request.category.as_deref()) {
+        Gate::Held
+    } else {
+        Gate::Deliver
+    }
+}
+
+/// Watch for the policy flipping so the frontend doesn't have to
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let _ = APP.set(app);
+    thread::spawn(|| {
+        let mut after_hours = is_after_hours();
+        loop {
+            thread::sleep(TICK);
+            let now = is_after_hours();
+            if now != after_hours {
+                after_hours = now;
+                println!(
+                    "[Claude PM] {}",
+                    if now {
+                        "Working hours ended"
+                    } else {
+                        "Working hours started"
+                    }
+                );
+                emit_state();
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_working_hours() -> WorkingHoursState {
+    state()
+}
+
+#[tauri::command]
+pub fn set_working_hours(settings: WorkingHoursSettings) -> Result<WorkingHoursState, Error> {
+    is_working(&settings, Local::now()).map_err(Error::InvalidInput)?;
+    config::update(|c| c.working_hours = settings)?;
+    emit_state();
+    Ok(state())
+}
+
+/// Force working (`after_hours: false`) or after hours until `until`, or until cleared;
+/// `None` clears the override
+#[tauri::command]
+pub fn override_working_hours(
+    policy_override: Option<PolicyOverride>,
+) -> Result<WorkingHoursState, Error> {
+    if policy_override
+        .as_ref()
+        .is_some_and(|o| o.until.is_some_and(|until| until <= store::now_ms()))
+    {
+        return Err(Error::InvalidInput(
+            "The override must last until a time in the future".to_string(),
+        ));
+    }
+    *OVERRIDE.lock().map_err(|e| e.to_string())? = policy_override;
+    emit_state();
+    Ok(state())
+}
@@ -0,0 +1,204 @@
+//! Relay of the server's WebSocket events into Tauri events
+//!
+//! The bridge keeps one connection to the server, reconnecting with backoff across
+//! restarts, and re-sends session subscriptions after each reconnect. Every message is
+//! numbered and kept in a replay buffer so the UI can catch up on what it missed.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, VecDeque};
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tungstenite::{Message, WebSocket};
+
+use crate::error::Error;
+use crate::{auth, server_port, server_probe};
+
+const REPLAY_CAPACITY: usize = 1000;
+const MAX_BACKOFF: Duration = Duration::from_secs(10);
+/// Read timeout so the loop can flush outgoing messages between reads
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeEvent {
+    pub seq: u64,
+    pub message: Value,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BridgeStatus {
+    connected: bool,
+}
+
+struct Bridge {
+    next_seq: u64,
+    replay: VecDeque<BridgeEvent>,
+    subscriptions: BTreeSet<String>,
+    outbox: Vec<Value>,
+}
+
+static BRIDGE: Mutex<Bridge> = Mutex::new(Bridge {
+    next_seq: 1,
+    replay: VecDeque::new(),
+    subscriptions: BTreeSet::new(),
+    outbox: Vec::new(),
+});
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+fn subscribe_message(session_id: &str) -> Value {
+    json!({ "type": "session:subscribe", "payload": { "sessionId": session_id } })
+}
+
+fn connect() -> Result<WebSocket<TcpStream>, String> {
+    let port = server_port();
+    let stream = server_probe::connect(port).ok_or("The server isn't accepting connections")?;
+    let url = format!(
+        "{}/?desktopToken={}",
+        server_probe::ws_url(port),
+        auth::token()
+    );
+    let (socket, _) = tungstenite::client(url, stream).map_err(|e| e.to_string())?;
+    socket
+        .get_ref()
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+fn record(app: &AppHandle, message: Value) {
+    let event = {
+        let Ok(mut bridge) = BRIDGE.lock() else {
+            return;
+        };
+        let event = BridgeEvent {
+            seq: bridge.next_seq,
+            message,
+        };
+        bridge.next_seq += 1;
+        if bridge.replay.len() == REPLAY_CAPACITY {
+            bridge.replay.pop_front();
+        }
+        bridge.replay.push_back(event.clone());
+        event
+    };
+    let _ = app.emit("server-event", event);
+}
+
+fn set_connected(app: &AppHandle, connected: bool) {
+    if CONNECTED.swap(connected, Ordering::SeqCst) != connected {
+        let _ = app.emit("server-bridge-status", BridgeStatus { connected });
+    }
+}
+
+/// Pump one connection until it drops
+fn run_connection(app: &AppHandle, mut socket: WebSocket<TcpStream>) {
+    // Restore subscriptions from before the reconnect
+    if let Ok(mut bridge) = BRIDGE.lock() {
+        let resubscribe: Vec<Value> = bridge
+            .subscriptions
+            .iter()
+            .map(|id| subscribe_message(id))
+            .collect();
+        bridge.outbox.splice(0..0, resubscribe);
+    }
+
+    loop {
+        let outgoing: Vec<Value> = BRIDGE
+            .lock()
+            .map(|mut bridge| std::mem::take(&mut bridge.outbox))
+            .unwrap_or_default();
+        for message in outgoing {
+            if socket.send(Message::text(message.to_string())).is_err() {
+                return;
+            }
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str(text.as_str()) {
+                Ok(message) => record(app, message),
+                Err(e) => eprintln!("[Claude PM] Ignoring malformed server event: {}", e),
+            },
+            Ok(Message::Close(_)) => return,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(_) => return,
+        }
+    }
+}
+
+/// Start the bridge thread; safe to call more than once
+pub fn start(app: AppHandle) {
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    thread::spawn(move || {
+        let mut backoff = Duration::from_millis(500);
+        loop {
+            match connect() {
+                Ok(socket) => {
+                    println!("[Claude PM] WebSocket bridge connected");
+                    set_connected(&app, true);
+                    backoff = Duration::from_millis(500);
+                    run_connection(&app, socket);
+                    set_connected(&app, false);
+                    println!("[Claude PM] WebSocket bridge disconnected, reconnecting");
+                }
+                Err(_) => backoff = (backoff * 2).min(MAX_BACKOFF),
+            }
+            thread::sleep(backoff);
+        }
+    });
+}
+
+/// Events with a sequence number greater than `since`, oldest first
+#[tauri::command]
+pub fn get_server_events_since(since: u64) -> Vec<BridgeEvent> {
+    BRIDGE
+        .lock()
+        .map(|bridge| {
+            bridge
+                .replay
+                .iter()
+                .filter(|event| event.seq > since)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn get_server_bridge_status() -> bool {
+    CONNECTED.load(Ordering::SeqCst)
+}
+
+/// Subscribe to a session's output; kept across reconnects until unsubscribed
+#[tauri::command]
+pub fn bridge_subscribe(session_id: String) -> Result<(), Error> {
+    let mut bridge = BRIDGE.lock().map_err(|e| e.to_string())?;
+    if bridge.subscriptions.insert(session_id.clone()) {
+        bridge.outbox.push(subscribe_message(&session_id));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn bridge_unsubscribe(session_id: String) -> Result<(), Error> {
+    let mut bridge = BRIDGE.lock().map_err(|e| e.to_string())?;
+    if bridge.subscriptions.remove(&session_id) {
+        bridge.outbox.push(json!({
+            "type": "session:unsubscribe",
+            "payload": { "sessionId": session_id }
+        }));
+    }
+    Ok(())
+}
@@ -165,7 +165,7 @@ export function SettingsPanel({ onClose }: SettingsPanelProps) {
                   type="text"
                   value={apiUrl}
                   onChange={handleApiUrlChange}
-                  placeholder="http://localhost:4847"
+                  placeholder="http://localhost:4850"
                 />
                 <button
                   onClick={handleApiUrlSave}
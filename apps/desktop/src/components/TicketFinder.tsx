@@ -7,6 +7,7 @@ import { useState, useEffect, useRef, useMemo } from 'react';
 import { useNavigate } from 'react-router-dom';
 import { useProjects } from '../hooks/useProjects';
 import { cn } from '../lib/utils';
+import { getTickets } from '../services/api';
 import { Search, FileText, ArrowRight, Folder } from 'lucide-react';
 import type { Ticket } from '../types/api';
 
@@ -21,7 +22,6 @@ function useAllTickets() {
   const projects = projectsData?.data ?? [];
 
   // We need to fetch tickets for each project
-  // For simplicity, we'll use the API directly
   const [tickets, setTickets] = useState<(Ticket & { projectName: string; projectId: string })[]>([]);
   const [isLoading, setIsLoading] = useState(false);
 
@@ -36,17 +36,14 @@ function useAllTickets() {
         // Fetch tickets from each project
         for (const project of projects) {
           try {
-            const baseUrl = localStorage.getItem('claude-pm-server-url') || 'http://localhost:4847';
-            const response = await fetch(`${baseUrl}/api/projects/${project.id}/tickets`);
-            if (response.ok) {
-              const data = await response.json();
-              const projectTickets = (data.data || []).map((t: Ticket) => ({
+            const projectTickets = await getTickets(project.id);
+            allTickets.push(
+              ...projectTickets.map((t) => ({
                 ...t,
                 projectName: project.name,
                 projectId: project.id,
-              }));
-              allTickets.push(...projectTickets);
-            }
+              }))
+            );
           } catch {
             // Skip failed project
           }
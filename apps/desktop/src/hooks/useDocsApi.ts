@@ -34,16 +34,12 @@ export interface DocContentResponse {
 
 async function getDocsTree(projectId: string): Promise<DocTreeResponse> {
   const baseUrl = await api.getApiUrl();
-  const apiKey = await api.getApiKey();
 
   const headers: Record<string, string> = {
     'Content-Type': 'application/json',
+    ...(await api.getAuthHeaders()),
   };
 
-  if (apiKey) {
-    headers['X-API-Key'] = apiKey;
-  }
-
   const response = await fetch(`${baseUrl}/api/projects/${projectId}/docs`, {
     headers,
   });
@@ -60,16 +56,12 @@ async function getDocsTree(projectId: string): Promise<DocTreeResponse> {
 
 async function getDocContent(projectId: string, docPath: string): Promise<DocContentResponse> {
   const baseUrl = await api.getApiUrl();
-  const apiKey = await api.getApiKey();
 
   const headers: Record<string, string> = {
     'Content-Type': 'application/json',
+    ...(await api.getAuthHeaders()),
   };
 
-  if (apiKey) {
-    headers['X-API-Key'] = apiKey;
-  }
-
   const response = await fetch(`${baseUrl}/api/projects/${projectId}/docs/${docPath}`, {
     headers,
   });
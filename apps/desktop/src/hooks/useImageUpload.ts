@@ -4,7 +4,7 @@
  */
 
 import { useMutation, useQueryClient } from '@tanstack/react-query';
-import { getApiUrl, getApiKey } from '../services/api';
+import { getApiUrl, getAuthHeaders } from '../services/api';
 
 interface UploadImageParams {
   ticketId: string;
@@ -24,12 +24,8 @@ async function uploadImage({ ticketId, imageBlob, filename }: UploadImageParams)
   formData.append('image', imageBlob, filename || 'pasted-image.png');
 
   const baseUrl = await getApiUrl();
-  const apiKey = await getApiKey();
 
-  const headers: Record<string, string> = {};
-  if (apiKey) {
-    headers['X-API-Key'] = apiKey;
-  }
+  const headers = await getAuthHeaders();
 
   const response = await fetch(`${baseUrl}/api/tickets/${ticketId}/images`, {
     method: 'POST',
@@ -5,7 +5,7 @@
  */
 
 import { useEffect, useCallback, useState, useSyncExternalStore, useRef } from 'react';
-import { getApiUrl, getApiKey } from '../services/api';
+import { getApiUrl, getApiKey, getDesktopToken } from '../services/api';
 import { useSessionStore } from '../stores/sessionStore';
 import { toast } from './use-toast';
 import type {
@@ -100,9 +100,16 @@ class WebSocketManager {
         this.cachedApiKey = await getApiKey() || '';
       }
 
-      const url = this.cachedApiKey
-        ? `${this.cachedUrl}?apiKey=${this.cachedApiKey}`
-        : this.cachedUrl;
+      const params = new URLSearchParams();
+      if (this.cachedApiKey) {
+        params.set('apiKey', this.cachedApiKey);
+      }
+      const desktopToken = await getDesktopToken();
+      if (desktopToken) {
+        params.set('desktopToken', desktopToken);
+      }
+      const query = params.toString();
+      const url = query ? `${this.cachedUrl}?${query}` : this.cachedUrl;
 
       console.log('[WebSocketManager] Creating new WebSocket connection');
       const ws = new WebSocket(url);
@@ -149,7 +149,7 @@ export function Settings() {
                   type="text"
                   value={apiUrl}
                   onChange={handleApiUrlChange}
-                  placeholder="http://localhost:4847"
+                  placeholder="http://localhost:4850"
                   className="flex-1 px-3 py-2 bg-surface-tertiary border border-line rounded-md text-content-primary text-sm outline-none transition-colors focus:border-indigo-500 placeholder:text-content-muted"
                 />
                 <button
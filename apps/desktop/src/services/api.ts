@@ -3,6 +3,7 @@
  * Handles all API calls to the Claude PM backend
  */
 
+import { invoke } from '@tauri-apps/api/core';
 import { load, type Store } from '@tauri-apps/plugin-store';
 import type {
   Session,
@@ -26,7 +27,8 @@ import type {
   NotificationCountResponse,
 } from '../types/api';
 
-const DEFAULT_API_URL = 'http://localhost:4847';
+// Desktop reverse proxy; forwards to whichever port the server is on
+const DEFAULT_API_URL = 'http://localhost:4850';
 const STORE_FILE = '.settings.dat';
 
 let storePromise: Promise<Store> | null = null;
@@ -44,6 +46,39 @@ export async function getApiKey(): Promise<string | null> {
   return key ?? null;
 }
 
+let desktopTokenPromise: Promise<string | null> | null = null;
+
+/**
+ * Per-launch token the local server requires on localhost requests.
+ * Null outside Tauri (e.g. running the frontend in a browser) or while the app is locked;
+ * a failed lookup is retried on the next request.
+ */
+export async function getDesktopToken(): Promise<string | null> {
+  if (!desktopTokenPromise) {
+    desktopTokenPromise = invoke<string>('get_auth_token').catch(() => {
+      desktopTokenPromise = null;
+      return null;
+    });
+  }
+  return desktopTokenPromise;
+}
+
+/**
+ * Auth headers for server requests
+ */
+export async function getAuthHeaders(): Promise<Record<string, string>> {
+  const headers: Record<string, string> = {};
+  const apiKey = await getApiKey();
+  if (apiKey) {
+    headers['X-API-Key'] = apiKey;
+  }
+  const desktopToken = await getDesktopToken();
+  if (desktopToken) {
+    headers['X-Desktop-Token'] = desktopToken;
+  }
+  return headers;
+}
+
 export async function setApiKey(key: string): Promise<void> {
   const store = await getStore();
   await store.set('apiKey', key);
@@ -84,16 +119,12 @@ export class ApiError extends Error {
 
 async function request<T>(endpoint: string, options: RequestInit = {}): Promise<T> {
   const baseUrl = await getApiUrl();
-  const apiKey = await getApiKey();
 
   const headers: Record<string, string> = {
     'Content-Type': 'application/json',
+    ...(await getAuthHeaders()),
   };
 
-  if (apiKey) {
-    headers['X-API-Key'] = apiKey;
-  }
-
   const response = await fetch(`${baseUrl}${endpoint}`, {
     ...options,
     headers: {
@@ -8,7 +8,7 @@ import {
   requestPermission,
   sendNotification,
 } from '@tauri-apps/plugin-notification';
-import { getApiKey, getApiUrl, ApiError } from './api';
+import { getAuthHeaders, getApiUrl, ApiError } from './api';
 
 export interface FocusResult {
   success: boolean;
@@ -20,16 +20,12 @@ export interface FocusResult {
  */
 export async function focusSession(sessionId: string): Promise<FocusResult> {
   const apiUrl = await getApiUrl();
-  const apiKey = await getApiKey();
 
   const headers: Record<string, string> = {
     'Content-Type': 'application/json',
+    ...(await getAuthHeaders()),
   };
 
-  if (apiKey) {
-    headers['X-API-Key'] = apiKey;
-  }
-
   const response = await fetch(`${apiUrl}/api/sessions/${sessionId}/focus`, {
     method: 'POST',
     headers,
@@ -1,16 +1,24 @@
 /**
  * Window Manager Service
- * Uses Tauri command to activate external applications via AppleScript
+ * Uses Tauri command to activate external applications (AppleScript on macOS, wmctrl on Linux)
  */
 
 import { invoke } from '@tauri-apps/api/core';
 
+export interface ActivateTarget {
+  /** e.g. "com.googlecode.iterm2"; used instead of the app name when set */
+  bundleId?: string;
+  /** Raise only the window whose title contains this, switching to its Space */
+  windowTitle?: string;
+}
+
 /**
- * Activate an application by name using AppleScript (macOS only)
+ * Activate an application by name
  * @param appName - The name of the application to activate (e.g., "Alacritty")
+ * @param target - Optional bundle identifier and window title to raise
  */
-export async function activateApp(appName: string): Promise<void> {
-  await invoke('activate_app', { appName });
+export async function activateApp(appName: string | null, target?: ActivateTarget): Promise<void> {
+  await invoke('activate_app', { appName, target });
 }
 
 /**
@@ -462,3 +462,38 @@ export interface Notification {
 export interface NotificationCountResponse {
   count: number;
 }
+
+// ============================================================================
+// Desktop Command Errors
+// ============================================================================
+
+export type DesktopErrorCode =
+  | 'npm-not-found'
+  | 'server-path-missing'
+  | 'dependencies-missing'
+  | 'port-in-use'
+  | 'permission-denied'
+  | 'locked'
+  | 'unauthorized'
+  | 'not-found'
+  | 'invalid-input'
+  | 'unsupported'
+  | 'timeout'
+  | 'cancelled'
+  | 'internal';
+
+/** Rejection value of every Tauri command */
+export interface DesktopError {
+  code: DesktopErrorCode;
+  message: string;
+  details: Record<string, unknown> | null;
+}
+
+export function isDesktopError(error: unknown): error is DesktopError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    'code' in error &&
+    'message' in error
+  );
+}
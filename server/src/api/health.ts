@@ -9,6 +9,9 @@ import { prisma } from '../config/db.js';
 const execAsync = promisify(exec);
 const router = Router();
 
+// REST API version, bumped on breaking changes; the desktop app checks it on startup
+const API_VERSION = 1;
+
 // Server start time for uptime calculation
 const startTime = Date.now();
 
@@ -31,6 +34,7 @@ interface HealthResponse {
   status: 'healthy' | 'degraded';
   uptime: number;
   version: string;
+  apiVersion: number;
   database: 'connected' | 'disconnected';
   tmux: 'available' | 'unavailable';
   timestamp: string;
@@ -74,6 +78,7 @@ router.get('/', (_req: Request, res: Response<HealthResponse>): void => {
       status,
       uptime: Math.floor((Date.now() - startTime) / 1000),
       version: packageVersion,
+      apiVersion: API_VERSION,
       database: dbStatus,
       tmux: tmuxStatus,
       timestamp: new Date().toISOString(),
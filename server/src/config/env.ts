@@ -11,6 +11,8 @@ const envSchema = z.object({
   HANDOFF_THRESHOLD_PERCENT: z.coerce.number().min(5).max(50).default(20),
   LOG_LEVEL: z.enum(['debug', 'info', 'warn', 'error']).default('info'),
   API_KEY: z.string().min(32).optional(),
+  // Set by the desktop app when it spawns the server; required on localhost requests
+  DESKTOP_AUTH_TOKEN: z.string().min(32).optional(),
 });
 
 const parsed = envSchema.safeParse(process.env);
@@ -1,3 +1,4 @@
+import { timingSafeEqual } from 'crypto';
 import { Request, Response, NextFunction } from 'express';
 import { env } from '../config/env.js';
 
@@ -31,30 +32,75 @@ export function isLocalhostAddress(address: string | undefined): boolean {
   );
 }
 
+/**
+ * Claude Code hooks are plain curl calls from agent sessions and carry no token.
+ * Only this route (relative to the /api mount) is exempt from the desktop token.
+ */
+const HOOK_INGEST_PATH = '/hooks/claude';
+
+/**
+ * Compare a header value against a secret in constant time.
+ * Header arrays and length mismatches never match.
+ */
+function matchesSecret(value: string | string[] | null | undefined, secret: string): boolean {
+  if (typeof value !== 'string') return false;
+  const given = Buffer.from(value);
+  const expected = Buffer.from(secret);
+  return given.length === expected.length && timingSafeEqual(given, expected);
+}
+
+/**
+ * Check a desktop auth token against DESKTOP_AUTH_TOKEN.
+ * Always passes when the server wasn't started by the desktop app.
+ */
+export function isValidDesktopToken(token: string | string[] | null | undefined): boolean {
+  return !env.DESKTOP_AUTH_TOKEN || matchesSecret(token, env.DESKTOP_AUTH_TOKEN);
+}
+
+function isHookIngest(req: Request): boolean {
+  return req.method === 'POST' && req.path === HOOK_INGEST_PATH;
+}
+
 /**
  * API key authentication middleware.
  *
  * Behavior:
- * - If API_KEY is not configured: auth is skipped (development mode)
- * - If request is from localhost: auth is skipped (web app support)
+ * - If request is from localhost: requires X-Desktop-Token when DESKTOP_AUTH_TOKEN is set
+ *   (except the Claude Code hook ingest route, POST /hooks/claude)
+ * - If API_KEY is not configured: requires X-Desktop-Token when DESKTOP_AUTH_TOKEN is set,
+ *   since the server listens on every interface by default; otherwise auth is skipped
+ *   (development mode)
  * - Otherwise: requires valid X-API-Key header (remote/native app access)
  */
 export function apiKeyAuth(req: Request, res: Response, next: NextFunction): void {
-  // If no API key is configured, skip auth (development mode)
-  if (!env.API_KEY) {
+  if (isLocalhost(req)) {
+    if (!isHookIngest(req) && !isValidDesktopToken(req.headers['x-desktop-token'])) {
+      res.status(401).json({
+        error: 'Unauthorized',
+        message: 'Missing or invalid desktop token',
+      });
+      return;
+    }
     next();
     return;
   }
 
-  // Skip auth for localhost requests (web app on same machine)
-  if (isLocalhost(req)) {
+  // If no API key is configured, only the desktop token gets in (none in development mode)
+  if (!env.API_KEY) {
+    if (!isValidDesktopToken(req.headers['x-desktop-token'])) {
+      res.status(401).json({
+        error: 'Unauthorized',
+        message: 'Missing or invalid desktop token',
+      });
+      return;
+    }
     next();
     return;
   }
 
   // Require API key for remote requests
   const apiKey = req.headers['x-api-key'];
-  if (!apiKey || apiKey !== env.API_KEY) {
+  if (!matchesSecret(apiKey, env.API_KEY)) {
     res.status(401).json({
       error: 'Unauthorized',
       message: 'Missing or invalid API key',
@@ -6,7 +6,7 @@
 import { WebSocketServer, WebSocket, RawData } from 'ws';
 import type { Server as HttpServer, IncomingMessage } from 'http';
 import { env } from '../config/env.js';
-import { isLocalhostAddress } from '../middleware/api-key-auth.js';
+import { isLocalhostAddress, isValidDesktopToken } from '../middleware/api-key-auth.js';
 import { v4 as uuid } from 'uuid';
 import { ZodError } from 'zod';
 import {
@@ -248,20 +248,24 @@ export class WebSocketManager {
    * Handle new connection
    */
   private handleConnection(ws: ExtendedWebSocket, request: IncomingMessage): void {
-    // Validate API key if configured (skip for localhost connections)
-    if (env.API_KEY) {
-      const remoteAddress = request.socket.remoteAddress;
-      const isLocal = isLocalhostAddress(remoteAddress);
+    // eslint-disable-next-line no-undef
+    const url = new URL(request.url ?? '', `http://${request.headers.host}`);
+    const isLocal = isLocalhostAddress(request.socket.remoteAddress);
+
+    // Localhost connections must carry the desktop token when the desktop app started us,
+    // and so must remote ones when there is no API key to check instead
+    if ((isLocal || !env.API_KEY) && !isValidDesktopToken(url.searchParams.get('desktopToken'))) {
+      ws.close(4001, 'Unauthorized: Invalid or missing desktop token');
+      return;
+    }
 
-      if (!isLocal) {
-        // eslint-disable-next-line no-undef
-        const url = new URL(request.url ?? '', `http://${request.headers.host}`);
-        const apiKey = url.searchParams.get('apiKey');
+    // Validate API key if configured (skip for localhost connections)
+    if (env.API_KEY && !isLocal) {
+      const apiKey = url.searchParams.get('apiKey');
 
-        if (!apiKey || apiKey !== env.API_KEY) {
-          ws.close(4001, 'Unauthorized: Invalid or missing API key');
-          return;
-        }
+      if (!apiKey || apiKey !== env.API_KEY) {
+        ws.close(4001, 'Unauthorized: Invalid or missing API key');
+        return;
       }
     }
 
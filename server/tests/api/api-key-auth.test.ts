@@ -0,0 +1,109 @@
+import { describe, it, expect, vi, beforeEach } from 'vitest';
+import type { Request, Response, NextFunction } from 'express';
+
+const DESKTOP_TOKEN = 'd'.repeat(64);
+const API_KEY = 'k'.repeat(32);
+
+const mockEnv = vi.hoisted(() => ({
+  env: {} as { API_KEY?: string; DESKTOP_AUTH_TOKEN?: string },
+}));
+
+vi.mock('../../src/config/env.js', () => mockEnv);
+
+import { apiKeyAuth } from '../../src/middleware/api-key-auth.js';
+
+function configure(values: { API_KEY?: string; DESKTOP_AUTH_TOKEN?: string }): void {
+  delete mockEnv.env.API_KEY;
+  delete mockEnv.env.DESKTOP_AUTH_TOKEN;
+  Object.assign(mockEnv.env, values);
+}
+
+function request(
+  ip: string,
+  headers: Record<string, string> = {},
+  path = '/api/projects',
+  method = 'GET'
+): Request {
+  return { ip, method, path, headers, socket: { remoteAddress: ip } } as unknown as Request;
+}
+
+function run(req: Request): { status?: number; next: boolean } {
+  const result: { status?: number; next: boolean } = { next: false };
+  const res = {
+    status(code: number) {
+      result.status = code;
+      return this;
+    },
+    json() {
+      return this;
+    },
+  } as unknown as Response;
+  const next: NextFunction = () => {
+    result.next = true;
+  };
+  apiKeyAuth(req, res, next);
+  return result;
+}
+
+describe('apiKeyAuth', () => {
+  beforeEach(() => {
+    configure({});
+  });
+
+  describe('without any credentials configured', () => {
+    it('lets local and remote requests through', () => {
+      expect(run(request('127.0.0.1')).next).toBe(true);
+      expect(run(request('192.168.1.20')).next).toBe(true);
+    });
+  });
+
+  describe('started by the desktop app without an API key', () => {
+    beforeEach(() => {
+      configure({ DESKTOP_AUTH_TOKEN: DESKTOP_TOKEN });
+    });
+
+    it('requires the desktop token from localhost', () => {
+      expect(run(request('127.0.0.1')).status).toBe(401);
+      expect(run(request('::1', { 'x-desktop-token': DESKTOP_TOKEN })).next).toBe(true);
+    });
+
+    it('requires the desktop token from other hosts on the network', () => {
+      expect(run(request('192.168.1.20')).status).toBe(401);
+      expect(run(request('192.168.1.20', { 'x-desktop-token': 'wrong' })).status).toBe(401);
+      expect(run(request('192.168.1.20', { 'x-desktop-token': DESKTOP_TOKEN })).next).toBe(true);
+    });
+
+    it('only exempts hook ingest from localhost', () => {
+      expect(run(request('127.0.0.1', {}, '/hooks/claude', 'POST')).next).toBe(true);
+      expect(run(request('192.168.1.20', {}, '/hooks/claude', 'POST')).status).toBe(401);
+    });
+
+    it('requires the desktop token on other hook routes', () => {
+      expect(run(request('127.0.0.1', {}, '/hooks/session-start', 'POST')).status).toBe(401);
+      expect(run(request('127.0.0.1', {}, '/hooks/health')).status).toBe(401);
+      expect(run(request('127.0.0.1', {}, '/hooks/claude')).status).toBe(401);
+    });
+
+    it('rejects tokens of a different length', () => {
+      expect(run(request('::1', { 'x-desktop-token': DESKTOP_TOKEN.slice(1) })).status).toBe(401);
+      expect(run(request('::1', { 'x-desktop-token': DESKTOP_TOKEN + 'd' })).status).toBe(401);
+    });
+  });
+
+  describe('with an API key configured', () => {
+    beforeEach(() => {
+      configure({ API_KEY, DESKTOP_AUTH_TOKEN: DESKTOP_TOKEN });
+    });
+
+    it('requires the API key from other hosts', () => {
+      expect(run(request('10.0.0.5')).status).toBe(401);
+      expect(run(request('10.0.0.5', { 'x-api-key': 'wrong' })).status).toBe(401);
+      expect(run(request('10.0.0.5', { 'x-api-key': API_KEY })).next).toBe(true);
+    });
+
+    it('still requires the desktop token from localhost', () => {
+      expect(run(request('127.0.0.1', { 'x-api-key': API_KEY })).status).toBe(401);
+      expect(run(request('127.0.0.1', { 'x-desktop-token': DESKTOP_TOKEN })).next).toBe(true);
+    });
+  });
+});